@@ -0,0 +1,66 @@
+// benches/fusion.rs
+
+//! Compares simulating a deep circuit of single-QDU patterns with simulating the same
+//! circuit after `onq::circuits::optimize::fuse_single_qdu_runs`, which multiplies
+//! each run into one `MatrixPattern`. The fusion pass itself is timed separately, as a
+//! caller pays for it once per circuit.
+//!
+//! Run with `cargo bench --bench fusion`.
+
+use onq::circuits::optimize::fuse_single_qdu_runs;
+use onq::{Circuit, CircuitBuilder, QduId, Simulator};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const QDUS: u64 = 8;
+const DEPTH: usize = 500;
+const ROUNDS: usize = 50;
+
+/// Builds `DEPTH` layers of H, phase and T on each of `QDUS` QDUs, then stabilizes all
+/// of them.
+fn deep_circuit() -> Circuit {
+    let qdus: Vec<QduId> = (0..QDUS).map(QduId).collect();
+    let mut builder = CircuitBuilder::new();
+    for layer in 0..DEPTH {
+        for &qdu in &qdus {
+            builder = builder.h(qdu).phase(qdu, 0.01 * layer as f64).t(qdu);
+        }
+    }
+    builder.stabilize(&qdus).build()
+}
+
+/// Runs `circuit` `ROUNDS` times and returns the elapsed time.
+fn time(simulator: &Simulator, circuit: &Circuit) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(simulator.run(black_box(circuit)).expect("circuit runs"));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let simulator = Simulator::new();
+    let circuit = deep_circuit();
+
+    let start = Instant::now();
+    let fused = fuse_single_qdu_runs(black_box(&circuit));
+    let fusion = start.elapsed();
+
+    let baseline = time(&simulator, &circuit);
+    let optimized = time(&simulator, &fused);
+    println!(
+        "{} QDUs x {} layers: {} operations, {} after fusion (fusion pass {:.3?})",
+        QDUS,
+        DEPTH,
+        circuit.len(),
+        fused.len(),
+        fusion
+    );
+    println!(
+        "{} rounds: original {:>10.3?}  fused {:>10.3?}  speedup {:.2}x",
+        ROUNDS,
+        baseline,
+        optimized,
+        baseline.as_secs_f64() / optimized.as_secs_f64()
+    );
+}
@@ -0,0 +1,77 @@
+// benches/kernels.rs
+
+//! Compares the kernel `onq::core::kernels::apply_matrix` selects for a matrix with a
+//! plain scalar complex product, over every node of a full 64-node state. Diagonal
+//! matrices take the packed kernel; dense ones stay on the scalar product, so they
+//! should show no change. The kernel is selected once per matrix, as the engine does
+//! once per operation.
+//!
+//! Run with `cargo bench --bench kernels`.
+
+use num_complex::Complex;
+use onq::core::{PotentialityState, kernels};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 20_000;
+
+type Matrix = [[Complex<f64>; 2]; 2];
+
+fn scalar(state: &mut [Complex<f64>; 2], m: &Matrix) {
+    let current = *state;
+    state[0] = m[0][0] * current[0] + m[0][1] * current[1];
+    state[1] = m[1][0] * current[0] + m[1][1] * current[1];
+}
+
+/// Applies `kernel` to every node of the state `ROUNDS` times and returns the elapsed time.
+fn time(
+    state: &PotentialityState,
+    matrix: &Matrix,
+    kernel: fn(&mut [Complex<f64>; 2], &Matrix),
+) -> Duration {
+    let mut amplitudes: Vec<[Complex<f64>; 2]> =
+        state.network.values().map(|t| t.core_state).collect();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for node in amplitudes.iter_mut() {
+            kernel(black_box(node), black_box(matrix));
+        }
+    }
+    black_box(&amplitudes);
+    start.elapsed()
+}
+
+fn report(name: &str, state: &PotentialityState, matrix: &Matrix) {
+    let kernel: fn(&mut [Complex<f64>; 2], &Matrix) = if kernels::is_diagonal(matrix) {
+        |state, m| kernels::apply_diagonal(state, m[0][0], m[1][1])
+    } else {
+        kernels::apply_2x2
+    };
+    let baseline = time(state, matrix, scalar);
+    let selected = time(state, matrix, kernel);
+    println!(
+        "{:<10} scalar {:>10.3?}  kernel {:>10.3?}  speedup {:.2}x",
+        name,
+        baseline,
+        selected,
+        baseline.as_secs_f64() / selected.as_secs_f64()
+    );
+}
+
+fn main() {
+    let state = PotentialityState::new_equilibrium();
+    let h = std::f64::consts::FRAC_1_SQRT_2;
+    let hadamard = [
+        [Complex::new(h, 0.0), Complex::new(h, 0.0)],
+        [Complex::new(h, 0.0), Complex::new(-h, 0.0)],
+    ];
+    let (sin, cos) = 0.3f64.sin_cos();
+    let phase = [
+        [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+        [Complex::new(0.0, 0.0), Complex::new(cos, sin)],
+    ];
+
+    println!("{} nodes x {} rounds", state.network.len(), ROUNDS);
+    report("dense", &state, &hadamard);
+    report("diagonal", &state, &phase);
+}
@@ -2,7 +2,7 @@
 //! Compares conceptual approach to standard quantum measurement experiments.
 
 use onq::{
-    CircuitBuilder, Operation, QduId, Simulator, OnqError,
+    CircuitBuilder, QduId, Simulator, OnqError,
 };
 
 // Helper for QduId creation
@@ -17,11 +17,8 @@ fn main() -> Result<(), OnqError> {
     // 1. Apply SqrtFlip to the initial |0> state.
     // 2. Stabilize the result.
     let circuit = CircuitBuilder::new()
-        .add_op(Operation::InteractionPattern {
-            target: q0,
-            pattern_id: "SqrtFlip".to_string(), // Use derived Sqrt(X) analog
-        })
-        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .sx(q0) // Use derived Sqrt(X) analog
+        .stabilize(&[q0])
         .build();
 
     // Print the circuit diagram
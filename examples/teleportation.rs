@@ -1,6 +1,7 @@
 //! Succinct examples demonstrating building and simulating circuits
 
-use onq::{CircuitBuilder, Operation, QduId, Simulator, StableState};
+use onq::circuits::templates::bell_pair;
+use onq::{Circuit, CircuitBuilder, QduId, Simulator, StableState};
 
 // Helper for QduId creation for brevity in examples
 fn qid(id: u64) -> QduId {
@@ -24,97 +25,41 @@ fn main() {
     println!("Building Teleportation Circuit...");
 
     // 1. Prepare Message State: Put msg_q in |+> state
-    builder = builder.add_op(Operation::InteractionPattern {
-        target: msg_q,
-        pattern_id: "Superposition".to_string(), // H analog
-    });
+    builder = builder.h(msg_q); // H analog
     println!("  Step 1: Prepared Message QDU in |+> state (using Superposition).");
 
     // 2. Create Bell Pair between Alice and Bob: |Φ+> = (1/sqrt(2))(|00> + |11>)
-    builder = builder.add_op(Operation::InteractionPattern {
-        // H on Alice
-        target: alice_q,
-        pattern_id: "Superposition".to_string(),
-    });
-    builder = builder.add_op(Operation::ControlledInteraction {
-        // CNOT(Alice, Bob)
-        control: alice_q,
-        target: bob_q,
-        pattern_id: "QualityFlip".to_string(), // X analog
-    });
+    builder = builder.append(&bell_pair(alice_q, bob_q).build());
     println!("  Step 2: Created Bell Pair between Alice and Bob.");
 
     // 3. Alice performs Bell Measurement operations (basis change)
-    builder = builder.add_op(Operation::ControlledInteraction {
-        // CNOT(Message, Alice)
-        control: msg_q,
-        target: alice_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
-    builder = builder.add_op(Operation::InteractionPattern {
-        // H on Message
-        target: msg_q,
-        pattern_id: "Superposition".to_string(),
-    });
+    builder = builder.cnot(msg_q, alice_q).h(msg_q);
     println!("  Step 3: Applied Bell Measurement basis change gates (CNOT, H).");
 
     // Step 4: Applied Quantum Recovery gates (CNOT, CZ analog).
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: alice_q,
-        target: bob_q,
-        pattern_id: "QualityFlip".to_string(), // CNOT 1->2 (Valid! Physically Adjacent)
-    });
+    builder = builder.cnot(alice_q, bob_q); // CNOT 1->2 (Valid! Physically Adjacent)
 
     // --- THE SWAP ROUTING PROTOCOL ---
     // We cannot CZ(msg_q, bob_q) because 0 and 2 are not adjacent.
     // We must SWAP the message state into Alice's adjacent node.
     // SWAP(0, 1) = CNOT(0,1) + CNOT(1,0) + CNOT(0,1)
-
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: msg_q,
-        target: alice_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: alice_q,
-        target: msg_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: msg_q,
-        target: alice_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
+    builder = builder
+        .cnot(msg_q, alice_q)
+        .cnot(alice_q, msg_q)
+        .cnot(msg_q, alice_q);
 
     // Now the message state resides in Node 1. We can apply the CZ to Bob (Node 2)!
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: alice_q, // The message state is now here
-        target: bob_q,
-        pattern_id: "PhaseIntroduce".to_string(), // CZ 1->2 (Valid! Physically Adjacent)
-    });
+    builder = builder.cz(alice_q, bob_q); // CZ 1->2 (Valid! Physically Adjacent)
 
     // SWAP back to restore the original structural positions
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: msg_q,
-        target: alice_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: alice_q,
-        target: msg_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
-    builder = builder.add_op(Operation::ControlledInteraction {
-        control: msg_q,
-        target: alice_q,
-        pattern_id: "QualityFlip".to_string(),
-    });
+    builder = builder
+        .cnot(msg_q, alice_q)
+        .cnot(alice_q, msg_q)
+        .cnot(msg_q, alice_q);
     println!("  Step 4: Applied Quantum Recovery gates (CNOT, CZ analog).");
     // 5. Stabilize Bob's QDU to observe the teleported state
     //    Optionally stabilize Alice and Message to see their final states too.
-    builder = builder.add_op(Operation::Stabilize {
-        targets: vec![msg_q, alice_q, bob_q],
-    });
+    builder = builder.stabilize(&[msg_q, alice_q, bob_q]);
     println!("  Step 5: Added final stabilization for all QDUs.");
 
     let circuit = builder.build();
@@ -140,8 +85,8 @@ fn main() {
             // Stabilizing the |+> state = (1/sqrt(2))[|0> + |1>] depends on the rules.
             // Based on our current stabilization (S(0)=0.25, S(1)=0.25), the outcome for Bob
             // will be deterministically 0 or 1 based on the final state hash and PRNG.
-            // A full verification would require state vector tomography (not implemented)
-            // or running statistical tests if stabilization were probabilistic.
+            // The final stabilization collapses Bob's QDU, so the verification below
+            // reruns the circuit without it and inspects the captured final state.
 
             println!("\nAnalysis:");
             if let Some(StableState::ResolvedQuality(bob_outcome)) = result.get_stable_state(&bob_q)
@@ -179,9 +124,21 @@ fn main() {
                     "  (These represent the classical bits Alice would send in standard protocol)"
                 );
             }
-            println!(
-                "\nVerification of perfect state teleportation would require state vector analysis."
-            );
+
+            // --- Verification: Bob's marginal before stabilization ---
+            let mut unmeasured = Circuit::new();
+            unmeasured.add_operations(circuit.operations()[..circuit.len() - 1].iter().cloned());
+            match simulator.run_with_state(&unmeasured) {
+                Ok(state_result) => {
+                    if let Some([p0, p1]) = state_result.marginal(&bob_q) {
+                        println!(
+                            "\nBob's QDU before stabilization: P(0)={:.4}, P(1)={:.4} (|+> expects 0.5/0.5)",
+                            p0, p1
+                        );
+                    }
+                }
+                Err(e) => eprintln!("\nState capture failed: {}", e),
+            }
         }
         Err(e) => {
             eprintln!("\n--- Simulation Failed ---");
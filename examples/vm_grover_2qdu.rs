@@ -2,41 +2,45 @@
 //! Demonstrates building Oracle and Diffusion operators from derived gates,
 //! classical loop control (for potential future scaling), and stabilization.
 
-use onq::core::QduId;
-use onq::operations::Operation;
-use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+use onq::prelude::*;
+use onq::vm::InstructionMacro;
 
 // Helper for QduId creation
 fn qid(id: u64) -> QduId {
     QduId(id)
 }
 
-// Helper to add the Oracle operation (CZ marking |11>)
-fn add_oracle(builder: ProgramBuilder, q0: QduId, q1: QduId) -> ProgramBuilder {
-    builder.pb_add(Instruction::QuantumOp(Operation::ControlledInteraction {
-        control: q0, // Order doesn't matter for CZ
-        target: q1,
-        pattern_id: "PhaseIntroduce".to_string(), // Z analog -> CZ
-    }))
+// Macro for the Oracle operation (CZ marking |11>), on placeholder QDUs 0 and 1
+fn oracle() -> InstructionMacro {
+    let (q0, q1) = (qid(0), qid(1));
+    InstructionMacro::new("oracle", &[q0, q1], &[], vec![
+        Instruction::QuantumOp(Operation::ControlledInteraction {
+            control: q0, // Order doesn't matter for CZ
+            target: q1,
+            pattern_id: "PhaseIntroduce".to_string(), // Z analog -> CZ
+        }),
+    ])
 }
 
-// Helper to add the Diffusion operator (H⊗H · X⊗X · CZ · X⊗X · H⊗H)
-fn add_diffusion(builder: ProgramBuilder, q0: QduId, q1: QduId) -> ProgramBuilder {
-    builder
+// Macro for the Diffusion operator (H⊗H · X⊗X · CZ · X⊗X · H⊗H), on placeholder QDUs 0 and 1
+fn diffusion() -> InstructionMacro {
+    let (q0, q1) = (qid(0), qid(1));
+    InstructionMacro::new("diffusion", &[q0, q1], &[], vec![
         // H⊗H
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() }))
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "Superposition".to_string() }))
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() }),
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "Superposition".to_string() }),
         // X⊗X
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "QualityFlip".to_string() }))
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "QualityFlip".to_string() }))
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "QualityFlip".to_string() }),
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "QualityFlip".to_string() }),
         // CZ
-        .pb_add(Instruction::QuantumOp(Operation::ControlledInteraction { control: q0, target: q1, pattern_id: "PhaseIntroduce".to_string() }))
+        Instruction::QuantumOp(Operation::ControlledInteraction { control: q0, target: q1, pattern_id: "PhaseIntroduce".to_string() }),
         // X⊗X
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "QualityFlip".to_string() }))
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "QualityFlip".to_string() }))
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "QualityFlip".to_string() }),
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "QualityFlip".to_string() }),
         // H⊗H
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() }))
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "Superposition".to_string() }))
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() }),
+        Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "Superposition".to_string() }),
+    ])
 }
 
 
@@ -51,40 +55,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let num_iterations = 1;
 
     // --- Build the Grover Program ---
-    let mut builder = ProgramBuilder::new();
+    let mut builder = ProgramBuilder::new().define_macro(oracle()).define_macro(diffusion());
 
     // Initialize classical loop counter
-    builder = builder.pb_add(Instruction::LoadImmediate { register: "k".to_string(), value: 0 });
-    builder = builder.pb_add(Instruction::LoadImmediate { register: "limit".to_string(), value: num_iterations });
+    builder = builder.pb_add(Instruction::LoadImmediate { register: "k".to_string(), value: num_iterations });
+    builder = builder.pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 });
 
     // 1. Prepare superposition state |++>
     builder = builder.pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() }));
     builder = builder.pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "Superposition".to_string() }));
 
-    // --- Grover Iteration Loop ---
-    builder = builder.pb_add(Instruction::Label("grover_loop".to_string()));
-
-    // 2. Apply Oracle (marks |11> with phase -1)
-    builder = add_oracle(builder, q0, q1);
-
-    // 3. Apply Diffusion Operator (amplifies marked state)
-    builder = add_diffusion(builder, q0, q1);
-
-    // 4. Loop control
-    builder = builder.pb_add(Instruction::Addi { r_dest: "k".to_string(), r_src: "k".to_string(), value: 1 });
-    builder = builder.pb_add(Instruction::CmpLt { // Check if k < limit
-        r_dest: "cond".to_string(),
-        r_src1: "k".to_string(),
-        r_src2: "limit".to_string(),
+    // --- Grover Iteration Loop: runs while k != 0 ---
+    builder = builder.while_reg_nonzero("k", |body| {
+        // 2. Apply Oracle (marks |11> with phase -1)
+        body.expand("oracle", &[q0, q1], &[])
+            // 3. Apply Diffusion Operator (amplifies marked state)
+            .expand("diffusion", &[q0, q1], &[])
+            // 4. Loop control
+            .pb_add(Instruction::Sub { r_dest: "k".to_string(), r_src1: "k".to_string(), r_src2: "one".to_string() })
     });
-    builder = builder.pb_add(Instruction::BranchIfZero { // If cond=0 (k >= limit), jump to end
-        register: "cond".to_string(),
-        label: "grover_end".to_string(),
-    });
-    builder = builder.pb_add(Instruction::Jump("grover_loop".to_string())); // If cond=1 (k < limit), loop again
-
-    // --- End of Loop ---
-    builder = builder.pb_add(Instruction::Label("grover_end".to_string()));
 
     // 5. Stabilize and Record results
     builder = builder.pb_add(Instruction::Stabilize { targets: vec![q0, q1] });
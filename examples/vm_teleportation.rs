@@ -3,9 +3,7 @@
 //! recording results to classical registers, and applying conditional
 //! recovery operations based on the classical results.
 
-use onq::core::QduId;
-use onq::operations::Operation;
-use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+use onq::prelude::*;
 // Helper for QduId creation
 fn qid(id: u64) -> QduId {
     QduId(id)
@@ -47,38 +45,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> { // Use Box<dyn Error> for
             pattern_id: "Superposition".to_string(),
         }))
         // 4. Stabilize Alice's qubits and Record results
-        .pb_add(Instruction::Stabilize { targets: vec![msg_q, alice_q] })
-        .pb_add(Instruction::Record { qdu: msg_q,   register: "m_msg".to_string() })
-        .pb_add(Instruction::Record { qdu: alice_q, register: "m_alice".to_string() })
+        .pb_add(Instruction::StabilizeInto { targets: vec![
+            (msg_q, "m_msg".to_string()),
+            (alice_q, "m_alice".to_string()),
+        ] })
 
         // 5. Bob's Classical Corrections (Conditional Operations)
-        // 5a. X Correction based on Alice's measurement (m_alice)
-        .pb_add(Instruction::Label("X_Correction".to_string())) // Label for clarity
-        .pb_add(Instruction::BranchIfZero { // If m_alice == 0, jump past X gate
+        // 5a. X Correction if Alice's measurement (m_alice) is 1
+        .pb_add(Instruction::QuantumOpIf {
             register: "m_alice".to_string(),
-            label: "skip_x_corr".to_string(),
+            op: Operation::InteractionPattern {
+                target: bob_q,
+                pattern_id: "QualityFlip".to_string(),
+            },
         })
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { // Apply X if m_alice == 1
-            target: bob_q,
-            pattern_id: "QualityFlip".to_string(),
-        }))
-        .pb_add(Instruction::Label("skip_x_corr".to_string()))
-
-        // 5b. Z Correction based on Message measurement (m_msg)
-        .pb_add(Instruction::Label("Z_Correction".to_string())) // Label for clarity
-        .pb_add(Instruction::BranchIfZero { // If m_msg == 0, jump past Z gate
+        // 5b. Z Correction if the Message measurement (m_msg) is 1
+        .pb_add(Instruction::QuantumOpIf {
             register: "m_msg".to_string(),
-            label: "skip_z_corr".to_string(),
+            op: Operation::InteractionPattern {
+                target: bob_q,
+                pattern_id: "PhaseIntroduce".to_string(),
+            },
         })
-        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { // Apply Z if m_msg == 1
-            target: bob_q,
-            pattern_id: "PhaseIntroduce".to_string(),
-        }))
-        .pb_add(Instruction::Label("skip_z_corr".to_string()))
 
         // 6. Stabilize Bob's qubit (optional, to verify outcome)
-        .pb_add(Instruction::Stabilize { targets: vec![bob_q] })
-        .pb_add(Instruction::Record { qdu: bob_q, register: "m_bob".to_string() })
+        .pb_add(Instruction::StabilizeInto { targets: vec![(bob_q, "m_bob".to_string())] })
 
         // 7. Halt
         .pb_add(Instruction::Halt)
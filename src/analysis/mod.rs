@@ -0,0 +1,15 @@
+// src/analysis/mod.rs
+
+//! Tools for analyzing circuits and states beyond running them.
+//!
+//! * [`StabilizerTableau`]: Exact stabilizer-analog tracking for Clifford-analog circuits,
+//!   used to verify integration structures (e.g. graph states) that the localized
+//!   simulation engine only approximates.
+//! * [`integration_metrics`]: Entanglement entropy, crossing bonds and mixedness across a
+//!   bipartition, for comparing how strongly circuits and locks integrate QDUs.
+
+pub mod integration;
+pub mod stabilizer;
+
+pub use integration::{IntegrationMetrics, integration_metrics};
+pub use stabilizer::StabilizerTableau;
@@ -0,0 +1,510 @@
+// src/analysis/stabilizer.rs
+
+//! Symbolic stabilizer-analog tracking for Clifford-analog circuits.
+//!
+//! A [`StabilizerTableau`] follows the group of Pauli-analog strings that stabilize the
+//! state prepared by a circuit, starting from |0...0>. Unlike the simulation engine it
+//! tracks integration between QDUs exactly, but only for the Clifford-analog patterns
+//! (`Superposition`, `QualityFlip`, `PhaseIntroduce`, `QualitativeY`, `HalfPhase`,
+//! `SqrtFlip`, their inverses, and their controlled `QualityFlip`/`PhaseIntroduce` forms).
+
+use crate::circuits::Circuit;
+use crate::core::{OnqError, QduId};
+use crate::operations::{Operation, PauliAxis};
+use std::collections::HashMap;
+
+/// One stabilizer generator: a signed product of Pauli-analog axes.
+/// `x[i] && z[i]` encodes the Y axis on QDU position `i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PauliRow {
+    x: Vec<bool>,
+    z: Vec<bool>,
+    negative: bool,
+}
+
+impl PauliRow {
+    /// Returns the product `self · other` of two commuting rows.
+    fn times(&self, other: &PauliRow) -> PauliRow {
+        // Exponent of i picked up when multiplying single-QDU axes (Aaronson-Gottesman)
+        let mut phase: i32 = 2 * i32::from(self.negative) + 2 * i32::from(other.negative);
+        for i in 0..self.x.len() {
+            let (x1, z1, x2, z2) = (
+                i32::from(self.x[i]),
+                i32::from(self.z[i]),
+                i32::from(other.x[i]),
+                i32::from(other.z[i]),
+            );
+            phase += match (x1, z1) {
+                (0, 0) => 0,
+                (1, 1) => z2 - x2,
+                (1, 0) => z2 * (2 * x2 - 1),
+                _ => x2 * (1 - 2 * z2),
+            };
+        }
+        PauliRow {
+            x: self.x.iter().zip(&other.x).map(|(a, b)| a ^ b).collect(),
+            z: self.z.iter().zip(&other.z).map(|(a, b)| a ^ b).collect(),
+            negative: phase.rem_euclid(4) == 2,
+        }
+    }
+}
+
+/// Tracks the stabilizer-analog group of a Clifford-analog state.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, PauliAxis, QduId};
+/// # use onq::analysis::StabilizerTableau;
+/// let (q0, q1) = (QduId(0), QduId(1));
+/// let bell = CircuitBuilder::new()
+///     .add_op(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() })
+///     .add_op(Operation::ControlledInteraction { control: q0, target: q1, pattern_id: "QualityFlip".to_string() })
+///     .build();
+///
+/// let tableau = StabilizerTableau::from_circuit(&bell).unwrap();
+/// assert!(tableau.stabilizes(&[(q0, PauliAxis::X), (q1, PauliAxis::X)]).unwrap());
+/// assert!(tableau.stabilizes(&[(q0, PauliAxis::Z), (q1, PauliAxis::Z)]).unwrap());
+/// assert!(!tableau.stabilizes(&[(q0, PauliAxis::Z)]).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StabilizerTableau {
+    positions: HashMap<QduId, usize>,
+    rows: Vec<PauliRow>,
+}
+
+impl StabilizerTableau {
+    /// Creates the tableau of the baseline state |0...0> over `qdus`,
+    /// stabilized by `Z` on every QDU.
+    pub fn new(qdus: &[QduId]) -> Self {
+        let mut sorted: Vec<QduId> = qdus.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        let n = sorted.len();
+        let positions = sorted
+            .into_iter()
+            .enumerate()
+            .map(|(i, q)| (q, i))
+            .collect();
+        let rows = (0..n)
+            .map(|i| PauliRow {
+                x: vec![false; n],
+                z: (0..n).map(|j| j == i).collect(),
+                negative: false,
+            })
+            .collect();
+        Self { positions, rows }
+    }
+
+    /// Builds the tableau of the state prepared by `circuit` from |0...0>.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if the circuit contains an operation
+    /// without a Clifford-analog stabilizer action.
+    pub fn from_circuit(circuit: &Circuit) -> Result<Self, OnqError> {
+        let qdus: Vec<QduId> = circuit.qdus().iter().copied().collect();
+        let mut tableau = Self::new(&qdus);
+        for op in circuit.operations() {
+            tableau.apply(op)?;
+        }
+        Ok(tableau)
+    }
+
+    /// Conjugates every generator by `op`.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` for QDUs outside the tableau and
+    /// `OnqError::InvalidOperation` for non-Clifford-analog operations.
+    pub fn apply(&mut self, op: &Operation) -> Result<(), OnqError> {
+        match op {
+            Operation::InteractionPattern { target, pattern_id } => {
+                let q = self.position(target)?;
+                self.apply_single(q, pattern_id)
+            }
+            Operation::BroadcastPattern {
+                targets,
+                pattern_id,
+            } => {
+                for target in targets {
+                    let q = self.position(target)?;
+                    self.apply_single(q, pattern_id)?;
+                }
+                Ok(())
+            }
+            Operation::ControlledInteraction {
+                control,
+                target,
+                pattern_id,
+            } => {
+                let c = self.position(control)?;
+                let t = self.position(target)?;
+                match pattern_id.as_str() {
+                    "QualityFlip" => self.controlled_flip(c, t),
+                    "PhaseIntroduce" => {
+                        self.superposition(t);
+                        self.controlled_flip(c, t);
+                        self.superposition(t);
+                    }
+                    _ => return Err(not_clifford(op)),
+                }
+                Ok(())
+            }
+            Operation::Permute { mapping } => {
+                let mut relabeled = self.positions.clone();
+                for (from, to) in mapping {
+                    let position = self.position(from)?;
+                    relabeled.insert(*to, position);
+                }
+                self.positions = relabeled;
+                Ok(())
+            }
+            Operation::Delay { .. } => Ok(()),
+            _ => Err(not_clifford(op)),
+        }
+    }
+
+    /// Returns `true` if the positive Pauli-analog string `terms` stabilizes the state,
+    /// i.e. belongs to the tracked stabilizer group. QDUs not listed carry the identity.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if a QDU is not part of the tableau.
+    pub fn stabilizes(&self, terms: &[(QduId, PauliAxis)]) -> Result<bool, OnqError> {
+        let n = self.positions.len();
+        let mut target = PauliRow {
+            x: vec![false; n],
+            z: vec![false; n],
+            negative: false,
+        };
+        for (qdu, axis) in terms {
+            let q = self.position(qdu)?;
+            let (x, z) = match axis {
+                PauliAxis::X => (true, false),
+                PauliAxis::Y => (true, true),
+                PauliAxis::Z => (false, true),
+            };
+            target.x[q] ^= x;
+            target.z[q] ^= z;
+        }
+
+        // Gaussian elimination over GF(2), remembering which generators form each pivot row
+        let mut basis: Vec<(Vec<bool>, Vec<bool>)> = Vec::new(); // (bits, generator mask)
+        for (g, row) in self.rows.iter().enumerate() {
+            let mut bits: Vec<bool> = row.x.iter().chain(&row.z).copied().collect();
+            let mut mask = vec![false; self.rows.len()];
+            mask[g] = true;
+            for (pivot_bits, pivot_mask) in &basis {
+                let pivot = pivot_bits.iter().position(|b| *b).unwrap_or(0);
+                if bits[pivot] {
+                    xor_into(&mut bits, pivot_bits);
+                    xor_into(&mut mask, pivot_mask);
+                }
+            }
+            if bits.iter().any(|b| *b) {
+                basis.push((bits, mask));
+            }
+        }
+
+        let mut remainder: Vec<bool> = target.x.iter().chain(&target.z).copied().collect();
+        let mut combination = vec![false; self.rows.len()];
+        for (pivot_bits, pivot_mask) in &basis {
+            let pivot = pivot_bits.iter().position(|b| *b).unwrap_or(0);
+            if remainder[pivot] {
+                xor_into(&mut remainder, pivot_bits);
+                xor_into(&mut combination, pivot_mask);
+            }
+        }
+        if remainder.iter().any(|b| *b) {
+            return Ok(false);
+        }
+
+        // The generators commute, so their product's sign is order-independent
+        let identity = PauliRow {
+            x: vec![false; n],
+            z: vec![false; n],
+            negative: false,
+        };
+        let product = self
+            .rows
+            .iter()
+            .zip(&combination)
+            .filter(|(_, used)| **used)
+            .fold(identity, |acc, (row, _)| acc.times(row));
+        Ok(!product.negative)
+    }
+
+    /// Returns `true` if [`apply`](Self::apply) accepts `op`, i.e. `op` has a
+    /// Clifford-analog stabilizer action.
+    pub fn supports(op: &Operation) -> bool {
+        match op {
+            Operation::InteractionPattern { pattern_id, .. }
+            | Operation::BroadcastPattern { pattern_id, .. } => {
+                CLIFFORD_PATTERNS.contains(&pattern_id.as_str())
+            }
+            Operation::ControlledInteraction { pattern_id, .. } => {
+                matches!(pattern_id.as_str(), "QualityFlip" | "PhaseIntroduce")
+            }
+            Operation::Permute { .. } | Operation::Delay { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the quality weights `[P(0), P(1)]` of stabilizing `qdu`: either
+    /// determinate (`[1, 0]` or `[0, 1]`) or evenly split.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if `qdu` is not part of the tableau.
+    pub fn weights(&self, qdu: &QduId) -> Result<[f64; 2], OnqError> {
+        let q = self.position(qdu)?;
+        if self.rows.iter().any(|r| r.x[q]) {
+            Ok([0.5, 0.5])
+        } else if self.stabilizes(&[(*qdu, PauliAxis::Z)])? {
+            Ok([1.0, 0.0])
+        } else {
+            Ok([0.0, 1.0])
+        }
+    }
+
+    /// Resolves `qdu` to `quality`, leaving the state stabilized by `Z` (quality 0) or
+    /// `-Z` (quality 1) on it. Integrated QDUs are updated consistently.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if `qdu` is not part of the tableau and
+    /// `OnqError::InvalidOperation` if `quality` has no weight (see [`weights`](Self::weights)).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// # use onq::analysis::StabilizerTableau;
+    /// let (q0, q1) = (QduId(0), QduId(1));
+    /// let bell = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() })
+    ///     .add_op(Operation::ControlledInteraction { control: q0, target: q1, pattern_id: "QualityFlip".to_string() })
+    ///     .build();
+    ///
+    /// let mut tableau = StabilizerTableau::from_circuit(&bell).unwrap();
+    /// assert_eq!(tableau.weights(&q1).unwrap(), [0.5, 0.5]);
+    /// tableau.collapse(&q0, 1).unwrap();
+    /// assert_eq!(tableau.weights(&q1).unwrap(), [0.0, 1.0]);
+    /// ```
+    pub fn collapse(&mut self, qdu: &QduId, quality: u8) -> Result<(), OnqError> {
+        let q = self.position(qdu)?;
+        let Some(pivot) = self.rows.iter().position(|r| r.x[q]) else {
+            let [w0, w1] = self.weights(qdu)?;
+            let weight = if quality == 0 { w0 } else { w1 };
+            if weight == 0.0 {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("{} cannot resolve to Quality{}", qdu, quality),
+                });
+            }
+            return Ok(());
+        };
+
+        // Every other generator anticommuting with Z is replaced by its product with
+        // the pivot, which commutes; the pivot itself becomes ±Z
+        let pivot_row = self.rows[pivot].clone();
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            if i != pivot && row.x[q] {
+                *row = row.times(&pivot_row);
+            }
+        }
+        let n = self.positions.len();
+        self.rows[pivot] = PauliRow {
+            x: vec![false; n],
+            z: (0..n).map(|j| j == q).collect(),
+            negative: quality == 1,
+        };
+        Ok(())
+    }
+
+    /// Returns the entanglement entropy, in bits, between the QDUs in `part` and the
+    /// rest of the tableau: the GF(2) rank of the generators restricted to `part`,
+    /// minus the size of `part`. It is 0 for product states and 1 per shared
+    /// Bell-analog pair.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if a QDU is not part of the tableau.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId};
+    /// # use onq::analysis::StabilizerTableau;
+    /// let (q0, q1, q2) = (QduId(0), QduId(1), QduId(2));
+    /// let circuit = CircuitBuilder::new().h(q0).cnot(q0, q1).h(q2).build();
+    ///
+    /// let tableau = StabilizerTableau::from_circuit(&circuit).unwrap();
+    /// assert_eq!(tableau.entanglement_entropy(&[q0]).unwrap(), 1.0);
+    /// assert_eq!(tableau.entanglement_entropy(&[q0, q1]).unwrap(), 0.0);
+    /// assert_eq!(tableau.entanglement_entropy(&[q2]).unwrap(), 0.0);
+    /// ```
+    pub fn entanglement_entropy(&self, part: &[QduId]) -> Result<f64, OnqError> {
+        let mut columns = Vec::with_capacity(part.len());
+        for qdu in part {
+            let q = self.position(qdu)?;
+            if !columns.contains(&q) {
+                columns.push(q);
+            }
+        }
+
+        // Rank over GF(2) of the generators restricted to the columns of `part`
+        let mut rank = 0;
+        let mut basis: Vec<Vec<bool>> = Vec::new();
+        for row in &self.rows {
+            let mut bits: Vec<bool> = columns
+                .iter()
+                .map(|&q| row.x[q])
+                .chain(columns.iter().map(|&q| row.z[q]))
+                .collect();
+            for pivot_bits in &basis {
+                let pivot = pivot_bits.iter().position(|b| *b).unwrap_or(0);
+                if bits[pivot] {
+                    xor_into(&mut bits, pivot_bits);
+                }
+            }
+            if let Some(pivot) = bits.iter().position(|b| *b) {
+                // Keep the basis reduced so every pivot column appears in one row only
+                for other in &mut basis {
+                    if other[pivot] {
+                        xor_into(other, &bits);
+                    }
+                }
+                basis.push(bits);
+                rank += 1;
+            }
+        }
+        Ok((rank - columns.len()) as f64)
+    }
+
+    fn position(&self, qdu: &QduId) -> Result<usize, OnqError> {
+        self.positions
+            .get(qdu)
+            .copied()
+            .ok_or_else(|| OnqError::ReferenceViolation {
+                message: format!("{} is not part of the stabilizer tableau.", qdu),
+            })
+    }
+
+    fn apply_single(&mut self, q: usize, pattern_id: &str) -> Result<(), OnqError> {
+        match pattern_id {
+            "Identity" => {}
+            "Superposition" => self.superposition(q),
+            "HalfPhase" => self.half_phase(q),
+            "HalfPhase_Inv" => (0..3).for_each(|_| self.half_phase(q)),
+            "QualityFlip" => self.rows.iter_mut().for_each(|r| r.negative ^= r.z[q]),
+            "PhaseIntroduce" => self.rows.iter_mut().for_each(|r| r.negative ^= r.x[q]),
+            "QualitativeY" => self
+                .rows
+                .iter_mut()
+                .for_each(|r| r.negative ^= r.x[q] ^ r.z[q]),
+            // √X = H·S·H and its inverse H·S†·H
+            "SqrtFlip" | "SqrtFlip_Inv" => {
+                let turns = if pattern_id == "SqrtFlip" { 1 } else { 3 };
+                self.superposition(q);
+                (0..turns).for_each(|_| self.half_phase(q));
+                self.superposition(q);
+            }
+            _ => {
+                return Err(OnqError::InvalidOperation {
+                    message: format!(
+                        "Pattern '{}' has no Clifford-analog stabilizer action",
+                        pattern_id
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn superposition(&mut self, q: usize) {
+        for r in &mut self.rows {
+            r.negative ^= r.x[q] && r.z[q];
+            std::mem::swap(&mut r.x[q], &mut r.z[q]);
+        }
+    }
+
+    fn half_phase(&mut self, q: usize) {
+        for r in &mut self.rows {
+            r.negative ^= r.x[q] && r.z[q];
+            r.z[q] ^= r.x[q];
+        }
+    }
+
+    fn controlled_flip(&mut self, c: usize, t: usize) {
+        for r in &mut self.rows {
+            r.negative ^= r.x[c] && r.z[t] && !(r.x[t] ^ r.z[c]);
+            r.x[t] ^= r.x[c];
+            r.z[c] ^= r.z[t];
+        }
+    }
+}
+
+/// Single-QDU patterns accepted by [`StabilizerTableau::apply`].
+const CLIFFORD_PATTERNS: [&str; 9] = [
+    "Identity",
+    "Superposition",
+    "HalfPhase",
+    "HalfPhase_Inv",
+    "QualityFlip",
+    "PhaseIntroduce",
+    "QualitativeY",
+    "SqrtFlip",
+    "SqrtFlip_Inv",
+];
+
+fn xor_into(acc: &mut [bool], other: &[bool]) {
+    for (a, b) in acc.iter_mut().zip(other) {
+        *a ^= *b;
+    }
+}
+
+fn not_clifford(op: &Operation) -> OnqError {
+    OnqError::InvalidOperation {
+        message: format!("{:?} has no Clifford-analog stabilizer action", op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pauli_row_products() {
+        // (XX)·(ZZ) = (XZ)⊗(XZ) = (-iY)⊗(-iY) = -(YY), and (YY)·(YY) = +I
+        let xx = PauliRow {
+            x: vec![true, true],
+            z: vec![false, false],
+            negative: false,
+        };
+        let zz = PauliRow {
+            x: vec![false, false],
+            z: vec![true, true],
+            negative: false,
+        };
+        let yy = xx.times(&zz);
+        assert_eq!(
+            (yy.x.clone(), yy.z.clone()),
+            (vec![true, true], vec![true, true])
+        );
+        assert!(yy.negative);
+        assert!(!yy.times(&yy).negative);
+    }
+
+    #[test]
+    fn test_phase_flip_sign() {
+        // Z on |+> yields |->, stabilized by -X rather than X
+        let q0 = QduId(0);
+        let mut tableau = StabilizerTableau::new(&[q0]);
+        tableau
+            .apply(&Operation::InteractionPattern {
+                target: q0,
+                pattern_id: "Superposition".to_string(),
+            })
+            .unwrap();
+        assert!(tableau.stabilizes(&[(q0, PauliAxis::X)]).unwrap());
+        tableau
+            .apply(&Operation::InteractionPattern {
+                target: q0,
+                pattern_id: "PhaseIntroduce".to_string(),
+            })
+            .unwrap();
+        assert!(!tableau.stabilizes(&[(q0, PauliAxis::X)]).unwrap());
+    }
+}
@@ -0,0 +1,273 @@
+// src/circuits/dag.rs
+
+//! Dependency-graph view of a [`Circuit`].
+//!
+//! In a [`CircuitDag`] each node is an operation and each edge records that a later
+//! operation acts on a QDU last touched by an earlier one. Any topological order of the
+//! nodes is an equivalent ordering of the circuit, which is what scheduling,
+//! commutation analysis, and optimization passes build on.
+
+use super::Circuit;
+use crate::core::QduId;
+use crate::operations::Operation;
+use std::collections::{BTreeSet, HashMap};
+
+/// A data dependency: operation `to` acts on `qdu` directly after operation `from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DagEdge {
+    /// Index of the earlier operation.
+    pub from: usize,
+    /// Index of the later operation.
+    pub to: usize,
+    /// The QDU carrying the dependency.
+    pub qdu: QduId,
+}
+
+/// The operations of a circuit connected by their QDU data dependencies.
+/// Nodes are identified by the operation's index in the original circuit.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, QduId};
+/// let h = |q| Operation::InteractionPattern { target: QduId(q), pattern_id: "Superposition".to_string() };
+/// let circuit = CircuitBuilder::new()
+///     .add_op(h(0))
+///     .add_op(h(1))
+///     .add_op(Operation::ControlledInteraction {
+///         control: QduId(0),
+///         target: QduId(1),
+///         pattern_id: "QualityFlip".to_string(),
+///     })
+///     .build();
+///
+/// let dag = circuit.to_dag();
+/// assert_eq!(dag.roots(), vec![0, 1]);
+/// assert_eq!(dag.predecessors(2), &[0, 1]);
+/// assert_eq!(dag.leaves(), vec![2]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitDag {
+    operations: Vec<Operation>,
+    edges: Vec<DagEdge>,
+    predecessors: Vec<Vec<usize>>,
+    successors: Vec<Vec<usize>>,
+    /// Operation-free copy of the circuit's name, metadata and classical bits.
+    annotations: Circuit,
+}
+
+impl CircuitDag {
+    /// Builds the dependency graph of `circuit`.
+    pub fn new(circuit: &Circuit) -> Self {
+        let operations = circuit.operations().to_vec();
+        let mut edges = Vec::new();
+        let mut predecessors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); operations.len()];
+        let mut successors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); operations.len()];
+        // Per QDU, the index of the latest operation acting on it
+        let mut last_on: HashMap<QduId, usize> = HashMap::new();
+
+        for (index, op) in operations.iter().enumerate() {
+            for qdu in circuit.occupied_qdus(op) {
+                if let Some(from) = last_on.insert(qdu, index) {
+                    edges.push(DagEdge {
+                        from,
+                        to: index,
+                        qdu,
+                    });
+                    predecessors[index].insert(from);
+                    successors[from].insert(index);
+                }
+            }
+        }
+
+        let mut annotations = circuit.derived();
+        annotations.carry_bits(circuit, Some);
+
+        Self {
+            operations,
+            edges,
+            predecessors: predecessors.into_iter().map(Vec::from_iter).collect(),
+            successors: successors.into_iter().map(Vec::from_iter).collect(),
+            annotations,
+        }
+    }
+
+    /// Returns the number of nodes (operations).
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns `true` if the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Returns the operation at node `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn operation(&self, index: usize) -> &Operation {
+        &self.operations[index]
+    }
+
+    /// Returns every dependency edge, ordered by their later operation.
+    pub fn edges(&self) -> &[DagEdge] {
+        &self.edges
+    }
+
+    /// Returns the nodes `index` directly depends on, in ascending order.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn predecessors(&self, index: usize) -> &[usize] {
+        &self.predecessors[index]
+    }
+
+    /// Returns the nodes directly depending on `index`, in ascending order.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn successors(&self, index: usize) -> &[usize] {
+        &self.successors[index]
+    }
+
+    /// Returns the nodes without predecessors.
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| self.predecessors[i].is_empty())
+            .collect()
+    }
+
+    /// Returns the nodes without successors.
+    pub fn leaves(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| self.successors[i].is_empty())
+            .collect()
+    }
+
+    /// Returns the nodes in a topological order, always choosing the ready node with
+    /// the lowest index; for a freshly built graph this is the original circuit order.
+    pub fn topological_order(&self) -> Vec<usize> {
+        let mut remaining: Vec<usize> = self.predecessors.iter().map(Vec::len).collect();
+        let mut ready: BTreeSet<usize> = self.roots().into_iter().collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for &next in &self.successors[index] {
+                remaining[next] -= 1;
+                if remaining[next] == 0 {
+                    ready.insert(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns all nodes that `index` transitively depends on, in ascending order.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        self.reachable(index, &self.predecessors)
+    }
+
+    /// Returns all nodes transitively depending on `index`, in ascending order.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn descendants(&self, index: usize) -> Vec<usize> {
+        self.reachable(index, &self.successors)
+    }
+
+    /// Rebuilds a circuit from the nodes, taken in [`topological_order`](Self::topological_order).
+    /// Name, metadata and classical bits of the original circuit are kept.
+    pub fn to_circuit(&self) -> Circuit {
+        let order = self.topological_order();
+        let mut new_index = vec![0; order.len()];
+        for (position, &index) in order.iter().enumerate() {
+            new_index[index] = position;
+        }
+
+        let mut circuit = self.annotations.derived();
+        circuit.add_operations(order.into_iter().map(|i| self.operations[i].clone()));
+        circuit.carry_bits(&self.annotations, |i| Some(new_index[i]));
+        circuit
+    }
+
+    /// Collects the nodes reachable from `start` (excluding it) along `adjacency`.
+    fn reachable(&self, start: usize, adjacency: &[Vec<usize>]) -> Vec<usize> {
+        let mut seen: BTreeSet<usize> = BTreeSet::new();
+        let mut stack = adjacency[start].clone();
+        while let Some(index) = stack.pop() {
+            if seen.insert(index) {
+                stack.extend(&adjacency[index]);
+            }
+        }
+        seen.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::CircuitBuilder;
+
+    fn pattern(target: u64) -> Operation {
+        Operation::InteractionPattern {
+            target: QduId(target),
+            pattern_id: "Superposition".to_string(),
+        }
+    }
+
+    fn cx(control: u64, target: u64) -> Operation {
+        Operation::ControlledInteraction {
+            control: QduId(control),
+            target: QduId(target),
+            pattern_id: "QualityFlip".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_dependencies_follow_qdus() {
+        // 0: H(0)  1: H(2)  2: CX(0,1)  3: CX(1,2)  4: H(0)
+        let circuit = CircuitBuilder::new()
+            .add_op(pattern(0))
+            .add_op(pattern(2))
+            .add_op(cx(0, 1))
+            .add_op(cx(1, 2))
+            .add_op(pattern(0))
+            .build();
+        let dag = circuit.to_dag();
+
+        assert_eq!(dag.len(), 5);
+        assert_eq!(dag.roots(), vec![0, 1]);
+        assert_eq!(dag.leaves(), vec![3, 4]);
+        assert_eq!(dag.predecessors(3), &[1, 2]);
+        assert_eq!(dag.successors(2), &[3, 4]);
+        assert_eq!(
+            dag.edges()[0],
+            DagEdge {
+                from: 0,
+                to: 2,
+                qdu: QduId(0)
+            }
+        );
+        assert_eq!(dag.ancestors(3), vec![0, 1, 2]);
+        assert_eq!(dag.descendants(0), vec![2, 3, 4]);
+        assert_eq!(dag.topological_order(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(dag.to_circuit(), circuit);
+    }
+
+    #[test]
+    fn test_shared_qdus_produce_single_dependency() {
+        let circuit = CircuitBuilder::new()
+            .add_op(cx(0, 1))
+            .add_op(cx(1, 0))
+            .build();
+        let dag = circuit.to_dag();
+        // One edge per QDU, but a single predecessor
+        assert_eq!(dag.edges().len(), 2);
+        assert_eq!(dag.predecessors(1), &[0]);
+        assert!(CircuitBuilder::new().build().to_dag().is_empty());
+    }
+}
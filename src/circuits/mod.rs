@@ -6,10 +6,22 @@
 //! This module provides the `Circuit` structure, which encapsulates a specific,
 //! ordered pathway of interactions and state changes (e.g. Sequential Ordering).
 
+pub mod dag;
+pub mod optimize;
+pub mod qasm;
+pub mod render;
+pub mod routing;
+pub mod templates;
+pub mod transpile;
+
+pub use dag::{CircuitDag, DagEdge};
+pub use render::DiagramStyle;
+
 // Import necessary types from other modules
-use crate::core::QduId; // OnqError might be used for future validation logic
-use crate::operations::Operation;
-use std::collections::{HashMap, HashSet}; // Using HashSet to efficiently track unique QDUs involved
+use crate::core::{OnqError, QduId, ReferenceFrame};
+use crate::operations::{Operation, PauliAxis};
+use num_complex::Complex;
+use std::collections::{BTreeMap, HashMap, HashSet}; // Using HashSet to efficiently track unique QDUs involved
 use std::fmt;
 
 /// Represents an ordered sequence of Operations applied to a set of QDUs.
@@ -47,6 +59,11 @@ use std::fmt;
 /// println!("{}", circuit1);
 /// ```
 #[derive(Clone, PartialEq)] // PartialEq useful for testing circuits
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "CircuitData", into = "CircuitData")
+)]
 pub struct Circuit {
     /// The unique set of QDUs involved across all operations in this circuit.
     /// The `HashSet` ensures uniqueness and provides efficient lookup.
@@ -55,11 +72,22 @@ pub struct Circuit {
     /// The ordered sequence of operations defining the circuit's logic.
     /// The order is critical and directly reflects (Sequential Ordering).
     operations: Vec<Operation>,
-    // --- Potential Future Fields ---
-    // /// Optional name for identification or debugging.
-    // name: Option<String>,
-    // /// Optional explicit reference to the `ReferenceFrame` providing context.
-    // frame: Option<ReferenceFrame>, // Would require ReferenceFrame type from core
+
+    /// Optional name for identification in diagrams and result logs.
+    name: Option<String>,
+
+    /// Arbitrary key/value annotations (author, experiment id, ...), kept sorted by key.
+    metadata: BTreeMap<String, String>,
+
+    /// Named classical bits: bit name -> (index of the `Stabilize` operation whose
+    /// outcome it holds, QDU read).
+    bits: BTreeMap<String, (usize, QduId)>,
+
+    /// Number of basis qualities of each QDU declared a qudit; all others are binary.
+    dimensions: BTreeMap<QduId, usize>,
+
+    /// The `ReferenceFrame` each QDU was placed in; all others share the default frame.
+    frames: BTreeMap<QduId, ReferenceFrame>,
 }
 
 impl Circuit {
@@ -68,7 +96,11 @@ impl Circuit {
         Self {
             qdus: HashSet::new(),
             operations: Vec::new(),
-            // name: None,
+            name: None,
+            metadata: BTreeMap::new(),
+            bits: BTreeMap::new(),
+            dimensions: BTreeMap::new(),
+            frames: BTreeMap::new(),
             // frame: None,
         }
     }
@@ -122,14 +154,423 @@ impl Circuit {
         self.operations.is_empty()
     }
 
+    /// Sets the circuit's name.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    /// Returns the circuit's name, if one was set.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Declares `qdu` a QDU with `dimension` basis qualities, adding it to the circuit.
+    /// QDUs are binary (dimension 2) unless declared otherwise.
+    ///
+    /// Qudits (`dimension > 2`) evolve only under [`Operation::QuditPattern`] and
+    /// stabilize to a `StableState::ResolvedQuality` in `0..dimension`; they take no
+    /// part in interactions. The simulator rejects dimensions below 2.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId};
+    /// let mut circuit = CircuitBuilder::new().h(QduId(0)).build();
+    /// circuit.set_dimension(QduId(1), 3);
+    /// assert_eq!(circuit.dimension(&QduId(1)), 3);
+    /// assert_eq!(circuit.dimension(&QduId(0)), 2);
+    /// ```
+    pub fn set_dimension(&mut self, qdu: QduId, dimension: usize) {
+        self.qdus.insert(qdu);
+        if dimension == 2 {
+            self.dimensions.remove(&qdu);
+        } else {
+            self.dimensions.insert(qdu, dimension);
+        }
+    }
+
+    /// Returns the number of basis qualities of `qdu` (2 unless declared otherwise).
+    pub fn dimension(&self, qdu: &QduId) -> usize {
+        self.dimensions.get(qdu).copied().unwrap_or(2)
+    }
+
+    /// Returns the QDUs declared with a dimension other than 2, sorted by QDU.
+    pub fn dimensions(&self) -> &BTreeMap<QduId, usize> {
+        &self.dimensions
+    }
+
+    /// Places `qdu` in `frame`, adding it to the circuit. QDUs not placed in a frame
+    /// share the default frame.
+    ///
+    /// Frames scope interactions: the simulator rejects a `ControlledInteraction` or
+    /// `PauliProduct` coupling QDUs of different frames with
+    /// `OnqError::ReferenceViolation` unless a `RelationalLock` has first been
+    /// established between them (and not since released).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, ReferenceFrame};
+    /// let mut circuit = CircuitBuilder::new().h(QduId(0)).build();
+    /// circuit.set_frame(QduId(1), ReferenceFrame::new(7));
+    /// assert_eq!(circuit.frame(&QduId(1)).map(|frame| frame.id()), Some(7));
+    /// assert_eq!(circuit.frame(&QduId(0)), None);
+    /// ```
+    pub fn set_frame(&mut self, qdu: QduId, frame: ReferenceFrame) {
+        self.qdus.insert(qdu);
+        self.frames.insert(qdu, frame);
+    }
+
+    /// Returns the frame `qdu` was placed in, or `None` for the default frame.
+    pub fn frame(&self, qdu: &QduId) -> Option<&ReferenceFrame> {
+        self.frames.get(qdu)
+    }
+
+    /// Returns the QDUs placed in a frame, sorted by QDU.
+    pub fn frames(&self) -> &BTreeMap<QduId, ReferenceFrame> {
+        &self.frames
+    }
+
+    /// Sets the metadata entry `key` to `value`, returning the previous value if any.
+    pub fn set_metadata(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<String> {
+        self.metadata.insert(key.into(), value.into())
+    }
+
+    /// Returns the metadata entries, sorted by key.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Appends a `Stabilize` of `target` and names its outcome `bit`, so the result of a
+    /// simulation exposes it through [`SimulationResult::classical_bit`](crate::SimulationResult::classical_bit).
+    ///
+    /// The bit holds the outcome of this stabilization, even if `target` is stabilized
+    /// again later. Reusing a name rebinds it to the new stabilization.
+    pub fn stabilize_into(&mut self, target: QduId, bit: impl Into<String>) {
+        self.add_operation(Operation::Stabilize {
+            targets: vec![target],
+        });
+        self.bits
+            .insert(bit.into(), (self.operations.len() - 1, target));
+    }
+
+    /// Returns the QDU whose stabilization outcome the classical bit `name` holds.
+    pub fn classical_bit(&self, name: &str) -> Option<QduId> {
+        self.bits.get(name).map(|(_, qdu)| *qdu)
+    }
+
+    /// Returns the names of all classical bits and the QDUs they read, sorted by name.
+    pub fn classical_bits(&self) -> Vec<(&str, QduId)> {
+        self.bits
+            .iter()
+            .map(|(name, (_, qdu))| (name.as_str(), *qdu))
+            .collect()
+    }
+
+    /// Returns the classical bits bound to the operation at `index`.
+    pub(crate) fn bits_at(&self, index: usize) -> impl Iterator<Item = (&str, QduId)> {
+        self.bits
+            .iter()
+            .filter(move |(_, (i, _))| *i == index)
+            .map(|(name, (_, qdu))| (name.as_str(), *qdu))
+    }
+
+    /// Names the outcome of `qdu` in the `Stabilize` at operation `index`.
+    pub(crate) fn bind_bit(&mut self, name: impl Into<String>, index: usize, qdu: QduId) {
+        self.bits.insert(name.into(), (index, qdu));
+    }
+
+    /// Copies the classical bits of `source` onto this circuit, moving each binding to
+    /// `new_index(old_index)`; bindings mapped to `None` are dropped.
+    pub(crate) fn carry_bits(
+        &mut self,
+        source: &Circuit,
+        new_index: impl Fn(usize) -> Option<usize>,
+    ) {
+        for (name, (index, qdu)) in &source.bits {
+            if let Some(index) = new_index(*index) {
+                self.bits.insert(name.clone(), (index, *qdu));
+            }
+        }
+    }
+
+    /// Returns an empty circuit carrying this circuit's name, metadata, QDU
+    /// dimensions and frames, as the starting point of circuits derived from it.
+    pub(crate) fn derived(&self) -> Circuit {
+        Circuit {
+            name: self.name.clone(),
+            metadata: self.metadata.clone(),
+            dimensions: self.dimensions.clone(),
+            frames: self.frames.clone(),
+            ..Circuit::new()
+        }
+    }
+
+    /// Fills in the name and any metadata keys not yet set from `other`, and takes
+    /// over the dimensions and frames of its QDUs.
+    fn merge_identity(&mut self, other: &Circuit) {
+        self.dimensions.extend(&other.dimensions);
+        self.frames.extend(
+            other
+                .frames
+                .iter()
+                .map(|(qdu, frame)| (*qdu, frame.clone())),
+        );
+        if self.name.is_none() {
+            self.name = other.name.clone();
+        }
+        for (key, value) in &other.metadata {
+            self.metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Returns the adjoint circuit: the operations in reverse order, each replaced by
+    /// its [`Operation::inverse`]. Running a circuit followed by its inverse restores the
+    /// initial state, which is the basis of uncomputation.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if any operation is not invertible
+    /// (e.g. `Stabilize` or `RelationalLock`).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let circuit = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "Superposition".to_string() })
+    ///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "SqrtFlip".to_string() })
+    ///     .build();
+    /// let inverse = circuit.inverse().unwrap();
+    /// assert_eq!(
+    ///     inverse.operations()[0],
+    ///     Operation::InteractionPattern { target: QduId(0), pattern_id: "SqrtFlip_Inv".to_string() }
+    /// );
+    /// ```
+    pub fn inverse(&self) -> Result<Circuit, OnqError> {
+        let mut inverse = self.derived();
+        for op in self.operations.iter().rev() {
+            inverse.add_operation(op.inverse()?);
+        }
+        Ok(inverse)
+    }
+
+    /// Groups the operations into moments: parallel layers in which no two operations
+    /// involve the same QDU. Each operation is placed in the earliest moment following
+    /// every earlier operation it shares a QDU with, so per-QDU order is preserved.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let h = |q| Operation::InteractionPattern { target: QduId(q), pattern_id: "Superposition".to_string() };
+    /// let circuit = CircuitBuilder::new()
+    ///     .add_op(h(0))
+    ///     .add_op(h(1))
+    ///     .add_op(Operation::ControlledInteraction {
+    ///         control: QduId(0),
+    ///         target: QduId(1),
+    ///         pattern_id: "QualityFlip".to_string(),
+    ///     })
+    ///     .add_op(h(2))
+    ///     .build();
+    ///
+    /// let moments = circuit.moments();
+    /// assert_eq!(moments.len(), 2);
+    /// assert_eq!(moments[0], vec![&h(0), &h(1), &h(2)]);
+    /// assert_eq!(circuit.depth(), 2);
+    /// ```
+    pub fn moments(&self) -> Vec<Vec<&Operation>> {
+        let layers = schedule(&self.operations, |op| self.occupied_qdus(op));
+        let mut moments: Vec<Vec<&Operation>> =
+            vec![Vec::new(); layers.iter().max().map_or(0, |last| last + 1)];
+        for (op, layer) in self.operations.iter().zip(layers) {
+            moments[layer].push(op);
+        }
+        moments
+    }
+
+    /// Returns the circuit depth: the number of [moments](Circuit::moments).
+    pub fn depth(&self) -> usize {
+        schedule(&self.operations, |op| self.occupied_qdus(op))
+            .into_iter()
+            .max()
+            .map_or(0, |last| last + 1)
+    }
+
+    /// Returns the QDUs `op` is ordered against: the QDUs it involves, or every QDU of
+    /// the circuit for a `Snapshot`, which records the whole state.
+    pub(crate) fn occupied_qdus(&self, op: &Operation) -> Vec<QduId> {
+        match op {
+            Operation::Snapshot { .. } => self.qdus.iter().copied().collect(),
+            _ => op.involved_qdus(),
+        }
+    }
+
+    /// Returns the dependency graph of this circuit (see [`CircuitDag`]).
+    pub fn to_dag(&self) -> CircuitDag {
+        CircuitDag::new(self)
+    }
+
+    /// Combines this circuit with `other`, which must act on a disjoint set of QDUs,
+    /// into one circuit running both side by side. The result interleaves the two
+    /// circuits moment by moment, so its depth is the larger of the two depths.
+    ///
+    /// Name and metadata are taken from `self`, with `other` filling in missing entries.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` listing the shared QDUs if the QDU sets overlap.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId};
+    /// let a = CircuitBuilder::new().h(QduId(0)).x(QduId(0)).build();
+    /// let b = CircuitBuilder::new().h(QduId(1)).cnot(QduId(1), QduId(2)).build();
+    ///
+    /// let both = a.tensor(&b).unwrap();
+    /// assert_eq!(both.len(), 4);
+    /// assert_eq!(both.depth(), 2);
+    /// assert!(a.tensor(&a).is_err());
+    /// ```
+    pub fn tensor(&self, other: &Circuit) -> Result<Circuit, OnqError> {
+        let mut shared: Vec<QduId> = self.qdus.intersection(&other.qdus).copied().collect();
+        if !shared.is_empty() {
+            shared.sort();
+            let shared: Vec<String> = shared.iter().map(QduId::to_string).collect();
+            return Err(OnqError::InvalidOperation {
+                message: format!("Cannot tensor circuits sharing QDUs: {}", shared.join(", ")),
+            });
+        }
+
+        let layers =
+            |circuit: &Circuit| schedule(&circuit.operations, |op| circuit.occupied_qdus(op));
+        let (left, right) = (layers(self), layers(other));
+        // (moment, side, index) sorts into moment-by-moment order, left side first
+        let mut order: Vec<(usize, usize, usize)> = left
+            .iter()
+            .enumerate()
+            .map(|(i, &moment)| (moment, 0, i))
+            .chain(right.iter().enumerate().map(|(i, &moment)| (moment, 1, i)))
+            .collect();
+        order.sort();
+
+        let mut combined = self.derived();
+        let mut new_index = [vec![0; self.len()], vec![0; other.len()]];
+        for (position, &(_, side, i)) in order.iter().enumerate() {
+            let source = if side == 0 { self } else { other };
+            combined.add_operation(source.operations[i].clone());
+            new_index[side][i] = position;
+        }
+        combined
+            .qdus
+            .extend(self.qdus.iter().chain(&other.qdus).copied());
+        combined.carry_bits(self, |i| Some(new_index[0][i]));
+        combined.carry_bits(other, |i| Some(new_index[1][i]));
+        combined.merge_identity(other);
+        Ok(combined)
+    }
+
+    /// Returns a circuit with this circuit's operations repeated `n` times, e.g. to
+    /// express a number of Grover-style iterations declaratively.
+    ///
+    /// Trailing `Stabilize` operations are treated as final readout rather than part of
+    /// the repeated body: they are kept once, after the last repetition.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let flip = Operation::InteractionPattern { target: QduId(0), pattern_id: "QualityFlip".to_string() };
+    /// let readout = Operation::Stabilize { targets: vec![QduId(0)] };
+    /// let circuit = CircuitBuilder::new().add_op(flip.clone()).add_op(readout.clone()).build();
+    ///
+    /// let repeated = circuit.repeat(3);
+    /// assert_eq!(repeated.operations(), &[flip.clone(), flip.clone(), flip, readout]);
+    /// ```
+    pub fn repeat(&self, n: usize) -> Circuit {
+        let body_len = self
+            .operations
+            .iter()
+            .rposition(|op| !matches!(op, Operation::Stabilize { .. }))
+            .map_or(0, |last| last + 1);
+        let (body, readout) = self.operations.split_at(body_len);
+
+        let mut repeated = self.derived();
+        for _ in 0..n {
+            repeated.add_operations(body.iter().cloned());
+        }
+        repeated.add_operations(readout.iter().cloned());
+        // Keep QDUs that only appear in the body known even when n == 0
+        repeated.qdus.extend(self.qdus.iter().copied());
+        // Body bits follow the last repetition; readout bits move past every repetition
+        repeated.carry_bits(self, |i| (i + n * body_len).checked_sub(body_len));
+        repeated
+    }
+
     // --- Potential Future Methods ---
-    // pub fn set_name(&mut self, name: String) { self.name = Some(name); }
-    // pub fn name(&self) -> Option<&str> { self.name.as_deref() }
-    // pub fn set_frame(&mut self, frame: ReferenceFrame) { self.frame = Some(frame); }
-    // pub fn frame(&self) -> Option<&ReferenceFrame> { self.frame.as_ref() }
     // pub fn validate(&self) -> Result<(), OnqError> { /* Check internal consistency */ Ok(()) }
 }
 
+/// Serialized form of a [`Circuit`]: QDUs sorted for stable output, and the
+/// QDU set rebuilt from the operations on deserialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CircuitData {
+    qdus: Vec<QduId>,
+    operations: Vec<Operation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    bits: BTreeMap<String, (usize, QduId)>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    dimensions: BTreeMap<QduId, usize>,
+    /// Frame IDs by QDU.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    frames: BTreeMap<QduId, u64>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Circuit> for CircuitData {
+    fn from(circuit: Circuit) -> Self {
+        let mut qdus: Vec<QduId> = circuit.qdus.into_iter().collect();
+        qdus.sort();
+        Self {
+            qdus,
+            operations: circuit.operations,
+            name: circuit.name,
+            metadata: circuit.metadata,
+            bits: circuit.bits,
+            dimensions: circuit.dimensions,
+            frames: circuit
+                .frames
+                .iter()
+                .map(|(qdu, frame)| (*qdu, frame.id()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<CircuitData> for Circuit {
+    fn from(data: CircuitData) -> Self {
+        let mut circuit = Circuit::new();
+        circuit.add_operations(data.operations);
+        circuit.qdus.extend(data.qdus);
+        circuit.name = data.name;
+        circuit.metadata = data.metadata;
+        circuit.bits = data.bits;
+        circuit.dimensions = data.dimensions;
+        circuit.frames = data
+            .frames
+            .into_iter()
+            .map(|(qdu, id)| (qdu, ReferenceFrame::new(id)))
+            .collect();
+        circuit
+    }
+}
+
 // Implement Default for convenient creation of empty circuits.
 impl Default for Circuit {
     fn default() -> Self {
@@ -159,9 +600,6 @@ impl Default for Circuit {
 /// ```
 pub struct CircuitBuilder {
     circuit: Circuit,
-    // Potential future fields:
-    // - qdu_allocator: QduAllocator, // To manage unique QDU ID creation during build
-    // - default_frame: Option<ReferenceFrame>,
 }
 
 impl CircuitBuilder {
@@ -169,11 +607,61 @@ impl CircuitBuilder {
     pub fn new() -> Self {
         Self {
             circuit: Circuit::new(),
-            // qdu_allocator: QduAllocator::new(),
-            // default_frame: None,
         }
     }
 
+    /// Creates a builder pre-populated from an OpenQASM 2 program, so further operations
+    /// can be appended. See [`qasm`] for the supported subset.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` naming the line of the first unsupported
+    /// or malformed statement.
+    pub fn from_qasm(source: &str) -> Result<Self, OnqError> {
+        Ok(Self {
+            circuit: qasm::parse_qasm(source)?,
+        })
+    }
+
+    /// Allocates a fresh QDU: one greater than every QDU the circuit knows so far.
+    ///
+    /// The QDU is registered with the circuit immediately, so it is never handed out
+    /// twice and it appears as an (idle) wire even if no operation uses it. Because QDUs
+    /// added through operations are known too, allocation stays unique when subcircuits
+    /// with hand-picked IDs are composed into the builder.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let mut builder = CircuitBuilder::new().add_op(Operation::InteractionPattern {
+    ///     target: QduId(4),
+    ///     pattern_id: "Superposition".to_string(),
+    /// });
+    /// let ancilla = builder.allocate_qdu();
+    /// assert_eq!(ancilla, QduId(5));
+    /// assert_eq!(builder.allocate_qdus(2), vec![QduId(6), QduId(7)]);
+    /// assert_eq!(builder.build().qdus().len(), 4);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the circuit already uses `QduId(u64::MAX)`.
+    pub fn allocate_qdu(&mut self) -> QduId {
+        let next = self
+            .circuit
+            .qdus
+            .iter()
+            .map(|qdu| qdu.0.checked_add(1).expect("QduId space exhausted"))
+            .max()
+            .unwrap_or(0);
+        let qdu = QduId(next);
+        self.circuit.qdus.insert(qdu);
+        qdu
+    }
+
+    /// Allocates `n` fresh QDUs with consecutive IDs (see [`allocate_qdu`](Self::allocate_qdu)).
+    pub fn allocate_qdus(&mut self, n: usize) -> Vec<QduId> {
+        (0..n).map(|_| self.allocate_qdu()).collect()
+    }
+
     /// Adds a single operation to the circuit being built.
     ///
     /// Returns `self` to allow for continued method chaining.
@@ -193,11 +681,229 @@ impl CircuitBuilder {
         self
     }
 
-    // --- Potential Future Builder Methods ---
-    // pub fn with_name(mut self, name: String) -> Self { self.circuit.set_name(name); self }
-    // pub fn with_frame(mut self, frame: ReferenceFrame) -> Self { self.circuit.set_frame(frame); self }
-    // /// Allocates a new QDU within the builder's context (requires allocator field)
-    // pub fn allocate_qdu(&mut self) -> QduId { self.qdu_allocator.allocate() }
+    /// Couples each consecutive pair of `qdus` with a `ControlledInteraction`, in register
+    /// order: `(q0, q1), (q1, q2), …`. The earlier QDU of each pair is the control.
+    ///
+    /// Note the engine's Locality Rule still applies to every emitted pair.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId};
+    /// let reg: Vec<QduId> = (0..4).map(QduId).collect();
+    /// let chain = CircuitBuilder::new().entangle_chain(&reg, "PhaseIntroduce").build();
+    /// let ring = CircuitBuilder::new().entangle_ring(&reg, "PhaseIntroduce").build();
+    /// let all = CircuitBuilder::new().entangle_all_pairs(&reg, "PhaseIntroduce").build();
+    /// assert_eq!((chain.len(), ring.len(), all.len()), (3, 4, 6));
+    /// ```
+    pub fn entangle_chain(self, qdus: &[QduId], pattern_id: &str) -> Self {
+        let pairs: Vec<(QduId, QduId)> = qdus.windows(2).map(|w| (w[0], w[1])).collect();
+        self.add_ops(controlled_pairs(&pairs, pattern_id))
+    }
+
+    /// Like [`entangle_chain`](Self::entangle_chain), then closes the ring by coupling the
+    /// last QDU (as control) back to the first. Registers of fewer than three QDUs have
+    /// no distinct closing pair and produce just the chain.
+    pub fn entangle_ring(self, qdus: &[QduId], pattern_id: &str) -> Self {
+        let builder = self.entangle_chain(qdus, pattern_id);
+        match qdus {
+            [first, .., last] if qdus.len() > 2 => {
+                builder.add_ops(controlled_pairs(&[(*last, *first)], pattern_id))
+            }
+            _ => builder,
+        }
+    }
+
+    /// Couples every pair of `qdus` with a `ControlledInteraction`, ordered by position in
+    /// the register: `(q0, q1), (q0, q2), …, (q1, q2), …`. The earlier QDU is the control.
+    pub fn entangle_all_pairs(self, qdus: &[QduId], pattern_id: &str) -> Self {
+        let pairs: Vec<(QduId, QduId)> = qdus
+            .iter()
+            .enumerate()
+            .flat_map(|(i, control)| qdus[i + 1..].iter().map(move |target| (*control, *target)))
+            .collect();
+        self.add_ops(controlled_pairs(&pairs, pattern_id))
+    }
+
+    /// Applies the interaction pattern `pattern_id` to `target`.
+    pub fn pattern(self, target: QduId, pattern_id: &str) -> Self {
+        self.add_op(Operation::InteractionPattern {
+            target,
+            pattern_id: pattern_id.to_string(),
+        })
+    }
+
+    /// Applies `Superposition` (H analog) to `target`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let (q0, q1) = (QduId(0), QduId(1));
+    /// let fluent = CircuitBuilder::new().h(q0).cnot(q0, q1).stabilize(&[q0, q1]).build();
+    /// let explicit = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() })
+    ///     .add_op(Operation::ControlledInteraction {
+    ///         control: q0,
+    ///         target: q1,
+    ///         pattern_id: "QualityFlip".to_string(),
+    ///     })
+    ///     .add_op(Operation::Stabilize { targets: vec![q0, q1] })
+    ///     .build();
+    /// assert_eq!(fluent, explicit);
+    /// ```
+    pub fn h(self, target: QduId) -> Self {
+        self.pattern(target, "Superposition")
+    }
+
+    /// Applies `QualityFlip` (X analog) to `target`.
+    pub fn x(self, target: QduId) -> Self {
+        self.pattern(target, "QualityFlip")
+    }
+
+    /// Applies `QualitativeY` (Y analog) to `target`.
+    pub fn y(self, target: QduId) -> Self {
+        self.pattern(target, "QualitativeY")
+    }
+
+    /// Applies `PhaseIntroduce` (Z analog) to `target`.
+    pub fn z(self, target: QduId) -> Self {
+        self.pattern(target, "PhaseIntroduce")
+    }
+
+    /// Applies `HalfPhase` (S analog) to `target`.
+    pub fn s(self, target: QduId) -> Self {
+        self.pattern(target, "HalfPhase")
+    }
+
+    /// Applies `QuarterPhase` (T analog) to `target`.
+    pub fn t(self, target: QduId) -> Self {
+        self.pattern(target, "QuarterPhase")
+    }
+
+    /// Applies `SqrtFlip` (√X analog) to `target`.
+    pub fn sx(self, target: QduId) -> Self {
+        self.pattern(target, "SqrtFlip")
+    }
+
+    /// Applies a `PhaseShift` of `theta` radians to `target`.
+    pub fn phase(self, target: QduId, theta: f64) -> Self {
+        self.add_op(Operation::PhaseShift { target, theta })
+    }
+
+    /// Declares `target` a QDU with `dimension` basis qualities (see
+    /// [`Circuit::set_dimension`]).
+    pub fn qudit(mut self, target: QduId, dimension: usize) -> Self {
+        self.circuit.set_dimension(target, dimension);
+        self
+    }
+
+    /// Places `target` in `frame` (see [`Circuit::set_frame`]).
+    pub fn in_frame(mut self, target: QduId, frame: ReferenceFrame) -> Self {
+        self.circuit.set_frame(target, frame);
+        self
+    }
+
+    /// Applies the `d`x`d` unitary `matrix` to `target` as a `QuditPattern`.
+    pub fn qudit_pattern(self, target: QduId, matrix: Vec<Vec<Complex<f64>>>) -> Self {
+        self.add_op(Operation::QuditPattern { target, matrix })
+    }
+
+    /// Applies `pattern_id` to `target`, conditioned on `control`.
+    pub fn controlled(self, control: QduId, target: QduId, pattern_id: &str) -> Self {
+        self.add_ops(controlled_pairs(&[(control, target)], pattern_id))
+    }
+
+    /// Applies a controlled `QualityFlip` (CNOT analog).
+    pub fn cnot(self, control: QduId, target: QduId) -> Self {
+        self.controlled(control, target, "QualityFlip")
+    }
+
+    /// Applies a controlled `PhaseIntroduce` (CZ analog).
+    pub fn cz(self, control: QduId, target: QduId) -> Self {
+        self.controlled(control, target, "PhaseIntroduce")
+    }
+
+    /// Stabilizes `targets`.
+    pub fn stabilize(self, targets: &[QduId]) -> Self {
+        self.add_op(Operation::Stabilize {
+            targets: targets.to_vec(),
+        })
+    }
+
+    /// Records the state under `label` (see [`Operation::Snapshot`] and
+    /// [`SimulationResult::snapshot`](crate::simulation::SimulationResult::snapshot)).
+    pub fn snapshot(self, label: &str) -> Self {
+        self.add_op(Operation::Snapshot {
+            label: label.to_string(),
+        })
+    }
+
+    /// Stabilizes `target` and names its outcome `bit` (see [`Circuit::stabilize_into`]).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// let circuit = CircuitBuilder::new()
+    ///     .x(QduId(0))
+    ///     .stabilize_into(QduId(0), "m0")
+    ///     .x(QduId(0))
+    ///     .stabilize_into(QduId(0), "m1")
+    ///     .build();
+    ///
+    /// let result = Simulator::new().run(&circuit).unwrap();
+    /// assert_eq!(result.classical_bit("m0"), Some(1));
+    /// assert_eq!(result.classical_bit("m1"), Some(0));
+    /// ```
+    pub fn stabilize_into(mut self, target: QduId, bit: &str) -> Self {
+        self.circuit.stabilize_into(target, bit);
+        self
+    }
+
+    /// Names the circuit being built.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.circuit.set_name(name);
+        self
+    }
+
+    /// Adds a metadata entry to the circuit being built, replacing any previous value.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.circuit.set_metadata(key, value);
+        self
+    }
+
+    /// Appends all operations and QDUs of `circuit`, composing it into the circuit
+    /// being built.
+    ///
+    /// Name and metadata are preserved: entries already on the builder win, and
+    /// the appended circuit's name and metadata fill in whatever is not yet set.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId};
+    /// let prep = CircuitBuilder::new()
+    ///     .with_name("bell-prep")
+    ///     .with_metadata("author", "alice")
+    ///     .h(QduId(0))
+    ///     .cnot(QduId(0), QduId(1))
+    ///     .build();
+    /// let experiment = CircuitBuilder::new()
+    ///     .with_metadata("run", "42")
+    ///     .append(&prep)
+    ///     .stabilize(&[QduId(0), QduId(1)])
+    ///     .build();
+    ///
+    /// assert_eq!(experiment.len(), 3);
+    /// assert_eq!(experiment.name(), Some("bell-prep"));
+    /// assert_eq!(experiment.metadata().len(), 2);
+    /// ```
+    pub fn append(mut self, circuit: &Circuit) -> Self {
+        let offset = self.circuit.len();
+        self.circuit.carry_bits(circuit, |i| Some(i + offset));
+        self.circuit
+            .add_operations(circuit.operations.iter().cloned());
+        self.circuit.qdus.extend(circuit.qdus.iter().copied());
+        self.circuit.merge_identity(circuit);
+        self
+    }
 
     /// Finalizes the construction process and returns the built `Circuit`.
     pub fn build(self) -> Circuit {
@@ -214,15 +920,91 @@ impl Default for CircuitBuilder {
     }
 }
 
-impl fmt::Display for Circuit {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// Options for [`Circuit::render`], the configurable form of the ASCII diagram
+/// produced by `Display`.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId};
+/// # use onq::circuits::CircuitDisplayOptions;
+/// let circuit = CircuitBuilder::new().h(QduId(0)).cnot(QduId(0), QduId(1)).build();
+/// let options = CircuitDisplayOptions {
+///     gate_width: 5,
+///     unicode: false,
+///     ..CircuitDisplayOptions::default()
+/// };
+/// let diagram = circuit.render(&options);
+/// assert!(diagram.contains("QDU(0): --H----@--"));
+/// assert!(diagram.contains("           |  "));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitDisplayOptions {
+    /// Width of each column in characters; longer gate symbols are truncated.
+    pub gate_width: usize,
+    /// Draw wires and symbols with Unicode box-drawing characters (`─`, `│`, `●`, `√X`)
+    /// rather than plain ASCII (`-`, `|`, `*`, `SX`).
+    pub unicode: bool,
+    /// Wrap the diagram into blocks of at most this many columns, or `None` for one block.
+    pub max_columns: Option<usize>,
+    /// Keep a column for each `Identity` pattern (drawn as a plain wire); when `false`,
+    /// `Identity` patterns are left out of the layout so they leave no gaps.
+    pub show_identity_gaps: bool,
+}
+
+impl Default for CircuitDisplayOptions {
+    fn default() -> Self {
+        Self {
+            gate_width: 7,
+            unicode: true,
+            max_columns: None,
+            show_identity_gaps: true,
+        }
+    }
+}
+
+impl Circuit {
+    /// Renders the ASCII diagram with the given options. `Display` uses
+    /// [`CircuitDisplayOptions::default`].
+    pub fn render(&self, options: &CircuitDisplayOptions) -> String {
+        let mut diagram = String::new();
+        // Writing into a String cannot fail
+        let _ = self.write_diagram(&mut diagram, options);
+        diagram
+    }
+
+    fn write_diagram<W: fmt::Write>(
+        &self,
+        f: &mut W,
+        options: &CircuitDisplayOptions,
+    ) -> fmt::Result {
+        // Header: optional name, counts, then any metadata
+        let header = |f: &mut W, num_ops: usize, num_qdus: usize| {
+            match &self.name {
+                Some(name) => write!(f, "onq::Circuit \"{}\"", name)?,
+                None => write!(f, "onq::Circuit")?,
+            }
+            writeln!(f, "[{} operations on {} QDUs]", num_ops, num_qdus)?;
+            for (key, value) in &self.metadata {
+                writeln!(f, "  {} = {}", key, value)?;
+            }
+            Ok(())
+        };
+
         if self.operations.is_empty() {
-            return writeln!(f, "onq::Circuit[0 operations on 0 QDUs]");
+            return header(f, 0, 0);
         }
 
         // --- Setup ---
-        let ops = &self.operations;
-        let num_ops = ops.len();
+        let num_ops = self.operations.len();
+        let ops: Vec<Operation> = self
+            .operations
+            .iter()
+            .filter(|op| {
+                options.show_identity_gaps
+                    || !matches!(op, Operation::InteractionPattern { pattern_id, .. } if pattern_id == "Identity")
+            })
+            .cloned()
+            .collect();
 
         // Get sorted list of unique QDUs and create row map
         let mut sorted_qdus: Vec<QduId> = self.qdus.iter().cloned().collect();
@@ -242,38 +1024,63 @@ impl fmt::Display for Circuit {
             .unwrap_or(0);
         let label_padding = " ".repeat(max_label_width + 2); // Label + ": "
 
+        // Align operations by moment. Operations drawn with vertical connectors also
+        // claim the rows they cross, so connectors never run through another gate.
+        let columns = schedule(&ops, |op| {
+            let mut rows: Vec<usize> = self
+                .occupied_qdus(op)
+                .iter()
+                .filter_map(|qid| qdu_to_row.get(qid).copied())
+                .collect();
+            if connects_rows(op)
+                && let (Some(&r_min), Some(&r_max)) = (rows.iter().min(), rows.iter().max())
+            {
+                rows = (r_min..=r_max).collect();
+            }
+            rows
+        });
+        let num_columns = columns.iter().max().map_or(0, |last| last + 1);
+
         // Grid dimensions and padding
-        const GATE_WIDTH: usize = 7; // e.g., "───H───"
-        const WIRE: &str = "───────"; // GATE_WIDTH dashes
-        const V_WIRE: char = '│';
-        const H_WIRE: char = '─';
+        let gate_width = options.gate_width.max(1); // e.g., "───H───"
+        let (h_wire, v_wire) = if options.unicode {
+            ('─', '│')
+        } else {
+            ('-', '|')
+        };
+        let wire = h_wire.to_string().repeat(gate_width);
 
         // Initialize grids
         // op_grid[row][time] stores the gate/wire segment string
-        let mut op_grid: Vec<Vec<String>> = vec![vec![WIRE.to_string(); num_ops]; num_qdus];
+        let mut op_grid: Vec<Vec<String>> = vec![vec![wire.clone(); num_columns]; num_qdus];
         // v_connect[row][time] stores the vertical connector char below this row at this time
-        let mut v_connect: Vec<Vec<char>> = vec![vec![' '; num_ops]; num_qdus]; // Note size N x T
+        let mut v_connect: Vec<Vec<char>> = vec![vec![' '; num_columns]; num_qdus]; // Note size N x T
 
         // Helper to format a gate symbol
-        fn format_gate(symbol: &str) -> String {
+        let format_gate = |symbol: &str| -> String {
+            let symbol = if options.unicode {
+                symbol.to_string()
+            } else {
+                ascii_symbol(symbol)
+            };
             let slen = symbol.chars().count(); // Use chars().count() for Unicode width if needed
-            if slen >= GATE_WIDTH {
-                symbol.chars().take(GATE_WIDTH).collect()
+            if slen >= gate_width {
+                symbol.chars().take(gate_width).collect()
             } else {
-                let total_dashes = GATE_WIDTH - slen;
+                let total_dashes = gate_width - slen;
                 let pre_dashes = total_dashes / 2;
                 let post_dashes = total_dashes - pre_dashes;
                 format!(
                     "{}{}{}",
-                    H_WIRE.to_string().repeat(pre_dashes),
+                    h_wire.to_string().repeat(pre_dashes),
                     symbol,
-                    H_WIRE.to_string().repeat(post_dashes)
+                    h_wire.to_string().repeat(post_dashes)
                 )
             }
-        }
+        };
 
         // --- Populate Grids ---
-        for (t, op) in ops.iter().enumerate() {
+        for (op, &t) in ops.iter().zip(&columns) {
             match op {
                 Operation::PhaseShift { target, .. } => {
                     if let Some(r) = qdu_to_row.get(target) {
@@ -281,24 +1088,52 @@ impl fmt::Display for Circuit {
                     }
                 }
                 Operation::InteractionPattern { target, pattern_id } => {
-                    if let Some(r) = qdu_to_row.get(target) {
-                        let symbol = match pattern_id.as_str() {
-                            "Identity" => continue, // Skip explicit Identity, leave wire
-                            "QualityFlip" => "X",
-                            "PhaseIntroduce" => "Z",
-                            "HalfPhase" => "S",
-                            "HalfPhase_Inv" => "S†",
-                            "QuarterPhase" => "T",
-                            "QuarterPhase_Inv" => "T†",
-                            "QualitativeY" => "Y",
-                            "PhiRotate" => "ΦR", // Using Φ symbol + R
-                            "Superposition" => "H",
-                            "SqrtFlip" => "√X", // Using √ symbol + X
-                            _ => "?",           // Unknown pattern
-                        };
+                    if let (Some(r), Some(symbol)) =
+                        (qdu_to_row.get(target), pattern_symbol(pattern_id))
+                    {
                         op_grid[*r][t] = format_gate(symbol);
                     }
                 }
+                Operation::Project { target, onto } => {
+                    if let Some(r) = qdu_to_row.get(target) {
+                        op_grid[*r][t] = format_gate(&format!("|{}>", onto.index()));
+                    }
+                }
+                Operation::Relax { target, .. } => {
+                    if let Some(r) = qdu_to_row.get(target) {
+                        op_grid[*r][t] = format_gate("γ");
+                    }
+                }
+                Operation::MatrixPattern { target, .. } => {
+                    if let Some(r) = qdu_to_row.get(target) {
+                        op_grid[*r][t] = format_gate("U");
+                    }
+                }
+                Operation::QuditPattern { target, matrix } => {
+                    if let Some(r) = qdu_to_row.get(target) {
+                        op_grid[*r][t] = format_gate(&format!("U{}", matrix.len()));
+                    }
+                }
+                Operation::BroadcastPattern {
+                    targets,
+                    pattern_id,
+                } => {
+                    // Skip explicit Identity, leave wires
+                    if let Some(symbol) = pattern_symbol(pattern_id) {
+                        for target_qid in targets {
+                            if let Some(r) = qdu_to_row.get(target_qid) {
+                                op_grid[*r][t] = format_gate(symbol);
+                            }
+                        }
+                    }
+                }
+                Operation::BroadcastPhaseShift { targets, .. } => {
+                    for target_qid in targets {
+                        if let Some(r) = qdu_to_row.get(target_qid) {
+                            op_grid[*r][t] = format_gate("P");
+                        }
+                    }
+                }
                 Operation::ControlledInteraction {
                     control,
                     target,
@@ -319,7 +1154,41 @@ impl fmt::Display for Circuit {
                         let r_min = (*r_ctrl).min(*r_tgt);
                         let r_max = (*r_ctrl).max(*r_tgt);
                         for row_vec in v_connect.iter_mut().take(r_max).skip(r_min) {
-                            row_vec[t] = V_WIRE;
+                            row_vec[t] = v_wire;
+                        }
+                    }
+                }
+                Operation::PauliProduct { terms, .. } => {
+                    let rows: Vec<(usize, &PauliAxis)> = terms
+                        .iter()
+                        .filter_map(|(qid, axis)| qdu_to_row.get(qid).map(|r| (*r, axis)))
+                        .collect();
+                    for (r, axis) in &rows {
+                        op_grid[*r][t] = format_gate(&format!("{:?}", axis));
+                    }
+
+                    // Connect the coupled QDUs vertically
+                    if let (Some(r_min), Some(r_max)) = (
+                        rows.iter().map(|(r, _)| *r).min(),
+                        rows.iter().map(|(r, _)| *r).max(),
+                    ) {
+                        for row_vec in v_connect.iter_mut().take(r_max).skip(r_min) {
+                            row_vec[t] = v_wire;
+                        }
+                    }
+                }
+                Operation::Permute { mapping } => {
+                    // Each source row shows where its potentiality is moved to
+                    let mut rows = Vec::with_capacity(mapping.len());
+                    for (from, to) in mapping {
+                        if let Some(r) = qdu_to_row.get(from) {
+                            op_grid[*r][t] = format_gate(&format!("→{}", to.0));
+                            rows.push(*r);
+                        }
+                    }
+                    if let (Some(r_min), Some(r_max)) = (rows.iter().min(), rows.iter().max()) {
+                        for row_vec in v_connect.iter_mut().take(*r_max).skip(*r_min) {
+                            row_vec[t] = v_wire;
                         }
                     }
                 }
@@ -332,10 +1201,23 @@ impl fmt::Display for Circuit {
 
                         // Add vertical connection lines
                         for row_vec in v_connect.iter_mut().take(r_max).skip(r_min) {
-                            row_vec[t] = V_WIRE;
+                            row_vec[t] = v_wire;
                         }
                     }
                 }
+                Operation::Delay { targets, ticks } => {
+                    for target_qid in targets {
+                        if let Some(r) = qdu_to_row.get(target_qid) {
+                            op_grid[*r][t] = format_gate(&format!("Δ{}", ticks));
+                        }
+                    }
+                }
+                Operation::Snapshot { .. } => {
+                    // A snapshot captures every QDU: mark the whole column
+                    for row in op_grid.iter_mut() {
+                        row[t] = format_gate("◇");
+                    }
+                }
                 Operation::Stabilize { targets } => {
                     for target_qid in targets {
                         if let Some(r) = qdu_to_row.get(target_qid) {
@@ -348,39 +1230,135 @@ impl fmt::Display for Circuit {
         }
 
         // --- Format Output String ---
-        writeln!(
-            f,
-            "onq::Circuit[{} operations on {} QDUs]",
-            num_ops, num_qdus
-        )?;
-        for r in 0..num_qdus {
-            // Print QDU label row
-            let label = format!("{}: ", sorted_qdus[r]);
-            write!(f, "{:<width$}", label, width = max_label_width + 2)?;
-            writeln!(f, "{}", op_grid[r].join(""))?;
-
-            // Print vertical connector row (if not the last QDU)
-            if r < num_qdus - 1 {
-                write!(f, "{}", label_padding)?; // Padding for alignment
-                for connector in v_connect[r].iter().take(num_ops) {
-                    let padding_needed = GATE_WIDTH.saturating_sub(1); // Width minus 1 for the connector char
-                    let pre_pad = padding_needed / 2;
-                    let post_pad = padding_needed - pre_pad;
-                    write!(
-                        f,
-                        "{}{}{}",
-                        " ".repeat(pre_pad),
-                        connector,
-                        " ".repeat(post_pad)
-                    )?;
+        header(f, num_ops, num_qdus)?;
+        let block_width = options.max_columns.unwrap_or(num_columns).max(1);
+        let blocks: Vec<usize> = (0..num_columns).step_by(block_width).collect();
+        for (block, &start) in blocks.iter().enumerate() {
+            let end = (start + block_width).min(num_columns);
+            if block > 0 {
+                writeln!(f)?; // Blank line between wrapped blocks
+            }
+            for r in 0..num_qdus {
+                // Print QDU label row
+                let label = format!("{}: ", sorted_qdus[r]);
+                write!(f, "{:<width$}", label, width = max_label_width + 2)?;
+                writeln!(f, "{}", op_grid[r][start..end].join(""))?;
+
+                // Print vertical connector row (if not the last QDU)
+                if r < num_qdus - 1 {
+                    write!(f, "{}", label_padding)?; // Padding for alignment
+                    for connector in &v_connect[r][start..end] {
+                        let padding_needed = gate_width.saturating_sub(1); // Width minus 1 for the connector char
+                        let pre_pad = padding_needed / 2;
+                        let post_pad = padding_needed - pre_pad;
+                        write!(
+                            f,
+                            "{}{}{}",
+                            " ".repeat(pre_pad),
+                            connector,
+                            " ".repeat(post_pad)
+                        )?;
+                    }
+                    writeln!(f)?; // Newline after connector row
                 }
-                writeln!(f)?; // Newline after connector row
             }
         }
         Ok(())
     }
 }
 
+impl fmt::Display for Circuit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_diagram(f, &CircuitDisplayOptions::default())
+    }
+}
+
+/// Replaces the non-ASCII characters of a diagram symbol with ASCII stand-ins.
+fn ascii_symbol(symbol: &str) -> String {
+    let mut ascii = String::with_capacity(symbol.len());
+    for c in symbol.chars() {
+        match c {
+            '●' => ascii.push('*'),
+            '†' => ascii.push('\''),
+            '√' => ascii.push('S'),
+            'Φ' => ascii.push_str("Phi"),
+            'γ' => ascii.push('g'),
+            'Δ' => ascii.push('D'),
+            '→' => ascii.push('>'),
+            c => ascii.push(c),
+        }
+    }
+    ascii
+}
+
+/// Assigns each operation the earliest layer after all earlier operations that share a
+/// resource with it, where `resources` lists what an operation occupies (QDUs or rows).
+fn schedule<K, F>(ops: &[Operation], resources: F) -> Vec<usize>
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&Operation) -> Vec<K>,
+{
+    // Per resource, the first layer still free
+    let mut next_free: HashMap<K, usize> = HashMap::new();
+    ops.iter()
+        .map(|op| {
+            let occupied = resources(op);
+            let layer = occupied
+                .iter()
+                .filter_map(|k| next_free.get(k).copied())
+                .max()
+                .unwrap_or(0);
+            for k in occupied {
+                next_free.insert(k, layer + 1);
+            }
+            layer
+        })
+        .collect()
+}
+
+/// Returns `true` for operations drawn with vertical connectors between their QDUs.
+fn connects_rows(op: &Operation) -> bool {
+    matches!(
+        op,
+        Operation::ControlledInteraction { .. }
+            | Operation::PauliProduct { .. }
+            | Operation::Permute { .. }
+            | Operation::RelationalLock { .. }
+    )
+}
+
+/// Expands `(control, target)` pairs into `ControlledInteraction` operations.
+fn controlled_pairs(pairs: &[(QduId, QduId)], pattern_id: &str) -> Vec<Operation> {
+    pairs
+        .iter()
+        .map(|(control, target)| Operation::ControlledInteraction {
+            control: *control,
+            target: *target,
+            pattern_id: pattern_id.to_string(),
+        })
+        .collect()
+}
+
+/// Maps an interaction pattern ID to its diagram symbol.
+/// Returns `None` for `Identity`, which is drawn as a plain wire.
+fn pattern_symbol(pattern_id: &str) -> Option<&'static str> {
+    let symbol = match pattern_id {
+        "Identity" => return None,
+        "QualityFlip" => "X",
+        "PhaseIntroduce" => "Z",
+        "HalfPhase" => "S",
+        "HalfPhase_Inv" => "S†",
+        "QuarterPhase" => "T",
+        "QuarterPhase_Inv" => "T†",
+        "QualitativeY" => "Y",
+        "PhiRotate" => "ΦR", // Using Φ symbol + R
+        "Superposition" => "H",
+        "SqrtFlip" => "√X", // Using √ symbol + X
+        _ => "?",           // Unknown pattern
+    };
+    Some(symbol)
+}
+
 // Keep the Debug impl delegating to Display
 impl fmt::Debug for Circuit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
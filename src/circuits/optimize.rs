@@ -0,0 +1,328 @@
+// src/circuits/optimize.rs
+
+//! Circuit optimization passes.
+//!
+//! Each pass returns a new, equivalent `Circuit` with fewer operations; [`optimize`]
+//! runs all of them. Two operations are *adjacent* when no operation between them
+//! involves their QDU, so passes see through unrelated operations on other QDUs. A
+//! `Snapshot` involves every QDU, so no pass merges or cancels operations across it.
+
+use super::Circuit;
+use crate::core::QduId;
+use crate::operations::Operation;
+use num_complex::Complex;
+use std::collections::HashMap;
+
+/// Phase shifts whose merged angle is within this distance of zero are dropped.
+const ANGLE_EPSILON: f64 = 1e-12;
+
+/// Runs every optimization pass over `circuit`.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, QduId};
+/// # use onq::circuits::optimize::optimize;
+/// let h = Operation::InteractionPattern { target: QduId(0), pattern_id: "Superposition".to_string() };
+/// let circuit = CircuitBuilder::new().add_op(h.clone()).add_op(h).build();
+/// assert!(optimize(&circuit).is_empty());
+/// ```
+pub fn optimize(circuit: &Circuit) -> Circuit {
+    fuse_single_qdu_runs(&cancel_adjacent_inverses(circuit))
+}
+
+/// Peephole pass removing adjacent operations that undo each other.
+///
+/// * An `InteractionPattern` followed by its inverse pattern on the same QDU
+///   (e.g. `QualityFlip`·`QualityFlip`, `Superposition`·`Superposition`,
+///   `HalfPhase`·`HalfPhase_Inv`) is removed, as is any `Identity` pattern.
+/// * Consecutive `PhaseShift`s on the same QDU are merged into one, and dropped
+///   entirely if their angles sum to zero.
+///
+/// Removals cascade, so `X·H·H·X` on one QDU is removed completely.
+pub fn cancel_adjacent_inverses(circuit: &Circuit) -> Circuit {
+    // Output slots; cancelled operations become `None`
+    let mut slots: Vec<Option<Operation>> = Vec::with_capacity(circuit.len());
+    // Per QDU, the slot indices of surviving operations involving it, in order
+    let mut history: HashMap<QduId, Vec<usize>> = HashMap::new();
+    // Slot of each operation kept unchanged, by its index in `circuit`
+    let mut kept: HashMap<usize, usize> = HashMap::new();
+
+    for (position, op) in circuit.operations().iter().enumerate() {
+        match op {
+            Operation::InteractionPattern { pattern_id, .. } if pattern_id == "Identity" => {
+                continue;
+            }
+            Operation::InteractionPattern { target, .. } => {
+                if let Some(index) = last_single_qdu_op(&slots, &history, *target)
+                    && op
+                        .inverse()
+                        .is_ok_and(|inverse| slots[index].as_ref() == Some(&inverse))
+                {
+                    slots[index] = None;
+                    history.get_mut(target).map(Vec::pop);
+                    continue;
+                }
+            }
+            Operation::PhaseShift { target, theta } => {
+                if let Some(index) = last_single_qdu_op(&slots, &history, *target)
+                    && let Some(Operation::PhaseShift {
+                        theta: previous, ..
+                    }) = &mut slots[index]
+                {
+                    *previous += theta;
+                    if previous.abs() < ANGLE_EPSILON {
+                        slots[index] = None;
+                        history.get_mut(target).map(Vec::pop);
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        let index = slots.len();
+        // A snapshot enters every QDU's history, so no run continues across it
+        for qdu in circuit.occupied_qdus(op) {
+            history.entry(qdu).or_default().push(index);
+        }
+        kept.insert(position, index);
+        slots.push(Some(op.clone()));
+    }
+
+    let mut optimized = circuit.derived();
+    let new_index = surviving_positions(&slots);
+    optimized.carry_bits(circuit, |i| kept.get(&i).and_then(|slot| new_index[*slot]));
+    optimized.add_operations(slots.into_iter().flatten());
+    // QDUs whose operations all cancelled still belong to the circuit
+    optimized.qdus.extend(circuit.qdus().iter().copied());
+    optimized
+}
+
+/// Gate fusion pass: multiplies each run of adjacent single-QDU unitary operations
+/// (`PhaseShift`, `InteractionPattern`, `MatrixPattern`) on the same QDU into one
+/// `Operation::MatrixPattern`, so the engine applies a single matrix per run.
+///
+/// Operations outside a run are left untouched, as are patterns the built-in
+/// registry does not know (so the engine still reports them).
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, QduId};
+/// # use onq::circuits::optimize::fuse_single_qdu_runs;
+/// let circuit = CircuitBuilder::new()
+///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "Superposition".to_string() })
+///     .add_op(Operation::PhaseShift { target: QduId(0), theta: 0.3 })
+///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "SqrtFlip".to_string() })
+///     .build();
+/// let fused = fuse_single_qdu_runs(&circuit);
+/// assert_eq!(fused.len(), 1);
+/// assert!(matches!(fused.operations()[0], Operation::MatrixPattern { .. }));
+/// ```
+pub fn fuse_single_qdu_runs(circuit: &Circuit) -> Circuit {
+    let mut slots: Vec<Option<Operation>> = Vec::with_capacity(circuit.len());
+    let mut history: HashMap<QduId, Vec<usize>> = HashMap::new();
+    let mut kept: HashMap<usize, usize> = HashMap::new();
+
+    for (position, op) in circuit.operations().iter().enumerate() {
+        if let (Some(matrix), [target]) = (op.local_matrix(), op.involved_qdus().as_slice())
+            && let Some(index) = last_single_qdu_op(&slots, &history, *target)
+            && let Some(previous) = slots[index].as_ref().and_then(Operation::local_matrix)
+        {
+            slots[index] = Some(Operation::MatrixPattern {
+                target: *target,
+                matrix: multiply(&matrix, &previous),
+            });
+            continue;
+        }
+
+        let index = slots.len();
+        for qdu in circuit.occupied_qdus(op) {
+            history.entry(qdu).or_default().push(index);
+        }
+        kept.insert(position, index);
+        slots.push(Some(op.clone()));
+    }
+
+    let mut fused = circuit.derived();
+    let new_index = surviving_positions(&slots);
+    fused.carry_bits(circuit, |i| kept.get(&i).and_then(|slot| new_index[*slot]));
+    fused.add_operations(slots.into_iter().flatten());
+    fused
+}
+
+/// Maps each slot to its operation's index in the output circuit (`None` if cancelled).
+fn surviving_positions(slots: &[Option<Operation>]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    slots
+        .iter()
+        .map(|slot| {
+            slot.as_ref().map(|_| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
+}
+
+/// Returns the matrix product `a · b` (apply `b`, then `a`).
+fn multiply(a: &[[Complex<f64>; 2]; 2], b: &[[Complex<f64>; 2]; 2]) -> [[Complex<f64>; 2]; 2] {
+    let entry = |r: usize, c: usize| a[r][0] * b[0][c] + a[r][1] * b[1][c];
+    [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]]
+}
+
+/// Returns the slot of the latest surviving operation on `qdu`, if it involves only `qdu`.
+fn last_single_qdu_op(
+    slots: &[Option<Operation>],
+    history: &HashMap<QduId, Vec<usize>>,
+    qdu: QduId,
+) -> Option<usize> {
+    let index = *history.get(&qdu)?.last()?;
+    slots[index]
+        .as_ref()
+        .filter(|op| op.involved_qdus() == [qdu])
+        .map(|_| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::CircuitBuilder;
+    use crate::simulation::SimulationResult;
+    use crate::simulation::engine::SimulationEngine;
+
+    fn pattern(target: u64, id: &str) -> Operation {
+        Operation::InteractionPattern {
+            target: QduId(target),
+            pattern_id: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cancellation_cascades_and_sees_through_other_qdus() {
+        let circuit = CircuitBuilder::new()
+            .add_op(pattern(0, "QualityFlip"))
+            .add_op(pattern(0, "Superposition"))
+            .add_op(pattern(1, "HalfPhase"))
+            .add_op(pattern(0, "Superposition"))
+            .add_op(pattern(1, "HalfPhase_Inv"))
+            .add_op(pattern(0, "QualityFlip"))
+            .add_op(pattern(0, "Identity"))
+            .build();
+        assert!(cancel_adjacent_inverses(&circuit).is_empty());
+    }
+
+    #[test]
+    fn test_multi_qdu_operations_block_cancellation() {
+        let circuit = CircuitBuilder::new()
+            .add_op(pattern(1, "QualityFlip"))
+            .add_op(Operation::ControlledInteraction {
+                control: QduId(0),
+                target: QduId(1),
+                pattern_id: "QualityFlip".to_string(),
+            })
+            .add_op(pattern(1, "QualityFlip"))
+            .add_op(pattern(0, "HalfPhase"))
+            .add_op(pattern(0, "HalfPhase"))
+            .build();
+        // Nothing cancels: the flips are separated, and HalfPhase is not self-inverse
+        assert_eq!(cancel_adjacent_inverses(&circuit), circuit);
+    }
+
+    #[test]
+    fn test_phase_shifts_merge() {
+        let shift = |theta: f64| Operation::PhaseShift {
+            target: QduId(0),
+            theta,
+        };
+        let circuit = CircuitBuilder::new()
+            .add_op(shift(0.25))
+            .add_op(pattern(1, "Superposition"))
+            .add_op(shift(0.5))
+            .build();
+        let optimized = cancel_adjacent_inverses(&circuit);
+        assert_eq!(
+            optimized.operations(),
+            &[shift(0.75), pattern(1, "Superposition")]
+        );
+
+        let cancelling = CircuitBuilder::new()
+            .add_op(shift(0.5))
+            .add_op(shift(-0.5))
+            .build();
+        assert!(cancel_adjacent_inverses(&cancelling).is_empty());
+    }
+
+    #[test]
+    fn test_snapshots_separate_runs() {
+        use crate::simulation::Simulator;
+
+        let circuit = CircuitBuilder::new()
+            .x(QduId(0))
+            .snapshot("s")
+            .x(QduId(0))
+            .h(QduId(0))
+            .snapshot("t")
+            .h(QduId(0))
+            .build();
+        assert_eq!(cancel_adjacent_inverses(&circuit), circuit);
+        // Only the run between the snapshots fuses
+        assert_eq!(fuse_single_qdu_runs(&circuit).len(), 5);
+
+        let expected = Simulator::new().run(&circuit).unwrap();
+        let optimized = Simulator::new().run(&optimize(&circuit)).unwrap();
+        for label in ["s", "t"] {
+            let want = expected.snapshot(label).unwrap().network[&0].core_state;
+            let got = optimized.snapshot(label).unwrap().network[&0].core_state;
+            assert!((want[0] - got[0]).norm() < 1e-12 && (want[1] - got[1]).norm() < 1e-12);
+        }
+        assert!(expected.snapshot("s").unwrap().network[&0].core_state[1].norm_sqr() > 0.99);
+    }
+
+    /// Runs `circuit` on a fresh engine and returns the core state of each QDU.
+    fn core_states(circuit: &Circuit) -> Vec<[Complex<f64>; 2]> {
+        let mut engine = SimulationEngine::init(circuit.qdus()).unwrap();
+        let mut result = SimulationResult::new();
+        for op in circuit.operations() {
+            match op {
+                Operation::Stabilize { targets } => engine.stabilize(targets, &mut result),
+                _ => engine.apply_operation(op),
+            }
+            .unwrap();
+        }
+        (0..circuit.qdus().len() as u64)
+            .map(|id| engine.get_state().network[&id].core_state)
+            .collect()
+    }
+
+    #[test]
+    fn test_fusion_preserves_state() {
+        let circuit = CircuitBuilder::new()
+            .add_op(pattern(0, "Superposition"))
+            .add_op(Operation::PhaseShift {
+                target: QduId(0),
+                theta: 0.7,
+            })
+            .add_op(pattern(1, "PhiRotate"))
+            .add_op(pattern(0, "SqrtFlip"))
+            .add_op(pattern(1, "QuarterPhase"))
+            .add_op(Operation::Stabilize {
+                targets: vec![QduId(1)],
+            })
+            .add_op(pattern(1, "Superposition"))
+            .build();
+
+        let fused = fuse_single_qdu_runs(&circuit);
+        // QDU 0: one fused run; QDU 1: a fused run, the stabilization, and a lone pattern
+        assert_eq!(fused.len(), 4);
+        assert!(matches!(
+            fused.operations()[3],
+            Operation::InteractionPattern { .. }
+        ));
+
+        for (expected, actual) in core_states(&circuit).iter().zip(core_states(&fused)) {
+            for i in 0..2 {
+                assert!((expected[i] - actual[i]).norm() < 1e-12);
+            }
+        }
+    }
+}
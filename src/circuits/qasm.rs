@@ -0,0 +1,524 @@
+// src/circuits/qasm.rs
+
+//! Import of OpenQASM 2 programs.
+//!
+//! A subset of OpenQASM 2.0 is translated onto the onq pattern analogs so textbook
+//! circuits can be replayed under onq stabilization semantics:
+//!
+//! | QASM                         | onq                                                 |
+//! |------------------------------|-----------------------------------------------------|
+//! | `id`, `x`, `y`, `z`, `h`     | `Identity`, `QualityFlip`, `QualitativeY`, `PhaseIntroduce`, `Superposition` |
+//! | `s`, `sdg`, `t`, `tdg`       | `HalfPhase`, `HalfPhase_Inv`, `QuarterPhase`, `QuarterPhase_Inv` |
+//! | `sx`, `sxdg`                 | `SqrtFlip`, `SqrtFlip_Inv`                          |
+//! | `u1(λ)`, `p(λ)`, `rz(λ)`     | `PhaseShift` (`rz` up to a global phase)            |
+//! | `cx`, `cy`, `cz`             | `ControlledInteraction` with `QualityFlip`, `QualitativeY`, `PhaseIntroduce` |
+//! | `swap`                       | `Permute`                                           |
+//! | `measure q -> c`             | `Stabilize`, naming the outcomes as classical bits `c[i]` |
+//!
+//! `OPENQASM`, `include` and `barrier` statements are accepted and have no effect.
+//! Quantum registers are mapped to consecutive `QduId`s in declaration order, at most
+//! 64 QDUs in all (the IVM capacity); each bit of a `creg` becomes a classical bit of
+//! the circuit named `c[i]`.
+//! Angles may use `pi`, numbers, `+ - * /` and parentheses. Anything else (custom
+//! `gate` definitions, `if`, `reset`, other gates) is rejected with an error naming the
+//! offending line.
+
+use super::Circuit;
+use crate::core::{OnqError, QduId};
+use crate::operations::Operation;
+use crate::simulation::engine::IVM_CAPACITY;
+use std::collections::HashMap;
+
+/// Parses an OpenQASM 2 program (see the [module docs](self) for the supported subset).
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` describing the first unsupported or malformed
+/// statement, including its line number.
+///
+/// # Examples
+/// ```
+/// # use onq::{Operation, QduId};
+/// # use onq::circuits::qasm::parse_qasm;
+/// let circuit = parse_qasm(r#"
+///     OPENQASM 2.0;
+///     include "qelib1.inc";
+///     qreg q[2];
+///     creg c[2];
+///     h q[0];
+///     cx q[0], q[1];
+///     measure q -> c;
+/// "#).unwrap();
+/// assert_eq!(circuit.len(), 3);
+/// assert_eq!(circuit.operations()[2], Operation::Stabilize { targets: vec![QduId(0), QduId(1)] });
+/// ```
+pub fn parse_qasm(source: &str) -> Result<Circuit, OnqError> {
+    let mut parser = QasmParser::default();
+    for (line, statement) in statements(source)? {
+        parser
+            .statement(&statement)
+            .map_err(|message| OnqError::InvalidOperation {
+                message: format!("QASM line {}: {}", line, message),
+            })?;
+    }
+    // Declared but unused QDUs still belong to the circuit
+    let mut circuit = parser.circuit;
+    circuit.qdus.extend((0..parser.next_qdu).map(QduId));
+    Ok(circuit)
+}
+
+/// Splits `source` into `;`-terminated statements, each tagged with its starting line.
+fn statements(source: &str) -> Result<Vec<(usize, String)>, OnqError> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start_line = 1;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = raw_line.split("//").next().unwrap_or("");
+        for (offset, piece) in line.split(';').enumerate() {
+            if offset > 0 {
+                statements.push((start_line, current.trim().to_string()));
+                current.clear();
+            }
+            if current.trim().is_empty() {
+                start_line = index + 1;
+            }
+            if piece.contains('{') {
+                return Err(OnqError::InvalidOperation {
+                    message: format!(
+                        "QASM line {}: custom gate definitions are not supported",
+                        index + 1
+                    ),
+                });
+            }
+            current.push_str(piece);
+            current.push(' ');
+        }
+    }
+    if !current.trim().is_empty() {
+        return Err(OnqError::InvalidOperation {
+            message: format!("QASM line {}: missing ';'", start_line),
+        });
+    }
+    Ok(statements
+        .into_iter()
+        .filter(|(_, statement)| !statement.is_empty())
+        .collect())
+}
+
+/// Translation state: declared registers and the circuit built so far.
+#[derive(Default)]
+struct QasmParser {
+    /// Register name -> (first QduId, size)
+    qregs: HashMap<String, (u64, u64)>,
+    /// Register name -> size
+    cregs: HashMap<String, u64>,
+    next_qdu: u64,
+    circuit: Circuit,
+}
+
+impl QasmParser {
+    fn statement(&mut self, statement: &str) -> Result<(), String> {
+        // The keyword ends at whitespace or at a gate's parameter list
+        let head_len = statement
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(statement.len());
+        let (head, rest) = (&statement[..head_len], statement[head_len..].trim());
+
+        match head {
+            "OPENQASM" | "include" | "barrier" => Ok(()),
+            "qreg" => self.declare_qreg(rest),
+            "creg" => self.declare_creg(rest),
+            "measure" => self.measure(rest),
+            gate => {
+                let (params, args) = split_params(rest)?;
+                self.gate(gate, &params, &args)
+            }
+        }
+    }
+
+    fn declare_qreg(&mut self, rest: &str) -> Result<(), String> {
+        let (name, size) = parse_indexed(rest)?;
+        let size = size.ok_or_else(|| format!("qreg '{}' needs a size", name))?;
+        if self.qregs.contains_key(&name) {
+            return Err(format!("qreg '{}' is declared twice", name));
+        }
+        // Untrusted sizes are bounded before any QDU list is built from them
+        let total = self
+            .next_qdu
+            .checked_add(size)
+            .filter(|&total| total <= IVM_CAPACITY as u64)
+            .ok_or_else(|| {
+                format!(
+                    "qreg '{}' of size {} exceeds the IVM capacity of {} QDUs",
+                    name, size, IVM_CAPACITY
+                )
+            })?;
+        self.qregs.insert(name, (self.next_qdu, size));
+        self.next_qdu = total;
+        Ok(())
+    }
+
+    fn declare_creg(&mut self, rest: &str) -> Result<(), String> {
+        let (name, size) = parse_indexed(rest)?;
+        let size = size.ok_or_else(|| format!("creg '{}' needs a size", name))?;
+        if self.cregs.insert(name.clone(), size).is_some() {
+            return Err(format!("creg '{}' is declared twice", name));
+        }
+        Ok(())
+    }
+
+    /// Translates `measure q -> c` or `measure q[i] -> c[j]` into a `Stabilize`
+    /// whose outcomes are named as the classical bits `c[j]`.
+    fn measure(&mut self, rest: &str) -> Result<(), String> {
+        let (qubits, bits) = rest
+            .split_once("->")
+            .ok_or_else(|| "measure requires '-> <creg>'".to_string())?;
+        let targets = self.operand(qubits.trim())?;
+
+        let (creg, index) = parse_indexed(bits)?;
+        let size = *self
+            .cregs
+            .get(&creg)
+            .ok_or_else(|| format!("undeclared creg '{}'", creg))?;
+        let width = match index {
+            Some(i) if i >= size => {
+                return Err(format!("index {} out of range for creg '{}'", i, creg));
+            }
+            Some(_) => 1,
+            None => size,
+        };
+        if width != targets.len() as u64 {
+            return Err("measure operands have different sizes".to_string());
+        }
+        let bits: Vec<u64> = match index {
+            Some(i) => vec![i],
+            None => (0..size).collect(),
+        };
+
+        let index = self.circuit.len();
+        self.push(Operation::Stabilize {
+            targets: targets.clone(),
+        });
+        for (qdu, bit) in targets.into_iter().zip(bits) {
+            self.circuit
+                .bind_bit(format!("{}[{}]", creg, bit), index, qdu);
+        }
+        Ok(())
+    }
+
+    /// Resolves `q` (whole register) or `q[i]` (single QDU).
+    fn operand(&self, operand: &str) -> Result<Vec<QduId>, String> {
+        let (name, index) = parse_indexed(operand)?;
+        let (first, size) = *self
+            .qregs
+            .get(&name)
+            .ok_or_else(|| format!("undeclared qreg '{}'", name))?;
+        match index {
+            Some(i) if i >= size => Err(format!("index {} out of range for qreg '{}'", i, name)),
+            Some(i) => Ok(vec![QduId(first + i)]),
+            None => Ok((first..first + size).map(QduId).collect()),
+        }
+    }
+
+    fn gate(&mut self, gate: &str, params: &[f64], args: &[&str]) -> Result<(), String> {
+        let expect = |arity: usize, param_count: usize| {
+            if args.len() != arity || params.len() != param_count {
+                Err(format!(
+                    "gate '{}' takes {} parameter(s) and {} argument(s)",
+                    gate, param_count, arity
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
+        if let Some(pattern_id) = single_qdu_pattern(gate) {
+            expect(1, 0)?;
+            let targets = self.operand(args[0])?;
+            self.push(match targets.as_slice() {
+                [target] => Operation::InteractionPattern {
+                    target: *target,
+                    pattern_id: pattern_id.to_string(),
+                },
+                _ => Operation::BroadcastPattern {
+                    targets,
+                    pattern_id: pattern_id.to_string(),
+                },
+            });
+            return Ok(());
+        }
+
+        match gate {
+            "u1" | "p" | "rz" => {
+                expect(1, 1)?;
+                let theta = params[0];
+                let targets = self.operand(args[0])?;
+                self.push(match targets.as_slice() {
+                    [target] => Operation::PhaseShift {
+                        target: *target,
+                        theta,
+                    },
+                    _ => Operation::BroadcastPhaseShift { targets, theta },
+                });
+                Ok(())
+            }
+            "cx" | "CX" | "cy" | "cz" | "swap" => {
+                expect(2, 0)?;
+                for (a, b) in self.pairs(args[0], args[1])? {
+                    self.push(match gate {
+                        "swap" => Operation::Permute {
+                            mapping: vec![(a, b), (b, a)],
+                        },
+                        _ => Operation::ControlledInteraction {
+                            control: a,
+                            target: b,
+                            pattern_id: match gate {
+                                "cy" => "QualitativeY",
+                                "cz" => "PhaseIntroduce",
+                                _ => "QualityFlip",
+                            }
+                            .to_string(),
+                        },
+                    });
+                }
+                Ok(())
+            }
+            "gate" | "opaque" => Err("custom gate definitions are not supported".to_string()),
+            "if" => Err("classically controlled operations are not supported".to_string()),
+            "reset" => Err("reset is not supported".to_string()),
+            _ => Err(format!("unsupported gate '{}'", gate)),
+        }
+    }
+
+    /// Pairs two operands, broadcasting over registers of equal size.
+    fn pairs(&self, first: &str, second: &str) -> Result<Vec<(QduId, QduId)>, String> {
+        let (a, b) = (self.operand(first)?, self.operand(second)?);
+        let pairs: Vec<(QduId, QduId)> = match (a.len(), b.len()) {
+            (1, _) => b.iter().map(|q| (a[0], *q)).collect(),
+            (_, 1) => a.iter().map(|q| (*q, b[0])).collect(),
+            (n, m) if n == m => a.into_iter().zip(b).collect(),
+            _ => return Err("register operands have different sizes".to_string()),
+        };
+        if pairs.iter().any(|(a, b)| a == b) {
+            return Err("a two-QDU gate needs two distinct QDUs".to_string());
+        }
+        Ok(pairs)
+    }
+
+    fn push(&mut self, op: Operation) {
+        self.circuit.add_operation(op);
+    }
+}
+
+/// Maps a parameterless single-QDU QASM gate to its interaction pattern.
+fn single_qdu_pattern(gate: &str) -> Option<&'static str> {
+    Some(match gate {
+        "id" => "Identity",
+        "x" => "QualityFlip",
+        "y" => "QualitativeY",
+        "z" => "PhaseIntroduce",
+        "h" => "Superposition",
+        "s" => "HalfPhase",
+        "sdg" => "HalfPhase_Inv",
+        "t" => "QuarterPhase",
+        "tdg" => "QuarterPhase_Inv",
+        "sx" => "SqrtFlip",
+        "sxdg" => "SqrtFlip_Inv",
+        _ => return None,
+    })
+}
+
+/// Splits `(p1, p2) a, b` into evaluated parameters and argument strings.
+fn split_params(rest: &str) -> Result<(Vec<f64>, Vec<&str>), String> {
+    let (params, args) = match rest.strip_prefix('(') {
+        Some(inner) => {
+            let close = inner
+                .rfind(')')
+                .ok_or_else(|| "unclosed parameter list".to_string())?;
+            let params = inner[..close]
+                .split(',')
+                .map(|p| AngleParser::evaluate(p.trim()))
+                .collect::<Result<Vec<f64>, String>>()?;
+            (params, &inner[close + 1..])
+        }
+        None => (Vec::new(), rest),
+    };
+    let args = args
+        .split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .collect();
+    Ok((params, args))
+}
+
+/// Parses `name` or `name[index]`.
+fn parse_indexed(text: &str) -> Result<(String, Option<u64>), String> {
+    let text = text.trim();
+    match text.split_once('[') {
+        Some((name, index)) => {
+            let index = index
+                .strip_suffix(']')
+                .and_then(|i| i.trim().parse::<u64>().ok())
+                .ok_or_else(|| format!("malformed operand '{}'", text))?;
+            Ok((name.trim().to_string(), Some(index)))
+        }
+        None if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            Ok((text.to_string(), None))
+        }
+        None => Err(format!("malformed operand '{}'", text)),
+    }
+}
+
+/// Deepest nesting of parentheses and unary minus an angle may use, keeping
+/// the recursive descent well within the stack.
+const MAX_ANGLE_DEPTH: usize = 64;
+
+/// Recursive-descent evaluator for QASM angle expressions.
+struct AngleParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    depth: usize,
+}
+
+impl AngleParser<'_> {
+    fn evaluate(expression: &str) -> Result<f64, String> {
+        let mut parser = AngleParser {
+            chars: expression.chars().peekable(),
+            depth: 0,
+        };
+        let value = parser.sum()?;
+        parser.skip_whitespace();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{}' in angle '{}'", c, expression)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn sum(&mut self) -> Result<f64, String> {
+        let mut value = self.product()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    value += self.product()?;
+                }
+                Some('-') => {
+                    self.chars.next();
+                    value -= self.product()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('*') => {
+                    self.chars.next();
+                    value *= self.factor()?;
+                }
+                Some('/') => {
+                    self.chars.next();
+                    value /= self.factor()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('-') => {
+                self.chars.next();
+                Ok(-self.nested(Self::factor)?)
+            }
+            Some('(') => {
+                self.chars.next();
+                let value = self.nested(Self::sum)?;
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some(')') => Ok(value),
+                    _ => Err("missing ')' in angle".to_string()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E'))
+                {
+                    number.push(c);
+                    if matches!(c, 'e' | 'E')
+                        && let Some(sign) = self.chars.next_if(|c| matches!(c, '+' | '-'))
+                    {
+                        number.push(sign);
+                    }
+                }
+                number
+                    .parse()
+                    .map_err(|_| format!("malformed number '{}'", number))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric()) {
+                    name.push(c);
+                }
+                match name.as_str() {
+                    "pi" => Ok(std::f64::consts::PI),
+                    _ => Err(format!("unknown identifier '{}' in angle", name)),
+                }
+            }
+            _ => Err("expected an angle".to_string()),
+        }
+    }
+
+    /// Runs `parse` one nesting level deeper, failing past [`MAX_ANGLE_DEPTH`].
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<f64, String>) -> Result<f64, String> {
+        if self.depth == MAX_ANGLE_DEPTH {
+            return Err(format!(
+                "angle nests deeper than {} levels",
+                MAX_ANGLE_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_expressions() {
+        let pi = std::f64::consts::PI;
+        assert_eq!(AngleParser::evaluate("pi/2"), Ok(pi / 2.0));
+        assert_eq!(AngleParser::evaluate("-3*pi/4"), Ok(-3.0 * pi / 4.0));
+        assert_eq!(AngleParser::evaluate("(1 + 0.5) * 2"), Ok(3.0));
+        assert!(AngleParser::evaluate("theta").is_err());
+        assert!(AngleParser::evaluate("pi pi").is_err());
+        assert_eq!(AngleParser::evaluate("1e-3"), Ok(1e-3));
+        assert_eq!(AngleParser::evaluate("2.5E+2 - pi"), Ok(250.0 - pi));
+    }
+
+    #[test]
+    fn test_angle_nesting_is_bounded() {
+        let nested = |depth| format!("{}pi{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(
+            AngleParser::evaluate(&nested(MAX_ANGLE_DEPTH)),
+            Ok(std::f64::consts::PI)
+        );
+        assert!(AngleParser::evaluate(&nested(MAX_ANGLE_DEPTH + 1)).is_err());
+        assert!(AngleParser::evaluate(&"-".repeat(200_000)).is_err());
+    }
+}
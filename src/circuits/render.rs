@@ -0,0 +1,587 @@
+// src/circuits/render.rs
+
+//! Publication-quality circuit diagrams.
+//!
+//! Besides the ASCII `Display` impl, a [`Circuit`] can be rendered as a standalone SVG
+//! document ([`Circuit::to_svg`]) or as a LaTeX `quantikz` environment
+//! ([`Circuit::to_latex`]). Both renderers share the same moment-aligned layout as the
+//! ASCII diagram and take their styling from a [`DiagramStyle`].
+
+use super::{Circuit, connects_rows, pattern_symbol, schedule};
+use crate::core::QduId;
+use crate::operations::Operation;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Styling options for [`Circuit::to_svg_with`] and [`Circuit::to_latex_with`].
+///
+/// Lengths are in SVG user units (pixels); colors are any SVG color value.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, QduId};
+/// # use onq::circuits::DiagramStyle;
+/// let circuit = CircuitBuilder::new()
+///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "Superposition".to_string() })
+///     .build();
+/// let style = DiagramStyle {
+///     gate_fill: "#dde8ff".to_string(),
+///     show_labels: false,
+///     ..DiagramStyle::default()
+/// };
+/// let svg = circuit.to_svg_with(&style);
+/// assert!(svg.contains("#dde8ff"));
+/// assert!(!svg.contains("QDU(0)"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramStyle {
+    /// Minimum width of a moment column.
+    pub column_width: f64,
+    /// Vertical distance between QDU wires.
+    pub row_height: f64,
+    /// Font family used for gate symbols and labels.
+    pub font_family: String,
+    /// Font size used for gate symbols and labels.
+    pub font_size: f64,
+    /// Color of wires and connectors.
+    pub wire_color: String,
+    /// Fill color of gate boxes.
+    pub gate_fill: String,
+    /// Outline color of gate boxes, controls and targets.
+    pub gate_stroke: String,
+    /// Color of gate symbols and labels.
+    pub text_color: String,
+    /// Background fill of the whole diagram, or `None` for transparent.
+    pub background: Option<String>,
+    /// Whether to label each wire with its QDU.
+    pub show_labels: bool,
+    /// `row sep` option of the quantikz environment (e.g. `"0.3cm"`), if set.
+    pub latex_row_sep: Option<String>,
+    /// `column sep` option of the quantikz environment (e.g. `"0.4cm"`), if set.
+    pub latex_column_sep: Option<String>,
+}
+
+impl Default for DiagramStyle {
+    fn default() -> Self {
+        Self {
+            column_width: 48.0,
+            row_height: 40.0,
+            font_family: "sans-serif".to_string(),
+            font_size: 14.0,
+            wire_color: "#000000".to_string(),
+            gate_fill: "#ffffff".to_string(),
+            gate_stroke: "#000000".to_string(),
+            text_color: "#000000".to_string(),
+            background: None,
+            show_labels: true,
+            latex_row_sep: None,
+            latex_column_sep: None,
+        }
+    }
+}
+
+/// How an operation is drawn on one of its rows.
+#[derive(Debug, Clone, PartialEq)]
+enum Glyph {
+    /// A boxed symbol.
+    Gate(String),
+    /// A filled control dot.
+    Control,
+    /// The ⊕ target of a controlled `QualityFlip`.
+    Target,
+    /// A stabilization meter.
+    Measure,
+}
+
+/// One operation placed on the diagram grid.
+struct Placed {
+    column: usize,
+    glyphs: Vec<(usize, Glyph)>,
+    /// Whether the glyph rows are joined by a vertical connector.
+    connected: bool,
+}
+
+/// A grid cell: the glyph drawn there and, if it carries a connector, how many rows
+/// down the connector reaches.
+type Cell<'a> = Option<(&'a Glyph, Option<isize>)>;
+
+/// The moment-aligned grid shared by the SVG and LaTeX renderers.
+struct Layout {
+    qdus: Vec<QduId>,
+    num_columns: usize,
+    placed: Vec<Placed>,
+}
+
+impl Layout {
+    fn new(circuit: &Circuit) -> Self {
+        let mut qdus: Vec<QduId> = circuit.qdus().iter().copied().collect();
+        qdus.sort();
+        let row_of: HashMap<QduId, usize> = qdus.iter().enumerate().map(|(r, q)| (*q, r)).collect();
+        let ops = circuit.operations();
+
+        // Same column assignment as the ASCII diagram
+        let columns = schedule(ops, |op| {
+            let rows: Vec<usize> = circuit
+                .occupied_qdus(op)
+                .iter()
+                .filter_map(|q| row_of.get(q).copied())
+                .collect();
+            match (connects_rows(op), rows.iter().min(), rows.iter().max()) {
+                (true, Some(&r_min), Some(&r_max)) => (r_min..=r_max).collect(),
+                _ => rows,
+            }
+        });
+
+        let placed = ops
+            .iter()
+            .zip(&columns)
+            .map(|(op, &column)| Placed {
+                column,
+                glyphs: glyphs(op)
+                    .into_iter()
+                    .filter_map(|(q, glyph)| row_of.get(&q).map(|r| (*r, glyph)))
+                    .collect(),
+                connected: connects_rows(op),
+            })
+            .collect();
+
+        Self {
+            qdus,
+            num_columns: columns.iter().max().map_or(0, |last| last + 1),
+            placed,
+        }
+    }
+
+    /// Returns the glyph grid, indexed as `grid[row][column]`.
+    fn grid(&self) -> Vec<Vec<Cell<'_>>> {
+        let mut grid = vec![vec![None; self.num_columns]; self.qdus.len()];
+        for placed in &self.placed {
+            let rows = placed.glyphs.iter().map(|(r, _)| *r);
+            let (r_min, r_max) = (rows.clone().min(), rows.max());
+            for (row, glyph) in &placed.glyphs {
+                // The topmost glyph of a connected operation carries the connector
+                let span = match (placed.connected, r_min, r_max) {
+                    (true, Some(top), Some(bottom)) if *row == top && bottom > top => {
+                        Some((bottom - top) as isize)
+                    }
+                    _ => None,
+                };
+                grid[*row][placed.column] = Some((glyph, span));
+            }
+        }
+        grid
+    }
+}
+
+/// Returns the glyphs drawn for `op`, per QDU.
+fn glyphs(op: &Operation) -> Vec<(QduId, Glyph)> {
+    let gate = |symbol: &str| Glyph::Gate(symbol.to_string());
+    match op {
+        Operation::PhaseShift { target, theta } => {
+            vec![(*target, gate(&format!("P({:.2})", theta)))]
+        }
+        Operation::InteractionPattern { target, pattern_id } => pattern_symbol(pattern_id)
+            .map(|symbol| vec![(*target, gate(symbol))])
+            .unwrap_or_default(),
+        Operation::MatrixPattern { target, .. } => vec![(*target, gate("U"))],
+        Operation::QuditPattern { target, matrix } => {
+            vec![(*target, gate(&format!("U{}", matrix.len())))]
+        }
+        Operation::Project { target, onto } => {
+            vec![(*target, gate(&format!("|{}>", onto.index())))]
+        }
+        Operation::Relax { target, .. } => vec![(*target, gate("γ"))],
+        Operation::BroadcastPattern {
+            targets,
+            pattern_id,
+        } => match pattern_symbol(pattern_id) {
+            Some(symbol) => targets.iter().map(|q| (*q, gate(symbol))).collect(),
+            None => Vec::new(),
+        },
+        Operation::BroadcastPhaseShift { targets, theta } => targets
+            .iter()
+            .map(|q| (*q, gate(&format!("P({:.2})", theta))))
+            .collect(),
+        Operation::ControlledInteraction {
+            control,
+            target,
+            pattern_id,
+        } => {
+            let target_glyph = match pattern_id.as_str() {
+                "QualityFlip" => Glyph::Target,
+                _ => gate(pattern_symbol(pattern_id).unwrap_or("I")),
+            };
+            vec![(*control, Glyph::Control), (*target, target_glyph)]
+        }
+        Operation::PauliProduct { terms, .. } => terms
+            .iter()
+            .map(|(q, axis)| (*q, gate(&format!("{:?}", axis))))
+            .collect(),
+        Operation::Permute { mapping } => mapping
+            .iter()
+            .map(|(from, to)| (*from, gate(&format!("→{}", to.0))))
+            .collect(),
+        Operation::RelationalLock { qdu1, qdu2, .. } => {
+            vec![(*qdu1, Glyph::Control), (*qdu2, Glyph::Control)]
+        }
+        Operation::Delay { targets, ticks } => targets
+            .iter()
+            .map(|q| (*q, gate(&format!("Δ{}", ticks))))
+            .collect(),
+        Operation::Stabilize { targets } => targets.iter().map(|q| (*q, Glyph::Measure)).collect(),
+        Operation::Snapshot { .. } => Vec::new(),
+    }
+}
+
+impl Circuit {
+    /// Renders the circuit as a standalone SVG document using the default [`DiagramStyle`].
+    pub fn to_svg(&self) -> String {
+        self.to_svg_with(&DiagramStyle::default())
+    }
+
+    /// Renders the circuit as a standalone SVG document using `style`.
+    ///
+    /// Wires are drawn top to bottom in ascending QDU order and operations are aligned
+    /// by moment, as in the ASCII diagram.
+    pub fn to_svg_with(&self, style: &DiagramStyle) -> String {
+        let layout = Layout::new(self);
+        let grid = layout.grid();
+        let char_width = style.font_size * 0.6;
+        let gate_height = style.row_height * 0.6;
+        let gate_width =
+            |symbol: &str| (symbol.chars().count() as f64 * char_width + 10.0).max(gate_height);
+
+        // Columns grow to fit their widest gate
+        let mut column_widths = vec![style.column_width; layout.num_columns];
+        for row in &grid {
+            for (column, cell) in row.iter().enumerate() {
+                if let Some((Glyph::Gate(symbol), _)) = cell {
+                    column_widths[column] = column_widths[column].max(gate_width(symbol) + 12.0);
+                }
+            }
+        }
+        let label_width = if style.show_labels {
+            layout
+                .qdus
+                .iter()
+                .map(|q| q.to_string().chars().count() as f64 * char_width + 12.0)
+                .fold(0.0, f64::max)
+        } else {
+            0.0
+        };
+        let margin = 10.0;
+        let wire_start = margin + label_width;
+        let mut column_x = Vec::with_capacity(layout.num_columns);
+        let mut x = wire_start;
+        for width in &column_widths {
+            column_x.push(x + width / 2.0);
+            x += width;
+        }
+        let wire_end = x + style.column_width / 2.0;
+        let width = wire_end + margin;
+        let height = 2.0 * margin + layout.qdus.len() as f64 * style.row_height;
+        let row_y = |row: usize| margin + (row as f64 + 0.5) * style.row_height;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="{font}" font-size="{size}">"#,
+            w = width,
+            h = height,
+            font = escape_xml(&style.font_family),
+            size = style.font_size,
+        );
+        if let Some(background) = &style.background {
+            let _ = writeln!(
+                svg,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                escape_xml(background)
+            );
+        }
+
+        // Wires and labels
+        for (row, qdu) in layout.qdus.iter().enumerate() {
+            let y = row_y(row);
+            if style.show_labels {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" fill="{}" dominant-baseline="central">{}</text>"#,
+                    margin,
+                    y,
+                    escape_xml(&style.text_color),
+                    escape_xml(&qdu.to_string())
+                );
+            }
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="{}"/>"#,
+                wire_start,
+                wire_end,
+                escape_xml(&style.wire_color),
+                y = y
+            );
+        }
+
+        // Connectors first, so gates are drawn over them
+        for (row, cells) in grid.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                if let Some((_, Some(span))) = cell {
+                    let _ = writeln!(
+                        svg,
+                        r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{}"/>"#,
+                        row_y(row),
+                        row_y(row + *span as usize),
+                        escape_xml(&style.wire_color),
+                        x = column_x[column]
+                    );
+                }
+            }
+        }
+
+        let stroke = escape_xml(&style.gate_stroke);
+        let fill = escape_xml(&style.gate_fill);
+        for (row, cells) in grid.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let Some((glyph, _)) = cell else { continue };
+                let (x, y) = (column_x[column], row_y(row));
+                match glyph {
+                    Glyph::Gate(symbol) => {
+                        let w = gate_width(symbol);
+                        let _ = writeln!(
+                            svg,
+                            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="{}"/>"#,
+                            x - w / 2.0,
+                            y - gate_height / 2.0,
+                            w,
+                            gate_height,
+                            fill,
+                            stroke
+                        );
+                        let _ = writeln!(
+                            svg,
+                            r#"<text x="{}" y="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                            x,
+                            y,
+                            escape_xml(&style.text_color),
+                            escape_xml(symbol)
+                        );
+                    }
+                    Glyph::Control => {
+                        let _ = writeln!(
+                            svg,
+                            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                            x,
+                            y,
+                            gate_height / 6.0,
+                            stroke
+                        );
+                    }
+                    Glyph::Target => {
+                        let r = gate_height / 2.5;
+                        let _ = writeln!(
+                            svg,
+                            r#"<circle cx="{x}" cy="{y}" r="{r}" fill="{}" stroke="{s}"/><line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="{s}"/><line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{s}"/>"#,
+                            fill,
+                            x - r,
+                            x + r,
+                            y - r,
+                            y + r,
+                            x = x,
+                            y = y,
+                            r = r,
+                            s = stroke
+                        );
+                    }
+                    Glyph::Measure => {
+                        let w = gate_height;
+                        let r = w * 0.35;
+                        let _ = writeln!(
+                            svg,
+                            r#"<rect x="{}" y="{}" width="{w}" height="{w}" fill="{}" stroke="{s}"/><path d="M {} {} A {r} {r} 0 0 1 {} {}" fill="none" stroke="{s}"/><line x1="{x}" y1="{}" x2="{}" y2="{}" stroke="{s}"/>"#,
+                            x - w / 2.0,
+                            y - w / 2.0,
+                            fill,
+                            x - r,
+                            y + w / 4.0,
+                            x + r,
+                            y + w / 4.0,
+                            y + w / 4.0,
+                            x + r * 0.8,
+                            y - w / 3.0,
+                            w = w,
+                            r = r,
+                            x = x,
+                            s = stroke
+                        );
+                    }
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the circuit as a LaTeX `quantikz` environment using the default
+    /// [`DiagramStyle`]. The output needs `\usepackage{tikz}` and
+    /// `\usetikzlibrary{quantikz2}` (or the `quantikz` package) in the preamble.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId};
+    /// let circuit = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern { target: QduId(0), pattern_id: "Superposition".to_string() })
+    ///     .add_op(Operation::ControlledInteraction {
+    ///         control: QduId(0),
+    ///         target: QduId(1),
+    ///         pattern_id: "QualityFlip".to_string(),
+    ///     })
+    ///     .add_op(Operation::Stabilize { targets: vec![QduId(0), QduId(1)] })
+    ///     .build();
+    ///
+    /// let latex = circuit.to_latex();
+    /// assert!(latex.contains(r"\lstick{QDU(0)} & \gate{H} & \ctrl{1} & \meter{} & \qw \\"));
+    /// assert!(latex.contains(r"\lstick{QDU(1)} & \qw & \targ{} & \meter{} & \qw"));
+    /// ```
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with(&DiagramStyle::default())
+    }
+
+    /// Renders the circuit as a LaTeX `quantikz` environment using `style`.
+    ///
+    /// Only the style options meaningful to LaTeX are used: `show_labels`,
+    /// `latex_row_sep` and `latex_column_sep`.
+    pub fn to_latex_with(&self, style: &DiagramStyle) -> String {
+        let layout = Layout::new(self);
+        let grid = layout.grid();
+
+        let options: Vec<String> = [
+            style
+                .latex_row_sep
+                .as_ref()
+                .map(|sep| format!("row sep={{{}}}", sep)),
+            style
+                .latex_column_sep
+                .as_ref()
+                .map(|sep| format!("column sep={{{}}}", sep)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut latex = String::from("\\begin{quantikz}");
+        if !options.is_empty() {
+            let _ = write!(latex, "[{}]", options.join(", "));
+        }
+        latex.push('\n');
+
+        let rows: Vec<String> = grid
+            .iter()
+            .zip(&layout.qdus)
+            .map(|(cells, qdu)| {
+                let mut row: Vec<String> = Vec::with_capacity(cells.len() + 2);
+                if style.show_labels {
+                    row.push(format!("\\lstick{{{}}}", qdu));
+                }
+                for cell in cells {
+                    row.push(match cell {
+                        None => "\\qw".to_string(),
+                        Some((glyph, span)) => latex_cell(glyph, *span),
+                    });
+                }
+                // Trailing wire segment after the last moment
+                row.push("\\qw".to_string());
+                format!("  {}", row.join(" & "))
+            })
+            .collect();
+        latex.push_str(&rows.join(" \\\\\n"));
+        if !rows.is_empty() {
+            latex.push('\n');
+        }
+        latex.push_str("\\end{quantikz}\n");
+        latex
+    }
+}
+
+/// Formats one quantikz cell; `span` draws a connector to the row `span` rows below.
+fn latex_cell(glyph: &Glyph, span: Option<isize>) -> String {
+    let connector = span.map(|s| format!(" \\vqw{{{}}}", s)).unwrap_or_default();
+    match glyph {
+        Glyph::Gate(symbol) => format!("\\gate{{{}}}{}", latex_symbol(symbol), connector),
+        Glyph::Control => match span {
+            Some(s) => format!("\\ctrl{{{}}}", s),
+            None => "\\control{}".to_string(),
+        },
+        Glyph::Target => format!("\\targ{{}}{}", connector),
+        Glyph::Measure => "\\meter{}".to_string(),
+    }
+}
+
+/// Converts a diagram symbol to LaTeX, switching to math mode for non-ASCII symbols.
+fn latex_symbol(symbol: &str) -> String {
+    if symbol.is_ascii() && !symbol.contains(['|', '>']) {
+        return symbol.to_string();
+    }
+    let mut math = String::new();
+    for c in symbol.chars() {
+        match c {
+            '†' => math.push_str("^\\dagger"),
+            '√' => math.push_str("\\sqrt "),
+            'Φ' => math.push_str("\\Phi "),
+            'γ' => math.push_str("\\gamma "),
+            'Δ' => math.push_str("\\Delta "),
+            '→' => math.push_str("\\to "),
+            '>' => math.push_str("\\rangle "),
+            c => math.push(c),
+        }
+    }
+    format!("${}$", math.trim_end())
+}
+
+/// Escapes the XML special characters in `text`.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::CircuitBuilder;
+
+    #[test]
+    fn test_latex_symbols() {
+        assert_eq!(latex_symbol("H"), "H");
+        assert_eq!(latex_symbol("S†"), "$S^\\dagger$");
+        assert_eq!(latex_symbol("√X"), "$\\sqrt X$");
+        assert_eq!(latex_symbol("|1>"), "$|1\\rangle$");
+    }
+
+    #[test]
+    fn test_svg_is_well_formed_and_escaped() {
+        let circuit = CircuitBuilder::new()
+            .add_op(Operation::Project {
+                target: QduId(0),
+                onto: crate::operations::Quality::Quality1,
+            })
+            .add_op(Operation::RelationalLock {
+                qdu1: QduId(0),
+                qdu2: QduId(2),
+                lock_type: crate::vm::program::LockType::BellPhiPlus,
+                establish: true,
+            })
+            .add_op(Operation::Stabilize {
+                targets: vec![QduId(1)],
+            })
+            .build();
+        let svg = circuit.to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("|1&gt;"));
+        // Three wires, the lock connector, the meter needle, and a dot at each end of the lock
+        assert_eq!(svg.matches("<line").count(), 3 + 1 + 1);
+        assert_eq!(svg.matches("<circle").count(), 2);
+    }
+}
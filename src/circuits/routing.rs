@@ -0,0 +1,407 @@
+// src/circuits/routing.rs
+
+//! Connectivity constraints for two-QDU operations.
+//!
+//! A [`CouplingMap`] lists which QDU pairs may interact directly. [`route`] rewrites a
+//! circuit written for all-to-all connectivity so that every two-QDU operation acts on
+//! a coupled pair, inserting swaps (two-element `Permute` operations) to move QDUs
+//! next to each other. This makes it possible to study locality-constrained variants of
+//! a circuit, e.g. a line or ring of QDUs, or the IVM neighbourhoods the engine enforces.
+
+use super::Circuit;
+use crate::core::{OnqError, QduId};
+use crate::operations::Operation;
+use crate::topology::IvmTopology;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// An undirected graph of the QDU pairs allowed to take part in two-QDU operations.
+///
+/// # Examples
+/// ```
+/// # use onq::QduId;
+/// # use onq::circuits::routing::CouplingMap;
+/// let line = CouplingMap::linear(4);
+/// assert!(line.are_coupled(QduId(1), QduId(2)));
+/// assert!(!line.are_coupled(QduId(0), QduId(3)));
+/// assert_eq!(line.distance(QduId(0), QduId(3)), Some(3));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CouplingMap {
+    neighbors: BTreeMap<QduId, BTreeSet<QduId>>,
+}
+
+impl CouplingMap {
+    /// Creates a coupling map with no QDUs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a coupling map from a list of coupled pairs.
+    pub fn from_edges(edges: impl IntoIterator<Item = (QduId, QduId)>) -> Self {
+        let mut map = Self::new();
+        for (a, b) in edges {
+            map.add_edge(a, b);
+        }
+        map
+    }
+
+    /// Couples `QduId(0) - QduId(1) - … - QduId(n-1)` in a line.
+    pub fn linear(n: u64) -> Self {
+        Self::from_edges((1..n).map(|i| (QduId(i - 1), QduId(i))))
+    }
+
+    /// Couples `QduId(0)` to `QduId(n-1)` in a ring.
+    pub fn ring(n: u64) -> Self {
+        let mut map = Self::linear(n);
+        if n > 2 {
+            map.add_edge(QduId(n - 1), QduId(0));
+        }
+        map
+    }
+
+    /// Couples the QDUs whose IVM nodes are adjacent, with `QduId(i)` standing for
+    /// physical node `i`. This matches the engine's Locality Rule for circuits using
+    /// `QduId(0)..QduId(n)`, which the simulator places on nodes `0..n`.
+    pub fn ivm() -> Self {
+        let topology = IvmTopology::new();
+        let nodes = topology.nodes.len() as u64;
+        Self::from_edges((0..nodes).flat_map(|a| {
+            let topology = &topology;
+            (a + 1..nodes)
+                .filter(move |b| topology.are_adjacent(a, *b))
+                .map(move |b| (QduId(a), QduId(b)))
+        }))
+    }
+
+    /// Adds a QDU without couplings, so it can hold a QDU during routing.
+    pub fn add_qdu(&mut self, qdu: QduId) {
+        self.neighbors.entry(qdu).or_default();
+    }
+
+    /// Couples `a` and `b`.
+    pub fn add_edge(&mut self, a: QduId, b: QduId) {
+        self.neighbors.entry(a).or_default().insert(b);
+        self.neighbors.entry(b).or_default().insert(a);
+    }
+
+    /// Returns `true` if `a` and `b` may interact directly.
+    pub fn are_coupled(&self, a: QduId, b: QduId) -> bool {
+        self.neighbors.get(&a).is_some_and(|n| n.contains(&b))
+    }
+
+    /// Returns `true` if `qdu` is part of the map.
+    pub fn contains(&self, qdu: QduId) -> bool {
+        self.neighbors.contains_key(&qdu)
+    }
+
+    /// Returns the QDUs of the map in ascending order.
+    pub fn qdus(&self) -> impl Iterator<Item = QduId> + '_ {
+        self.neighbors.keys().copied()
+    }
+
+    /// Returns the QDUs coupled to `qdu`, in ascending order.
+    pub fn neighbors(&self, qdu: QduId) -> impl Iterator<Item = QduId> + '_ {
+        self.neighbors.get(&qdu).into_iter().flatten().copied()
+    }
+
+    /// Returns the coupled pairs `(a, b)` with `a < b`.
+    pub fn edges(&self) -> Vec<(QduId, QduId)> {
+        self.neighbors
+            .iter()
+            .flat_map(|(a, n)| n.range(*a..).map(move |b| (*a, *b)))
+            .collect()
+    }
+
+    /// Returns a shortest chain of coupled QDUs from `from` to `to` (both included),
+    /// or `None` if they are not connected.
+    pub fn shortest_path(&self, from: QduId, to: QduId) -> Option<Vec<QduId>> {
+        if !self.contains(from) || !self.contains(to) {
+            return None;
+        }
+        let mut previous = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                let mut step = to;
+                while step != from {
+                    step = previous[&step];
+                    path.push(step);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.neighbors(current) {
+                if let Entry::Vacant(entry) = previous.entry(next) {
+                    entry.insert(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the number of couplings on a shortest path between `a` and `b`.
+    pub fn distance(&self, a: QduId, b: QduId) -> Option<usize> {
+        self.shortest_path(a, b).map(|path| path.len() - 1)
+    }
+
+    /// Checks that every two-QDU interaction of `circuit` acts on a coupled pair.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` for the first uncoupled interaction.
+    pub fn check(&self, circuit: &Circuit) -> Result<(), OnqError> {
+        for (index, op) in circuit.operations().iter().enumerate() {
+            for (a, b) in coupled_pairs(op) {
+                if !self.are_coupled(a, b) {
+                    return Err(OnqError::InvalidOperation {
+                        message: format!(
+                            "Operation {} couples {} and {}, which are not coupled in the map",
+                            index, a, b
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of [`route`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutedCircuit {
+    /// The routed circuit, acting on the QDUs of the coupling map.
+    pub circuit: Circuit,
+    /// Where each QDU of the original circuit ends up after the inserted swaps.
+    pub final_layout: BTreeMap<QduId, QduId>,
+    /// The number of swaps inserted.
+    pub swaps: usize,
+}
+
+/// Routes `circuit` onto `map`: every QDU starts at the map position with the same ID,
+/// and before each two-QDU interaction on uncoupled positions, the first QDU is swapped
+/// along a shortest path until it sits next to the second.
+///
+/// Operations are rewritten onto the positions their QDUs occupy at that point, so
+/// stabilization outcomes (and classical bits) of the routed circuit are reported for
+/// positions; `final_layout` maps them back to the original QDUs.
+///
+/// # Errors
+/// Returns `OnqError::ReferenceViolation` if a QDU taking part in a two-QDU interaction
+/// is missing from the map, and `OnqError::InvalidOperation` if two interacting QDUs
+/// are not connected or a multi-QDU `PauliProduct` chain cannot be made local.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId};
+/// # use onq::circuits::routing::{route, CouplingMap};
+/// let circuit = CircuitBuilder::new().cnot(QduId(0), QduId(2)).build();
+/// let map = CouplingMap::linear(3);
+///
+/// let routed = route(&circuit, &map).unwrap();
+/// assert_eq!(routed.swaps, 1);
+/// assert_eq!(routed.final_layout[&QduId(0)], QduId(1));
+/// assert!(map.check(&routed.circuit).is_ok());
+/// ```
+pub fn route(circuit: &Circuit, map: &CouplingMap) -> Result<RoutedCircuit, OnqError> {
+    let mut layout = Layout::new(circuit.qdus().iter().copied());
+    let mut routed = circuit.derived();
+    let mut swaps = 0;
+
+    for (index, op) in circuit.operations().iter().enumerate() {
+        let pairs = coupled_pairs(op);
+        for &(a, b) in &pairs {
+            let (from, to) = (layout.position(a), layout.position(b));
+            for qdu in [a, b] {
+                if !map.contains(layout.position(qdu)) {
+                    return Err(OnqError::ReferenceViolation {
+                        message: format!("QDU {} is not part of the coupling map", qdu),
+                    });
+                }
+            }
+            if map.are_coupled(from, to) {
+                continue;
+            }
+            let path = map
+                .shortest_path(from, to)
+                .ok_or_else(|| OnqError::InvalidOperation {
+                    message: format!(
+                        "No coupling path connects {} and {} (operation {})",
+                        a, b, index
+                    ),
+                })?;
+            for step in path[..path.len() - 1].windows(2) {
+                routed.add_operation(Operation::Permute {
+                    mapping: vec![(step[0], step[1]), (step[1], step[0])],
+                });
+                layout.swap(step[0], step[1]);
+                swaps += 1;
+            }
+        }
+
+        let placed = op.map_qdus(|q| layout.position(q));
+        if let Some((a, b)) = coupled_pairs(&placed)
+            .into_iter()
+            .find(|(a, b)| !map.are_coupled(*a, *b))
+        {
+            return Err(OnqError::InvalidOperation {
+                message: format!(
+                    "Could not route operation {}: positions {} and {} remain uncoupled",
+                    index, a, b
+                ),
+            });
+        }
+        for (name, qdu) in circuit.bits_at(index) {
+            routed.bind_bit(name, routed.len(), layout.position(qdu));
+        }
+        routed.add_operation(placed);
+    }
+
+    routed
+        .qdus
+        .extend(circuit.qdus().iter().map(|q| layout.position(*q)));
+    let final_layout = circuit
+        .qdus()
+        .iter()
+        .map(|q| (*q, layout.position(*q)))
+        .collect();
+    Ok(RoutedCircuit {
+        circuit: routed,
+        final_layout,
+        swaps,
+    })
+}
+
+/// The QDU pairs an operation requires to be coupled.
+fn coupled_pairs(op: &Operation) -> Vec<(QduId, QduId)> {
+    match op {
+        Operation::ControlledInteraction {
+            control, target, ..
+        } => vec![(*control, *target)],
+        Operation::RelationalLock {
+            qdu1,
+            qdu2,
+            establish: true,
+            ..
+        } => vec![(*qdu1, *qdu2)],
+        Operation::PauliProduct { terms, .. } => terms
+            .windows(2)
+            .map(|pair| (pair[0].0, pair[1].0))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Tracks which position each QDU occupies while swaps are inserted.
+struct Layout {
+    positions: HashMap<QduId, QduId>,
+    occupants: HashMap<QduId, QduId>,
+}
+
+impl Layout {
+    fn new(qdus: impl Iterator<Item = QduId>) -> Self {
+        let positions: HashMap<QduId, QduId> = qdus.map(|q| (q, q)).collect();
+        let occupants = positions.clone();
+        Layout {
+            positions,
+            occupants,
+        }
+    }
+
+    fn position(&self, qdu: QduId) -> QduId {
+        self.positions.get(&qdu).copied().unwrap_or(qdu)
+    }
+
+    /// Exchanges whatever occupies positions `a` and `b`.
+    fn swap(&mut self, a: QduId, b: QduId) {
+        let occupant_a = self.occupants.remove(&a);
+        let occupant_b = self.occupants.remove(&b);
+        if let Some(qdu) = occupant_a {
+            self.positions.insert(qdu, b);
+            self.occupants.insert(b, qdu);
+        }
+        if let Some(qdu) = occupant_b {
+            self.positions.insert(qdu, a);
+            self.occupants.insert(a, qdu);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::CircuitBuilder;
+
+    fn qid(id: u64) -> QduId {
+        QduId(id)
+    }
+
+    #[test]
+    fn test_coupling_map_paths() {
+        let ring = CouplingMap::ring(6);
+        assert_eq!(ring.edges().len(), 6);
+        assert_eq!(ring.distance(qid(0), qid(5)), Some(1));
+        assert_eq!(ring.shortest_path(qid(0), qid(3)).map(|p| p.len()), Some(4));
+        assert_eq!(ring.shortest_path(qid(2), qid(2)), Some(vec![qid(2)]));
+
+        let mut split = CouplingMap::linear(2);
+        split.add_qdu(qid(7));
+        assert_eq!(split.shortest_path(qid(0), qid(7)), None);
+        assert_eq!(split.shortest_path(qid(0), qid(9)), None);
+
+        let ivm = CouplingMap::ivm();
+        assert_eq!(ivm.qdus().count(), 64);
+        assert!(ivm.are_coupled(qid(0), qid(1)));
+        assert!(!ivm.are_coupled(qid(0), qid(2)));
+    }
+
+    #[test]
+    fn test_route_inserts_swaps_and_tracks_layout() {
+        let circuit = CircuitBuilder::new()
+            .h(qid(0))
+            .cnot(qid(0), qid(3))
+            .x(qid(0))
+            .cnot(qid(2), qid(0))
+            .stabilize_into(qid(0), "m")
+            .build();
+        let map = CouplingMap::linear(4);
+        let routed = route(&circuit, &map).unwrap();
+
+        // 0 walks to position 2, pushing 2 back to position 1
+        assert_eq!(routed.swaps, 2);
+        assert_eq!(routed.final_layout[&qid(0)], qid(2));
+        assert_eq!(routed.final_layout[&qid(2)], qid(1));
+        assert!(map.check(&routed.circuit).is_ok());
+        assert_eq!(
+            routed.circuit.operations()[3],
+            Operation::ControlledInteraction {
+                control: qid(2),
+                target: qid(3),
+                pattern_id: "QualityFlip".to_string()
+            }
+        );
+        assert_eq!(routed.circuit.classical_bit("m"), Some(qid(2)));
+
+        // Nothing to do when the circuit already respects the map
+        let local = CircuitBuilder::new().cnot(qid(0), qid(1)).build();
+        assert_eq!(route(&local, &map).unwrap().circuit, local);
+    }
+
+    #[test]
+    fn test_route_rejects_unreachable_qdus() {
+        let circuit = CircuitBuilder::new().cnot(qid(0), qid(5)).build();
+        assert!(matches!(
+            route(&circuit, &CouplingMap::linear(4)),
+            Err(OnqError::ReferenceViolation { .. })
+        ));
+        assert!(CouplingMap::linear(4).check(&circuit).is_err());
+
+        let mut split = CouplingMap::linear(2);
+        split.add_qdu(qid(5));
+        assert!(matches!(
+            route(&circuit, &split),
+            Err(OnqError::InvalidOperation { .. })
+        ));
+    }
+}
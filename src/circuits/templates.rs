@@ -0,0 +1,210 @@
+// src/circuits/templates.rs
+
+//! Ready-made preparations of standard states.
+//!
+//! Each template returns a [`CircuitBuilder`] preparing the state on the given QDUs,
+//! so callers can keep appending operations (typically a final `stabilize`).
+//! Multi-QDU templates only couple consecutive QDUs of the slice, so passing QDUs in
+//! IVM order keeps every interaction within the engine's Locality Rule.
+
+use super::CircuitBuilder;
+use crate::core::QduId;
+use crate::operations::{Operation, PauliAxis};
+
+/// Prepares the Bell-pair analog `(|00> + |11>)/√2` on `a` and `b`.
+///
+/// # Examples
+/// ```
+/// # use onq::QduId;
+/// # use onq::circuits::templates::bell_pair;
+/// let (a, b) = (QduId(0), QduId(1));
+/// let circuit = bell_pair(a, b).stabilize(&[a, b]).build();
+/// assert_eq!(circuit.len(), 3);
+/// ```
+pub fn bell_pair(a: QduId, b: QduId) -> CircuitBuilder {
+    CircuitBuilder::new().h(a).cnot(a, b)
+}
+
+/// Prepares the GHZ analog `(|0…0> + |1…1>)/√2` on `qdus`: a `Superposition` on the
+/// first QDU, then a chain of controlled `QualityFlip`s.
+pub fn ghz(qdus: &[QduId]) -> CircuitBuilder {
+    match qdus.first() {
+        Some(first) => CircuitBuilder::new()
+            .h(*first)
+            .entangle_chain(qdus, "QualityFlip"),
+        None => CircuitBuilder::new(),
+    }
+}
+
+/// Prepares the W-state analog `(|10…0> + |01…0> + … + |0…01>)/√n` on `qdus`.
+///
+/// The single excitation starts on the first QDU and is passed down the chain: each
+/// step keeps it with probability `1/(n-k)` using a controlled rotation (built from
+/// `PauliProduct` Y-rotations and controlled `QualityFlip`s), then moves the rest on.
+pub fn w_state(qdus: &[QduId]) -> CircuitBuilder {
+    let Some(first) = qdus.first() else {
+        return CircuitBuilder::new();
+    };
+    let n = qdus.len();
+    let mut builder = CircuitBuilder::new().x(*first);
+    for (k, pair) in qdus.windows(2).enumerate() {
+        let (current, next) = (pair[0], pair[1]);
+        // Rotation angle α with cos²(α/2) = 1/(n-k)
+        let alpha = 2.0 * (1.0 / (n - k) as f64).sqrt().acos();
+        // Controlled Ry(α) = Ry(α/2) · CX · Ry(-α/2) · CX; exp(iθY) = Ry(-2θ)
+        builder = builder
+            .add_op(y_rotation(next, alpha / 2.0))
+            .cnot(current, next)
+            .add_op(y_rotation(next, -alpha / 2.0))
+            .cnot(current, next)
+            .cnot(next, current);
+    }
+    builder
+}
+
+/// Prepares the uniform superposition over all `2^n` basis states of `qdus`
+/// with a single broadcast `Superposition`.
+pub fn uniform_superposition(qdus: &[QduId]) -> CircuitBuilder {
+    if qdus.is_empty() {
+        return CircuitBuilder::new();
+    }
+    CircuitBuilder::new().add_op(Operation::BroadcastPattern {
+        targets: qdus.to_vec(),
+        pattern_id: "Superposition".to_string(),
+    })
+}
+
+/// Returns the rotation `Ry(α)` analog on `target`.
+fn y_rotation(target: QduId, alpha: f64) -> Operation {
+    Operation::PauliProduct {
+        terms: vec![(target, PauliAxis::Y)],
+        theta: -alpha / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::Circuit;
+    use crate::operations::PatternRegistry;
+    use num_complex::Complex;
+
+    /// Dense reference simulation of the operations used by the templates,
+    /// with QDU `k` of `qdus` as bit `k` of the basis index.
+    fn amplitudes(circuit: &Circuit, qdus: &[QduId]) -> Vec<Complex<f64>> {
+        let bit = |q: &QduId| 1usize << qdus.iter().position(|x| x == q).unwrap();
+        let mut state = vec![Complex::new(0.0, 0.0); 1 << qdus.len()];
+        state[0] = Complex::new(1.0, 0.0);
+        let apply = |state: &mut Vec<Complex<f64>>,
+                     mask: usize,
+                     control: usize,
+                     m: [[Complex<f64>; 2]; 2]| {
+            for i in 0..state.len() {
+                if i & mask == 0 && i & control == control {
+                    let (a0, a1) = (state[i], state[i | mask]);
+                    state[i] = m[0][0] * a0 + m[0][1] * a1;
+                    state[i | mask] = m[1][0] * a0 + m[1][1] * a1;
+                }
+            }
+        };
+        let registry = PatternRegistry::builtin();
+        for op in circuit.operations() {
+            match op {
+                Operation::InteractionPattern { target, pattern_id } => apply(
+                    &mut state,
+                    bit(target),
+                    0,
+                    registry.matrix(pattern_id).unwrap(),
+                ),
+                Operation::BroadcastPattern {
+                    targets,
+                    pattern_id,
+                } => {
+                    for target in targets {
+                        apply(
+                            &mut state,
+                            bit(target),
+                            0,
+                            registry.matrix(pattern_id).unwrap(),
+                        );
+                    }
+                }
+                Operation::ControlledInteraction {
+                    control,
+                    target,
+                    pattern_id,
+                } => apply(
+                    &mut state,
+                    bit(target),
+                    bit(control),
+                    registry.matrix(pattern_id).unwrap(),
+                ),
+                Operation::PauliProduct { terms, theta } => {
+                    let (c, s) = (
+                        Complex::new(theta.cos(), 0.0),
+                        Complex::new(theta.sin(), 0.0),
+                    );
+                    apply(&mut state, bit(&terms[0].0), 0, [[c, s], [-s, c]]);
+                }
+                other => panic!("unexpected operation {:?}", other),
+            }
+        }
+        state
+    }
+
+    fn assert_probabilities(circuit: &Circuit, qdus: &[QduId], expected: &[(usize, f64)]) {
+        let state = amplitudes(circuit, qdus);
+        for (index, amplitude) in state.iter().enumerate() {
+            let p = expected
+                .iter()
+                .find(|(i, _)| *i == index)
+                .map_or(0.0, |(_, p)| *p);
+            assert!(
+                (amplitude.norm_sqr() - p).abs() < 1e-12,
+                "|{:b}>: {}",
+                index,
+                amplitude
+            );
+        }
+    }
+
+    #[test]
+    fn test_templates_prepare_expected_states() {
+        let qdus: Vec<QduId> = (0..4).map(QduId).collect();
+
+        assert_probabilities(
+            &bell_pair(qdus[0], qdus[1]).build(),
+            &qdus[..2],
+            &[(0b00, 0.5), (0b11, 0.5)],
+        );
+        assert_probabilities(&ghz(&qdus).build(), &qdus, &[(0b0000, 0.5), (0b1111, 0.5)]);
+        assert_probabilities(
+            &w_state(&qdus).build(),
+            &qdus,
+            &[
+                (0b0001, 0.25),
+                (0b0010, 0.25),
+                (0b0100, 0.25),
+                (0b1000, 0.25),
+            ],
+        );
+        let uniform: Vec<(usize, f64)> = (0..16).map(|i| (i, 1.0 / 16.0)).collect();
+        assert_probabilities(&uniform_superposition(&qdus).build(), &qdus, &uniform);
+
+        assert!(w_state(&[]).build().is_empty());
+        assert_eq!(w_state(&qdus[..1]).build().len(), 1);
+    }
+
+    #[test]
+    fn test_templates_respect_locality() {
+        // Every multi-QDU operation couples neighbours in the given order
+        let qdus: Vec<QduId> = (0..5).map(QduId).collect();
+        for circuit in [ghz(&qdus).build(), w_state(&qdus).build()] {
+            for op in circuit.operations() {
+                if let [a, b] = op.involved_qdus().as_slice() {
+                    assert_eq!(a.0.abs_diff(b.0), 1, "{:?}", op);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+// src/circuits/transpile.rs
+
+//! Rewriting circuits onto a restricted set of interaction patterns.
+//!
+//! [`transpile`] replaces every pattern outside a caller-given allow-list by an
+//! equivalent sequence of allowed patterns, found by searching the known identities
+//! between the built-in patterns (e.g. `PhaseIntroduce = HalfPhase·HalfPhase`,
+//! `QualityFlip = Superposition·PhaseIntroduce·Superposition`). This makes it easy to
+//! study what remains expressible with a subset of the derived operations.
+
+use super::Circuit;
+use crate::core::{OnqError, QduId};
+use crate::operations::Operation;
+use std::collections::HashMap;
+
+/// Maximum nesting of identities tried when decomposing a single pattern.
+const MAX_DEPTH: usize = 4;
+
+/// Known single-QDU identities: each pattern with alternative sequences of patterns,
+/// in application order, equal to it up to a global phase.
+const IDENTITIES: &[(&str, &[&[&str]])] = &[
+    (
+        "PhaseIntroduce",
+        &[
+            &["HalfPhase", "HalfPhase"],
+            &[
+                "QuarterPhase",
+                "QuarterPhase",
+                "QuarterPhase",
+                "QuarterPhase",
+            ],
+            &["Superposition", "QualityFlip", "Superposition"],
+        ],
+    ),
+    (
+        "HalfPhase",
+        &[
+            &["QuarterPhase", "QuarterPhase"],
+            &["PhaseIntroduce", "HalfPhase_Inv"],
+        ],
+    ),
+    (
+        "HalfPhase_Inv",
+        &[
+            &["PhaseIntroduce", "HalfPhase"],
+            &["QuarterPhase_Inv", "QuarterPhase_Inv"],
+            &["HalfPhase", "HalfPhase", "HalfPhase"],
+        ],
+    ),
+    ("QuarterPhase", &[&["HalfPhase", "QuarterPhase_Inv"]]),
+    ("QuarterPhase_Inv", &[&["HalfPhase_Inv", "QuarterPhase"]]),
+    (
+        "QualityFlip",
+        &[
+            &["Superposition", "PhaseIntroduce", "Superposition"],
+            &["SqrtFlip", "SqrtFlip"],
+        ],
+    ),
+    (
+        "SqrtFlip",
+        &[
+            &["Superposition", "HalfPhase", "Superposition"],
+            &["SqrtFlip_Inv", "QualityFlip"],
+        ],
+    ),
+    (
+        "SqrtFlip_Inv",
+        &[
+            &["Superposition", "HalfPhase_Inv", "Superposition"],
+            &["SqrtFlip", "QualityFlip"],
+        ],
+    ),
+    (
+        "QualitativeY",
+        &[
+            &["HalfPhase_Inv", "QualityFlip", "HalfPhase"],
+            &["QualityFlip", "PhaseIntroduce"],
+        ],
+    ),
+    ("Superposition", &[&["HalfPhase", "SqrtFlip", "HalfPhase"]]),
+    (
+        "PhiRotate",
+        &[&["HalfPhase_Inv", "PhiXRotate", "HalfPhase"]],
+    ),
+    (
+        "PhiXRotate",
+        &[&["HalfPhase", "PhiRotate", "HalfPhase_Inv"]],
+    ),
+    (
+        "PhiRotate_Inv",
+        &[&["HalfPhase_Inv", "PhiXRotate_Inv", "HalfPhase"]],
+    ),
+    (
+        "PhiXRotate_Inv",
+        &[&["HalfPhase", "PhiRotate_Inv", "HalfPhase_Inv"]],
+    ),
+];
+
+/// Rewrites `circuit` so every `InteractionPattern`, `BroadcastPattern` and
+/// `ControlledInteraction` only uses pattern IDs from `allowed`.
+///
+/// * `Identity` patterns are dropped unless allowed.
+/// * Other single-QDU patterns are replaced by the shortest equivalent sequence of
+///   allowed patterns (equal up to a global phase, which stabilization ignores).
+/// * Controlled `QualityFlip`, `PhaseIntroduce` and `QualitativeY` are rewritten into
+///   one another by exact conjugation of the target (e.g. `CX = H·CZ·H`); the
+///   conjugating patterns are transpiled in turn.
+///
+/// Operations without a pattern ID (phase shifts, matrices, stabilization, …) are
+/// left unchanged, as are the circuit's name, metadata and classical bits.
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` naming the first pattern that cannot be
+/// expressed with the allowed set.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId};
+/// # use onq::circuits::transpile::transpile;
+/// let circuit = CircuitBuilder::new().z(QduId(0)).cnot(QduId(0), QduId(1)).build();
+///
+/// let restricted = transpile(&circuit, &["Superposition", "HalfPhase", "PhaseIntroduce"]).unwrap();
+/// // Z becomes itself; CX becomes H·CZ·H on the target
+/// assert_eq!(restricted.len(), 4);
+/// assert!(transpile(&circuit, &["HalfPhase"]).is_err());
+/// ```
+pub fn transpile(circuit: &Circuit, allowed: &[&str]) -> Result<Circuit, OnqError> {
+    let mut transpiler = Transpiler {
+        allowed,
+        cache: HashMap::new(),
+    };
+    let mut transpiled = circuit.derived();
+    let mut new_index = Vec::with_capacity(circuit.len());
+
+    for op in circuit.operations() {
+        let ops = match op {
+            Operation::InteractionPattern { target, pattern_id } => {
+                transpiler.single(*target, pattern_id)?
+            }
+            Operation::BroadcastPattern {
+                targets,
+                pattern_id,
+            } => transpiler
+                .sequence(pattern_id)?
+                .into_iter()
+                .map(|pattern_id| Operation::BroadcastPattern {
+                    targets: targets.clone(),
+                    pattern_id,
+                })
+                .collect(),
+            Operation::ControlledInteraction {
+                control,
+                target,
+                pattern_id,
+            } => transpiler.controlled(*control, *target, pattern_id, MAX_DEPTH)?,
+            op => vec![op.clone()],
+        };
+        new_index.push(transpiled.len() + ops.len().saturating_sub(1));
+        transpiled.add_operations(ops);
+    }
+
+    // Stabilizations are kept one-to-one, so classical bits simply move with them
+    transpiled.carry_bits(circuit, |i| Some(new_index[i]));
+    transpiled.qdus.extend(circuit.qdus().iter().copied());
+    Ok(transpiled)
+}
+
+struct Transpiler<'a> {
+    allowed: &'a [&'a str],
+    /// Decomposition of each single-QDU pattern seen so far.
+    cache: HashMap<String, Vec<String>>,
+}
+
+impl Transpiler<'_> {
+    fn single(&mut self, target: QduId, pattern_id: &str) -> Result<Vec<Operation>, OnqError> {
+        Ok(self
+            .sequence(pattern_id)?
+            .into_iter()
+            .map(|pattern_id| Operation::InteractionPattern { target, pattern_id })
+            .collect())
+    }
+
+    /// Returns the allowed patterns equivalent to `pattern_id`, in application order.
+    fn sequence(&mut self, pattern_id: &str) -> Result<Vec<String>, OnqError> {
+        if let Some(sequence) = self.cache.get(pattern_id) {
+            return Ok(sequence.clone());
+        }
+        let sequence = decompose(pattern_id, self.allowed, MAX_DEPTH)
+            .ok_or_else(|| self.inexpressible(pattern_id))?;
+        let sequence: Vec<String> = sequence.into_iter().map(str::to_string).collect();
+        self.cache.insert(pattern_id.to_string(), sequence.clone());
+        Ok(sequence)
+    }
+
+    fn controlled(
+        &mut self,
+        control: QduId,
+        target: QduId,
+        pattern_id: &str,
+        depth: usize,
+    ) -> Result<Vec<Operation>, OnqError> {
+        if self.allowed.contains(&pattern_id) {
+            return Ok(vec![Operation::ControlledInteraction {
+                control,
+                target,
+                pattern_id: pattern_id.to_string(),
+            }]);
+        }
+        // Exact conjugations: (before, controlled pattern, after) on the target
+        let rewrites: &[(&str, &str, &str)] = match pattern_id {
+            "QualityFlip" => &[
+                ("Superposition", "PhaseIntroduce", "Superposition"),
+                ("HalfPhase", "QualitativeY", "HalfPhase_Inv"),
+            ],
+            "PhaseIntroduce" => &[("Superposition", "QualityFlip", "Superposition")],
+            "QualitativeY" => &[("HalfPhase_Inv", "QualityFlip", "HalfPhase")],
+            _ => &[],
+        };
+        if depth > 0 {
+            for (before, inner, after) in rewrites {
+                let attempt = (|| {
+                    let mut ops = self.single(target, before)?;
+                    ops.extend(self.controlled(control, target, inner, depth - 1)?);
+                    ops.extend(self.single(target, after)?);
+                    Ok::<_, OnqError>(ops)
+                })();
+                if attempt.is_ok() {
+                    return attempt;
+                }
+            }
+        }
+        Err(self.inexpressible(&format!("controlled {}", pattern_id)))
+    }
+
+    fn inexpressible(&self, what: &str) -> OnqError {
+        OnqError::InvalidOperation {
+            message: format!(
+                "Pattern '{}' cannot be expressed with the allowed patterns [{}]",
+                what,
+                self.allowed.join(", ")
+            ),
+        }
+    }
+}
+
+/// Finds the shortest sequence of allowed patterns equal to `pattern_id` using at most
+/// `depth` nested identities.
+fn decompose<'a>(pattern_id: &'a str, allowed: &[&str], depth: usize) -> Option<Vec<&'a str>> {
+    if allowed.contains(&pattern_id) {
+        return Some(vec![pattern_id]);
+    }
+    if pattern_id == "Identity" {
+        return Some(Vec::new());
+    }
+    if depth == 0 {
+        return None;
+    }
+    let (_, alternatives) = IDENTITIES.iter().find(|(id, _)| *id == pattern_id)?;
+    alternatives
+        .iter()
+        .filter_map(|alternative| {
+            alternative
+                .iter()
+                .map(|step| decompose(step, allowed, depth - 1))
+                .collect::<Option<Vec<Vec<&str>>>>()
+                .map(|steps| steps.concat())
+        })
+        .min_by_key(Vec::len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::PatternRegistry;
+    use num_complex::Complex;
+
+    type Matrix = [[Complex<f64>; 2]; 2];
+
+    fn product(sequence: &[&str]) -> Matrix {
+        let registry = PatternRegistry::builtin();
+        let mut result = registry.matrix("Identity").unwrap();
+        for step in sequence {
+            let m = registry.matrix(step).unwrap();
+            let entry = |r: usize, c: usize| m[r][0] * result[0][c] + m[r][1] * result[1][c];
+            result = [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]];
+        }
+        result
+    }
+
+    /// `a == e^{iφ}·b` for some global phase φ.
+    fn equal_up_to_phase(a: &Matrix, b: &Matrix) -> bool {
+        let inner: Complex<f64> = (0..2)
+            .flat_map(|r| (0..2).map(move |c| (r, c)))
+            .map(|(r, c)| b[r][c].conj() * a[r][c])
+            .sum();
+        (inner.norm() - 2.0).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_identities_hold_up_to_global_phase() {
+        for (pattern_id, alternatives) in IDENTITIES {
+            let expected = product(&[pattern_id]);
+            for alternative in *alternatives {
+                assert!(
+                    equal_up_to_phase(&product(alternative), &expected),
+                    "{} != {:?}",
+                    pattern_id,
+                    alternative
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_decomposition_prefers_shortest_sequence() {
+        let allowed = ["QuarterPhase", "Superposition", "QualityFlip"];
+        assert_eq!(
+            decompose("PhaseIntroduce", &allowed, MAX_DEPTH).map(|s| s.len()),
+            Some(3)
+        );
+        assert_eq!(decompose("Identity", &allowed, MAX_DEPTH), Some(vec![]));
+        assert_eq!(decompose("PhiRotate", &allowed, MAX_DEPTH), None);
+
+        // Every result is equivalent to the original pattern
+        for (pattern_id, _) in IDENTITIES {
+            if let Some(sequence) = decompose(pattern_id, &allowed, MAX_DEPTH) {
+                assert!(equal_up_to_phase(
+                    &product(&sequence),
+                    &product(&[pattern_id])
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// src/core/density.rs
+
+//! Mixed-state representation of the tensor network.
+//!
+//! Each IVM node holds a 2x2 density matrix `ρ` instead of a pair of amplitudes, so
+//! non-unitary processes such as relaxation produce genuine mixtures rather than
+//! pure-state approximations. Quality weights are the diagonal entries of `ρ`.
+
+use crate::core::PotentialityState;
+use num_complex::Complex;
+use std::collections::HashMap;
+
+/// The 2x2 density matrix of a single node.
+pub type DensityMatrix = [[Complex<f64>; 2]; 2];
+
+/// A network of per-node density matrices mirroring a [`PotentialityState`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityState {
+    /// The density matrix of every node, keyed by IVM node ID.
+    pub network: HashMap<u64, DensityMatrix>,
+}
+
+impl DensityState {
+    /// Builds the pure density matrices `|ψ⟩⟨ψ|` of every node of `state`,
+    /// normalizing each local state.
+    pub fn from_state(state: &PotentialityState) -> Self {
+        let network = state
+            .network
+            .iter()
+            .map(|(id, tensor)| {
+                let [a, b] = tensor.core_state;
+                let norm = a.norm_sqr() + b.norm_sqr();
+                let scale = if norm > 0.0 { 1.0 / norm } else { 0.0 };
+                let rho = [
+                    [a * a.conj() * scale, a * b.conj() * scale],
+                    [b * a.conj() * scale, b * b.conj() * scale],
+                ];
+                (*id, rho)
+            })
+            .collect();
+        Self { network }
+    }
+
+    /// Returns the quality weights `[ρ₀₀, ρ₁₁]` of a node.
+    pub fn weights(&self, node: u64) -> Option<[f64; 2]> {
+        self.network
+            .get(&node)
+            .map(|rho| [rho[0][0].re, rho[1][1].re])
+    }
+
+    /// Returns the probability of the qualities `assignment` (`(node, quality)` pairs),
+    /// the product of the listed nodes' normalized diagonal entries.
+    pub fn probability_of(&self, assignment: &[(u64, u8)]) -> Result<f64, String> {
+        let mut probability = 1.0;
+        for &(node, quality) in assignment {
+            let rho = self.node(node)?;
+            let trace = rho[0][0].re + rho[1][1].re;
+            if trace <= 0.0 {
+                return Err(format!("QDU {} has zero trace.", node));
+            }
+            let index = usize::from(quality);
+            probability *= rho[index][index].re / trace;
+        }
+        Ok(probability)
+    }
+
+    /// Returns the purity `tr(ρ²)` of a node: 1 for a pure state, 1/2 for the
+    /// maximally mixed one.
+    pub fn purity(&self, node: u64) -> Option<f64> {
+        self.network.get(&node).map(|rho| {
+            (0..2)
+                .flat_map(|r| (0..2).map(move |c| (r, c)))
+                .map(|(r, c)| (rho[r][c] * rho[c][r]).re)
+                .sum()
+        })
+    }
+
+    /// Returns the von Neumann entropy `-tr(ρ·log₂ρ)` of a node in bits: 0 for a pure
+    /// state, 1 for the maximally mixed one.
+    pub fn von_neumann_entropy(&self, node: u64) -> Option<f64> {
+        let rho = self.network.get(&node)?;
+        let trace = rho[0][0].re + rho[1][1].re;
+        if trace <= 0.0 {
+            return Some(0.0);
+        }
+        // Eigenvalues of a 2x2 Hermitian matrix with unit trace: (1 ± √(2·tr(ρ²) - 1)) / 2
+        let purity = self.purity(node)? / (trace * trace);
+        let spread = (2.0 * purity - 1.0).clamp(0.0, 1.0).sqrt();
+        let entropy = [(1.0 + spread) / 2.0, (1.0 - spread) / 2.0]
+            .into_iter()
+            .filter(|lambda| *lambda > 1e-15)
+            .map(|lambda| -lambda * lambda.log2())
+            .sum();
+        Some(entropy)
+    }
+
+    /// Returns the expectation value `tr(ρ·P)` of a Hermitian operator on a node.
+    pub fn expectation(&self, node: u64, operator: &[[Complex<f64>; 2]; 2]) -> Result<f64, String> {
+        let rho = self.node(node)?;
+        Ok((0..2)
+            .flat_map(|r| (0..2).map(move |c| (r, c)))
+            .map(|(r, c)| (rho[r][c] * operator[c][r]).re)
+            .sum())
+    }
+
+    /// Evolves a node under a unitary: `ρ → U·ρ·U†`.
+    pub fn apply_unitary(
+        &mut self,
+        node: u64,
+        matrix: &[[Complex<f64>; 2]; 2],
+    ) -> Result<(), String> {
+        let rho = self.node_mut(node)?;
+        let product: DensityMatrix = std::array::from_fn(|r| {
+            std::array::from_fn(|c| matrix[r][0] * rho[0][c] + matrix[r][1] * rho[1][c])
+        });
+        *rho = std::array::from_fn(|r| {
+            std::array::from_fn(|c| {
+                product[r][0] * matrix[c][0].conj() + product[r][1] * matrix[c][1].conj()
+            })
+        });
+        Ok(())
+    }
+
+    /// Applies the amplitude-damping channel transferring a fraction `rate` of the
+    /// Quality1 population into Quality0; coherences decay by `√(1 - rate)`.
+    pub fn amplitude_damp(&mut self, node: u64, rate: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("Relaxation rate {} is outside [0, 1].", rate));
+        }
+        let rho = self.node_mut(node)?;
+        let decay = (1.0 - rate).sqrt();
+        rho[0][0] += rho[1][1] * rate;
+        rho[1][1] *= 1.0 - rate;
+        rho[0][1] *= decay;
+        rho[1][0] *= decay;
+        Ok(())
+    }
+
+    /// Projects a node onto the basis quality `index` and renormalizes.
+    /// Fails if the node has (numerically) no weight on that quality.
+    pub fn project(&mut self, node: u64, index: usize) -> Result<(), String> {
+        let rho = self.node_mut(node)?;
+        if rho[index][index].re < 1e-24 {
+            return Err(format!(
+                "QDU {} has no potentiality for Quality{}; projection is undefined.",
+                node, index
+            ));
+        }
+        Self::collapse_matrix(rho, index);
+        Ok(())
+    }
+
+    /// Replaces a node with the resolved quality `|k⟩⟨k|`.
+    pub fn collapse(&mut self, node: u64, index: usize) -> Result<(), String> {
+        Self::collapse_matrix(self.node_mut(node)?, index);
+        Ok(())
+    }
+
+    /// Moves density matrices between nodes according to `(from, to)` pairs; the
+    /// mapping is checked by [`PotentialityState::permute`].
+    pub fn permute(&mut self, mapping: &[(u64, u64)]) -> Result<(), String> {
+        let mut moved = Vec::with_capacity(mapping.len());
+        for &(from, to) in mapping {
+            let rho = self
+                .network
+                .remove(&from)
+                .ok_or_else(|| format!("QDU {} does not exist in the network.", from))?;
+            moved.push((to, rho));
+        }
+        self.network.extend(moved);
+        Ok(())
+    }
+
+    fn collapse_matrix(rho: &mut DensityMatrix, index: usize) {
+        *rho = [[Complex::new(0.0, 0.0); 2]; 2];
+        rho[index][index] = Complex::new(1.0, 0.0);
+    }
+
+    fn node(&self, node: u64) -> Result<&DensityMatrix, String> {
+        self.network
+            .get(&node)
+            .ok_or_else(|| format!("QDU {} does not exist in the network.", node))
+    }
+
+    fn node_mut(&mut self, node: u64) -> Result<&mut DensityMatrix, String> {
+        self.network
+            .get_mut(&node)
+            .ok_or_else(|| format!("QDU {} does not exist in the network.", node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn test_damping_mixes_a_superposition() {
+        let mut state = PotentialityState::new();
+        let h = Complex::new(FRAC_1_SQRT_2, 0.0);
+        state.apply_local_operation(0, &[[h, h], [h, -h]]).unwrap();
+
+        let mut density = DensityState::from_state(&state);
+        assert!((density.purity(0).unwrap() - 1.0).abs() < 1e-12);
+
+        density.amplitude_damp(0, 0.5).unwrap();
+        let [w0, w1] = density.weights(0).unwrap();
+        assert!((w0 - 0.75).abs() < 1e-12);
+        assert!((w1 - 0.25).abs() < 1e-12);
+        // A pure state with these weights would have purity 1; damping leaves a mixture
+        assert!(density.purity(0).unwrap() < 1.0 - 1e-6);
+        assert!(density.von_neumann_entropy(0).unwrap() > 0.1);
+        assert_eq!(density.von_neumann_entropy(1), Some(0.0));
+
+        // Unitaries preserve the trace and the purity
+        let purity = density.purity(0).unwrap();
+        density.apply_unitary(0, &[[h, h], [h, -h]]).unwrap();
+        let [w0, w1] = density.weights(0).unwrap();
+        assert!((w0 + w1 - 1.0).abs() < 1e-12);
+        assert!((density.purity(0).unwrap() - purity).abs() < 1e-12);
+    }
+}
@@ -1,11 +1,13 @@
 //! Error handling logic
 
 use std::fmt;
+use std::time::Duration;
 
 /// Unique identifier for a Qualitative Distinction Unit (QDU).
 /// Its uniqueness is context-dependent within a simulation, reflecting
 /// its distinct position and origin within a structure.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QduId(pub u64);
 
 impl fmt::Display for QduId {
@@ -53,6 +55,26 @@ pub enum OnqError {
         message: String
     },
 
+    /// A simulation asked for more QDUs than the Isotropic Vector Matrix holds.
+    CapacityExceeded {
+        /// Number of QDUs requested
+        requested: usize,
+        /// Number of QDUs the IVM holds
+        capacity: usize,
+        /// Estimated memory the requested simulation would take, in bytes
+        required_bytes: usize,
+    },
+
+    /// A VM run was stopped for exceeding its instruction limit or timeout.
+    ExecutionLimitExceeded {
+        /// Number of instructions executed before the run was stopped
+        executed: u64,
+        /// Execution time of the run before it was stopped
+        elapsed: Duration,
+        /// Which limit was exceeded
+        message: String,
+    },
+
     /// General error encountered during the simulation process itself.
     SimulationError {
         /// SimulationError failure message
@@ -69,6 +91,16 @@ impl fmt::Display for OnqError {
             OnqError::BoundaryFailure { qdu_id, message } => write!(f, "Boundary Failure ({}): {}", qdu_id, message),
             OnqError::ReferenceViolation { message } => write!(f, "Reference Violation: {}", message),
             OnqError::InvalidOperation { message } => write!(f, "Invalid Operation: {}", message),
+            OnqError::CapacityExceeded { requested, capacity, required_bytes } => write!(
+                f,
+                "Capacity Exceeded: {} QDUs requested (~{} bytes), but the Isotropic Vector Matrix holds {}",
+                requested, required_bytes, capacity
+            ),
+            OnqError::ExecutionLimitExceeded { executed, elapsed, message } => write!(
+                f,
+                "Execution Limit Exceeded after {} instructions ({:?}): {}",
+                executed, elapsed, message
+            ),
             OnqError::SimulationError { message } => write!(f, "Simulation Process Error: {}", message),
         }
     }
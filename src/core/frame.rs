@@ -13,16 +13,15 @@ pub struct ReferenceFrame {
     /// Unique identifier for this frame within a simulation context.
     id: u64,
     // Future potential:
-    // - Rules governing interactions specific to this frame type.
     // - Links to parent/child frames simulating structural depth.
 }
 
 impl ReferenceFrame {
     /// Creates a new Reference Frame.
-    /// ID uniqueness should be managed by the simulation environment.
-    #[allow(dead_code)]
-    pub(crate) fn new(id: u64) -> Self {
-    // pub(crate) as frame creation might be controlled centrally.
+    /// Frames with equal IDs are the same frame; QDUs are placed in frames with
+    /// [`Circuit::set_frame`](crate::Circuit::set_frame), and interactions across
+    /// frames require an established `RelationalLock`.
+    pub fn new(id: u64) -> Self {
         Self { id }
     }
 
@@ -0,0 +1,255 @@
+// src/core/kernels.rs
+
+//! Complex arithmetic kernels for local tensor updates.
+//!
+//! A local tensor holds two complex amplitudes, i.e. four `f64` lanes. Diagonal
+//! matrices, such as phase shifts and Z-axis patterns, take a packed kernel working on
+//! the `[re0, im0, re1, im1]` layout with lane-wise arithmetic the compiler lowers to
+//! SIMD instructions on stable Rust (`std::simd` is not yet stable), skipping the cross
+//! terms. Dense matrices keep the scalar complex product: packing them measured slower
+//! (see `cargo bench --bench kernels`).
+//!
+//! The `_extended` kernels evaluate the same expressions in double-double arithmetic
+//! (an unevaluated sum of two `f64`s, about 106 mantissa bits) using error-free
+//! transformations, rounding to `f64` only once at the end.
+//!
+//! This module is public only for that benchmark and is not part of the supported API.
+
+use num_complex::Complex;
+
+/// Four `f64` lanes holding two complex amplitudes as `[re0, im0, re1, im1]`.
+pub type Packed = [f64; 4];
+
+/// Packs a pair of amplitudes into `[re0, im0, re1, im1]`.
+#[inline]
+pub fn pack(state: &[Complex<f64>; 2]) -> Packed {
+    [state[0].re, state[0].im, state[1].re, state[1].im]
+}
+
+/// Unpacks `[re0, im0, re1, im1]` into a pair of amplitudes.
+#[inline]
+pub fn unpack(lanes: Packed) -> [Complex<f64>; 2] {
+    [
+        Complex::new(lanes[0], lanes[1]),
+        Complex::new(lanes[2], lanes[3]),
+    ]
+}
+
+/// Returns `true` if both off-diagonal entries of `matrix` are exactly zero.
+#[inline]
+pub fn is_diagonal(matrix: &[[Complex<f64>; 2]; 2]) -> bool {
+    matrix[0][1] == Complex::new(0.0, 0.0) && matrix[1][0] == Complex::new(0.0, 0.0)
+}
+
+/// Multiplies the amplitudes lane-wise by the complex pair `(m0, m1)`.
+///
+/// The real parts are broadcast as `[ar0, ar0, ar1, ar1]` and the imaginary parts as
+/// `[ai0, ai0, ai1, ai1]`, so the product is
+/// `[ar0, ar0, ar1, ar1]·[mr0, mi0, mr1, mi1] + [ai0, ai0, ai1, ai1]·[-mi0, mr0, -mi1, mr1]`,
+/// which keeps every step a 4-lane operation.
+#[inline(always)]
+fn mul_lanes(lanes: Packed, m: Packed) -> Packed {
+    let re = [lanes[0], lanes[0], lanes[2], lanes[2]];
+    let im = [lanes[1], lanes[1], lanes[3], lanes[3]];
+    let swapped = [-m[1], m[0], -m[3], m[2]];
+    std::array::from_fn(|k| re[k] * m[k] + im[k] * swapped[k])
+}
+
+/// Applies a diagonal matrix `diag(d0, d1)` to the amplitudes.
+#[inline]
+pub fn apply_diagonal(state: &mut [Complex<f64>; 2], d0: Complex<f64>, d1: Complex<f64>) {
+    *state = unpack(mul_lanes(pack(state), [d0.re, d0.im, d1.re, d1.im]));
+}
+
+/// Applies a general 2x2 matrix to the amplitudes with the scalar complex product.
+#[inline]
+pub fn apply_2x2(state: &mut [Complex<f64>; 2], matrix: &[[Complex<f64>; 2]; 2]) {
+    let [a0, a1] = *state;
+    state[0] = matrix[0][0] * a0 + matrix[0][1] * a1;
+    state[1] = matrix[1][0] * a0 + matrix[1][1] * a1;
+}
+
+/// Applies `matrix`, taking the diagonal kernel when the off-diagonal entries vanish.
+#[inline]
+pub fn apply_matrix(state: &mut [Complex<f64>; 2], matrix: &[[Complex<f64>; 2]; 2]) {
+    if is_diagonal(matrix) {
+        apply_diagonal(state, matrix[0][0], matrix[1][1]);
+    } else {
+        apply_2x2(state, matrix);
+    }
+}
+
+/// An unevaluated sum `hi + lo` with `|lo| <= ulp(hi) / 2`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    const ZERO: DoubleDouble = DoubleDouble { hi: 0.0, lo: 0.0 };
+
+    /// The exact product `a·b`.
+    #[inline]
+    fn product(a: f64, b: f64) -> Self {
+        let hi = a * b;
+        Self {
+            hi,
+            lo: a.mul_add(b, -hi),
+        }
+    }
+
+    /// Adds `other`, keeping the rounding error of the leading parts.
+    #[inline]
+    fn add(self, other: Self) -> Self {
+        let sum = self.hi + other.hi;
+        let virtual_other = sum - self.hi;
+        let error = (self.hi - (sum - virtual_other)) + (other.hi - virtual_other);
+        let lo = error + self.lo + other.lo;
+        let hi = sum + lo;
+        Self {
+            hi,
+            lo: lo - (hi - sum),
+        }
+    }
+
+    /// Divides by `other`, rounding the quotient to `f64`.
+    #[inline]
+    fn div_to_f64(self, other: Self) -> f64 {
+        let q = self.hi / other.hi;
+        // Residual self - q·other, exact in its leading part
+        let residual = self.add(Self::product(-q, other.hi)).add(Self {
+            hi: -q * other.lo,
+            lo: 0.0,
+        });
+        q + residual.hi / other.hi
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+}
+
+/// Returns `Σ aᵢ·bᵢ` evaluated in double-double arithmetic and rounded once.
+#[inline]
+fn dot_extended(terms: [(f64, f64); 4]) -> f64 {
+    terms
+        .iter()
+        .fold(DoubleDouble::ZERO, |sum, &(a, b)| {
+            sum.add(DoubleDouble::product(a, b))
+        })
+        .to_f64()
+}
+
+/// Applies a general 2x2 matrix like [`apply_2x2`], evaluating every output lane in
+/// double-double arithmetic.
+pub fn apply_matrix_extended(state: &mut [Complex<f64>; 2], matrix: &[[Complex<f64>; 2]; 2]) {
+    let [a0, a1] = *state;
+    *state = std::array::from_fn(|row| {
+        let [m0, m1] = matrix[row];
+        Complex::new(
+            dot_extended([
+                (m0.re, a0.re),
+                (-m0.im, a0.im),
+                (m1.re, a1.re),
+                (-m1.im, a1.im),
+            ]),
+            dot_extended([
+                (m0.re, a0.im),
+                (m0.im, a0.re),
+                (m1.re, a1.im),
+                (m1.im, a1.re),
+            ]),
+        )
+    });
+}
+
+/// Returns the quality weights `|a|²/(|a|²+|b|²)`, `|b|²/(|a|²+|b|²)`, evaluated in
+/// double-double arithmetic and rounded once.
+pub fn normalized_weights_extended(state: &[Complex<f64>; 2]) -> [f64; 2] {
+    let weight =
+        |c: Complex<f64>| DoubleDouble::product(c.re, c.re).add(DoubleDouble::product(c.im, c.im));
+    let [w0, w1] = [weight(state[0]), weight(state[1])];
+    let total = w0.add(w1);
+    [w0.div_to_f64(total), w1.div_to_f64(total)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(state: [Complex<f64>; 2], m: &[[Complex<f64>; 2]; 2]) -> [Complex<f64>; 2] {
+        [
+            m[0][0] * state[0] + m[0][1] * state[1],
+            m[1][0] * state[0] + m[1][1] * state[1],
+        ]
+    }
+
+    #[test]
+    fn test_kernels_match_scalar_product() {
+        let state = [Complex::new(0.6, -0.1), Complex::new(0.3, 0.734)];
+        let dense = [
+            [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+            [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+        ];
+        let diagonal = [
+            [Complex::new(0.0, 1.0), Complex::new(0.0, 0.0)],
+            [Complex::new(0.0, 0.0), Complex::new(-0.8, 0.6)],
+        ];
+
+        assert!(!is_diagonal(&dense));
+        assert!(is_diagonal(&diagonal));
+        for m in [&dense, &diagonal] {
+            let expected = reference(state, m);
+            let mut general = state;
+            apply_2x2(&mut general, m);
+            let mut dispatched = state;
+            apply_matrix(&mut dispatched, m);
+            for k in 0..2 {
+                assert!((general[k] - expected[k]).norm() < 1e-15);
+                assert!((dispatched[k] - expected[k]).norm() < 1e-15);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_kernels_round_once() {
+        let state = [Complex::new(0.1, 0.2), Complex::new(0.3, -0.4)];
+        let [w0, w1] = normalized_weights_extended(&state);
+        let total = state[0].norm_sqr() + state[1].norm_sqr();
+        assert!((w0 - state[0].norm_sqr() / total).abs() <= 2.0 * f64::EPSILON);
+        assert!((w0 + w1 - 1.0).abs() <= f64::EPSILON);
+        // |a|² = 1 + 2⁻⁶⁰ and the total 1 + 2⁻⁵⁴ + 2⁻⁶⁰ both round to 1 in f64, so a
+        // plain evaluation returns 2⁻⁵⁴ for the weight of |b|² = 2⁻⁵⁴
+        let [_, w1] = normalized_weights_extended(&[
+            Complex::new(1.0, 2f64.powi(-30)),
+            Complex::new(2f64.powi(-27), 0.0),
+        ]);
+        assert!(w1 < 2f64.powi(-54));
+
+        // Cancellation the plain kernel loses entirely
+        let big = 1e17;
+        let state = [Complex::new(big, 0.0), Complex::new(1.0, 0.0)];
+        let difference = [
+            [Complex::new(1.0, 0.0), Complex::new(1.0, 0.0)],
+            [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+        ];
+        let mut extended = state;
+        apply_matrix_extended(&mut extended, &difference);
+        assert_eq!(extended[0].re, big + 1.0);
+        assert_eq!(extended[1].re, big);
+
+        let dense = [
+            [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+            [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+        ];
+        let state = [Complex::new(0.6, -0.1), Complex::new(0.3, 0.734)];
+        let mut extended = state;
+        apply_matrix_extended(&mut extended, &dense);
+        let expected = reference(state, &dense);
+        for k in 0..2 {
+            assert!((extended[k] - expected[k]).norm() < 1e-15);
+        }
+    }
+}
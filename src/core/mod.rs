@@ -3,13 +3,18 @@
 //! Core data structures and types
 
 // Declare modules within core
+pub mod density;
 pub mod error;
 pub mod frame;
+#[doc(hidden)]
+pub mod kernels;
 pub mod qdu;
+mod siphash;
 /// Geometric tensor network state representation
 pub mod state;
 
 // Re-export public types for convenient access via `onq::core::TypeName`
+pub use density::DensityState;
 pub use error::{OnqError, QduId};
 pub use frame::ReferenceFrame;
 pub use qdu::Qdu;
@@ -0,0 +1,114 @@
+// src/core/siphash.rs
+
+//! A fixed-key SipHash-1-3 for the stabilization draw.
+//!
+//! `std`'s `DefaultHasher` is documented as unspecified across Rust releases and
+//! feeds integers in native byte order, so a draw derived from it may differ between
+//! toolchains and platforms. This hasher implements the same algorithm with the key
+//! fixed to zero and integers written little-endian, which reproduces the draws of
+//! `DefaultHasher` on little-endian targets while pinning them everywhere else.
+
+use std::hash::Hasher;
+
+/// SipHash-1-3 with a zero key over little-endian bytes.
+#[derive(Debug, Clone)]
+pub(crate) struct StableHasher {
+    v: [u64; 4],
+    /// Bytes not yet compressed, packed little-endian into the low `ntail` bytes.
+    tail: u64,
+    ntail: usize,
+    length: usize,
+}
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        let (k0, k1) = (0u64, 0u64);
+        Self {
+            v: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    #[inline]
+    fn round(&mut self) {
+        let [v0, v1, v2, v3] = &mut self.v;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    #[inline]
+    fn compress(&mut self, block: u64) {
+        self.v[3] ^= block;
+        self.round();
+        self.v[0] ^= block;
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length += bytes.len();
+        for &byte in bytes {
+            self.tail |= (byte as u64) << (8 * self.ntail);
+            self.ntail += 1;
+            if self.ntail == 8 {
+                self.compress(self.tail);
+                self.tail = 0;
+                self.ntail = 0;
+            }
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = self.clone();
+        let block = ((self.length as u64 & 0xff) << 56) | self.tail;
+        state.compress(block);
+        state.v[2] ^= 0xff;
+        for _ in 0..3 {
+            state.round();
+        }
+        state.v.iter().fold(0, |hash, v| hash ^ v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers_hash_as_little_endian_bytes() {
+        let mut hasher = StableHasher::new();
+        hasher.write_u64(u64::from_le_bytes([0, 1, 2, 3, 4, 5, 6, 7]));
+        let mut bytes = StableHasher::new();
+        bytes.write(&[0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(hasher.finish(), bytes.finish());
+    }
+
+    #[test]
+    fn test_pinned_hashes() {
+        // The values std's SipHash-1-3 produced when the draw moved off DefaultHasher
+        assert_eq!(StableHasher::new().finish(), 0xd1fb_a762_150c_532c);
+        let mut hasher = StableHasher::new();
+        hasher.write_u64(0.5f64.to_bits());
+        hasher.write_u64(0.5f64.to_bits());
+        assert_eq!(hasher.finish(), 0xef28_25db_2d6f_5b7a);
+    }
+}
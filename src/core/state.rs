@@ -1,3 +1,7 @@
+use crate::core::kernels;
+use crate::simulation::{
+    AmplitudeWeighted, CoherenceFiltered, StabilizationSeed, StabilizationStrategy,
+};
 use num_complex::Complex;
 use std::collections::HashMap;
 use std::fmt;
@@ -11,7 +15,8 @@ pub enum StableState {
     /// Represents a specific, distinct qualitative outcome.
     /// The interpretation of the `u64` value depends on the basis defined
     /// by the context and the stabilization process. It might represent
-    /// an index into a set of possible qualities or a direct value.
+    /// an index into a set of possible qualities or a direct value. A qudit of
+    /// basis dimension `d` resolves to a level below `d`.
     ResolvedQuality(u64),
     // Future: Could have variants like `Undetermined` if stabilization fails coherently,
     // or `Dissolved` if it leads to framework errors (though errors might be better).
@@ -119,11 +124,79 @@ impl GeometricPotentialityState {
 
     /// Deterministically resolves the potentiality of specific QDUs.
     /// Replaces probabilistic measurement with a Golden Ratio (1/phi) coherence filter.
+    /// Only the targets collapse: their bonds are severed on both sides, and every other
+    /// node keeps its potentiality, so mid-circuit stabilization of ancillas leaves the
+    /// rest of the network in superposition.
     pub fn stabilize(&mut self, targets: &[u64]) -> Result<HashMap<u64, u8>, String> {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+        self.stabilize_with_salt(targets, 0)
+    }
+
+    /// Like [`stabilize`](Self::stabilize), but mixes `salt` into the deterministic seed
+    /// so identical states can resolve differently (e.g. across shots).
+    /// A salt of 0 reproduces `stabilize` exactly.
+    pub fn stabilize_with_salt(
+        &mut self,
+        targets: &[u64],
+        salt: u64,
+    ) -> Result<HashMap<u64, u8>, String> {
+        self.stabilize_with(targets, salt, true)
+    }
+
+    /// Stabilizes `targets` with an explicit seed `salt` and, if `coherence_filter` is
+    /// `false`, without the Golden Ratio filter: outcomes are then drawn purely from the
+    /// quality weights by the deterministic seed.
+    pub fn stabilize_with(
+        &mut self,
+        targets: &[u64],
+        salt: u64,
+        coherence_filter: bool,
+    ) -> Result<HashMap<u64, u8>, String> {
+        if coherence_filter {
+            self.stabilize_by(targets, salt, &CoherenceFiltered::default())
+        } else {
+            self.stabilize_by(targets, salt, &AmplitudeWeighted)
+        }
+    }
+
+    /// Stabilizes `targets` with an explicit seed `salt`, letting `strategy` select each
+    /// outcome from the normalized quality weights and the deterministic draw.
+    pub fn stabilize_by(
+        &mut self,
+        targets: &[u64],
+        salt: u64,
+        strategy: &dyn StabilizationStrategy,
+    ) -> Result<HashMap<u64, u8>, String> {
+        self.stabilize_seeded(targets, StabilizationSeed::StateDerived, salt, strategy)
+    }
+
+    /// Like [`stabilize_by`](Self::stabilize_by), with the deterministic draw seeded as
+    /// `seed` describes instead of from the state alone.
+    pub fn stabilize_seeded(
+        &mut self,
+        targets: &[u64],
+        seed: StabilizationSeed,
+        salt: u64,
+        strategy: &dyn StabilizationStrategy,
+    ) -> Result<HashMap<u64, u8>, String> {
+        self.stabilize_weighted(targets, seed, salt, strategy, |state| {
+            let [prob_0, prob_1] = [state[0].norm_sqr(), state[1].norm_sqr()];
+            let total = prob_0 + prob_1;
+            [prob_0 / total, prob_1 / total]
+        })
+    }
+
+    /// Like [`stabilize_seeded`](Self::stabilize_seeded), with `weights` computing the
+    /// normalized quality weights handed to `strategy` from each local state. The
+    /// deterministic draw is always derived from the plain `f64` weights.
+    pub(crate) fn stabilize_weighted(
+        &mut self,
+        targets: &[u64],
+        seed: StabilizationSeed,
+        salt: u64,
+        strategy: &dyn StabilizationStrategy,
+        weights: impl Fn(&[Complex<f64>; 2]) -> [f64; 2],
+    ) -> Result<HashMap<u64, u8>, String> {
         let mut outcomes = HashMap::new();
-        let inverse_phi = 0.61803398875; // The Golden Ratio Coherence Threshold
 
         for &target in targets {
             let tensor = self
@@ -136,37 +209,19 @@ impl GeometricPotentialityState {
             let prob_1 = tensor.core_state[1].norm_sqr();
 
             // 2. The Deterministic Seed
-            // We hash the exact floating-point memory of the core state to generate
-            // a strictly deterministic pseudo-random number.
-            let mut hasher = DefaultHasher::new();
-            prob_0.to_bits().hash(&mut hasher);
-            prob_1.to_bits().hash(&mut hasher);
-            let seed = hasher.finish();
-
-            // Generate a deterministic float between 0.0 and 1.0
-            let prng_val = (seed % 1000000) as f64 / 1000000.0;
-
-            // 3. The Coherence Filter & Selection
-            // If a state breaches the Golden Ratio threshold, it forces structural reality.
-            // Otherwise, the deterministic PRNG collapses the wave based on weight.
-            let outcome = if prob_0 > inverse_phi {
-                0 // Quality0 has achieved dominant structural coherence
-            } else if prob_1 > inverse_phi {
-                1 // Quality1 has achieved dominant structural coherence
-            } else {
-                // Neither breached the threshold natively; use the deterministic PRNG
-                if prng_val <= (prob_0 / (prob_0 + prob_1)) {
-                    0
-                } else {
-                    1
-                }
-            };
+            let prng_val = stabilization_draw(&[prob_0, prob_1], seed, salt, target);
+
+            // 3. The Selection
+            // The strategy decides how the weights and the deterministic draw collapse
+            // the wave (e.g. the Golden Ratio coherence filter).
+            let outcome = strategy.select(weights(&tensor.core_state), prng_val);
 
             outcomes.insert(target, outcome);
         }
 
         // 4. Collapse the Geometry
         // Once the outcome is determined, we sever the potentiality and lock it into reality.
+        // Only the targets collapse: every other node keeps its (normalized) potentiality.
         for (&target, &outcome) in &outcomes {
             let tensor = self.network.get_mut(&target).unwrap();
 
@@ -176,9 +231,14 @@ impl GeometricPotentialityState {
                 tensor.core_state = [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)];
             }
 
-            // Sever the entanglement bonds! The potentiality has collapsed,
+            // Sever the entanglement bonds on both sides! The potentiality has collapsed,
             // freeing the adjacent geometry to form new connections.
-            tensor.bonds.clear();
+            let neighbors: Vec<u64> = tensor.bonds.drain().map(|(neighbor, _)| neighbor).collect();
+            for neighbor in neighbors {
+                if let Some(neighbor_tensor) = self.network.get_mut(&neighbor) {
+                    neighbor_tensor.bonds.remove(&target);
+                }
+            }
         }
 
         Ok(outcomes)
@@ -195,11 +255,138 @@ impl GeometricPotentialityState {
             .get_mut(&target)
             .ok_or_else(|| format!("QDU {} does not exist in the network.", target))?;
 
-        let current_state = tensor.core_state;
+        // Matrix * vector multiplication, completely localized to one node; diagonal
+        // matrices skip the cross terms
+        kernels::apply_matrix(&mut tensor.core_state, matrix);
+
+        Ok(())
+    }
+
+    /// Applies `exp(iθ·P)` for a product `P` of single-QDU axes, one per `(qdu, axis)` term.
+    ///
+    /// Each local tensor evolves under its own axis with the angle scaled by the
+    /// expectation value of the remaining axes on their local states (a mean-field update).
+    /// This is exact whenever every other QDU is an eigenstate of its axis, e.g. ZZ
+    /// couplings on resolved qualities, and preserves each local norm otherwise.
+    pub fn apply_pauli_product(
+        &mut self,
+        terms: &[(u64, [[Complex<f64>; 2]; 2])],
+        theta: f64,
+    ) -> Result<(), String> {
+        // Expectation <ψ|P|ψ> of each axis on the pre-operation local state
+        let mut expectations = Vec::with_capacity(terms.len());
+        for (qdu_id, axis) in terms {
+            let psi = self
+                .network
+                .get(qdu_id)
+                .ok_or_else(|| format!("QDU {} does not exist in the network.", qdu_id))?
+                .core_state;
+            let p_psi = [
+                axis[0][0] * psi[0] + axis[0][1] * psi[1],
+                axis[1][0] * psi[0] + axis[1][1] * psi[1],
+            ];
+            expectations.push((psi[0].conj() * p_psi[0] + psi[1].conj() * p_psi[1]).re);
+        }
+
+        for (k, (qdu_id, axis)) in terms.iter().enumerate() {
+            let rest: f64 = expectations
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != k)
+                .map(|(_, e)| e)
+                .product();
+            self.apply_local_operation(*qdu_id, &axis_rotation(axis, theta * rest))?;
+        }
+
+        Ok(())
+    }
+
+    /// Projects a QDU onto the basis quality `index` (0 or 1) and renormalizes.
+    /// Fails if the QDU has (numerically) no amplitude on that quality.
+    /// Like stabilization, the projected QDU's entanglement bonds are severed.
+    pub fn project(&mut self, target: u64, index: usize) -> Result<(), String> {
+        let tensor = self
+            .network
+            .get_mut(&target)
+            .ok_or_else(|| format!("QDU {} does not exist in the network.", target))?;
+
+        let amplitude = tensor.core_state[index];
+        let norm = amplitude.norm();
+        if norm < 1e-12 {
+            return Err(format!(
+                "QDU {} has no potentiality for Quality{}; projection is undefined.",
+                target, index
+            ));
+        }
 
-        // Standard matrix * vector multiplication, but completely localized to one node
-        tensor.core_state[0] = matrix[0][0] * current_state[0] + matrix[0][1] * current_state[1];
-        tensor.core_state[1] = matrix[1][0] * current_state[0] + matrix[1][1] * current_state[1];
+        // Keep the amplitude's phase so the projection is a pure filter
+        tensor.core_state = [Complex::new(0.0, 0.0); 2];
+        tensor.core_state[index] = amplitude / norm;
+        tensor.bonds.clear();
+
+        Ok(())
+    }
+
+    /// Transfers a fraction `rate` of a QDU's Quality1 weight into Quality0,
+    /// keeping the phase of each amplitude. The local norm is preserved.
+    pub fn relax(&mut self, target: u64, rate: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("Relaxation rate {} is outside [0, 1].", rate));
+        }
+        let tensor = self
+            .network
+            .get_mut(&target)
+            .ok_or_else(|| format!("QDU {} does not exist in the network.", target))?;
+
+        let [amp0, amp1] = tensor.core_state;
+        let weight0 = amp0.norm_sqr() + rate * amp1.norm_sqr();
+        // A QDU with no Quality0 potentiality has no phase to keep; take it as real
+        let phase0 = if amp0.norm_sqr() > 1e-24 {
+            amp0 / amp0.norm()
+        } else {
+            Complex::new(1.0, 0.0)
+        };
+        tensor.core_state = [phase0 * weight0.sqrt(), amp1 * (1.0 - rate).sqrt()];
+
+        Ok(())
+    }
+
+    /// Moves local tensors between nodes according to `(from, to)` pairs, which must
+    /// form a permutation. Bonds to relabeled nodes follow their tensors.
+    pub fn permute(&mut self, mapping: &[(u64, u64)]) -> Result<(), String> {
+        let relabel: HashMap<u64, u64> = mapping.iter().copied().collect();
+        let mut sources: Vec<u64> = relabel.keys().copied().collect();
+        let mut destinations: Vec<u64> = relabel.values().copied().collect();
+        sources.sort_unstable();
+        destinations.sort_unstable();
+        destinations.dedup();
+        if relabel.len() != mapping.len() || sources != destinations {
+            return Err("Permutation mapping must relabel each QDU exactly once.".to_string());
+        }
+
+        let mut moved = Vec::with_capacity(mapping.len());
+        for (&from, &to) in &relabel {
+            let tensor = self
+                .network
+                .remove(&from)
+                .ok_or_else(|| format!("QDU {} does not exist in the network.", from))?;
+            moved.push((to, tensor));
+        }
+        self.network.extend(moved);
+
+        for tensor in self.network.values_mut() {
+            if tensor
+                .bonds
+                .keys()
+                .any(|neighbor| relabel.contains_key(neighbor))
+            {
+                tensor.bonds = tensor
+                    .bonds
+                    .drain()
+                    .map(|(neighbor, bond)| (*relabel.get(&neighbor).unwrap_or(&neighbor), bond))
+                    .collect();
+            }
+        }
 
         Ok(())
     }
@@ -220,29 +407,51 @@ impl GeometricPotentialityState {
         let control_state = self.network.get(&control).unwrap().core_state;
         let target_state = self.network.get(&target).unwrap().core_state;
 
-        // 3. Create the initial Bond Tensor (a 2x2 matrix flattened into a Vec of length 4).
+        // 3. Build the Bond Tensor (a 2x2 matrix flattened to length 4).
         // This represents the joint probability space of just these two adjacent nodes.
         // T_{ij} = Control_{i} * Target_{j}
-        let mut bond_tensor = vec![Complex::new(0.0, 0.0); 4];
-        bond_tensor[0] = control_state[0] * target_state[0]; // |00>
-        bond_tensor[1] = control_state[0] * target_state[1]; // |01>
-        bond_tensor[2] = control_state[1] * target_state[0]; // |10>
-        bond_tensor[3] = control_state[1] * target_state[1]; // |11>
-
-        // 4. Update both LocalTensors to hold a reference to this shared bond
-        if let Some(c_tensor) = self.network.get_mut(&control) {
-            c_tensor.bonds.insert(target, bond_tensor.clone());
-        }
-
-        if let Some(t_tensor) = self.network.get_mut(&target) {
-            // Depending on the tensor network definition, the target might store the transposed bond,
-            // but for simplicity in this baseline, they share the identical state map.
-            t_tensor.bonds.insert(control, bond_tensor);
+        let bond_tensor = [
+            control_state[0] * target_state[0], // |00>
+            control_state[0] * target_state[1], // |01>
+            control_state[1] * target_state[0], // |10>
+            control_state[1] * target_state[1], // |11>
+        ];
+
+        // 4. Update both LocalTensors to hold a copy of this shared bond. Re-bonding an
+        // existing pair overwrites its buffers in place; only new bonds allocate.
+        // Depending on the tensor network definition, the target might store the transposed
+        // bond, but for simplicity in this baseline, they share the identical state map.
+        for (node, neighbor) in [(control, target), (target, control)] {
+            if let Some(tensor) = self.network.get_mut(&node) {
+                let bond = tensor.bonds.entry(neighbor).or_default();
+                bond.clear();
+                bond.extend_from_slice(&bond_tensor);
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the amplitude of the qualities `assignment` (`(node, quality)` pairs), each
+    /// node's local state taken normalized. Nodes not listed are traced out, so this is
+    /// the amplitude of the listed nodes' product state.
+    pub fn amplitude_of(&self, assignment: &[(u64, u8)]) -> Result<Complex<f64>, String> {
+        let mut amplitude = Complex::new(1.0, 0.0);
+        for &(node, quality) in assignment {
+            let tensor = self
+                .network
+                .get(&node)
+                .ok_or_else(|| format!("QDU {} does not exist in the network.", node))?;
+            let [a, b] = tensor.core_state;
+            let norm = (a.norm_sqr() + b.norm_sqr()).sqrt();
+            if norm == 0.0 {
+                return Err(format!("QDU {} has zero norm.", node));
+            }
+            amplitude *= tensor.core_state[usize::from(quality)] / norm;
+        }
+        Ok(amplitude)
+    }
+
     /// Approximates the global norm of the tensor network.
     /// For locally unitary states, this ensures the system hasn't leaked probability.
     pub fn global_norm_sq(&self) -> f64 {
@@ -255,5 +464,60 @@ impl GeometricPotentialityState {
     }
 }
 
+/// Returns the deterministic draw in `[0, 1)` used to stabilize `node` with the
+/// (unnormalized) quality weights, one per basis level (`[prob_0, prob_1]` for a
+/// binary node).
+///
+/// We hash the exact bit patterns of the weights to generate a strictly deterministic
+/// pseudo-random number. The hash is a fixed-key SipHash over little-endian bytes, so
+/// the draw is the same on every platform and toolchain.
+pub(crate) fn stabilization_draw(
+    weights: &[f64],
+    seed: StabilizationSeed,
+    salt: u64,
+    node: u64,
+) -> f64 {
+    use super::siphash::StableHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = StableHasher::new();
+    match seed {
+        StabilizationSeed::StateDerived => {
+            for weight in weights {
+                weight.to_bits().hash(&mut hasher);
+            }
+        }
+        StabilizationSeed::Mixed(value) => {
+            for weight in weights {
+                weight.to_bits().hash(&mut hasher);
+            }
+            value.hash(&mut hasher);
+        }
+        // The state is ignored: the draw depends only on the seed and the node
+        StabilizationSeed::Fixed(value) => {
+            value.hash(&mut hasher);
+            node.hash(&mut hasher);
+        }
+    }
+    if salt != 0 {
+        salt.hash(&mut hasher);
+    }
+    let seed = hasher.finish();
+
+    // Generate a deterministic float between 0.0 and 1.0
+    (seed % 1000000) as f64 / 1000000.0
+}
+
+/// Returns `exp(iφP) = cos(φ)·I + i·sin(φ)·P` for an involutory axis `P`.
+pub(crate) fn axis_rotation(axis: &[[Complex<f64>; 2]; 2], phi: f64) -> [[Complex<f64>; 2]; 2] {
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let i_sin = Complex::new(0.0, sin_phi);
+    let identity_part = Complex::new(cos_phi, 0.0);
+    [
+        [identity_part + i_sin * axis[0][0], i_sin * axis[0][1]],
+        [i_sin * axis[1][0], identity_part + i_sin * axis[1][1]],
+    ]
+}
+
 /// Patch for state migration
 pub type PotentialityState = GeometricPotentialityState;
@@ -0,0 +1,166 @@
+// src/engine.rs
+
+//! Low-level access to the simulation engine, for building custom executors.
+//!
+//! Requires the `engine` feature. [`Engine`] is the engine behind [`Simulator`] and
+//! [`OnqVm`](crate::vm::OnqVm), driven one operation at a time: initialize it from a
+//! set of QDUs, apply operations, read the state and stabilize QDUs whenever the
+//! executor decides to. Outcomes accumulate in a [`SimulationResult`] exactly as they
+//! would in a simulator run.
+//!
+//! # Examples
+//! ```
+//! # use onq::engine::Engine;
+//! # use onq::{Operation, QduId};
+//! # use std::collections::HashSet;
+//! let (q0, q1) = (QduId(0), QduId(1));
+//! let mut engine = Engine::new(&HashSet::from([q0, q1])).unwrap();
+//! engine
+//!     .apply(&Operation::InteractionPattern {
+//!         target: q0,
+//!         pattern_id: "QualityFlip".to_string(),
+//!     })
+//!     .unwrap();
+//! assert_eq!(engine.marginals()[&q0], [0.0, 1.0]);
+//!
+//! // The executor decides what to do with the outcome
+//! if engine.stabilize(&[q0]).unwrap() == [1] {
+//!     engine
+//!         .apply(&Operation::InteractionPattern {
+//!             target: q1,
+//!             pattern_id: "QualityFlip".to_string(),
+//!         })
+//!         .unwrap();
+//! }
+//! assert_eq!(engine.stabilize(&[q1]).unwrap(), [1]);
+//! assert_eq!(engine.into_result().all_stable_outcomes().len(), 2);
+//! ```
+
+use crate::core::{OnqError, PotentialityState, QduId};
+use crate::operations::Operation;
+use crate::simulation::engine::SimulationEngine;
+use crate::simulation::{SimulationResult, Simulator, SimulatorConfig};
+use std::collections::{BTreeMap, HashSet};
+
+/// A simulation engine stepped directly by the caller.
+pub struct Engine {
+    inner: SimulationEngine,
+    result: SimulationResult,
+}
+
+impl Engine {
+    /// Creates an engine holding `qdus` in their `|0>` state, mapped onto IVM nodes in
+    /// ascending QDU order, with the default [`SimulatorConfig`].
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `qdus` is empty and
+    /// `OnqError::CapacityExceeded` if it holds more than the 64 IVM nodes.
+    pub fn new(qdus: &HashSet<QduId>) -> Result<Self, OnqError> {
+        Ok(Self::from_inner(SimulationEngine::init(qdus)?))
+    }
+
+    /// Creates an engine with `order[i]` mapped to IVM node `i`, so that QDUs meant to
+    /// interact can be placed on adjacent nodes.
+    ///
+    /// # Errors
+    /// As [`Engine::new`], and `OnqError::InvalidOperation` if a QDU appears twice.
+    pub fn with_order(order: &[QduId]) -> Result<Self, OnqError> {
+        Ok(Self::from_inner(SimulationEngine::init_with_order(order)?))
+    }
+
+    fn from_inner(inner: SimulationEngine) -> Self {
+        let mut engine = Self {
+            inner,
+            result: SimulationResult::new(),
+        };
+        engine.set_config(SimulatorConfig::default());
+        engine
+    }
+
+    /// Sets the stabilization rules, tolerances and validation settings used from now on.
+    pub fn set_config(&mut self, config: SimulatorConfig) {
+        self.inner.set_config(config);
+        self.result.set_semantics(config.semantics());
+    }
+
+    /// Replaces the state, e.g. with one prepared by hand or taken from a checkpoint.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if the state lacks the node of a QDU and
+    /// `OnqError::Incoherence` if a QDU's local state has zero norm.
+    pub fn set_state(&mut self, state: PotentialityState) -> Result<(), OnqError> {
+        self.inner.set_state(state)
+    }
+
+    /// Applies `op` as a simulator would: `Stabilize` resolves its targets into the
+    /// result, `Snapshot` records the state and anything else evolves it.
+    ///
+    /// # Errors
+    /// Returns the error the operation raises, e.g. `OnqError::ReferenceViolation` for
+    /// a QDU the engine does not hold.
+    pub fn apply(&mut self, op: &Operation) -> Result<(), OnqError> {
+        Simulator::dispatch_operation(&mut self.inner, op, &mut self.result)
+    }
+
+    /// Stabilizes `targets`, returning their resolved qualities in the same order.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` for a QDU the engine does not hold, or the
+    /// error stabilization raises.
+    pub fn stabilize(&mut self, targets: &[QduId]) -> Result<Vec<u64>, OnqError> {
+        self.inner.stabilize(targets, &mut self.result)?;
+        targets
+            .iter()
+            .map(|target| {
+                self.result
+                    .get_stable_state(target)
+                    .and_then(|state| state.get_resolved_value())
+                    .ok_or_else(|| OnqError::SimulationError {
+                        message: format!("Stabilization recorded no outcome for {}", target),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> &PotentialityState {
+        self.inner.get_state()
+    }
+
+    /// Returns the IVM node holding each QDU.
+    pub fn nodes(&self) -> BTreeMap<QduId, u64> {
+        self.inner.qdu_nodes()
+    }
+
+    /// Returns the quality probabilities `[P(0), P(1)]` of every binary QDU.
+    pub fn marginals(&self) -> BTreeMap<QduId, [f64; 2]> {
+        self.inner.marginals()
+    }
+
+    /// Returns the probability of the joint qualities `outcome`.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` for a QDU the engine does not hold.
+    pub fn probability_of(&self, outcome: &[(QduId, u8)]) -> Result<f64, OnqError> {
+        self.inner.probability_of_outcome(outcome)
+    }
+
+    /// Runs the state checks selected by the configured validation mode.
+    pub fn validate(&self) -> Result<(), OnqError> {
+        self.inner.validate()
+    }
+
+    /// Returns the outcomes recorded so far.
+    pub fn result(&self) -> &SimulationResult {
+        &self.result
+    }
+
+    /// Finishes, returning the recorded outcomes together with the accumulated global
+    /// phase and renormalizations.
+    pub fn into_result(mut self) -> SimulationResult {
+        self.result.set_global_phase(self.inner.global_phase());
+        self.result
+            .record_renormalizations(self.inner.renormalizations());
+        self.result
+    }
+}
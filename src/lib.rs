@@ -25,11 +25,29 @@
 //!   of quantum operations and `CircuitBuilder` for easy construction.
 //! * **Validation (`onq::validation`):** Offers functions to check state validity
 //!   (normalization, phase coherence interpretation).
+//! * **Library (`onq::library`):** Ready-made preparations of structured states, such as
+//!   graph-state analogs.
+//! * **Analysis (`onq::analysis`):** Tools for inspecting circuits beyond running them,
+//!   such as exact stabilizer-analog tracking (`StabilizerTableau`).
+//! * **Pipelines (`onq::pipeline`):** Structured experiments (`Pipeline`) composed of
+//!   preparation, evolution, stabilization and analysis stages.
 //! * **ONQ Virtual Machine (`onq::vm`):** An interpreter (`OnqVm`) that executes
 //!   `Program`s containing mixed sequences of `Instruction`s (quantum ops, classical ops,
 //!   control flow based on stabilization results).
 //! * **Simulation Engine (`onq::simulation::engine` - internal):** Handles the underlying
-//!   state vector evolution and stabilization logic.
+//!   state vector evolution and stabilization logic; exposed as `onq::engine` with the
+//!   `engine` feature.
+//!
+//! ## Optional Features
+//!
+//! * `serde`: `Serialize`/`Deserialize` for `Circuit`, `Operation`, `LockType`, VM
+//!   `Program`s, `Pipeline`s, `SimulatorConfig` and the types they contain, so circuits,
+//!   programs, whole experiments and the configuration a run was made under can be
+//!   stored as JSON/YAML fixtures.
+//! * `engine`: The `onq::engine` module, a public facade (`Engine`) over the simulation
+//!   engine for custom executors stepping operations and stabilizations themselves.
+//!
+//! The [`prelude`] re-exports the most commonly used types: `use onq::prelude::*;`.
 //!
 //! ## Interpretation & Differences from QM
 //!
@@ -45,9 +63,15 @@
 //!
 //! **See the project README for detailed explanations of concepts, interpretations, and limitations.**
 
+pub mod analysis;
 pub mod circuits;
 pub mod core;
+#[cfg(feature = "engine")]
+pub mod engine;
+pub mod library;
 pub mod operations;
+pub mod pipeline;
+pub mod prelude;
 pub mod simulation;
 pub mod topology;
 pub mod validation;
@@ -55,9 +79,9 @@ pub mod vm;
 
 // Re-export the most common types for easier top-level use
 pub use circuits::{Circuit, CircuitBuilder};
-pub use core::{OnqError, PotentialityState, QduId, StableState}; // Removed Qdu, ReferenceFrame unless needed publicly
-pub use operations::Operation;
-pub use simulation::{SimulationResult, Simulator};
+pub use core::{OnqError, PotentialityState, QduId, ReferenceFrame, StableState}; // Removed Qdu unless needed publicly
+pub use operations::{Operation, PauliAxis, Quality};
+pub use simulation::{ShotResults, SimulationResult, Simulator};
 pub use validation::{
     calculate_global_phase_coherence, check_normalization, check_phase_coherence, validate_state,
 };
@@ -95,6 +119,7 @@ pub use vm::{Instruction, Program, ProgramBuilder, program::LockType};
 ///
 ///         // Analysis based on current interpretation:
 ///         // Input state |0> -> Superposition -> (1/sqrt(2))(|0> + |1>)
+///         // Under ScoringMode::Full (the default scores amplitudes only):
 ///         // Scores: S(0) = C_A(0)*C_B(0)*amp(0)^2 = 1.0 * 1.0 * 0.5 = 0.5
 ///         //         S(1) = C_A(1)*C_B(1)*amp(1)^2 = 1.0 * 0.5 * 0.5 = 0.25
 ///         // Outcome |0> is favored due to higher C_B score (lower Hamming weight).
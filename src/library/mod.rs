@@ -0,0 +1,184 @@
+// src/library/mod.rs
+
+//! Ready-made preparations of structured, highly integrated states.
+//!
+//! * [`Graph`] and [`graph_state`]: Cluster/graph-state analogs, with
+//!   [`graph_state_stabilizers`], [`verify_graph_state`], and [`local_complement`]
+//!   for studying how integration structures transform.
+
+use crate::analysis::StabilizerTableau;
+use crate::circuits::{Circuit, CircuitBuilder};
+use crate::core::{OnqError, QduId};
+use crate::operations::{Operation, PauliAxis};
+
+/// A simple undirected graph over QDUs describing an integration structure.
+///
+/// # Examples
+/// ```
+/// # use onq::QduId;
+/// # use onq::library::Graph;
+/// let (a, b, c) = (QduId(0), QduId(1), QduId(2));
+/// let path = Graph::new(vec![a, b, c], vec![(a, b), (b, c)]).unwrap();
+/// assert_eq!(path.neighbors(b), vec![a, c]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    vertices: Vec<QduId>,
+    edges: Vec<(QduId, QduId)>,
+}
+
+impl Graph {
+    /// Creates a graph from its vertices and undirected edges.
+    /// Duplicate vertices and edges (in either orientation) are merged.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if an edge is a self-loop or
+    /// references a QDU that is not a vertex.
+    pub fn new(vertices: Vec<QduId>, edges: Vec<(QduId, QduId)>) -> Result<Self, OnqError> {
+        let mut graph = Graph {
+            vertices: Vec::with_capacity(vertices.len()),
+            edges: Vec::with_capacity(edges.len()),
+        };
+        for v in vertices {
+            if !graph.vertices.contains(&v) {
+                graph.vertices.push(v);
+            }
+        }
+        for (a, b) in edges {
+            if a == b || !graph.vertices.contains(&a) || !graph.vertices.contains(&b) {
+                return Err(OnqError::ReferenceViolation {
+                    message: format!("Edge ({}, {}) is not a valid edge of the graph", a, b),
+                });
+            }
+            if !graph.has_edge(a, b) {
+                graph.edges.push((a, b));
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Returns the vertices in insertion order.
+    pub fn vertices(&self) -> &[QduId] {
+        &self.vertices
+    }
+
+    /// Returns the edges in insertion order.
+    pub fn edges(&self) -> &[(QduId, QduId)] {
+        &self.edges
+    }
+
+    /// Returns `true` if `a` and `b` are joined by an edge.
+    pub fn has_edge(&self, a: QduId, b: QduId) -> bool {
+        self.edges
+            .iter()
+            .any(|&(u, v)| (u, v) == (a, b) || (u, v) == (b, a))
+    }
+
+    /// Returns the neighbors of `v`, in vertex order.
+    pub fn neighbors(&self, v: QduId) -> Vec<QduId> {
+        self.vertices
+            .iter()
+            .copied()
+            .filter(|&u| self.has_edge(u, v))
+            .collect()
+    }
+}
+
+/// Builds the circuit preparing the graph-state analog of `graph`:
+/// `Superposition` on every vertex, then a controlled `PhaseIntroduce` per edge.
+pub fn graph_state(graph: &Graph) -> Circuit {
+    let mut builder = CircuitBuilder::new().add_op(Operation::BroadcastPattern {
+        targets: graph.vertices.clone(),
+        pattern_id: "Superposition".to_string(),
+    });
+    for &(a, b) in &graph.edges {
+        builder = builder.add_op(Operation::ControlledInteraction {
+            control: a,
+            target: b,
+            pattern_id: "PhaseIntroduce".to_string(),
+        });
+    }
+    builder.build()
+}
+
+/// Returns the stabilizer-analog generators of the graph state, one per vertex `v`:
+/// `X` on `v` and `Z` on each of its neighbors.
+pub fn graph_state_stabilizers(graph: &Graph) -> Vec<Vec<(QduId, PauliAxis)>> {
+    graph
+        .vertices
+        .iter()
+        .map(|&v| {
+            std::iter::once((v, PauliAxis::X))
+                .chain(graph.neighbors(v).into_iter().map(|u| (u, PauliAxis::Z)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Checks whether `circuit`, run from |0...0>, prepares the graph state of `graph`,
+/// i.e. whether every generator from [`graph_state_stabilizers`] stabilizes its output.
+///
+/// The check is exact and symbolic (see [`StabilizerTableau`]), so it verifies the
+/// integration structure independently of the localized simulation engine.
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` if the circuit uses non-Clifford-analog operations,
+/// or `OnqError::ReferenceViolation` if it acts on QDUs outside the graph.
+///
+/// # Examples
+/// ```
+/// # use onq::QduId;
+/// # use onq::library::{Graph, graph_state, verify_graph_state};
+/// let reg: Vec<QduId> = (0..3).map(QduId).collect();
+/// let line = Graph::new(reg.clone(), vec![(reg[0], reg[1]), (reg[1], reg[2])]).unwrap();
+/// assert!(verify_graph_state(&graph_state(&line), &line).unwrap());
+/// ```
+pub fn verify_graph_state(circuit: &Circuit, graph: &Graph) -> Result<bool, OnqError> {
+    if let Some(stray) = circuit.qdus().iter().find(|q| !graph.vertices.contains(q)) {
+        return Err(OnqError::ReferenceViolation {
+            message: format!("{} is acted upon but is not a vertex of the graph", stray),
+        });
+    }
+
+    let mut tableau = StabilizerTableau::new(&graph.vertices);
+    for op in circuit.operations() {
+        tableau.apply(op)?;
+    }
+    for generator in graph_state_stabilizers(graph) {
+        if !tableau.stabilizes(&generator)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the local complement of `graph` at `v`: edges among the neighbors of `v`
+/// are toggled. Graph states related by local complementation are equivalent up to
+/// single-QDU operations: `SqrtFlip` on `v` and `HalfPhase_Inv` on each neighbor.
+///
+/// # Errors
+/// Returns `OnqError::ReferenceViolation` if `v` is not a vertex of the graph.
+pub fn local_complement(graph: &Graph, v: QduId) -> Result<Graph, OnqError> {
+    if !graph.vertices.contains(&v) {
+        return Err(OnqError::ReferenceViolation {
+            message: format!("{} is not a vertex of the graph", v),
+        });
+    }
+
+    let neighbors = graph.neighbors(v);
+    let mut edges: Vec<(QduId, QduId)> = graph.edges.clone();
+    for (i, &a) in neighbors.iter().enumerate() {
+        for &b in &neighbors[i + 1..] {
+            match edges
+                .iter()
+                .position(|&(u, w)| (u, w) == (a, b) || (u, w) == (b, a))
+            {
+                Some(index) => {
+                    edges.remove(index);
+                }
+                None => edges.push((a, b)),
+            }
+        }
+    }
+    Graph::new(graph.vertices.clone(), edges)
+}
@@ -8,9 +8,13 @@
 //! within a simulation, according to the necessary consequences outlined
 //! in the framework.
 
+pub mod patterns;
+pub use patterns::PatternRegistry;
+
 // Import necessary types from the core module
-use crate::core::QduId;
+use crate::core::{OnqError, QduId};
 use crate::vm::program::LockType;
+use num_complex::Complex;
 /// Represents a defined operation within onq framework.
 ///
 /// Operations are derived from principles like:
@@ -27,6 +31,7 @@ use crate::vm::program::LockType;
 ///
 /// These operations act upon `PotentialityState` within the simulation engine.
 #[derive(Debug, Clone, PartialEq)] // Using PartialEq for simplicity; f64 comparison needs care in practice.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operation {
     /// Represents applying a phase shift to a single QDU.
     /// Derived from the inherent phase component `e^(iθ)` in
@@ -60,6 +65,29 @@ pub enum Operation {
         // Future: May include parameters specific to the pattern.
     },
 
+    /// Applies the same single-QDU interaction pattern to every QDU in `targets`.
+    /// Equivalent to one `InteractionPattern` per target, in order, but kept as a
+    /// single circuit entry since the targets are transformed independently.
+    ///
+    /// Analogy: A layer of identical single-qubit gates, e.g. H on every input qubit.
+    BroadcastPattern {
+        /// The QDUs undergoing the transformation.
+        targets: Vec<QduId>,
+        /// Identifier for the transformation pattern (`P_op`) applied to each target.
+        pattern_id: String,
+    },
+
+    /// Applies the same phase shift to every QDU in `targets`.
+    /// Equivalent to one `PhaseShift` per target.
+    ///
+    /// Analogy: A layer of identical Rz or Phase gates.
+    BroadcastPhaseShift {
+        /// The QDUs whose potentiality state phase is modified.
+        targets: Vec<QduId>,
+        /// The phase angle `theta` (in radians) applied to each target.
+        theta: f64,
+    },
+
     /// Represents a controlled interaction between two QDUs.
     /// Derived from Interactive Necessity, where frames/distinctions influence
     /// each other, and Integration requirements that link QDUs within a frame.
@@ -80,6 +108,30 @@ pub enum Operation {
         pattern_id: String,
     },
 
+    /// Applies the multi-QDU phase interaction `exp(iθ·P)`, where `P = P₁ ⊗ P₂ ⊗ …` is a
+    /// product of Pauli-analog axes, one per QDU in `terms` (e.g. ZZ or XX couplings).
+    /// Consecutive QDUs in `terms` must be adjacent in the IVM, as for other
+    /// multi-QDU interactions.
+    ///
+    /// Analogy: A single evolution step under an interaction Hamiltonian term,
+    /// like Rzz/Rxx gates in quantum computing.
+    PauliProduct {
+        /// The QDUs involved, each paired with the axis applied to it. QDUs must be distinct.
+        terms: Vec<(QduId, PauliAxis)>,
+        /// The interaction angle `theta` (in radians).
+        theta: f64,
+    },
+
+    /// Relabels QDUs within the global state: the potentiality held by the first QDU of
+    /// each `(from, to)` pair is moved to the second. The pairs must form a permutation,
+    /// i.e. every QDU appears exactly once as a source and exactly once as a destination.
+    ///
+    /// Analogy: A qubit permutation (a network of SWAPs), as inserted by routing passes.
+    Permute {
+        /// The `(from, to)` relabeling pairs.
+        mapping: Vec<(QduId, QduId)>,
+    },
+
     /// Represents establishing, modifying, or breaking a specific phase relationship
     /// or structural lock between two QDUs.
     /// Derived from Reference Structure, Frame Interaction,
@@ -95,10 +147,85 @@ pub enum Operation {
         qdu2: QduId,
         /// The target integrated/entangled state type for the lock.
         lock_type: LockType,
-        /// If true, project onto lock state; if false, currently no-op.
+        /// If true, project onto lock state and permit interactions between the two
+        /// QDUs across reference frames; if false, release that permission.
         establish: bool,
     },
 
+    /// Applies an explicit 2x2 unitary matrix to a single QDU.
+    /// Produced by optimization passes that fuse runs of single-QDU operations, and
+    /// usable directly for transformations that have no named interaction pattern.
+    ///
+    /// Analogy: An arbitrary single-qubit unitary gate (U3).
+    MatrixPattern {
+        /// The target QDU undergoing the transformation.
+        target: QduId,
+        /// The row-major unitary matrix applied to the QDU's `[Quality0, Quality1]` amplitudes.
+        matrix: [[Complex<f64>; 2]; 2],
+    },
+
+    /// Applies an explicit `d`x`d` unitary matrix to a single QDU of dimension `d`
+    /// (see [`Circuit::set_dimension`](crate::Circuit::set_dimension)). This is the
+    /// only transformation qudits (`d > 2`) accept; on a binary QDU a 2x2 matrix acts
+    /// like `MatrixPattern`.
+    ///
+    /// Analogy: An arbitrary single-qudit unitary gate.
+    QuditPattern {
+        /// The target QDU undergoing the transformation.
+        target: QduId,
+        /// The row-major unitary matrix applied to the QDU's `d` quality amplitudes.
+        matrix: Vec<Vec<Complex<f64>>>,
+    },
+
+    /// Projects a single QDU onto one of its basis qualities and renormalizes.
+    /// This is a non-unitary filter: unlike `Stabilize`, the outcome is chosen by the
+    /// caller rather than resolved, and it fails if the QDU has no potentiality for it.
+    ///
+    /// Analogy: Post-selection onto |0> or |1>, the single-qubit counterpart of the
+    /// projective `RelationalLock`.
+    Project {
+        /// The QDU being filtered.
+        target: QduId,
+        /// The basis quality the QDU is projected onto.
+        onto: Quality,
+    },
+
+    /// Relaxes a QDU toward its baseline quality: a fraction `rate` of the Quality1
+    /// potentiality decays into Quality0. This is a non-unitary process modelling
+    /// gradual loss of stability; applying it repeatedly drives the QDU to Quality0.
+    ///
+    /// Analogy: The amplitude-damping channel with damping probability `rate`. Resulting
+    /// quality weights match the channel exactly; since the engine holds pure local
+    /// states, the phase of each amplitude is retained rather than mixed.
+    Relax {
+        /// The QDU undergoing relaxation.
+        target: QduId,
+        /// The decay fraction, in `[0, 1]`.
+        rate: f64,
+    },
+
+    /// Leaves the `targets` idle for an abstract duration of `ticks`.
+    /// The state is unchanged; the operation exists so circuits can express timing
+    /// explicitly, as a hook for future timing and noise models.
+    ///
+    /// Analogy: An idle/delay instruction in a scheduled quantum circuit.
+    Delay {
+        /// The QDUs left idle.
+        targets: Vec<QduId>,
+        /// The idle duration, in abstract ticks.
+        ticks: u64,
+    },
+
+    /// Records the state of the circuit's QDUs under `label` in the
+    /// [`SimulationResult`](crate::simulation::SimulationResult), so intermediate
+    /// points of a circuit can be inspected after the run. The state is unchanged.
+    ///
+    /// Analogy: A simulator snapshot instruction; it has no physical counterpart.
+    Snapshot {
+        /// The name the captured state is recorded under.
+        label: String,
+    },
+
     /// Represents the Stabilization Protocol (SP).
     /// This operation instructs the simulation engine to attempt resolution
     /// of the `PotentialityState` of the `targets` into a `StableState`.
@@ -123,6 +250,50 @@ pub enum Operation {
     // - No-op or Delay operations if timing/sequence needs explicit pauses.
 }
 
+/// One of the two basis qualities of a QDU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quality {
+    /// The baseline quality (|0> analog).
+    Quality0,
+    /// The flipped quality (|1> analog).
+    Quality1,
+}
+
+impl Quality {
+    /// Returns the basis index of this quality (0 or 1).
+    pub fn index(&self) -> usize {
+        match self {
+            Quality::Quality0 => 0,
+            Quality::Quality1 => 1,
+        }
+    }
+}
+
+/// A Pauli-analog axis used by [`Operation::PauliProduct`].
+/// Each axis corresponds to a derived single-QDU interaction pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PauliAxis {
+    /// Quality flip axis (X analog).
+    X,
+    /// Combined flip and phase axis (Y analog).
+    Y,
+    /// Phase axis (Z analog).
+    Z,
+}
+
+impl PauliAxis {
+    /// Returns the interaction pattern ID whose matrix is this axis.
+    pub fn pattern_id(&self) -> &'static str {
+        match self {
+            PauliAxis::X => "QualityFlip",
+            PauliAxis::Y => "QualitativeY",
+            PauliAxis::Z => "PhaseIntroduce",
+        }
+    }
+}
+
 impl Operation {
     /// Returns a list of all QDU IDs directly mentioned in the operation's parameters.
     ///
@@ -152,13 +323,226 @@ impl Operation {
         match self {
             Operation::PhaseShift { target, .. } => vec![*target],
             Operation::InteractionPattern { target, .. } => vec![*target],
+            Operation::MatrixPattern { target, .. } => vec![*target],
+            Operation::QuditPattern { target, .. } => vec![*target],
+            Operation::Project { target, .. } => vec![*target],
+            Operation::Relax { target, .. } => vec![*target],
+            Operation::BroadcastPattern { targets, .. } => targets.clone(),
+            Operation::BroadcastPhaseShift { targets, .. } => targets.clone(),
             Operation::ControlledInteraction { control, target, .. } => vec![*control, *target],
+            Operation::PauliProduct { terms, .. } => terms.iter().map(|(q, _)| *q).collect(),
+            Operation::Permute { mapping } => mapping.iter().map(|(from, _)| *from).collect(),
             Operation::RelationalLock { qdu1, qdu2, .. } => vec![*qdu1, *qdu2],
+            Operation::Delay { targets, .. } => targets.clone(),
+            Operation::Snapshot { .. } => Vec::new(),
             Operation::Stabilize { targets } => targets.clone(),
         }
     }
 
+    /// Returns the name of the operation's variant, e.g. `"ControlledInteraction"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Operation::PhaseShift { .. } => "PhaseShift",
+            Operation::InteractionPattern { .. } => "InteractionPattern",
+            Operation::MatrixPattern { .. } => "MatrixPattern",
+            Operation::QuditPattern { .. } => "QuditPattern",
+            Operation::Project { .. } => "Project",
+            Operation::Relax { .. } => "Relax",
+            Operation::BroadcastPattern { .. } => "BroadcastPattern",
+            Operation::BroadcastPhaseShift { .. } => "BroadcastPhaseShift",
+            Operation::ControlledInteraction { .. } => "ControlledInteraction",
+            Operation::PauliProduct { .. } => "PauliProduct",
+            Operation::Permute { .. } => "Permute",
+            Operation::RelationalLock { .. } => "RelationalLock",
+            Operation::Delay { .. } => "Delay",
+            Operation::Snapshot { .. } => "Snapshot",
+            Operation::Stabilize { .. } => "Stabilize",
+        }
+    }
+
+    /// Returns a copy of this operation with its angle parameter replaced by `theta`,
+    /// or `None` if the operation has no angle (only `PhaseShift`, `BroadcastPhaseShift`
+    /// and `PauliProduct` do).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{Operation, QduId};
+    /// let template = Operation::PhaseShift { target: QduId(0), theta: 0.0 };
+    /// assert_eq!(
+    ///     template.with_angle(0.25),
+    ///     Some(Operation::PhaseShift { target: QduId(0), theta: 0.25 })
+    /// );
+    /// assert_eq!(Operation::Stabilize { targets: vec![QduId(0)] }.with_angle(0.25), None);
+    /// ```
+    pub fn with_angle(&self, theta: f64) -> Option<Operation> {
+        let mut op = self.clone();
+        match &mut op {
+            Operation::PhaseShift { theta: angle, .. }
+            | Operation::BroadcastPhaseShift { theta: angle, .. }
+            | Operation::PauliProduct { theta: angle, .. } => *angle = theta,
+            _ => return None,
+        }
+        Some(op)
+    }
+
+    /// Returns the operation undoing this one, so that applying `self` then
+    /// `self.inverse()` leaves the potentiality state unchanged.
+    ///
+    /// Interaction patterns are inverted through [`PatternRegistry::inverse`], angles are
+    /// negated, and permutations are reversed.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` for non-invertible operations (`Stabilize`,
+    /// `RelationalLock`, `Project`, `Relax`) and for patterns with no registered inverse.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{Operation, QduId};
+    /// let s = Operation::InteractionPattern { target: QduId(0), pattern_id: "HalfPhase".to_string() };
+    /// let s_inv = Operation::InteractionPattern { target: QduId(0), pattern_id: "HalfPhase_Inv".to_string() };
+    /// assert_eq!(s.inverse().unwrap(), s_inv);
+    /// assert!(Operation::Stabilize { targets: vec![QduId(0)] }.inverse().is_err());
+    /// ```
+    pub fn inverse(&self) -> Result<Operation, OnqError> {
+        let invert_pattern = |pattern_id: &str| {
+            PatternRegistry::builtin()
+                .inverse(pattern_id)
+                .map(str::to_string)
+                .ok_or_else(|| OnqError::InvalidOperation {
+                    message: format!("Interaction Pattern '{}' has no known inverse", pattern_id),
+                })
+        };
+        match self {
+            Operation::PhaseShift { target, theta } => Ok(Operation::PhaseShift {
+                target: *target,
+                theta: -theta,
+            }),
+            Operation::InteractionPattern { target, pattern_id } => {
+                Ok(Operation::InteractionPattern {
+                    target: *target,
+                    pattern_id: invert_pattern(pattern_id)?,
+                })
+            }
+            Operation::MatrixPattern { target, matrix } => Ok(Operation::MatrixPattern {
+                target: *target,
+                matrix: adjoint(matrix),
+            }),
+            Operation::QuditPattern { target, matrix } => Ok(Operation::QuditPattern {
+                target: *target,
+                matrix: (0..matrix.len())
+                    .map(|c| matrix.iter().map(|row| row[c].conj()).collect())
+                    .collect(),
+            }),
+            Operation::BroadcastPattern { targets, pattern_id } => Ok(Operation::BroadcastPattern {
+                targets: targets.clone(),
+                pattern_id: invert_pattern(pattern_id)?,
+            }),
+            Operation::BroadcastPhaseShift { targets, theta } => {
+                Ok(Operation::BroadcastPhaseShift {
+                    targets: targets.clone(),
+                    theta: -theta,
+                })
+            }
+            Operation::ControlledInteraction {
+                control,
+                target,
+                pattern_id,
+            } => Ok(Operation::ControlledInteraction {
+                control: *control,
+                target: *target,
+                pattern_id: invert_pattern(pattern_id)?,
+            }),
+            Operation::PauliProduct { terms, theta } => Ok(Operation::PauliProduct {
+                terms: terms.clone(),
+                theta: -theta,
+            }),
+            Operation::Permute { mapping } => Ok(Operation::Permute {
+                mapping: mapping.iter().map(|&(from, to)| (to, from)).collect(),
+            }),
+            Operation::Delay { .. } | Operation::Snapshot { .. } => Ok(self.clone()),
+            Operation::RelationalLock { .. }
+            | Operation::Project { .. }
+            | Operation::Relax { .. }
+            | Operation::Stabilize { .. } => Err(OnqError::InvalidOperation {
+                message: format!("Operation {:?} is not invertible", self),
+            }),
+        }
+    }
+
+    /// Returns a copy of this operation with every QDU replaced by `f(qdu)`, as used
+    /// when relabeling or routing circuits.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{Operation, QduId};
+    /// let cx = Operation::ControlledInteraction { control: QduId(0), target: QduId(1), pattern_id: "QualityFlip".to_string() };
+    /// let shifted = cx.map_qdus(|q| QduId(q.0 + 10));
+    /// assert_eq!(shifted.involved_qdus(), vec![QduId(10), QduId(11)]);
+    /// ```
+    pub fn map_qdus(&self, f: impl Fn(QduId) -> QduId) -> Operation {
+        let mut op = self.clone();
+        match &mut op {
+            Operation::PhaseShift { target, .. }
+            | Operation::InteractionPattern { target, .. }
+            | Operation::MatrixPattern { target, .. }
+            | Operation::QuditPattern { target, .. }
+            | Operation::Project { target, .. }
+            | Operation::Relax { target, .. } => *target = f(*target),
+            Operation::BroadcastPattern { targets, .. }
+            | Operation::BroadcastPhaseShift { targets, .. }
+            | Operation::Delay { targets, .. }
+            | Operation::Stabilize { targets } => {
+                targets.iter_mut().for_each(|q| *q = f(*q));
+            }
+            Operation::ControlledInteraction {
+                control, target, ..
+            } => {
+                *control = f(*control);
+                *target = f(*target);
+            }
+            Operation::PauliProduct { terms, .. } => {
+                terms.iter_mut().for_each(|(q, _)| *q = f(*q));
+            }
+            Operation::Permute { mapping } => {
+                mapping.iter_mut().for_each(|(from, to)| {
+                    *from = f(*from);
+                    *to = f(*to);
+                });
+            }
+            Operation::RelationalLock { qdu1, qdu2, .. } => {
+                *qdu1 = f(*qdu1);
+                *qdu2 = f(*qdu2);
+            }
+            Operation::Snapshot { .. } => {}
+        }
+        op
+    }
+
+    /// Returns the 2x2 matrix of a single-QDU unitary operation (`PhaseShift`,
+    /// `InteractionPattern` with a built-in pattern, or `MatrixPattern`), else `None`.
+    pub(crate) fn local_matrix(&self) -> Option<[[Complex<f64>; 2]; 2]> {
+        match self {
+            Operation::PhaseShift { theta, .. } => Some([
+                [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+                [Complex::new(0.0, 0.0), Complex::from_polar(1.0, *theta)],
+            ]),
+            Operation::InteractionPattern { pattern_id, .. } => {
+                PatternRegistry::builtin().matrix(pattern_id)
+            }
+            Operation::MatrixPattern { matrix, .. } => Some(*matrix),
+            _ => None,
+        }
+    }
+
     // Potential future methods:
     // - `validate(&self, context: &SimulationContext) -> Result<(), OnqError>`
     // - `required_frame_properties(&self) -> FrameProperties`
 }
+
+/// Returns the conjugate transpose of a 2x2 matrix.
+pub(crate) fn adjoint(matrix: &[[Complex<f64>; 2]; 2]) -> [[Complex<f64>; 2]; 2] {
+    [
+        [matrix[0][0].conj(), matrix[1][0].conj()],
+        [matrix[0][1].conj(), matrix[1][1].conj()],
+    ]
+}
@@ -0,0 +1,242 @@
+// src/operations/patterns.rs
+
+//! Registry of the derived single-QDU interaction patterns.
+//!
+//! Every `pattern_id` used by [`Operation::InteractionPattern`](super::Operation::InteractionPattern)
+//! and [`Operation::ControlledInteraction`](super::Operation::ControlledInteraction) is resolved
+//! to its 2x2 matrix through a [`PatternRegistry`].
+
+use num_complex::Complex;
+use num_traits::identities::Zero;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Maps interaction pattern IDs to their 2x2 transformation matrices.
+///
+/// # Examples
+/// ```
+/// # use onq::operations::PatternRegistry;
+/// let registry = PatternRegistry::builtin();
+/// assert!(registry.contains("Superposition"));
+/// assert_eq!(registry.suggest("Superpostion"), vec!["Superposition"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternRegistry {
+    patterns: HashMap<String, [[Complex<f64>; 2]; 2]>,
+}
+
+impl PatternRegistry {
+    /// Creates a registry containing the built-in derived patterns.
+    pub fn new() -> Self {
+        use std::f64::consts::{FRAC_1_SQRT_2, PI};
+        const PHI: f64 = 1.618_033_988_749_895;
+        let one = Complex::new(1.0, 0.0);
+        let i = Complex::i();
+        let exp_i_pi_4 = Complex::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+        let exp_neg_i_pi_4 = Complex::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let h = Complex::new(FRAC_1_SQRT_2, 0.0);
+        let (sin_phi, cos_phi) = (PI / PHI / 2.0).sin_cos();
+
+        let mut registry = Self {
+            patterns: HashMap::new(),
+        };
+        registry.register("Identity", [[one, Complex::zero()], [Complex::zero(), one]]);
+        registry.register(
+            "QualityFlip",
+            [[Complex::zero(), one], [one, Complex::zero()]],
+        );
+        registry.register(
+            "PhaseIntroduce",
+            [[one, Complex::zero()], [Complex::zero(), -one]],
+        );
+        registry.register("Superposition", [[h, h], [h, -h]]);
+        registry.register(
+            "PhiRotate",
+            [
+                [Complex::new(cos_phi, 0.0), Complex::new(-sin_phi, 0.0)],
+                [Complex::new(sin_phi, 0.0), Complex::new(cos_phi, 0.0)],
+            ],
+        );
+        registry.register(
+            "PhiXRotate",
+            [
+                [Complex::new(cos_phi, 0.0), -i * sin_phi],
+                [-i * sin_phi, Complex::new(cos_phi, 0.0)],
+            ],
+        );
+        registry.register(
+            "PhiRotate_Inv",
+            [
+                [Complex::new(cos_phi, 0.0), Complex::new(sin_phi, 0.0)],
+                [Complex::new(-sin_phi, 0.0), Complex::new(cos_phi, 0.0)],
+            ],
+        );
+        registry.register(
+            "PhiXRotate_Inv",
+            [
+                [Complex::new(cos_phi, 0.0), i * sin_phi],
+                [i * sin_phi, Complex::new(cos_phi, 0.0)],
+            ],
+        );
+        registry.register(
+            "SqrtFlip",
+            [
+                [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+                [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+            ],
+        );
+        registry.register(
+            "SqrtFlip_Inv",
+            [
+                [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
+                [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
+            ],
+        );
+        registry.register("HalfPhase", [[one, Complex::zero()], [Complex::zero(), i]]);
+        registry.register(
+            "QualitativeY",
+            [[Complex::zero(), -i], [i, Complex::zero()]],
+        );
+        registry.register(
+            "QuarterPhase",
+            [[one, Complex::zero()], [Complex::zero(), exp_i_pi_4]],
+        );
+        registry.register(
+            "HalfPhase_Inv",
+            [[one, Complex::zero()], [Complex::zero(), -i]],
+        );
+        registry.register(
+            "QuarterPhase_Inv",
+            [[one, Complex::zero()], [Complex::zero(), exp_neg_i_pi_4]],
+        );
+        registry
+    }
+
+    /// Returns the shared registry of built-in patterns used by the simulation engine.
+    pub fn builtin() -> &'static PatternRegistry {
+        static BUILTIN: OnceLock<PatternRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(PatternRegistry::new)
+    }
+
+    /// Registers (or replaces) a pattern under `pattern_id`.
+    pub fn register(&mut self, pattern_id: &str, matrix: [[Complex<f64>; 2]; 2]) {
+        self.patterns.insert(pattern_id.to_string(), matrix);
+    }
+
+    /// Returns the matrix registered under `pattern_id`, if any.
+    pub fn matrix(&self, pattern_id: &str) -> Option<[[Complex<f64>; 2]; 2]> {
+        self.patterns.get(pattern_id).copied()
+    }
+
+    /// Returns `true` if `pattern_id` is registered.
+    pub fn contains(&self, pattern_id: &str) -> bool {
+        self.patterns.contains_key(pattern_id)
+    }
+
+    /// Returns all registered pattern IDs, sorted alphabetically.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.patterns.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the ID of the registered pattern undoing `pattern_id`, i.e. the one whose
+    /// matrix is the adjoint of its matrix. Self-inverse patterns return their own ID.
+    /// `None` if `pattern_id` is unknown or no registered pattern is its inverse.
+    pub fn inverse(&self, pattern_id: &str) -> Option<&str> {
+        let matrix = self.matrix(pattern_id)?;
+        let adjoint = super::adjoint(&matrix);
+        let matches = |candidate: &[[Complex<f64>; 2]; 2]| {
+            (0..2).all(|r| (0..2).all(|c| (candidate[r][c] - adjoint[r][c]).norm() < 1e-12))
+        };
+        if matches(&matrix) {
+            return self
+                .patterns
+                .get_key_value(pattern_id)
+                .map(|(id, _)| id.as_str());
+        }
+        self.names()
+            .into_iter()
+            .find(|candidate| matches(&self.patterns[*candidate]))
+    }
+
+    /// Returns the registered pattern IDs closest to `name`, best match first.
+    ///
+    /// Candidates are ranked by case-insensitive edit distance and only those within
+    /// roughly a third of the name's length are returned, so unrelated names yield
+    /// no suggestions.
+    pub fn suggest(&self, name: &str) -> Vec<&str> {
+        let needle = name.to_lowercase();
+        let max_distance = (needle.chars().count() / 3).max(1);
+
+        let mut candidates: Vec<(usize, &str)> = self
+            .names()
+            .into_iter()
+            .map(|candidate| (edit_distance(&needle, &candidate.to_lowercase()), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        candidates.sort();
+        candidates
+            .into_iter()
+            .map(|(_, candidate)| candidate)
+            .collect()
+    }
+}
+
+impl Default for PatternRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("superposition", "superposition"), 0);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let registry = PatternRegistry::builtin();
+        // Case-only differences are the best possible match
+        assert_eq!(registry.suggest("qualityflip")[0], "QualityFlip");
+        // Close typos of several patterns are all offered, best first
+        assert_eq!(registry.suggest("HalfPhase_In")[0], "HalfPhase_Inv");
+        assert!(registry.suggest("HalfPhase_In").contains(&"HalfPhase"));
+        // Unrelated names produce nothing
+        assert!(registry.suggest("Teleport").is_empty());
+    }
+
+    #[test]
+    fn test_inverses() {
+        let registry = PatternRegistry::builtin();
+        assert_eq!(registry.inverse("Superposition"), Some("Superposition"));
+        assert_eq!(registry.inverse("HalfPhase"), Some("HalfPhase_Inv"));
+        assert_eq!(registry.inverse("QuarterPhase_Inv"), Some("QuarterPhase"));
+        assert_eq!(registry.inverse("PhiRotate"), Some("PhiRotate_Inv"));
+        assert_eq!(registry.inverse("PhiXRotate_Inv"), Some("PhiXRotate"));
+        assert_eq!(registry.inverse("Unknown"), None);
+    }
+}
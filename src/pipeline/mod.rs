@@ -0,0 +1,345 @@
+// src/pipeline/mod.rs
+
+//! Structured experiments as Prepare → Evolve → Stabilize → Analyze stages.
+//!
+//! A [`Pipeline`] bundles the pieces of an experiment that otherwise live as loose
+//! code in example binaries: a preparation circuit, an evolution stage (a circuit or a
+//! VM [`Program`]), which QDUs to stabilize, and how to analyze the outcome. The whole
+//! experiment is a single value that can be stored (serialized as one artifact with the
+//! `serde` feature), inspected (`Display`) and run.
+
+use crate::circuits::{Circuit, CircuitBuilder};
+use crate::core::{OnqError, PotentialityState, QduId};
+use crate::operations::Operation;
+use crate::simulation::{SeedMode, ShotResults, SimulationResult, Simulator};
+use crate::vm::{Instruction, OnqVm, Program};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The evolution stage of a [`Pipeline`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Evolution {
+    /// A fixed sequence of operations.
+    Circuit(Circuit),
+    /// A mixed classical/quantum program executed on the [`OnqVm`].
+    /// The program is responsible for its own stabilization and recording.
+    Program(Program),
+}
+
+/// Which QDUs the stabilization stage of a [`Pipeline`] resolves.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StabilizationSpec {
+    /// No final stabilization.
+    None,
+    /// Stabilize every QDU touched by the preparation or evolution stages.
+    #[default]
+    All,
+    /// Stabilize only the listed QDUs.
+    Targets(Vec<QduId>),
+}
+
+/// How the outcome of a [`Pipeline`] is analyzed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnalysisSpec {
+    /// A single run, reporting the stable outcomes.
+    #[default]
+    Outcomes,
+    /// Repeated runs aggregated into outcome counts (see [`Simulator::run_shots`]).
+    Shots(usize),
+}
+
+/// The result of running a [`Pipeline`], shaped by its evolution and analysis stages.
+#[derive(Debug, Clone)]
+pub enum PipelineOutput {
+    /// Stable outcomes of a single circuit run. Boxed, as results are much larger than
+    /// the other outputs.
+    Outcomes(Box<SimulationResult>),
+    /// Aggregated outcomes of a multi-shot circuit run.
+    Shots(ShotResults),
+    /// Final classical memory and quantum state of a VM program run.
+    Vm {
+        /// Classical registers at halt.
+        registers: HashMap<String, u64>,
+        /// The quantum state at halt, if the program used any QDUs.
+        final_state: Option<PotentialityState>,
+    },
+}
+
+/// A complete experiment: preparation, evolution, stabilization and analysis.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, Operation, QduId, StableState};
+/// # use onq::pipeline::{PipelineBuilder, PipelineOutput, StabilizationSpec};
+/// let (q0, q1) = (QduId(0), QduId(1));
+/// let pipeline = PipelineBuilder::new()
+///     .prepare(
+///         CircuitBuilder::new()
+///             .add_op(Operation::InteractionPattern {
+///                 target: q0,
+///                 pattern_id: "QualityFlip".to_string(),
+///             })
+///             .build(),
+///     )
+///     .evolve_circuit(
+///         CircuitBuilder::new()
+///             .add_op(Operation::ControlledInteraction {
+///                 control: q0,
+///                 target: q1,
+///                 pattern_id: "QualityFlip".to_string(),
+///             })
+///             .build(),
+///     )
+///     .stabilize(StabilizationSpec::Targets(vec![q1]))
+///     .build()
+///     .unwrap();
+///
+/// match pipeline.run().unwrap() {
+///     PipelineOutput::Outcomes(result) => {
+///         assert_eq!(result.get_stable_state(&q1), Some(&StableState::ResolvedQuality(1)));
+///     }
+///     other => panic!("unexpected output: {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "PipelineData")
+)]
+pub struct Pipeline {
+    prepare: Circuit,
+    evolve: Evolution,
+    stabilize: StabilizationSpec,
+    analyze: AnalysisSpec,
+}
+
+/// Deserialized form of a [`Pipeline`], checked by [`PipelineBuilder::build`] so that
+/// a stored pipeline holds stages that fit together.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PipelineData {
+    prepare: Circuit,
+    evolve: Evolution,
+    stabilize: StabilizationSpec,
+    analyze: AnalysisSpec,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PipelineData> for Pipeline {
+    type Error = String;
+
+    fn try_from(data: PipelineData) -> Result<Self, String> {
+        PipelineBuilder {
+            prepare: Some(data.prepare),
+            evolve: Some(data.evolve),
+            stabilize: data.stabilize,
+            analyze: data.analyze,
+        }
+        .build()
+    }
+}
+
+impl Pipeline {
+    /// Returns the preparation stage.
+    pub fn preparation(&self) -> &Circuit {
+        &self.prepare
+    }
+
+    /// Returns the evolution stage.
+    pub fn evolution(&self) -> &Evolution {
+        &self.evolve
+    }
+
+    /// Returns the stabilization stage.
+    pub fn stabilization(&self) -> &StabilizationSpec {
+        &self.stabilize
+    }
+
+    /// Returns the analysis stage.
+    pub fn analysis(&self) -> &AnalysisSpec {
+        &self.analyze
+    }
+
+    /// Flattens a circuit-evolution pipeline into the single circuit it executes:
+    /// preparation, evolution, then a final `Stabilize` per the stabilization stage.
+    /// Returns `None` for program-evolution pipelines.
+    pub fn to_circuit(&self) -> Option<Circuit> {
+        let Evolution::Circuit(evolve) = &self.evolve else {
+            return None;
+        };
+        let builder = CircuitBuilder::new().append(&self.prepare).append(evolve);
+        let mut targets: Vec<QduId> = match &self.stabilize {
+            StabilizationSpec::None => Vec::new(),
+            StabilizationSpec::All => self.prepare.qdus().union(evolve.qdus()).copied().collect(),
+            StabilizationSpec::Targets(targets) => targets.clone(),
+        };
+        targets.sort();
+        let builder = if targets.is_empty() {
+            builder
+        } else {
+            builder.add_op(Operation::Stabilize { targets })
+        };
+        Some(builder.build())
+    }
+
+    /// Runs every stage and returns the analyzed output.
+    ///
+    /// # Errors
+    /// Returns any `OnqError` raised while simulating or interpreting the stages.
+    pub fn run(&self) -> Result<PipelineOutput, OnqError> {
+        match &self.evolve {
+            Evolution::Circuit(_) => {
+                let circuit = self
+                    .to_circuit()
+                    .expect("circuit evolution always flattens to a circuit");
+                let simulator = Simulator::new();
+                match self.analyze {
+                    AnalysisSpec::Outcomes => simulator
+                        .run(&circuit)
+                        .map(|result| PipelineOutput::Outcomes(Box::new(result))),
+                    AnalysisSpec::Shots(shots) => simulator
+                        .run_shots(&circuit, shots, SeedMode::PerShot)
+                        .map(PipelineOutput::Shots),
+                }
+            }
+            Evolution::Program(program) => {
+                let program = self.prepended_program(program);
+                let mut vm = OnqVm::new();
+                vm.run(&program)?;
+                Ok(PipelineOutput::Vm {
+                    registers: vm.get_classical_memory(),
+                    final_state: vm.get_final_state(),
+                })
+            }
+        }
+    }
+
+    /// Returns `program` with the preparation operations prepended, shifting its labels.
+    fn prepended_program(&self, program: &Program) -> Program {
+        let offset = self.prepare.len();
+        let instructions = self
+            .prepare
+            .operations()
+            .iter()
+            .cloned()
+            .map(Instruction::QuantumOp)
+            .chain(program.instructions().iter().cloned())
+            .collect();
+        let label_map = program
+            .label_map
+            .iter()
+            .map(|(label, pc)| (label.clone(), pc + offset))
+            .collect();
+        Program {
+            instructions,
+            label_map,
+            exports: program.exports.clone(),
+            imports: program.imports.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== Prepare ===")?;
+        write!(f, "{}", self.prepare)?;
+        writeln!(f, "=== Evolve ===")?;
+        match &self.evolve {
+            Evolution::Circuit(circuit) => write!(f, "{}", circuit)?,
+            Evolution::Program(program) => write!(f, "{}", program)?,
+        }
+        writeln!(f, "=== Stabilize ===")?;
+        match &self.stabilize {
+            StabilizationSpec::None => writeln!(f, "none")?,
+            StabilizationSpec::All => writeln!(f, "all")?,
+            StabilizationSpec::Targets(targets) => writeln!(f, "{:?}", targets)?,
+        }
+        writeln!(f, "=== Analyze ===")?;
+        match self.analyze {
+            AnalysisSpec::Outcomes => writeln!(f, "outcomes"),
+            AnalysisSpec::Shots(shots) => writeln!(f, "shots: {}", shots),
+        }
+    }
+}
+
+/// Builder for [`Pipeline`]. Every stage is optional: preparation and evolution
+/// default to empty circuits, stabilization to [`StabilizationSpec::All`] and
+/// analysis to [`AnalysisSpec::Outcomes`].
+#[derive(Debug, Clone, Default)]
+pub struct PipelineBuilder {
+    prepare: Option<Circuit>,
+    evolve: Option<Evolution>,
+    stabilize: StabilizationSpec,
+    analyze: AnalysisSpec,
+}
+
+impl PipelineBuilder {
+    /// Creates a builder with default stages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the preparation circuit.
+    pub fn prepare(mut self, circuit: Circuit) -> Self {
+        self.prepare = Some(circuit);
+        self
+    }
+
+    /// Sets a circuit as the evolution stage.
+    pub fn evolve_circuit(mut self, circuit: Circuit) -> Self {
+        self.evolve = Some(Evolution::Circuit(circuit));
+        self
+    }
+
+    /// Sets a VM program as the evolution stage.
+    pub fn evolve_program(mut self, program: Program) -> Self {
+        self.evolve = Some(Evolution::Program(program));
+        self
+    }
+
+    /// Sets the stabilization stage.
+    pub fn stabilize(mut self, spec: StabilizationSpec) -> Self {
+        self.stabilize = spec;
+        self
+    }
+
+    /// Sets the analysis stage.
+    pub fn analyze(mut self, spec: AnalysisSpec) -> Self {
+        self.analyze = spec;
+        self
+    }
+
+    /// Builds the pipeline, checking that the stages fit together.
+    ///
+    /// Program evolution stabilizes and records through its own instructions, so it
+    /// must be paired with [`StabilizationSpec::None`] and [`AnalysisSpec::Outcomes`].
+    pub fn build(self) -> Result<Pipeline, String> {
+        let evolve = self
+            .evolve
+            .unwrap_or_else(|| Evolution::Circuit(Circuit::new()));
+        if let Evolution::Program(_) = evolve {
+            if self.stabilize != StabilizationSpec::None {
+                return Err(
+                    "Program evolution must use StabilizationSpec::None; stabilize inside the program"
+                        .to_string(),
+                );
+            }
+            if self.analyze != AnalysisSpec::Outcomes {
+                return Err("Program evolution only supports AnalysisSpec::Outcomes".to_string());
+            }
+        }
+        if self.analyze == AnalysisSpec::Shots(0) {
+            return Err("Shot analysis requires at least one shot".to_string());
+        }
+        Ok(Pipeline {
+            prepare: self.prepare.unwrap_or_default(),
+            evolve,
+            stabilize: self.stabilize,
+            analyze: self.analyze,
+        })
+    }
+}
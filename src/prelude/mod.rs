@@ -0,0 +1,26 @@
+// src/prelude/mod.rs
+
+//! Convenience re-exports of the types most programs need.
+//!
+//! ```
+//! use onq::prelude::*;
+//!
+//! let circuit = CircuitBuilder::new()
+//!     .add_op(Operation::InteractionPattern {
+//!         target: QduId(0),
+//!         pattern_id: "QualityFlip".to_string(),
+//!     })
+//!     .add_op(Operation::Stabilize { targets: vec![QduId(0)] })
+//!     .build();
+//! let result = Simulator::new().run(&circuit).unwrap();
+//! assert_eq!(result.get_stable_state(&QduId(0)), Some(&StableState::ResolvedQuality(1)));
+//! ```
+
+pub use crate::analysis::StabilizerTableau;
+pub use crate::circuits::{Circuit, CircuitBuilder};
+pub use crate::core::{OnqError, PotentialityState, QduId, StableState};
+pub use crate::operations::{Operation, PatternRegistry, PauliAxis, Quality};
+pub use crate::pipeline::{Pipeline, PipelineBuilder};
+pub use crate::simulation::{ShotResults, SimulationResult, Simulator};
+pub use crate::vm::program::LockType;
+pub use crate::vm::{Instruction, OnqVm, Program, ProgramBuilder};
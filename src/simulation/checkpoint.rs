@@ -0,0 +1,320 @@
+// src/simulation/checkpoint.rs
+
+//! Saving the engine state of a paused simulation so it can be resumed later.
+
+use crate::core::state::LocalTensor;
+use crate::core::{OnqError, PotentialityState, QduId, StableState};
+use num_complex::Complex;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// First line of every checkpoint file, naming the format version.
+const HEADER: &str = "onq-checkpoint 1";
+
+/// The engine state of a simulation at a point between two operations: the IVM node
+/// of every QDU, the local tensors of all nodes and the outcomes stabilized so far.
+///
+/// Checkpoints are taken with
+/// [`SimulationSession::checkpoint`](super::SimulationSession::checkpoint), written to
+/// disk with [`save`](Checkpoint::save) and continued with
+/// [`Simulator::resume`](super::Simulator::resume), so long simulations can survive
+/// restarts. The file is plain text and stores amplitudes exactly, so a resumed run
+/// stabilizes exactly like an uninterrupted one.
+///
+/// Density matrices are not stored: a resumed density-matrix run starts from the pure
+/// density matrices of the stored amplitudes.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator, StableState};
+/// # use onq::simulation::Checkpoint;
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new().x(q0).stabilize(&[q0]).build();
+/// let simulator = Simulator::new();
+///
+/// let mut session = simulator.session(&circuit).unwrap();
+/// session.step().unwrap();
+/// let text = session.checkpoint().unwrap().to_string();
+///
+/// let checkpoint: Checkpoint = text.parse().unwrap();
+/// let remaining = circuit.operations()[1..].iter().cloned();
+/// let result = simulator.resume(&checkpoint, remaining).unwrap();
+/// assert_eq!(result.get_stable_state(&q0), Some(&StableState::ResolvedQuality(1)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    /// The IVM node of each QDU.
+    qdu_nodes: BTreeMap<QduId, u64>,
+    /// The global state at the checkpoint.
+    state: PotentialityState,
+    /// Outcomes stabilized before the checkpoint.
+    outcomes: BTreeMap<QduId, u64>,
+    /// Global phase factored out of `state`.
+    global_phase: f64,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint of `state`, with `qdu_nodes` naming the node of each QDU.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `qdu_nodes` is empty or its nodes are
+    /// not exactly `0..n`, and `OnqError::ReferenceViolation` if `state` lacks one of
+    /// those nodes.
+    pub fn new(
+        qdu_nodes: BTreeMap<QduId, u64>,
+        state: PotentialityState,
+    ) -> Result<Self, OnqError> {
+        let mut nodes: Vec<u64> = qdu_nodes.values().copied().collect();
+        nodes.sort_unstable();
+        if nodes.is_empty() || nodes.iter().enumerate().any(|(i, &node)| node != i as u64) {
+            return Err(OnqError::InvalidOperation {
+                message: "Checkpoint QDUs must occupy the IVM nodes 0..n exactly once".to_string(),
+            });
+        }
+        if let Some((qdu, node)) = qdu_nodes
+            .iter()
+            .find(|(_, node)| !state.network.contains_key(node))
+        {
+            return Err(OnqError::ReferenceViolation {
+                message: format!("Checkpoint state has no IVM node {} for {}", node, qdu),
+            });
+        }
+        Ok(Self {
+            qdu_nodes,
+            state,
+            outcomes: BTreeMap::new(),
+            global_phase: 0.0,
+        })
+    }
+
+    /// Records the outcomes stabilized before the checkpoint.
+    pub fn with_outcomes(mut self, outcomes: &HashMap<QduId, StableState>) -> Self {
+        self.outcomes = outcomes
+            .iter()
+            .filter_map(|(qdu, state)| Some((*qdu, state.get_resolved_value()?)))
+            .collect();
+        self
+    }
+
+    /// Records the global phase factored out of the state, see
+    /// [`SimulatorConfig::with_global_phase_tracking`](super::SimulatorConfig::with_global_phase_tracking).
+    pub fn with_global_phase(mut self, phase: f64) -> Self {
+        self.global_phase = phase;
+        self
+    }
+
+    /// Returns the IVM node of every QDU, sorted by QDU.
+    pub fn qdu_nodes(&self) -> &BTreeMap<QduId, u64> {
+        &self.qdu_nodes
+    }
+
+    /// Returns the global state at the checkpoint, indexed by physical IVM node.
+    pub fn state(&self) -> &PotentialityState {
+        &self.state
+    }
+
+    /// Returns the global phase factored out of the state (0 unless tracked).
+    pub fn global_phase(&self) -> f64 {
+        self.global_phase
+    }
+
+    /// Returns the outcomes stabilized before the checkpoint, sorted by QDU.
+    pub fn outcomes(&self) -> &BTreeMap<QduId, u64> {
+        &self.outcomes
+    }
+
+    /// Returns the QDUs ordered by IVM node, as expected by
+    /// [`Simulator::run_from_state`](super::Simulator::run_from_state).
+    pub fn qdu_order(&self) -> Vec<QduId> {
+        let mut order: Vec<(u64, QduId)> = self
+            .qdu_nodes
+            .iter()
+            .map(|(qdu, node)| (*node, *qdu))
+            .collect();
+        order.sort_unstable();
+        order.into_iter().map(|(_, qdu)| qdu).collect()
+    }
+
+    /// Writes the checkpoint to `path`, replacing any existing file.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OnqError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()).map_err(|e| OnqError::SimulationError {
+            message: format!("Cannot write checkpoint {}: {}", path.display(), e),
+        })
+    }
+
+    /// Reads a checkpoint written by [`save`](Checkpoint::save).
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` if the file cannot be read, and the errors of
+    /// parsing ([`str::parse`]) and [`Checkpoint::new`] otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, OnqError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| OnqError::SimulationError {
+                message: format!("Cannot read checkpoint {}: {}", path.display(), e),
+            })?
+            .parse()
+    }
+}
+
+/// Writes the text format read back by [`str::parse`]: a header line, then one line per
+/// QDU (`qdu <id> <node>`), node (`node <id> <re0> <im0> <re1> <im1>`), bond
+/// (`bond <node> <neighbor> <re> <im>...`) and outcome (`outcome <id> <quality>`),
+/// plus a `phase <radians>` line if a global phase was tracked. Floats are written in
+/// their shortest exact form.
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (qdu, node) in &self.qdu_nodes {
+            writeln!(f, "qdu {} {}", qdu.0, node)?;
+        }
+        let mut nodes: Vec<_> = self.state.network.iter().collect();
+        nodes.sort_by_key(|(node, _)| **node);
+        for (node, tensor) in &nodes {
+            let [a, b] = tensor.core_state;
+            writeln!(
+                f,
+                "node {} {:?} {:?} {:?} {:?}",
+                node, a.re, a.im, b.re, b.im
+            )?;
+        }
+        for (node, tensor) in &nodes {
+            let mut bonds: Vec<_> = tensor.bonds.iter().collect();
+            bonds.sort_by_key(|(neighbor, _)| **neighbor);
+            for (neighbor, bond) in bonds {
+                write!(f, "bond {} {}", node, neighbor)?;
+                for amplitude in bond {
+                    write!(f, " {:?} {:?}", amplitude.re, amplitude.im)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        for (qdu, quality) in &self.outcomes {
+            writeln!(f, "outcome {} {}", qdu.0, quality)?;
+        }
+        if self.global_phase != 0.0 {
+            writeln!(f, "phase {:?}", self.global_phase)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Checkpoint {
+    type Err = OnqError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => {}
+            _ => {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("Checkpoint must start with '{}'", HEADER),
+                });
+            }
+        }
+
+        let mut qdu_nodes = BTreeMap::new();
+        let mut outcomes = BTreeMap::new();
+        let mut state = PotentialityState::new();
+        let mut global_phase = 0.0;
+        for (index, line) in lines {
+            parse_line(
+                line,
+                &mut qdu_nodes,
+                &mut state,
+                &mut outcomes,
+                &mut global_phase,
+            )
+            .map_err(|message| OnqError::InvalidOperation {
+                message: format!("Checkpoint line {}: {}", index + 1, message),
+            })?;
+        }
+
+        let mut checkpoint = Checkpoint::new(qdu_nodes, state)?;
+        checkpoint.outcomes = outcomes;
+        checkpoint.global_phase = global_phase;
+        Ok(checkpoint)
+    }
+}
+
+/// Applies one record line of a checkpoint.
+fn parse_line(
+    line: &str,
+    qdu_nodes: &mut BTreeMap<QduId, u64>,
+    state: &mut PotentialityState,
+    outcomes: &mut BTreeMap<QduId, u64>,
+    global_phase: &mut f64,
+) -> Result<(), String> {
+    let mut fields = line.split_whitespace();
+    let kind = fields.next().unwrap_or_default();
+    let fields: Vec<&str> = fields.collect();
+    match (kind, fields.as_slice()) {
+        ("qdu", [qdu, node]) => {
+            if qdu_nodes
+                .insert(QduId(integer(qdu)?), integer(node)?)
+                .is_some()
+            {
+                return Err(format!("QDU {} is listed twice", qdu));
+            }
+        }
+        ("node", [node, amplitudes @ ..]) if amplitudes.len() == 4 => {
+            let [a, b] = complexes(amplitudes)?[..] else {
+                unreachable!("four floats form two amplitudes")
+            };
+            tensor(state, integer(node)?)?.core_state = [a, b];
+        }
+        ("bond", [node, neighbor, amplitudes @ ..]) if amplitudes.len() % 2 == 0 => {
+            let bond = complexes(amplitudes)?;
+            tensor(state, integer(node)?)?
+                .bonds
+                .insert(integer(neighbor)?, bond);
+        }
+        ("outcome", [qdu, quality]) => {
+            outcomes.insert(QduId(integer(qdu)?), integer(quality)?);
+        }
+        ("phase", [phase]) => {
+            *global_phase = phase
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", phase))?;
+        }
+        _ => return Err(format!("unrecognized record '{}'", line.trim())),
+    }
+    Ok(())
+}
+
+fn integer(field: &str) -> Result<u64, String> {
+    field
+        .parse()
+        .map_err(|_| format!("'{}' is not an unsigned integer", field))
+}
+
+/// Parses `re im` pairs into amplitudes.
+fn complexes(fields: &[&str]) -> Result<Vec<Complex<f64>>, String> {
+    let floats = fields
+        .iter()
+        .map(|field| {
+            field
+                .parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number", field))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(floats
+        .chunks(2)
+        .map(|pair| Complex::new(pair[0], pair[1]))
+        .collect())
+}
+
+fn tensor(state: &mut PotentialityState, node: u64) -> Result<&mut LocalTensor, String> {
+    state
+        .network
+        .get_mut(&node)
+        .ok_or_else(|| format!("IVM node {} does not exist", node))
+}
@@ -0,0 +1,180 @@
+// src/simulation/clifford.rs
+
+//! The stabilizer-tableau backend ([`Backend::StabilizerTableau`]) for Clifford-analog
+//! circuits.
+//!
+//! Circuits built only from Clifford-analog patterns and stabilizations are run on a
+//! [`StabilizerTableau`] instead of the tensor network. The tableau tracks integration
+//! between QDUs exactly in polynomial time, so stabilization outcomes of integrated
+//! QDUs are correlated as the patterns dictate. QDUs that are never integrated with
+//! another are also evolved on a tensor-network engine and resolve there, so their
+//! outcomes match the tensor network bit for bit; the tableau's weights of such QDUs
+//! are exact, while the engine's carry rounding error the draw is sensitive to.
+//!
+//! [`Backend::StabilizerTableau`]: crate::simulation::Backend::StabilizerTableau
+
+use crate::analysis::StabilizerTableau;
+use crate::circuits::Circuit;
+use crate::core::state::stabilization_draw;
+use crate::core::{OnqError, QduId, StableState};
+use crate::operations::Operation;
+use crate::simulation::engine::{IVM_CAPACITY, SimulationEngine};
+use crate::simulation::{
+    AmplitudeWeighted, Backend, CoherenceFiltered, ReadoutError, SimulationResult, SimulatorConfig,
+    StabilizationStrategy, StateRepresentation,
+};
+use crate::topology::IvmTopology;
+use std::collections::{HashMap, HashSet};
+
+/// Returns `true` if `circuit` runs on the tableau under `config`.
+pub(crate) fn eligible(circuit: &Circuit, config: &SimulatorConfig) -> bool {
+    config.backend() == Backend::StabilizerTableau
+        && !config.profiling()
+        && !config.global_phase_tracking()
+        && config.boundary_model().is_none()
+        && config.representation() == StateRepresentation::Amplitudes
+        && !circuit.is_empty()
+        && circuit.dimensions().is_empty()
+        && circuit.frames().is_empty()
+        && circuit.qdus().len() <= IVM_CAPACITY
+        && circuit
+            .operations()
+            .iter()
+            .all(|op| matches!(op, Operation::Stabilize { .. }) || StabilizerTableau::supports(op))
+}
+
+/// Runs an [`eligible`] circuit on a stabilizer tableau. `local` is a fresh engine
+/// configured as the simulator's, without readout error, on which QDUs never
+/// integrated with another evolve and resolve.
+pub(crate) fn run(
+    circuit: &Circuit,
+    config: &SimulatorConfig,
+    mut local: SimulationEngine,
+    strategy: Option<&dyn StabilizationStrategy>,
+    readout: Option<&ReadoutError>,
+    salt: u64,
+) -> Result<SimulationResult, OnqError> {
+    let mut result = SimulationResult::new();
+    result.set_semantics(config.semantics());
+
+    // QDUs occupy IVM nodes in ascending order, as in the engine
+    let mut sorted: Vec<QduId> = circuit.qdus().iter().copied().collect();
+    sorted.sort();
+    let nodes: HashMap<QduId, u64> = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, qdu)| (*qdu, i as u64))
+        .collect();
+    let node = |qdu: &QduId| {
+        nodes
+            .get(qdu)
+            .copied()
+            .ok_or_else(|| OnqError::ReferenceViolation {
+                message: format!("QDU {} not mapped to physical matrix.", qdu),
+            })
+    };
+    let topology = IvmTopology::new();
+
+    let filtered = CoherenceFiltered::default();
+    let strategy: &dyn StabilizationStrategy = match strategy {
+        Some(strategy) => strategy,
+        None if config.semantics().coherence_filtered() => &filtered,
+        None => &AmplitudeWeighted,
+    };
+
+    let mut tableau = StabilizerTableau::new(&sorted);
+    // QDUs a multi-QDU operation has acted on; only the tableau models them
+    let mut integrated: HashSet<QduId> = HashSet::new();
+    let mut readout_count = 0;
+    for (index, op) in circuit.operations().iter().enumerate() {
+        match op {
+            Operation::Stabilize { targets } => {
+                for target in targets {
+                    let quality = if integrated.contains(target) {
+                        let weights = tableau.weights(target)?;
+                        let draw = stabilization_draw(
+                            &weights,
+                            config.stabilization_seed(),
+                            salt,
+                            node(target)?,
+                        );
+                        strategy.select(weights, draw)
+                    } else {
+                        let mut resolved = SimulationResult::new();
+                        local.stabilize(std::slice::from_ref(target), &mut resolved)?;
+                        resolved
+                            .get_stable_state(target)
+                            .and_then(StableState::get_resolved_value)
+                            .ok_or_else(|| OnqError::SimulationError {
+                                message: format!("{} did not resolve", target),
+                            })? as u8
+                    };
+                    tableau
+                        .collapse(target, quality)
+                        .map_err(|e| OnqError::SimulationError {
+                            message: format!("Strategy '{}': {}", strategy.name(), e),
+                        })?;
+
+                    let flipped =
+                        readout.is_some_and(|readout| readout.flips(target, salt, readout_count));
+                    readout_count += 1;
+                    let reported = if flipped { quality ^ 1 } else { quality };
+                    result.record_stable_state(
+                        *target,
+                        StableState::ResolvedQuality(reported as u64),
+                    );
+                    result.record_readout(*target, flipped);
+                }
+            }
+            _ => {
+                // The Locality Rule holds on the tableau too
+                if let Operation::ControlledInteraction {
+                    control, target, ..
+                } = op
+                {
+                    let (c, t) = (node(control)?, node(target)?);
+                    if !topology.are_adjacent(c, t) {
+                        return Err(OnqError::InvalidOperation {
+                            message: format!(
+                                "Topological Error: QDU {} and QDU {} are not physically adjacent in the IVM. Route through intermediate nodes.",
+                                c, t
+                            ),
+                        });
+                    }
+                }
+                tableau.apply(op)?;
+                match op {
+                    Operation::ControlledInteraction {
+                        control, target, ..
+                    } => {
+                        integrated.extend([*control, *target]);
+                    }
+                    Operation::Permute { mapping } => {
+                        local.apply_operation(op)?;
+                        let moved: Vec<QduId> = mapping
+                            .iter()
+                            .filter(|(from, _)| integrated.contains(from))
+                            .map(|(_, to)| *to)
+                            .collect();
+                        for (from, _) in mapping {
+                            integrated.remove(from);
+                        }
+                        integrated.extend(moved);
+                    }
+                    // Patterns and delays act on each of their QDUs alone
+                    _ => local.apply_operation(op)?,
+                }
+            }
+        }
+        for (bit, qdu) in circuit.bits_at(index) {
+            if let Some(value) = result
+                .get_stable_state(&qdu)
+                .and_then(|state| state.get_resolved_value())
+            {
+                result.record_classical_bit(bit, value);
+            }
+        }
+    }
+    result.record_renormalizations(local.renormalizations());
+    Ok(result)
+}
@@ -0,0 +1,539 @@
+// src/simulation/config.rs
+
+//! Configuration accepted by [`Simulator::with_config`](super::Simulator::with_config).
+
+use crate::validation::{DEFAULT_AMPLITUDE_TOLERANCE, DEFAULT_NORM_TOLERANCE};
+use std::fmt;
+
+/// Pins the stabilization behavior of a simulation to a documented rule set, so
+/// results published against one crate version stay reproducible in later ones.
+///
+/// Every version is deterministic: the outcome of stabilizing a given state is
+/// always the same. Versions differ in *which* outcome is selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SemanticsVersion {
+    /// Amplitude-only: the outcome is drawn from the quality weights `|a|²`, `|b|²`
+    /// using the deterministic state-derived seed. No coherence filter is applied.
+    V1,
+    /// Coherence-filtered: a quality whose weight exceeds the Golden Ratio threshold
+    /// (1/φ ≈ 0.618) is selected outright; otherwise the outcome is drawn as in `V1`.
+    #[default]
+    V2,
+}
+
+impl SemanticsVersion {
+    /// The most recent semantics, used by default.
+    pub const LATEST: SemanticsVersion = SemanticsVersion::V2;
+
+    /// Returns `true` if this version applies the 1/φ coherence filter before sampling.
+    pub fn coherence_filtered(&self) -> bool {
+        match self {
+            SemanticsVersion::V1 => false,
+            SemanticsVersion::V2 => true,
+        }
+    }
+}
+
+impl fmt::Display for SemanticsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticsVersion::V1 => write!(f, "v1 (amplitude-only)"),
+            SemanticsVersion::V2 => write!(f, "v2 (coherence-filtered)"),
+        }
+    }
+}
+
+/// How the deterministic draw behind each stabilization is seeded.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator};
+/// # use onq::simulation::{StabilizationSeed, SimulatorConfig};
+/// let (q0, q1) = (QduId(0), QduId(1));
+/// // Two QDUs in identical states always resolve alike under the default seeding
+/// let circuit = CircuitBuilder::new().h(q0).h(q1).stabilize(&[q0, q1]).build();
+/// let result = Simulator::new().run(&circuit).unwrap();
+/// assert_eq!(result.get_stable_state(&q0), result.get_stable_state(&q1));
+///
+/// // A fixed seed draws per QDU instead, independent of the state
+/// let config = SimulatorConfig::new().with_stabilization_seed(StabilizationSeed::Fixed(3));
+/// let fixed = Simulator::with_config(config);
+/// assert_eq!(fixed.run(&circuit).unwrap(), fixed.run(&circuit).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StabilizationSeed {
+    /// The draw is derived from the quality weights of the QDU alone, so identical
+    /// states always resolve identically.
+    #[default]
+    StateDerived,
+    /// The given value is mixed into the state-derived seed: runs stay reproducible
+    /// for one value while different values resolve identical states differently.
+    Mixed(u64),
+    /// The draw is derived from the given value and the QDU's node, ignoring the state.
+    Fixed(u64),
+}
+
+/// How thoroughly the simulator checks the state while running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValidationMode {
+    /// No checks; the fastest option.
+    #[default]
+    Off,
+    /// Checks that the global state stays normalized within the norm tolerance.
+    Basic,
+    /// Additionally checks that every local tensor is finite and normalized within
+    /// the amplitude tolerance, catching errors the global product can mask.
+    Strict,
+}
+
+/// When the simulator runs the checks selected by [`ValidationMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValidationTiming {
+    /// Once, after the last operation.
+    #[default]
+    EndOnly,
+    /// After every operation, pinpointing the first operation that breaks the state.
+    PerOperation,
+}
+
+/// How the simulator represents the state of each node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateRepresentation {
+    /// A pair of amplitudes per node; non-unitary processes are approximated by
+    /// pure states.
+    #[default]
+    Amplitudes,
+    /// A 2x2 density matrix per node ([`DensityState`](crate::core::DensityState)).
+    /// `Relax` becomes the amplitude-damping channel, and stabilization weights and
+    /// marginals are read from the diagonals, so mixtures are modeled faithfully.
+    DensityMatrix,
+}
+
+/// The simulation backend circuits run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backend {
+    /// The localized tensor network of the IVM, which runs every circuit.
+    #[default]
+    TensorNetwork,
+    /// A stabilizer tableau for circuits made only of Clifford-analog patterns (see
+    /// [`StabilizerTableau::supports`](crate::analysis::StabilizerTableau::supports))
+    /// and stabilizations. Other circuits, and density-matrix, profiled, boundary-model
+    /// and global-phase-tracking runs, fall back to the tensor network.
+    ///
+    /// This is a different model, not a faster route to the same results: the tableau
+    /// tracks integration between QDUs exactly, so outcomes of integrated QDUs are
+    /// correlated as the patterns dictate, where the tensor network keeps every node's
+    /// state local. QDUs never integrated with another resolve exactly as on the
+    /// tensor network. Tableau runs capture no final state.
+    StabilizerTableau,
+}
+
+/// The floating-point precision amplitudes are kept at between operations.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator};
+/// # use onq::simulation::{Precision, SimulatorConfig};
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new().phase(q0, 0.1).h(q0).build();
+/// let extended = Simulator::with_config(SimulatorConfig::new().with_precision(Precision::Extended));
+/// let [a, _] = extended.run_with_state(&circuit).unwrap().final_state().unwrap().network[&0].core_state;
+/// let [b, _] = Simulator::new().run_with_state(&circuit).unwrap().final_state().unwrap().network[&0].core_state;
+/// assert!((a - b).norm() < 1e-15);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Precision {
+    /// Full `f64` precision throughout.
+    #[default]
+    Double,
+    /// Single-node updates of the amplitudes and the normalized quality weights
+    /// compared by the coherence filter are evaluated in double-double arithmetic
+    /// (about 106 mantissa bits) and rounded to `f64` once, rather than after every
+    /// product and sum. Intended for studying how sensitive outcomes near the
+    /// 1/φ threshold are to rounding error; the deterministic draw and density
+    /// matrices are unaffected.
+    Extended,
+}
+
+impl Precision {
+    /// Returns the number of significant mantissa bits intermediate results carry.
+    pub fn mantissa_bits(&self) -> u32 {
+        match self {
+            Precision::Double => f64::MANTISSA_DIGITS,
+            Precision::Extended => 2 * f64::MANTISSA_DIGITS,
+        }
+    }
+}
+
+/// How the quality weights are scored before the stabilization strategy selects an
+/// outcome from them.
+///
+/// The score of a quality is its weight `|amplitude|²` multiplied by the enabled
+/// factors, then normalized over both qualities:
+/// * **C_A, phase coherence**: `(1 + cos φ)/2` for the phase `φ` of the quality's
+///   amplitude, favoring amplitudes aligned with the reference phase. Global phase
+///   therefore matters to coherence-weighted scoring.
+/// * **C_B, pattern resonance**: `1/(1 + h)` for the Hamming weight `h` of the
+///   quality, favoring Quality0 (1) over Quality1 (1/2).
+///
+/// # Examples
+/// ```
+/// # use onq::{PotentialityState, Simulator};
+/// # use onq::simulation::{ScoringMode, SimulatorConfig};
+/// # use num_complex::Complex;
+/// let mut state = PotentialityState::new();
+/// let amplitudes = [Complex::new(0.4f64.sqrt(), 0.0), Complex::new(0.6f64.sqrt(), 0.0)];
+/// state.network.get_mut(&0).unwrap().core_state = amplitudes;
+///
+/// // Resonance turns the weights 0.4 / 0.6 into the scores 0.4 / 0.3, i.e. 4/7 / 3/7
+/// let config = SimulatorConfig::new().with_scoring(ScoringMode::Full);
+/// let analysis = Simulator::with_config(config).analyze_stabilization(&state, &[0]).unwrap();
+/// let [zero, one] = analysis[0].scores;
+/// assert!((zero.score - 4.0 / 7.0).abs() < 1e-12);
+/// assert!((one.weight - 0.6).abs() < 1e-12 && one.resonance == 0.5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScoringMode {
+    /// The raw quality weights, as stabilization has always used.
+    #[default]
+    AmplitudeOnly,
+    /// Weights scaled by the phase coherence C_A.
+    CoherenceWeighted,
+    /// Weights scaled by the pattern resonance C_B.
+    ResonanceWeighted,
+    /// Weights scaled by both, `S(k) = C_A(k)·C_B(k)·|amplitude_k|²`.
+    Full,
+}
+
+impl ScoringMode {
+    /// Returns `true` if scores include the phase coherence C_A.
+    pub fn uses_coherence(&self) -> bool {
+        matches!(self, ScoringMode::CoherenceWeighted | ScoringMode::Full)
+    }
+
+    /// Returns `true` if scores include the pattern resonance C_B.
+    pub fn uses_resonance(&self) -> bool {
+        matches!(self, ScoringMode::ResonanceWeighted | ScoringMode::Full)
+    }
+}
+
+/// What the simulator does when an operation leaves a local state off unit norm, as
+/// floating-point drift does over long runs of projections and relaxations.
+///
+/// A node counts as drifted once its squared norm is further than the amplitude
+/// tolerance from 1. Nodes with zero or non-finite norm are never renormalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RenormalizationPolicy {
+    /// Leaves drifted states alone, so the checks of [`ValidationMode`] fail once the
+    /// drift exceeds their tolerance.
+    #[default]
+    Error,
+    /// Rescales drifted states to unit norm and records each rescaling in
+    /// [`SimulationResult::renormalizations`](super::SimulationResult::renormalizations).
+    WarnAndRenormalize,
+    /// Rescales drifted states to unit norm without recording it.
+    SilentRenormalize,
+}
+
+impl RenormalizationPolicy {
+    /// Returns `true` if drifted states are rescaled.
+    pub fn renormalizes(&self) -> bool {
+        !matches!(self, RenormalizationPolicy::Error)
+    }
+}
+
+/// Tracks the integrity of each QDU's distinction boundary, which every non-unitary
+/// projection (`Project` and each stabilization) wears down.
+///
+/// Every QDU starts with integrity 1; each projection multiplies it by `1 - decay`.
+/// Once it falls below `threshold` the simulator fails with
+/// `OnqError::BoundaryFailure` for that QDU. Unitary evolution does not restore it.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, OnqError, QduId, Simulator};
+/// # use onq::simulation::{BoundaryModel, SimulatorConfig};
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new()
+///     .stabilize(&[q0])
+///     .stabilize(&[q0])
+///     .stabilize(&[q0])
+///     .build();
+/// let config = SimulatorConfig::new().with_boundary_model(BoundaryModel::new(0.25, 0.5));
+/// // 1 -> 0.75 -> 0.5625 -> 0.42: the third readout breaks the boundary
+/// assert!(matches!(
+///     Simulator::with_config(config).run(&circuit),
+///     Err(OnqError::BoundaryFailure { qdu_id, .. }) if qdu_id == q0
+/// ));
+/// assert!(Simulator::new().run(&circuit).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundaryModel {
+    decay: f64,
+    threshold: f64,
+}
+
+impl BoundaryModel {
+    /// Creates a model losing the fraction `decay` of the integrity per projection
+    /// and failing below `threshold`.
+    pub fn new(decay: f64, threshold: f64) -> Self {
+        Self { decay, threshold }
+    }
+
+    /// Returns the fraction of integrity each projection removes.
+    pub fn decay(&self) -> f64 {
+        self.decay
+    }
+
+    /// Returns the integrity below which a boundary fails.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+}
+
+/// Settings controlling how a [`Simulator`](super::Simulator) executes circuits.
+///
+/// # Examples
+/// ```
+/// # use onq::simulation::{SemanticsVersion, SimulatorConfig, ValidationMode, ValidationTiming};
+/// let config = SimulatorConfig::new()
+///     .with_semantics(SemanticsVersion::V1)
+///     .with_validation(ValidationMode::Strict)
+///     .with_validation_timing(ValidationTiming::PerOperation)
+///     .with_norm_tolerance(1e-9);
+/// assert_eq!(config.semantics(), SemanticsVersion::V1);
+/// assert_eq!(config.validation(), ValidationMode::Strict);
+/// assert_eq!(config.norm_tolerance(), 1e-9);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulatorConfig {
+    semantics: SemanticsVersion,
+    stabilization_seed: StabilizationSeed,
+    norm_tolerance: f64,
+    amplitude_tolerance: f64,
+    validation: ValidationMode,
+    validation_timing: ValidationTiming,
+    representation: StateRepresentation,
+    backend: Backend,
+    profiling: bool,
+    global_phase_tracking: bool,
+    renormalization: RenormalizationPolicy,
+    precision: Precision,
+    scoring: ScoringMode,
+    boundary_model: Option<BoundaryModel>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            semantics: SemanticsVersion::default(),
+            stabilization_seed: StabilizationSeed::default(),
+            norm_tolerance: DEFAULT_NORM_TOLERANCE,
+            amplitude_tolerance: DEFAULT_AMPLITUDE_TOLERANCE,
+            validation: ValidationMode::default(),
+            validation_timing: ValidationTiming::default(),
+            representation: StateRepresentation::default(),
+            backend: Backend::TensorNetwork,
+            profiling: false,
+            global_phase_tracking: false,
+            renormalization: RenormalizationPolicy::default(),
+            precision: Precision::default(),
+            scoring: ScoringMode::default(),
+            boundary_model: None,
+        }
+    }
+}
+
+impl SimulatorConfig {
+    /// Creates a configuration with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the stabilization semantics version.
+    pub fn with_semantics(mut self, semantics: SemanticsVersion) -> Self {
+        self.semantics = semantics;
+        self
+    }
+
+    /// Sets how the stabilization draw is seeded. Multi-shot runs additionally mix
+    /// their per-shot salt into it (see [`SeedMode`](super::SeedMode)).
+    pub fn with_stabilization_seed(mut self, seed: StabilizationSeed) -> Self {
+        self.stabilization_seed = seed;
+        self
+    }
+
+    /// Sets how far the squared norm of the global state may drift from 1.
+    pub fn with_norm_tolerance(mut self, tolerance: f64) -> Self {
+        self.norm_tolerance = tolerance;
+        self
+    }
+
+    /// Sets the tolerance for amplitude-level checks: unitarity of `MatrixPattern`
+    /// matrices and, in strict validation, the norm of each local tensor.
+    pub fn with_amplitude_tolerance(mut self, tolerance: f64) -> Self {
+        self.amplitude_tolerance = tolerance;
+        self
+    }
+
+    /// Sets which state checks run.
+    pub fn with_validation(mut self, validation: ValidationMode) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Sets when the state checks run.
+    pub fn with_validation_timing(mut self, timing: ValidationTiming) -> Self {
+        self.validation_timing = timing;
+        self
+    }
+
+    /// Sets how the state of each node is represented.
+    pub fn with_representation(mut self, representation: StateRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Sets the backend circuits run on (see [`Backend`]).
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Records the wall-clock time of every operation into a
+    /// [`Profile`](super::Profile) on the result. Profiled runs always execute on the
+    /// tensor network, whatever the [`Backend`].
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.profiling = enabled;
+        self
+    }
+
+    /// Factors the phase of every local state out into an accumulated global phase as
+    /// operations touch it, leaving each local state with a real, non-negative leading
+    /// amplitude. States that differ only by a global phase then compare equal, and the
+    /// phase itself is reported by
+    /// [`SimulationResult::global_phase`](super::SimulationResult::global_phase).
+    ///
+    /// Off by default: rephasing can change the last bit of the quality weights and
+    /// with it the state-derived stabilization draw.
+    pub fn with_global_phase_tracking(mut self, enabled: bool) -> Self {
+        self.global_phase_tracking = enabled;
+        self
+    }
+
+    /// Sets what happens to local states that drift off unit norm, checked after
+    /// every operation.
+    pub fn with_renormalization(mut self, policy: RenormalizationPolicy) -> Self {
+        self.renormalization = policy;
+        self
+    }
+
+    /// Sets the precision amplitudes are kept at between operations.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Sets how quality weights are scored before an outcome is selected.
+    pub fn with_scoring(mut self, scoring: ScoringMode) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Enables boundary modeling: projections wear down each QDU's boundary
+    /// integrity until the run fails with `OnqError::BoundaryFailure`.
+    pub fn with_boundary_model(mut self, model: BoundaryModel) -> Self {
+        self.boundary_model = Some(model);
+        self
+    }
+
+    /// Disables boundary modeling (the default).
+    pub fn without_boundary_model(mut self) -> Self {
+        self.boundary_model = None;
+        self
+    }
+
+    /// Returns the stabilization semantics version.
+    pub fn semantics(&self) -> SemanticsVersion {
+        self.semantics
+    }
+
+    /// Returns how the stabilization draw is seeded
+    /// (default [`StabilizationSeed::StateDerived`]).
+    pub fn stabilization_seed(&self) -> StabilizationSeed {
+        self.stabilization_seed
+    }
+
+    /// Returns the global norm tolerance (default `1e-6`).
+    pub fn norm_tolerance(&self) -> f64 {
+        self.norm_tolerance
+    }
+
+    /// Returns the amplitude tolerance (default `1e-9`).
+    pub fn amplitude_tolerance(&self) -> f64 {
+        self.amplitude_tolerance
+    }
+
+    /// Returns which state checks run (default [`ValidationMode::Off`]).
+    pub fn validation(&self) -> ValidationMode {
+        self.validation
+    }
+
+    /// Returns when the state checks run (default [`ValidationTiming::EndOnly`]).
+    pub fn validation_timing(&self) -> ValidationTiming {
+        self.validation_timing
+    }
+
+    /// Returns how the state of each node is represented
+    /// (default [`StateRepresentation::Amplitudes`]).
+    pub fn representation(&self) -> StateRepresentation {
+        self.representation
+    }
+
+    /// Returns the backend circuits run on (default [`Backend::TensorNetwork`]).
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Returns `true` if runs record a per-operation timing profile (default `false`).
+    pub fn profiling(&self) -> bool {
+        self.profiling
+    }
+
+    /// Returns `true` if local phases are factored out into a tracked global phase
+    /// (default `false`).
+    pub fn global_phase_tracking(&self) -> bool {
+        self.global_phase_tracking
+    }
+
+    /// Returns the renormalization policy.
+    pub fn renormalization(&self) -> RenormalizationPolicy {
+        self.renormalization
+    }
+
+    /// Returns the precision amplitudes are kept at (default [`Precision::Double`]).
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Returns the scoring mode (default [`ScoringMode::AmplitudeOnly`]).
+    pub fn scoring(&self) -> ScoringMode {
+        self.scoring
+    }
+
+    /// Returns the boundary model, if boundary modeling is enabled.
+    pub fn boundary_model(&self) -> Option<BoundaryModel> {
+        self.boundary_model
+    }
+}
@@ -0,0 +1,126 @@
+// src/simulation/diagnostics.rs
+
+//! Explains how stabilization would resolve the nodes of a state.
+
+use super::engine::{phase_alignment, resonance, selection_weights};
+use super::{SimulatorConfig, StabilizationStrategy};
+use crate::core::state::stabilization_draw;
+use crate::core::{OnqError, PHI, PotentialityState};
+use std::fmt;
+
+/// The score of one quality of a node under stabilization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutcomeScore {
+    /// The quality (0 or 1) scored.
+    pub quality: u8,
+    /// The normalized quality weight `|amplitude|²`.
+    pub weight: f64,
+    /// The phase-coherence factor C_A of the quality's amplitude.
+    pub phase_coherence: f64,
+    /// The pattern-resonance factor C_B of the quality.
+    pub resonance: f64,
+    /// The normalized score the strategy selects from under the configured
+    /// [`ScoringMode`](super::ScoringMode); the weight itself for amplitude-only scoring.
+    pub score: f64,
+    /// Whether the score exceeds the Golden Ratio threshold 1/φ, so the coherence
+    /// filter selects this quality without drawing.
+    pub passes_filter: bool,
+}
+
+/// How stabilization would resolve one node of a state, returned by
+/// [`Simulator::analyze_stabilization`](super::Simulator::analyze_stabilization).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StabilizationAnalysis {
+    /// The IVM node analyzed.
+    pub node: u64,
+    /// The score of Quality0 and Quality1.
+    pub scores: [OutcomeScore; 2],
+    /// Phase alignment of the two amplitudes, `(1 + cos Δφ) / 2`, as in
+    /// [`calculate_global_phase_coherence`](crate::calculate_global_phase_coherence);
+    /// 1 for a node without superposition.
+    pub phase_coherence: f64,
+    /// The deterministic draw in `[0, 1)` derived from the state and the seed.
+    pub draw: f64,
+    /// The quality the simulator's strategy selects from the weights and the draw.
+    pub selected: u8,
+}
+
+impl StabilizationAnalysis {
+    /// Returns `true` if the selection was made by the coherence filter rather than
+    /// by the draw, i.e. some quality passes the filter.
+    pub fn filtered(&self) -> bool {
+        self.scores.iter().any(|score| score.passes_filter)
+    }
+}
+
+impl fmt::Display for StabilizationAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [s0, s1] = self.scores;
+        write!(
+            f,
+            "node {}: W(0)={:.4}, W(1)={:.4}, S(0)={:.4}, S(1)={:.4}, coherence={:.4}, draw={:.6} -> {}",
+            self.node,
+            s0.weight,
+            s1.weight,
+            s0.score,
+            s1.score,
+            self.phase_coherence,
+            self.draw,
+            self.selected
+        )?;
+        if self.filtered() {
+            write!(f, " (passes 1/φ filter)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Scores every node of `targets` in `state` as the engine would when stabilizing
+/// them, without collapsing anything.
+pub(crate) fn analyze(
+    state: &PotentialityState,
+    targets: &[u64],
+    config: &SimulatorConfig,
+    strategy: &dyn StabilizationStrategy,
+) -> Result<Vec<StabilizationAnalysis>, OnqError> {
+    targets
+        .iter()
+        .map(|&node| {
+            let tensor = state
+                .network
+                .get(&node)
+                .ok_or_else(|| OnqError::ReferenceViolation {
+                    message: format!("IVM node {} does not exist in the state", node),
+                })?;
+            let [a, b] = tensor.core_state;
+            let raw = [a.norm_sqr(), b.norm_sqr()];
+            let total = raw[0] + raw[1];
+            if total == 0.0 || !total.is_finite() {
+                return Err(OnqError::Incoherence {
+                    message: format!("Node {} has no stabilizable weight", node),
+                });
+            }
+            let scores = selection_weights(config, &tensor.core_state);
+            let phase_coherence = if raw[0] > 1e-12 && raw[1] > 1e-12 {
+                (1.0 + (a.arg() - b.arg()).cos()) / 2.0
+            } else {
+                1.0
+            };
+            let draw = stabilization_draw(&raw, config.stabilization_seed(), 0, node);
+            Ok(StabilizationAnalysis {
+                node,
+                scores: [0, 1].map(|quality| OutcomeScore {
+                    quality,
+                    weight: raw[quality as usize] / total,
+                    phase_coherence: phase_alignment(tensor.core_state[quality as usize]),
+                    resonance: resonance(quality),
+                    score: scores[quality as usize],
+                    passes_filter: scores[quality as usize] > 1.0 / PHI,
+                }),
+                phase_coherence,
+                draw,
+                selected: strategy.select(scores, draw),
+            })
+        })
+        .collect()
+}
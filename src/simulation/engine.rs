@@ -1,9 +1,42 @@
-use crate::core::{OnqError, PotentialityState, QduId, StableState};
-use crate::operations::Operation;
-use crate::simulation::SimulationResult;
+use crate::core::density::DensityMatrix;
+use crate::core::kernels;
+use crate::core::state::{LocalTensor, axis_rotation, stabilization_draw};
+use crate::core::{
+    DensityState, OnqError, PHI, PotentialityState, QduId, ReferenceFrame, StableState,
+};
+use crate::operations::{Operation, PatternRegistry};
+use crate::simulation::{
+    AmplitudeWeighted, CoherenceFiltered, Precision, ReadoutError, Renormalization,
+    RenormalizationPolicy, ScoringMode, SimulationResult, SimulatorConfig, StabilizationStrategy,
+    StateRepresentation, ValidationMode,
+};
+use crate::validation;
 use num_complex::Complex;
 use num_traits::identities::Zero;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Number of QDUs the Isotropic Vector Matrix holds.
+pub(crate) const IVM_CAPACITY: usize = 64;
+
+/// Most neighbors an IVM node has, i.e. the most bonds a local tensor can hold.
+const MAX_BONDS_PER_NODE: usize = 6;
+
+/// Returns an upper estimate of the bytes an engine over `num_qdus` QDUs holds: every
+/// IVM node's local tensor, the QDU mapping and a full set of bonds per QDU, plus the
+/// per-node density matrices in density-matrix mode. Hash table overhead is not
+/// counted. Counts beyond [`IVM_CAPACITY`] extrapolate as if the IVM were larger.
+pub(crate) fn estimate_memory(num_qdus: usize, representation: StateRepresentation) -> usize {
+    let nodes = num_qdus.max(IVM_CAPACITY);
+    let node = size_of::<u64>() + size_of::<LocalTensor>();
+    let mapping = size_of::<QduId>() + size_of::<u64>();
+    let bond = size_of::<u64>() + size_of::<Vec<Complex<f64>>>() + 4 * size_of::<Complex<f64>>();
+    let density = match representation {
+        StateRepresentation::Amplitudes => 0,
+        StateRepresentation::DensityMatrix => size_of::<u64>() + size_of::<DensityMatrix>(),
+    };
+    nodes * (node + density) + num_qdus * (mapping + MAX_BONDS_PER_NODE * bond)
+}
 
 #[derive(Debug)]
 pub(crate) struct SimulationEngine {
@@ -13,6 +46,49 @@ pub(crate) struct SimulationEngine {
 
     /// The localized Tensor Network bounded by the Isotropic Vector Matrix
     global_state: PotentialityState,
+
+    /// Mixed into the stabilization seed; 0 keeps the purely state-derived seed.
+    stabilization_salt: u64,
+
+    /// The stabilization rule set, tolerances and validation settings in effect.
+    config: SimulatorConfig,
+
+    /// Overrides the outcome selection of the semantics version, if set.
+    strategy: Option<Arc<dyn StabilizationStrategy>>,
+
+    /// Flips recorded stabilization outcomes, if set.
+    readout: Option<Arc<ReadoutError>>,
+
+    /// Number of stabilizations recorded so far, distinguishing repeated readouts.
+    readout_count: u64,
+
+    /// Per-node density matrices, tracked alongside the amplitudes in
+    /// [`StateRepresentation::DensityMatrix`] mode.
+    density: Option<DensityState>,
+
+    /// Phase factored out of the local states, in radians within `(-π, π]`.
+    global_phase: f64,
+
+    /// Number of operations applied so far, numbering recorded renormalizations.
+    operation_count: usize,
+
+    /// Local states rescaled under [`RenormalizationPolicy::WarnAndRenormalize`].
+    renormalizations: Vec<Renormalization>,
+
+    /// State vectors of the nodes holding qudits (basis dimension above 2), keyed by
+    /// IVM node. These nodes take no part in bonds; their binary tensors stay unused.
+    qudits: HashMap<u64, Vec<Complex<f64>>>,
+
+    /// The reference frame of each QDU placed in one; all others share the default frame.
+    frames: HashMap<QduId, ReferenceFrame>,
+
+    /// QDU pairs (smaller ID first) with an established `RelationalLock`, which may
+    /// interact across frames.
+    locks: HashSet<(QduId, QduId)>,
+
+    /// Boundary integrity of each QDU projected so far under the configured
+    /// [`BoundaryModel`](crate::simulation::BoundaryModel).
+    boundaries: HashMap<QduId, f64>,
 }
 
 impl SimulationEngine {
@@ -24,20 +100,30 @@ impl SimulationEngine {
             });
         }
 
-        let mut qdu_indices = HashMap::new();
-
         // This ensures QDU 0, 1, and 2 form a physically connected, contiguous wire.
         let mut sorted_ids: Vec<QduId> = qdu_ids.iter().cloned().collect();
         sorted_ids.sort();
+        Self::init_with_order(&sorted_ids)
+    }
+
+    /// Initializes the engine with `order[i]` mapped to IVM node `i`.
+    pub(crate) fn init_with_order(order: &[QduId]) -> Result<Self, OnqError> {
+        let mut qdu_indices = HashMap::new();
 
         // Map the requested QDUs to the 64 available IVM slots
-        for (i, qdu_id) in sorted_ids.into_iter().enumerate() {
-            if i >= 64 {
-                return Err(OnqError::SimulationError {
-                    message: "Hardware Limit Exceeded: The Isotropic Vector Matrix supports a maximum of 64 localized QDUs.".to_string()
+        if order.len() > IVM_CAPACITY {
+            return Err(OnqError::CapacityExceeded {
+                requested: order.len(),
+                capacity: IVM_CAPACITY,
+                required_bytes: estimate_memory(order.len(), StateRepresentation::Amplitudes),
+            });
+        }
+        for (i, qdu_id) in order.iter().enumerate() {
+            if qdu_indices.insert(*qdu_id, i as u64).is_some() {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("{} appears more than once in the QDU order", qdu_id),
                 });
             }
-            qdu_indices.insert(qdu_id, i as u64);
         }
 
         // Initialize the Tensor Network
@@ -46,6 +132,19 @@ impl SimulationEngine {
         Ok(Self {
             qdu_indices,
             global_state,
+            stabilization_salt: 0,
+            config: SimulatorConfig::default(),
+            strategy: None,
+            readout: None,
+            readout_count: 0,
+            density: None,
+            global_phase: 0.0,
+            operation_count: 0,
+            renormalizations: Vec::new(),
+            qudits: HashMap::new(),
+            frames: HashMap::new(),
+            locks: HashSet::new(),
+            boundaries: HashMap::new(),
         })
     }
 
@@ -53,34 +152,439 @@ impl SimulationEngine {
         &self.global_state
     }
 
+    /// Returns the density matrices, if the engine runs in density-matrix mode.
+    pub(crate) fn density(&self) -> Option<&DensityState> {
+        self.density.as_ref()
+    }
+
+    /// Returns the quality probabilities `[P(0), P(1)]` of every mapped QDU, read from
+    /// the density diagonals in density-matrix mode. Qudits are left out.
+    pub(crate) fn marginals(&self) -> BTreeMap<QduId, [f64; 2]> {
+        self.qdu_indices
+            .iter()
+            .filter(|(_, physical_id)| !self.qudits.contains_key(physical_id))
+            .filter_map(|(qdu, physical_id)| {
+                let [w0, w1] = self.weights(*physical_id)?;
+                let norm = w0 + w1;
+                (norm > 0.0).then(|| (*qdu, [w0 / norm, w1 / norm]))
+            })
+            .collect()
+    }
+
+    /// Returns the QDUs with a basis dimension above 2, in order.
+    pub(crate) fn qudit_qdus(&self) -> Vec<QduId> {
+        self.qdu_indices
+            .iter()
+            .filter(|(_, physical_id)| self.qudits.contains_key(physical_id))
+            .map(|(qdu, _)| *qdu)
+            .collect()
+    }
+
+    /// Returns the phase factored out of the local states (0 unless global phase
+    /// tracking is enabled).
+    pub(crate) fn global_phase(&self) -> f64 {
+        self.global_phase
+    }
+
+    /// Sets the phase factored out of the local states, e.g. when resuming.
+    pub(crate) fn set_global_phase(&mut self, phase: f64) {
+        self.global_phase = wrap_phase(phase);
+    }
+
+    /// Rotates the local state of a node so its leading amplitude (Quality0, or
+    /// Quality1 if Quality0 carries no weight) is real and non-negative, adding the
+    /// removed phase to the global phase.
+    fn factor_global_phase(&mut self, physical_id: u64) {
+        let tolerance = self.config.amplitude_tolerance();
+        let Some(tensor) = self.global_state.network.get_mut(&physical_id) else {
+            return;
+        };
+        let [a, b] = tensor.core_state;
+        let norm = a.norm_sqr() + b.norm_sqr();
+        let leading = if a.norm_sqr() > tolerance * norm {
+            a
+        } else {
+            b
+        };
+        let phase = leading.arg();
+        if phase == 0.0 || norm == 0.0 {
+            return;
+        }
+        let rotation = Complex::from_polar(1.0, -phase);
+        tensor.core_state = [a * rotation, b * rotation];
+        self.global_phase = wrap_phase(self.global_phase + phase);
+    }
+
+    /// Applies a single-node matrix to the amplitudes of `physical_id`, evaluated in
+    /// double-double arithmetic under [`Precision::Extended`].
+    fn apply_local(
+        &mut self,
+        physical_id: u64,
+        matrix: &[[Complex<f64>; 2]; 2],
+    ) -> Result<(), OnqError> {
+        if self.config.precision() != Precision::Extended {
+            return self
+                .global_state
+                .apply_local_operation(physical_id, matrix)
+                .map_err(|e| OnqError::SimulationError { message: e });
+        }
+        let tensor = self
+            .global_state
+            .network
+            .get_mut(&physical_id)
+            .ok_or_else(|| OnqError::SimulationError {
+                message: format!("QDU {} does not exist in the network.", physical_id),
+            })?;
+        kernels::apply_matrix_extended(&mut tensor.core_state, matrix);
+        Ok(())
+    }
+
+    /// Returns the local states rescaled so far.
+    pub(crate) fn renormalizations(&self) -> &[Renormalization] {
+        &self.renormalizations
+    }
+
+    /// Rescales the local state of `qdu` to unit norm if it drifted further than the
+    /// amplitude tolerance, recording it under
+    /// [`RenormalizationPolicy::WarnAndRenormalize`].
+    fn renormalize(&mut self, qdu: QduId) -> Result<(), OnqError> {
+        let physical_id = self.get_physical_id(&qdu)?;
+        let tolerance = self.config.amplitude_tolerance();
+        let Some(tensor) = self.global_state.network.get_mut(&physical_id) else {
+            return Ok(());
+        };
+        let [a, b] = tensor.core_state;
+        let norm_sq = a.norm_sqr() + b.norm_sqr();
+        if !norm_sq.is_finite() || norm_sq == 0.0 || (norm_sq - 1.0).abs() <= tolerance {
+            return Ok(());
+        }
+        let scale = norm_sq.sqrt().recip();
+        tensor.core_state = [a * scale, b * scale];
+        if self.config.renormalization() == RenormalizationPolicy::WarnAndRenormalize {
+            self.renormalizations.push(Renormalization {
+                operation: self.operation_count,
+                qdu,
+                norm_sq,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the number of mapped QDUs.
+    pub(crate) fn qdu_count(&self) -> usize {
+        self.qdu_indices.len()
+    }
+
+    /// Returns the IVM node of every mapped QDU.
+    pub(crate) fn qdu_nodes(&self) -> BTreeMap<QduId, u64> {
+        self.qdu_indices.iter().map(|(q, n)| (*q, *n)).collect()
+    }
+
+    /// Returns the amplitude of the joint qualities `outcome` (see
+    /// [`PotentialityState::amplitude_of`]).
+    pub(crate) fn amplitude_of(&self, outcome: &[(QduId, u8)]) -> Result<Complex<f64>, OnqError> {
+        let assignment = physical_outcome(outcome, |qdu| self.qdu_indices.get(qdu).copied())?;
+        self.global_state
+            .amplitude_of(&assignment)
+            .map(|amplitude| amplitude * Complex::from_polar(1.0, self.global_phase))
+            .map_err(|e| OnqError::SimulationError { message: e })
+    }
+
+    /// Returns the probability of the joint qualities `outcome`, read from the density
+    /// diagonals in density-matrix mode.
+    pub(crate) fn probability_of_outcome(&self, outcome: &[(QduId, u8)]) -> Result<f64, OnqError> {
+        let assignment = physical_outcome(outcome, |qdu| self.qdu_indices.get(qdu).copied())?;
+        match &self.density {
+            Some(density) => density.probability_of(&assignment),
+            None => self
+                .global_state
+                .amplitude_of(&assignment)
+                .map(|amplitude| amplitude.norm_sqr()),
+        }
+        .map_err(|e| OnqError::SimulationError { message: e })
+    }
+
+    /// Returns the unnormalized quality weights of a node.
+    fn weights(&self, physical_id: u64) -> Option<[f64; 2]> {
+        match &self.density {
+            Some(density) => density.weights(physical_id),
+            None => {
+                let [a, b] = self.global_state.network.get(&physical_id)?.core_state;
+                Some([a.norm_sqr(), b.norm_sqr()])
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn get_state_mut_for_test(&mut self) -> &mut PotentialityState {
         &mut self.global_state
     }
 
-    #[allow(dead_code)]
+    /// Replaces the global state, checking that every mapped QDU has a node in it
+    /// holding a non-zero local state.
     pub(crate) fn set_state(&mut self, state: PotentialityState) -> Result<(), OnqError> {
+        for (qdu, physical_id) in &self.qdu_indices {
+            let Some(tensor) = state.network.get(physical_id) else {
+                return Err(OnqError::ReferenceViolation {
+                    message: format!("Initial state has no IVM node {} for {}", physical_id, qdu),
+                });
+            };
+            if tensor.core_state.iter().all(|c| c.norm_sqr() < 1e-12) {
+                return Err(OnqError::Incoherence {
+                    message: format!("Initial state of {} has zero norm", qdu),
+                });
+            }
+        }
         self.global_state = state;
+        if self.density.is_some() {
+            self.density = Some(DensityState::from_state(&self.global_state));
+        }
+        Ok(())
+    }
+
+    /// Sets the value mixed into the stabilization seed (see `stabilize_with_salt`).
+    pub(crate) fn set_stabilization_salt(&mut self, salt: u64) {
+        self.stabilization_salt = salt;
+    }
+
+    /// Sets the stabilization rule set, tolerances and validation settings.
+    /// Entering density-matrix mode starts from the pure density matrices of the
+    /// current amplitudes.
+    pub(crate) fn set_config(&mut self, config: SimulatorConfig) {
+        self.config = config;
+        self.density = match config.representation() {
+            StateRepresentation::Amplitudes => None,
+            StateRepresentation::DensityMatrix => self
+                .density
+                .take()
+                .or_else(|| Some(DensityState::from_state(&self.global_state))),
+        };
+    }
+
+    /// Sets the basis dimension of QDUs, starting every qudit in its ground level.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` for a dimension below 2, or for a qudit in
+    /// density-matrix mode, which tracks binary nodes only.
+    pub(crate) fn set_dimensions(
+        &mut self,
+        dimensions: &BTreeMap<QduId, usize>,
+    ) -> Result<(), OnqError> {
+        for (qdu, &dimension) in dimensions {
+            if dimension < 2 {
+                return Err(OnqError::InvalidOperation {
+                    message: format!(
+                        "{} has basis dimension {}; at least 2 is required",
+                        qdu, dimension
+                    ),
+                });
+            }
+            let physical_id = self.get_physical_id(qdu)?;
+            if dimension == 2 {
+                self.qudits.remove(&physical_id);
+                continue;
+            }
+            if self.density.is_some() {
+                return Err(OnqError::InvalidOperation {
+                    message: format!(
+                        "{} has basis dimension {}; density-matrix mode supports binary QDUs only",
+                        qdu, dimension
+                    ),
+                });
+            }
+            let mut levels = vec![Complex::new(0.0, 0.0); dimension];
+            levels[0] = Complex::new(1.0, 0.0);
+            self.qudits.insert(physical_id, levels);
+        }
         Ok(())
     }
 
+    /// Sets the reference frames QDUs were placed in.
+    pub(crate) fn set_frames(&mut self, frames: &BTreeMap<QduId, ReferenceFrame>) {
+        self.frames = frames
+            .iter()
+            .map(|(qdu, frame)| (*qdu, frame.clone()))
+            .collect();
+    }
+
+    /// Rejects an interaction between `a` and `b` across reference frames unless a
+    /// `RelationalLock` between them is established.
+    fn check_frames(&self, a: QduId, b: QduId) -> Result<(), OnqError> {
+        let (frame_a, frame_b) = (self.frames.get(&a), self.frames.get(&b));
+        if frame_a == frame_b || self.locks.contains(&lock_key(a, b)) {
+            return Ok(());
+        }
+        let describe = |frame: Option<&ReferenceFrame>| {
+            frame.map_or_else(
+                || "the default frame".to_string(),
+                |frame| frame.to_string(),
+            )
+        };
+        Err(OnqError::ReferenceViolation {
+            message: format!(
+                "{} in {} and {} in {} interact across frames without an established RelationalLock",
+                a,
+                describe(frame_a),
+                b,
+                describe(frame_b)
+            ),
+        })
+    }
+
+    /// Wears down the boundary of `qdu` for one projection under the configured
+    /// boundary model, failing once its integrity falls below the threshold.
+    fn wear_boundary(&mut self, qdu: QduId) -> Result<(), OnqError> {
+        let Some(model) = self.config.boundary_model() else {
+            return Ok(());
+        };
+        let integrity = self.boundaries.entry(qdu).or_insert(1.0);
+        *integrity *= 1.0 - model.decay();
+        if *integrity < model.threshold() {
+            return Err(OnqError::BoundaryFailure {
+                qdu_id: qdu,
+                message: format!(
+                    "Boundary integrity {:.4} fell below {} under repeated projection",
+                    integrity,
+                    model.threshold()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the basis dimension of the QDU on `physical_id`.
+    fn dimension_of(&self, physical_id: u64) -> usize {
+        self.qudits.get(&physical_id).map_or(2, Vec::len)
+    }
+
+    /// Sets the strategy overriding the semantics version's outcome selection.
+    pub(crate) fn set_strategy(&mut self, strategy: Option<Arc<dyn StabilizationStrategy>>) {
+        self.strategy = strategy;
+    }
+
+    /// Sets the readout error model applied to recorded outcomes.
+    pub(crate) fn set_readout(&mut self, readout: Option<Arc<ReadoutError>>) {
+        self.readout = readout;
+    }
+
+    /// Runs the state checks selected by the configured [`ValidationMode`].
+    pub(crate) fn validate(&self) -> Result<(), OnqError> {
+        match self.config.validation() {
+            ValidationMode::Off => Ok(()),
+            ValidationMode::Basic => validation::check_normalization(
+                &self.global_state,
+                Some(self.config.norm_tolerance()),
+            ),
+            ValidationMode::Strict => validation::validate_state(
+                &self.global_state,
+                self.qdu_indices.len(),
+                Some(self.config.norm_tolerance()),
+                None,
+                Some(self.config.amplitude_tolerance()),
+            ),
+        }
+    }
+
     /// The new O(1) Localized Execution Engine
     pub(crate) fn apply_operation(&mut self, op: &Operation) -> Result<(), OnqError> {
+        // Qudits accept only dimension-generic operations
+        if !self.qudits.is_empty()
+            && !matches!(
+                op,
+                Operation::QuditPattern { .. }
+                    | Operation::Delay { .. }
+                    | Operation::Snapshot { .. }
+            )
+        {
+            for qdu in op.involved_qdus() {
+                let physical_id = self.get_physical_id(&qdu)?;
+                if let Some(levels) = self.qudits.get(&physical_id) {
+                    return Err(OnqError::InvalidOperation {
+                        message: format!(
+                            "{} on {} requires a binary QDU; it has basis dimension {}",
+                            op.kind(),
+                            qdu,
+                            levels.len()
+                        ),
+                    });
+                }
+            }
+        }
+
         match op {
             Operation::PhaseShift { target, theta } => {
                 let physical_id = self.get_physical_id(target)?;
                 let matrix = phase_shift_matrix(*theta);
-                self.global_state
-                    .apply_local_operation(physical_id, &matrix)
-                    .map_err(|e| OnqError::SimulationError { message: e })?;
+                self.apply_local(physical_id, &matrix)?;
             }
 
             Operation::InteractionPattern { target, pattern_id } => {
                 let physical_id = self.get_physical_id(target)?;
                 let matrix = self.get_interaction_matrix(pattern_id)?;
-                self.global_state
-                    .apply_local_operation(physical_id, &matrix)
-                    .map_err(|e| OnqError::SimulationError { message: e })?;
+                self.apply_local(physical_id, &matrix)?;
+            }
+
+            Operation::MatrixPattern { target, matrix } => {
+                let physical_id = self.get_physical_id(target)?;
+                if !is_unitary(matrix, self.config.amplitude_tolerance()) {
+                    return Err(OnqError::InvalidOperation {
+                        message: format!(
+                            "MatrixPattern on {} is not unitary: {:?}",
+                            target, matrix
+                        ),
+                    });
+                }
+                self.apply_local(physical_id, matrix)?;
+            }
+
+            Operation::QuditPattern { target, matrix } => {
+                let physical_id = self.get_physical_id(target)?;
+                let dimension = self.dimension_of(physical_id);
+                if matrix.len() != dimension || matrix.iter().any(|row| row.len() != dimension) {
+                    return Err(OnqError::InvalidOperation {
+                        message: format!(
+                            "QuditPattern on {} must be {}x{} to match its basis dimension",
+                            target, dimension, dimension
+                        ),
+                    });
+                }
+                if !is_unitary_dense(matrix, self.config.amplitude_tolerance()) {
+                    return Err(OnqError::InvalidOperation {
+                        message: format!("QuditPattern on {} is not unitary: {:?}", target, matrix),
+                    });
+                }
+                match self.qudits.get_mut(&physical_id) {
+                    Some(levels) => {
+                        let updated: Vec<Complex<f64>> = matrix
+                            .iter()
+                            .map(|row| row.iter().zip(levels.iter()).map(|(m, a)| m * a).sum())
+                            .collect();
+                        *levels = updated;
+                    }
+                    None => {
+                        let local = [[matrix[0][0], matrix[0][1]], [matrix[1][0], matrix[1][1]]];
+                        self.apply_local(physical_id, &local)?;
+                    }
+                }
+            }
+
+            Operation::BroadcastPattern {
+                targets,
+                pattern_id,
+            } => {
+                let matrix = self.get_interaction_matrix(pattern_id)?;
+                for target in targets {
+                    let physical_id = self.get_physical_id(target)?;
+                    self.apply_local(physical_id, &matrix)?;
+                }
+            }
+
+            Operation::BroadcastPhaseShift { targets, theta } => {
+                let matrix = phase_shift_matrix(*theta);
+                for target in targets {
+                    let physical_id = self.get_physical_id(target)?;
+                    self.apply_local(physical_id, &matrix)?;
+                }
             }
 
             Operation::ControlledInteraction {
@@ -90,6 +594,7 @@ impl SimulationEngine {
             } => {
                 let phys_control = self.get_physical_id(control)?;
                 let phys_target = self.get_physical_id(target)?;
+                self.check_frames(*control, *target)?;
 
                 // 1. Enforce IVM Geometry & Build the Bond
                 self.global_state
@@ -101,11 +606,62 @@ impl SimulationEngine {
 
                 // (Note: In a full PEPS network, applying U to a bonded state updates the bond tensor.
                 // For now, we apply it locally to simulate the gate completion).
+                self.apply_local(phys_target, &matrix)?;
+            }
+
+            Operation::PauliProduct { terms, theta } => {
+                let mut physical_terms = Vec::with_capacity(terms.len());
+                for (qdu, axis) in terms {
+                    let physical_id = self.get_physical_id(qdu)?;
+                    if physical_terms.iter().any(|(id, _)| *id == physical_id) {
+                        return Err(OnqError::InvalidOperation {
+                            message: format!("PauliProduct lists {} more than once", qdu),
+                        });
+                    }
+                    let matrix = self.get_interaction_matrix(axis.pattern_id())?;
+                    physical_terms.push((physical_id, matrix));
+                }
+
+                // Enforce frame rules and IVM geometry along the chain of coupled QDUs
+                for pair in terms.windows(2) {
+                    self.check_frames(pair[0].0, pair[1].0)?;
+                }
+                for pair in physical_terms.windows(2) {
+                    self.global_state
+                        .apply_entanglement(pair[0].0, pair[1].0)
+                        .map_err(|e| OnqError::InvalidOperation { message: e })?;
+                }
+
                 self.global_state
-                    .apply_local_operation(phys_target, &matrix)
+                    .apply_pauli_product(&physical_terms, *theta)
                     .map_err(|e| OnqError::SimulationError { message: e })?;
             }
 
+            Operation::Project { target, onto } => {
+                let physical_id = self.get_physical_id(target)?;
+                self.global_state
+                    .project(physical_id, onto.index())
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+                self.wear_boundary(*target)?;
+            }
+
+            Operation::Relax { target, rate } => {
+                let physical_id = self.get_physical_id(target)?;
+                self.global_state
+                    .relax(physical_id, *rate)
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+            }
+
+            Operation::Permute { mapping } => {
+                let mut physical_mapping = Vec::with_capacity(mapping.len());
+                for (from, to) in mapping {
+                    physical_mapping.push((self.get_physical_id(from)?, self.get_physical_id(to)?));
+                }
+                self.global_state
+                    .permute(&physical_mapping)
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+            }
+
             Operation::RelationalLock {
                 qdu1,
                 qdu2,
@@ -113,6 +669,7 @@ impl SimulationEngine {
                 ..
             } => {
                 if !*establish {
+                    self.locks.remove(&lock_key(*qdu1, *qdu2));
                     return Ok(());
                 }
 
@@ -123,8 +680,19 @@ impl SimulationEngine {
                 self.global_state
                     .apply_entanglement(phys_1, phys_2)
                     .map_err(|e| OnqError::InvalidOperation { message: e })?;
+                self.locks.insert(lock_key(*qdu1, *qdu2));
             }
 
+            Operation::Delay { targets, .. } => {
+                // No timing model yet: idling leaves the state untouched
+                for target in targets {
+                    self.get_physical_id(target)?;
+                }
+            }
+
+            // Recorded by the simulator; the state is untouched
+            Operation::Snapshot { .. } => {}
+
             Operation::Stabilize { .. } => {
                 return Err(OnqError::InvalidOperation {
                     message: "Stabilize operation should not be passed directly to apply_operation"
@@ -133,11 +701,144 @@ impl SimulationEngine {
             }
         };
 
+        if self.config.renormalization().renormalizes() {
+            for qdu in op.involved_qdus() {
+                self.renormalize(qdu)?;
+            }
+        }
+        self.operation_count += 1;
+
+        if self.config.global_phase_tracking() {
+            for qdu in op.involved_qdus() {
+                let physical_id = self.get_physical_id(&qdu)?;
+                self.factor_global_phase(physical_id);
+            }
+        }
+
+        if self.density.is_some() {
+            self.apply_to_density(op)?;
+        }
+
         // Optional: Localized norm check
         // validation::check_normalization(&self.global_state, None)?;
         Ok(())
     }
 
+    /// Mirrors an operation already applied to the amplitudes onto the density
+    /// matrices. Geometry checks have passed by then; only the local updates remain.
+    /// Each update is applied to its node in place as it is derived.
+    fn apply_to_density(&mut self, op: &Operation) -> Result<(), OnqError> {
+        match op {
+            Operation::PhaseShift { target, theta } => {
+                let physical_id = self.get_physical_id(target)?;
+                self.apply_density_unitary(physical_id, &phase_shift_matrix(*theta))?;
+            }
+            Operation::InteractionPattern { target, pattern_id }
+            | Operation::ControlledInteraction {
+                target, pattern_id, ..
+            } => {
+                let matrix = self.get_interaction_matrix(pattern_id)?;
+                let physical_id = self.get_physical_id(target)?;
+                self.apply_density_unitary(physical_id, &matrix)?;
+            }
+            Operation::MatrixPattern { target, matrix } => {
+                let physical_id = self.get_physical_id(target)?;
+                self.apply_density_unitary(physical_id, matrix)?;
+            }
+            // Density-matrix mode holds binary QDUs only, so the matrix is 2x2
+            Operation::QuditPattern { target, matrix } => {
+                let physical_id = self.get_physical_id(target)?;
+                let local = [[matrix[0][0], matrix[0][1]], [matrix[1][0], matrix[1][1]]];
+                self.apply_density_unitary(physical_id, &local)?;
+            }
+            Operation::BroadcastPattern {
+                targets,
+                pattern_id,
+            } => {
+                let matrix = self.get_interaction_matrix(pattern_id)?;
+                for target in targets {
+                    let physical_id = self.get_physical_id(target)?;
+                    self.apply_density_unitary(physical_id, &matrix)?;
+                }
+            }
+            Operation::BroadcastPhaseShift { targets, theta } => {
+                let matrix = phase_shift_matrix(*theta);
+                for target in targets {
+                    let physical_id = self.get_physical_id(target)?;
+                    self.apply_density_unitary(physical_id, &matrix)?;
+                }
+            }
+            Operation::PauliProduct { terms, theta } => {
+                // The same mean-field update as the amplitudes, with tr(ρP) expectations
+                // taken on the pre-operation matrices before any node is updated
+                let density = self.density.as_ref().expect("density mode");
+                let mut axes = Vec::with_capacity(terms.len());
+                let mut expectations = Vec::with_capacity(terms.len());
+                for (qdu, axis) in terms {
+                    let physical_id = self.get_physical_id(qdu)?;
+                    let matrix = self.get_interaction_matrix(axis.pattern_id())?;
+                    expectations.push(
+                        density
+                            .expectation(physical_id, &matrix)
+                            .map_err(|e| OnqError::SimulationError { message: e })?,
+                    );
+                    axes.push((physical_id, matrix));
+                }
+                for (k, (physical_id, axis)) in axes.iter().enumerate() {
+                    let rest: f64 = expectations
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != k)
+                        .map(|(_, e)| e)
+                        .product();
+                    self.apply_density_unitary(*physical_id, &axis_rotation(axis, theta * rest))?;
+                }
+            }
+            Operation::Project { target, onto } => {
+                let physical_id = self.get_physical_id(target)?;
+                let density = self.density.as_mut().expect("density mode");
+                density
+                    .project(physical_id, onto.index())
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+            }
+            Operation::Relax { target, rate } => {
+                let physical_id = self.get_physical_id(target)?;
+                let density = self.density.as_mut().expect("density mode");
+                density
+                    .amplitude_damp(physical_id, *rate)
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+            }
+            Operation::Permute { mapping } => {
+                let mut physical_mapping = Vec::with_capacity(mapping.len());
+                for (from, to) in mapping {
+                    physical_mapping.push((self.get_physical_id(from)?, self.get_physical_id(to)?));
+                }
+                let density = self.density.as_mut().expect("density mode");
+                density
+                    .permute(&physical_mapping)
+                    .map_err(|e| OnqError::InvalidOperation { message: e })?;
+            }
+            Operation::RelationalLock { .. }
+            | Operation::Delay { .. }
+            | Operation::Snapshot { .. }
+            | Operation::Stabilize { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Applies `matrix` to the density matrix of node `physical_id` in place.
+    fn apply_density_unitary(
+        &mut self,
+        physical_id: u64,
+        matrix: &[[Complex<f64>; 2]; 2],
+    ) -> Result<(), OnqError> {
+        self.density
+            .as_mut()
+            .expect("density mode")
+            .apply_unitary(physical_id, matrix)
+            .map_err(|e| OnqError::SimulationError { message: e })
+    }
+
     /// Helper to map abstract QduId to the physical u64 IVM node ID
     fn get_physical_id(&self, qdu_id: &QduId) -> Result<u64, OnqError> {
         self.qdu_indices
@@ -162,107 +863,298 @@ impl SimulationEngine {
         let mut target_ids = Vec::new();
         for qdu_id in targets {
             target_ids.push(self.get_physical_id(qdu_id)?);
+            self.wear_boundary(*qdu_id)?;
+        }
+
+        // Qudits resolve from their own state vectors
+        let mut levels = HashMap::new();
+        for &physical_id in &target_ids {
+            if self.qudits.contains_key(&physical_id) {
+                levels.insert(physical_id, self.stabilize_qudit(physical_id)?);
+            }
+        }
+        target_ids.retain(|physical_id| !levels.contains_key(physical_id));
+
+        // In density-matrix mode the draw is taken from the diagonals: hand them to the
+        // amplitude network as a real local state before collapsing
+        if let Some(density) = &self.density {
+            for &physical_id in &target_ids {
+                let [w0, w1] = density.weights(physical_id).unwrap_or([1.0, 0.0]);
+                if let Some(tensor) = self.global_state.network.get_mut(&physical_id) {
+                    tensor.core_state = [
+                        Complex::new(w0.max(0.0).sqrt(), 0.0),
+                        Complex::new(w1.max(0.0).sqrt(), 0.0),
+                    ];
+                }
+            }
         }
 
         // 2. Run the deterministic, geometric collapse!
+        let filtered = CoherenceFiltered::default();
+        let strategy: &dyn StabilizationStrategy = match &self.strategy {
+            Some(strategy) => strategy.as_ref(),
+            None if self.config.semantics().coherence_filtered() => &filtered,
+            None => &AmplitudeWeighted,
+        };
+        let config = self.config;
         let outcomes = self
             .global_state
-            .stabilize(&target_ids)
+            .stabilize_weighted(
+                &target_ids,
+                config.stabilization_seed(),
+                self.stabilization_salt,
+                strategy,
+                |amplitudes| selection_weights(&config, amplitudes),
+            )
             .map_err(|e| OnqError::SimulationError { message: e })?;
 
+        if let Some(density) = self.density.as_mut() {
+            for (&physical_id, &quality) in &outcomes {
+                density
+                    .collapse(physical_id, quality as usize)
+                    .map_err(|e| OnqError::SimulationError { message: e })?;
+            }
+        }
+
         // 3. Record the results back into the VM's log
+        result.set_semantics(self.config.semantics());
         for target_qdu_id in targets {
             let phys_id = self.get_physical_id(target_qdu_id)?;
-            if let Some(&quality) = outcomes.get(&phys_id) {
+            if let Some(&level) = levels.get(&phys_id) {
+                // Readout error models binary QDUs only
+                result.record_stable_state(*target_qdu_id, StableState::ResolvedQuality(level));
+                result.record_readout(*target_qdu_id, false);
+            } else if let Some(&quality) = outcomes.get(&phys_id) {
+                // Readout error corrupts the report, not the collapsed state
+                let flipped = self.readout.as_ref().is_some_and(|readout| {
+                    readout.flips(target_qdu_id, self.stabilization_salt, self.readout_count)
+                });
+                self.readout_count += 1;
+                let reported = if flipped { quality ^ 1 } else { quality };
                 result.record_stable_state(
                     *target_qdu_id,
-                    StableState::ResolvedQuality(quality as u64),
+                    StableState::ResolvedQuality(reported as u64),
                 );
+                result.record_readout(*target_qdu_id, flipped);
             }
         }
 
         Ok(())
     }
 
+    /// Resets `targets` to Quality0 (level 0 for qudits) by a projective reset: each is
+    /// stabilized, then flipped back if it resolved to Quality1. The collapse follows the
+    /// configured stabilization rules, but readout errors do not affect the reset.
+    pub(crate) fn reset(&mut self, targets: &[QduId]) -> Result<(), OnqError> {
+        let mut result = SimulationResult::new();
+        self.stabilize(targets, &mut result)?;
+        for qdu in targets {
+            let physical_id = self.get_physical_id(qdu)?;
+            if let Some(state) = self.qudits.get_mut(&physical_id) {
+                state.iter_mut().for_each(|a| *a = Complex::zero());
+                state[0] = Complex::new(1.0, 0.0);
+                continue;
+            }
+            let reported = result
+                .get_stable_state(qdu)
+                .and_then(StableState::get_resolved_value)
+                .unwrap_or(0);
+            let quality = if result.readout_flipped(qdu) {
+                reported ^ 1
+            } else {
+                reported
+            };
+            if quality == 1 {
+                self.apply_operation(&Operation::InteractionPattern {
+                    target: *qdu,
+                    pattern_id: "QualityFlip".to_string(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the qudit on `physical_id` to one basis level and collapses it there.
+    ///
+    /// The level is drawn from the normalized level weights as a binary quality is;
+    /// under coherence-filtered semantics a level weighing more than 1/φ is selected
+    /// outright. Custom strategies and scoring modes apply to binary QDUs only.
+    fn stabilize_qudit(&mut self, physical_id: u64) -> Result<u64, OnqError> {
+        let weights: Vec<f64> = self.qudits[&physical_id]
+            .iter()
+            .map(|a| a.norm_sqr())
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 || !total.is_finite() {
+            return Err(OnqError::Incoherence {
+                message: format!("Qudit on node {} has no stabilizable weight", physical_id),
+            });
+        }
+        let draw = stabilization_draw(
+            &weights,
+            self.config.stabilization_seed(),
+            self.stabilization_salt,
+            physical_id,
+        );
+
+        let filtered = if self.strategy.is_none() && self.config.semantics().coherence_filtered() {
+            weights.iter().position(|w| w / total > 1.0 / PHI)
+        } else {
+            None
+        };
+        let level = filtered.unwrap_or_else(|| {
+            let mut cumulative = 0.0;
+            weights
+                .iter()
+                .position(|w| {
+                    cumulative += w / total;
+                    *w > 0.0 && cumulative >= draw
+                })
+                .or_else(|| weights.iter().rposition(|w| *w > 0.0))
+                .unwrap_or(0)
+        });
+
+        let state = self.qudits.get_mut(&physical_id).expect("qudit node");
+        state.iter_mut().for_each(|a| *a = Complex::zero());
+        state[level] = Complex::new(1.0, 0.0);
+        Ok(level as u64)
+    }
+
     /// Gets the 2x2 matrix for a given interaction pattern ID.
+    /// Unknown IDs produce an error suggesting the closest registered patterns.
     fn get_interaction_matrix(&self, pattern_id: &str) -> Result<[[Complex<f64>; 2]; 2], OnqError> {
-        use std::f64::consts::{FRAC_1_SQRT_2, PI};
-        const PHI: f64 = 1.618_033_988_749_895;
-        let i = Complex::i();
-        let exp_i_pi_4 = Complex::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2);
-        let exp_neg_i_pi_4 = Complex::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
-
-        match pattern_id {
-            "Identity" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), Complex::new(1.0, 0.0)],
-            ]),
-            "QualityFlip" => Ok([
-                [Complex::zero(), Complex::new(1.0, 0.0)],
-                [Complex::new(1.0, 0.0), Complex::zero()],
-            ]),
-            "PhaseIntroduce" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), Complex::new(-1.0, 0.0)],
-            ]),
-            "Superposition" => Ok([
-                [
-                    Complex::new(FRAC_1_SQRT_2, 0.0),
-                    Complex::new(FRAC_1_SQRT_2, 0.0),
-                ],
-                [
-                    Complex::new(FRAC_1_SQRT_2, 0.0),
-                    Complex::new(-FRAC_1_SQRT_2, 0.0),
-                ],
-            ]),
-            "PhiRotate" => {
-                let theta = PI / PHI;
-                let (sin_a, cos_a) = (theta / 2.0).sin_cos();
-                Ok([
-                    [Complex::new(cos_a, 0.0), Complex::new(-sin_a, 0.0)],
-                    [Complex::new(sin_a, 0.0), Complex::new(cos_a, 0.0)],
-                ])
-            }
-            "PhiXRotate" => {
-                let theta = PI / PHI;
-                let (sin_a, cos_a) = (theta / 2.0).sin_cos();
-                Ok([
-                    [Complex::new(cos_a, 0.0), -i * sin_a],
-                    [-i * sin_a, Complex::new(cos_a, 0.0)],
-                ])
-            }
-            "SqrtFlip" => Ok([
-                [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
-                [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
-            ]),
-            "SqrtFlip_Inv" => Ok([
-                [Complex::new(0.5, -0.5), Complex::new(0.5, 0.5)],
-                [Complex::new(0.5, 0.5), Complex::new(0.5, -0.5)],
-            ]),
-            "HalfPhase" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), i],
-            ]),
-            "QualitativeY" => Ok([[Complex::zero(), -i], [i, Complex::zero()]]),
-            "QuarterPhase" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), exp_i_pi_4],
-            ]),
-            "HalfPhase_Inv" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), -i],
-            ]),
-            "QuarterPhase_Inv" => Ok([
-                [Complex::new(1.0, 0.0), Complex::zero()],
-                [Complex::zero(), exp_neg_i_pi_4],
-            ]),
-            _ => Err(OnqError::InvalidOperation {
-                message: format!("Interaction Pattern '{}' is not defined", pattern_id),
-            }),
-        }
+        let registry = PatternRegistry::builtin();
+        registry.matrix(pattern_id).ok_or_else(|| {
+            let suggestions = registry.suggest(pattern_id);
+            let message = if suggestions.is_empty() {
+                format!("Interaction Pattern '{}' is not defined", pattern_id)
+            } else {
+                format!(
+                    "Interaction Pattern '{}' is not defined; did you mean '{}'?",
+                    pattern_id,
+                    suggestions.join("' or '")
+                )
+            };
+            OnqError::InvalidOperation { message }
+        })
     }
 } // <-- END OF impl SimulationEngine
 
+/// Returns the normalized weights the stabilization strategy selects from: the quality
+/// weights of `amplitudes`, computed at the configured precision and scaled by the
+/// configured [`ScoringMode`].
+pub(crate) fn selection_weights(
+    config: &SimulatorConfig,
+    amplitudes: &[Complex<f64>; 2],
+) -> [f64; 2] {
+    let weights = match config.precision() {
+        Precision::Extended => kernels::normalized_weights_extended(amplitudes),
+        _ => {
+            let [w0, w1] = [amplitudes[0].norm_sqr(), amplitudes[1].norm_sqr()];
+            let total = w0 + w1;
+            [w0 / total, w1 / total]
+        }
+    };
+    let scoring = config.scoring();
+    if scoring == ScoringMode::AmplitudeOnly {
+        return weights;
+    }
+    let scores: [f64; 2] = std::array::from_fn(|quality| {
+        let mut score = weights[quality];
+        if scoring.uses_coherence() {
+            score *= phase_alignment(amplitudes[quality]);
+        }
+        if scoring.uses_resonance() {
+            score *= resonance(quality as u8);
+        }
+        score
+    });
+    let total = scores[0] + scores[1];
+    // Scores that all vanish (e.g. every amplitude anti-aligned) carry no preference
+    if total > 0.0 {
+        [scores[0] / total, scores[1] / total]
+    } else {
+        weights
+    }
+}
+
+/// The phase-coherence factor C_A of an amplitude: its alignment with the reference
+/// phase, `(1 + cos arg)/2`, from 1 for a real positive amplitude to 0 for a negative one.
+pub(crate) fn phase_alignment(amplitude: Complex<f64>) -> f64 {
+    if amplitude == Complex::zero() {
+        return 1.0;
+    }
+    (1.0 + amplitude.arg().cos()) / 2.0
+}
+
+/// The pattern-resonance factor C_B of a quality: `1/(1 + Hamming weight)`.
+pub(crate) fn resonance(quality: u8) -> f64 {
+    1.0 / (1.0 + quality.count_ones() as f64)
+}
+
+/// Wraps `phase` into `(-π, π]`.
+fn wrap_phase(phase: f64) -> f64 {
+    use std::f64::consts::{PI, TAU};
+    let wrapped = phase.rem_euclid(TAU);
+    if wrapped > PI { wrapped - TAU } else { wrapped }
+}
+
+/// Translates a joint outcome over QDUs into `(node, quality)` pairs, checking that
+/// every quality is 0 or 1 and every QDU is listed once.
+pub(crate) fn physical_outcome(
+    outcome: &[(QduId, u8)],
+    node_of: impl Fn(&QduId) -> Option<u64>,
+) -> Result<Vec<(u64, u8)>, OnqError> {
+    let mut seen = HashSet::new();
+    outcome
+        .iter()
+        .map(|(qdu, quality)| {
+            if *quality > 1 {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("Quality {} of {} is not 0 or 1", quality, qdu),
+                });
+            }
+            if !seen.insert(*qdu) {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("{} appears more than once in the outcome", qdu),
+                });
+            }
+            let node = node_of(qdu).ok_or_else(|| OnqError::ReferenceViolation {
+                message: format!("QDU {} not mapped to physical matrix.", qdu),
+            })?;
+            Ok((node, *quality))
+        })
+        .collect()
+}
+
+/// Returns `true` if `matrix` times its conjugate transpose is the identity within
+/// `tolerance`.
+fn is_unitary(matrix: &[[Complex<f64>; 2]; 2], tolerance: f64) -> bool {
+    (0..2).all(|r| {
+        (0..2).all(|c| {
+            let entry = matrix[r][0] * matrix[c][0].conj() + matrix[r][1] * matrix[c][1].conj();
+            let expected = if r == c { 1.0 } else { 0.0 };
+            (entry - Complex::new(expected, 0.0)).norm() < tolerance
+        })
+    })
+}
+
+/// Orders a QDU pair so a lock is found whichever way round it was named.
+fn lock_key(a: QduId, b: QduId) -> (QduId, QduId) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+/// Checks `M·M† = I` for a square matrix of any dimension.
+fn is_unitary_dense(matrix: &[Vec<Complex<f64>>], tolerance: f64) -> bool {
+    matrix.iter().enumerate().all(|(r, row)| {
+        matrix.iter().enumerate().all(|(c, other)| {
+            let entry: Complex<f64> = row.iter().zip(other).map(|(a, b)| a * b.conj()).sum();
+            let expected = if r == c { 1.0 } else { 0.0 };
+            (entry - Complex::new(expected, 0.0)).norm() < tolerance
+        })
+    })
+}
+
 /// Provides the 2x2 matrix for the PhaseShift operation.
 fn phase_shift_matrix(theta: f64) -> [[Complex<f64>; 2]; 2] {
     [
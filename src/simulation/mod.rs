@@ -4,17 +4,43 @@
 //! This module contains the `Simulator` entry point and the internal `SimulationEngine`
 //! responsible for managing and evolving the state according to derived rules.
 
+mod checkpoint;
+mod clifford;
+mod config;
+mod diagnostics;
 // Make engine module crate visible for tests
 pub(crate) mod engine;
+mod profile;
+mod progress;
+mod readout;
 mod results; // Changed visibility to pub(crate)
+mod session;
+mod shots;
+mod strategy;
 
 // Re-export the main public interface types
-pub use results::SimulationResult;
+pub use checkpoint::Checkpoint;
+pub use config::{
+    Backend, BoundaryModel, Precision, RenormalizationPolicy, ScoringMode, SemanticsVersion,
+    SimulatorConfig, StabilizationSeed, StateRepresentation, ValidationMode, ValidationTiming,
+};
+pub use diagnostics::{OutcomeScore, StabilizationAnalysis};
+pub use profile::Profile;
+pub use progress::Progress;
+pub(crate) use progress::ProgressHook;
+pub use readout::ReadoutError;
+pub use results::{Renormalization, SimulationResult};
+pub use session::SimulationSession;
+pub use shots::{JointOutcome, SeedMode, ShotResults};
+pub use strategy::{AmplitudeWeighted, CoherenceFiltered, MaxWeight, StabilizationStrategy};
 
 // Import necessary types for the Simulator struct and its methods
 use crate::circuits::Circuit;
-use crate::core::OnqError;
+use crate::core::{OnqError, PotentialityState, QduId, StableState};
 use crate::operations::Operation;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
 // Make engine accessible within the crate
 use engine::SimulationEngine;
 
@@ -23,10 +49,17 @@ use engine::SimulationEngine;
 /// according to rules (or placeholders thereof).
 #[derive(Default)] // Allows Simulator::default() -> Simulator::new()
 pub struct Simulator {
+    /// Settings applied to every run.
+    config: SimulatorConfig,
+    /// Overrides the outcome selection of the semantics version, if set.
+    strategy: Option<Arc<dyn StabilizationStrategy>>,
+    /// Flips recorded stabilization outcomes, if set.
+    readout: Option<Arc<ReadoutError>>,
+    /// Receives the progress of every run, if set.
+    progress: Option<ProgressHook>,
     // Future potential configuration options:
     // - seed_source: SeedSource, // For deterministic stabilization if probabilistic
     // - precision_level: FloatPrecision,
-    // - validation_mode: ValidationMode, // e.g., Off, Basic, Strict
 }
 
 impl Simulator {
@@ -35,6 +68,194 @@ impl Simulator {
         Self::default()
     }
 
+    /// Creates a new Simulator using `config`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::Simulator;
+    /// # use onq::simulation::{SemanticsVersion, SimulatorConfig};
+    /// let simulator =
+    ///     Simulator::with_config(SimulatorConfig::new().with_semantics(SemanticsVersion::V1));
+    /// assert_eq!(simulator.config().semantics(), SemanticsVersion::V1);
+    /// ```
+    pub fn with_config(config: SimulatorConfig) -> Self {
+        Self {
+            config,
+            strategy: None,
+            readout: None,
+            progress: None,
+        }
+    }
+
+    /// Returns the configuration applied to every run.
+    pub fn config(&self) -> &SimulatorConfig {
+        &self.config
+    }
+
+    /// Returns an upper estimate of the memory, in bytes, a run over `num_qdus` QDUs
+    /// takes under the configured state representation, so callers can plan before
+    /// starting a run. Runs over more than 64 QDUs fail with
+    /// `OnqError::CapacityExceeded`, reporting the estimate for amplitude runs.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, OnqError, QduId, Simulator};
+    /// # use onq::simulation::{SimulatorConfig, StateRepresentation};
+    /// let simulator = Simulator::new();
+    /// assert!(simulator.estimate_memory(8) < simulator.estimate_memory(64));
+    /// let density = SimulatorConfig::new().with_representation(StateRepresentation::DensityMatrix);
+    /// assert!(Simulator::with_config(density).estimate_memory(8) > simulator.estimate_memory(8));
+    ///
+    /// let qdus: Vec<QduId> = (0..65).map(QduId).collect();
+    /// let circuit = CircuitBuilder::new().stabilize(&qdus).build();
+    /// let error = simulator.run(&circuit).unwrap_err();
+    /// assert_eq!(
+    ///     error,
+    ///     OnqError::CapacityExceeded {
+    ///         requested: 65,
+    ///         capacity: 64,
+    ///         required_bytes: simulator.estimate_memory(65),
+    ///     }
+    /// );
+    /// ```
+    pub fn estimate_memory(&self, num_qdus: usize) -> usize {
+        engine::estimate_memory(num_qdus, self.config.representation())
+    }
+
+    /// Selects stabilization outcomes with `strategy` instead of the rule of the
+    /// configured [`SemanticsVersion`]. The deterministic seeding is unchanged, so
+    /// strategies can be compared on identical draws.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId, Simulator};
+    /// # use onq::simulation::{AmplitudeWeighted, MaxWeight, SeedMode};
+    /// let q0 = QduId(0);
+    /// let circuit = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern { target: q0, pattern_id: "PhiRotate".to_string() })
+    ///     .stabilize(&[q0])
+    ///     .build();
+    ///
+    /// let argmax = Simulator::new().with_strategy(MaxWeight);
+    /// assert_eq!(argmax.strategy().map(|s| s.name()), Some("max-weight"));
+    /// assert_eq!(argmax.run_shots(&circuit, 32, SeedMode::PerShot).unwrap().counts().len(), 1);
+    ///
+    /// let sampled = Simulator::new().with_strategy(AmplitudeWeighted);
+    /// assert_eq!(sampled.run_shots(&circuit, 32, SeedMode::PerShot).unwrap().counts().len(), 2);
+    /// ```
+    pub fn with_strategy<S>(mut self, strategy: S) -> Self
+    where
+        S: StabilizationStrategy + 'static,
+    {
+        self.strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Returns the strategy overriding the semantics version, if any.
+    pub fn strategy(&self) -> Option<&dyn StabilizationStrategy> {
+        self.strategy.as_deref()
+    }
+
+    /// Explains how stabilizing the IVM nodes `targets` of `state` would resolve under
+    /// this simulator's semantics, strategy, seeding, precision and scoring: the weight
+    /// and score of each quality, whether it passes the 1/φ coherence filter, the phase coherence of the
+    /// node, the deterministic draw and the selected quality. Nothing is collapsed.
+    ///
+    /// Nodes are analyzed independently with a shot salt of 0, i.e. as a single run
+    /// stabilizing them would. Readout error is not applied.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if `state` has no node in `targets`, and
+    /// `OnqError::Incoherence` if a target has zero or non-finite norm.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator, StableState};
+    /// let (q0, q1) = (QduId(0), QduId(1));
+    /// let circuit = CircuitBuilder::new().h(q0).x(q1).build();
+    /// let simulator = Simulator::new();
+    /// let state = simulator.run_with_state(&circuit).unwrap().final_state().unwrap().clone();
+    ///
+    /// let [balanced, flipped] = simulator.analyze_stabilization(&state, &[0, 1]).unwrap()[..] else {
+    ///     unreachable!()
+    /// };
+    /// assert!(!balanced.filtered()); // resolved by the draw
+    /// assert!(flipped.scores[1].passes_filter);
+    /// assert_eq!(flipped.selected, 1);
+    ///
+    /// // The analysis predicts the outcome of stabilizing
+    /// let stabilized = CircuitBuilder::new().h(q0).x(q1).stabilize(&[q0]).build();
+    /// let result = simulator.run(&stabilized).unwrap();
+    /// assert_eq!(
+    ///     result.get_stable_state(&q0),
+    ///     Some(&StableState::ResolvedQuality(balanced.selected as u64))
+    /// );
+    /// ```
+    pub fn analyze_stabilization(
+        &self,
+        state: &PotentialityState,
+        targets: &[u64],
+    ) -> Result<Vec<StabilizationAnalysis>, OnqError> {
+        let filtered = CoherenceFiltered::default();
+        let strategy: &dyn StabilizationStrategy = match self.strategy() {
+            Some(strategy) => strategy,
+            None if self.config.semantics().coherence_filtered() => &filtered,
+            None => &AmplitudeWeighted,
+        };
+        diagnostics::analyze(state, targets, &self.config, strategy)
+    }
+
+    /// Applies `readout` to every stabilization: the state collapses onto the
+    /// selected quality, but the recorded outcome is flipped with the configured
+    /// probability. Flipped QDUs are listed by [`SimulationResult::readout_flips`].
+    pub fn with_readout_error(mut self, readout: ReadoutError) -> Self {
+        self.readout = Some(Arc::new(readout));
+        self
+    }
+
+    /// Returns the readout error model, if any.
+    pub fn readout_error(&self) -> Option<&ReadoutError> {
+        self.readout.as_deref()
+    }
+
+    /// Calls `callback` with the [`Progress`] of every run after each operation, so
+    /// frontends can show progress on long runs. Multi-shot runs report every shot,
+    /// and runs on the stabilizer-tableau backend report once, when they complete.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// # use std::sync::{Arc, Mutex};
+    /// let q0 = QduId(0);
+    /// let circuit = CircuitBuilder::new().h(q0).h(q0).stabilize(&[q0]).build();
+    ///
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&seen);
+    /// let simulator = Simulator::new().with_progress(move |progress| {
+    ///     sink.lock().unwrap().push((progress.completed, progress.total));
+    /// });
+    /// simulator.run(&circuit).unwrap();
+    /// assert_eq!(*seen.lock().unwrap(), vec![(1, Some(3)), (2, Some(3)), (3, Some(3))]);
+    /// ```
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressHook::new(callback));
+        self
+    }
+
+    /// Reports the progress of a run, if a callback is registered.
+    fn report_progress(&self, completed: usize, total: Option<usize>, qdus: usize) {
+        if let Some(hook) = &self.progress {
+            hook.report(Progress {
+                completed,
+                total,
+                qdus,
+            });
+        }
+    }
+
     /// Runs a simulation of the provided circuit.
     ///
     /// Executes the sequence of operations defined in the `circuit`, updating the
@@ -49,49 +270,539 @@ impl Simulator {
     /// * `Err(OnqError)` if the simulation encounters an error state reflecting a violation
     ///   of principles (e.g., incoherence, instability) or invalid operations.
     pub fn run(&self, circuit: &Circuit) -> Result<SimulationResult, OnqError> {
+        Ok(self.run_engine(circuit, 0)?.0)
+    }
+
+    /// Like [`Simulator::run`], but also captures the final global state and the
+    /// marginal quality probabilities of every QDU left un-stabilized, available through
+    /// [`SimulationResult::final_state`] and [`SimulationResult::marginal`]. In
+    /// density-matrix mode the density matrices are captured as well
+    /// ([`SimulationResult::final_density`]).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// let (q0, q1) = (QduId(0), QduId(1));
+    /// let circuit = CircuitBuilder::new().x(q0).h(q1).stabilize(&[q0]).build();
+    ///
+    /// let result = Simulator::new().run_with_state(&circuit).unwrap();
+    /// assert!(result.final_state().is_some());
+    /// assert_eq!(result.marginal(&q0), None); // stabilized
+    /// let [p0, p1] = result.marginal(&q1).unwrap();
+    /// assert!((p0 - 0.5).abs() < 1e-9 && (p1 - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn run_with_state(&self, circuit: &Circuit) -> Result<SimulationResult, OnqError> {
+        let (mut result, engine) = self.run_engine(circuit, 0)?;
+        if let Some(engine) = engine {
+            let marginals = engine
+                .marginals()
+                .into_iter()
+                .filter(|(qdu, _)| result.get_stable_state(qdu).is_none())
+                .collect();
+            result.capture_state(engine.get_state().clone(), engine.qdu_nodes(), marginals);
+            if let Some(density) = engine.density() {
+                result.capture_density(density.clone());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Like [`Simulator::run`], but calls `observer` after every operation with its
+    /// index in the circuit, the operation and read access to the state it produced.
+    ///
+    /// The state is indexed by physical IVM node, as in
+    /// [`SimulationResult::final_state`]. Observed runs always execute on the tensor
+    /// network, whatever the [`Backend`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// let q0 = QduId(0);
+    /// let circuit = CircuitBuilder::new().h(q0).h(q0).stabilize(&[q0]).build();
+    ///
+    /// let mut trace = Vec::new();
+    /// Simulator::new()
+    ///     .run_with_observer(&circuit, |step, _op, state| {
+    ///         trace.push((step, state.network[&0].core_state[1].norm_sqr()));
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(trace.len(), 3);
+    /// assert!((trace[0].1 - 0.5).abs() < 1e-12 && trace[1].1 < 1e-12);
+    /// ```
+    pub fn run_with_observer<F>(
+        &self,
+        circuit: &Circuit,
+        mut observer: F,
+    ) -> Result<SimulationResult, OnqError>
+    where
+        F: FnMut(usize, &Operation, &PotentialityState),
+    {
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+        if circuit.is_empty() {
+            return Ok(result);
+        }
+        let engine = SimulationEngine::init(circuit.qdus())?;
+        Ok(self
+            .run_on_engine(engine, circuit, 0, result, Some(&mut observer))?
+            .0)
+    }
+
+    /// Starts a [`SimulationSession`] executing `circuit` one operation at a time, so
+    /// a run can be paused, inspected and resumed.
+    ///
+    /// Like observed runs, sessions always execute on the tensor network, whatever the
+    /// [`Backend`].
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if the circuit uses no QDUs and
+    /// `OnqError::CapacityExceeded` if it uses more than the 64 IVM nodes.
+    pub fn session<'a>(&'a self, circuit: &'a Circuit) -> Result<SimulationSession<'a>, OnqError> {
+        let mut engine = SimulationEngine::init(circuit.qdus())?;
+        self.configure_engine(&mut engine, 0);
+        engine.set_dimensions(circuit.dimensions())?;
+        engine.set_frames(circuit.frames());
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+        Ok(SimulationSession::new(self, circuit, engine, result))
+    }
+
+    /// Runs `circuit` starting from `initial` instead of the `|0...0>` baseline.
+    ///
+    /// `qdu_order[i]` names the QDU held by IVM node `i` of `initial`, so the prepared
+    /// potentiality of each node is attributed to the right QDU. Two-QDU operations are
+    /// subject to the Locality Rule on this placement.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `qdu_order` is empty or lists a QDU
+    /// twice; `OnqError::CapacityExceeded` if it exceeds the 64 IVM nodes;
+    /// `OnqError::ReferenceViolation` if a QDU of the
+    /// circuit is missing from `qdu_order` or `initial` lacks one of the used nodes;
+    /// `OnqError::Incoherence` if a used node has zero norm; and any error raised while
+    /// running the circuit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, PotentialityState, QduId, Simulator, StableState};
+    /// # use num_complex::Complex;
+    /// let (a, b) = (QduId(7), QduId(3));
+    /// // Prepare node 0 (QDU 7) in |1>
+    /// let mut initial = PotentialityState::new();
+    /// initial.network.get_mut(&0).unwrap().core_state = [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)];
+    ///
+    /// let circuit = CircuitBuilder::new().stabilize(&[a, b]).build();
+    /// let result = Simulator::new().run_from_state(&circuit, initial, &[a, b]).unwrap();
+    /// assert_eq!(result.get_stable_state(&a), Some(&StableState::ResolvedQuality(1)));
+    /// assert_eq!(result.get_stable_state(&b), Some(&StableState::ResolvedQuality(0)));
+    /// ```
+    pub fn run_from_state(
+        &self,
+        circuit: &Circuit,
+        initial: PotentialityState,
+        qdu_order: &[QduId],
+    ) -> Result<SimulationResult, OnqError> {
+        if qdu_order.is_empty() {
+            return Err(OnqError::InvalidOperation {
+                message: "Cannot initialize simulation engine with zero QDUs".to_string(),
+            });
+        }
+        if let Some(missing) = circuit.qdus().iter().find(|q| !qdu_order.contains(q)) {
+            return Err(OnqError::ReferenceViolation {
+                message: format!(
+                    "{} is used by the circuit but missing from the QDU order",
+                    missing
+                ),
+            });
+        }
+        let mut engine = SimulationEngine::init_with_order(qdu_order)?;
+        engine.set_state(initial)?;
+
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+        Ok(self.run_on_engine(engine, circuit, 0, result, None)?.0)
+    }
+
+    /// Runs the circuit `shots` times and aggregates the joint stabilization outcomes
+    /// into a histogram.
+    ///
+    /// Each shot mixes a salt chosen by `seed_mode` into the deterministic stabilization
+    /// seed, so shots over the same circuit can resolve differently while the whole run
+    /// stays reproducible. With [`SeedMode::PerShot`], shot 0 resolves exactly like
+    /// [`Simulator::run`].
+    ///
+    /// With the `parallel` feature, shots run concurrently on the rayon thread pool;
+    /// the histogram is identical to the sequential one.
+    ///
+    /// # Errors
+    /// Returns the first `OnqError` raised by any shot.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// # use onq::simulation::SeedMode;
+    /// let q0 = QduId(0);
+    /// let circuit = CircuitBuilder::new().h(q0).stabilize(&[q0]).build();
+    /// let simulator = Simulator::new();
+    ///
+    /// let fixed = simulator.run_shots(&circuit, 16, SeedMode::Fixed).unwrap();
+    /// assert_eq!(fixed.counts().len(), 1);
+    ///
+    /// let seeded = simulator.run_shots(&circuit, 16, SeedMode::Seeded(42)).unwrap();
+    /// assert_eq!(seeded.counts().values().sum::<usize>(), 16);
+    /// ```
+    pub fn run_shots(
+        &self,
+        circuit: &Circuit,
+        shots: usize,
+        seed_mode: SeedMode,
+    ) -> Result<ShotResults, OnqError> {
+        #[cfg(feature = "parallel")]
+        return self.run_shots_parallel(circuit, shots, seed_mode);
+        #[cfg(not(feature = "parallel"))]
+        self.run_shots_with(circuit, shots, seed_mode, |_| false)
+    }
+
+    /// Like [`Simulator::run_shots`], but additionally captures the residual state of
+    /// shots whose joint outcome matches `selector` (heralded post-selection).
+    ///
+    /// For every selected outcome, the final `PotentialityState` of the first shot
+    /// producing it is kept as a representative, available through
+    /// [`ShotResults::heralded_state`]. Stabilized QDUs are collapsed in that state while
+    /// un-stabilized QDUs keep their potentiality, so it can seed subsequent workflows.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Operation, QduId, Simulator};
+    /// let (herald, data) = (QduId(0), QduId(1));
+    /// let circuit = CircuitBuilder::new()
+    ///     .add_op(Operation::InteractionPattern {
+    ///         target: herald,
+    ///         pattern_id: "QualityFlip".to_string(),
+    ///     })
+    ///     .add_op(Operation::InteractionPattern {
+    ///         target: data,
+    ///         pattern_id: "Superposition".to_string(),
+    ///     })
+    ///     .add_op(Operation::Stabilize { targets: vec![herald] })
+    ///     .build();
+    ///
+    /// let results = Simulator::new()
+    ///     .run_shots_heralded(&circuit, 8, |outcome| outcome[&herald] == 1)
+    ///     .unwrap();
+    /// let heralded = [(herald, 1)].into_iter().collect();
+    /// assert_eq!(results.count(&heralded), 8);
+    /// assert!(results.heralded_state(&heralded).is_some());
+    /// ```
+    pub fn run_shots_heralded<F>(
+        &self,
+        circuit: &Circuit,
+        shots: usize,
+        selector: F,
+    ) -> Result<ShotResults, OnqError>
+    where
+        F: Fn(&JointOutcome) -> bool,
+    {
+        self.run_shots_with(circuit, shots, SeedMode::PerShot, selector)
+    }
+
+    /// Shared loop of the multi-shot runners.
+    fn run_shots_with<F>(
+        &self,
+        circuit: &Circuit,
+        shots: usize,
+        seed_mode: SeedMode,
+        selector: F,
+    ) -> Result<ShotResults, OnqError>
+    where
+        F: Fn(&JointOutcome) -> bool,
+    {
+        let mut results = ShotResults::new(shots, seed_mode);
+        for shot in 0..shots {
+            let (result, engine) = self.run_engine(circuit, seed_mode.salt(shot as u64))?;
+            let outcome = joint_outcome(&result);
+            let residual = match engine {
+                Some(engine) if selector(&outcome) => Some(engine.get_state().clone()),
+                _ => None,
+            };
+            results.record_shot(outcome, residual);
+        }
+        Ok(results)
+    }
+
+    /// Runs the shots of [`Simulator::run_shots`] on the rayon thread pool. Shots are
+    /// independent engines, so the histogram matches the sequential run exactly.
+    #[cfg(feature = "parallel")]
+    fn run_shots_parallel(
+        &self,
+        circuit: &Circuit,
+        shots: usize,
+        seed_mode: SeedMode,
+    ) -> Result<ShotResults, OnqError> {
+        use rayon::prelude::*;
+
+        let outcomes = (0..shots)
+            .into_par_iter()
+            .map(|shot| {
+                self.run_engine(circuit, seed_mode.salt(shot as u64))
+                    .map(|(result, _)| joint_outcome(&result))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut results = ShotResults::new(shots, seed_mode);
+        for outcome in outcomes {
+            results.record_shot(outcome, None);
+        }
+        Ok(results)
+    }
+
+    /// Executes `circuit` on a fresh engine using the given stabilization salt,
+    /// returning the outcomes and the final engine (`None` for an empty circuit).
+    fn run_engine(
+        &self,
+        circuit: &Circuit,
+        salt: u64,
+    ) -> Result<(SimulationResult, Option<SimulationEngine>), OnqError> {
+        // The results container records stable outcomes and the semantics in effect.
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+
         // Handle empty circuit case
         if circuit.is_empty() {
-            return Ok(SimulationResult::new());
+            return Ok((result, None));
+        }
+
+        if clifford::eligible(circuit, &self.config) {
+            // QDUs never integrated with another resolve on this engine
+            let mut local = SimulationEngine::init(circuit.qdus())?;
+            self.configure_engine(&mut local, salt);
+            local.set_readout(None);
+            let result = clifford::run(
+                circuit,
+                &self.config,
+                local,
+                self.strategy.as_deref(),
+                self.readout.as_deref(),
+                salt,
+            )?;
+            self.report_progress(circuit.len(), Some(circuit.len()), circuit.qdus().len());
+            return Ok((result, None));
         }
 
         // 1. Initialize the simulation engine with all unique QDUs involved in the circuit.
         // This sets up the initial state vector (placeholder: |0...0>).
-        let mut engine = SimulationEngine::init(circuit.qdus())?;
+        let engine = SimulationEngine::init(circuit.qdus())?;
+        let (result, engine) = self.run_on_engine(engine, circuit, salt, result, None)?;
+        Ok((result, Some(engine)))
+    }
 
-        // 2. Initialize the results container to store stable outcomes.
-        let mut result = SimulationResult::new();
+    /// Executes `circuit` on a prepared engine, recording into `result`.
+    fn run_on_engine(
+        &self,
+        mut engine: SimulationEngine,
+        circuit: &Circuit,
+        salt: u64,
+        mut result: SimulationResult,
+        mut observer: Option<&mut Observer<'_>>,
+    ) -> Result<(SimulationResult, SimulationEngine), OnqError> {
+        self.configure_engine(&mut engine, salt);
+        engine.set_dimensions(circuit.dimensions())?;
+        engine.set_frames(circuit.frames());
 
-        // 3. Iterate through the ordered sequence of operations in the circuit.
-        for op in circuit.operations() {
-            match op {
-                // Handle stabilization operation specifically
-                Operation::Stabilize { targets } => {
-                    // Instruct the engine to perform the stabilization protocol
-                    // for the specified target QDUs. This updates the 'result' map
-                    // and potentially collapses the engine's state vector.
-                    // **CRITICAL:** Uses placeholder stabilization logic in the engine currently.
-                    engine.stabilize(targets, &mut result)?;
-                }
-                // For all other operations, instruct the engine to apply them
-                _ => {
-                    // Apply the state evolution operation to the engine's state vector.
-                    // **CRITICAL:** Uses placeholder gate application logic in the engine currently.
-                    engine.apply_operation(op)?;
-                }
+        // 2. Iterate through the ordered sequence of operations in the circuit.
+        for (index, op) in circuit.operations().iter().enumerate() {
+            self.execute_step(&mut engine, circuit, index, &mut result)?;
+            if let Some(observer) = observer.as_mut() {
+                observer(index, op, engine.get_state());
             }
-            // Optional: Perform state validation after each step if configured/needed for debugging.
-            // engine.validate_state()?;
+            self.report_progress(index + 1, Some(circuit.len()), engine.qdu_count());
         }
+        engine.validate()?;
+        result.set_global_phase(engine.global_phase());
+        result.record_renormalizations(engine.renormalizations());
 
-        // Optional: Final validation check on the state after all operations.
-        // engine.validate_state()?;
+        // Return the collected stable outcomes.
+        Ok((result, engine))
+    }
 
-        // TODO: Optionally populate result.final_potentialities with engine.global_state if desired
-        // for non-stabilized QDUs.
+    /// Applies the simulator's settings to a fresh engine.
+    fn configure_engine(&self, engine: &mut SimulationEngine, salt: u64) {
+        engine.set_stabilization_salt(salt);
+        engine.set_config(self.config);
+        engine.set_strategy(self.strategy.clone());
+        engine.set_readout(self.readout.clone());
+    }
+
+    /// Executes operation `index` of `circuit`, recording its outcomes into `result`.
+    fn execute_step(
+        &self,
+        engine: &mut SimulationEngine,
+        circuit: &Circuit,
+        index: usize,
+        result: &mut SimulationResult,
+    ) -> Result<(), OnqError> {
+        self.execute_operation(engine, &circuit.operations()[index], result)?;
+        // Name the outcomes this stabilization binds to classical bits
+        for (bit, qdu) in circuit.bits_at(index) {
+            if let Some(value) = result
+                .get_stable_state(&qdu)
+                .and_then(|state| state.get_resolved_value())
+            {
+                result.record_classical_bit(bit, value);
+            }
+        }
+        if self.config.validation_timing() == ValidationTiming::PerOperation {
+            engine.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Runs a simulation over a lazily produced sequence of operations.
+    ///
+    /// Unlike [`Simulator::run`], the operations are never materialized into a `Circuit`,
+    /// so very large generated circuits (e.g. from a generator or a file reader) can be
+    /// executed one operation at a time.
+    ///
+    /// # Arguments
+    /// * `qdus` - The complete set of QDUs the operations may refer to. This must be known
+    ///   up front since the engine maps QDUs onto the IVM before the first operation runs.
+    /// * `ops` - Any iterable yielding the `Operation`s to execute, in order.
+    ///
+    /// # Returns
+    /// * `Ok(SimulationResult)` containing the stable outcomes recorded during stabilization.
+    /// * `Err(OnqError)` if `qdus` is empty, if an operation refers to a QDU not in `qdus`
+    ///   (`ReferenceViolation`), or if any operation fails as in [`Simulator::run`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{Operation, QduId, Simulator, StableState};
+    /// # use std::collections::HashSet;
+    /// let qdus: HashSet<QduId> = (0..4).map(QduId).collect();
+    /// let ops = (0..4)
+    ///     .map(|i| Operation::InteractionPattern {
+    ///         target: QduId(i),
+    ///         pattern_id: "QualityFlip".to_string(),
+    ///     })
+    ///     .chain(std::iter::once(Operation::Stabilize { targets: vec![QduId(3)] }));
+    ///
+    /// let result = Simulator::new().run_stream(&qdus, ops).unwrap();
+    /// assert_eq!(result.get_stable_state(&QduId(3)), Some(&StableState::ResolvedQuality(1)));
+    /// ```
+    pub fn run_stream<I>(&self, qdus: &HashSet<QduId>, ops: I) -> Result<SimulationResult, OnqError>
+    where
+        I: IntoIterator<Item = Operation>,
+    {
+        let mut engine = SimulationEngine::init(qdus)?;
+        self.configure_engine(&mut engine, 0);
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+        self.run_ops(engine, ops, result)
+    }
+
+    /// Continues a simulation from `checkpoint`, executing `remaining_ops` in order.
+    ///
+    /// The outcomes stabilized before the checkpoint are part of the returned result.
+    /// Operations may only refer to the QDUs of the checkpoint.
+    ///
+    /// # Errors
+    /// Returns `OnqError::Incoherence` if a QDU of the checkpoint has a zero-norm local
+    /// state, `OnqError::ReferenceViolation` if an operation refers to a QDU outside
+    /// the checkpoint, and any error raised while running the operations.
+    pub fn resume<I>(
+        &self,
+        checkpoint: &Checkpoint,
+        remaining_ops: I,
+    ) -> Result<SimulationResult, OnqError>
+    where
+        I: IntoIterator<Item = Operation>,
+    {
+        let mut engine = SimulationEngine::init_with_order(&checkpoint.qdu_order())?;
+        engine.set_state(checkpoint.state().clone())?;
+        engine.set_global_phase(checkpoint.global_phase());
+        self.configure_engine(&mut engine, 0);
+        let mut result = SimulationResult::new();
+        result.set_semantics(self.config.semantics());
+        for (qdu, quality) in checkpoint.outcomes() {
+            result.record_stable_state(*qdu, StableState::ResolvedQuality(*quality));
+        }
+        self.run_ops(engine, remaining_ops, result)
+    }
+
+    /// Executes `ops` on a configured engine and validates the final state.
+    fn run_ops<I>(
+        &self,
+        mut engine: SimulationEngine,
+        ops: I,
+        mut result: SimulationResult,
+    ) -> Result<SimulationResult, OnqError>
+    where
+        I: IntoIterator<Item = Operation>,
+    {
+        for (index, op) in ops.into_iter().enumerate() {
+            self.execute_operation(&mut engine, &op, &mut result)?;
+            if self.config.validation_timing() == ValidationTiming::PerOperation {
+                engine.validate()?;
+            }
+            self.report_progress(index + 1, None, engine.qdu_count());
+        }
+        engine.validate()?;
+        result.set_global_phase(engine.global_phase());
+        result.record_renormalizations(engine.renormalizations());
 
-        // Return the collected stable outcomes.
         Ok(result)
     }
+
+    /// Executes a single operation, timing it when profiling is enabled.
+    fn execute_operation(
+        &self,
+        engine: &mut SimulationEngine,
+        op: &Operation,
+        result: &mut SimulationResult,
+    ) -> Result<(), OnqError> {
+        if !self.config.profiling() {
+            return Self::dispatch_operation(engine, op, result);
+        }
+        let start = Instant::now();
+        Self::dispatch_operation(engine, op, result)?;
+        result.record_timing(op.kind(), start.elapsed());
+        Ok(())
+    }
+
+    /// Routes `Stabilize` to the stabilization protocol, `Snapshot` to the result and
+    /// everything else to state evolution.
+    pub(crate) fn dispatch_operation(
+        engine: &mut SimulationEngine,
+        op: &Operation,
+        result: &mut SimulationResult,
+    ) -> Result<(), OnqError> {
+        match op {
+            // Handle stabilization operation specifically
+            Operation::Stabilize { targets } => {
+                // Instruct the engine to perform the stabilization protocol
+                // for the specified target QDUs. This updates the 'result' map
+                // and potentially collapses the engine's state vector.
+                engine.stabilize(targets, result)
+            }
+            Operation::Snapshot { label } => {
+                result.record_snapshot(label, engine.get_state().clone(), engine.qdu_nodes());
+                Ok(())
+            }
+            // For all other operations, instruct the engine to apply them
+            _ => engine.apply_operation(op),
+        }
+    }
+}
+
+/// Callback invoked after each operation with its index, the operation and the state.
+type Observer<'a> = dyn FnMut(usize, &Operation, &PotentialityState) + 'a;
+
+/// Collects the stabilized qualities of a run into a joint outcome.
+fn joint_outcome(result: &SimulationResult) -> JointOutcome {
+    result
+        .all_stable_outcomes()
+        .iter()
+        .map(|(qdu, state)| match state {
+            StableState::ResolvedQuality(value) => (*qdu, *value),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -137,4 +848,28 @@ mod tests {
         let outcome = result.get_stable_state(&QduId(0)).unwrap();
         assert_eq!(outcome, &StableState::ResolvedQuality(1));
     }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_shots_match_sequential_shots() {
+        let qdus: Vec<QduId> = (0..3).map(QduId).collect();
+        let circuit = crate::circuits::CircuitBuilder::new()
+            .h(qdus[0])
+            .h(qdus[2])
+            .cnot(qdus[0], qdus[1])
+            .stabilize(&qdus)
+            .build();
+        let simulator = Simulator::new();
+
+        for seed_mode in [SeedMode::PerShot, SeedMode::Fixed, SeedMode::Seeded(42)] {
+            let parallel = simulator
+                .run_shots_parallel(&circuit, 200, seed_mode)
+                .unwrap();
+            let sequential = simulator
+                .run_shots_with(&circuit, 200, seed_mode, |_| false)
+                .unwrap();
+            assert_eq!(parallel.counts(), sequential.counts(), "{:?}", seed_mode);
+            assert_eq!(parallel.shots(), sequential.shots());
+        }
+    }
 }
@@ -0,0 +1,91 @@
+// src/simulation/profile.rs
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Wall-clock time spent on each operation of a profiled run, recorded when
+/// [`SimulatorConfig::with_profiling`](super::SimulatorConfig::with_profiling) is
+/// enabled and read with [`SimulationResult::profile`](super::SimulationResult::profile).
+///
+/// Times are kept per circuit position and aggregated per operation kind
+/// ([`Operation::kind`](crate::Operation::kind)), so the engine paths that dominate a
+/// circuit stand out.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator};
+/// # use onq::simulation::SimulatorConfig;
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new().h(q0).h(q0).stabilize(&[q0]).build();
+///
+/// let simulator = Simulator::with_config(SimulatorConfig::new().with_profiling(true));
+/// let profile = simulator.run(&circuit).unwrap().profile().cloned().unwrap();
+/// assert_eq!(profile.per_position().len(), 3);
+/// assert_eq!(profile.count("InteractionPattern"), 2);
+/// assert_eq!(profile.total(), profile.per_position().iter().sum());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Time of each executed operation, in circuit order.
+    per_position: Vec<Duration>,
+    /// Operation kind -> (number executed, total time).
+    per_kind: BTreeMap<&'static str, (usize, Duration)>,
+}
+
+impl Profile {
+    /// Creates an empty profile. (Internal visibility)
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the next operation, of kind `kind`, took `elapsed`.
+    /// (Internal visibility)
+    pub(crate) fn record(&mut self, kind: &'static str, elapsed: Duration) {
+        self.per_position.push(elapsed);
+        let entry = self.per_kind.entry(kind).or_default();
+        entry.0 += 1;
+        entry.1 += elapsed;
+    }
+
+    /// Returns the time of each executed operation, indexed by circuit position.
+    pub fn per_position(&self) -> &[Duration] {
+        &self.per_position
+    }
+
+    /// Returns the total time spent on operations of `kind`.
+    pub fn time(&self, kind: &str) -> Duration {
+        self.per_kind
+            .get(kind)
+            .map_or(Duration::ZERO, |(_, time)| *time)
+    }
+
+    /// Returns how many operations of `kind` were executed.
+    pub fn count(&self, kind: &str) -> usize {
+        self.per_kind.get(kind).map_or(0, |(count, _)| *count)
+    }
+
+    /// Returns the executed operation kinds with their count and total time, sorted
+    /// by kind.
+    pub fn per_kind(&self) -> impl Iterator<Item = (&'static str, usize, Duration)> + '_ {
+        self.per_kind
+            .iter()
+            .map(|(kind, (count, time))| (*kind, *count, *time))
+    }
+
+    /// Returns the time spent on all operations.
+    pub fn total(&self) -> Duration {
+        self.per_position.iter().sum()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Profile ({:?} total):", self.total())?;
+        let mut kinds: Vec<_> = self.per_kind().collect();
+        kinds.sort_by_key(|(_, _, time)| std::cmp::Reverse(*time));
+        for (kind, count, time) in kinds {
+            writeln!(f, "  {:<22} {:>6} ops  {:?}", kind, count, time)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// src/simulation/progress.rs
+use std::fmt;
+use std::sync::Arc;
+
+/// How far a run has advanced, reported to the callback registered with
+/// [`Simulator::with_progress`](super::Simulator::with_progress) or
+/// [`OnqVm::with_progress`](crate::vm::OnqVm::with_progress) after every operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Operations (or VM instructions) executed so far.
+    pub completed: usize,
+    /// Operations the run executes in total, if known up front. Streamed operations
+    /// and VM programs, whose control flow decides the count, report `None`.
+    pub total: Option<usize>,
+    /// Number of QDUs the run simulates.
+    pub qdus: usize,
+}
+
+impl Progress {
+    /// Returns the completed fraction in `[0, 1]`, if the total is known.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::simulation::Progress;
+    /// let progress = Progress { completed: 3, total: Some(4), qdus: 2 };
+    /// assert_eq!(progress.fraction(), Some(0.75));
+    /// assert_eq!(Progress { total: None, ..progress }.fraction(), None);
+    /// ```
+    pub fn fraction(&self) -> Option<f64> {
+        self.total.map(|total| match total {
+            0 => 1.0,
+            total => self.completed as f64 / total as f64,
+        })
+    }
+}
+
+/// A shared progress callback. (Internal visibility)
+#[derive(Clone)]
+pub(crate) struct ProgressHook(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressHook {
+    pub(crate) fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    /// Reports `progress` to the callback.
+    pub(crate) fn report(&self, progress: Progress) {
+        (self.0)(&progress);
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
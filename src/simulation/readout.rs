@@ -0,0 +1,110 @@
+// src/simulation/readout.rs
+
+//! Imperfect stabilization readout.
+//!
+//! A [`ReadoutError`] flips the *recorded* quality of a stabilized QDU with a
+//! configured probability, emulating a stabilization process that resolves the state
+//! correctly but reports it unreliably. The state itself collapses onto the true
+//! quality. Flips are drawn deterministically from the readout seed, the shot salt and
+//! the QDU, so runs stay reproducible.
+
+use crate::core::{OnqError, QduId};
+use std::collections::BTreeMap;
+
+/// Per-QDU probabilities of flipping a stabilization outcome.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, OnqError, QduId, Simulator, StableState};
+/// # use onq::simulation::ReadoutError;
+/// let (q0, q1) = (QduId(0), QduId(1));
+/// let readout = ReadoutError::uniform(0.0)?.with_qdu(q1, 1.0)?;
+/// let circuit = CircuitBuilder::new().stabilize(&[q0, q1]).build();
+///
+/// let result = Simulator::new().with_readout_error(readout).run(&circuit)?;
+/// assert_eq!(result.get_stable_state(&q0), Some(&StableState::ResolvedQuality(0)));
+/// assert_eq!(result.get_stable_state(&q1), Some(&StableState::ResolvedQuality(1)));
+/// assert!(result.readout_flipped(&q1) && !result.readout_flipped(&q0));
+/// # Ok::<(), OnqError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadoutError {
+    default: f64,
+    per_qdu: BTreeMap<QduId, f64>,
+    seed: u64,
+}
+
+impl ReadoutError {
+    /// Flips the outcome of every QDU with `probability`.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `probability` is outside `[0, 1]`.
+    pub fn uniform(probability: f64) -> Result<Self, OnqError> {
+        Ok(Self {
+            default: check_probability(probability)?,
+            per_qdu: BTreeMap::new(),
+            seed: 0,
+        })
+    }
+
+    /// Overrides the flip probability of `qdu`.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `probability` is outside `[0, 1]`.
+    pub fn with_qdu(mut self, qdu: QduId, probability: f64) -> Result<Self, OnqError> {
+        self.per_qdu.insert(qdu, check_probability(probability)?);
+        Ok(self)
+    }
+
+    /// Sets the seed the flips are drawn from (default 0).
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the flip probability of `qdu`.
+    pub fn probability(&self, qdu: &QduId) -> f64 {
+        self.per_qdu.get(qdu).copied().unwrap_or(self.default)
+    }
+
+    /// Returns the seed the flips are drawn from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Decides whether the `count`-th stabilization of `qdu` in a run with `salt` is
+    /// reported flipped.
+    pub(crate) fn flips(&self, qdu: &QduId, salt: u64, count: u64) -> bool {
+        let probability = self.probability(qdu);
+        if probability <= 0.0 {
+            return false;
+        }
+        let mut z = self.seed;
+        for value in [salt, qdu.0, count] {
+            z = splitmix64(z ^ value);
+        }
+        let draw = (z >> 11) as f64 / (1u64 << 53) as f64;
+        draw < probability
+    }
+}
+
+fn check_probability(probability: f64) -> Result<f64, OnqError> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(OnqError::InvalidOperation {
+            message: format!(
+                "Readout error probability {} is outside [0, 1]",
+                probability
+            ),
+        })
+    }
+}
+
+/// SplitMix64 step: advances `z` by the golden gamma and finalizes it.
+fn splitmix64(z: u64) -> u64 {
+    let mut z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
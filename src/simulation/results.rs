@@ -1,16 +1,55 @@
 // src/simulation/results.rs
-use crate::core::{QduId, StableState};
-use std::collections::HashMap;
+use super::engine::physical_outcome;
+use super::{Profile, SemanticsVersion};
+use crate::core::{DensityState, OnqError, PotentialityState, QduId, StableState};
+use num_complex::Complex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
+use std::time::Duration;
+
+/// A local state rescaled to unit norm under
+/// [`RenormalizationPolicy::WarnAndRenormalize`](super::RenormalizationPolicy::WarnAndRenormalize).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Renormalization {
+    /// Position of the operation after which the state was rescaled, counting the
+    /// operations executed by the engine.
+    pub operation: usize,
+    /// The QDU whose local state was rescaled.
+    pub qdu: QduId,
+    /// The squared norm of the local state before rescaling.
+    pub norm_sq: f64,
+}
 
 /// Holds the results of a circuit simulation.
-/// Contains the final `StableState` outcomes for QDUs that underwent stabilization.
-#[derive(Debug, Clone, PartialEq)]
+/// Contains the final `StableState` outcomes for QDUs that underwent stabilization,
+/// and, for runs made with [`Simulator::run_with_state`](super::Simulator::run_with_state),
+/// the final global state with the marginals of the un-stabilized QDUs.
+#[derive(Debug, Clone)]
 pub struct SimulationResult {
     /// Maps stabilized QDU IDs to their resulting StableState.
     stable_outcomes: HashMap<QduId, StableState>,
-    // Optional: Include the final potentiality states of non-stabilized QDUs
-    // final_potentialities: HashMap<QduId, PotentialityState>,
+    /// Named classical bits of the circuit, holding the outcome of their stabilization.
+    classical_bits: BTreeMap<String, u64>,
+    /// The stabilization semantics the outcomes were produced under.
+    semantics: SemanticsVersion,
+    /// The global state at the end of the run, if captured.
+    final_state: Option<PotentialityState>,
+    /// Stabilized QDUs whose recorded quality was flipped by the readout error model.
+    readout_flips: BTreeSet<QduId>,
+    /// The IVM node of each QDU in the captured state.
+    qdu_nodes: BTreeMap<QduId, u64>,
+    /// The density matrices at the end of a density-matrix run, if captured.
+    final_density: Option<DensityState>,
+    /// Quality probabilities `[P(0), P(1)]` of each QDU left un-stabilized, if captured.
+    marginals: BTreeMap<QduId, [f64; 2]>,
+    /// Global states recorded by `Operation::Snapshot`, keyed by label.
+    snapshots: BTreeMap<String, PotentialityState>,
+    /// Per-operation timings, if the run was profiled. Boxed, as most runs have none.
+    profile: Option<Box<Profile>>,
+    /// Phase factored out of the local states, if global phase tracking was enabled.
+    global_phase: f64,
+    /// Local states rescaled to unit norm during the run.
+    renormalizations: Vec<Renormalization>,
 }
 
 impl SimulationResult {
@@ -18,7 +57,17 @@ impl SimulationResult {
     pub(crate) fn new() -> Self {
         Self {
             stable_outcomes: HashMap::new(),
-            // final_potentialities: HashMap::new(),
+            classical_bits: BTreeMap::new(),
+            semantics: SemanticsVersion::default(),
+            readout_flips: BTreeSet::new(),
+            final_state: None,
+            qdu_nodes: BTreeMap::new(),
+            final_density: None,
+            marginals: BTreeMap::new(),
+            snapshots: BTreeMap::new(),
+            profile: None,
+            global_phase: 0.0,
+            renormalizations: Vec::new(),
         }
     }
 
@@ -27,6 +76,241 @@ impl SimulationResult {
         self.stable_outcomes.insert(qdu_id, state);
     }
 
+    /// Records whether the readout of a stabilized QDU was flipped. (Internal visibility)
+    pub(crate) fn record_readout(&mut self, qdu_id: QduId, flipped: bool) {
+        if flipped {
+            self.readout_flips.insert(qdu_id);
+        } else {
+            self.readout_flips.remove(&qdu_id);
+        }
+    }
+
+    /// Returns `true` if the recorded outcome of `qdu_id` is the flip of the quality it
+    /// stabilized to, due to the simulator's
+    /// [`ReadoutError`](super::ReadoutError).
+    pub fn readout_flipped(&self, qdu_id: &QduId) -> bool {
+        self.readout_flips.contains(qdu_id)
+    }
+
+    /// Returns the QDUs whose recorded outcome was flipped by readout error.
+    pub fn readout_flips(&self) -> &BTreeSet<QduId> {
+        &self.readout_flips
+    }
+
+    /// Records the value of a named classical bit. (Internal visibility)
+    pub(crate) fn record_classical_bit(&mut self, name: &str, value: u64) {
+        self.classical_bits.insert(name.to_string(), value);
+    }
+
+    /// Returns the value (0 or 1) of the classical bit `name`, as named with
+    /// `stabilize_into`. Returns `None` if the circuit has no such bit or its
+    /// stabilization did not resolve to a quality.
+    pub fn classical_bit(&self, name: &str) -> Option<u64> {
+        self.classical_bits.get(name).copied()
+    }
+
+    /// Returns all recorded classical bits, sorted by name.
+    pub fn classical_bits(&self) -> &BTreeMap<String, u64> {
+        &self.classical_bits
+    }
+
+    /// Records the semantics the outcomes are produced under. (Internal visibility)
+    pub(crate) fn set_semantics(&mut self, semantics: SemanticsVersion) {
+        self.semantics = semantics;
+    }
+
+    /// Returns the stabilization semantics version these outcomes were produced under.
+    pub fn semantics(&self) -> SemanticsVersion {
+        self.semantics
+    }
+
+    /// Stores the final global state and the marginals of un-stabilized QDUs.
+    /// (Internal visibility)
+    pub(crate) fn capture_state(
+        &mut self,
+        state: PotentialityState,
+        qdu_nodes: BTreeMap<QduId, u64>,
+        marginals: BTreeMap<QduId, [f64; 2]>,
+    ) {
+        self.final_state = Some(state);
+        self.qdu_nodes = qdu_nodes;
+        self.marginals = marginals;
+    }
+
+    /// Stores the final density matrices of a density-matrix run. (Internal visibility)
+    pub(crate) fn capture_density(&mut self, density: DensityState) {
+        self.final_density = Some(density);
+    }
+
+    /// Records the global state under `label`, replacing an earlier snapshot with the
+    /// same label. (Internal visibility)
+    pub(crate) fn record_snapshot(
+        &mut self,
+        label: &str,
+        state: PotentialityState,
+        qdu_nodes: BTreeMap<QduId, u64>,
+    ) {
+        self.snapshots.insert(label.to_string(), state);
+        self.qdu_nodes = qdu_nodes;
+    }
+
+    /// Returns the global state recorded by the `Operation::Snapshot` labelled `label`.
+    /// If several snapshots share a label, the last one executed is kept.
+    ///
+    /// The state is indexed by physical IVM node, as in
+    /// [`SimulationResult::final_state`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// let q0 = QduId(0);
+    /// let circuit = CircuitBuilder::new()
+    ///     .h(q0)
+    ///     .snapshot("after-h")
+    ///     .stabilize(&[q0])
+    ///     .build();
+    ///
+    /// let result = Simulator::new().run(&circuit).unwrap();
+    /// let state = result.snapshot("after-h").unwrap();
+    /// assert!((state.network[&0].core_state[1].norm_sqr() - 0.5).abs() < 1e-12);
+    /// assert!(result.snapshot("missing").is_none());
+    /// ```
+    pub fn snapshot(&self, label: &str) -> Option<&PotentialityState> {
+        self.snapshots.get(label)
+    }
+
+    /// Returns all recorded snapshots, sorted by label.
+    pub fn snapshots(&self) -> &BTreeMap<String, PotentialityState> {
+        &self.snapshots
+    }
+
+    /// Records that the next operation, of kind `kind`, took `elapsed`.
+    /// (Internal visibility)
+    pub(crate) fn record_timing(&mut self, kind: &'static str, elapsed: Duration) {
+        self.profile
+            .get_or_insert_with(|| Box::new(Profile::new()))
+            .record(kind, elapsed);
+    }
+
+    /// Returns the per-operation timings of the run, if it was made with
+    /// [`SimulatorConfig::with_profiling`](super::SimulatorConfig::with_profiling).
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
+
+    /// Records the phase factored out of the local states. (Internal visibility)
+    pub(crate) fn set_global_phase(&mut self, phase: f64) {
+        self.global_phase = phase;
+    }
+
+    /// Returns the global phase accumulated over the run, in radians within `(-π, π]`.
+    ///
+    /// With [`SimulatorConfig::with_global_phase_tracking`](super::SimulatorConfig::with_global_phase_tracking)
+    /// the local states in [`SimulationResult::final_state`] are kept free of phase, so
+    /// runs that differ only by a global phase capture equal states; the phase is
+    /// reported here instead and reapplied by [`SimulationResult::amplitude_of`].
+    /// Always 0 without tracking.
+    pub fn global_phase(&self) -> f64 {
+        self.global_phase
+    }
+
+    /// Records the local states rescaled during the run. (Internal visibility)
+    pub(crate) fn record_renormalizations(&mut self, renormalizations: &[Renormalization]) {
+        self.renormalizations = renormalizations.to_vec();
+    }
+
+    /// Returns the local states rescaled to unit norm during the run, in order. Only
+    /// runs under
+    /// [`RenormalizationPolicy::WarnAndRenormalize`](super::RenormalizationPolicy::WarnAndRenormalize)
+    /// record them.
+    pub fn renormalizations(&self) -> &[Renormalization] {
+        &self.renormalizations
+    }
+
+    /// Returns the global state at the end of the run, if it was captured with
+    /// [`Simulator::run_with_state`](super::Simulator::run_with_state).
+    ///
+    /// The state is indexed by physical IVM node: the circuit's QDUs occupy nodes
+    /// `0..n` in ascending QDU order.
+    pub fn final_state(&self) -> Option<&PotentialityState> {
+        self.final_state.as_ref()
+    }
+
+    /// Returns the amplitude of the joint qualities `outcome` in the captured final
+    /// state, mapping each QDU to its IVM node. Each QDU's local state is taken
+    /// normalized and QDUs not listed are traced out.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if no state was captured (see
+    /// [`Simulator::run_with_state`](super::Simulator::run_with_state)), a quality is
+    /// not 0 or 1, or a QDU is listed twice, and `OnqError::ReferenceViolation` for QDUs
+    /// not in the circuit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, QduId, Simulator};
+    /// let (q0, q1) = (QduId(0), QduId(1));
+    /// let circuit = CircuitBuilder::new().h(q0).x(q1).build();
+    /// let result = Simulator::new().run_with_state(&circuit).unwrap();
+    ///
+    /// let amplitude = result.amplitude_of(&[(q0, 1), (q1, 1)]).unwrap();
+    /// assert!((amplitude.re - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+    /// assert_eq!(result.probability_of_outcome(&[(q1, 0)]).unwrap(), 0.0);
+    /// ```
+    pub fn amplitude_of(&self, outcome: &[(QduId, u8)]) -> Result<Complex<f64>, OnqError> {
+        let state = self.captured_state()?;
+        let assignment = physical_outcome(outcome, |qdu| self.qdu_nodes.get(qdu).copied())?;
+        state
+            .amplitude_of(&assignment)
+            .map(|amplitude| amplitude * Complex::from_polar(1.0, self.global_phase))
+            .map_err(|e| OnqError::SimulationError { message: e })
+    }
+
+    /// Returns the probability of the joint qualities `outcome` in the captured final
+    /// state, read from the density diagonals for density-matrix runs.
+    ///
+    /// # Errors
+    /// As [`SimulationResult::amplitude_of`].
+    pub fn probability_of_outcome(&self, outcome: &[(QduId, u8)]) -> Result<f64, OnqError> {
+        let state = self.captured_state()?;
+        let assignment = physical_outcome(outcome, |qdu| self.qdu_nodes.get(qdu).copied())?;
+        match &self.final_density {
+            Some(density) => density.probability_of(&assignment),
+            None => state
+                .amplitude_of(&assignment)
+                .map(|amplitude| amplitude.norm_sqr()),
+        }
+        .map_err(|e| OnqError::SimulationError { message: e })
+    }
+
+    fn captured_state(&self) -> Result<&PotentialityState, OnqError> {
+        self.final_state
+            .as_ref()
+            .ok_or_else(|| OnqError::InvalidOperation {
+                message: "No final state was captured; run with Simulator::run_with_state"
+                    .to_string(),
+            })
+    }
+
+    /// Returns the per-node density matrices at the end of the run, if it was captured
+    /// with [`Simulator::run_with_state`](super::Simulator::run_with_state) under
+    /// [`StateRepresentation::DensityMatrix`](super::StateRepresentation::DensityMatrix).
+    /// Nodes are indexed as in [`SimulationResult::final_state`].
+    pub fn final_density(&self) -> Option<&DensityState> {
+        self.final_density.as_ref()
+    }
+
+    /// Returns the quality probabilities `[P(0), P(1)]` of `qdu` at the end of the run,
+    /// if it was left un-stabilized and the state was captured.
+    pub fn marginal(&self, qdu_id: &QduId) -> Option<[f64; 2]> {
+        self.marginals.get(qdu_id).copied()
+    }
+
+    /// Returns the marginals of all un-stabilized QDUs, sorted by QDU.
+    pub fn marginals(&self) -> &BTreeMap<QduId, [f64; 2]> {
+        &self.marginals
+    }
+
     /// Gets the stable outcome for a specific QDU, if it was stabilized during the simulation.
     /// Returns `None` if the QDU was not stabilized or not part of the simulation.
     pub fn get_stable_state(&self, qdu_id: &QduId) -> Option<&StableState> {
@@ -50,10 +334,42 @@ impl fmt::Display for SimulationResult {
             sorted_outcomes.sort_by_key(|(id, _)| *id);
             writeln!(f, "  Stable Outcomes:")?;
             for (id, state) in sorted_outcomes {
-                writeln!(f, "    {}: {}", id, state)?;
+                if self.readout_flips.contains(id) {
+                    writeln!(f, "    {}: {} (readout flipped)", id, state)?;
+                } else {
+                    writeln!(f, "    {}: {}", id, state)?;
+                }
             }
         }
-        // Add display logic here if final_potentialities is included later
+        if !self.classical_bits.is_empty() {
+            writeln!(f, "  Classical Bits:")?;
+            for (name, value) in &self.classical_bits {
+                writeln!(f, "    {}: {}", name, value)?;
+            }
+        }
+        if !self.marginals.is_empty() {
+            writeln!(f, "  Marginals:")?;
+            for (id, [p0, p1]) in &self.marginals {
+                writeln!(f, "    {}: P(0)={:.4}, P(1)={:.4}", id, p0, p1)?;
+            }
+        }
+        if !self.snapshots.is_empty() {
+            let labels: Vec<&str> = self.snapshots.keys().map(String::as_str).collect();
+            writeln!(f, "  Snapshots: {}", labels.join(", "))?;
+        }
         Ok(())
     }
 }
+
+// The captured states are snapshots of the run rather than part of its outcome, so they
+// are left out of comparisons, as are the timings and the unobservable global phase (the
+// marginals derived from the states are compared).
+impl PartialEq for SimulationResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.stable_outcomes == other.stable_outcomes
+            && self.classical_bits == other.classical_bits
+            && self.semantics == other.semantics
+            && self.readout_flips == other.readout_flips
+            && self.marginals == other.marginals
+    }
+}
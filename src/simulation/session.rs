@@ -0,0 +1,184 @@
+// src/simulation/session.rs
+use super::engine::SimulationEngine;
+use super::{Checkpoint, SimulationResult, Simulator};
+use crate::circuits::Circuit;
+use crate::core::{OnqError, PotentialityState};
+use crate::operations::Operation;
+
+/// A simulation of a circuit that executes one operation at a time, created with
+/// [`Simulator::session`].
+///
+/// The session can be advanced with [`step`](SimulationSession::step) or
+/// [`run_to`](SimulationSession::run_to) and inspected between operations with
+/// [`peek_state`](SimulationSession::peek_state), which suits interactive exploration
+/// and debuggers. [`finish`](SimulationSession::finish) runs the remaining operations
+/// and returns the same result as [`Simulator::run`].
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator, StableState};
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new().h(q0).h(q0).stabilize(&[q0]).build();
+/// let simulator = Simulator::new();
+///
+/// let mut session = simulator.session(&circuit).unwrap();
+/// session.step().unwrap();
+/// assert!((session.peek_state().network[&0].core_state[1].norm_sqr() - 0.5).abs() < 1e-12);
+///
+/// session.run_to(2).unwrap();
+/// assert!(session.peek_state().network[&0].core_state[1].norm_sqr() < 1e-12);
+///
+/// let result = session.finish().unwrap();
+/// assert_eq!(result.get_stable_state(&q0), Some(&StableState::ResolvedQuality(0)));
+/// ```
+pub struct SimulationSession<'a> {
+    /// The simulator whose settings the session runs under.
+    simulator: &'a Simulator,
+    /// The circuit being executed.
+    circuit: &'a Circuit,
+    /// The engine holding the state after the executed operations.
+    engine: SimulationEngine,
+    /// Outcomes recorded by the executed operations.
+    result: SimulationResult,
+    /// Index of the next operation to execute.
+    position: usize,
+}
+
+impl<'a> SimulationSession<'a> {
+    /// Creates a session positioned before the first operation. (Internal visibility)
+    pub(crate) fn new(
+        simulator: &'a Simulator,
+        circuit: &'a Circuit,
+        engine: SimulationEngine,
+        result: SimulationResult,
+    ) -> Self {
+        Self {
+            simulator,
+            circuit,
+            engine,
+            result,
+            position: 0,
+        }
+    }
+
+    /// Returns the index of the next operation to execute.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns `true` once every operation of the circuit has been executed.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.circuit.len()
+    }
+
+    /// Returns the operation executed by the next [`step`](SimulationSession::step),
+    /// if any remain.
+    pub fn next_operation(&self) -> Option<&'a Operation> {
+        self.circuit.operations().get(self.position)
+    }
+
+    /// Executes the next operation and returns it, or `None` if the circuit is finished.
+    ///
+    /// # Errors
+    /// Returns any error raised by the operation. The session does not advance past a
+    /// failed operation.
+    pub fn step(&mut self) -> Result<Option<&'a Operation>, OnqError> {
+        let Some(op) = self.next_operation() else {
+            return Ok(None);
+        };
+        self.simulator.execute_step(
+            &mut self.engine,
+            self.circuit,
+            self.position,
+            &mut self.result,
+        )?;
+        self.position += 1;
+        Ok(Some(op))
+    }
+
+    /// Executes operations until `op_index` is the next one to run, pausing before it.
+    /// `run_to(circuit.len())` runs to the end without the final validation of
+    /// [`finish`](SimulationSession::finish).
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if `op_index` lies beyond the end of the
+    /// circuit or before the current position, and any error raised by an operation.
+    pub fn run_to(&mut self, op_index: usize) -> Result<(), OnqError> {
+        if op_index > self.circuit.len() || op_index < self.position {
+            return Err(OnqError::InvalidOperation {
+                message: format!(
+                    "Cannot run to operation {}: the session is at operation {} of {}",
+                    op_index,
+                    self.position,
+                    self.circuit.len()
+                ),
+            });
+        }
+        while self.position < op_index {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the state after the executed operations, indexed by physical IVM node
+    /// as in [`SimulationResult::final_state`].
+    pub fn peek_state(&self) -> &PotentialityState {
+        self.engine.get_state()
+    }
+
+    /// Returns the global phase accumulated so far (0 unless
+    /// [global phase tracking](super::SimulatorConfig::with_global_phase_tracking) is
+    /// enabled).
+    pub fn global_phase(&self) -> f64 {
+        self.engine.global_phase()
+    }
+
+    /// Returns the outcomes recorded so far.
+    pub fn result(&self) -> &SimulationResult {
+        &self.result
+    }
+
+    /// Captures the engine state and the outcomes recorded so far, so the remaining
+    /// operations can be run later with [`Simulator::resume`].
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if the circuit has qudits, whose states a
+    /// checkpoint cannot hold.
+    pub fn checkpoint(&self) -> Result<Checkpoint, OnqError> {
+        let qudits = self.engine.qudit_qdus();
+        if !qudits.is_empty() {
+            let names: Vec<String> = qudits.iter().map(ToString::to_string).collect();
+            return Err(OnqError::InvalidOperation {
+                message: format!(
+                    "Cannot checkpoint a run with qudits ({}); checkpoints hold binary QDUs only",
+                    names.join(", ")
+                ),
+            });
+        }
+        Ok(
+            Checkpoint::new(self.engine.qdu_nodes(), self.engine.get_state().clone())
+                .expect("the engine maps its QDUs onto nodes 0..n")
+                .with_outcomes(self.result.all_stable_outcomes())
+                .with_global_phase(self.engine.global_phase()),
+        )
+    }
+
+    /// Returns the operations not yet executed.
+    pub fn remaining_operations(&self) -> &'a [Operation] {
+        &self.circuit.operations()[self.position.min(self.circuit.len())..]
+    }
+
+    /// Executes the remaining operations, validates the final state and returns the
+    /// recorded outcomes.
+    ///
+    /// # Errors
+    /// Returns any error raised by a remaining operation or by the final validation.
+    pub fn finish(mut self) -> Result<SimulationResult, OnqError> {
+        self.run_to(self.circuit.len())?;
+        self.engine.validate()?;
+        self.result.set_global_phase(self.engine.global_phase());
+        self.result
+            .record_renormalizations(self.engine.renormalizations());
+        Ok(self.result)
+    }
+}
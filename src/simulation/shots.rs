@@ -0,0 +1,140 @@
+// src/simulation/shots.rs
+use crate::core::{PotentialityState, QduId};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The joint stabilization outcome of one shot: each stabilized QDU mapped to its
+/// resolved quality. Ordered by QDU so outcomes compare and display consistently.
+pub type JointOutcome = BTreeMap<QduId, u64>;
+
+/// How [`Simulator::run_shots`](super::Simulator::run_shots) varies the deterministic
+/// stabilization seed from one shot to the next.
+///
+/// Stabilization is deterministic for a given state, so without variation every shot
+/// of a circuit resolves identically. Each mode derives a per-shot *salt* mixed into
+/// the state-derived seed; a salt of 0 reproduces [`Simulator::run`](super::Simulator::run).
+///
+/// # Examples
+/// ```
+/// # use onq::simulation::SeedMode;
+/// assert_eq!(SeedMode::PerShot.salt(3), 3);
+/// assert_eq!(SeedMode::Fixed.salt(3), 0);
+/// assert_eq!(SeedMode::Seeded(7).salt(3), SeedMode::Seeded(7).salt(3));
+/// assert_ne!(SeedMode::Seeded(7).salt(3), SeedMode::Seeded(8).salt(3));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SeedMode {
+    /// Each shot uses its index as the salt, so shot 0 matches a single run.
+    #[default]
+    PerShot,
+    /// Every shot uses salt 0, reproducing the single-run outcome in each shot.
+    Fixed,
+    /// Salts are derived from the given seed and the shot index, giving independent
+    /// but reproducible sequences of shots for different seeds.
+    Seeded(u64),
+}
+
+impl SeedMode {
+    /// Returns the salt mixed into the stabilization seed of shot `shot`.
+    pub fn salt(&self, shot: u64) -> u64 {
+        match self {
+            SeedMode::PerShot => shot,
+            SeedMode::Fixed => 0,
+            SeedMode::Seeded(seed) => {
+                // SplitMix64 finalizer over the seeded shot counter
+                let mut z =
+                    seed.wrapping_add(shot.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^ (z >> 31)
+            }
+        }
+    }
+}
+
+/// Aggregated results of running a circuit for multiple shots.
+#[derive(Debug, Clone)]
+pub struct ShotResults {
+    /// Number of shots executed.
+    shots: usize,
+    /// How the stabilization seed varied across shots.
+    seed_mode: SeedMode,
+    /// Maps each joint outcome to the number of shots that produced it.
+    counts: HashMap<JointOutcome, usize>,
+    /// For selected (heralded) outcomes, the final state of the first shot producing it.
+    heralded_states: HashMap<JointOutcome, PotentialityState>,
+}
+
+impl ShotResults {
+    /// Creates an empty result set for `shots` shots. (Internal visibility)
+    pub(crate) fn new(shots: usize, seed_mode: SeedMode) -> Self {
+        Self {
+            shots,
+            seed_mode,
+            counts: HashMap::new(),
+            heralded_states: HashMap::new(),
+        }
+    }
+
+    /// Records one shot's outcome, keeping `state` as the representative
+    /// residual state if this is the first heralded shot with that outcome.
+    pub(crate) fn record_shot(&mut self, outcome: JointOutcome, state: Option<PotentialityState>) {
+        if let Some(state) = state {
+            self.heralded_states.entry(outcome.clone()).or_insert(state);
+        }
+        *self.counts.entry(outcome).or_insert(0) += 1;
+    }
+
+    /// Returns the number of shots executed.
+    pub fn shots(&self) -> usize {
+        self.shots
+    }
+
+    /// Returns how the stabilization seed varied across shots.
+    pub fn seed_mode(&self) -> SeedMode {
+        self.seed_mode
+    }
+
+    /// Returns the histogram of joint outcomes.
+    pub fn counts(&self) -> &HashMap<JointOutcome, usize> {
+        &self.counts
+    }
+
+    /// Returns how many shots produced `outcome`.
+    pub fn count(&self, outcome: &JointOutcome) -> usize {
+        self.counts.get(outcome).copied().unwrap_or(0)
+    }
+
+    /// Returns the representative residual state captured for a heralded `outcome`:
+    /// the full state (including un-stabilized QDUs) at the end of the first shot
+    /// that produced it. `None` if the outcome was never selected.
+    pub fn heralded_state(&self, outcome: &JointOutcome) -> Option<&PotentialityState> {
+        self.heralded_states.get(outcome)
+    }
+
+    /// Returns all captured heralded states, keyed by outcome.
+    pub fn heralded_states(&self) -> &HashMap<JointOutcome, PotentialityState> {
+        &self.heralded_states
+    }
+}
+
+impl fmt::Display for ShotResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Shot Results ({} shots):", self.shots)?;
+        let mut sorted: Vec<_> = self.counts.iter().collect();
+        sorted.sort();
+        for (outcome, count) in sorted {
+            let label: Vec<String> = outcome
+                .iter()
+                .map(|(qdu, value)| format!("{}={}", qdu, value))
+                .collect();
+            let herald = if self.heralded_states.contains_key(outcome) {
+                " (heralded)"
+            } else {
+                ""
+            };
+            writeln!(f, "  [{}]: {}{}", label.join(", "), count, herald)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+// src/simulation/strategy.rs
+
+//! Interchangeable rules for selecting stabilization outcomes.
+//!
+//! Stabilization first derives a deterministic *draw* in `[0, 1)` from the state of
+//! the QDU (and the shot salt), then asks a [`StabilizationStrategy`] to pick a quality
+//! from the normalized quality weights and that draw. The engine then collapses the
+//! QDU onto the selected quality. Swapping the strategy on a
+//! [`Simulator`](super::Simulator) compares interpretations without touching the engine.
+
+use crate::core::PHI;
+use std::fmt;
+
+/// Selects the quality a QDU stabilizes to.
+///
+/// Implementations must be deterministic: the same weights and draw always give the
+/// same quality, so runs stay reproducible.
+///
+/// # Examples
+/// ```
+/// # use onq::{CircuitBuilder, QduId, Simulator, StableState};
+/// # use onq::simulation::StabilizationStrategy;
+/// /// Always resolves to the less likely quality.
+/// #[derive(Debug)]
+/// struct Contrarian;
+///
+/// impl StabilizationStrategy for Contrarian {
+///     fn name(&self) -> &str {
+///         "contrarian"
+///     }
+///     fn select(&self, weights: [f64; 2], _draw: f64) -> u8 {
+///         if weights[0] >= weights[1] { 1 } else { 0 }
+///     }
+/// }
+///
+/// let q0 = QduId(0);
+/// let circuit = CircuitBuilder::new().stabilize(&[q0]).build();
+/// let result = Simulator::new().with_strategy(Contrarian).run(&circuit).unwrap();
+/// assert_eq!(result.get_stable_state(&q0), Some(&StableState::ResolvedQuality(1)));
+/// ```
+pub trait StabilizationStrategy: fmt::Debug + Send + Sync {
+    /// A short name identifying the strategy.
+    fn name(&self) -> &str;
+
+    /// Returns the selected quality (0 or 1) given the normalized quality weights
+    /// `[|a|², |b|²]` and a deterministic draw in `[0, 1)`.
+    fn select(&self, weights: [f64; 2], draw: f64) -> u8;
+}
+
+/// Draws the outcome from the quality weights alone: Quality0 is selected when the
+/// draw falls below its weight. This is the rule of [`SemanticsVersion::V1`](super::SemanticsVersion::V1).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AmplitudeWeighted;
+
+impl StabilizationStrategy for AmplitudeWeighted {
+    fn name(&self) -> &str {
+        "amplitude-weighted"
+    }
+
+    fn select(&self, weights: [f64; 2], draw: f64) -> u8 {
+        if draw <= weights[0] { 0 } else { 1 }
+    }
+}
+
+/// Selects a quality outright when its weight exceeds `threshold`, otherwise draws as
+/// [`AmplitudeWeighted`]. The default threshold is the Golden Ratio coherence
+/// threshold 1/φ ≈ 0.618, the rule of [`SemanticsVersion::V2`](super::SemanticsVersion::V2).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoherenceFiltered {
+    /// The weight above which a quality is selected without drawing.
+    pub threshold: f64,
+}
+
+impl Default for CoherenceFiltered {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0 / PHI,
+        }
+    }
+}
+
+impl StabilizationStrategy for CoherenceFiltered {
+    fn name(&self) -> &str {
+        "coherence-filtered"
+    }
+
+    fn select(&self, weights: [f64; 2], draw: f64) -> u8 {
+        if weights[0] > self.threshold {
+            0
+        } else if weights[1] > self.threshold {
+            1
+        } else {
+            AmplitudeWeighted.select(weights, draw)
+        }
+    }
+}
+
+/// Always selects the quality with the larger weight, ignoring the draw; ties resolve
+/// to Quality0. Every shot of a circuit then resolves identically.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MaxWeight;
+
+impl StabilizationStrategy for MaxWeight {
+    fn name(&self) -> &str {
+        "max-weight"
+    }
+
+    fn select(&self, weights: [f64; 2], _draw: f64) -> u8 {
+        if weights[1] > weights[0] { 1 } else { 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_strategies() {
+        let dominant = [0.2, 0.8];
+        let balanced = [0.5, 0.5];
+
+        assert_eq!(AmplitudeWeighted.select(dominant, 0.1), 0);
+        assert_eq!(AmplitudeWeighted.select(dominant, 0.9), 1);
+
+        let filtered = CoherenceFiltered::default();
+        assert_eq!(filtered.select(dominant, 0.1), 1);
+        assert_eq!(filtered.select(balanced, 0.1), 0);
+        assert_eq!(filtered.select(balanced, 0.9), 1);
+        assert_eq!(
+            CoherenceFiltered { threshold: 0.9 }.select(dominant, 0.1),
+            0
+        );
+
+        assert_eq!(MaxWeight.select(dominant, 0.0), 1);
+        assert_eq!(MaxWeight.select(balanced, 0.99), 0);
+    }
+}
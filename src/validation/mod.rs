@@ -5,7 +5,8 @@
 use crate::core::{OnqError, PotentialityState};
 
 // Default tolerance values
-const DEFAULT_NORM_TOLERANCE: f64 = 1e-6; // Slightly relaxed for tensor product accumulation
+pub(crate) const DEFAULT_NORM_TOLERANCE: f64 = 1e-6; // Slightly relaxed for tensor product accumulation
+pub(crate) const DEFAULT_AMPLITUDE_TOLERANCE: f64 = 1e-9;
 const DEFAULT_COHERENCE_THRESHOLD: f64 = 0.618; // The Golden Ratio (1/phi)
 
 // --- Public Validation Functions ---
@@ -31,8 +32,43 @@ pub fn check_normalization(
     }
 }
 
-/// Calculates a global measure of phase coherence for the geometric matrix.
-/// It computes the average internal phase alignment of all active LocalTensors.
+/// Checks that every local tensor is finite and normalized on its own.
+/// Unlike [`check_normalization`], an over-normalized node cannot hide behind an
+/// under-normalized one.
+pub fn check_local_normalization(
+    state: &PotentialityState,
+    tolerance: Option<f64>,
+) -> Result<(), OnqError> {
+    let effective_tolerance = tolerance.unwrap_or(DEFAULT_AMPLITUDE_TOLERANCE);
+    let mut nodes: Vec<_> = state.network.iter().collect();
+    nodes.sort_by_key(|(id, _)| **id);
+    for (id, tensor) in nodes {
+        let [amp0, amp1] = tensor.core_state;
+        if !(amp0.re.is_finite()
+            && amp0.im.is_finite()
+            && amp1.re.is_finite()
+            && amp1.im.is_finite())
+        {
+            return Err(OnqError::Incoherence {
+                message: format!(
+                    "Node {} holds non-finite amplitudes {:?}",
+                    id, tensor.core_state
+                ),
+            });
+        }
+        let norm_sq = amp0.norm_sqr() + amp1.norm_sqr();
+        if (norm_sq - 1.0).abs() > effective_tolerance {
+            return Err(OnqError::Incoherence {
+                message: format!(
+                    "Node {} is not normalized. Local norm squared: {}",
+                    id, norm_sq
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Calculates a global measure of phase coherence for the geometric matrix.
 /// It computes the average internal phase alignment of all active LocalTensors.
 pub fn calculate_global_phase_coherence(state: &PotentialityState) -> f64 {
@@ -87,16 +123,20 @@ pub fn check_phase_coherence(
     }
 }
 
-/// Performs basic validation checks on the geometric state.
+/// Performs basic validation checks on the geometric state: the global norm and, if
+/// `amplitude_tolerance` is given, the norm of every local tensor.
 /// We keep the unused parameters prefixed with `_` so we don't break the VM interpreter calls.
 pub fn validate_state(
     state: &PotentialityState,
     _num_qdus: usize,
     norm_tolerance: Option<f64>,
     _coherence_threshold: Option<f64>,
-    _amplitude_tolerance: Option<f64>,
+    amplitude_tolerance: Option<f64>,
 ) -> Result<(), OnqError> {
     check_normalization(state, norm_tolerance)?;
+    if amplitude_tolerance.is_some() {
+        check_local_normalization(state, amplitude_tolerance)?;
+    }
     Ok(())
 }
 
@@ -120,6 +160,15 @@ mod tests {
 
         // The geometric check should catch the localized collapse
         assert!(check_normalization(&state, None).is_err());
+
+        // A second node compensating the loss hides it from the global product only
+        if let Some(tensor) = state.network.get_mut(&1) {
+            tensor.core_state[0] = Complex::new(2.0, 0.0);
+        }
+        assert!(check_normalization(&state, None).is_ok());
+        assert!(check_local_normalization(&state, None).is_err());
+        assert!(validate_state(&state, 2, None, None, None).is_ok());
+        assert!(validate_state(&state, 2, None, None, Some(1e-9)).is_err());
     }
 
     #[test]
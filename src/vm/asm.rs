@@ -0,0 +1,1162 @@
+// src/vm/asm.rs
+
+//! The `onq-asm` text format for VM programs.
+//!
+//! A program is written one instruction per line as a mnemonic followed by its
+//! comma-separated operands, in the order of the fields of the matching
+//! [`Instruction`] variant. Mnemonics are the variant names in snake case
+//! (`BranchIfNotZero` is `branch_if_not_zero`, `FPhaseShift` is `f_phase_shift`).
+//! A line holding only `name:` defines a label at the next instruction, and `;`
+//! starts a comment running to the end of the line. The directives `.export name`
+//! and `.import name` declare labels shared with other programs when linked (see
+//! [`ProgramBuilder::export`] and [`ProgramBuilder::import`]).
+//!
+//! [`parse_asm`] ([`Program::parse`]) reads the format and [`write_asm`]
+//! ([`Program::to_asm`]) writes it, so a program survives the round trip unchanged.
+//!
+//! | Operand                  | Syntax                                                    |
+//! |--------------------------|-----------------------------------------------------------|
+//! | register, label, pattern | a bare word (letters, digits, `_ . + -`) or a quoted string |
+//! | QDU                      | `q<id>`, e.g. `q3`                                        |
+//! | integer, float           | `42`, `-0.5`, `1e-9`, `inf`, `NaN`                        |
+//! | list                     | `[a, b, ...]`                                             |
+//! | pair, complex number     | `(a, b)`; a complex number is `(re, im)`                  |
+//! | quantum operation        | the `Operation` variant in snake case with its fields in parentheses, e.g. `interaction_pattern(q0, Superposition)` |
+//! | lock type, quality, axis | the variant name, e.g. `BellPhiPlus`, `Quality1`, `Z`     |
+//!
+//! Quoted strings take the escapes `\\`, `\"`, `\n`, `\r`, `\t`, `\0` and `\u{...}`.
+//!
+//! ```text
+//! ; Counts the 1 outcomes of three superposed QDUs
+//!     load_immediate ones, 0
+//!     quantum_op broadcast_pattern([q0, q1, q2], Superposition)
+//!     stabilize [q0, q1, q2]
+//!     record q0, m
+//!     branch_if_zero m, next
+//!     addi ones, ones, 1
+//! next:
+//!     print "ones = {}", [ones]
+//!     halt
+//! ```
+
+use super::program::{Instruction, LockType, Program, ProgramBuilder};
+use crate::core::{OnqError, QduId};
+use crate::operations::{Operation, PauliAxis, Quality};
+use num_complex::Complex;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Parses an `onq-asm` program (see the [module docs](self) for the syntax).
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` describing the first malformed line, with its
+/// line number, or the validation error of [`ProgramBuilder::build`].
+///
+/// # Examples
+/// ```
+/// # use onq::vm::asm::parse_asm;
+/// # use onq::vm::OnqVm;
+/// let program = parse_asm(r#"
+///     quantum_op interaction_pattern(q0, QualityFlip)
+///     stabilize [q0]
+///     record q0, m
+///     branch_if_zero m, done
+///     print "flipped"
+/// done:
+///     halt
+/// "#).unwrap();
+/// assert_eq!(program.instruction_count(), 6);
+///
+/// let mut vm = OnqVm::new().with_output(|_| {});
+/// assert_eq!(vm.run(&program).unwrap().register("m"), 1);
+/// ```
+pub fn parse_asm(source: &str) -> Result<Program, OnqError> {
+    let mut builder = ProgramBuilder::new();
+    let mut labels = HashSet::new();
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| OnqError::InvalidOperation {
+            message: format!("asm line {}: {}", index + 1, message),
+        };
+        let tokens = tokenize(line).map_err(error)?;
+        match tokens.as_slice() {
+            [
+                Token::Word(directive),
+                Token::Word(label) | Token::Str(label),
+            ] if directive == ".export" => {
+                builder = builder.export(label);
+                continue;
+            }
+            [
+                Token::Word(directive),
+                Token::Word(label) | Token::Str(label),
+            ] if directive == ".import" => {
+                builder = builder.import(label);
+                continue;
+            }
+            _ => {}
+        }
+        match parse_line(&tokens).map_err(error)? {
+            Some(Instruction::Label(label)) => {
+                if !labels.insert(label.clone()) {
+                    return Err(error(format!("label '{}' is defined twice", label)));
+                }
+                builder = builder.pb_add(Instruction::Label(label));
+            }
+            Some(instruction) => builder = builder.pb_add(instruction),
+            None => {}
+        }
+    }
+    builder
+        .build()
+        .map_err(|message| OnqError::InvalidOperation {
+            message: format!("asm: {}", message),
+        })
+}
+
+/// Writes `program` in the `onq-asm` format, one instruction per line, with its labels
+/// on their own lines before the instructions they resolve to (sorted by name where
+/// several share one), after its `.import` and `.export` directives. [`parse_asm`]
+/// reads the text back into an equal program.
+///
+/// Names are written as bare words where possible and quoted otherwise, and floats in
+/// their shortest exact form.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::asm::{parse_asm, write_asm};
+/// # use onq::vm::{Instruction, ProgramBuilder};
+/// let program = ProgramBuilder::new()
+///     .pb_add(Instruction::Label("start".to_string()))
+///     .pb_add(Instruction::FLoad { register: "theta".to_string(), value: 0.1 })
+///     .pb_add(Instruction::Print { format: "at start".to_string(), registers: vec![] })
+///     .pb_add(Instruction::Jump("start".to_string()))
+///     .build()
+///     .unwrap();
+///
+/// let text = write_asm(&program);
+/// assert_eq!(text, "start:\n    f_load theta, 0.1\n    print \"at start\"\n    jump start\n");
+/// assert_eq!(parse_asm(&text).unwrap(), program);
+/// ```
+pub fn write_asm(program: &Program) -> String {
+    let mut labels: Vec<(usize, &str)> = program
+        .label_map
+        .iter()
+        .map(|(label, pc)| (*pc, label.as_str()))
+        .collect();
+    labels.sort();
+    let mut labels = labels.into_iter().peekable();
+
+    let mut text = String::new();
+    for label in &program.imports {
+        let _ = writeln!(text, ".import {}", write_name(label));
+    }
+    for label in &program.exports {
+        let _ = writeln!(text, ".export {}", write_name(label));
+    }
+    for pc in 0..=program.instructions.len() {
+        while let Some((_, label)) = labels.next_if(|(at, _)| *at <= pc) {
+            let _ = writeln!(text, "{}:", write_name(label));
+        }
+        if let Some(instruction) = program.instructions.get(pc) {
+            let _ = writeln!(text, "    {}", write_instruction(instruction));
+        }
+    }
+    text
+}
+
+/// Writes one instruction as its mnemonic and operands.
+pub(crate) fn write_instruction(instruction: &Instruction) -> String {
+    let operands = match instruction {
+        Instruction::QuantumOp(op) => vec![write_operation(op)],
+        Instruction::QuantumOpIf { register, op } => {
+            vec![write_name(register), write_operation(op)]
+        }
+        Instruction::Stabilize { targets } | Instruction::ResetQdu { targets } => {
+            vec![write_qdus(targets)]
+        }
+        Instruction::Record { qdu, register } => vec![write_qdu(*qdu), write_name(register)],
+        Instruction::StabilizeInto { targets } => {
+            vec![write_list(targets.iter().map(|(qdu, register)| {
+                format!("({}, {})", write_qdu(*qdu), write_name(register))
+            }))]
+        }
+        Instruction::Label(label) => return format!("{}:", write_name(label)),
+        Instruction::Jump(label) | Instruction::Call(label) => vec![write_name(label)],
+        Instruction::BranchIfZero { register, label }
+        | Instruction::BranchIfNotZero { register, label }
+        | Instruction::Repeat { register, label } => {
+            vec![write_name(register), write_name(label)]
+        }
+        Instruction::BranchIfEq { r1, r2, label }
+        | Instruction::BranchIfNe { r1, r2, label }
+        | Instruction::BranchIfLt { r1, r2, label }
+        | Instruction::BranchIfGe { r1, r2, label } => {
+            vec![write_name(r1), write_name(r2), write_name(label)]
+        }
+        Instruction::Return | Instruction::Halt | Instruction::NoOp => vec![],
+        Instruction::LoadImmediate { register, value }
+        | Instruction::Rand {
+            register,
+            upper_bound: value,
+        } => vec![write_name(register), value.to_string()],
+        Instruction::Copy {
+            source_reg,
+            dest_reg,
+        } => vec![write_name(source_reg), write_name(dest_reg)],
+        Instruction::Store {
+            base,
+            index_reg,
+            src: register,
+        }
+        | Instruction::Load {
+            base,
+            index_reg,
+            dest: register,
+        } => vec![
+            write_name(base),
+            write_name(index_reg),
+            write_name(register),
+        ],
+        // Format strings are always quoted, to read as text
+        Instruction::Print { format, registers } if registers.is_empty() => {
+            vec![format!("{:?}", format)]
+        }
+        Instruction::Print { format, registers } => vec![
+            format!("{:?}", format),
+            write_list(registers.iter().map(|r| write_name(r))),
+        ],
+        Instruction::HostCall { name, args, ret } => vec![
+            write_name(name),
+            write_list(args.iter().map(|r| write_name(r))),
+            write_name(ret),
+        ],
+        Instruction::Addi {
+            r_dest,
+            r_src,
+            value,
+        } => vec![write_name(r_dest), write_name(r_src), value.to_string()],
+        Instruction::OnqNot { r_dest, r_src } | Instruction::FFromBits { r_dest, r_src } => {
+            vec![write_name(r_dest), write_name(r_src)]
+        }
+        Instruction::OnqAdd {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::And {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Or {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Xor {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Sub {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Mul {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpEq {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpGt {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpLt {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpLtS {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::SubS {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::FAdd {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::FMul {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::FCmp {
+            r_dest,
+            r_src1,
+            r_src2,
+        } => vec![write_name(r_dest), write_name(r_src1), write_name(r_src2)],
+        Instruction::SignExtend {
+            r_dest,
+            r_src,
+            bits,
+        } => vec![write_name(r_dest), write_name(r_src), bits.to_string()],
+        Instruction::FLoad { register, value } => {
+            vec![write_name(register), format!("{:?}", value)]
+        }
+        Instruction::FPhaseShift { target, theta_reg } => {
+            vec![write_qdu(*target), write_name(theta_reg)]
+        }
+        Instruction::QuantumOpDyn {
+            op_template,
+            angle_register,
+        } => vec![write_operation(op_template), write_name(angle_register)],
+    };
+    let mnemonic = snake_case(instruction.kind());
+    if operands.is_empty() {
+        mnemonic
+    } else {
+        format!("{} {}", mnemonic, operands.join(", "))
+    }
+}
+
+/// Writes a quantum operation as `name(operands)`.
+fn write_operation(op: &Operation) -> String {
+    let operands = match op {
+        Operation::PhaseShift { target, theta } => vec![write_qdu(*target), format!("{:?}", theta)],
+        Operation::InteractionPattern { target, pattern_id } => {
+            vec![write_qdu(*target), write_name(pattern_id)]
+        }
+        Operation::BroadcastPattern {
+            targets,
+            pattern_id,
+        } => vec![write_qdus(targets), write_name(pattern_id)],
+        Operation::BroadcastPhaseShift { targets, theta } => {
+            vec![write_qdus(targets), format!("{:?}", theta)]
+        }
+        Operation::ControlledInteraction {
+            control,
+            target,
+            pattern_id,
+        } => vec![
+            write_qdu(*control),
+            write_qdu(*target),
+            write_name(pattern_id),
+        ],
+        Operation::PauliProduct { terms, theta } => vec![
+            write_list(
+                terms
+                    .iter()
+                    .map(|(qdu, axis)| format!("({}, {:?})", write_qdu(*qdu), axis)),
+            ),
+            format!("{:?}", theta),
+        ],
+        Operation::Permute { mapping } => {
+            vec![write_list(mapping.iter().map(|(from, to)| {
+                format!("({}, {})", write_qdu(*from), write_qdu(*to))
+            }))]
+        }
+        Operation::RelationalLock {
+            qdu1,
+            qdu2,
+            lock_type,
+            establish,
+        } => vec![
+            write_qdu(*qdu1),
+            write_qdu(*qdu2),
+            format!("{:?}", lock_type),
+            establish.to_string(),
+        ],
+        Operation::MatrixPattern { target, matrix } => vec![
+            write_qdu(*target),
+            write_matrix(matrix.iter().map(|row| row.as_slice())),
+        ],
+        Operation::QuditPattern { target, matrix } => vec![
+            write_qdu(*target),
+            write_matrix(matrix.iter().map(Vec::as_slice)),
+        ],
+        Operation::Project { target, onto } => vec![write_qdu(*target), format!("{:?}", onto)],
+        Operation::Relax { target, rate } => vec![write_qdu(*target), format!("{:?}", rate)],
+        Operation::Delay { targets, ticks } => vec![write_qdus(targets), ticks.to_string()],
+        Operation::Snapshot { label } => vec![write_name(label)],
+        Operation::Stabilize { targets } => vec![write_qdus(targets)],
+    };
+    format!("{}({})", snake_case(op.kind()), operands.join(", "))
+}
+
+/// Converts a variant name to its mnemonic (`FPhaseShift` to `f_phase_shift`).
+fn snake_case(kind: &str) -> String {
+    let mut mnemonic = String::new();
+    for (i, c) in kind.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            mnemonic.push('_');
+        }
+        mnemonic.push(c.to_ascii_lowercase());
+    }
+    mnemonic
+}
+
+/// Writes a name as a bare word if it is one, quoted otherwise.
+fn write_name(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(is_word_char) {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+fn write_qdu(qdu: QduId) -> String {
+    format!("q{}", qdu.0)
+}
+
+fn write_qdus(qdus: &[QduId]) -> String {
+    write_list(qdus.iter().map(|qdu| write_qdu(*qdu)))
+}
+
+fn write_list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(", "))
+}
+
+/// Writes a matrix of complex numbers as a list of rows.
+fn write_matrix<'a>(rows: impl Iterator<Item = &'a [Complex<f64>]>) -> String {
+    write_list(rows.map(|row| {
+        write_list(
+            row.iter()
+                .map(|entry| format!("({:?}, {:?})", entry.re, entry.im)),
+        )
+    }))
+}
+
+/// A lexical token of a line.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare word: a mnemonic, name, QDU, number or variant name.
+    Word(String),
+    /// A quoted string, unescaped.
+    Str(String),
+    /// One of `[ ] ( ) , :`.
+    Punct(char),
+}
+
+/// Splits a line into tokens, dropping its comment.
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' => break,
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' | ']' | '(' | ')' | ',' | ':' => {
+                tokens.push(Token::Punct(c));
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Str(unescape(&mut chars)?));
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|&&c| is_word_char(c)) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Returns `true` for the characters of a bare word.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-')
+}
+
+/// Reads the rest of a quoted string, after its opening quote.
+fn unescape(chars: &mut impl Iterator<Item = char>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next().ok_or("unterminated string")? {
+            '"' => return Ok(text),
+            '\\' => match chars.next().ok_or("unterminated string")? {
+                '\\' => text.push('\\'),
+                '"' => text.push('"'),
+                '\'' => text.push('\''),
+                'n' => text.push('\n'),
+                'r' => text.push('\r'),
+                't' => text.push('\t'),
+                '0' => text.push('\0'),
+                'u' => {
+                    if chars.next() != Some('{') {
+                        return Err("expected '{' after '\\u'".to_string());
+                    }
+                    let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape '\\u{{{}}}'", hex))?;
+                    text.push(c);
+                }
+                c => return Err(format!("unknown escape '\\{}'", c)),
+            },
+            c => text.push(c),
+        }
+    }
+}
+
+/// An operand, before it is interpreted by its instruction.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    /// A bare word.
+    Word(String),
+    /// A quoted string.
+    Str(String),
+    /// `[a, b, ...]`
+    List(Vec<Value>),
+    /// `(a, b, ...)`
+    Tuple(Vec<Value>),
+    /// `name(a, b, ...)`
+    Call(String, Vec<Value>),
+}
+
+/// Parses the tokens of a line into an instruction, a label definition or nothing.
+fn parse_line(tokens: &[Token]) -> Result<Option<Instruction>, String> {
+    let (mnemonic, rest) = match tokens {
+        [] => return Ok(None),
+        [Token::Word(label) | Token::Str(label), Token::Punct(':')] => {
+            return Ok(Some(Instruction::Label(label.clone())));
+        }
+        [Token::Word(mnemonic), rest @ ..] => (mnemonic.as_str(), rest),
+        _ => return Err("expected a mnemonic or a label definition".to_string()),
+    };
+
+    let mut position = 0;
+    let mut operands = Vec::new();
+    if !rest.is_empty() {
+        loop {
+            operands.push(parse_value(rest, &mut position)?);
+            match rest.get(position) {
+                None => break,
+                Some(Token::Punct(',')) => position += 1,
+                Some(token) => return Err(format!("expected ',' but found {}", describe(token))),
+            }
+        }
+    }
+    instruction(mnemonic, &operands).map(Some)
+}
+
+/// Parses one operand starting at `tokens[*position]`.
+fn parse_value(tokens: &[Token], position: &mut usize) -> Result<Value, String> {
+    let token = tokens.get(*position).ok_or("missing operand")?;
+    *position += 1;
+    match token {
+        Token::Word(word) => {
+            if tokens.get(*position) == Some(&Token::Punct('(')) {
+                *position += 1;
+                Ok(Value::Call(
+                    word.clone(),
+                    parse_sequence(tokens, position, ')')?,
+                ))
+            } else {
+                Ok(Value::Word(word.clone()))
+            }
+        }
+        Token::Str(text) => Ok(Value::Str(text.clone())),
+        Token::Punct('[') => Ok(Value::List(parse_sequence(tokens, position, ']')?)),
+        Token::Punct('(') => Ok(Value::Tuple(parse_sequence(tokens, position, ')')?)),
+        token => Err(format!("expected an operand but found {}", describe(token))),
+    }
+}
+
+/// Parses comma-separated operands up to and including the closing `close`.
+fn parse_sequence(
+    tokens: &[Token],
+    position: &mut usize,
+    close: char,
+) -> Result<Vec<Value>, String> {
+    let mut values = Vec::new();
+    if tokens.get(*position) == Some(&Token::Punct(close)) {
+        *position += 1;
+        return Ok(values);
+    }
+    loop {
+        values.push(parse_value(tokens, position)?);
+        match tokens.get(*position) {
+            Some(Token::Punct(',')) => *position += 1,
+            Some(Token::Punct(c)) if *c == close => {
+                *position += 1;
+                return Ok(values);
+            }
+            Some(token) => {
+                return Err(format!(
+                    "expected ',' or '{}' but found {}",
+                    close,
+                    describe(token)
+                ));
+            }
+            None => return Err(format!("missing '{}'", close)),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Str(text) => format!("{:?}", text),
+        Token::Punct(c) => format!("'{}'", c),
+    }
+}
+
+/// Builds the instruction named by `mnemonic` from its operands.
+fn instruction(mnemonic: &str, operands: &[Value]) -> Result<Instruction, String> {
+    let three = |operands: &[Value]| -> Result<(String, String, String), String> {
+        let [a, b, c] = arity(mnemonic, operands)?;
+        Ok((name(a)?, name(b)?, name(c)?))
+    };
+    // Instructions with three name operands
+    let ternary = |make: fn(String, String, String) -> Instruction| {
+        three(operands).map(|(a, b, c)| make(a, b, c))
+    };
+    let instruction = match mnemonic {
+        "quantum_op" => {
+            let [op] = arity(mnemonic, operands)?;
+            Instruction::QuantumOp(operation(op)?)
+        }
+        "quantum_op_if" => {
+            let [register, op] = arity(mnemonic, operands)?;
+            Instruction::QuantumOpIf {
+                register: name(register)?,
+                op: operation(op)?,
+            }
+        }
+        "stabilize" => {
+            let [targets] = arity(mnemonic, operands)?;
+            Instruction::Stabilize {
+                targets: qdus(targets)?,
+            }
+        }
+        "record" => {
+            let [qdu_value, register] = arity(mnemonic, operands)?;
+            Instruction::Record {
+                qdu: qdu(qdu_value)?,
+                register: name(register)?,
+            }
+        }
+        "reset_qdu" => {
+            let [targets] = arity(mnemonic, operands)?;
+            Instruction::ResetQdu {
+                targets: qdus(targets)?,
+            }
+        }
+        "stabilize_into" => {
+            let [targets] = arity(mnemonic, operands)?;
+            Instruction::StabilizeInto {
+                targets: list(targets)?
+                    .iter()
+                    .map(|target| {
+                        let [qdu_value, register] = pair(target)?;
+                        Ok((qdu(qdu_value)?, name(register)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            }
+        }
+        "jump" => {
+            let [label] = arity(mnemonic, operands)?;
+            Instruction::Jump(name(label)?)
+        }
+        "call" => {
+            let [label] = arity(mnemonic, operands)?;
+            Instruction::Call(name(label)?)
+        }
+        "return" => {
+            let [] = arity(mnemonic, operands)?;
+            Instruction::Return
+        }
+        "halt" => {
+            let [] = arity(mnemonic, operands)?;
+            Instruction::Halt
+        }
+        "no_op" => {
+            let [] = arity(mnemonic, operands)?;
+            Instruction::NoOp
+        }
+        "branch_if_zero" | "branch_if_not_zero" | "repeat" => {
+            let [register, label] = arity(mnemonic, operands)?;
+            let (register, label) = (name(register)?, name(label)?);
+            match mnemonic {
+                "branch_if_zero" => Instruction::BranchIfZero { register, label },
+                "branch_if_not_zero" => Instruction::BranchIfNotZero { register, label },
+                _ => Instruction::Repeat { register, label },
+            }
+        }
+        "branch_if_eq" => ternary(|r1, r2, label| Instruction::BranchIfEq { r1, r2, label })?,
+        "branch_if_ne" => ternary(|r1, r2, label| Instruction::BranchIfNe { r1, r2, label })?,
+        "branch_if_lt" => ternary(|r1, r2, label| Instruction::BranchIfLt { r1, r2, label })?,
+        "branch_if_ge" => ternary(|r1, r2, label| Instruction::BranchIfGe { r1, r2, label })?,
+        "load_immediate" => {
+            let [register, value] = arity(mnemonic, operands)?;
+            Instruction::LoadImmediate {
+                register: name(register)?,
+                value: number(value)?,
+            }
+        }
+        "copy" => {
+            let [source_reg, dest_reg] = arity(mnemonic, operands)?;
+            Instruction::Copy {
+                source_reg: name(source_reg)?,
+                dest_reg: name(dest_reg)?,
+            }
+        }
+        "store" => {
+            let (base, index_reg, src) = three(operands)?;
+            Instruction::Store {
+                base,
+                index_reg,
+                src,
+            }
+        }
+        "load" => {
+            let (base, index_reg, dest) = three(operands)?;
+            Instruction::Load {
+                base,
+                index_reg,
+                dest,
+            }
+        }
+        "rand" => {
+            let [register, upper_bound] = arity(mnemonic, operands)?;
+            Instruction::Rand {
+                register: name(register)?,
+                upper_bound: number(upper_bound)?,
+            }
+        }
+        "print" => {
+            let (format, registers) = match operands {
+                [format] => (format, Vec::new()),
+                [format, registers] => (
+                    format,
+                    list(registers)?
+                        .iter()
+                        .map(name)
+                        .collect::<Result<_, _>>()?,
+                ),
+                _ => {
+                    return Err(format!(
+                        "'print' takes 1 or 2 operands, found {}",
+                        operands.len()
+                    ));
+                }
+            };
+            Instruction::Print {
+                format: name(format)?,
+                registers,
+            }
+        }
+        "host_call" => {
+            let [function, args, ret] = arity(mnemonic, operands)?;
+            Instruction::HostCall {
+                name: name(function)?,
+                args: list(args)?.iter().map(name).collect::<Result<_, _>>()?,
+                ret: name(ret)?,
+            }
+        }
+        "addi" => {
+            let [r_dest, r_src, value] = arity(mnemonic, operands)?;
+            Instruction::Addi {
+                r_dest: name(r_dest)?,
+                r_src: name(r_src)?,
+                value: number(value)?,
+            }
+        }
+        "onq_not" | "f_from_bits" => {
+            let [r_dest, r_src] = arity(mnemonic, operands)?;
+            let (r_dest, r_src) = (name(r_dest)?, name(r_src)?);
+            if mnemonic == "onq_not" {
+                Instruction::OnqNot { r_dest, r_src }
+            } else {
+                Instruction::FFromBits { r_dest, r_src }
+            }
+        }
+        "onq_add" => ternary(|r_dest, r_src1, r_src2| Instruction::OnqAdd {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "and" => ternary(|r_dest, r_src1, r_src2| Instruction::And {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "or" => ternary(|r_dest, r_src1, r_src2| Instruction::Or {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "xor" => ternary(|r_dest, r_src1, r_src2| Instruction::Xor {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "sub" => ternary(|r_dest, r_src1, r_src2| Instruction::Sub {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "mul" => ternary(|r_dest, r_src1, r_src2| Instruction::Mul {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "cmp_eq" => ternary(|r_dest, r_src1, r_src2| Instruction::CmpEq {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "cmp_gt" => ternary(|r_dest, r_src1, r_src2| Instruction::CmpGt {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "cmp_lt" => ternary(|r_dest, r_src1, r_src2| Instruction::CmpLt {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "cmp_lt_s" => ternary(|r_dest, r_src1, r_src2| Instruction::CmpLtS {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "sub_s" => ternary(|r_dest, r_src1, r_src2| Instruction::SubS {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "f_add" => ternary(|r_dest, r_src1, r_src2| Instruction::FAdd {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "f_mul" => ternary(|r_dest, r_src1, r_src2| Instruction::FMul {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "f_cmp" => ternary(|r_dest, r_src1, r_src2| Instruction::FCmp {
+            r_dest,
+            r_src1,
+            r_src2,
+        })?,
+        "sign_extend" => {
+            let [r_dest, r_src, bits] = arity(mnemonic, operands)?;
+            Instruction::SignExtend {
+                r_dest: name(r_dest)?,
+                r_src: name(r_src)?,
+                bits: number(bits)?,
+            }
+        }
+        "f_load" => {
+            let [register, value] = arity(mnemonic, operands)?;
+            Instruction::FLoad {
+                register: name(register)?,
+                value: number(value)?,
+            }
+        }
+        "f_phase_shift" => {
+            let [target, theta_reg] = arity(mnemonic, operands)?;
+            Instruction::FPhaseShift {
+                target: qdu(target)?,
+                theta_reg: name(theta_reg)?,
+            }
+        }
+        "quantum_op_dyn" => {
+            let [op_template, angle_register] = arity(mnemonic, operands)?;
+            Instruction::QuantumOpDyn {
+                op_template: operation(op_template)?,
+                angle_register: name(angle_register)?,
+            }
+        }
+        mnemonic => return Err(format!("unknown mnemonic '{}'", mnemonic)),
+    };
+    Ok(instruction)
+}
+
+/// Builds the quantum operation written as `name(operands)`.
+fn operation(value: &Value) -> Result<Operation, String> {
+    let Value::Call(kind, operands) = value else {
+        return Err(format!(
+            "expected a quantum operation but found {}",
+            show(value)
+        ));
+    };
+    let kind = kind.as_str();
+    let op = match kind {
+        "phase_shift" => {
+            let [target, theta] = arity(kind, operands)?;
+            Operation::PhaseShift {
+                target: qdu(target)?,
+                theta: number(theta)?,
+            }
+        }
+        "interaction_pattern" => {
+            let [target, pattern_id] = arity(kind, operands)?;
+            Operation::InteractionPattern {
+                target: qdu(target)?,
+                pattern_id: name(pattern_id)?,
+            }
+        }
+        "broadcast_pattern" => {
+            let [targets, pattern_id] = arity(kind, operands)?;
+            Operation::BroadcastPattern {
+                targets: qdus(targets)?,
+                pattern_id: name(pattern_id)?,
+            }
+        }
+        "broadcast_phase_shift" => {
+            let [targets, theta] = arity(kind, operands)?;
+            Operation::BroadcastPhaseShift {
+                targets: qdus(targets)?,
+                theta: number(theta)?,
+            }
+        }
+        "controlled_interaction" => {
+            let [control, target, pattern_id] = arity(kind, operands)?;
+            Operation::ControlledInteraction {
+                control: qdu(control)?,
+                target: qdu(target)?,
+                pattern_id: name(pattern_id)?,
+            }
+        }
+        "pauli_product" => {
+            let [terms, theta] = arity(kind, operands)?;
+            Operation::PauliProduct {
+                terms: list(terms)?
+                    .iter()
+                    .map(|term| {
+                        let [target, axis] = pair(term)?;
+                        Ok((qdu(target)?, variant(axis, PAULI_AXES)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+                theta: number(theta)?,
+            }
+        }
+        "permute" => {
+            let [mapping] = arity(kind, operands)?;
+            Operation::Permute {
+                mapping: list(mapping)?
+                    .iter()
+                    .map(|entry| {
+                        let [from, to] = pair(entry)?;
+                        Ok((qdu(from)?, qdu(to)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            }
+        }
+        "relational_lock" => {
+            let [qdu1, qdu2, lock_type, establish] = arity(kind, operands)?;
+            Operation::RelationalLock {
+                qdu1: qdu(qdu1)?,
+                qdu2: qdu(qdu2)?,
+                lock_type: variant(lock_type, LOCK_TYPES)?,
+                establish: variant(establish, &[("true", true), ("false", false)])?,
+            }
+        }
+        "matrix_pattern" => {
+            let [target, rows] = arity(kind, operands)?;
+            let rows = matrix(rows)?;
+            let [row0, row1] = rows.as_slice() else {
+                return Err("'matrix_pattern' takes a 2x2 matrix".to_string());
+            };
+            let ([a, b], [c, d]) = (row0.as_slice(), row1.as_slice()) else {
+                return Err("'matrix_pattern' takes a 2x2 matrix".to_string());
+            };
+            Operation::MatrixPattern {
+                target: qdu(target)?,
+                matrix: [[*a, *b], [*c, *d]],
+            }
+        }
+        "qudit_pattern" => {
+            let [target, rows] = arity(kind, operands)?;
+            Operation::QuditPattern {
+                target: qdu(target)?,
+                matrix: matrix(rows)?,
+            }
+        }
+        "project" => {
+            let [target, onto] = arity(kind, operands)?;
+            Operation::Project {
+                target: qdu(target)?,
+                onto: variant(onto, QUALITIES)?,
+            }
+        }
+        "relax" => {
+            let [target, rate] = arity(kind, operands)?;
+            Operation::Relax {
+                target: qdu(target)?,
+                rate: number(rate)?,
+            }
+        }
+        "delay" => {
+            let [targets, ticks] = arity(kind, operands)?;
+            Operation::Delay {
+                targets: qdus(targets)?,
+                ticks: number(ticks)?,
+            }
+        }
+        "snapshot" => {
+            let [label] = arity(kind, operands)?;
+            Operation::Snapshot {
+                label: name(label)?,
+            }
+        }
+        "stabilize" => {
+            let [targets] = arity(kind, operands)?;
+            Operation::Stabilize {
+                targets: qdus(targets)?,
+            }
+        }
+        kind => return Err(format!("unknown quantum operation '{}'", kind)),
+    };
+    Ok(op)
+}
+
+/// The names of the [`LockType`] variants.
+const LOCK_TYPES: &[(&str, LockType)] = &[
+    ("BellPhiPlus", LockType::BellPhiPlus),
+    ("BellPhiMinus", LockType::BellPhiMinus),
+    ("BellPsiPlus", LockType::BellPsiPlus),
+    ("BellPsiMinus", LockType::BellPsiMinus),
+];
+
+/// The names of the [`Quality`] variants.
+const QUALITIES: &[(&str, Quality)] = &[
+    ("Quality0", Quality::Quality0),
+    ("Quality1", Quality::Quality1),
+];
+
+/// The names of the [`PauliAxis`] variants.
+const PAULI_AXES: &[(&str, PauliAxis)] = &[
+    ("X", PauliAxis::X),
+    ("Y", PauliAxis::Y),
+    ("Z", PauliAxis::Z),
+];
+
+/// Checks that `name` got exactly `N` operands.
+fn arity<'a, const N: usize>(name: &str, operands: &'a [Value]) -> Result<&'a [Value; N], String> {
+    operands
+        .try_into()
+        .map_err(|_| format!("'{}' takes {} operands, found {}", name, N, operands.len()))
+}
+
+/// Shows an operand in error messages.
+fn show(value: &Value) -> String {
+    match value {
+        Value::Word(word) => format!("'{}'", word),
+        Value::Str(text) => format!("{:?}", text),
+        Value::List(_) => "a list".to_string(),
+        Value::Tuple(_) => "a pair".to_string(),
+        Value::Call(name, _) => format!("'{}(...)'", name),
+    }
+}
+
+/// Reads a register, label, pattern id or format string.
+fn name(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Word(word) | Value::Str(word) => Ok(word.clone()),
+        value => Err(format!("expected a name but found {}", show(value))),
+    }
+}
+
+/// Reads a QDU written `q<id>`.
+fn qdu(value: &Value) -> Result<QduId, String> {
+    match value {
+        Value::Word(word) => word
+            .strip_prefix('q')
+            .and_then(|id| id.parse().ok())
+            .map(QduId)
+            .ok_or_else(|| format!("expected a QDU 'q<id>' but found '{}'", word)),
+        value => Err(format!("expected a QDU 'q<id>' but found {}", show(value))),
+    }
+}
+
+/// Reads an integer or float.
+fn number<T: FromStr>(value: &Value) -> Result<T, String> {
+    match value {
+        Value::Word(word) => word
+            .parse()
+            .map_err(|_| format!("'{}' is not a valid number here", word)),
+        value => Err(format!("expected a number but found {}", show(value))),
+    }
+}
+
+/// Reads the elements of `[...]`.
+fn list(value: &Value) -> Result<&[Value], String> {
+    match value {
+        Value::List(values) => Ok(values),
+        value => Err(format!("expected a list '[...]' but found {}", show(value))),
+    }
+}
+
+/// Reads a QDU list.
+fn qdus(value: &Value) -> Result<Vec<QduId>, String> {
+    list(value)?.iter().map(qdu).collect()
+}
+
+/// Reads the elements of `(a, b)`.
+fn pair(value: &Value) -> Result<&[Value; 2], String> {
+    match value {
+        Value::Tuple(values) => arity("a pair", values),
+        value => Err(format!(
+            "expected a pair '(a, b)' but found {}",
+            show(value)
+        )),
+    }
+}
+
+/// Reads a matrix of complex numbers written as a list of rows.
+fn matrix(value: &Value) -> Result<Vec<Vec<Complex<f64>>>, String> {
+    list(value)?
+        .iter()
+        .map(|row| {
+            list(row)?
+                .iter()
+                .map(|entry| {
+                    let [re, im] = pair(entry)?;
+                    Ok(Complex::new(number(re)?, number(im)?))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads one of the named `variants`.
+fn variant<T: Clone>(value: &Value, variants: &[(&str, T)]) -> Result<T, String> {
+    let word = match value {
+        Value::Word(word) => word,
+        value => return Err(format!("expected a variant name but found {}", show(value))),
+    };
+    variants
+        .iter()
+        .find(|(name, _)| name == word)
+        .map(|(_, variant)| variant.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = variants.iter().map(|(name, _)| *name).collect();
+            format!("expected one of {} but found '{}'", names.join(", "), word)
+        })
+}
@@ -0,0 +1,267 @@
+// src/vm/checkpoint.rs
+
+//! Saving the complete state of a paused VM run so it can be restored and continued.
+
+use super::result::StabilizationEvent;
+use crate::core::{OnqError, QduId};
+use crate::simulation::Checkpoint;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// First line of every VM checkpoint file, naming the format version.
+const HEADER: &str = "onq-vm-checkpoint 1";
+
+/// Line separating the VM state from the engine [`Checkpoint`] that follows it.
+const ENGINE_MARKER: &str = "engine";
+
+/// The complete state of a paused VM run: the program counter, call stack, every
+/// register and array, the outcomes of the last `Stabilize`, the stabilization log,
+/// the random number generator and the engine state.
+///
+/// Checkpoints are taken with [`OnqVm::checkpoint`](super::OnqVm::checkpoint), written
+/// to disk with [`save`](VmCheckpoint::save) and restored with
+/// [`OnqVm::restore`](super::OnqVm::restore) together with the program they were taken
+/// from, so long programs can survive restarts and a failing run can be reproduced
+/// from the point just before it fails. The file is plain text and stores amplitudes
+/// and floats exactly, so a restored run continues exactly like the original.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::{Instruction, OnqVm, ProgramBuilder, VmCheckpoint};
+/// # use onq::{Operation, QduId};
+/// let program = ProgramBuilder::new()
+///     .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+///         target: QduId(0),
+///         pattern_id: "Superposition".to_string(),
+///     }))
+///     .pb_add(Instruction::Stabilize { targets: vec![QduId(0)] })
+///     .pb_add(Instruction::Record { qdu: QduId(0), register: "m".to_string() })
+///     .build()
+///     .unwrap();
+///
+/// let mut vm = OnqVm::new();
+/// vm.set_breakpoint(1);
+/// vm.run(&program).unwrap();
+/// let text = vm.checkpoint().unwrap().to_string();
+/// let expected = vm.continue_run().unwrap();
+///
+/// let checkpoint: VmCheckpoint = text.parse().unwrap();
+/// let mut restored = OnqVm::new();
+/// restored.restore(&program, &checkpoint).unwrap();
+/// assert_eq!(restored.continue_run().unwrap().register("m"), expected.register("m"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VmCheckpoint {
+    /// Index of the instruction the run executes next.
+    pub(crate) program_counter: usize,
+    /// Return addresses of the active `Call`s, innermost last.
+    pub(crate) call_stack: Vec<usize>,
+    /// Instructions executed before the checkpoint.
+    pub(crate) executed_instructions: u64,
+    /// The classical registers.
+    pub(crate) classical_memory: BTreeMap<String, u64>,
+    /// The float registers.
+    pub(crate) float_memory: BTreeMap<String, f64>,
+    /// The classical arrays, stored sparsely by element index.
+    pub(crate) array_memory: BTreeMap<String, BTreeMap<u64, u64>>,
+    /// The outcomes of the last executed `Stabilize`.
+    pub(crate) last_outcomes: BTreeMap<QduId, u64>,
+    /// Every `Stabilize` executed before the checkpoint.
+    pub(crate) stabilizations: Vec<StabilizationEvent>,
+    /// State words of the random number generator.
+    pub(crate) rng_state: [u64; 4],
+    /// The engine state, if the program holds any QDUs.
+    pub(crate) engine: Option<Checkpoint>,
+}
+
+impl VmCheckpoint {
+    /// Returns the index of the instruction the restored run executes next.
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Returns the number of instructions executed before the checkpoint.
+    pub fn instruction_count(&self) -> u64 {
+        self.executed_instructions
+    }
+
+    /// Returns the classical registers, sorted by name.
+    pub fn classical_memory(&self) -> &BTreeMap<String, u64> {
+        &self.classical_memory
+    }
+
+    /// Returns the engine state, or `None` if the program holds no QDUs.
+    pub fn engine(&self) -> Option<&Checkpoint> {
+        self.engine.as_ref()
+    }
+
+    /// Writes the checkpoint to `path`, replacing any existing file.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), OnqError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()).map_err(|e| OnqError::SimulationError {
+            message: format!("Cannot write VM checkpoint {}: {}", path.display(), e),
+        })
+    }
+
+    /// Reads a checkpoint written by [`save`](VmCheckpoint::save).
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` if the file cannot be read, and the errors of
+    /// parsing ([`str::parse`]) otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, OnqError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| OnqError::SimulationError {
+                message: format!("Cannot read VM checkpoint {}: {}", path.display(), e),
+            })?
+            .parse()
+    }
+}
+
+/// Writes the text format read back by [`str::parse`]: a header line, then one line per
+/// record (`pc <pc>`, `executed <count>`, `rng <state word>...`, `call <return pc>`,
+/// `reg <name> <value>`, `freg <name> <value>`, `array <base> <index> <value>`,
+/// `last <qdu> <quality>` and `stabilized <pc> <qdu> <quality>...`), followed by an
+/// `engine` line and the engine [`Checkpoint`] if the program holds QDUs. Floats are
+/// written in their shortest exact form.
+impl fmt::Display for VmCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "pc {}", self.program_counter)?;
+        writeln!(f, "executed {}", self.executed_instructions)?;
+        let [s0, s1, s2, s3] = self.rng_state;
+        writeln!(f, "rng {} {} {} {}", s0, s1, s2, s3)?;
+        for return_pc in &self.call_stack {
+            writeln!(f, "call {}", return_pc)?;
+        }
+        for (name, value) in &self.classical_memory {
+            writeln!(f, "reg {} {}", name, value)?;
+        }
+        for (name, value) in &self.float_memory {
+            writeln!(f, "freg {} {:?}", name, value)?;
+        }
+        for (base, elements) in &self.array_memory {
+            for (index, value) in elements {
+                writeln!(f, "array {} {} {}", base, index, value)?;
+            }
+        }
+        for (qdu, quality) in &self.last_outcomes {
+            writeln!(f, "last {} {}", qdu.0, quality)?;
+        }
+        for event in &self.stabilizations {
+            write!(f, "stabilized {}", event.pc)?;
+            for (qdu, quality) in &event.outcomes {
+                write!(f, " {} {}", qdu.0, quality)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(engine) = &self.engine {
+            writeln!(f, "{}", ENGINE_MARKER)?;
+            write!(f, "{}", engine)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for VmCheckpoint {
+    type Err = OnqError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (vm_text, engine_text) = match text.split_once(&format!("\n{}\n", ENGINE_MARKER)) {
+            Some((vm_text, engine_text)) => (vm_text, Some(engine_text)),
+            None => (text, None),
+        };
+
+        let mut lines = vm_text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => {}
+            _ => {
+                return Err(OnqError::InvalidOperation {
+                    message: format!("VM checkpoint must start with '{}'", HEADER),
+                });
+            }
+        }
+
+        let mut checkpoint = VmCheckpoint {
+            program_counter: 0,
+            call_stack: Vec::new(),
+            executed_instructions: 0,
+            classical_memory: BTreeMap::new(),
+            float_memory: BTreeMap::new(),
+            array_memory: BTreeMap::new(),
+            last_outcomes: BTreeMap::new(),
+            stabilizations: Vec::new(),
+            rng_state: [0; 4],
+            engine: engine_text.map(str::parse).transpose()?,
+        };
+        for (index, line) in lines {
+            parse_line(line, &mut checkpoint).map_err(|message| OnqError::InvalidOperation {
+                message: format!("VM checkpoint line {}: {}", index + 1, message),
+            })?;
+        }
+        Ok(checkpoint)
+    }
+}
+
+/// Applies one record line of a VM checkpoint.
+fn parse_line(line: &str, checkpoint: &mut VmCheckpoint) -> Result<(), String> {
+    let mut fields = line.split_whitespace();
+    let kind = fields.next().unwrap_or_default();
+    let fields: Vec<&str> = fields.collect();
+    match (kind, fields.as_slice()) {
+        ("pc", [pc]) => checkpoint.program_counter = integer(pc)? as usize,
+        ("executed", [count]) => checkpoint.executed_instructions = integer(count)?,
+        ("rng", [s0, s1, s2, s3]) => {
+            checkpoint.rng_state = [integer(s0)?, integer(s1)?, integer(s2)?, integer(s3)?];
+        }
+        ("call", [return_pc]) => checkpoint.call_stack.push(integer(return_pc)? as usize),
+        ("reg", [name, value]) => {
+            checkpoint
+                .classical_memory
+                .insert(name.to_string(), integer(value)?);
+        }
+        ("freg", [name, value]) => {
+            let value = value
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", value))?;
+            checkpoint.float_memory.insert(name.to_string(), value);
+        }
+        ("array", [base, index, value]) => {
+            checkpoint
+                .array_memory
+                .entry(base.to_string())
+                .or_default()
+                .insert(integer(index)?, integer(value)?);
+        }
+        ("last", [qdu, quality]) => {
+            checkpoint
+                .last_outcomes
+                .insert(QduId(integer(qdu)?), integer(quality)?);
+        }
+        ("stabilized", [pc, outcomes @ ..]) if outcomes.len() % 2 == 0 => {
+            let outcomes = outcomes
+                .chunks(2)
+                .map(|pair| Ok((QduId(integer(pair[0])?), integer(pair[1])?)))
+                .collect::<Result<_, String>>()?;
+            checkpoint.stabilizations.push(StabilizationEvent {
+                pc: integer(pc)? as usize,
+                outcomes,
+            });
+        }
+        _ => return Err(format!("unrecognized record '{}'", line.trim())),
+    }
+    Ok(())
+}
+
+fn integer(field: &str) -> Result<u64, String> {
+    field
+        .parse()
+        .map_err(|_| format!("'{}' is not an unsigned integer", field))
+}
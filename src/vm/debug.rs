@@ -0,0 +1,78 @@
+// src/vm/debug.rs
+
+//! Defines the breakpoints and register watchpoints of an [`OnqVm`](super::OnqVm).
+
+use super::program::Program;
+use std::fmt;
+use std::sync::Arc;
+
+/// A position at which [`OnqVm`](super::OnqVm) pauses before executing the
+/// instruction there, set with [`OnqVm::set_breakpoint`](super::OnqVm::set_breakpoint).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+    /// The instruction at this index of the program.
+    Pc(usize),
+    /// The instruction a label resolves to.
+    Label(String),
+}
+
+impl Breakpoint {
+    /// Returns `true` if this breakpoint is at instruction `pc` of `program`.
+    pub(crate) fn is_at(&self, program: &Program, pc: usize) -> bool {
+        match self {
+            Breakpoint::Pc(at) => *at == pc,
+            Breakpoint::Label(label) => program.get_label_pc(label) == Some(pc),
+        }
+    }
+}
+
+impl From<usize> for Breakpoint {
+    fn from(pc: usize) -> Self {
+        Breakpoint::Pc(pc)
+    }
+}
+
+impl From<&str> for Breakpoint {
+    fn from(label: &str) -> Self {
+        Breakpoint::Label(label.to_string())
+    }
+}
+
+impl From<String> for Breakpoint {
+    fn from(label: String) -> Self {
+        Breakpoint::Label(label)
+    }
+}
+
+/// A change in the value of a watched classical register, set with
+/// [`OnqVm::set_watchpoint`](super::OnqVm::set_watchpoint) or
+/// [`OnqVm::set_watch_callback`](super::OnqVm::set_watch_callback).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegisterChange {
+    /// Index of the instruction that changed the register.
+    pub pc: usize,
+    /// The watched register.
+    pub register: String,
+    /// The value before the instruction, 0 if the register did not exist.
+    pub old: u64,
+    /// The value after the instruction.
+    pub new: u64,
+}
+
+/// What a watchpoint does when its register changes. (Internal visibility)
+#[derive(Clone)]
+pub(crate) enum WatchAction {
+    /// Pauses the run after the changing instruction.
+    Pause,
+    /// Calls the callback and carries on.
+    Callback(Arc<dyn Fn(&RegisterChange) + Send + Sync>),
+}
+
+impl fmt::Debug for WatchAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchAction::Pause => write!(f, "Pause"),
+            WatchAction::Callback(_) => write!(f, "Callback(..)"),
+        }
+    }
+}
@@ -2,11 +2,42 @@
 
 //! Defines the ONQ Virtual Machine (ONQ-VM) interpreter.
 
+use super::checkpoint::VmCheckpoint;
+use super::debug::{Breakpoint, RegisterChange, WatchAction};
 use super::program::{Instruction, Program}; // Use super to access sibling module
+use super::result::{HaltReason, StabilizationEvent, VmRunResult};
+use super::rng::VmRng;
+use super::shots::VmShotResults;
+use super::stats::ExecutionStats;
+use super::trace::{TraceEvent, TraceLevel, Tracer};
 use crate::core::{OnqError, QduId};
-use crate::simulation::SimulationResult; // Needed temporarily for stabilize call
+use crate::operations::Operation;
 use crate::simulation::engine::SimulationEngine; // Use pub(crate) engine
-use std::collections::{HashMap, HashSet};
+use crate::simulation::{Checkpoint, SimulationResult}; // Needed temporarily for stabilize call
+use crate::simulation::{Progress, ProgressHook, SeedMode};
+use num_complex::Complex;
+use rand::{Rng, RngExt, SeedableRng};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The number of instructions a run may execute by default.
+pub const DEFAULT_INSTRUCTION_LIMIT: u64 = 1_000_000;
+
+/// The deepest nesting of `Call`s a program may reach.
+const MAX_CALL_DEPTH: usize = 1024;
+
+/// How far [`OnqVm::resume`] executes a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resume {
+    /// Until a halt or a breakpoint, including one at the first instruction.
+    FromStart,
+    /// Until a halt or a breakpoint after the first instruction.
+    Continue,
+    /// A single instruction.
+    Step,
+}
 
 /// The ONQ Virtual Machine (ONQ-VM).
 ///
@@ -35,8 +66,8 @@ use std::collections::{HashMap, HashSet};
 ///
 /// let mut vm = OnqVm::new();
 /// match vm.run(&program) {
-///     Ok(()) => {
-///         let measurement_result = vm.get_classical_register("m");
+///     Ok(result) => {
+///         let measurement_result = result.register("m");
 ///         println!("Stabilization outcome for QDU(0): {}", measurement_result);
 ///         // Outcome (0 or 1) is deterministic for this VM run.
 ///         assert!(measurement_result == 0 || measurement_result == 1);
@@ -50,13 +81,58 @@ pub struct OnqVm {
     engine: Option<SimulationEngine>,
     /// Named classical registers holding u64 values.
     classical_memory: HashMap<String, u64>,
+    /// Named float registers holding f64 values (a bank separate from `classical_memory`).
+    float_memory: HashMap<String, f64>,
+    /// Named classical arrays of u64 values, stored sparsely by element index.
+    array_memory: HashMap<String, BTreeMap<u64, u64>>,
     /// Stores the outcomes from the most recently executed `Stabilize` instruction.
     /// Keyed by QduId, maps to the resolved StableState value (0 or 1).
     last_stabilization_outcomes: HashMap<QduId, u64>,
     /// Program Counter: index of the next instruction to execute.
     program_counter: usize,
+    /// Return addresses of the active `Call`s, innermost last.
+    call_stack: Vec<usize>,
     /// Flag indicating if the VM has halted.
     is_halted: bool,
+    /// The program of a paused run, kept so that it can be resumed.
+    program: Option<Arc<Program>>,
+    /// Number of QDUs the current run simulates.
+    qdu_count: usize,
+    /// Instructions executed so far in the current run.
+    executed_instructions: u64,
+    /// Every `Stabilize` executed so far in the current run.
+    stabilizations: Vec<StabilizationEvent>,
+    /// Instruction and operation counts of the current run.
+    stats: ExecutionStats,
+    /// Why the current run halted, once it has.
+    halt_reason: HaltReason,
+    /// Time spent executing the current run, excluding pauses.
+    elapsed: Duration,
+    /// Positions at which runs pause.
+    breakpoints: Vec<Breakpoint>,
+    /// Classical registers whose changes pause runs or are reported to a callback.
+    watchpoints: BTreeMap<String, WatchAction>,
+    /// Receives the progress of every run, if set.
+    progress: Option<ProgressHook>,
+    /// Receives the lines written by `Print`; standard output if unset.
+    output: Option<OutputSink>,
+    /// The functions `HostCall` instructions call, by name.
+    host_functions: HashMap<String, HostFunction>,
+    /// Receives the trace events of every run, if tracing is enabled.
+    tracer: Option<Tracer>,
+    /// Instructions a run may execute before it fails, if limited.
+    instruction_limit: Option<u64>,
+    /// Execution time after which a run fails, if limited.
+    timeout: Option<Duration>,
+    /// Seed the random number generator is reset to at the start of every run.
+    rng_seed: u64,
+    /// Source of the values drawn by `Rand`.
+    rng: VmRng,
+    /// How [`OnqVm::run_shots`] varies the seeds from one shot to the next.
+    seed_mode: SeedMode,
+    /// Salt of the current shot, mixed into the stabilization seed and the `Rand` seed;
+    /// 0 outside multi-shot runs.
+    shot_salt: u64,
     // Potential future fields: cycle count, error state details, configuration
 }
 
@@ -66,19 +142,211 @@ impl OnqVm {
         Self {
             engine: None,
             classical_memory: HashMap::new(),
+            float_memory: HashMap::new(),
+            array_memory: HashMap::new(),
             last_stabilization_outcomes: HashMap::new(),
             program_counter: 0,
-            is_halted: false,
+            call_stack: Vec::new(),
+            is_halted: true,
+            program: None,
+            qdu_count: 0,
+            executed_instructions: 0,
+            stabilizations: Vec::new(),
+            stats: ExecutionStats::new(),
+            halt_reason: HaltReason::EndOfProgram,
+            elapsed: Duration::ZERO,
+            breakpoints: Vec::new(),
+            watchpoints: BTreeMap::new(),
+            progress: None,
+            output: None,
+            host_functions: HashMap::new(),
+            tracer: None,
+            instruction_limit: Some(DEFAULT_INSTRUCTION_LIMIT),
+            timeout: None,
+            rng_seed: 0,
+            rng: VmRng::seed_from_u64(0),
+            seed_mode: SeedMode::PerShot,
+            shot_salt: 0,
         }
     }
 
-    /// Resets the VM state (PC, halted flag, memory, engine) for a new run.
+    /// Calls `callback` with the [`Progress`] of every run after each executed
+    /// instruction. The total is unknown up front, as control flow decides how many
+    /// instructions run.
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressHook::new(callback));
+        self
+    }
+
+    /// Sends the lines written by `Print` instructions to `sink` instead of standard
+    /// output.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// # use std::sync::{Arc, Mutex};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::LoadImmediate { register: "i".to_string(), value: 3 })
+    ///     .pb_add(Instruction::Print { format: "i = {}".to_string(), registers: vec!["i".to_string()] })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let lines = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&lines);
+    /// let mut vm = OnqVm::new().with_output(move |line| sink.lock().unwrap().push(line.to_string()));
+    /// vm.run(&program).unwrap();
+    /// assert_eq!(*lines.lock().unwrap(), ["i = 3"]);
+    /// ```
+    pub fn with_output<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.output = Some(OutputSink(Arc::new(sink)));
+        self
+    }
+
+    /// Registers `function` as the host function `HostCall` instructions call by
+    /// `name`, replacing any function registered under that name. It receives the
+    /// values of the call's argument registers, and returns the value stored in its
+    /// return register, or an error message failing the run.
+    ///
+    /// Host functions let programs call out to Rust for table lookups, classical
+    /// optimizers or logging without new instructions.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// let squares = [0, 1, 4, 9, 16];
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::HostCall { name: "square".to_string(), args: vec!["i".to_string()], ret: "s".to_string() })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new().with_host_function("square", move |args| {
+    ///     squares.get(args[0] as usize).copied().ok_or_else(|| format!("no square of {}", args[0]))
+    /// });
+    /// let result = vm.run_with_inputs(&program, &[("i".to_string(), 3)].into()).unwrap();
+    /// assert_eq!(result.register("s"), 9);
+    /// assert!(vm.run_with_inputs(&program, &[("i".to_string(), 7)].into()).is_err());
+    /// ```
+    pub fn with_host_function<F>(mut self, name: &str, function: F) -> Self
+    where
+        F: Fn(&[u64]) -> Result<u64, String> + Send + Sync + 'static,
+    {
+        self.host_functions
+            .insert(name.to_string(), HostFunction(Arc::new(function)));
+        self
+    }
+
+    /// Calls `callback` with every [`TraceEvent`] of every run that `level` reports.
+    /// Tracing is off by default; [`TraceLevel::Off`] turns it off again.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder, TraceEvent, TraceLevel};
+    /// # use std::sync::{Arc, Mutex};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::Label("skip".to_string()))
+    ///     .pb_add(Instruction::BranchIfNotZero { register: "x".to_string(), label: "skip".to_string() })
+    ///     .pb_add(Instruction::Halt)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&events);
+    /// let mut vm = OnqVm::new().with_trace(TraceLevel::Events, move |event| {
+    ///     sink.lock().unwrap().push(event.clone())
+    /// });
+    /// vm.run(&program).unwrap();
+    /// assert!(events.lock().unwrap().contains(&TraceEvent::Branch { pc: 0, target: 1, taken: false }));
+    /// ```
+    pub fn with_trace<F>(mut self, level: TraceLevel, callback: F) -> Self
+    where
+        F: Fn(&TraceEvent) + Send + Sync + 'static,
+    {
+        self.tracer = match level {
+            TraceLevel::Off => None,
+            level => Some(Tracer::new(level, callback)),
+        };
+        self
+    }
+
+    /// Fails every following run with `OnqError::ExecutionLimitExceeded` once it has
+    /// executed `limit` instructions, counting every pass through a loop (default
+    /// [`DEFAULT_INSTRUCTION_LIMIT`]).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// # use onq::OnqError;
+    /// let spin = ProgramBuilder::new()
+    ///     .pb_add(Instruction::Label("spin".to_string()))
+    ///     .pb_add(Instruction::Jump("spin".to_string()))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new().with_instruction_limit(500);
+    /// match vm.run(&spin) {
+    ///     Err(OnqError::ExecutionLimitExceeded { executed, .. }) => assert_eq!(executed, 500),
+    ///     other => panic!("unexpected {:?}", other),
+    /// }
+    /// ```
+    pub fn with_instruction_limit(mut self, limit: u64) -> Self {
+        self.instruction_limit = Some(limit);
+        self
+    }
+
+    /// Lets runs execute any number of instructions; a run that never halts then only
+    /// stops at its timeout, if one is set.
+    pub fn without_instruction_limit(mut self) -> Self {
+        self.instruction_limit = None;
+        self
+    }
+
+    /// Fails every following run with `OnqError::ExecutionLimitExceeded` once it has
+    /// been executing for `timeout`, not counting the time it spends paused. Runs
+    /// have no timeout by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Seeds the random number generator `Rand` draws from. Every run restarts the
+    /// generator from this seed, so a program draws the same values on each run
+    /// (default seed 0).
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = seed;
+        self
+    }
+
+    /// Sets how [`OnqVm::run_shots`] varies the stabilization and `Rand` seeds from one
+    /// shot to the next (default [`SeedMode::PerShot`]).
+    pub fn with_seed_mode(mut self, seed_mode: SeedMode) -> Self {
+        self.seed_mode = seed_mode;
+        self
+    }
+
+    /// Resets the VM state (PC, halted flag, memory, engine, RNG) for a new run.
     fn reset(&mut self) {
         self.engine = None; // Engine needs re-initialization based on program QDUs
         self.classical_memory.clear();
+        self.float_memory.clear();
+        self.array_memory.clear();
         self.last_stabilization_outcomes.clear();
         self.program_counter = 0;
+        self.call_stack.clear();
         self.is_halted = false;
+        self.program = None;
+        self.qdu_count = 0;
+        self.executed_instructions = 0;
+        self.stabilizations.clear();
+        self.stats = ExecutionStats::new();
+        self.halt_reason = HaltReason::EndOfProgram;
+        self.elapsed = Duration::ZERO;
+        self.rng = VmRng::seed_from_u64(self.rng_seed ^ self.shot_salt);
     }
 
     /// Runs a given `Program` until it halts or encounters an error.
@@ -87,320 +355,1091 @@ impl OnqVm {
     /// * `program` - The `Program` to execute.
     ///
     /// # Returns
-    /// * `Ok(())` if the program halts successfully.
+    /// * `Ok(VmRunResult)` summarizing the run if the program halts successfully.
     /// * `Err(OnqError)` if a simulation error or runtime error occurs (e.g., label not found, invalid op).
-    pub fn run(&mut self, program: &Program) -> Result<(), OnqError> {
+    pub fn run(&mut self, program: &Program) -> Result<VmRunResult, OnqError> {
+        self.run_with_inputs(program, &HashMap::new())
+    }
+
+    /// Runs `program` as [`OnqVm::run`] does, with the classical registers named in
+    /// `inputs` holding the given values when execution starts, so one program can be
+    /// run across many input values.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// # use std::collections::HashMap;
+    /// let double = ProgramBuilder::new()
+    ///     .pb_add(Instruction::OnqAdd { r_dest: "y".to_string(), r_src1: "x".to_string(), r_src2: "x".to_string() })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new();
+    /// for x in 0..4 {
+    ///     let result = vm.run_with_inputs(&double, &HashMap::from([("x".to_string(), x)])).unwrap();
+    ///     assert_eq!(result.register("y"), 2 * x);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// As [`OnqVm::run`].
+    pub fn run_with_inputs(
+        &mut self,
+        program: &Program,
+        inputs: &HashMap<String, u64>,
+    ) -> Result<VmRunResult, OnqError> {
         self.reset();
-        println!("[VM RUN START]"); // DEBUG
+        self.classical_memory.extend(
+            inputs
+                .iter()
+                .map(|(register, value)| (register.clone(), *value)),
+        );
 
         // 1. Determine all QDUs involved...
         let all_qdus = Self::collect_qdus(program)?;
         if !all_qdus.is_empty() {
-            self.engine = Some(SimulationEngine::init(&all_qdus)?);
-            println!("[VM Engine Initialized for {:?}]", all_qdus); // DEBUG
+            let mut engine = SimulationEngine::init(&all_qdus)?;
+            engine.set_stabilization_salt(self.shot_salt);
+            self.engine = Some(engine);
         } else {
             self.engine = None;
-            println!("[VM Engine Not Needed (No QDUs)]"); // DEBUG
         }
+        self.qdu_count = all_qdus.len();
+        self.trace(TraceLevel::Events, || TraceEvent::RunStarted {
+            qdus: all_qdus.len(),
+        });
 
         // 2. Execution Loop
-        let mut executed_instruction_count = 0; // DEBUG loop counter
-        const MAX_INSTRUCTIONS: u64 = 1000; // DEBUG limit
+        self.execute(program, Resume::FromStart)
+    }
 
-        while !self.is_halted {
-            // --- DEBUG: Safety break for infinite loops ---
-            if executed_instruction_count > MAX_INSTRUCTIONS {
-                return Err(OnqError::SimulationError {
+    /// Runs `program` `shots` times and aggregates the final classical registers and
+    /// stabilization outcomes of all shots into distributions.
+    ///
+    /// Stabilization is deterministic for a given state, so every shot mixes a salt
+    /// derived from its index and the VM's [`SeedMode`] (see [`OnqVm::with_seed_mode`])
+    /// into both the stabilization seed and the `Rand` seed. With the default
+    /// [`SeedMode::PerShot`], shot 0 resolves exactly like [`OnqVm::run`].
+    ///
+    /// # Errors
+    /// Returns the first `OnqError` raised by any shot, or
+    /// `OnqError::InvalidOperation` if a shot pauses at a breakpoint or watchpoint.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// # use onq::simulation::SeedMode;
+    /// # use onq::{Operation, QduId};
+    /// let coin = ProgramBuilder::new()
+    ///     .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+    ///         target: QduId(0),
+    ///         pattern_id: "Superposition".to_string(),
+    ///     }))
+    ///     .pb_add(Instruction::Stabilize { targets: vec![QduId(0)] })
+    ///     .pb_add(Instruction::Record { qdu: QduId(0), register: "m".to_string() })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let results = OnqVm::new().run_shots(&coin, 64).unwrap();
+    /// assert_eq!(results.register_counts("m").values().sum::<usize>(), 64);
+    /// assert!(results.register_count("m", 0) > 0 && results.register_count("m", 1) > 0);
+    ///
+    /// let fixed = OnqVm::new().with_seed_mode(SeedMode::Fixed).run_shots(&coin, 64).unwrap();
+    /// assert_eq!(fixed.register_counts("m").len(), 1);
+    /// ```
+    pub fn run_shots(
+        &mut self,
+        program: &Program,
+        shots: usize,
+    ) -> Result<VmShotResults, OnqError> {
+        let mut results = VmShotResults::new(shots, self.seed_mode);
+        for shot in 0..shots {
+            self.shot_salt = self.seed_mode.salt(shot as u64);
+            let result = self.run(program);
+            self.shot_salt = 0;
+            let result = result?;
+            if result.halt_reason().is_paused() {
+                return Err(OnqError::InvalidOperation {
                     message: format!(
-                        "Execution exceeded maximum instruction limit ({}) - potential infinite loop?",
-                        MAX_INSTRUCTIONS
+                        "Shot {} paused ({:?}); clear breakpoints and pausing watchpoints \
+                         before running shots",
+                        shot,
+                        result.halt_reason()
                     ),
                 });
             }
-            executed_instruction_count += 1;
-            // --- End DEBUG ---
+            results.record_shot(&result);
+        }
+        Ok(results)
+    }
+
+    /// Executes the next instruction of a paused run and pauses again, unless the
+    /// program halts.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if no run is paused, or the error the
+    /// instruction raises.
+    pub fn step(&mut self) -> Result<VmRunResult, OnqError> {
+        self.resume(Resume::Step)
+    }
+
+    /// Resumes a paused run until it halts or reaches the next breakpoint. The
+    /// breakpoint the run paused at, if any, does not pause it again.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if no run is paused, or the error an
+    /// instruction raises.
+    pub fn continue_run(&mut self) -> Result<VmRunResult, OnqError> {
+        self.resume(Resume::Continue)
+    }
+
+    /// Pauses every following run before it executes the instruction at `at`: an
+    /// instruction index or a label, e.g. `vm.set_breakpoint(3)` or
+    /// `vm.set_breakpoint("loop")`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{HaltReason, Instruction, OnqVm, ProgramBuilder};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::LoadImmediate { register: "i".to_string(), value: 0 })
+    ///     .pb_add(Instruction::Label("loop".to_string()))
+    ///     .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+    ///     .pb_add(Instruction::CmpLt { r_dest: "more".to_string(), r_src1: "i".to_string(), r_src2: "n".to_string() })
+    ///     .pb_add(Instruction::BranchIfNotZero { register: "more".to_string(), label: "loop".to_string() })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new();
+    /// vm.set_breakpoint("loop");
+    /// let paused = vm.run_with_inputs(&program, &[("n".to_string(), 3)].into()).unwrap();
+    /// assert_eq!(paused.halt_reason(), HaltReason::Breakpoint { pc: 1 });
+    /// assert_eq!(vm.get_classical_register("i"), 0);
+    ///
+    /// vm.step().unwrap();
+    /// assert_eq!(vm.get_classical_register("i"), 1);
+    ///
+    /// // Each pass around the loop pauses at the breakpoint again
+    /// let paused = vm.continue_run().unwrap();
+    /// assert_eq!(paused.halt_reason(), HaltReason::Breakpoint { pc: 1 });
+    /// vm.step().unwrap();
+    /// assert_eq!(vm.get_classical_register("i"), 2);
+    ///
+    /// vm.clear_breakpoints();
+    /// assert_eq!(vm.continue_run().unwrap().halt_reason(), HaltReason::EndOfProgram);
+    /// assert_eq!(vm.get_classical_register("i"), 3);
+    /// ```
+    pub fn set_breakpoint(&mut self, at: impl Into<Breakpoint>) {
+        let breakpoint = at.into();
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes the breakpoint at `at`, if set.
+    pub fn remove_breakpoint(&mut self, at: impl Into<Breakpoint>) {
+        let breakpoint = at.into();
+        self.breakpoints.retain(|set| *set != breakpoint);
+    }
+
+    /// Removes every breakpoint.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Returns the breakpoints set, in the order they were set.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Pauses every following run after any instruction that changes the value of
+    /// classical register `register`, reporting the change as
+    /// [`HaltReason::Watchpoint`]; a change by the instruction that ends the run does
+    /// not pause it. Replaces a callback set for the register.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{HaltReason, Instruction, OnqVm, ProgramBuilder, RegisterChange};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::LoadImmediate { register: "x".to_string(), value: 5 })
+    ///     .pb_add(Instruction::LoadImmediate { register: "x".to_string(), value: 5 })
+    ///     .pb_add(Instruction::Addi { r_dest: "x".to_string(), r_src: "x".to_string(), value: 1 })
+    ///     .pb_add(Instruction::Halt)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new();
+    /// vm.set_watchpoint("x");
+    /// let paused = vm.run(&program).unwrap();
+    /// let change = RegisterChange { pc: 0, register: "x".to_string(), old: 0, new: 5 };
+    /// assert_eq!(paused.halt_reason(), HaltReason::Watchpoint(change));
+    ///
+    /// // Rewriting the same value is not a change
+    /// let paused = vm.continue_run().unwrap();
+    /// let change = RegisterChange { pc: 2, register: "x".to_string(), old: 5, new: 6 };
+    /// assert_eq!(paused.halt_reason(), HaltReason::Watchpoint(change));
+    /// ```
+    pub fn set_watchpoint(&mut self, register: &str) {
+        self.watchpoints
+            .insert(register.to_string(), WatchAction::Pause);
+    }
+
+    /// Calls `callback` whenever an instruction of a following run changes the value
+    /// of classical register `register`, without pausing. Replaces a watchpoint set
+    /// for the register.
+    pub fn set_watch_callback<F>(&mut self, register: &str, callback: F)
+    where
+        F: Fn(&RegisterChange) + Send + Sync + 'static,
+    {
+        self.watchpoints.insert(
+            register.to_string(),
+            WatchAction::Callback(Arc::new(callback)),
+        );
+    }
+
+    /// Stops watching classical register `register`.
+    pub fn remove_watchpoint(&mut self, register: &str) {
+        self.watchpoints.remove(register);
+    }
+
+    /// Stops watching every register.
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Captures the complete state of the paused run, to be written to disk or
+    /// restored into a VM with [`OnqVm::restore`]. The random number generator state
+    /// is copied as it stands, so this run and every restored copy draw the same
+    /// values from here on, and the same ones as a run never checkpointed.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if no run is paused, or if a register or
+    /// array name is empty or contains whitespace, which the text format cannot hold.
+    pub fn checkpoint(&self) -> Result<VmCheckpoint, OnqError> {
+        if self.program.is_none() || self.is_halted {
+            return Err(OnqError::InvalidOperation {
+                message: "No paused run to checkpoint: the VM has not run or has halted."
+                    .to_string(),
+            });
+        }
+        if let Some(name) = self
+            .classical_memory
+            .keys()
+            .chain(self.float_memory.keys())
+            .chain(self.array_memory.keys())
+            .find(|name| name.is_empty() || name.contains(char::is_whitespace))
+        {
+            return Err(OnqError::InvalidOperation {
+                message: format!(
+                    "Cannot checkpoint register '{}': names must be non-empty and free of whitespace",
+                    name
+                ),
+            });
+        }
+        let engine = self
+            .engine
+            .as_ref()
+            .map(|engine| {
+                Ok::<_, OnqError>(
+                    Checkpoint::new(engine.qdu_nodes(), engine.get_state().clone())?
+                        .with_global_phase(engine.global_phase()),
+                )
+            })
+            .transpose()?;
+        Ok(VmCheckpoint {
+            program_counter: self.program_counter,
+            call_stack: self.call_stack.clone(),
+            executed_instructions: self.executed_instructions,
+            classical_memory: self.classical_memory.clone().into_iter().collect(),
+            float_memory: self.float_memory.clone().into_iter().collect(),
+            array_memory: self.array_memory.clone().into_iter().collect(),
+            last_outcomes: self
+                .last_stabilization_outcomes
+                .clone()
+                .into_iter()
+                .collect(),
+            stabilizations: self.stabilizations.clone(),
+            rng_state: self.rng.state(),
+            engine,
+        })
+    }
+
+    /// Replaces the VM state with `checkpoint`, taken from a run of `program`, leaving
+    /// the run paused so it continues with [`OnqVm::step`] or [`OnqVm::continue_run`].
+    /// Execution statistics restart from zero.
+    ///
+    /// # Errors
+    /// Returns `OnqError::ReferenceViolation` if the engine state does not hold exactly
+    /// the QDUs of `program`, and `OnqError::InvalidOperation` if the program counter
+    /// or a return address lies outside `program`.
+    pub fn restore(
+        &mut self,
+        program: &Program,
+        checkpoint: &VmCheckpoint,
+    ) -> Result<(), OnqError> {
+        self.reset();
+        let all_qdus = Self::collect_qdus(program)?;
+        let held: HashSet<QduId> = checkpoint
+            .engine()
+            .map(|engine| engine.qdu_nodes().keys().copied().collect())
+            .unwrap_or_default();
+        if held != all_qdus {
+            return Err(OnqError::ReferenceViolation {
+                message: format!(
+                    "VM checkpoint holds the QDUs {:?}, but the program uses {:?}",
+                    held, all_qdus
+                ),
+            });
+        }
+        if let Some(pc) = std::iter::once(&checkpoint.program_counter)
+            .chain(&checkpoint.call_stack)
+            .find(|&&pc| pc >= program.instruction_count())
+        {
+            return Err(OnqError::InvalidOperation {
+                message: format!(
+                    "VM checkpoint position {} lies outside the program (0..{})",
+                    pc,
+                    program.instruction_count()
+                ),
+            });
+        }
+
+        self.engine = match checkpoint.engine() {
+            Some(saved) => {
+                let mut engine = SimulationEngine::init_with_order(&saved.qdu_order())?;
+                engine.set_state(saved.state().clone())?;
+                engine.set_global_phase(saved.global_phase());
+                Some(engine)
+            }
+            None => None,
+        };
+        self.program_counter = checkpoint.program_counter;
+        self.call_stack = checkpoint.call_stack.clone();
+        self.executed_instructions = checkpoint.executed_instructions;
+        self.classical_memory = checkpoint.classical_memory.clone().into_iter().collect();
+        self.float_memory = checkpoint.float_memory.clone().into_iter().collect();
+        self.array_memory = checkpoint.array_memory.clone().into_iter().collect();
+        self.last_stabilization_outcomes = checkpoint.last_outcomes.clone().into_iter().collect();
+        self.stabilizations = checkpoint.stabilizations.clone();
+        self.rng = VmRng::from_state(checkpoint.rng_state);
+        self.qdu_count = all_qdus.len();
+        self.program = Some(Arc::new(program.clone()));
+        Ok(())
+    }
+
+    /// Returns the instruction and quantum operation counts of the last run, or of the
+    /// current run so far if it is paused.
+    pub fn stats(&self) -> &ExecutionStats {
+        &self.stats
+    }
+
+    /// Returns the index of the instruction a paused run executes next.
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Returns `true` once the current run has halted, or if nothing has run yet.
+    pub fn is_halted(&self) -> bool {
+        self.is_halted
+    }
+
+    /// Executes the paused program from the program counter as far as `mode` allows,
+    /// returning the run so far.
+    fn resume(&mut self, mode: Resume) -> Result<VmRunResult, OnqError> {
+        let program = match &self.program {
+            Some(program) if !self.is_halted => Arc::clone(program),
+            _ => {
+                return Err(OnqError::InvalidOperation {
+                    message: "No paused run to resume: the VM has not run or has halted."
+                        .to_string(),
+                });
+            }
+        };
+        self.execute(&program, mode)
+    }
 
+    /// Executes `program` from the program counter as far as `mode` allows, returning
+    /// the run so far. The program is copied into the VM only if the run pauses, so
+    /// runs that halt never clone it.
+    fn execute(&mut self, program: &Program, mode: Resume) -> Result<VmRunResult, OnqError> {
+        let start = Instant::now();
+        let mut outcome = Ok(None);
+        for executed in 0.. {
+            if self.is_halted {
+                break;
+            }
             let pc = self.program_counter;
+            if executed > 0 && mode == Resume::Step {
+                outcome = Ok(Some(HaltReason::Step));
+                break;
+            }
+            if (executed > 0 || mode == Resume::FromStart)
+                && mode != Resume::Step
+                && self
+                    .breakpoints
+                    .iter()
+                    .any(|breakpoint| breakpoint.is_at(program, pc))
+            {
+                outcome = Ok(Some(HaltReason::Breakpoint { pc }));
+                break;
+            }
+            if let Err(e) = self.check_limits(self.elapsed + start.elapsed()) {
+                outcome = Err(e);
+                break;
+            }
+            let watched = self.watched_values();
+            if let Err(e) = self.execute_next(program) {
+                outcome = Err(e);
+                break;
+            }
+            if let Some(change) = self.report_changes(pc, watched) {
+                outcome = Ok(Some(HaltReason::Watchpoint(change)));
+                break;
+            }
+        }
+        self.elapsed += start.elapsed();
 
-            // Fetch instruction
-            println!("[VM] PC={:04} Fetching...", pc); // DEBUG
-            let instruction =
-                program
-                    .get_instruction(pc)
-                    .ok_or_else(|| OnqError::SimulationError {
-                        message: format!(
-                            "Program Counter ({}) out of bounds (0..{}).",
-                            pc,
-                            program.instruction_count()
-                        ),
-                    })?;
-            println!("[VM] PC={:04} Executing: {:?}", pc, instruction); // DEBUG
+        let reason = match outcome {
+            Ok(Some(pause)) => {
+                if self.program.is_none() {
+                    self.program = Some(Arc::new(program.clone()));
+                }
+                pause
+            }
+            Ok(None) => {
+                let reason = self.halt_reason.clone();
+                self.trace(TraceLevel::Events, || TraceEvent::RunFinished {
+                    reason: reason.clone(),
+                    instructions: self.executed_instructions,
+                });
+                reason
+            }
+            Err(e) => {
+                // A failed run cannot be resumed
+                self.is_halted = true;
+                return Err(e);
+            }
+        };
+        Ok(VmRunResult::new(
+            self.classical_memory.clone(),
+            self.stabilizations.clone(),
+            reason,
+            self.executed_instructions,
+            self.elapsed,
+        ))
+    }
 
-            // Advance PC before execution (simplifies branching)
-            self.program_counter += 1;
+    /// Fails if executing another instruction would exceed the instruction limit, or
+    /// the run has been executing for `elapsed`, beyond its timeout.
+    fn check_limits(&self, elapsed: Duration) -> Result<(), OnqError> {
+        let exceeded = |message: String| OnqError::ExecutionLimitExceeded {
+            executed: self.executed_instructions,
+            elapsed,
+            message,
+        };
+        if let Some(limit) = self.instruction_limit
+            && self.executed_instructions >= limit
+        {
+            return Err(exceeded(format!(
+                "instruction limit of {} reached - potential infinite loop?",
+                limit
+            )));
+        }
+        if let Some(timeout) = self.timeout
+            && elapsed >= timeout
+        {
+            return Err(exceeded(format!("timeout of {:?} reached", timeout)));
+        }
+        Ok(())
+    }
 
-            // Execute instruction
-            match instruction {
-                Instruction::QuantumOp(op) => {
-                    if let Some(engine) = self.engine.as_mut() {
-                        engine.apply_operation(op)?;
-                    } else {
-                        return Err(OnqError::InvalidOperation { message: "Cannot execute QuantumOp: SimulationEngine not initialized (no QDUs defined in program?).".to_string() });
-                    }
+    /// Returns the current value of every watched register.
+    fn watched_values(&self) -> Vec<u64> {
+        self.watchpoints
+            .keys()
+            .map(|register| self.register(register))
+            .collect()
+    }
+
+    /// Compares the watched registers with their values `before` the instruction at
+    /// `pc`, calling the callbacks of changed ones. Returns the change to pause at, if
+    /// a paused register changed and the run has not halted.
+    fn report_changes(&self, pc: usize, before: Vec<u64>) -> Option<RegisterChange> {
+        let mut pause = None;
+        for ((register, action), old) in self.watchpoints.iter().zip(before) {
+            let new = self.register(register);
+            if new == old {
+                continue;
+            }
+            let change = RegisterChange {
+                pc,
+                register: register.clone(),
+                old,
+                new,
+            };
+            match action {
+                WatchAction::Callback(callback) => callback(&change),
+                WatchAction::Pause if !self.is_halted => {
+                    pause.get_or_insert(change);
                 }
-                Instruction::Stabilize { targets } => {
-                    if targets.is_empty() {
-                        println!("[VM] PC={:04} Stabilize: No targets.", pc); // DEBUG
-                        continue;
-                    }
+                WatchAction::Pause => {}
+            }
+        }
+        pause
+    }
+
+    /// Fetches and executes the instruction at the program counter.
+    fn execute_next(&mut self, program: &Program) -> Result<(), OnqError> {
+        self.executed_instructions += 1;
+        let pc = self.program_counter;
+
+        // Fetch instruction
+        let instruction = program
+            .get_instruction(pc)
+            .ok_or_else(|| OnqError::SimulationError {
+                message: format!(
+                    "Program Counter ({}) out of bounds (0..{}).",
+                    pc,
+                    program.instruction_count()
+                ),
+            })?;
+        self.trace(TraceLevel::Instructions, || TraceEvent::Instruction {
+            pc,
+            instruction: instruction.clone(),
+        });
+        self.stats.record(instruction);
+
+        // Advance PC before execution (simplifies branching)
+        self.program_counter += 1;
+
+        // Execute instruction
+        match instruction {
+            Instruction::QuantumOp(op) => {
+                if let Some(engine) = self.engine.as_mut() {
+                    engine.apply_operation(op)?;
+                } else {
+                    return Err(OnqError::InvalidOperation { message: "Cannot execute QuantumOp: SimulationEngine not initialized (no QDUs defined in program?).".to_string() });
+                }
+            }
+            Instruction::QuantumOpIf { register, op } => {
+                if self.register(register) != 0 {
                     if let Some(engine) = self.engine.as_mut() {
-                        let mut temp_result = SimulationResult::new();
-                        println!(
-                            "[VM] PC={:04} Calling engine.stabilize for {:?}",
-                            pc, targets
-                        ); // DEBUG
-                        engine.stabilize(targets, &mut temp_result)?; // This might return Err
-                        println!(
-                            "[VM] PC={:04} engine.stabilize finished. Temp result: {:?}",
-                            pc, temp_result
-                        ); // DEBUG
-
-                        // Store the u64 outcomes for Record instruction
-                        self.last_stabilization_outcomes = temp_result.all_stable_outcomes().iter()
-                             .filter_map(|(qid, state)| {
-                                 // DEBUG: See what get_resolved_value returns
-                                 let resolved = state.get_resolved_value();
-                                 println!("[VM] PC={:04} Stabilize: QDU {}, State {:?}, Resolved Value: {:?}", pc, qid, state, resolved); // DEBUG
-                                 resolved.map(|val| (*qid, val))
-                             })
-                             .collect();
-                        println!(
-                            "[VM] PC={:04} Stored last_stabilization_outcomes: {:?}",
-                            pc, self.last_stabilization_outcomes
-                        ); // DEBUG
+                        engine.apply_operation(op)?;
+                        self.stats.record_operation(op);
                     } else {
                         return Err(OnqError::InvalidOperation {
-                            message: "Cannot execute Stabilize: SimulationEngine not initialized."
-                                .to_string(),
+                            message:
+                                "Cannot execute QuantumOpIf: SimulationEngine not initialized."
+                                    .to_string(),
                         });
                     }
                 }
-                Instruction::Record { qdu, register } => {
-                    println!("[VM] PC={:04} Attempting to record for QDU {}", pc, qdu); // DEBUG
-                    println!(
-                        "[VM] PC={:04} Current last_stabilization_outcomes: {:?}",
-                        pc, self.last_stabilization_outcomes
-                    ); // DEBUG
-                    // Attempt to get the value
-                    let value_option = self.last_stabilization_outcomes.get(qdu);
-                    println!(
-                        "[VM] PC={:04} Value Option for QDU {}: {:?}",
-                        pc, qdu, value_option
-                    ); // DEBUG
-
-                    let value = value_option.ok_or_else(|| {
-                        OnqError::InvalidOperation { message: format!("Cannot Record: QDU {} was not found in the last stabilization results ({:?}). Was Stabilize called immediately prior with this QDU?", qdu, self.last_stabilization_outcomes) }
-                    })?;
-                    println!(
-                        "[VM] PC={:04} Recording value {} to register '{}'",
-                        pc, value, register
-                    ); // DEBUG
-                    self.classical_memory.insert(register.clone(), *value);
-                    println!(
-                        "[VM] PC={:04} Classical memory now: {:?}",
-                        pc, self.classical_memory
-                    ); // DEBUG
+            }
+            Instruction::Stabilize { targets } => {
+                self.stabilize(pc, targets, "Stabilize")?;
+            }
+            Instruction::StabilizeInto { targets } => {
+                let qdus: Vec<QduId> = targets.iter().map(|(qdu, _)| *qdu).collect();
+                self.stabilize(pc, &qdus, "StabilizeInto")?;
+                for (qdu, register) in targets {
+                    let value = self
+                        .last_stabilization_outcomes
+                        .get(qdu)
+                        .copied()
+                        .unwrap_or(0);
+                    self.classical_memory.insert(register.clone(), value);
                 }
-                Instruction::Label(_) => {
-                    println!("[VM] PC={:04} Encountered Label (No-Op)", pc); // DEBUG
-                    // No operation, labels handled during build/jump resolution
+            }
+            Instruction::ResetQdu { targets } => {
+                if targets.is_empty() {
+                    // Nothing to reset
+                } else if let Some(engine) = self.engine.as_mut() {
+                    engine.reset(targets)?;
+                    // The reset QDUs no longer hold their last outcomes
+                    for qdu in targets {
+                        self.last_stabilization_outcomes.remove(qdu);
+                    }
+                } else {
+                    return Err(OnqError::InvalidOperation {
+                        message: "Cannot execute ResetQdu: SimulationEngine not initialized."
+                            .to_string(),
+                    });
                 }
-                Instruction::Jump(label) => {
+            }
+            Instruction::Record { qdu, register } => {
+                let value = self.last_stabilization_outcomes.get(qdu).ok_or_else(|| {
+                    OnqError::InvalidOperation { message: format!("Cannot Record: QDU {} was not found in the last stabilization results ({:?}). Was Stabilize called immediately prior with this QDU?", qdu, self.last_stabilization_outcomes) }
+                })?;
+                self.classical_memory.insert(register.clone(), *value);
+            }
+            Instruction::Label(_) => {
+                // No operation, labels handled during build/jump resolution
+            }
+            Instruction::Jump(label) => {
+                let target_pc =
+                    program
+                        .get_label_pc(label)
+                        .ok_or_else(|| OnqError::SimulationError {
+                            message: format!(
+                                "Runtime Error: Jump target label '{}' not found.",
+                                label
+                            ),
+                        })?;
+                self.program_counter = target_pc; // Set PC to target instruction index
+            }
+            Instruction::BranchIfZero { register, label } => {
+                let reg_value = self.classical_memory.get(register).copied().unwrap_or(0); // Default to 0
+                if reg_value == 0 {
                     let target_pc =
                         program
                             .get_label_pc(label)
                             .ok_or_else(|| OnqError::SimulationError {
-                                message: format!(
-                                    "Runtime Error: Jump target label '{}' not found.",
-                                    label
-                                ),
-                            })?;
-                    println!(
-                        "[VM] PC={:04} Jumping to label '{}' (PC={})",
-                        pc, label, target_pc
-                    ); // DEBUG
-                    self.program_counter = target_pc; // Set PC to target instruction index
-                }
-                Instruction::BranchIfZero { register, label } => {
-                    let reg_value = self.classical_memory.get(register).copied().unwrap_or(0); // Default to 0
-                    println!(
-                        "[VM] PC={:04} BranchIfZero: Reg '{}' = {}",
-                        pc, register, reg_value
-                    ); // DEBUG
-                    if reg_value == 0 {
-                        let target_pc = program.get_label_pc(label).ok_or_else(|| {
-                            OnqError::SimulationError {
                                 message: format!(
                                     "Runtime Error: Branch target label '{}' not found.",
                                     label
                                 ),
-                            }
-                        })?;
-                        println!(
-                            "[VM] PC={:04} Branch taken to label '{}' (PC={})",
-                            pc, label, target_pc
-                        ); // DEBUG
-                        self.program_counter = target_pc;
-                    } else {
-                        println!("[VM] PC={:04} Branch not taken.", pc); // DEBUG
-                    }
-                    // If branch not taken, PC remains incremented from before match
-                }
-                Instruction::LoadImmediate { register, value } => {
-                    println!("[VM] PC={:04} LoadImm: Reg '{}' = {}", pc, register, value); // DEBUG
-                    self.classical_memory.insert(register.clone(), *value);
-                }
-                Instruction::Copy {
-                    source_reg,
-                    dest_reg,
-                } => {
-                    let value = self.classical_memory.get(source_reg).copied().unwrap_or(0);
-                    println!(
-                        "[VM] PC={:04} Copy: Reg '{}' = {} from Reg '{}'",
-                        pc, dest_reg, value, source_reg
-                    ); // DEBUG
-                    self.classical_memory.insert(dest_reg.clone(), value);
-                }
-                Instruction::OnqAdd {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory
-                        .insert(r_dest.clone(), val1.wrapping_add(val2));
-                }
-                Instruction::Addi {
-                    r_dest,
-                    r_src,
-                    value,
-                } => {
-                    let val_src = self.classical_memory.get(r_src).copied().unwrap_or(0);
-                    let result = val_src.wrapping_add(*value);
-                    println!(
-                        "[VM] PC={:04} Addi: Reg '{}' = {} + {} = {}",
-                        pc, r_dest, val_src, value, result
-                    ); // DEBUG
-                    self.classical_memory.insert(r_dest.clone(), result);
+                            })?;
+                    self.program_counter = target_pc;
                 }
-                Instruction::Sub {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory
-                        .insert(r_dest.clone(), val1.wrapping_sub(val2));
+                // If branch not taken, PC remains incremented from before match
+            }
+            Instruction::Call(label) => {
+                if self.call_stack.len() >= MAX_CALL_DEPTH {
+                    return Err(OnqError::SimulationError {
+                        message: format!(
+                            "Runtime Error: Call to '{}' exceeds the maximum call depth ({}).",
+                            label, MAX_CALL_DEPTH
+                        ),
+                    });
                 }
-                Instruction::Mul {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory
-                        .insert(r_dest.clone(), val1.wrapping_mul(val2));
+                // The PC already points past the Call
+                self.call_stack.push(self.program_counter);
+                self.jump_to(program, label)?;
+            }
+            Instruction::Return => {
+                self.program_counter =
+                    self.call_stack
+                        .pop()
+                        .ok_or_else(|| OnqError::SimulationError {
+                            message: "Runtime Error: Return with an empty call stack.".to_string(),
+                        })?;
+            }
+            Instruction::BranchIfNotZero { register, label } => {
+                let reg_value = self.register(register);
+                if reg_value != 0 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::OnqNot { r_dest, r_src } => {
-                    let val_src = self.classical_memory.get(r_src).copied().unwrap_or(0);
-                    self.classical_memory.insert(r_dest.clone(), !val_src); // Bitwise NOT
+            }
+            Instruction::Repeat { register, label } => {
+                let remaining = self.register(register).saturating_sub(1);
+                self.classical_memory.insert(register.clone(), remaining);
+                if remaining != 0 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::And {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory.insert(r_dest.clone(), val1 & val2); // Bitwise AND
+            }
+            Instruction::BranchIfEq { r1, r2, label } => {
+                let (val1, val2) = (self.register(r1), self.register(r2));
+                if val1 == val2 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::Or {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory.insert(r_dest.clone(), val1 | val2); // Bitwise OR
+            }
+            Instruction::BranchIfNe { r1, r2, label } => {
+                let (val1, val2) = (self.register(r1), self.register(r2));
+                if val1 != val2 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::Xor {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory.insert(r_dest.clone(), val1 ^ val2); // Bitwise XOR
+            }
+            Instruction::BranchIfLt { r1, r2, label } => {
+                let (val1, val2) = (self.register(r1), self.register(r2));
+                if val1 < val2 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::CmpEq {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    let result = if val1 == val2 { 1 } else { 0 };
-                    println!(
-                        "[VM] PC={:04} CmpEq: Reg '{}' = ({} == {}) = {}",
-                        pc, r_dest, val1, val2, result
-                    ); // DEBUG
-                    self.classical_memory.insert(r_dest.clone(), result);
+            }
+            Instruction::BranchIfGe { r1, r2, label } => {
+                let (val1, val2) = (self.register(r1), self.register(r2));
+                if val1 >= val2 {
+                    self.jump_to(program, label)?;
                 }
-                Instruction::CmpLt {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory
-                        .insert(r_dest.clone(), if val1 < val2 { 1 } else { 0 });
+            }
+            Instruction::LoadImmediate { register, value } => {
+                self.classical_memory.insert(register.clone(), *value);
+            }
+            Instruction::Rand {
+                register,
+                upper_bound,
+            } => {
+                let value = match *upper_bound {
+                    0 => self.rng.next_u64(),
+                    bound => self.rng.random_range(0..bound),
+                };
+                self.classical_memory.insert(register.clone(), value);
+            }
+            Instruction::Copy {
+                source_reg,
+                dest_reg,
+            } => {
+                let value = self.classical_memory.get(source_reg).copied().unwrap_or(0);
+                self.classical_memory.insert(dest_reg.clone(), value);
+            }
+            Instruction::Store {
+                base,
+                index_reg,
+                src,
+            } => {
+                let (index, value) = (self.register(index_reg), self.register(src));
+                self.array_memory
+                    .entry(base.clone())
+                    .or_default()
+                    .insert(index, value);
+            }
+            Instruction::Load {
+                base,
+                index_reg,
+                dest,
+            } => {
+                let value = self.get_array_element(base, self.register(index_reg));
+                self.classical_memory.insert(dest.clone(), value);
+            }
+            Instruction::OnqAdd {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory
+                    .insert(r_dest.clone(), val1.wrapping_add(val2));
+            }
+            Instruction::Addi {
+                r_dest,
+                r_src,
+                value,
+            } => {
+                let val_src = self.classical_memory.get(r_src).copied().unwrap_or(0);
+                let result = val_src.wrapping_add(*value);
+                self.classical_memory.insert(r_dest.clone(), result);
+            }
+            Instruction::Sub {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory
+                    .insert(r_dest.clone(), val1.wrapping_sub(val2));
+            }
+            Instruction::Mul {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory
+                    .insert(r_dest.clone(), val1.wrapping_mul(val2));
+            }
+            Instruction::OnqNot { r_dest, r_src } => {
+                let val_src = self.classical_memory.get(r_src).copied().unwrap_or(0);
+                self.classical_memory.insert(r_dest.clone(), !val_src); // Bitwise NOT
+            }
+            Instruction::And {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory.insert(r_dest.clone(), val1 & val2); // Bitwise AND
+            }
+            Instruction::Or {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory.insert(r_dest.clone(), val1 | val2); // Bitwise OR
+            }
+            Instruction::Xor {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory.insert(r_dest.clone(), val1 ^ val2); // Bitwise XOR
+            }
+            Instruction::CmpEq {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                let result = if val1 == val2 { 1 } else { 0 };
+                self.classical_memory.insert(r_dest.clone(), result);
+            }
+            Instruction::CmpLt {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory
+                    .insert(r_dest.clone(), if val1 < val2 { 1 } else { 0 });
+            }
+            Instruction::CmpLtS {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.register(r_src1) as i64;
+                let val2 = self.register(r_src2) as i64;
+                self.classical_memory
+                    .insert(r_dest.clone(), if val1 < val2 { 1 } else { 0 });
+            }
+            Instruction::SubS {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.register(r_src1) as i64;
+                let val2 = self.register(r_src2) as i64;
+                let difference =
+                    val1.checked_sub(val2)
+                        .ok_or_else(|| OnqError::SimulationError {
+                            message: format!(
+                                "Runtime Error: SubS {} - {} overflows i64.",
+                                val1, val2
+                            ),
+                        })?;
+                self.classical_memory
+                    .insert(r_dest.clone(), difference as u64);
+            }
+            Instruction::SignExtend {
+                r_dest,
+                r_src,
+                bits,
+            } => {
+                let value = self.register(r_src);
+                let extended = match *bits {
+                    1..=63 => {
+                        let shift = 64 - bits;
+                        (((value << shift) as i64) >> shift) as u64
+                    }
+                    _ => value,
+                };
+                self.classical_memory.insert(r_dest.clone(), extended);
+            }
+            Instruction::FLoad { register, value } => {
+                self.float_memory.insert(register.clone(), *value);
+            }
+            Instruction::FAdd {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.float_memory.get(r_src1).copied().unwrap_or(0.0);
+                let val2 = self.float_memory.get(r_src2).copied().unwrap_or(0.0);
+                self.float_memory.insert(r_dest.clone(), val1 + val2);
+            }
+            Instruction::FMul {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.float_memory.get(r_src1).copied().unwrap_or(0.0);
+                let val2 = self.float_memory.get(r_src2).copied().unwrap_or(0.0);
+                self.float_memory.insert(r_dest.clone(), val1 * val2);
+            }
+            Instruction::FCmp {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.float_memory.get(r_src1).copied().unwrap_or(0.0);
+                let val2 = self.float_memory.get(r_src2).copied().unwrap_or(0.0);
+                self.classical_memory
+                    .insert(r_dest.clone(), if val1 > val2 { 1 } else { 0 });
+            }
+            Instruction::FFromBits { r_dest, r_src } => {
+                let bits = self.classical_memory.get(r_src).copied().unwrap_or(0);
+                self.float_memory
+                    .insert(r_dest.clone(), f64::from_bits(bits));
+            }
+            Instruction::FPhaseShift { target, theta_reg } => {
+                let theta = self.float_memory.get(theta_reg).copied().unwrap_or(0.0);
+                if let Some(engine) = self.engine.as_mut() {
+                    engine.apply_operation(&Operation::PhaseShift {
+                        target: *target,
+                        theta,
+                    })?;
+                } else {
+                    return Err(OnqError::InvalidOperation {
+                        message: "Cannot execute FPhaseShift: SimulationEngine not initialized."
+                            .to_string(),
+                    });
                 }
-                // Add similar println! for other classical ops if needed
-                Instruction::Halt => {
-                    println!("[VM] PC={:04} Halting.", pc); // DEBUG
-                    self.is_halted = true;
+            }
+            Instruction::QuantumOpDyn {
+                op_template,
+                angle_register,
+            } => {
+                let theta = self
+                    .float_memory
+                    .get(angle_register)
+                    .copied()
+                    .unwrap_or(0.0);
+                let op =
+                    op_template
+                        .with_angle(theta)
+                        .ok_or_else(|| OnqError::InvalidOperation {
+                            message: format!(
+                                "QuantumOpDyn template has no angle parameter: {:?}",
+                                op_template
+                            ),
+                        })?;
+                if let Some(engine) = self.engine.as_mut() {
+                    engine.apply_operation(&op)?;
+                } else {
+                    return Err(OnqError::InvalidOperation {
+                        message: "Cannot execute QuantumOpDyn: SimulationEngine not initialized."
+                            .to_string(),
+                    });
                 }
-                Instruction::NoOp => {
-                    println!("[VM] PC={:04} NoOp.", pc); // DEBUG
-                    // Do nothing
+            }
+            Instruction::Print { format, registers } => {
+                let mut line = String::with_capacity(format.len());
+                let mut values = registers.iter().map(|register| self.register(register));
+                let mut pieces = format.split("{}");
+                line.push_str(pieces.next().unwrap_or_default());
+                for piece in pieces {
+                    if let Some(value) = values.next() {
+                        line.push_str(&value.to_string());
+                    }
+                    line.push_str(piece);
                 }
-                Instruction::CmpGt {
-                    r_dest,
-                    r_src1,
-                    r_src2,
-                } => {
-                    let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
-                    let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
-                    self.classical_memory
-                        .insert(r_dest.clone(), if val1 > val2 { 1 } else { 0 });
+                match &self.output {
+                    Some(OutputSink(sink)) => sink(&line),
+                    None => println!("{}", line),
                 }
-            } // End match instruction
-
-            // Check if PC ran off the end without halting
-            if !self.is_halted && self.program_counter >= program.instruction_count() {
-                println!(
-                    "[VM] PC={} Reached end of program. Halting.",
-                    self.program_counter
-                ); // DEBUG
+            }
+            Instruction::HostCall { name, args, ret } => {
+                let HostFunction(function) =
+                    self.host_functions
+                        .get(name)
+                        .ok_or_else(|| OnqError::SimulationError {
+                            message: format!(
+                                "Runtime Error: Host function '{}' is not registered.",
+                                name
+                            ),
+                        })?;
+                let values: Vec<u64> = args.iter().map(|arg| self.register(arg)).collect();
+                let value = function(&values).map_err(|e| OnqError::SimulationError {
+                    message: format!("Runtime Error: Host function '{}' failed: {}", name, e),
+                })?;
+                self.classical_memory.insert(ret.clone(), value);
+            }
+            Instruction::Halt => {
                 self.is_halted = true;
+                self.halt_reason = HaltReason::Halted;
             }
-        } // End while !self.is_halted
+            Instruction::NoOp => {
+                // Do nothing
+            }
+            Instruction::CmpGt {
+                r_dest,
+                r_src1,
+                r_src2,
+            } => {
+                let val1 = self.classical_memory.get(r_src1).copied().unwrap_or(0);
+                let val2 = self.classical_memory.get(r_src2).copied().unwrap_or(0);
+                self.classical_memory
+                    .insert(r_dest.clone(), if val1 > val2 { 1 } else { 0 });
+            }
+        } // End match instruction
+
+        if instruction.is_control_flow() {
+            let target = self.program_counter;
+            self.trace(TraceLevel::Events, || TraceEvent::Branch {
+                pc,
+                target,
+                taken: target != pc + 1,
+            });
+        }
 
-        println!("[VM RUN END]"); // DEBUG
+        // Check if PC ran off the end without halting
+        if !self.is_halted && self.program_counter >= program.instruction_count() {
+            self.is_halted = true;
+        }
+
+        if let Some(hook) = &self.progress {
+            hook.report(Progress {
+                completed: self.executed_instructions as usize,
+                total: None,
+                qdus: self.qdu_count,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reports the event built by `event` to the trace callback, if `level` is traced.
+    fn trace(&self, level: TraceLevel, event: impl FnOnce() -> TraceEvent) {
+        if let Some(tracer) = &self.tracer {
+            tracer.emit(level, event);
+        }
+    }
+
+    /// Stabilizes `targets`, keeping the outcomes for `Record` and logging them, for the
+    /// `kind` instruction at `pc`. Stabilizing no QDUs does nothing.
+    fn stabilize(&mut self, pc: usize, targets: &[QduId], kind: &str) -> Result<(), OnqError> {
+        if targets.is_empty() {
+            // Nothing to stabilize
+        } else if let Some(engine) = self.engine.as_mut() {
+            let mut temp_result = SimulationResult::new();
+            engine.stabilize(targets, &mut temp_result)?; // This might return Err
+
+            // Store the u64 outcomes for Record instruction
+            self.last_stabilization_outcomes = temp_result
+                .all_stable_outcomes()
+                .iter()
+                .filter_map(|(qid, state)| state.get_resolved_value().map(|val| (*qid, val)))
+                .collect();
+            let event = StabilizationEvent {
+                pc,
+                outcomes: self
+                    .last_stabilization_outcomes
+                    .iter()
+                    .map(|(qdu, value)| (*qdu, *value))
+                    .collect(),
+            };
+            self.trace(TraceLevel::Events, || TraceEvent::Stabilized {
+                pc,
+                outcomes: event.outcomes.clone(),
+            });
+            self.stabilizations.push(event);
+        } else {
+            return Err(OnqError::InvalidOperation {
+                message: format!("Cannot execute {}: SimulationEngine not initialized.", kind),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads a classical register, treating a non-existent one as 0.
+    fn register(&self, name: &str) -> u64 {
+        self.classical_memory.get(name).copied().unwrap_or(0)
+    }
+
+    /// Continues execution after `label`.
+    fn jump_to(&mut self, program: &Program, label: &str) -> Result<(), OnqError> {
+        self.program_counter =
+            program
+                .get_label_pc(label)
+                .ok_or_else(|| OnqError::SimulationError {
+                    message: format!("Runtime Error: Branch target label '{}' not found.", label),
+                })?;
         Ok(())
     }
 
@@ -409,15 +1448,24 @@ impl OnqVm {
         let mut qdus = HashSet::new();
         for instruction in &program.instructions {
             match instruction {
-                Instruction::QuantumOp(op) => {
+                Instruction::QuantumOp(op) | Instruction::QuantumOpIf { op, .. } => {
                     qdus.extend(op.involved_qdus());
                 }
-                Instruction::Stabilize { targets } => {
+                Instruction::Stabilize { targets } | Instruction::ResetQdu { targets } => {
                     qdus.extend(targets);
                 }
                 Instruction::Record { qdu, .. } => {
                     qdus.insert(*qdu);
                 }
+                Instruction::StabilizeInto { targets } => {
+                    qdus.extend(targets.iter().map(|(qdu, _)| *qdu));
+                }
+                Instruction::FPhaseShift { target, .. } => {
+                    qdus.insert(*target);
+                }
+                Instruction::QuantumOpDyn { op_template, .. } => {
+                    qdus.extend(op_template.involved_qdus());
+                }
                 // Classical/Control flow ops don't directly involve QDUs
                 _ => {}
             }
@@ -436,6 +1484,39 @@ impl OnqVm {
         self.classical_memory.clone()
     }
 
+    /// Reads the value of a classical register as a signed (two's complement) integer
+    /// after a run. Returns 0 if the register does not exist.
+    pub fn get_signed_register(&self, name: &str) -> i64 {
+        self.register(name) as i64
+    }
+
+    /// Reads element `index` of a classical array after a run.
+    /// Returns 0 if the element was never stored to.
+    pub fn get_array_element(&self, base: &str, index: u64) -> u64 {
+        self.array_memory
+            .get(base)
+            .and_then(|array| array.get(&index))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns a clone of the elements stored to a classical array, keyed by index
+    /// (empty if the array was never written).
+    pub fn get_array(&self, base: &str) -> BTreeMap<u64, u64> {
+        self.array_memory.get(base).cloned().unwrap_or_default()
+    }
+
+    /// Reads the value of a float register after a run.
+    /// Returns 0.0 if the register does not exist.
+    pub fn get_float_register(&self, name: &str) -> f64 {
+        self.float_memory.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Returns a clone of the entire float register map.
+    pub fn get_float_memory(&self) -> HashMap<String, f64> {
+        self.float_memory.clone()
+    }
+
     /// Returns a clone of the current quantum PotentialityState, if the
     /// simulation engine has been initialized (i.e., if the program contained quantum ops).
     /// Returns `None` if no quantum state exists (e.g., purely classical program or before run).
@@ -445,6 +1526,36 @@ impl OnqVm {
         self.engine.as_ref().map(|e| e.get_state().clone())
         // Note: PotentialityState derives Clone, which uses Vec::clone, performing a deep copy.
     }
+
+    /// Returns the amplitude of the joint qualities `outcome` in the current quantum
+    /// state, mapping each QDU to its IVM node. Each QDU's local state is taken
+    /// normalized and QDUs not listed are traced out.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` if no quantum state exists, a quality is
+    /// not 0 or 1, or a QDU is listed twice, and `OnqError::ReferenceViolation` for
+    /// QDUs the program does not use.
+    pub fn amplitude_of(&self, outcome: &[(QduId, u8)]) -> Result<Complex<f64>, OnqError> {
+        self.quantum_engine()?.amplitude_of(outcome)
+    }
+
+    /// Returns the probability of the joint qualities `outcome` in the current quantum
+    /// state.
+    ///
+    /// # Errors
+    /// As [`OnqVm::amplitude_of`].
+    pub fn probability_of_outcome(&self, outcome: &[(QduId, u8)]) -> Result<f64, OnqError> {
+        self.quantum_engine()?.probability_of_outcome(outcome)
+    }
+
+    fn quantum_engine(&self) -> Result<&SimulationEngine, OnqError> {
+        self.engine
+            .as_ref()
+            .ok_or_else(|| OnqError::InvalidOperation {
+                message: "No quantum state exists; the program has no quantum operations"
+                    .to_string(),
+            })
+    }
     // Potential future methods:
     // - step(): Execute one instruction
     // - get_potentiality_state(): Get a clone of the engine's state (if engine exists)
@@ -452,6 +1563,29 @@ impl OnqVm {
     // - inject_error(...): For noise simulation
 }
 
+/// A shared callback receiving the lines written by `Print`.
+#[derive(Clone)]
+struct OutputSink(Arc<dyn Fn(&str) + Send + Sync>);
+
+impl fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OutputSink")
+    }
+}
+
+/// The signature of the Rust functions called by `HostCall`.
+type HostFn = dyn Fn(&[u64]) -> Result<u64, String> + Send + Sync;
+
+/// A shared Rust function called by `HostCall`.
+#[derive(Clone)]
+struct HostFunction(Arc<HostFn>);
+
+impl fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HostFunction")
+    }
+}
+
 // Default implementation
 impl Default for OnqVm {
     fn default() -> Self {
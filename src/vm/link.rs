@@ -0,0 +1,139 @@
+// src/vm/link.rs
+
+//! Linking separately built [`Program`]s into one, see [`link`].
+
+use super::program::{Instruction, Program};
+use crate::core::OnqError;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Links `units` into one program, placing their instructions one after another in
+/// order, so execution starts at the first instruction of the first unit.
+///
+/// Every label a unit imports ([`ProgramBuilder::import`](super::ProgramBuilder::import))
+/// resolves to the unit exporting it ([`ProgramBuilder::export`](super::ProgramBuilder::export)),
+/// so a library of VM routines can be built once and linked into many programs. Label
+/// references are relocated to the new instruction indices. A local label defined by
+/// more than one unit is renamed to `"<unit index>.<label>"` in each unit defining it,
+/// so the generated labels of the structured control-flow helpers never clash.
+///
+/// Execution never falls from one unit into the next: a `Halt` is placed after a unit
+/// that could otherwise run off its end. The linked program exports every label the
+/// units export and imports none.
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` if two units export the same label, or a unit
+/// imports a label no unit exports.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::{link, Instruction, OnqVm, ProgramBuilder};
+/// let library = ProgramBuilder::new()
+///     .export("square")
+///     .pb_add(Instruction::Label("square".to_string()))
+///     .pb_add(Instruction::Mul { r_dest: "x".to_string(), r_src1: "x".to_string(), r_src2: "x".to_string() })
+///     .pb_add(Instruction::Return)
+///     .build()
+///     .unwrap();
+/// let main = ProgramBuilder::new()
+///     .import("square")
+///     .pb_add(Instruction::LoadImmediate { register: "x".to_string(), value: 3 })
+///     .pb_add(Instruction::Call("square".to_string()))
+///     .pb_add(Instruction::Call("square".to_string()))
+///     .build()
+///     .unwrap();
+///
+/// let program = link(&[main, library]).unwrap();
+/// assert_eq!(program.instruction_count(), 6); // A Halt separates the units
+/// let mut vm = OnqVm::new();
+/// assert_eq!(vm.run(&program).unwrap().register("x"), 81);
+/// ```
+pub fn link(units: &[Program]) -> Result<Program, OnqError> {
+    let error = |message: String| OnqError::InvalidOperation {
+        message: format!("Cannot link: {}", message),
+    };
+
+    let mut exporters: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, unit) in units.iter().enumerate() {
+        for label in &unit.exports {
+            if let Some(first) = exporters.insert(label, index) {
+                return Err(error(format!(
+                    "label '{}' is exported by units {} and {}",
+                    label, first, index
+                )));
+            }
+        }
+    }
+    for (index, unit) in units.iter().enumerate() {
+        if let Some(label) = unit
+            .imports
+            .iter()
+            .find(|l| !exporters.contains_key(l.as_str()))
+        {
+            return Err(error(format!(
+                "unit {} imports label '{}', which no unit exports",
+                index, label
+            )));
+        }
+    }
+
+    // Names of local labels defined by several units, which need renaming
+    let mut definitions: HashMap<&str, usize> = HashMap::new();
+    for unit in units {
+        for label in unit.label_map.keys() {
+            *definitions.entry(label).or_default() += 1;
+        }
+    }
+    let mut taken: BTreeSet<String> = definitions.keys().map(|l| l.to_string()).collect();
+
+    let mut instructions = Vec::new();
+    let mut label_map = HashMap::new();
+    let mut exports = BTreeSet::new();
+    for (index, unit) in units.iter().enumerate() {
+        let offset = instructions.len();
+        let mut renamed: HashMap<&str, String> = HashMap::new();
+        for (label, &pc) in &unit.label_map {
+            let mut name = label.clone();
+            if definitions[label.as_str()] > 1 && !unit.exports.contains(label) {
+                name = format!("{}.{}", index, label);
+                let mut suffix = 1;
+                while taken.contains(&name) {
+                    name = format!("{}.{}.{}", index, label, suffix);
+                    suffix += 1;
+                }
+                taken.insert(name.clone());
+            }
+            label_map.insert(name.clone(), pc + offset);
+            renamed.insert(label, name);
+        }
+
+        for instruction in &unit.instructions {
+            let mut instruction = instruction.clone();
+            if let Some(label) = instruction.branch_label_mut()
+                && let Some(name) = renamed.get(label.as_str())
+            {
+                *label = name.clone();
+            }
+            instructions.push(instruction);
+        }
+
+        let labelled_end = unit
+            .label_map
+            .values()
+            .any(|&pc| pc == unit.instructions.len());
+        let ends = matches!(
+            unit.instructions.last(),
+            Some(Instruction::Halt | Instruction::Jump(_) | Instruction::Return)
+        );
+        if index + 1 < units.len() && (labelled_end || !ends) {
+            instructions.push(Instruction::Halt);
+        }
+        exports.extend(unit.exports.iter().cloned());
+    }
+
+    Ok(Program {
+        instructions,
+        label_map,
+        exports,
+        imports: BTreeSet::new(),
+    })
+}
@@ -0,0 +1,150 @@
+// src/vm/macros.rs
+
+//! Named, parameterized blocks of instructions that
+//! [`ProgramBuilder`](super::ProgramBuilder) expands inline.
+
+use super::program::Instruction;
+use crate::core::QduId;
+use std::collections::HashMap;
+
+/// A named block of instructions, parameterized by QDUs and register names, that a
+/// [`ProgramBuilder`](super::ProgramBuilder) expands inline wherever it is used, so a
+/// repeated block such as "apply oracle" becomes a single
+/// [`ProgramBuilder::expand`](super::ProgramBuilder::expand).
+///
+/// The body is written against placeholder parameters: every use of a QDU parameter
+/// and every register, float register or array named like a register parameter is
+/// replaced by the corresponding argument of the expansion. Other QDUs and names are
+/// left alone. Labels defined in the body are renamed to `"<name>.N.<label>"` in each
+/// expansion, so a macro with a loop can be expanded more than once.
+///
+/// Unlike a `Call`, an expansion costs no jumps and can act on different QDUs each time.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::{InstructionMacro, Instruction, OnqVm, ProgramBuilder};
+/// # use onq::{Operation, QduId};
+/// // Flips `target` and counts the flip in `flips`
+/// let flip = InstructionMacro::new(
+///     "flip",
+///     &[QduId(0)],
+///     &["flips"],
+///     vec![
+///         Instruction::QuantumOp(Operation::InteractionPattern {
+///             target: QduId(0),
+///             pattern_id: "QualityFlip".to_string(),
+///         }),
+///         Instruction::Addi { r_dest: "flips".to_string(), r_src: "flips".to_string(), value: 1 },
+///     ],
+/// );
+/// let program = ProgramBuilder::new()
+///     .define_macro(flip)
+///     .expand("flip", &[QduId(4)], &["n"])
+///     .expand("flip", &[QduId(7)], &["n"])
+///     .pb_add(Instruction::Stabilize { targets: vec![QduId(4), QduId(7)] })
+///     .pb_add(Instruction::Record { qdu: QduId(7), register: "m".to_string() })
+///     .build()
+///     .unwrap();
+///
+/// let mut vm = OnqVm::new();
+/// let result = vm.run(&program).unwrap();
+/// assert_eq!(result.register("n"), 2);
+/// assert_eq!(result.register("m"), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionMacro {
+    name: String,
+    qdu_params: Vec<QduId>,
+    register_params: Vec<String>,
+    body: Vec<Instruction>,
+}
+
+impl InstructionMacro {
+    /// Creates a macro named `name` whose `body` uses the placeholder QDUs `qdu_params`
+    /// and registers `register_params`.
+    pub fn new(
+        name: &str,
+        qdu_params: &[QduId],
+        register_params: &[&str],
+        body: Vec<Instruction>,
+    ) -> Self {
+        InstructionMacro {
+            name: name.to_string(),
+            qdu_params: qdu_params.to_vec(),
+            register_params: register_params.iter().map(|r| r.to_string()).collect(),
+            body,
+        }
+    }
+
+    /// Returns the name the macro is expanded by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the instructions of one expansion, numbered `number`, with `qdus` and
+    /// `registers` in place of the parameters. (Internal visibility)
+    pub(crate) fn expand(
+        &self,
+        number: usize,
+        qdus: &[QduId],
+        registers: &[&str],
+    ) -> Result<Vec<Instruction>, String> {
+        if qdus.len() != self.qdu_params.len() || registers.len() != self.register_params.len() {
+            return Err(format!(
+                "Macro '{}' takes {} QDUs and {} registers, but was given {} and {}",
+                self.name,
+                self.qdu_params.len(),
+                self.register_params.len(),
+                qdus.len(),
+                registers.len()
+            ));
+        }
+        let qdu_args: HashMap<QduId, QduId> = self
+            .qdu_params
+            .iter()
+            .copied()
+            .zip(qdus.iter().copied())
+            .collect();
+        let register_args: HashMap<&str, &str> = self
+            .register_params
+            .iter()
+            .map(String::as_str)
+            .zip(registers.iter().copied())
+            .collect();
+        let labels: HashMap<&str, String> = self
+            .body
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Label(label) => Some((
+                    label.as_str(),
+                    format!("{}.{}.{}", self.name, number, label),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        Ok(self
+            .body
+            .iter()
+            .map(|instruction| {
+                let mut instruction =
+                    instruction.map_qdus(|qdu| qdu_args.get(&qdu).copied().unwrap_or(qdu));
+                for name in instruction.names_mut() {
+                    if let Some(arg) = register_args.get(name.as_str()) {
+                        *name = arg.to_string();
+                    }
+                }
+                let label = match &mut instruction {
+                    Instruction::Label(label) => Some(label),
+                    other => other.branch_label_mut(),
+                };
+                if let Some(label) = label
+                    && let Some(renamed) = labels.get(label.as_str())
+                {
+                    *label = renamed.clone();
+                }
+                instruction
+            })
+            .collect())
+    }
+}
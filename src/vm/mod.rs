@@ -11,13 +11,52 @@
 //! * [`Instruction`]: Enum defining all executable VM operations (quantum, classical, control flow, etc.).
 //! * [`Program`]: Represents a compiled, executable sequence of instructions with resolved labels.
 //! * [`ProgramBuilder`]: A utility for constructing `Program` instances fluently.
+//! * [`InstructionMacro`]: A named block of instructions the builder expands inline.
+//! * [`asm`]: The `onq-asm` text format, read with [`Program::parse`].
+//! * [`link()`]: Joins separately built programs through their exported and imported labels.
 //! * [`OnqVm`]: The virtual machine interpreter that manages state (quantum and classical)
 //!   and executes `Program` instructions step-by-step according to derived rules.
+//! * [`Breakpoint`]: A position at which [`OnqVm`] pauses a run, to be stepped or continued.
+//! * [`RegisterChange`]: A change of a classical register watched with [`OnqVm::set_watchpoint`].
+//! * [`VmRunResult`]: The summary of a run: final registers, stabilization log, halt reason.
+//! * [`VmShotResults`]: Register value and stabilization outcome distributions of
+//!   [`OnqVm::run_shots`].
+//! * [`VmCheckpoint`]: The saved state of a paused run, restored with [`OnqVm::restore`].
+//! * [`VerificationIssue`]: A mistake found by the static checks of [`Program::verify`].
+//! * [`ExecutionStats`]: Per-kind instruction and quantum operation counts of a run.
+//! * [`TraceLevel`] / [`TraceEvent`]: Structured tracing of runs, enabled with [`OnqVm::with_trace`].
+//! * [`transform`]: Program-to-program rewrites, such as [`defer_stabilization`].
+//! * [`optimize`]: Jump threading, constant folding and dead code elimination, reporting
+//!   each change as an [`Optimization`].
 
 // Declare modules
 pub mod program;
+pub mod asm;
+pub mod checkpoint;
+pub mod debug;
 pub mod interpreter;
+pub mod link;
+pub mod macros;
+pub mod optimize;
+pub mod result;
+pub(crate) mod rng;
+pub mod shots;
+pub mod stats;
+pub mod trace;
+pub mod transform;
+pub mod verify;
 
 // Re-export public types from submodules
 pub use program::{Instruction, Program, ProgramBuilder};
-pub use interpreter::OnqVm;
+pub use interpreter::{DEFAULT_INSTRUCTION_LIMIT, OnqVm};
+pub use link::link;
+pub use macros::InstructionMacro;
+pub use optimize::{Optimization, OptimizedProgram};
+pub use checkpoint::VmCheckpoint;
+pub use debug::{Breakpoint, RegisterChange};
+pub use result::{HaltReason, StabilizationEvent, VmRunResult};
+pub use shots::VmShotResults;
+pub use stats::ExecutionStats;
+pub use trace::{TraceEvent, TraceLevel};
+pub use transform::defer_stabilization;
+pub use verify::VerificationIssue;
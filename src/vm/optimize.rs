@@ -0,0 +1,502 @@
+// src/vm/optimize.rs
+
+//! Program optimization passes for the ONQ-VM.
+//!
+//! [`optimize`] rewrites a built [`Program`] into an equivalent one that executes
+//! fewer instructions, and reports every change it made as an [`Optimization`]. It
+//! runs three passes until none of them finds anything more to do:
+//!
+//! * **Jump threading**: a jump, branch or call to a label holding a `Jump` is
+//!   retargeted to that jump's label, so chains of jumps are taken in one step.
+//! * **Constant folding**: within a straight-line block, a classical instruction whose
+//!   operands are all known constants is replaced by a `LoadImmediate` of its result,
+//!   and a branch whose outcome is known becomes a `Jump` or is removed.
+//! * **Dead code elimination**: instructions no path from the start of the program
+//!   reaches (e.g. after a `Jump`, `Halt` or `Return`) are removed, as are jumps to the
+//!   instruction right after them.
+//!
+//! Every run of the optimized program computes the same registers, prints the same
+//! output and applies the same quantum operations as the original. Instruction counts
+//! and program counters differ, and QDUs used only by removed instructions are no
+//! longer allocated.
+
+use super::asm::write_instruction;
+use super::program::{Instruction, Program};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// One change made by [`optimize`]. Program counters are indices into the original
+/// program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Optimization {
+    /// A branch, jump or call was retargeted past the `Jump` its label held.
+    ThreadedJump {
+        /// Index of the retargeted instruction.
+        pc: usize,
+        /// The label it targeted before.
+        from: String,
+        /// The label it targets now.
+        to: String,
+    },
+    /// An instruction with constant operands was replaced by its result.
+    FoldedConstant {
+        /// Index of the folded instruction.
+        pc: usize,
+        /// The instruction before folding.
+        before: Instruction,
+        /// The replacement: a `LoadImmediate` or `Jump`, or `None` for a branch that is
+        /// never taken.
+        after: Option<Instruction>,
+    },
+    /// An instruction that can never execute was removed.
+    RemovedUnreachable {
+        /// Index of the removed instruction.
+        pc: usize,
+        /// The removed instruction.
+        instruction: Instruction,
+    },
+    /// A `Jump` to the instruction right after it was removed.
+    RemovedRedundantJump {
+        /// Index of the removed jump.
+        pc: usize,
+        /// The label it jumped to.
+        label: String,
+    },
+}
+
+impl Optimization {
+    /// Returns the index in the original program of the instruction this change affects.
+    pub fn pc(&self) -> usize {
+        match self {
+            Optimization::ThreadedJump { pc, .. }
+            | Optimization::FoldedConstant { pc, .. }
+            | Optimization::RemovedUnreachable { pc, .. }
+            | Optimization::RemovedRedundantJump { pc, .. } => *pc,
+        }
+    }
+}
+
+impl fmt::Display for Optimization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Optimization::ThreadedJump { pc, from, to } => {
+                write!(f, "pc {}: retargeted from '{}' to '{}'", pc, from, to)
+            }
+            Optimization::FoldedConstant {
+                pc,
+                before,
+                after: Some(after),
+            } => write!(
+                f,
+                "pc {}: folded '{}' into '{}'",
+                pc,
+                write_instruction(before),
+                write_instruction(after)
+            ),
+            Optimization::FoldedConstant {
+                pc,
+                before,
+                after: None,
+            } => write!(
+                f,
+                "pc {}: removed '{}', which is never taken",
+                pc,
+                write_instruction(before)
+            ),
+            Optimization::RemovedUnreachable { pc, instruction } => write!(
+                f,
+                "pc {}: removed unreachable '{}'",
+                pc,
+                write_instruction(instruction)
+            ),
+            Optimization::RemovedRedundantJump { pc, label } => {
+                write!(
+                    f,
+                    "pc {}: removed jump to the next instruction '{}'",
+                    pc, label
+                )
+            }
+        }
+    }
+}
+
+/// The result of [`optimize`]: the optimized program and the changes that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizedProgram {
+    /// The optimized program.
+    program: Program,
+    /// Every change, in the order the passes made them.
+    changes: Vec<Optimization>,
+}
+
+impl OptimizedProgram {
+    /// Returns the optimized program.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Consumes the result, returning the optimized program.
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    /// Returns every change, in the order the passes made them. A single instruction
+    /// can be changed more than once, e.g. folded into a `Jump` that is then threaded.
+    pub fn changes(&self) -> &[Optimization] {
+        &self.changes
+    }
+
+    /// Returns `true` if the optimizer found nothing to change.
+    pub fn is_unchanged(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Optimizes `program` with jump threading, constant folding and dead code elimination.
+/// See the [module docs](self) for what each pass does.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+/// # use onq::vm::optimize::{optimize, Optimization};
+/// let program = ProgramBuilder::new()
+///     .pb_add(Instruction::LoadImmediate { register: "n".to_string(), value: 2 })
+///     .pb_add(Instruction::Addi { r_dest: "n".to_string(), r_src: "n".to_string(), value: 3 })
+///     .pb_add(Instruction::Jump("end".to_string()))
+///     .pb_add(Instruction::Print { format: "never".to_string(), registers: vec![] })
+///     .pb_add(Instruction::Label("end".to_string()))
+///     .pb_add(Instruction::Halt)
+///     .build()
+///     .unwrap();
+///
+/// let optimized = optimize(&program);
+/// assert_eq!(optimized.program().instruction_count(), 3);
+/// assert_eq!(
+///     optimized.program().instructions()[1],
+///     Instruction::LoadImmediate { register: "n".to_string(), value: 5 }
+/// );
+/// assert!(matches!(optimized.changes()[1], Optimization::RemovedUnreachable { pc: 3, .. }));
+///
+/// let mut vm = OnqVm::new();
+/// assert_eq!(vm.run(optimized.program()).unwrap().register("n"), 5);
+/// ```
+pub fn optimize(program: &Program) -> OptimizedProgram {
+    let mut work = Work {
+        slots: program.instructions.iter().cloned().map(Some).collect(),
+        label_map: &program.label_map,
+        exports: &program.exports,
+        changes: Vec::new(),
+    };
+    loop {
+        let before = work.changes.len();
+        work.thread_jumps();
+        work.fold_constants();
+        work.eliminate_dead_code();
+        if work.changes.len() == before {
+            break;
+        }
+    }
+
+    // Compact the surviving instructions, moving each label to the first surviving
+    // instruction at or after its old position
+    let mut new_pc = Vec::with_capacity(work.slots.len() + 1);
+    let mut instructions = Vec::new();
+    for slot in &work.slots {
+        new_pc.push(instructions.len());
+        if let Some(instruction) = slot {
+            instructions.push(instruction.clone());
+        }
+    }
+    new_pc.push(instructions.len());
+    let label_map = program
+        .label_map
+        .iter()
+        .map(|(label, pc)| (label.clone(), new_pc[(*pc).min(work.slots.len())]))
+        .collect();
+
+    OptimizedProgram {
+        program: Program {
+            instructions,
+            label_map,
+            exports: program.exports.clone(),
+            imports: program.imports.clone(),
+        },
+        changes: work.changes,
+    }
+}
+
+/// The program being optimized. Instructions stay at their original index; removed
+/// ones become `None` until the final compaction.
+struct Work<'a> {
+    slots: Vec<Option<Instruction>>,
+    label_map: &'a HashMap<String, usize>,
+    exports: &'a BTreeSet<String>,
+    changes: Vec<Optimization>,
+}
+
+impl Work<'_> {
+    /// Returns the index of the first surviving instruction at or after `pc`, or the
+    /// program length if there is none.
+    fn resolve(&self, pc: usize) -> usize {
+        (pc..self.slots.len())
+            .find(|&pc| self.slots[pc].is_some())
+            .unwrap_or(self.slots.len())
+    }
+
+    /// Returns the index of the instruction `label` resolves to.
+    fn target(&self, label: &str) -> Option<usize> {
+        self.label_map.get(label).map(|&pc| self.resolve(pc))
+    }
+
+    /// Retargets every jump, branch and call whose label holds a `Jump` to the end of
+    /// the chain of jumps. Chains that loop forever are left alone.
+    fn thread_jumps(&mut self) {
+        for pc in 0..self.slots.len() {
+            let Some(label) = self.slots[pc].as_ref().and_then(Instruction::branch_label) else {
+                continue;
+            };
+            let mut to = label.to_string();
+            let mut visited = BTreeSet::new();
+            while let Some(target) = self.target(&to)
+                && let Some(Instruction::Jump(next)) = &self.slots.get(target).cloned().flatten()
+            {
+                if !visited.insert(target) {
+                    to = label.to_string();
+                    break;
+                }
+                to = next.clone();
+            }
+            if to != label {
+                let from = label.to_string();
+                if let Some(label) = self.slots[pc]
+                    .as_mut()
+                    .and_then(Instruction::branch_label_mut)
+                {
+                    *label = to.clone();
+                }
+                self.changes
+                    .push(Optimization::ThreadedJump { pc, from, to });
+            }
+        }
+    }
+
+    /// Folds classical instructions and branches whose operands are known constants.
+    /// Register values are only tracked within straight-line code: everything is
+    /// forgotten at label targets and after calls.
+    fn fold_constants(&mut self) {
+        let entries: BTreeSet<usize> = self
+            .label_map
+            .values()
+            .map(|&pc| self.resolve(pc))
+            .collect();
+        let mut known: HashMap<String, u64> = HashMap::new();
+        for pc in 0..self.slots.len() {
+            let Some(before) = self.slots[pc].clone() else {
+                continue;
+            };
+            if entries.contains(&pc) {
+                known.clear();
+            }
+            let after = fold(&before, &known);
+            let executed = match &after {
+                Some(replacement) => replacement.as_ref(),
+                None => Some(&before),
+            };
+            match executed {
+                Some(Instruction::LoadImmediate { register, value }) => {
+                    known.insert(register.clone(), *value);
+                }
+                Some(
+                    Instruction::Call(_)
+                    | Instruction::Jump(_)
+                    | Instruction::Return
+                    | Instruction::Halt,
+                ) => known.clear(),
+                Some(instruction) => {
+                    for register in instruction.written_registers() {
+                        known.remove(register);
+                    }
+                }
+                None => {}
+            }
+            if let Some(after) = after
+                && after.as_ref() != Some(&before)
+            {
+                self.slots[pc] = after.clone();
+                self.changes
+                    .push(Optimization::FoldedConstant { pc, before, after });
+            }
+        }
+    }
+
+    /// Removes the instructions no path from the start or an exported label reaches,
+    /// and jumps to the next surviving instruction.
+    fn eliminate_dead_code(&mut self) {
+        for pc in 0..self.slots.len() {
+            if let Some(Instruction::Jump(label)) = &self.slots[pc]
+                && self.target(label) == Some(self.resolve(pc + 1))
+            {
+                let label = label.clone();
+                self.slots[pc] = None;
+                self.changes
+                    .push(Optimization::RemovedRedundantJump { pc, label });
+            }
+        }
+
+        let mut reachable = vec![false; self.slots.len()];
+        let mut pending = vec![self.resolve(0)];
+        // Exported routines are entered from other units once linked
+        pending.extend(self.exports.iter().filter_map(|label| self.target(label)));
+        while let Some(pc) = pending.pop() {
+            let Some(instruction) = self.slots.get(pc).cloned().flatten() else {
+                continue;
+            };
+            if std::mem::replace(&mut reachable[pc], true) {
+                continue;
+            }
+            if let Some(target) = instruction.branch_label().and_then(|l| self.target(l)) {
+                pending.push(target);
+            }
+            // A call continues after the callee returns
+            if !matches!(
+                instruction,
+                Instruction::Jump(_) | Instruction::Return | Instruction::Halt
+            ) {
+                pending.push(self.resolve(pc + 1));
+            }
+        }
+
+        for (pc, reachable) in reachable.into_iter().enumerate() {
+            if !reachable && let Some(instruction) = self.slots[pc].take() {
+                self.changes
+                    .push(Optimization::RemovedUnreachable { pc, instruction });
+            }
+        }
+    }
+}
+
+/// Returns what `instruction` folds into given the `known` register values: `None` if
+/// it cannot be folded, `Some(None)` if it has no effect, and `Some(Some(..))` for its
+/// replacement. The results match the interpreter exactly.
+fn fold(instruction: &Instruction, known: &HashMap<String, u64>) -> Option<Option<Instruction>> {
+    let value = |register: &String| known.get(register).copied();
+    let load = |register: &String, value: u64| {
+        Some(Some(Instruction::LoadImmediate {
+            register: register.clone(),
+            value,
+        }))
+    };
+    let branch =
+        |taken: bool, label: &String| Some(taken.then(|| Instruction::Jump(label.clone())));
+    match instruction {
+        Instruction::Copy {
+            source_reg,
+            dest_reg,
+        } => load(dest_reg, value(source_reg)?),
+        Instruction::Addi {
+            r_dest,
+            r_src,
+            value: immediate,
+        } => load(r_dest, value(r_src)?.wrapping_add(*immediate)),
+        Instruction::OnqNot { r_dest, r_src } => load(r_dest, !value(r_src)?),
+        Instruction::SignExtend {
+            r_dest,
+            r_src,
+            bits,
+        } => {
+            let value = value(r_src)?;
+            let extended = match *bits {
+                1..=63 => {
+                    let shift = 64 - bits;
+                    (((value << shift) as i64) >> shift) as u64
+                }
+                _ => value,
+            };
+            load(r_dest, extended)
+        }
+        Instruction::OnqAdd {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::And {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Or {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Xor {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Sub {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::Mul {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpEq {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpGt {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpLt {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::CmpLtS {
+            r_dest,
+            r_src1,
+            r_src2,
+        }
+        | Instruction::SubS {
+            r_dest,
+            r_src1,
+            r_src2,
+        } => {
+            let (a, b) = (value(r_src1)?, value(r_src2)?);
+            let result = match instruction {
+                Instruction::OnqAdd { .. } => a.wrapping_add(b),
+                Instruction::And { .. } => a & b,
+                Instruction::Or { .. } => a | b,
+                Instruction::Xor { .. } => a ^ b,
+                Instruction::Sub { .. } => a.wrapping_sub(b),
+                Instruction::Mul { .. } => a.wrapping_mul(b),
+                Instruction::CmpEq { .. } => (a == b) as u64,
+                Instruction::CmpGt { .. } => (a > b) as u64,
+                Instruction::CmpLt { .. } => (a < b) as u64,
+                Instruction::CmpLtS { .. } => ((a as i64) < (b as i64)) as u64,
+                // An overflowing SubS is left to fail at runtime
+                _ => (a as i64).checked_sub(b as i64)? as u64,
+            };
+            load(r_dest, result)
+        }
+        Instruction::QuantumOpIf { register, op } => {
+            Some((value(register)? != 0).then(|| Instruction::QuantumOp(op.clone())))
+        }
+        // The last pass of a loop only clears its counter
+        Instruction::Repeat { register, .. } if value(register)? <= 1 => load(register, 0),
+        Instruction::BranchIfZero { register, label } => branch(value(register)? == 0, label),
+        Instruction::BranchIfNotZero { register, label } => branch(value(register)? != 0, label),
+        Instruction::BranchIfEq { r1, r2, label } => branch(value(r1)? == value(r2)?, label),
+        Instruction::BranchIfNe { r1, r2, label } => branch(value(r1)? != value(r2)?, label),
+        Instruction::BranchIfLt { r1, r2, label } => branch(value(r1)? < value(r2)?, label),
+        Instruction::BranchIfGe { r1, r2, label } => branch(value(r1)? >= value(r2)?, label),
+        _ => None,
+    }
+}
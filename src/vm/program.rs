@@ -3,13 +3,19 @@
 //! Defines the structures and interpreter for the ONQ Virtual Machine (ONQ-VM).
 //! Enables mixed classical/quantum computation based on ONQ principles.
 
-use crate::core::QduId;
+use super::macros::InstructionMacro;
+use super::verify::VerificationIssue;
+use crate::circuits::Circuit;
+use crate::core::{OnqError, QduId};
 use crate::operations::Operation;
-use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 
 /// Specifies the target entangled state for a RelationalLock operation.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)] // Eq/Hash useful if used as keys later
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LockType {
     /// Target state: |Φ+> = (1/sqrt(2))(|00> + |11>)
     BellPhiPlus,
@@ -25,11 +31,21 @@ pub enum LockType {
 
 /// Represents a single instruction executable by the ONQ-VM.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction {
     // --- Quantum Operations ---
     /// Apply a standard quantum operation derived from ONQ.
     QuantumOp(Operation),
 
+    /// Apply `op` only if the value of `register` is non-zero, e.g. a feed-forward
+    /// correction on a recorded outcome, without a branch and label around it.
+    QuantumOpIf {
+        /// The register deciding whether `op` is applied. A non-existent register reads 0.
+        register: String,
+        /// The operation applied when `register` is non-zero.
+        op: Operation,
+    },
+
     // --- Stabilization & Classical Recording ---
     /// Perform ONQ stabilization on target QDUs. The result is held implicitly
     /// until potentially recorded by a subsequent `Record` instruction.
@@ -51,6 +67,22 @@ pub enum Instruction {
         /// where the outcome (0 or 1) will be stored as a `u64`.
         register: String,
     },
+    /// Stabilize the QDUs of `targets` together, like `Stabilize`, and write the outcome
+    /// (0 or 1) of each into the register paired with it. Replaces a `Stabilize`
+    /// followed by one `Record` per QDU, without depending on which stabilization ran
+    /// last; a later `Record` of these QDUs still reads the outcomes.
+    StabilizeInto {
+        /// The QDUs to stabilize, each with the register its outcome is written to.
+        targets: Vec<(QduId, String)>,
+    },
+    /// Reset the `targets` QDUs to Quality0 by a projective reset: each is stabilized and
+    /// flipped back if it resolved to Quality1. Lets a loop reuse a few ancilla QDUs
+    /// instead of adding QDUs, and with them state size, on every pass. The outcomes
+    /// are neither logged nor left for `Record`.
+    ResetQdu {
+        /// The QDUs to reset.
+        targets: Vec<QduId>,
+    },
 
     // --- Control Flow ---
     /// Defines a named label at this point in the instruction sequence.
@@ -74,6 +106,96 @@ pub enum Instruction {
         /// The target label name to jump to if the register's value is 0.
         label: String,
     },
+    /// Conditionally jump execution to the instruction immediately following the
+    /// specified `Label` *if* the value in the classical `register` is not zero.
+    /// If the register does not exist, its value is treated as zero (no jump).
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    BranchIfNotZero {
+        /// The name of the classical register to check.
+        register: String,
+        /// The target label name to jump to if the register's value is not 0.
+        label: String,
+    },
+    /// Jump to the instruction following `label` if the values in registers `r1` and
+    /// `r2` are equal. Non-existent registers read 0.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    BranchIfEq {
+        /// The first register name.
+        r1: String,
+        /// The second register name.
+        r2: String,
+        /// The target label name to jump to if the comparison holds.
+        label: String,
+    },
+    /// Jump to the instruction following `label` if the values in registers `r1` and
+    /// `r2` differ. Non-existent registers read 0.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    BranchIfNe {
+        /// The first register name.
+        r1: String,
+        /// The second register name.
+        r2: String,
+        /// The target label name to jump to if the comparison holds.
+        label: String,
+    },
+    /// Jump to the instruction following `label` if the value in `r1` is less than the
+    /// value in `r2` (unsigned). Non-existent registers read 0.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    BranchIfLt {
+        /// The first register name.
+        r1: String,
+        /// The second register name.
+        r2: String,
+        /// The target label name to jump to if the comparison holds.
+        label: String,
+    },
+    /// Jump to the instruction following `label` if the value in `r1` is greater than
+    /// or equal to the value in `r2` (unsigned). Non-existent registers read 0.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    BranchIfGe {
+        /// The first register name.
+        r1: String,
+        /// The second register name.
+        r2: String,
+        /// The target label name to jump to if the comparison holds.
+        label: String,
+    },
+    /// Count down a loop: decrement `register` and jump to the instruction following
+    /// `label` unless it reached 0, so a loop body ending in `Repeat` runs as many times
+    /// as `register` held, at the cost of one instruction per pass. A register already
+    /// at 0 stays 0 and falls through. See [`ProgramBuilder::repeat`], which also skips
+    /// the body for a count of 0.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined.
+    Repeat {
+        /// The register counting the passes left.
+        register: String,
+        /// The label at the start of the loop body.
+        label: String,
+    },
+    /// Call the subroutine starting after the specified `Label`: push the position of
+    /// the next instruction onto the VM's call stack and jump to the label.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the `label` is undefined
+    /// or the call stack is full.
+    Call(String),
+    /// Return from the current subroutine to the instruction following its `Call`.
+    ///
+    /// # Errors
+    /// Returns `OnqError::SimulationError` during VM execution if the call stack is empty.
+    Return,
     // --- Classical Operations (Minimal Initial Set) ---
     /// Load an immediate unsigned 64-bit integer value into a classical register.
     LoadImmediate {
@@ -89,8 +211,71 @@ pub enum Instruction {
         /// The name of the register to write to.
         dest_reg: String,
     },
+    /// Store the value in register `src` into element `index_reg` (the value of that
+    /// register) of the classical array `base`. Arrays form a space separate from the
+    /// registers, grow as elements are stored, and read 0 at indices never stored to.
+    Store {
+        /// The name of the array written.
+        base: String,
+        /// The register holding the element index.
+        index_reg: String,
+        /// The register holding the value stored.
+        src: String,
+    },
+    /// Load element `index_reg` (the value of that register) of the classical array
+    /// `base` into register `dest`. Reads 0 for elements never stored to.
+    Load {
+        /// The name of the array read.
+        base: String,
+        /// The register holding the element index.
+        index_reg: String,
+        /// The destination register name.
+        dest: String,
+    },
+    /// Load a uniformly distributed random value in `0..upper_bound` into `register`,
+    /// drawn from the VM's seeded random number generator (see
+    /// [`OnqVm::with_rng_seed`](super::OnqVm::with_rng_seed)). An `upper_bound` of 0
+    /// draws from the full `u64` range.
+    Rand {
+        /// The destination register name.
+        register: String,
+        /// The exclusive upper bound of the drawn value.
+        upper_bound: u64,
+    },
     // Future: Add arithmetic/logic (Add, Xor, And, Not, Compare, etc.)
 
+    // --- Host Output ---
+    /// Write a line to the VM's output sink (standard output unless set with
+    /// [`OnqVm::with_output`](super::OnqVm::with_output)): `format` with each `{}`
+    /// replaced, in order, by the value of the corresponding register.
+    ///
+    /// # Errors
+    /// `ProgramBuilder::build` rejects a `format` whose number of `{}` placeholders
+    /// differs from the number of `registers`.
+    Print {
+        /// The line written, with one `{}` placeholder per register.
+        format: String,
+        /// The registers whose values fill the placeholders.
+        registers: Vec<String>,
+    },
+
+    // --- Host Calls ---
+    /// Call the Rust function registered on the VM as `name` (see
+    /// [`OnqVm::with_host_function`](super::OnqVm::with_host_function)) with the values
+    /// of the `args` registers, and store the value it returns in `ret`.
+    ///
+    /// # Errors
+    /// Fails at runtime if no function named `name` is registered, or the function
+    /// returns an error.
+    HostCall {
+        /// The name the host function is registered under.
+        name: String,
+        /// The registers whose values are passed to the function, in order.
+        args: Vec<String>,
+        /// The register receiving the function's return value.
+        ret: String,
+    },
+
     // --- Execution Control ---
     /// Halt the VM execution.
     Halt,
@@ -194,18 +379,427 @@ pub enum Instruction {
         /// The second source register name.
         r_src2: String,
      },
+
+    // --- Signed Integer Operations ---
+    // Registers hold `i64` values in two's complement; the instructions below interpret
+    // their bits as signed where the unsigned ones above do not.
+    /// Compare for less than (signed): Set `r_dest` to 1 if `r_src1` < `r_src2` as
+    /// `i64`, else 0. Reads 0 for non-existent source registers.
+    CmpLtS {
+        /// The destination register name.
+        r_dest: String,
+        /// The first source register name.
+        r_src1: String,
+        /// The second source register name.
+        r_src2: String,
+    },
+    /// Subtract `r_src2` from `r_src1` as `i64` and store in `r_dest`.
+    ///
+    /// # Errors
+    /// Unlike the wrapping `Sub`, returns `OnqError::SimulationError` during VM
+    /// execution if the difference overflows `i64`.
+    SubS {
+        /// The destination register name.
+        r_dest: String,
+        /// The first source register name.
+        r_src1: String,
+        /// The second source register name.
+        r_src2: String,
+    },
+    /// Sign-extend the low `bits` bits of `r_src` to a full `i64` and store in `r_dest`,
+    /// e.g. to read an 8-bit two's-complement field. `bits` of 0 or 64 and above copy
+    /// the value unchanged.
+    SignExtend {
+        /// The destination register name.
+        r_dest: String,
+        /// The source register name.
+        r_src: String,
+        /// The width of the signed field in `r_src`.
+        bits: u32,
+    },
+
+    // --- Floating-Point Operations ---
+    // Float registers form a separate bank from the `u64` registers above:
+    // the same name may refer to one register in each bank.
+    /// Load an immediate `f64` value into a float register.
+    FLoad {
+        /// The destination float register name.
+        register: String,
+        /// The `f64` value to load.
+        value: f64,
+    },
+    /// Add float registers `r_src1` and `r_src2` and store in float register `r_dest`.
+    /// Reads 0.0 for non-existent source registers.
+    FAdd {
+        /// The destination float register name.
+        r_dest: String,
+        /// The first source float register name.
+        r_src1: String,
+        /// The second source float register name.
+        r_src2: String,
+    },
+    /// Multiply float registers `r_src1` and `r_src2` and store in float register `r_dest`.
+    /// Reads 0.0 for non-existent source registers.
+    FMul {
+        /// The destination float register name.
+        r_dest: String,
+        /// The first source float register name.
+        r_src1: String,
+        /// The second source float register name.
+        r_src2: String,
+    },
+    /// Compare floats: set the `u64` register `r_dest` to 1 if float `r_src1` > float
+    /// `r_src2`, else 0 (including when either is NaN). The result can drive `BranchIfZero`.
+    FCmp {
+        /// The destination `u64` register name.
+        r_dest: String,
+        /// The first source float register name.
+        r_src1: String,
+        /// The second source float register name.
+        r_src2: String,
+    },
+    /// Reinterpret the bits of the `u64` register `r_src` as an `f64` (IEEE 754)
+    /// and store it in float register `r_dest`.
+    FFromBits {
+        /// The destination float register name.
+        r_dest: String,
+        /// The source `u64` register name.
+        r_src: String,
+    },
+    /// Apply `Operation::PhaseShift` to `target` with the angle (in radians) read from
+    /// float register `theta_reg` at runtime. Reads 0.0 if the register does not exist.
+    /// Shorthand for `QuantumOpDyn` with a `PhaseShift` template.
+    FPhaseShift {
+        /// The target QDU whose phase is modified.
+        target: QduId,
+        /// The float register holding the phase angle.
+        theta_reg: String,
+    },
+    /// Apply `op_template` with its angle replaced, at runtime, by the value of float
+    /// register `angle_register` (see [`Operation::with_angle`]). Reads 0.0 if the register
+    /// does not exist. The template's own angle is ignored.
+    ///
+    /// # Errors
+    /// `ProgramBuilder::build` rejects templates without an angle parameter
+    /// (anything other than `PhaseShift`, `BroadcastPhaseShift` or `PauliProduct`).
+    QuantumOpDyn {
+        /// The operation to apply, with a placeholder angle.
+        op_template: Operation,
+        /// The float register holding the angle (in radians).
+        angle_register: String,
+    },
+}
+
+impl Instruction {
+    /// Returns the name of the instruction's variant, e.g. `"BranchIfZero"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Instruction::QuantumOp(_) => "QuantumOp",
+            Instruction::QuantumOpIf { .. } => "QuantumOpIf",
+            Instruction::Stabilize { .. } => "Stabilize",
+            Instruction::Record { .. } => "Record",
+            Instruction::StabilizeInto { .. } => "StabilizeInto",
+            Instruction::ResetQdu { .. } => "ResetQdu",
+            Instruction::Label(_) => "Label",
+            Instruction::Jump(_) => "Jump",
+            Instruction::BranchIfZero { .. } => "BranchIfZero",
+            Instruction::BranchIfNotZero { .. } => "BranchIfNotZero",
+            Instruction::BranchIfEq { .. } => "BranchIfEq",
+            Instruction::BranchIfNe { .. } => "BranchIfNe",
+            Instruction::BranchIfLt { .. } => "BranchIfLt",
+            Instruction::BranchIfGe { .. } => "BranchIfGe",
+            Instruction::Repeat { .. } => "Repeat",
+            Instruction::Call(_) => "Call",
+            Instruction::Return => "Return",
+            Instruction::LoadImmediate { .. } => "LoadImmediate",
+            Instruction::Copy { .. } => "Copy",
+            Instruction::Store { .. } => "Store",
+            Instruction::Load { .. } => "Load",
+            Instruction::Rand { .. } => "Rand",
+            Instruction::Print { .. } => "Print",
+            Instruction::HostCall { .. } => "HostCall",
+            Instruction::Halt => "Halt",
+            Instruction::NoOp => "NoOp",
+            Instruction::Addi { .. } => "Addi",
+            Instruction::OnqAdd { .. } => "OnqAdd",
+            Instruction::OnqNot { .. } => "OnqNot",
+            Instruction::And { .. } => "And",
+            Instruction::Or { .. } => "Or",
+            Instruction::Xor { .. } => "Xor",
+            Instruction::Sub { .. } => "Sub",
+            Instruction::Mul { .. } => "Mul",
+            Instruction::CmpEq { .. } => "CmpEq",
+            Instruction::CmpGt { .. } => "CmpGt",
+            Instruction::CmpLt { .. } => "CmpLt",
+            Instruction::CmpLtS { .. } => "CmpLtS",
+            Instruction::SubS { .. } => "SubS",
+            Instruction::SignExtend { .. } => "SignExtend",
+            Instruction::FLoad { .. } => "FLoad",
+            Instruction::FAdd { .. } => "FAdd",
+            Instruction::FMul { .. } => "FMul",
+            Instruction::FCmp { .. } => "FCmp",
+            Instruction::FFromBits { .. } => "FFromBits",
+            Instruction::FPhaseShift { .. } => "FPhaseShift",
+            Instruction::QuantumOpDyn { .. } => "QuantumOpDyn",
+        }
+    }
+
+    /// Returns `true` for instructions that may continue anywhere but the next
+    /// instruction: jumps, branches, calls and returns.
+    pub(crate) fn is_control_flow(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Jump(_)
+                | Instruction::BranchIfZero { .. }
+                | Instruction::BranchIfNotZero { .. }
+                | Instruction::BranchIfEq { .. }
+                | Instruction::BranchIfNe { .. }
+                | Instruction::BranchIfLt { .. }
+                | Instruction::BranchIfGe { .. }
+                | Instruction::Repeat { .. }
+                | Instruction::Call(_)
+                | Instruction::Return
+        )
+    }
+
+    /// Returns the label a jump, branch or call transfers control to, if any.
+    pub(crate) fn branch_label(&self) -> Option<&str> {
+        match self {
+            Instruction::Jump(label)
+            | Instruction::Call(label)
+            | Instruction::BranchIfZero { label, .. }
+            | Instruction::BranchIfNotZero { label, .. }
+            | Instruction::BranchIfEq { label, .. }
+            | Instruction::BranchIfNe { label, .. }
+            | Instruction::BranchIfLt { label, .. }
+            | Instruction::BranchIfGe { label, .. }
+            | Instruction::Repeat { label, .. } => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Returns the label a jump, branch or call transfers control to, for retargeting.
+    pub(crate) fn branch_label_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Instruction::Jump(label)
+            | Instruction::Call(label)
+            | Instruction::BranchIfZero { label, .. }
+            | Instruction::BranchIfNotZero { label, .. }
+            | Instruction::BranchIfEq { label, .. }
+            | Instruction::BranchIfNe { label, .. }
+            | Instruction::BranchIfLt { label, .. }
+            | Instruction::BranchIfGe { label, .. }
+            | Instruction::Repeat { label, .. } => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of the `u64` classical registers this instruction reads.
+    /// Float registers are not included.
+    pub(crate) fn read_registers(&self) -> Vec<&str> {
+        match self {
+            Instruction::BranchIfZero { register, .. }
+            | Instruction::BranchIfNotZero { register, .. }
+            | Instruction::Repeat { register, .. }
+            | Instruction::QuantumOpIf { register, .. } => vec![register],
+            Instruction::Copy { source_reg, .. } => vec![source_reg],
+            Instruction::Print { registers, .. } => registers.iter().map(String::as_str).collect(),
+            Instruction::HostCall { args, .. } => args.iter().map(String::as_str).collect(),
+            Instruction::Store { index_reg, src, .. } => vec![index_reg, src],
+            Instruction::Load { index_reg, .. } => vec![index_reg],
+            Instruction::Addi { r_src, .. }
+            | Instruction::OnqNot { r_src, .. }
+            | Instruction::SignExtend { r_src, .. }
+            | Instruction::FFromBits { r_src, .. } => vec![r_src],
+            Instruction::OnqAdd { r_src1, r_src2, .. }
+            | Instruction::And { r_src1, r_src2, .. }
+            | Instruction::Or { r_src1, r_src2, .. }
+            | Instruction::Xor { r_src1, r_src2, .. }
+            | Instruction::Sub { r_src1, r_src2, .. }
+            | Instruction::Mul { r_src1, r_src2, .. }
+            | Instruction::CmpEq { r_src1, r_src2, .. }
+            | Instruction::CmpGt { r_src1, r_src2, .. }
+            | Instruction::CmpLt { r_src1, r_src2, .. }
+            | Instruction::CmpLtS { r_src1, r_src2, .. }
+            | Instruction::SubS { r_src1, r_src2, .. } => vec![r_src1, r_src2],
+            Instruction::BranchIfEq { r1, r2, .. }
+            | Instruction::BranchIfNe { r1, r2, .. }
+            | Instruction::BranchIfLt { r1, r2, .. }
+            | Instruction::BranchIfGe { r1, r2, .. } => vec![r1, r2],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the names of the `u64` classical registers this instruction writes.
+    /// Float registers are not included.
+    pub(crate) fn written_registers(&self) -> Vec<&str> {
+        match self {
+            Instruction::StabilizeInto { targets } => {
+                targets.iter().map(|(_, register)| register.as_str()).collect()
+            }
+            other => other.written_register().into_iter().collect(),
+        }
+    }
+
+    /// Returns the name of the single `u64` register written by instructions writing at
+    /// most one.
+    fn written_register(&self) -> Option<&str> {
+        match self {
+            Instruction::Record { register, .. }
+            | Instruction::LoadImmediate { register, .. }
+            | Instruction::Rand { register, .. }
+            | Instruction::Repeat { register, .. } => Some(register),
+            Instruction::Copy { dest_reg, .. } => Some(dest_reg),
+            Instruction::Load { dest, .. } => Some(dest),
+            Instruction::HostCall { ret, .. } => Some(ret),
+            Instruction::Addi { r_dest, .. }
+            | Instruction::OnqAdd { r_dest, .. }
+            | Instruction::OnqNot { r_dest, .. }
+            | Instruction::And { r_dest, .. }
+            | Instruction::Or { r_dest, .. }
+            | Instruction::Xor { r_dest, .. }
+            | Instruction::Sub { r_dest, .. }
+            | Instruction::Mul { r_dest, .. }
+            | Instruction::CmpEq { r_dest, .. }
+            | Instruction::CmpGt { r_dest, .. }
+            | Instruction::CmpLt { r_dest, .. }
+            | Instruction::CmpLtS { r_dest, .. }
+            | Instruction::SubS { r_dest, .. }
+            | Instruction::SignExtend { r_dest, .. }
+            | Instruction::FCmp { r_dest, .. } => Some(r_dest),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of the float registers this instruction reads.
+    pub(crate) fn read_float_registers(&self) -> Vec<&str> {
+        match self {
+            Instruction::FAdd { r_src1, r_src2, .. }
+            | Instruction::FMul { r_src1, r_src2, .. }
+            | Instruction::FCmp { r_src1, r_src2, .. } => vec![r_src1, r_src2],
+            Instruction::FPhaseShift { theta_reg, .. } => vec![theta_reg],
+            Instruction::QuantumOpDyn { angle_register, .. } => vec![angle_register],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the name of the float register this instruction writes, if any.
+    pub(crate) fn written_float_register(&self) -> Option<&str> {
+        match self {
+            Instruction::FLoad { register, .. } => Some(register),
+            Instruction::FAdd { r_dest, .. }
+            | Instruction::FMul { r_dest, .. }
+            | Instruction::FFromBits { r_dest, .. } => Some(r_dest),
+            _ => None,
+        }
+    }
+
+    /// Returns every register, float register and array name the instruction names,
+    /// for renaming.
+    pub(crate) fn names_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Instruction::Record { register, .. }
+            | Instruction::QuantumOpIf { register, .. }
+            | Instruction::BranchIfZero { register, .. }
+            | Instruction::BranchIfNotZero { register, .. }
+            | Instruction::Repeat { register, .. }
+            | Instruction::LoadImmediate { register, .. }
+            | Instruction::Rand { register, .. }
+            | Instruction::FLoad { register, .. } => vec![register],
+            Instruction::BranchIfEq { r1, r2, .. }
+            | Instruction::BranchIfNe { r1, r2, .. }
+            | Instruction::BranchIfLt { r1, r2, .. }
+            | Instruction::BranchIfGe { r1, r2, .. } => vec![r1, r2],
+            Instruction::Copy { source_reg, dest_reg } => vec![source_reg, dest_reg],
+            Instruction::Store { base, index_reg, src } => vec![base, index_reg, src],
+            Instruction::Load { base, index_reg, dest } => vec![base, index_reg, dest],
+            Instruction::Print { registers, .. } => registers.iter_mut().collect(),
+            Instruction::HostCall { args, ret, .. } => {
+                args.iter_mut().chain(std::iter::once(ret)).collect()
+            }
+            Instruction::StabilizeInto { targets } => {
+                targets.iter_mut().map(|(_, register)| register).collect()
+            }
+            Instruction::Addi { r_dest, r_src, .. }
+            | Instruction::OnqNot { r_dest, r_src }
+            | Instruction::SignExtend { r_dest, r_src, .. }
+            | Instruction::FFromBits { r_dest, r_src } => vec![r_dest, r_src],
+            Instruction::OnqAdd { r_dest, r_src1, r_src2 }
+            | Instruction::And { r_dest, r_src1, r_src2 }
+            | Instruction::Or { r_dest, r_src1, r_src2 }
+            | Instruction::Xor { r_dest, r_src1, r_src2 }
+            | Instruction::Sub { r_dest, r_src1, r_src2 }
+            | Instruction::Mul { r_dest, r_src1, r_src2 }
+            | Instruction::CmpEq { r_dest, r_src1, r_src2 }
+            | Instruction::CmpGt { r_dest, r_src1, r_src2 }
+            | Instruction::CmpLt { r_dest, r_src1, r_src2 }
+            | Instruction::CmpLtS { r_dest, r_src1, r_src2 }
+            | Instruction::SubS { r_dest, r_src1, r_src2 }
+            | Instruction::FAdd { r_dest, r_src1, r_src2 }
+            | Instruction::FMul { r_dest, r_src1, r_src2 }
+            | Instruction::FCmp { r_dest, r_src1, r_src2 } => vec![r_dest, r_src1, r_src2],
+            Instruction::FPhaseShift { theta_reg, .. } => vec![theta_reg],
+            Instruction::QuantumOpDyn { angle_register, .. } => vec![angle_register],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the instruction with every QDU it acts on or reads replaced by `f(qdu)`.
+    pub(crate) fn map_qdus(&self, f: impl Fn(QduId) -> QduId) -> Instruction {
+        match self {
+            Instruction::QuantumOp(op) => Instruction::QuantumOp(op.map_qdus(f)),
+            Instruction::QuantumOpIf { register, op } => Instruction::QuantumOpIf {
+                register: register.clone(),
+                op: op.map_qdus(f),
+            },
+            Instruction::QuantumOpDyn { op_template, angle_register } => Instruction::QuantumOpDyn {
+                op_template: op_template.map_qdus(f),
+                angle_register: angle_register.clone(),
+            },
+            Instruction::Stabilize { targets } => Instruction::Stabilize {
+                targets: targets.iter().map(|&qdu| f(qdu)).collect(),
+            },
+            Instruction::ResetQdu { targets } => Instruction::ResetQdu {
+                targets: targets.iter().map(|&qdu| f(qdu)).collect(),
+            },
+            Instruction::Record { qdu, register } => Instruction::Record {
+                qdu: f(*qdu),
+                register: register.clone(),
+            },
+            Instruction::StabilizeInto { targets } => Instruction::StabilizeInto {
+                targets: targets
+                    .iter()
+                    .map(|(qdu, register)| (f(*qdu), register.clone()))
+                    .collect(),
+            },
+            Instruction::FPhaseShift { target, theta_reg } => Instruction::FPhaseShift {
+                target: f(*target),
+                theta_reg: theta_reg.clone(),
+            },
+            other => other.clone(),
+        }
+    }
 }
 
 // --- Program Structure ---
 
 /// Represents a complete program for the ONQ-VM.
 /// Contains instructions and resolved label locations.
-#[derive(Debug, Clone)] // PartialEq might be complex due to HashMap order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ProgramData", into = "ProgramData")
+)]
 pub struct Program {
     /// Ordered sequence of instructions.
     pub(crate) instructions: Vec<Instruction>,
     /// Map from label name to instruction index (program counter position).
     pub(crate) label_map: HashMap<String, usize>,
+    /// Labels other programs may jump to or call once linked.
+    pub(crate) exports: BTreeSet<String>,
+    /// Labels this program targets that another program defines and exports.
+    pub(crate) imports: BTreeSet<String>,
 }
 
 impl Program {
@@ -214,6 +808,8 @@ impl Program {
         Program {
             instructions: Vec::new(),
             label_map: HashMap::new(),
+            exports: BTreeSet::new(),
+            imports: BTreeSet::new(),
         }
     }
 
@@ -227,10 +823,192 @@ impl Program {
         self.label_map.get(label).copied()
     }
 
+    /// Parses a program written in the `onq-asm` text format. See [`asm`](super::asm)
+    /// for the syntax.
+    ///
+    /// # Errors
+    /// Returns `OnqError::InvalidOperation` naming the line of the first malformed
+    /// instruction, a label defined twice, or a jump to an undefined label.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::Program;
+    /// let program = Program::parse("
+    ///     load_immediate n, 3
+    /// loop:
+    ///     addi n, n, 18446744073709551615 ; n - 1
+    ///     branch_if_not_zero n, loop
+    ///     halt
+    /// ").unwrap();
+    /// assert_eq!(program.instruction_count(), 4);
+    ///
+    /// let err = Program::parse("jump nowhere").unwrap_err();
+    /// assert!(err.to_string().contains("nowhere"));
+    /// ```
+    pub fn parse(source: &str) -> Result<Program, OnqError> {
+        super::asm::parse_asm(source)
+    }
+
+    /// Writes the program in the `onq-asm` text format, which [`Program::parse`] reads
+    /// back into an equal program, so compiled programs can be stored, diffed and
+    /// processed by external tools. See [`asm::write_asm`](super::asm::write_asm).
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Program, QduId};
+    /// let circuit = CircuitBuilder::new().h(QduId(0)).stabilize(&[QduId(0)]).build();
+    /// let program = Program::from_circuit(&circuit);
+    ///
+    /// let text = program.to_asm();
+    /// assert!(text.contains("stabilize [q0]"));
+    /// assert_eq!(Program::parse(&text).unwrap(), program);
+    /// ```
+    pub fn to_asm(&self) -> String {
+        super::asm::write_asm(self)
+    }
+
+    /// Checks the program for mistakes that would otherwise surface one at a time at
+    /// runtime, or not at all, and reports all of them:
+    ///
+    /// * reads of registers no instruction writes;
+    /// * `Record`s whose QDU the last `Stabilize` before them did not cover on every path;
+    /// * labels marking instructions no path from the start reaches;
+    /// * quantum operations that use one QDU twice, e.g. as both control and target.
+    ///
+    /// Registers supplied with [`OnqVm::run_with_inputs`](super::OnqVm::run_with_inputs)
+    /// are reported as never written; callers providing inputs can skip those issues.
+    ///
+    /// # Errors
+    /// Returns every [`VerificationIssue`] found, sorted by program counter.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::Program;
+    /// # use onq::vm::VerificationIssue;
+    /// let program = Program::parse("
+    ///     quantum_op controlled_interaction(q0, q0, QualityFlip)
+    ///     record q0, m
+    ///     print \"{}\", [n]
+    ///     halt
+    /// ").unwrap();
+    ///
+    /// let issues = program.verify().unwrap_err();
+    /// assert_eq!(issues.len(), 3);
+    /// assert_eq!(issues[0], VerificationIssue::AliasedQdu { pc: 0, qdu: onq::QduId(0) });
+    /// assert_eq!(issues[2].to_string(), "pc 2: register 'n' is read but never written");
+    /// ```
+    pub fn verify(&self) -> Result<(), Vec<VerificationIssue>> {
+        let issues = super::verify::verify(self);
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Lowers a circuit into an equivalent VM program.
+    ///
+    /// Every operation becomes an `Instruction::QuantumOp`, except `Stabilize`, which
+    /// becomes an `Instruction::Stabilize` followed by one `Record` per target into the
+    /// register named by [`Program::register_for`]. A QDU stabilized more than once keeps
+    /// its latest outcome, as in a `SimulationResult`; classical bits named with
+    /// `stabilize_into` are recorded into registers of the same name. Idle QDUs of the
+    /// circuit get an `Identity` pattern so the VM lays out the same QDUs as the
+    /// `Simulator`. The program ends with `Halt`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::{CircuitBuilder, Program, QduId};
+    /// # use onq::vm::OnqVm;
+    /// let circuit = CircuitBuilder::new().x(QduId(0)).stabilize(&[QduId(0)]).build();
+    /// let program = Program::from_circuit(&circuit);
+    ///
+    /// let mut vm = OnqVm::new();
+    /// vm.run(&program).unwrap();
+    /// assert_eq!(vm.get_classical_register(&Program::register_for(QduId(0))), 1);
+    /// ```
+    pub fn from_circuit(circuit: &Circuit) -> Program {
+        let mut instructions = Vec::with_capacity(circuit.len() + 1);
+
+        let mut idle: Vec<QduId> = circuit
+            .qdus()
+            .iter()
+            .filter(|qdu| {
+                !circuit
+                    .operations()
+                    .iter()
+                    .any(|op| op.involved_qdus().contains(qdu))
+            })
+            .copied()
+            .collect();
+        idle.sort();
+        instructions.extend(idle.into_iter().map(|target| {
+            Instruction::QuantumOp(Operation::InteractionPattern {
+                target,
+                pattern_id: "Identity".to_string(),
+            })
+        }));
+
+        for (index, op) in circuit.operations().iter().enumerate() {
+            match op {
+                Operation::Stabilize { targets } => {
+                    instructions.push(Instruction::Stabilize {
+                        targets: targets.clone(),
+                    });
+                    instructions.extend(targets.iter().map(|qdu| Instruction::Record {
+                        qdu: *qdu,
+                        register: Program::register_for(*qdu),
+                    }));
+                    // Classical bits of the circuit become registers of the same name
+                    instructions.extend(circuit.bits_at(index).map(|(bit, qdu)| {
+                        Instruction::Record {
+                            qdu,
+                            register: bit.to_string(),
+                        }
+                    }));
+                }
+                op => instructions.push(Instruction::QuantumOp(op.clone())),
+            }
+        }
+        instructions.push(Instruction::Halt);
+
+        Program {
+            instructions,
+            label_map: HashMap::new(),
+            exports: BTreeSet::new(),
+            imports: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the classical register [`Program::from_circuit`] records the
+    /// stabilization outcome of `qdu` into (`"q<id>"`).
+    pub fn register_for(qdu: QduId) -> String {
+        format!("q{}", qdu.0)
+    }
+
     /// Returns the total number of instructions.
     pub fn instruction_count(&self) -> usize {
         self.instructions.len()
     }
+
+    /// Returns a slice containing the ordered sequence of executable instructions.
+    /// Labels are not included; they are resolved into the label map during build.
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Returns the labels other programs may target once linked with
+    /// [`link`](super::link::link), declared with [`ProgramBuilder::export`].
+    pub fn exports(&self) -> &BTreeSet<String> {
+        &self.exports
+    }
+
+    /// Returns the labels this program targets that another program must export,
+    /// declared with [`ProgramBuilder::import`]. A program with imports only runs once
+    /// linked.
+    pub fn imports(&self) -> &BTreeSet<String> {
+        &self.imports
+    }
 }
 
 impl fmt::Display for Program {
@@ -251,6 +1029,61 @@ impl fmt::Display for Program {
     }
 }
 
+/// Serialized form of a [`Program`]: labels sorted for stable output, and the
+/// program re-validated as [`ProgramBuilder::build`] does on deserialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ProgramData {
+    instructions: Vec<Instruction>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, usize>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    exports: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    imports: BTreeSet<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Program> for ProgramData {
+    fn from(program: Program) -> Self {
+        Self {
+            instructions: program.instructions,
+            labels: program.label_map.into_iter().collect(),
+            exports: program.exports,
+            imports: program.imports,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ProgramData> for Program {
+    type Error = String;
+
+    fn try_from(data: ProgramData) -> Result<Self, String> {
+        if data
+            .instructions
+            .iter()
+            .any(|i| matches!(i, Instruction::Label(_)))
+        {
+            return Err(
+                "Serialized programs hold labels in 'labels', not as instructions".to_string(),
+            );
+        }
+        let len = data.instructions.len();
+        if let Some((label, pc)) = data.labels.iter().find(|(_, pc)| **pc > len) {
+            return Err(format!("Label '{}' lies outside the program (PC {})", label, pc));
+        }
+        ProgramBuilder {
+            instructions: data.instructions,
+            label_map: data.labels.into_iter().collect(),
+            exports: data.exports,
+            imports: data.imports,
+            ..ProgramBuilder::default()
+        }
+        .build()
+    }
+}
+
 
 // --- Program Builder ---
 
@@ -279,6 +1112,11 @@ pub struct ProgramBuilder {
     instructions: Vec<Instruction>,
     label_map: HashMap<String, usize>,
     pending_labels: HashMap<String, Vec<usize>>, // label -> list of instruction indices needing this label's PC
+    next_block: usize, // Numbers the labels generated by the structured control-flow helpers
+    exports: BTreeSet<String>, // Labels visible to other programs when linked
+    imports: BTreeSet<String>, // Labels defined by other programs when linked
+    macros: HashMap<String, InstructionMacro>, // Macros `expand` can add
+    errors: Vec<String>, // Failed expansions, reported by `build`
 }
 
 impl ProgramBuilder {
@@ -320,20 +1158,353 @@ impl ProgramBuilder {
          self
      }
 
+    /// Exports `label`, so other programs that import it can jump to or call it once
+    /// linked with [`link`](super::link::link). The label must be defined in this program.
+    pub fn export(mut self, label: &str) -> Self {
+        self.exports.insert(label.to_string());
+        self
+    }
+
+    /// Declares `label` as defined by another program, so this program can target it
+    /// without defining it. The program then only runs once linked with
+    /// [`link`](super::link::link) to a program exporting `label`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, ProgramBuilder};
+    /// let main = ProgramBuilder::new()
+    ///     .import("square")
+    ///     .pb_add(Instruction::Call("square".to_string()))
+    ///     .pb_add(Instruction::Halt)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(main.imports().contains("square"));
+    ///
+    /// assert!(ProgramBuilder::new().pb_add(Instruction::Call("square".to_string())).build().is_err());
+    /// ```
+    pub fn import(mut self, label: &str) -> Self {
+        self.imports.insert(label.to_string());
+        self
+    }
+
+    /// Defines `instruction_macro` for [`expand`](ProgramBuilder::expand), replacing any
+    /// macro of the same name. Blocks built by the structured control-flow helpers see
+    /// the macros defined before them.
+    pub fn define_macro(mut self, instruction_macro: InstructionMacro) -> Self {
+        self.macros
+            .insert(instruction_macro.name().to_string(), instruction_macro);
+        self
+    }
+
+    /// Adds the body of the macro `name` (see [`InstructionMacro`]), with `qdus` and
+    /// `registers` in place of its parameters.
+    ///
+    /// # Errors
+    /// [`build`](ProgramBuilder::build) fails if no macro `name` is defined or the
+    /// number of arguments differs from the number of parameters.
+    pub fn expand(mut self, name: &str, qdus: &[QduId], registers: &[&str]) -> Self {
+        let expansion = match self.macros.get(name) {
+            Some(instruction_macro) => {
+                instruction_macro.expand(self.next_block, qdus, registers)
+            }
+            None => Err(format!("Macro '{}' is not defined", name)),
+        };
+        self.next_block += 1;
+        match expansion {
+            Ok(instructions) => self.add_many(instructions),
+            Err(message) => {
+                self.errors.push(message);
+                self
+            }
+        }
+    }
+
+    /// Adds a loop running the instructions `body` adds for as long as `register` is
+    /// non-zero, testing it before every pass:
+    ///
+    /// ```text
+    /// while.N:     BranchIfZero register, while.N.end
+    ///              body...
+    ///              Jump while.N
+    /// while.N.end:
+    /// ```
+    ///
+    /// `body` receives an empty builder and may nest further loops and conditionals.
+    /// Generated labels are numbered `while.N`, `repeat.N`, `if.N.else` and so on, so
+    /// labels of that form should not be defined by hand.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::LoadImmediate { register: "k".to_string(), value: 3 })
+    ///     .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+    ///     .while_reg_nonzero("k", |body| {
+    ///         body.pb_add(Instruction::Addi { r_dest: "sum".to_string(), r_src: "sum".to_string(), value: 10 })
+    ///             .pb_add(Instruction::Sub { r_dest: "k".to_string(), r_src1: "k".to_string(), r_src2: "one".to_string() })
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new();
+    /// assert_eq!(vm.run(&program).unwrap().register("sum"), 30);
+    /// ```
+    pub fn while_reg_nonzero<F>(mut self, register: &str, body: F) -> Self
+    where
+        F: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        let start = format!("while.{}", self.next_block);
+        let end = format!("{}.end", start);
+        self.next_block += 1;
+        let body = self.block(body);
+
+        self.pb_add(Instruction::Label(start.clone()))
+            .pb_add(Instruction::BranchIfZero {
+                register: register.to_string(),
+                label: end.clone(),
+            })
+            .append(body)
+            .pb_add(Instruction::Jump(start))
+            .pb_add(Instruction::Label(end))
+    }
+
+    /// Adds a loop running the instructions `body` adds as many times as `register`
+    /// holds, counting it down to 0 with a `Repeat`:
+    ///
+    /// ```text
+    ///               BranchIfZero register, repeat.N.end
+    /// repeat.N:     body...
+    ///               Repeat register, repeat.N
+    /// repeat.N.end:
+    /// ```
+    ///
+    /// Each pass costs one instruction beyond `body`, against three for the equivalent
+    /// [`while_reg_nonzero`](ProgramBuilder::while_reg_nonzero) loop. The count is read
+    /// as the loop runs, so `body` should leave `register` alone. See
+    /// [`while_reg_nonzero`](ProgramBuilder::while_reg_nonzero) for how `body` is built
+    /// and labels named.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// let program = ProgramBuilder::new()
+    ///     .pb_add(Instruction::LoadImmediate { register: "k".to_string(), value: 4 })
+    ///     .repeat("k", |body| {
+    ///         body.pb_add(Instruction::Addi { r_dest: "sum".to_string(), r_src: "sum".to_string(), value: 10 })
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut vm = OnqVm::new();
+    /// assert_eq!(vm.run(&program).unwrap().register("sum"), 40);
+    /// assert_eq!(vm.stats().total_instructions(), 1 + 1 + 4 * 2);
+    /// ```
+    pub fn repeat<F>(mut self, register: &str, body: F) -> Self
+    where
+        F: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        let start = format!("repeat.{}", self.next_block);
+        let end = format!("{}.end", start);
+        self.next_block += 1;
+        let body = self.block(body);
+
+        self.pb_add(Instruction::BranchIfZero {
+            register: register.to_string(),
+            label: end.clone(),
+        })
+        .pb_add(Instruction::Label(start.clone()))
+        .append(body)
+        .pb_add(Instruction::Repeat {
+            register: register.to_string(),
+            label: start,
+        })
+        .pb_add(Instruction::Label(end))
+    }
+
+    /// Adds a conditional running the instructions `then_branch` adds if `register` is
+    /// zero and those `else_branch` adds otherwise:
+    ///
+    /// ```text
+    ///              BranchIfNotZero register, if.N.else
+    ///              then...
+    ///              Jump if.N.end
+    /// if.N.else:   else...
+    /// if.N.end:
+    /// ```
+    ///
+    /// An empty branch costs nothing: with an empty `else_branch` only the
+    /// `BranchIfNotZero` past `then...` remains. See
+    /// [`while_reg_nonzero`](ProgramBuilder::while_reg_nonzero) for how branches are
+    /// built and labels named.
+    ///
+    /// # Examples
+    /// ```
+    /// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+    /// let set = |value| move |branch: ProgramBuilder| {
+    ///     branch.pb_add(Instruction::LoadImmediate { register: "r".to_string(), value })
+    /// };
+    /// let program = ProgramBuilder::new()
+    ///     .if_zero("m0", set(10), set(20))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(program.instruction_count(), 4);
+    ///
+    /// let mut vm = OnqVm::new();
+    /// assert_eq!(vm.run(&program).unwrap().register("r"), 10);
+    /// ```
+    pub fn if_zero<T, E>(self, register: &str, then_branch: T, else_branch: E) -> Self
+    where
+        T: FnOnce(ProgramBuilder) -> ProgramBuilder,
+        E: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        self.conditional(register, true, then_branch, else_branch)
+    }
+
+    /// Adds a conditional running the instructions `then_branch` adds if `register` is
+    /// non-zero and those `else_branch` adds otherwise. The mirror image of
+    /// [`if_zero`](ProgramBuilder::if_zero).
+    pub fn if_nonzero<T, E>(self, register: &str, then_branch: T, else_branch: E) -> Self
+    where
+        T: FnOnce(ProgramBuilder) -> ProgramBuilder,
+        E: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        self.conditional(register, false, then_branch, else_branch)
+    }
+
+    /// Lays out an `if_zero` (`on_zero`) or `if_nonzero` conditional.
+    fn conditional<T, E>(
+        mut self,
+        register: &str,
+        on_zero: bool,
+        then_branch: T,
+        else_branch: E,
+    ) -> Self
+    where
+        T: FnOnce(ProgramBuilder) -> ProgramBuilder,
+        E: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        let else_label = format!("if.{}.else", self.next_block);
+        let end_label = format!("if.{}.end", self.next_block);
+        self.next_block += 1;
+        let then_block = self.block(then_branch);
+        let else_block = self.block(else_branch);
+
+        // Branches past `then` when its condition does not hold
+        let skip = |label: String| {
+            let register = register.to_string();
+            if on_zero {
+                Instruction::BranchIfNotZero { register, label }
+            } else {
+                Instruction::BranchIfZero { register, label }
+            }
+        };
+        if else_block.is_empty() {
+            self.pb_add(skip(end_label.clone()))
+                .append(then_block)
+                .pb_add(Instruction::Label(end_label))
+        } else {
+            self.pb_add(skip(else_label.clone()))
+                .append(then_block)
+                .pb_add(Instruction::Jump(end_label.clone()))
+                .pb_add(Instruction::Label(else_label))
+                .append(else_block)
+                .pb_add(Instruction::Label(end_label))
+        }
+    }
+
+    /// Builds a nested block with `build`, continuing this builder's label numbering.
+    fn block<F>(&mut self, build: F) -> ProgramBuilder
+    where
+        F: FnOnce(ProgramBuilder) -> ProgramBuilder,
+    {
+        let block = build(ProgramBuilder {
+            next_block: self.next_block,
+            macros: self.macros.clone(),
+            ..ProgramBuilder::default()
+        });
+        self.next_block = block.next_block;
+        block
+    }
+
+    /// Returns `true` if no instruction or label has been added.
+    fn is_empty(&self) -> bool {
+        self.instructions.is_empty() && self.label_map.is_empty()
+    }
+
+    /// Adds the instructions and labels of a nested block.
+    fn append(mut self, block: ProgramBuilder) -> Self {
+        let mut labels: Vec<(usize, String)> = block
+            .label_map
+            .into_iter()
+            .map(|(label, pc)| (pc, label))
+            .collect();
+        labels.sort();
+        let mut labels = labels.into_iter().peekable();
+        for (pc, instruction) in block.instructions.into_iter().enumerate() {
+            while let Some((_, label)) = labels.next_if(|(at, _)| *at == pc) {
+                self = self.pb_add(Instruction::Label(label));
+            }
+            self = self.pb_add(instruction);
+        }
+        for (_, label) in labels {
+            self = self.pb_add(Instruction::Label(label));
+        }
+        self.exports.extend(block.exports);
+        self.imports.extend(block.imports);
+        self.errors.extend(block.errors);
+        self
+    }
+
     /// Builds the final `Program`, resolving all labels.
-    /// Returns an error if any jump targets are undefined.
+    /// Returns an error if any jump targets are undefined and not imported, an exported
+    /// label is undefined, an imported label is defined, or a macro expansion failed.
     pub fn build(self) -> Result<Program, String> {
+        if let Some(message) = self.errors.first() {
+            return Err(message.clone());
+        }
+        if let Some(label) = self.exports.iter().find(|l| !self.label_map.contains_key(*l)) {
+            return Err(format!("Exported label '{}' is not defined", label));
+        }
+        if let Some(label) = self.imports.iter().find(|l| self.label_map.contains_key(*l)) {
+            return Err(format!("Imported label '{}' is also defined", label));
+        }
         // Validation: Ensure all jump/branch targets exist in label_map
         let mut undefined_labels = Vec::new();
         for instruction in &self.instructions {
+            if let Instruction::QuantumOpDyn { op_template, .. } = instruction
+                && op_template.with_angle(0.0).is_none()
+            {
+                return Err(format!(
+                    "QuantumOpDyn template has no angle parameter: {:?}",
+                    op_template
+                ));
+            }
+            if let Instruction::Print { format, registers } = instruction
+                && format.matches("{}").count() != registers.len()
+            {
+                return Err(format!(
+                    "Print format {:?} has {} placeholders for {} registers",
+                    format,
+                    format.matches("{}").count(),
+                    registers.len()
+                ));
+            }
             match instruction {
-                Instruction::Jump(label) | Instruction::BranchIfZero { label, .. } => {
-                    if !self.label_map.contains_key(label) {
-                        // Check if already recorded as undefined to avoid duplicates
-                        if !undefined_labels.contains(label) {
-                            undefined_labels.push(label.clone());
-                        }
-                    }
+                // Check if already recorded as undefined to avoid duplicates
+                Instruction::Jump(label)
+                | Instruction::Call(label)
+                | Instruction::BranchIfZero { label, .. }
+                | Instruction::BranchIfNotZero { label, .. }
+                | Instruction::BranchIfEq { label, .. }
+                | Instruction::BranchIfNe { label, .. }
+                | Instruction::BranchIfLt { label, .. }
+                | Instruction::BranchIfGe { label, .. }
+                | Instruction::Repeat { label, .. }
+                    if !self.label_map.contains_key(label)
+                        && !self.imports.contains(label)
+                        && !undefined_labels.contains(label) =>
+                {
+                    undefined_labels.push(label.clone());
                 }
                 _ => {} // Other instruction types are fine
             }
@@ -345,6 +1516,8 @@ impl ProgramBuilder {
             Ok(Program {
                 instructions: self.instructions,
                 label_map: self.label_map,
+                exports: self.exports,
+                imports: self.imports,
             })
         }
     }
@@ -0,0 +1,114 @@
+// src/vm/result.rs
+
+//! Defines the summary of a VM run returned by [`OnqVm::run`](super::OnqVm::run).
+
+use super::debug::RegisterChange;
+use crate::core::QduId;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Why a VM run stopped, or paused.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HaltReason {
+    /// A `Halt` instruction was executed.
+    Halted,
+    /// The program counter ran past the last instruction.
+    EndOfProgram,
+    /// The run paused before the instruction at `pc`, which has a breakpoint.
+    Breakpoint {
+        /// Index of the instruction the run resumes at.
+        pc: usize,
+    },
+    /// The run paused after the single instruction [`OnqVm::step`](super::OnqVm::step)
+    /// executed.
+    Step,
+    /// The run paused after an instruction changed a register watched with
+    /// [`OnqVm::set_watchpoint`](super::OnqVm::set_watchpoint).
+    Watchpoint(RegisterChange),
+}
+
+impl HaltReason {
+    /// Returns `true` if the run paused and can be resumed with
+    /// [`OnqVm::step`](super::OnqVm::step) or
+    /// [`OnqVm::continue_run`](super::OnqVm::continue_run).
+    pub fn is_paused(&self) -> bool {
+        matches!(
+            self,
+            HaltReason::Breakpoint { .. } | HaltReason::Step | HaltReason::Watchpoint(_)
+        )
+    }
+}
+
+/// The outcomes resolved by one executed `Stabilize` instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StabilizationEvent {
+    /// Index of the `Stabilize` instruction in the program.
+    pub pc: usize,
+    /// The resolved quality of each stabilized QDU.
+    pub outcomes: BTreeMap<QduId, u64>,
+}
+
+/// The summary of a VM run, as of when it halted or paused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VmRunResult {
+    /// The classical registers when the run stopped.
+    classical_memory: HashMap<String, u64>,
+    /// Every executed `Stabilize`, in execution order.
+    stabilizations: Vec<StabilizationEvent>,
+    /// Why the run stopped.
+    halt_reason: HaltReason,
+    /// Number of instructions executed.
+    instruction_count: u64,
+    /// Wall-clock time the run took.
+    elapsed: Duration,
+}
+
+impl VmRunResult {
+    /// Creates a run summary. (Internal visibility)
+    pub(crate) fn new(
+        classical_memory: HashMap<String, u64>,
+        stabilizations: Vec<StabilizationEvent>,
+        halt_reason: HaltReason,
+        instruction_count: u64,
+        elapsed: Duration,
+    ) -> Self {
+        Self {
+            classical_memory,
+            stabilizations,
+            halt_reason,
+            instruction_count,
+            elapsed,
+        }
+    }
+
+    /// Returns the value of a classical register when the run stopped, treating a
+    /// non-existent one as 0.
+    pub fn register(&self, name: &str) -> u64 {
+        self.classical_memory.get(name).copied().unwrap_or(0)
+    }
+
+    /// Returns the classical registers when the run stopped.
+    pub fn classical_memory(&self) -> &HashMap<String, u64> {
+        &self.classical_memory
+    }
+
+    /// Returns every executed `Stabilize` with its outcomes, in execution order.
+    pub fn stabilizations(&self) -> &[StabilizationEvent] {
+        &self.stabilizations
+    }
+
+    /// Returns why the run stopped, or paused.
+    pub fn halt_reason(&self) -> HaltReason {
+        self.halt_reason.clone()
+    }
+
+    /// Returns the number of instructions executed, counting every pass through a loop.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Returns the wall-clock time the run took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
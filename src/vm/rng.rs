@@ -0,0 +1,117 @@
+// src/vm/rng.rs
+
+//! The random number generator behind `Rand`, with a state that checkpoints can hold.
+
+use rand::{SeedableRng, TryRng};
+use std::convert::Infallible;
+
+/// A xoshiro256++ generator drawing the same sequence as
+/// [`rand::rngs::Xoshiro256PlusPlus`], whose four state words can be read and set so a
+/// checkpoint captures the generator exactly without drawing from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VmRng {
+    s: [u64; 4],
+}
+
+impl VmRng {
+    /// Returns the four state words.
+    pub(crate) fn state(&self) -> [u64; 4] {
+        self.s
+    }
+
+    /// Builds a generator continuing from `state`, as returned by [`VmRng::state`].
+    /// An all-zero state, from which xoshiro never leaves, is replaced like in
+    /// [`SeedableRng::from_seed`].
+    pub(crate) fn from_state(state: [u64; 4]) -> Self {
+        if state == [0; 4] {
+            return Self::seed_from_u64(0);
+        }
+        VmRng { s: state }
+    }
+}
+
+impl SeedableRng for VmRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Self {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().expect("chunks of 8 bytes"));
+        }
+        Self::from_state(state)
+    }
+
+    /// Expands `state` with SplitMix64, as [`rand::rngs::Xoshiro256PlusPlus`] does.
+    fn seed_from_u64(mut state: u64) -> Self {
+        const PHI: u64 = 0x9e3779b97f4a7c15;
+        let mut s = [0; 4];
+        for word in s.iter_mut() {
+            state = state.wrapping_add(PHI);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            *word = z ^ (z >> 31);
+        }
+        VmRng { s }
+    }
+}
+
+impl TryRng for VmRng {
+    type Error = Infallible;
+
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        // The upper bits are the stronger ones
+        self.try_next_u64().map(|value| (value >> 32) as u32)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+        let result = self.s[0]
+            .wrapping_add(self.s[3])
+            .rotate_left(23)
+            .wrapping_add(self.s[0]);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+        Ok(result)
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Infallible> {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.try_next_u64()?.to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::Xoshiro256PlusPlus;
+    use rand::{Rng, RngExt};
+
+    #[test]
+    fn test_matches_rand_xoshiro() {
+        for seed in [0, 7, u64::MAX] {
+            let mut ours = VmRng::seed_from_u64(seed);
+            let mut theirs = Xoshiro256PlusPlus::seed_from_u64(seed);
+            for _ in 0..64 {
+                assert_eq!(ours.next_u64(), theirs.next_u64());
+                assert_eq!(ours.random_range(0..6u64), theirs.random_range(0..6u64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let mut rng = VmRng::seed_from_u64(3);
+        rng.next_u64();
+        let mut resumed = VmRng::from_state(rng.state());
+        assert_eq!(resumed.next_u64(), rng.next_u64());
+        assert_ne!(VmRng::from_state([0; 4]).state(), [0; 4]);
+    }
+}
@@ -0,0 +1,132 @@
+// src/vm/shots.rs
+
+//! Defines the aggregated results of running a program for many shots with
+//! [`OnqVm::run_shots`](super::OnqVm::run_shots).
+
+use super::result::VmRunResult;
+use crate::simulation::{JointOutcome, SeedMode};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Aggregated results of running a VM program for multiple shots: how often each
+/// classical register ended with each value, and how often each joint stabilization
+/// outcome occurred.
+///
+/// A register a shot never wrote counts as holding 0 in that shot, as in
+/// [`VmRunResult::register`], so the counts of every register sum to the number of shots.
+#[derive(Debug, Clone)]
+pub struct VmShotResults {
+    /// Number of shots executed.
+    shots: usize,
+    /// How the stabilization seed varied across shots.
+    seed_mode: SeedMode,
+    /// Maps each register written in some shot to its final values and their counts.
+    register_counts: BTreeMap<String, BTreeMap<u64, usize>>,
+    /// Maps each joint stabilization outcome to the number of shots that produced it.
+    outcome_counts: HashMap<JointOutcome, usize>,
+}
+
+impl VmShotResults {
+    /// Creates an empty result set for `shots` shots. (Internal visibility)
+    pub(crate) fn new(shots: usize, seed_mode: SeedMode) -> Self {
+        Self {
+            shots,
+            seed_mode,
+            register_counts: BTreeMap::new(),
+            outcome_counts: HashMap::new(),
+        }
+    }
+
+    /// Records the final registers and the stabilization outcomes of one shot. Each
+    /// QDU contributes the outcome of the last `Stabilize` that resolved it.
+    pub(crate) fn record_shot(&mut self, result: &VmRunResult) {
+        for (register, value) in result.classical_memory() {
+            *self
+                .register_counts
+                .entry(register.clone())
+                .or_default()
+                .entry(*value)
+                .or_insert(0) += 1;
+        }
+        let outcome: JointOutcome = result
+            .stabilizations()
+            .iter()
+            .flat_map(|event| event.outcomes.iter().map(|(qdu, value)| (*qdu, *value)))
+            .collect();
+        *self.outcome_counts.entry(outcome).or_insert(0) += 1;
+    }
+
+    /// Returns the number of shots executed.
+    pub fn shots(&self) -> usize {
+        self.shots
+    }
+
+    /// Returns how the stabilization seed varied across shots.
+    pub fn seed_mode(&self) -> SeedMode {
+        self.seed_mode
+    }
+
+    /// Returns the names of the registers written in any shot, in order.
+    pub fn registers(&self) -> impl Iterator<Item = &str> {
+        self.register_counts.keys().map(String::as_str)
+    }
+
+    /// Returns the distribution of the final values of register `name`: each value
+    /// mapped to the number of shots that ended with it.
+    pub fn register_counts(&self, name: &str) -> BTreeMap<u64, usize> {
+        let mut counts = self.register_counts.get(name).cloned().unwrap_or_default();
+        let written: usize = counts.values().sum();
+        if written < self.shots {
+            *counts.entry(0).or_insert(0) += self.shots - written;
+        }
+        counts
+    }
+
+    /// Returns how many shots ended with register `name` holding `value`.
+    pub fn register_count(&self, name: &str, value: u64) -> usize {
+        self.register_counts(name).get(&value).copied().unwrap_or(0)
+    }
+
+    /// Returns the fraction of shots that ended with register `name` holding `value`,
+    /// or 0.0 if no shots ran.
+    pub fn register_probability(&self, name: &str, value: u64) -> f64 {
+        if self.shots == 0 {
+            return 0.0;
+        }
+        self.register_count(name, value) as f64 / self.shots as f64
+    }
+
+    /// Returns the histogram of joint stabilization outcomes.
+    pub fn outcome_counts(&self) -> &HashMap<JointOutcome, usize> {
+        &self.outcome_counts
+    }
+
+    /// Returns how many shots produced the joint stabilization `outcome`.
+    pub fn outcome_count(&self, outcome: &JointOutcome) -> usize {
+        self.outcome_counts.get(outcome).copied().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for VmShotResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "VM Shot Results ({} shots):", self.shots)?;
+        for register in self.registers() {
+            let counts: Vec<String> = self
+                .register_counts(register)
+                .iter()
+                .map(|(value, count)| format!("{}: {}", value, count))
+                .collect();
+            writeln!(f, "  {} = {{{}}}", register, counts.join(", "))?;
+        }
+        let mut sorted: Vec<_> = self.outcome_counts.iter().collect();
+        sorted.sort();
+        for (outcome, count) in sorted {
+            let label: Vec<String> = outcome
+                .iter()
+                .map(|(qdu, value)| format!("{}={}", qdu, value))
+                .collect();
+            writeln!(f, "  [{}]: {}", label.join(", "), count)?;
+        }
+        Ok(())
+    }
+}
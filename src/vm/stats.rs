@@ -0,0 +1,118 @@
+// src/vm/stats.rs
+
+//! Defines the execution counts an [`OnqVm`](super::OnqVm) collects during a run.
+
+use super::program::Instruction;
+use crate::operations::Operation;
+use std::collections::BTreeMap;
+
+/// How often each kind of instruction and quantum operation executed during a run,
+/// read with [`OnqVm::stats`](super::OnqVm::stats). Instructions inside loops count
+/// once per pass, so two formulations of a program can be compared by cost.
+///
+/// # Examples
+/// ```
+/// # use onq::vm::{Instruction, OnqVm, ProgramBuilder};
+/// # use onq::{Operation, QduId};
+/// let h = |q| Instruction::QuantumOp(Operation::InteractionPattern {
+///     target: QduId(q),
+///     pattern_id: "Superposition".to_string(),
+/// });
+/// let program = ProgramBuilder::new()
+///     .pb_add(h(0))
+///     .pb_add(h(1))
+///     .pb_add(Instruction::Stabilize { targets: vec![QduId(0), QduId(1)] })
+///     .build()
+///     .unwrap();
+///
+/// let mut vm = OnqVm::new();
+/// vm.run(&program).unwrap();
+/// let stats = vm.stats();
+/// assert_eq!(stats.instruction_count("QuantumOp"), 2);
+/// assert_eq!(stats.pattern_count("Superposition"), 2);
+/// assert_eq!(stats.total_instructions(), 3);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionStats {
+    /// Instruction kind ([`Instruction::kind`]) -> number executed.
+    instructions: BTreeMap<&'static str, u64>,
+    /// Quantum operation kind ([`Operation::kind`]) -> number applied.
+    operations: BTreeMap<&'static str, u64>,
+    /// Pattern id of a pattern operation -> number applied.
+    patterns: BTreeMap<String, u64>,
+}
+
+impl ExecutionStats {
+    /// Creates empty statistics. (Internal visibility)
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an executed instruction, and the quantum operation it applies, if any.
+    /// The operation of a `QuantumOpIf` is recorded with
+    /// [`record_operation`](ExecutionStats::record_operation) once it is applied.
+    /// (Internal visibility)
+    pub(crate) fn record(&mut self, instruction: &Instruction) {
+        *self.instructions.entry(instruction.kind()).or_default() += 1;
+        match instruction {
+            Instruction::QuantumOp(op) => self.record_operation(op),
+            Instruction::QuantumOpDyn { op_template, .. } => self.record_operation(op_template),
+            Instruction::FPhaseShift { .. } => {
+                *self.operations.entry("PhaseShift").or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Records an applied quantum operation. (Internal visibility)
+    pub(crate) fn record_operation(&mut self, op: &Operation) {
+        *self.operations.entry(op.kind()).or_default() += 1;
+        if let Operation::InteractionPattern { pattern_id, .. }
+        | Operation::BroadcastPattern { pattern_id, .. }
+        | Operation::ControlledInteraction { pattern_id, .. } = op
+        {
+            *self.patterns.entry(pattern_id.clone()).or_default() += 1;
+        }
+    }
+
+    /// Returns how many instructions of kind `kind` (e.g. `"BranchIfZero"`) executed.
+    pub fn instruction_count(&self, kind: &str) -> u64 {
+        self.instructions.get(kind).copied().unwrap_or(0)
+    }
+
+    /// Returns how many quantum operations of kind `kind` (e.g. `"PhaseShift"`) were
+    /// applied, by `QuantumOp`, `QuantumOpIf`, `QuantumOpDyn` or `FPhaseShift` instructions.
+    pub fn operation_count(&self, kind: &str) -> u64 {
+        self.operations.get(kind).copied().unwrap_or(0)
+    }
+
+    /// Returns how many pattern operations with pattern id `pattern_id` were applied.
+    pub fn pattern_count(&self, pattern_id: &str) -> u64 {
+        self.patterns.get(pattern_id).copied().unwrap_or(0)
+    }
+
+    /// Returns the number executed of every instruction kind that executed, by kind.
+    pub fn instructions(&self) -> &BTreeMap<&'static str, u64> {
+        &self.instructions
+    }
+
+    /// Returns the number applied of every quantum operation kind that was applied.
+    pub fn operations(&self) -> &BTreeMap<&'static str, u64> {
+        &self.operations
+    }
+
+    /// Returns the number applied of every pattern id that was applied.
+    pub fn patterns(&self) -> &BTreeMap<String, u64> {
+        &self.patterns
+    }
+
+    /// Returns the total number of instructions executed.
+    pub fn total_instructions(&self) -> u64 {
+        self.instructions.values().sum()
+    }
+
+    /// Returns the total number of quantum operations applied.
+    pub fn total_operations(&self) -> u64 {
+        self.operations.values().sum()
+    }
+}
@@ -0,0 +1,138 @@
+// src/vm/trace.rs
+
+//! Defines the structured trace events an [`OnqVm`](super::OnqVm) emits while running.
+
+use super::program::Instruction;
+use super::result::HaltReason;
+use crate::core::QduId;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// How much of a run [`OnqVm::with_trace`](super::OnqVm::with_trace) reports. Each
+/// level includes the events of the levels below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TraceLevel {
+    /// No events (the default).
+    #[default]
+    Off,
+    /// The start and end of a run, every stabilization and every control-flow
+    /// instruction.
+    Events,
+    /// Additionally every instruction, before it executes.
+    Instructions,
+}
+
+/// One event of a traced VM run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    /// A run started.
+    RunStarted {
+        /// Number of QDUs the run simulates.
+        qdus: usize,
+    },
+    /// An instruction is about to execute.
+    Instruction {
+        /// Index of the instruction in the program.
+        pc: usize,
+        /// The instruction.
+        instruction: Instruction,
+    },
+    /// A `Stabilize` resolved its targets.
+    Stabilized {
+        /// Index of the `Stabilize` in the program.
+        pc: usize,
+        /// The resolved quality of each stabilized QDU.
+        outcomes: BTreeMap<QduId, u64>,
+    },
+    /// A jump, branch, call or return executed.
+    Branch {
+        /// Index of the control-flow instruction in the program.
+        pc: usize,
+        /// Index of the instruction execution continues at.
+        target: usize,
+        /// `false` for a branch that fell through to the next instruction.
+        taken: bool,
+    },
+    /// A run stopped.
+    RunFinished {
+        /// Why the run stopped.
+        reason: HaltReason,
+        /// Number of instructions executed.
+        instructions: u64,
+    },
+}
+
+impl TraceEvent {
+    /// Returns the lowest [`TraceLevel`] that reports this event.
+    pub fn level(&self) -> TraceLevel {
+        match self {
+            TraceEvent::Instruction { .. } => TraceLevel::Instructions,
+            _ => TraceLevel::Events,
+        }
+    }
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::RunStarted { qdus } => write!(f, "run started ({} QDUs)", qdus),
+            TraceEvent::Instruction { pc, instruction } => {
+                write!(f, "PC={:04} {:?}", pc, instruction)
+            }
+            TraceEvent::Stabilized { pc, outcomes } => {
+                write!(f, "PC={:04} stabilized", pc)?;
+                for (qdu, value) in outcomes {
+                    write!(f, " {}={}", qdu, value)?;
+                }
+                Ok(())
+            }
+            TraceEvent::Branch { pc, target, taken } => {
+                let verb = if *taken { "taken" } else { "not taken" };
+                write!(f, "PC={:04} branch {} -> PC={:04}", pc, verb, target)
+            }
+            TraceEvent::RunFinished {
+                reason,
+                instructions,
+            } => write!(
+                f,
+                "run finished: {:?} after {} instructions",
+                reason, instructions
+            ),
+        }
+    }
+}
+
+/// A trace callback together with the level it receives. (Internal visibility)
+#[derive(Clone)]
+pub(crate) struct Tracer {
+    level: TraceLevel,
+    sink: Arc<dyn Fn(&TraceEvent) + Send + Sync>,
+}
+
+impl Tracer {
+    pub(crate) fn new<F>(level: TraceLevel, sink: F) -> Self
+    where
+        F: Fn(&TraceEvent) + Send + Sync + 'static,
+    {
+        Self {
+            level,
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Sends the event built by `event` to the callback if `level` is reported.
+    pub(crate) fn emit(&self, level: TraceLevel, event: impl FnOnce() -> TraceEvent) {
+        if level <= self.level {
+            (self.sink)(&event());
+        }
+    }
+}
+
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("level", &self.level)
+            .finish_non_exhaustive()
+    }
+}
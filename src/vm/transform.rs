@@ -0,0 +1,254 @@
+// src/vm/transform.rs
+
+//! Program-to-program rewrites for the ONQ-VM.
+//!
+//! Transforms take a built [`Program`] and return an equivalent (or deliberately
+//! comparable) `Program`, reporting an `OnqError` when the rewrite cannot be
+//! performed without changing the program's meaning.
+
+use super::program::{Instruction, Program};
+use crate::core::{OnqError, QduId};
+use std::collections::HashMap;
+
+/// Rewrites mid-program stabilization into its deferred (measure-at-end) form.
+///
+/// Every `Stabilize` is removed from its original position and merged into a single
+/// `Stabilize` at the end of the program, followed by the original `Record`s (in order)
+/// and the final `Halt`, if any. This is only possible when nothing after a stabilization
+/// depends on its outcome or acts on the stabilized QDU again.
+///
+/// Classically-conditioned corrections (`BranchIfZero`/`BranchIfNotZero` on a recorded
+/// outcome, `QuantumOpIf`) cannot be deferred: the textbook rewrite turns them into
+/// controlled operations, but onq's `ControlledInteraction` bonds the two QDUs and applies
+/// its pattern to the target whatever the control's state, so it is not conditional on
+/// the outcome and the rewrite would change the program's results.
+///
+/// Comparing a program against its deferred form is a direct way to see where onq's
+/// mid-run stabilization semantics differ from deferred measurement.
+///
+/// # Errors
+/// Returns `OnqError::InvalidOperation` naming the offending program counter if the rewrite
+/// is impossible, e.g. when a stabilized QDU is acted upon again, a recorded register is
+/// read by classical instructions or conditions a correction, or the program contains
+/// other control flow (loops, jumps).
+///
+/// # Examples
+/// ```
+/// # use onq::{Instruction, Operation, ProgramBuilder, QduId};
+/// # use onq::vm::defer_stabilization;
+/// let flip = |target| {
+///     Instruction::QuantumOp(Operation::InteractionPattern {
+///         target,
+///         pattern_id: "QualityFlip".to_string(),
+///     })
+/// };
+/// let program = ProgramBuilder::new()
+///     .pb_add(flip(QduId(0)))
+///     .pb_add(Instruction::Stabilize { targets: vec![QduId(0)] })
+///     .pb_add(Instruction::Record { qdu: QduId(0), register: "m0".to_string() })
+///     .pb_add(flip(QduId(1)))
+///     .pb_add(Instruction::Halt)
+///     .build()
+///     .unwrap();
+///
+/// let deferred = defer_stabilization(&program).unwrap();
+/// assert_eq!(deferred.instructions()[1], flip(QduId(1)));
+/// assert_eq!(deferred.instructions()[2], Instruction::Stabilize { targets: vec![QduId(0)] });
+///
+/// // A correction conditioned on the outcome has no controlled equivalent
+/// let corrected = ProgramBuilder::new()
+///     .pb_add(Instruction::Stabilize { targets: vec![QduId(0)] })
+///     .pb_add(Instruction::Record { qdu: QduId(0), register: "m0".to_string() })
+///     .pb_add(Instruction::QuantumOpIf {
+///         register: "m0".to_string(),
+///         op: Operation::InteractionPattern { target: QduId(1), pattern_id: "QualityFlip".to_string() },
+///     })
+///     .build()
+///     .unwrap();
+/// assert!(defer_stabilization(&corrected).is_err());
+/// ```
+pub fn defer_stabilization(program: &Program) -> Result<Program, OnqError> {
+    let instructions = program.instructions();
+    let mut rewritten = Vec::with_capacity(instructions.len());
+    let mut deferred: Vec<QduId> = Vec::new();
+    let mut records: Vec<Instruction> = Vec::new();
+    // Register name -> QDU whose deferred outcome it will hold
+    let mut register_source: HashMap<&str, QduId> = HashMap::new();
+    let mut halts = false;
+
+    let mut pc = 0;
+    while pc < instructions.len() {
+        match &instructions[pc] {
+            Instruction::QuantumOp(op) => {
+                if let Some(qdu) = op
+                    .involved_qdus()
+                    .into_iter()
+                    .find(|q| deferred.contains(q))
+                {
+                    return Err(impossible(
+                        pc,
+                        format!("{} is acted upon after its stabilization", qdu),
+                    ));
+                }
+                rewritten.push(Instruction::QuantumOp(op.clone()));
+            }
+            Instruction::FPhaseShift { target, .. } if deferred.contains(target) => {
+                return Err(impossible(
+                    pc,
+                    format!("{} is acted upon after its stabilization", target),
+                ));
+            }
+            Instruction::QuantumOpDyn { op_template, .. }
+                if let Some(qdu) = op_template
+                    .involved_qdus()
+                    .into_iter()
+                    .find(|q| deferred.contains(q)) =>
+            {
+                return Err(impossible(
+                    pc,
+                    format!("{} is acted upon after its stabilization", qdu),
+                ));
+            }
+            Instruction::Stabilize { targets } => {
+                for target in targets {
+                    if deferred.contains(target) {
+                        return Err(impossible(pc, format!("{} is stabilized twice", target)));
+                    }
+                    deferred.push(*target);
+                }
+            }
+            Instruction::Record { qdu, register } => {
+                if !deferred.contains(qdu) {
+                    return Err(impossible(
+                        pc,
+                        format!("{} is recorded without a preceding stabilization", qdu),
+                    ));
+                }
+                register_source.insert(register, *qdu);
+                records.push(instructions[pc].clone());
+            }
+            Instruction::StabilizeInto { targets } => {
+                for (qdu, register) in targets {
+                    if deferred.contains(qdu) {
+                        return Err(impossible(pc, format!("{} is stabilized twice", qdu)));
+                    }
+                    deferred.push(*qdu);
+                    register_source.insert(register, *qdu);
+                    records.push(Instruction::Record {
+                        qdu: *qdu,
+                        register: register.clone(),
+                    });
+                }
+            }
+            Instruction::BranchIfZero { register, .. }
+            | Instruction::BranchIfNotZero { register, .. }
+            | Instruction::QuantumOpIf { register, .. } => {
+                return Err(impossible(pc, conditional(register, &register_source)));
+            }
+            Instruction::Jump(_) => {
+                return Err(impossible(
+                    pc,
+                    "unconditional jumps are not supported".to_string(),
+                ));
+            }
+            Instruction::Repeat { .. } => {
+                return Err(impossible(pc, "loops are not supported".to_string()));
+            }
+            Instruction::ResetQdu { .. } => {
+                return Err(impossible(pc, "resets are not supported".to_string()));
+            }
+            Instruction::Call(_) | Instruction::Return => {
+                return Err(impossible(
+                    pc,
+                    "subroutine calls are not supported".to_string(),
+                ));
+            }
+            Instruction::BranchIfEq { .. }
+            | Instruction::BranchIfNe { .. }
+            | Instruction::BranchIfLt { .. }
+            | Instruction::BranchIfGe { .. } => {
+                return Err(impossible(
+                    pc,
+                    "compare-and-branch instructions are not supported".to_string(),
+                ));
+            }
+            Instruction::Halt => {
+                if pc + 1 != instructions.len() {
+                    return Err(impossible(
+                        pc,
+                        "Halt before the end of the program".to_string(),
+                    ));
+                }
+                halts = true;
+            }
+            Instruction::Label(_) => {} // Labels are resolved at build time
+            classical => {
+                if let Some(register) = classical
+                    .read_registers()
+                    .into_iter()
+                    .find(|r| register_source.contains_key(r))
+                {
+                    return Err(impossible(
+                        pc,
+                        format!(
+                            "register '{}' is read before the deferred stabilization",
+                            register
+                        ),
+                    ));
+                }
+                if let Some(register) = classical
+                    .written_registers()
+                    .into_iter()
+                    .find(|r| register_source.contains_key(r))
+                {
+                    return Err(impossible(
+                        pc,
+                        format!(
+                            "register '{}' is overwritten before the deferred stabilization",
+                            register
+                        ),
+                    ));
+                }
+                rewritten.push(classical.clone());
+            }
+        }
+        pc += 1;
+    }
+
+    if !deferred.is_empty() {
+        rewritten.push(Instruction::Stabilize { targets: deferred });
+    }
+    rewritten.extend(records);
+    if halts {
+        rewritten.push(Instruction::Halt);
+    }
+
+    // No control flow survives the rewrite, so no labels need relocating.
+    Ok(Program {
+        instructions: rewritten,
+        label_map: HashMap::new(),
+        exports: Default::default(),
+        imports: Default::default(),
+    })
+}
+
+/// Explains why a branch or conditional operation on `register` cannot be deferred.
+fn conditional(register: &str, register_source: &HashMap<&str, QduId>) -> String {
+    match register_source.get(register) {
+        Some(qdu) => format!(
+            "correction conditioned on the outcome of {} has no controlled equivalent, \
+             as ControlledInteraction applies its pattern whatever the control's state",
+            qdu
+        ),
+        None => format!(
+            "conditional on '{}', which does not hold a stabilization outcome",
+            register
+        ),
+    }
+}
+
+fn impossible(pc: usize, reason: String) -> OnqError {
+    OnqError::InvalidOperation {
+        message: format!("Cannot defer stabilization at PC {}: {}", pc, reason),
+    }
+}
@@ -0,0 +1,258 @@
+// src/vm/verify.rs
+
+//! Static checks of a [`Program`] before it runs, see [`Program::verify`].
+
+use super::program::{Instruction, Program};
+use crate::core::QduId;
+use crate::operations::Operation;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+/// A problem [`Program::verify`] found in a program.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VerificationIssue {
+    /// A classical register is read, but no instruction of the program writes it, so it
+    /// always reads 0 unless supplied with [`OnqVm::run_with_inputs`](super::OnqVm::run_with_inputs).
+    UnwrittenRegister {
+        /// Index of the reading instruction.
+        pc: usize,
+        /// The register read.
+        register: String,
+    },
+    /// A float register is read, but no instruction of the program writes it, so it
+    /// always reads 0.0.
+    UnwrittenFloatRegister {
+        /// Index of the reading instruction.
+        pc: usize,
+        /// The float register read.
+        register: String,
+    },
+    /// A `Record` can execute when the last `Stabilize` before it, on some path, did
+    /// not cover its QDU, so the run fails there.
+    UnstabilizedRecord {
+        /// Index of the `Record`.
+        pc: usize,
+        /// The recorded QDU.
+        qdu: QduId,
+    },
+    /// A label marks an instruction that no path from the start of the program reaches.
+    UnreachableLabel {
+        /// The label.
+        label: String,
+        /// Index of the instruction it marks.
+        pc: usize,
+    },
+    /// A quantum operation uses the same QDU twice, e.g. as both control and target.
+    AliasedQdu {
+        /// Index of the instruction holding the operation.
+        pc: usize,
+        /// The QDU used twice.
+        qdu: QduId,
+    },
+}
+
+impl VerificationIssue {
+    /// Returns the index of the instruction the issue is at.
+    pub fn pc(&self) -> usize {
+        match self {
+            VerificationIssue::UnwrittenRegister { pc, .. }
+            | VerificationIssue::UnwrittenFloatRegister { pc, .. }
+            | VerificationIssue::UnstabilizedRecord { pc, .. }
+            | VerificationIssue::UnreachableLabel { pc, .. }
+            | VerificationIssue::AliasedQdu { pc, .. } => *pc,
+        }
+    }
+}
+
+impl fmt::Display for VerificationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationIssue::UnwrittenRegister { pc, register } => write!(
+                f,
+                "pc {}: register '{}' is read but never written",
+                pc, register
+            ),
+            VerificationIssue::UnwrittenFloatRegister { pc, register } => write!(
+                f,
+                "pc {}: float register '{}' is read but never written",
+                pc, register
+            ),
+            VerificationIssue::UnstabilizedRecord { pc, qdu } => write!(
+                f,
+                "pc {}: {} is recorded without a preceding Stabilize of it on every path",
+                pc, qdu
+            ),
+            VerificationIssue::UnreachableLabel { label, pc } => {
+                write!(f, "pc {}: label '{}' is unreachable", pc, label)
+            }
+            VerificationIssue::AliasedQdu { pc, qdu } => {
+                write!(f, "pc {}: {} is used twice by one operation", pc, qdu)
+            }
+        }
+    }
+}
+
+/// Runs every check over `program`, returning the issues sorted by program counter.
+/// (Internal visibility)
+pub(crate) fn verify(program: &Program) -> Vec<VerificationIssue> {
+    let instructions = &program.instructions;
+    let mut issues = Vec::new();
+
+    let written: HashSet<&str> = instructions
+        .iter()
+        .flat_map(Instruction::written_registers)
+        .collect();
+    let written_floats: HashSet<&str> = instructions
+        .iter()
+        .filter_map(Instruction::written_float_register)
+        .collect();
+    for (pc, instruction) in instructions.iter().enumerate() {
+        let mut reported = HashSet::new();
+        for register in instruction.read_registers() {
+            if !written.contains(register) && reported.insert(register) {
+                issues.push(VerificationIssue::UnwrittenRegister {
+                    pc,
+                    register: register.to_string(),
+                });
+            }
+        }
+        for register in instruction.read_float_registers() {
+            if !written_floats.contains(register) && reported.insert(register) {
+                issues.push(VerificationIssue::UnwrittenFloatRegister {
+                    pc,
+                    register: register.to_string(),
+                });
+            }
+        }
+
+        let op = match instruction {
+            Instruction::QuantumOp(op) | Instruction::QuantumOpIf { op, .. } => Some(op),
+            Instruction::QuantumOpDyn { op_template, .. } => Some(op_template),
+            _ => None,
+        };
+        let aliased = match (instruction, op) {
+            (Instruction::Stabilize { targets } | Instruction::ResetQdu { targets }, _) => {
+                first_duplicate(targets.iter().copied())
+            }
+            (Instruction::StabilizeInto { targets }, _) => {
+                first_duplicate(targets.iter().map(|(qdu, _)| *qdu))
+            }
+            (_, Some(op)) => aliased_qdu(op),
+            _ => None,
+        };
+        if let Some(qdu) = aliased {
+            issues.push(VerificationIssue::AliasedQdu { pc, qdu });
+        }
+    }
+
+    let stabilized = stabilized_qdus(program);
+    for (pc, instruction) in instructions.iter().enumerate() {
+        if let Instruction::Record { qdu, .. } = instruction
+            && let Some(covered) = &stabilized[pc]
+            && !covered.contains(qdu)
+        {
+            issues.push(VerificationIssue::UnstabilizedRecord { pc, qdu: *qdu });
+        }
+    }
+    for (label, &pc) in &program.label_map {
+        if pc < instructions.len() && stabilized[pc].is_none() {
+            issues.push(VerificationIssue::UnreachableLabel {
+                label: label.clone(),
+                pc,
+            });
+        }
+    }
+
+    issues.sort_by_key(|issue| (issue.pc(), issue.to_string()));
+    issues
+}
+
+/// Returns, for each instruction, the QDUs the last `Stabilize` covered, and no
+/// `ResetQdu` reset since, on every path that reaches it, or `None` if no path reaches it.
+///
+/// A call continues at its label and a `Return` at the instruction after any `Call`.
+/// Exported labels are entry points too, and a call to an imported label continues at
+/// the next instruction, as the linked routine cannot be seen.
+fn stabilized_qdus(program: &Program) -> Vec<Option<BTreeSet<QduId>>> {
+    let instructions = &program.instructions;
+    let return_sites: Vec<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| matches!(instruction, Instruction::Call(_)))
+        .map(|(pc, _)| pc + 1)
+        .collect();
+
+    let mut states: Vec<Option<BTreeSet<QduId>>> = vec![None; instructions.len() + 1];
+    let mut pending = vec![0];
+    for label in &program.exports {
+        pending.extend(program.get_label_pc(label));
+    }
+    for &pc in &pending {
+        states[pc] = Some(BTreeSet::new());
+    }
+    while let Some(pc) = pending.pop() {
+        let (Some(instruction), Some(state)) = (instructions.get(pc), &states[pc]) else {
+            continue;
+        };
+        let out = match instruction {
+            // An empty Stabilize leaves the last outcomes alone
+            Instruction::Stabilize { targets } if !targets.is_empty() => {
+                targets.iter().copied().collect()
+            }
+            Instruction::StabilizeInto { targets } if !targets.is_empty() => {
+                targets.iter().map(|(qdu, _)| *qdu).collect()
+            }
+            Instruction::ResetQdu { targets } => state
+                .iter()
+                .copied()
+                .filter(|qdu| !targets.contains(qdu))
+                .collect(),
+            _ => state.clone(),
+        };
+
+        let target = instruction
+            .branch_label()
+            .and_then(|label| program.get_label_pc(label));
+        let successors: Vec<usize> = match instruction {
+            Instruction::Call(label) if program.imports.contains(label) => vec![pc + 1],
+            Instruction::Jump(_) | Instruction::Call(_) => target.into_iter().collect(),
+            Instruction::Return => return_sites.clone(),
+            Instruction::Halt => Vec::new(),
+            _ => target.into_iter().chain([pc + 1]).collect(),
+        };
+        for successor in successors {
+            let merged = match &states[successor] {
+                None => out.clone(),
+                Some(current) => current.intersection(&out).copied().collect(),
+            };
+            if states[successor].as_ref() != Some(&merged) {
+                states[successor] = Some(merged);
+                pending.push(successor);
+            }
+        }
+    }
+    states
+}
+
+/// Returns a QDU `op` uses in two roles, or twice in one list.
+fn aliased_qdu(op: &Operation) -> Option<QduId> {
+    match op {
+        Operation::ControlledInteraction {
+            control, target, ..
+        } if control == target => Some(*control),
+        Operation::RelationalLock { qdu1, qdu2, .. } if qdu1 == qdu2 => Some(*qdu1),
+        Operation::BroadcastPattern { targets, .. }
+        | Operation::BroadcastPhaseShift { targets, .. }
+        | Operation::Delay { targets, .. }
+        | Operation::Stabilize { targets } => first_duplicate(targets.iter().copied()),
+        Operation::PauliProduct { terms, .. } => first_duplicate(terms.iter().map(|(qdu, _)| *qdu)),
+        Operation::Permute { mapping } => first_duplicate(mapping.iter().map(|(from, _)| *from))
+            .or_else(|| first_duplicate(mapping.iter().map(|(_, to)| *to))),
+        _ => None,
+    }
+}
+
+fn first_duplicate(qdus: impl Iterator<Item = QduId>) -> Option<QduId> {
+    let mut seen = HashSet::new();
+    qdus.into_iter().find(|qdu| !seen.insert(*qdu))
+}
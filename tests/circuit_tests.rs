@@ -0,0 +1,679 @@
+// tests/circuit_tests.rs
+
+use onq::{CircuitBuilder, Operation, QduId, Simulator, StableState};
+
+// Helper function to create QduId for tests
+fn qid(id: u64) -> QduId {
+    QduId(id)
+}
+
+// Helper to extract (control, target) pairs from a circuit of controlled interactions
+fn controlled_pairs(ops: &[Operation]) -> Vec<(u64, u64)> {
+    ops.iter()
+        .map(|op| match op {
+            Operation::ControlledInteraction {
+                control, target, ..
+            } => (control.0, target.0),
+            other => panic!("Expected ControlledInteraction, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_entangle_topology_helpers() {
+    let reg: Vec<QduId> = (0..4).map(qid).collect();
+
+    let chain = CircuitBuilder::new()
+        .entangle_chain(&reg, "QualityFlip")
+        .build();
+    assert_eq!(
+        controlled_pairs(chain.operations()),
+        vec![(0, 1), (1, 2), (2, 3)]
+    );
+
+    let ring = CircuitBuilder::new()
+        .entangle_ring(&reg, "QualityFlip")
+        .build();
+    assert_eq!(
+        controlled_pairs(ring.operations()),
+        vec![(0, 1), (1, 2), (2, 3), (3, 0)]
+    );
+
+    let all = CircuitBuilder::new()
+        .entangle_all_pairs(&reg, "PhaseIntroduce")
+        .build();
+    assert_eq!(
+        controlled_pairs(all.operations()),
+        vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+    );
+
+    // Degenerate registers
+    let pair = CircuitBuilder::new()
+        .entangle_ring(&reg[..2], "QualityFlip")
+        .build();
+    assert_eq!(controlled_pairs(pair.operations()), vec![(0, 1)]);
+    assert!(
+        CircuitBuilder::new()
+            .entangle_chain(&reg[..1], "QualityFlip")
+            .build()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_delay_is_displayed_and_idle() -> Result<(), onq::OnqError> {
+    let q0 = qid(0);
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "QualityFlip".to_string(),
+        })
+        .add_op(Operation::Delay {
+            targets: vec![q0],
+            ticks: 5,
+        })
+        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .build();
+
+    let diagram = format!("{}", circuit);
+    assert!(
+        diagram.contains("Δ5"),
+        "Delay missing from diagram:\n{}",
+        diagram
+    );
+
+    let result = onq::Simulator::new().run(&circuit)?;
+    assert_eq!(
+        result.get_stable_state(&q0),
+        Some(&onq::StableState::ResolvedQuality(1))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_graph_state_and_local_complementation() -> Result<(), onq::OnqError> {
+    use onq::library::{Graph, graph_state, local_complement, verify_graph_state};
+
+    // Star graph centered on q0
+    let reg: Vec<QduId> = (0..4).map(qid).collect();
+    let star = Graph::new(
+        reg.clone(),
+        vec![(reg[0], reg[1]), (reg[0], reg[2]), (reg[0], reg[3])],
+    )?;
+    let circuit = graph_state(&star);
+    assert!(verify_graph_state(&circuit, &star)?);
+
+    // Complementing at the center turns the star into the complete graph
+    let complete = local_complement(&star, reg[0])?;
+    assert_eq!(complete.edges().len(), 6);
+    assert!(!verify_graph_state(&circuit, &complete)?);
+
+    // ...and the two states differ only by local operations
+    let mut builder = CircuitBuilder::new().add_ops(circuit.operations().to_vec());
+    builder = builder.add_op(Operation::InteractionPattern {
+        target: reg[0],
+        pattern_id: "SqrtFlip".to_string(),
+    });
+    for neighbor in star.neighbors(reg[0]) {
+        builder = builder.add_op(Operation::InteractionPattern {
+            target: neighbor,
+            pattern_id: "HalfPhase_Inv".to_string(),
+        });
+    }
+    assert!(verify_graph_state(&builder.build(), &complete)?);
+    Ok(())
+}
+
+#[test]
+fn test_circuit_inverse_uncomputes() -> Result<(), onq::OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let pattern = |target: QduId, id: &str| Operation::InteractionPattern {
+        target,
+        pattern_id: id.to_string(),
+    };
+    let circuit = CircuitBuilder::new()
+        .add_op(pattern(q0, "Superposition"))
+        .add_op(pattern(q0, "QuarterPhase"))
+        .add_op(pattern(q1, "QualityFlip"))
+        .add_op(pattern(q1, "PhiRotate"))
+        .add_op(Operation::PhaseShift {
+            target: q1,
+            theta: 0.7,
+        })
+        .add_op(Operation::ControlledInteraction {
+            control: q0,
+            target: q1,
+            pattern_id: "SqrtFlip".to_string(),
+        })
+        .build();
+
+    let inverse = circuit.inverse()?;
+    assert_eq!(inverse.len(), circuit.len());
+    assert_eq!(inverse.operations()[0], circuit.operations()[5].inverse()?);
+    assert_eq!(inverse.inverse()?, circuit);
+
+    // Circuit followed by its inverse returns both QDUs to Quality0
+    let roundtrip = CircuitBuilder::new()
+        .add_ops(circuit.operations().iter().cloned())
+        .add_ops(inverse.operations().iter().cloned())
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, q1],
+        })
+        .build();
+    let result = Simulator::new().run(&roundtrip)?;
+    for q in [q0, q1] {
+        assert_eq!(
+            result.get_stable_state(&q),
+            Some(&StableState::ResolvedQuality(0))
+        );
+    }
+
+    // Stabilization cannot be undone
+    let measured = CircuitBuilder::new()
+        .add_op(pattern(q0, "Superposition"))
+        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .build();
+    assert!(matches!(
+        measured.inverse(),
+        Err(onq::OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_circuit_repeat_keeps_readout_last() -> Result<(), onq::OnqError> {
+    let q0 = qid(0);
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "SqrtFlip".to_string(),
+        })
+        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .build();
+
+    // Two SqrtFlips make a QualityFlip; four return to the baseline
+    let run = |n: usize| -> Result<Option<StableState>, onq::OnqError> {
+        let result = Simulator::new().run(&circuit.repeat(n))?;
+        Ok(result.get_stable_state(&q0).cloned())
+    };
+    assert_eq!(run(2)?, Some(StableState::ResolvedQuality(1)));
+    assert_eq!(run(4)?, Some(StableState::ResolvedQuality(0)));
+
+    let repeated = circuit.repeat(3);
+    assert_eq!(repeated.len(), 4);
+    assert!(matches!(
+        repeated.operations()[3],
+        Operation::Stabilize { .. }
+    ));
+    assert_eq!(circuit.repeat(1), circuit);
+    assert_eq!(circuit.repeat(0).len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_moments_depth_and_aligned_display() {
+    let (q0, q1, q2) = (qid(0), qid(1), qid(2));
+    let h = |target: QduId| Operation::InteractionPattern {
+        target,
+        pattern_id: "Superposition".to_string(),
+    };
+    let cx = Operation::ControlledInteraction {
+        control: q0,
+        target: q2,
+        pattern_id: "QualityFlip".to_string(),
+    };
+    let circuit = CircuitBuilder::new()
+        .add_op(h(q0))
+        .add_op(h(q1))
+        .add_op(h(q2))
+        .add_op(cx.clone())
+        .add_op(h(q1))
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, q1, q2],
+        })
+        .build();
+
+    // The second H on q1 shares a moment with the controlled op on q0/q2
+    let moments = circuit.moments();
+    assert_eq!(circuit.depth(), 3);
+    assert_eq!(moments[0], vec![&h(q0), &h(q1), &h(q2)]);
+    assert_eq!(moments[1], vec![&cx, &h(q1)]);
+    assert_eq!(moments[2].len(), 1);
+    assert_eq!(CircuitBuilder::new().build().depth(), 0);
+
+    // The diagram shows the first layer in one column, but the connector from q0 to q2
+    // crosses q1, so its H is drawn in a column of its own
+    let diagram = format!("{}", circuit);
+    let rows: Vec<&str> = diagram.lines().filter(|l| l.starts_with("QDU")).collect();
+    assert_eq!(rows[0], "QDU(0): ───H──────@─────────────M───");
+    assert_eq!(rows[1], "QDU(1): ───H─────────────H──────M───");
+    assert_eq!(rows[2], "QDU(2): ───H──────X─────────────M───");
+}
+
+#[test]
+fn test_qasm_bell_pair_import() -> Result<(), onq::OnqError> {
+    let source = r#"
+        OPENQASM 2.0;
+        include "qelib1.inc";
+        qreg q[2];
+        qreg anc[1]; // idle register
+        creg c[2];
+        h q[0];
+        cx q[0], q[1];
+        u1(-pi/2) q[1];
+        barrier q;
+        measure q -> c;
+    "#;
+    let circuit = CircuitBuilder::from_qasm(source)?.build();
+
+    assert_eq!(circuit.qdus().len(), 3);
+    assert_eq!(
+        circuit.operations()[1],
+        Operation::ControlledInteraction {
+            control: qid(0),
+            target: qid(1),
+            pattern_id: "QualityFlip".to_string(),
+        }
+    );
+    assert_eq!(
+        circuit.operations()[2],
+        Operation::PhaseShift {
+            target: qid(1),
+            theta: -std::f64::consts::FRAC_PI_2,
+        }
+    );
+
+    // The imported circuit replays under onq stabilization
+    let result = Simulator::new().run(&circuit)?;
+    assert_eq!(result.all_stable_outcomes().len(), 2);
+    assert!(result.get_stable_state(&qid(2)).is_none());
+    Ok(())
+}
+
+#[test]
+fn test_qasm_rejects_unsupported_gates() {
+    let error = onq::circuits::qasm::parse_qasm("qreg q[3];\nh q[0];\nccx q[0], q[1], q[2];")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("line 3"), "{}", error);
+    assert!(error.contains("unsupported gate 'ccx'"), "{}", error);
+
+    for source in [
+        "qreg q[1];\nx r[0];",
+        "qreg q[1];\nx q[1];",
+        "qreg q[1];\nh q[0]",
+    ] {
+        assert!(CircuitBuilder::from_qasm(source).is_err(), "{}", source);
+    }
+
+    // Hostile register sizes are rejected before any QDU or bit list is built
+    assert_eq!(
+        onq::circuits::qasm::parse_qasm("qreg q[64];")
+            .unwrap()
+            .qdus()
+            .len(),
+        64
+    );
+    for source in [
+        "qreg q[18446744073709551615];\nqreg r[2];",
+        "qreg q[60];\nqreg r[2];\nqreg s[18446744073709551614];",
+        "qreg q[4000000000];\ncreg c[4000000000];\nmeasure q -> c;",
+        "qreg q[60];\nqreg r[5];",
+        "qreg q[2];\ncreg c[4000000000];\nmeasure q -> c;",
+    ] {
+        let error = onq::circuits::qasm::parse_qasm(source)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("capacity") || error.contains("different sizes"),
+            "{}",
+            error
+        );
+    }
+
+    // Deeply nested angles fail cleanly instead of exhausting the stack
+    let nesting = 200_000;
+    let source = format!(
+        "qreg q[1];\nrz({}pi{}) q[0];",
+        "(".repeat(nesting),
+        ")".repeat(nesting)
+    );
+    let error = onq::circuits::qasm::parse_qasm(&source).unwrap_err();
+    assert!(
+        matches!(error, onq::OnqError::InvalidOperation { .. }),
+        "{}",
+        error
+    );
+    assert!(error.to_string().contains("nests deeper"), "{}", error);
+    assert!(onq::circuits::qasm::parse_qasm("qreg q[1];\nrz(1e-3) q[0];").is_ok());
+}
+
+#[test]
+fn test_allocate_qdu_avoids_composed_ids() {
+    let mut builder = CircuitBuilder::new();
+    let q0 = builder.allocate_qdu();
+    let q1 = builder.allocate_qdu();
+    assert_eq!((q0, q1), (qid(0), qid(1)));
+
+    // A subcircuit with hand-picked IDs composed into the builder
+    let sub = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: qid(3),
+            pattern_id: "QualityFlip".to_string(),
+        })
+        .build();
+    let mut builder = builder.add_ops(sub.operations().iter().cloned());
+    let ancilla = builder.allocate_qdu();
+    assert_eq!(ancilla, qid(4));
+
+    let circuit = builder
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, ancilla],
+        })
+        .build();
+    // Allocated QDUs are part of the circuit even when idle
+    assert!(circuit.qdus().contains(&q1));
+    assert_eq!(circuit.qdus().len(), 4);
+}
+
+#[test]
+fn test_fluent_helpers_expand_to_operations() {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .y(q0)
+        .z(q1)
+        .s(q1)
+        .t(q1)
+        .phase(q0, 0.5)
+        .cz(q0, q1)
+        .build();
+    let pattern = |target, id: &str| Operation::InteractionPattern {
+        target,
+        pattern_id: id.to_string(),
+    };
+    assert_eq!(
+        circuit.operations(),
+        &[
+            pattern(q0, "QualityFlip"),
+            pattern(q0, "QualitativeY"),
+            pattern(q1, "PhaseIntroduce"),
+            pattern(q1, "HalfPhase"),
+            pattern(q1, "QuarterPhase"),
+            Operation::PhaseShift {
+                target: q0,
+                theta: 0.5
+            },
+            Operation::ControlledInteraction {
+                control: q0,
+                target: q1,
+                pattern_id: "PhaseIntroduce".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_circuit_name_and_metadata() {
+    let circuit = CircuitBuilder::new()
+        .with_name("ghz-3")
+        .with_metadata("author", "alice")
+        .with_metadata("experiment", "E12")
+        .h(qid(0))
+        .cnot(qid(0), qid(1))
+        .build();
+
+    let diagram = circuit.to_string();
+    let mut lines = diagram.lines();
+    assert_eq!(
+        lines.next(),
+        Some("onq::Circuit \"ghz-3\"[2 operations on 2 QDUs]")
+    );
+    assert_eq!(lines.next(), Some("  author = alice"));
+    assert_eq!(lines.next(), Some("  experiment = E12"));
+
+    // Derived circuits keep the identification
+    let inverse = circuit.inverse().unwrap();
+    assert_eq!(inverse.name(), Some("ghz-3"));
+    assert_eq!(circuit.repeat(2).metadata(), circuit.metadata());
+    assert_eq!(
+        onq::circuits::optimize::optimize(&circuit).metadata()["experiment"],
+        "E12"
+    );
+
+    // Unnamed circuits keep the plain header
+    let plain = CircuitBuilder::new().h(qid(0)).build();
+    assert!(
+        plain
+            .to_string()
+            .starts_with("onq::Circuit[1 operations on 1 QDUs]")
+    );
+}
+
+#[test]
+fn test_tensor_interleaves_disjoint_circuits() {
+    let left = CircuitBuilder::new()
+        .with_name("left")
+        .h(qid(0))
+        .x(qid(0))
+        .z(qid(0))
+        .build();
+    let right = CircuitBuilder::new()
+        .with_metadata("block", "right")
+        .cnot(qid(1), qid(2))
+        .build();
+
+    let combined = left.tensor(&right).unwrap();
+    let moments = combined.moments();
+    assert_eq!(moments.len(), 3);
+    assert_eq!(moments[0].len(), 2);
+    assert_eq!(combined.operations()[1], right.operations()[0]);
+    assert_eq!(combined.name(), Some("left"));
+    assert_eq!(combined.metadata()["block"], "right");
+
+    let overlapping = CircuitBuilder::new().x(qid(2)).build();
+    let error = combined.tensor(&overlapping).unwrap_err().to_string();
+    assert!(error.contains("QDU(2)"), "{}", error);
+}
+
+#[test]
+fn test_snapshot_is_ordered_after_every_earlier_operation() -> Result<(), onq::OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .h(q1)
+        .x(q0)
+        .snapshot("s")
+        .build();
+
+    // The snapshot gets a moment and a diagram column of its own, after the gates
+    assert_eq!(circuit.depth(), 3);
+    assert_eq!(circuit.moments()[2], vec![&circuit.operations()[3]]);
+    let diagram = format!("{}", circuit);
+    let rows: Vec<&str> = diagram.lines().filter(|l| l.starts_with("QDU")).collect();
+    assert_eq!(rows[0], "QDU(0): ───X──────X──────◇───");
+    assert_eq!(rows[1], "QDU(1): ───H─────────────◇───");
+    let dag = circuit.to_dag();
+    assert_eq!(dag.predecessors(3), &[1, 2]);
+
+    // Tensoring keeps it after the gates of its own circuit, so it records |->
+    let prepared = CircuitBuilder::new().x(q0).h(q0).snapshot("minus").build();
+    let other = CircuitBuilder::new().h(q1).build();
+    let combined = prepared.tensor(&other)?;
+    assert_eq!(combined.operations().last(), prepared.operations().last());
+    let result = Simulator::new().run(&combined)?;
+    let [a, b] = result.snapshot("minus").unwrap().network[&0].core_state;
+    assert!((a.norm_sqr() - 0.5).abs() < 1e-12 && (a + b).norm() < 1e-12);
+    Ok(())
+}
+
+#[test]
+fn test_render_options_wrap_and_identity_gaps() {
+    use onq::circuits::CircuitDisplayOptions;
+
+    let circuit = CircuitBuilder::new()
+        .h(qid(0))
+        .pattern(qid(0), "Identity")
+        .x(qid(0))
+        .sx(qid(0))
+        .build();
+
+    // The default rendering matches Display
+    let default = CircuitDisplayOptions::default();
+    assert_eq!(circuit.render(&default), circuit.to_string());
+    assert!(circuit.to_string().contains("───H─────────────X─────√X───"));
+
+    let compact = CircuitDisplayOptions {
+        gate_width: 3,
+        unicode: false,
+        show_identity_gaps: false,
+        max_columns: Some(2),
+    };
+    assert_eq!(
+        circuit.render(&compact),
+        "onq::Circuit[4 operations on 1 QDUs]\nQDU(0): -H--X-\n\nQDU(0): SX-\n"
+    );
+}
+
+#[test]
+fn test_classical_bits_survive_transformations() -> Result<(), onq::OnqError> {
+    let circuit = CircuitBuilder::new()
+        .x(qid(0))
+        .x(qid(1))
+        .x(qid(1))
+        .stabilize_into(qid(0), "flag")
+        .h(qid(1))
+        .stabilize_into(qid(1), "coin")
+        .build();
+    assert_eq!(
+        circuit.classical_bits(),
+        vec![("coin", qid(1)), ("flag", qid(0))]
+    );
+
+    let expected = Simulator::new().run(&circuit)?;
+    assert_eq!(expected.classical_bit("flag"), Some(1));
+    assert!(expected.to_string().contains("Classical Bits:"));
+
+    // The cancelled X·X pair shifts the stabilizations; the bits follow them
+    let optimized = onq::circuits::optimize::optimize(&circuit);
+    assert!(optimized.len() < circuit.len());
+    let rerun = Simulator::new().run(&optimized)?;
+    assert_eq!(rerun.classical_bits(), expected.classical_bits());
+
+    let reordered = circuit.to_dag().to_circuit();
+    assert_eq!(reordered.classical_bits(), circuit.classical_bits());
+
+    // QASM measurements name their classical bits after the creg
+    let imported = onq::circuits::qasm::parse_qasm(
+        "qreg q[2]; creg c[2]; x q[1]; measure q[1] -> c[0]; measure q -> c;",
+    )?;
+    assert_eq!(imported.classical_bit("c[0]"), Some(qid(0)));
+    assert_eq!(imported.classical_bit("c[1]"), Some(qid(1)));
+    Ok(())
+}
+
+#[test]
+fn test_transpile_to_restricted_pattern_set() -> Result<(), onq::OnqError> {
+    use onq::circuits::transpile::transpile;
+
+    let circuit = CircuitBuilder::new()
+        .with_name("mixed")
+        .x(qid(0))
+        .y(qid(0))
+        .t(qid(1))
+        .controlled(qid(0), qid(1), "QualitativeY")
+        .stabilize_into(qid(1), "out")
+        .build();
+    let allowed = ["Superposition", "QuarterPhase", "PhaseIntroduce"];
+    let restricted = transpile(&circuit, &allowed)?;
+
+    for op in restricted.operations() {
+        match op {
+            Operation::InteractionPattern { pattern_id, .. }
+            | Operation::ControlledInteraction { pattern_id, .. } => {
+                assert!(allowed.contains(&pattern_id.as_str()), "{:?}", op)
+            }
+            op => assert!(matches!(op, Operation::Stabilize { .. }), "{:?}", op),
+        }
+    }
+    assert_eq!(restricted.name(), Some("mixed"));
+    assert_eq!(restricted.classical_bit("out"), Some(qid(1)));
+
+    let err = transpile(&circuit, &["Superposition", "QualityFlip"]).unwrap_err();
+    assert!(err.to_string().contains("QuarterPhase"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn test_routed_circuit_satisfies_engine_locality() -> Result<(), onq::OnqError> {
+    use onq::circuits::routing::{CouplingMap, route};
+
+    // QDUs 0 and 2 sit on non-adjacent IVM nodes once QDU 1 is in use
+    let circuit = CircuitBuilder::new()
+        .x(qid(1))
+        .h(qid(0))
+        .cnot(qid(0), qid(2))
+        .stabilize(&[qid(0), qid(1), qid(2)])
+        .build();
+    assert!(Simulator::new().run(&circuit).is_err());
+
+    let map = CouplingMap::ivm();
+    let routed = route(&circuit, &map)?;
+    assert_eq!(routed.swaps, 1);
+    map.check(&routed.circuit)?;
+
+    // The flipped QDU 1 was swapped onto position 0
+    let result = Simulator::new().run(&routed.circuit)?;
+    let position = routed.final_layout[&qid(1)];
+    assert_eq!(position, qid(0));
+    assert_eq!(
+        result.get_stable_state(&position),
+        Some(&StableState::ResolvedQuality(1))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_integration_metrics_compare_locks_and_relaxation() -> Result<(), onq::OnqError> {
+    use onq::analysis::integration_metrics;
+    use onq::{LockType, OnqError};
+
+    let (q0, q1, q2) = (qid(0), qid(1), qid(2));
+    let locked = CircuitBuilder::new()
+        .add_op(Operation::RelationalLock {
+            qdu1: q0,
+            qdu2: q1,
+            lock_type: LockType::BellPhiPlus,
+            establish: true,
+        })
+        .add_op(Operation::RelationalLock {
+            qdu1: q1,
+            qdu2: q2,
+            lock_type: LockType::BellPhiPlus,
+            establish: true,
+        })
+        .build();
+
+    // Locks have no stabilizer action, but their bonds across each cut are counted
+    let middle = integration_metrics(&locked, &[q1])?;
+    assert_eq!(middle.entropy, None);
+    assert_eq!(middle.crossing_bonds, 2);
+    assert_eq!(
+        integration_metrics(&locked, &[q0, q1, q2])?.crossing_bonds,
+        0
+    );
+
+    let relaxed = CircuitBuilder::new()
+        .h(q0)
+        .add_op(Operation::Relax {
+            target: q0,
+            rate: 0.5,
+        })
+        .build();
+    assert!(integration_metrics(&relaxed, &[q0])?.mixedness > 0.1);
+    assert!(matches!(
+        integration_metrics(&relaxed, &[q2]),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// tests/engine_tests.rs
+#![cfg(feature = "engine")]
+
+use onq::engine::Engine;
+use onq::simulation::SimulatorConfig;
+use onq::{CircuitBuilder, OnqError, QduId, Simulator};
+use std::collections::HashSet;
+
+#[test]
+fn test_engine_matches_simulator() -> Result<(), OnqError> {
+    let (q0, q1) = (QduId(0), QduId(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .cnot(q0, q1)
+        .phase(q1, 0.3)
+        .stabilize(&[q0, q1])
+        .build();
+    let config = SimulatorConfig::new().with_global_phase_tracking(true);
+
+    let mut engine = Engine::new(circuit.qdus())?;
+    engine.set_config(config);
+    for op in circuit.operations() {
+        engine.apply(op)?;
+    }
+    engine.validate()?;
+    assert_eq!(
+        engine.into_result(),
+        Simulator::with_config(config).run(&circuit)?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_engine_reads_state_and_rejects_unknown_qdus() -> Result<(), OnqError> {
+    let (q0, q1) = (QduId(0), QduId(1));
+    let mut engine = Engine::with_order(&[q1, q0])?;
+    assert_eq!(engine.nodes()[&q1], 0);
+
+    for op in CircuitBuilder::new().h(q0).build().operations() {
+        engine.apply(op)?;
+    }
+    assert!((engine.probability_of(&[(q0, 1)])? - 0.5).abs() < 1e-12);
+    assert!((engine.state().network[&1].core_state[1].norm_sqr() - 0.5).abs() < 1e-12);
+
+    let outcome = engine.stabilize(&[q0])?;
+    assert_eq!(engine.marginals()[&q0][outcome[0] as usize], 1.0);
+    assert!(matches!(
+        engine.stabilize(&[QduId(2)]),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    assert!(matches!(
+        Engine::new(&HashSet::new()),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
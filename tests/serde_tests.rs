@@ -0,0 +1,179 @@
+// tests/serde_tests.rs
+#![cfg(feature = "serde")]
+
+use num_complex::Complex;
+use onq::{Circuit, CircuitBuilder, LockType, Operation, PauliAxis, QduId, Quality};
+
+#[test]
+fn test_circuit_json_roundtrip() {
+    let (q0, q1, q7) = (QduId(0), QduId(1), QduId(7));
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::RelationalLock {
+            qdu1: q0,
+            qdu2: q1,
+            lock_type: LockType::BellPsiMinus,
+            establish: true,
+        })
+        .add_op(Operation::PauliProduct {
+            terms: vec![(q0, PauliAxis::Z), (q1, PauliAxis::X)],
+            theta: 0.25,
+        })
+        .add_op(Operation::MatrixPattern {
+            target: q1,
+            matrix: [
+                [Complex::new(0.0, 0.0), Complex::new(0.0, -1.0)],
+                [Complex::new(0.0, 1.0), Complex::new(0.0, 0.0)],
+            ],
+        })
+        .add_op(Operation::Project {
+            target: q1,
+            onto: Quality::Quality1,
+        })
+        .add_op(Operation::Delay {
+            targets: vec![q7],
+            ticks: 3,
+        })
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, q1],
+        })
+        .build();
+
+    let json = serde_json::to_string(&circuit).expect("serialize");
+    // Output is stable: QDUs are written in sorted order
+    assert!(
+        json.starts_with(r#"{"qdus":[0,1,7],"operations":["#),
+        "{}",
+        json
+    );
+    assert_eq!(json, serde_json::to_string(&circuit).unwrap());
+
+    let restored: Circuit = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(restored, circuit);
+
+    // Name and metadata are written only when present
+    let mut named = circuit.clone();
+    named.set_name("bell");
+    named.set_metadata("run", "7");
+    named.stabilize_into(q1, "m1");
+    let json = serde_json::to_string(&named).unwrap();
+    assert!(
+        json.ends_with(r#""name":"bell","metadata":{"run":"7"},"bits":{"m1":[7,1]}}"#),
+        "{}",
+        json
+    );
+    assert_eq!(serde_json::from_str::<Circuit>(&json).unwrap(), named);
+
+    // QDUs without operations (e.g. after an optimization pass cancels them) are kept
+    let idle: Circuit = serde_json::from_str(r#"{"qdus":[3],"operations":[]}"#).unwrap();
+    assert!(idle.is_empty());
+    assert!(idle.qdus().contains(&QduId(3)));
+}
+
+#[test]
+fn test_pipeline_json_roundtrip() {
+    use onq::pipeline::{
+        AnalysisSpec, Pipeline, PipelineBuilder, PipelineOutput, StabilizationSpec,
+    };
+    use onq::vm::{Instruction, Program, ProgramBuilder};
+
+    let (q0, q1) = (QduId(0), QduId(1));
+    let circuit_pipeline = PipelineBuilder::new()
+        .prepare(CircuitBuilder::new().x(q0).build())
+        .evolve_circuit(CircuitBuilder::new().cnot(q0, q1).build())
+        .stabilize(StabilizationSpec::Targets(vec![q1]))
+        .analyze(AnalysisSpec::Shots(4))
+        .build()
+        .unwrap();
+
+    let json = serde_json::to_string(&circuit_pipeline).expect("serialize");
+    let restored: Pipeline = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    assert_eq!(restored.preparation(), circuit_pipeline.preparation());
+    assert_eq!(
+        restored.stabilization(),
+        &StabilizationSpec::Targets(vec![q1])
+    );
+    assert_eq!(restored.analysis(), &AnalysisSpec::Shots(4));
+    match (circuit_pipeline.run().unwrap(), restored.run().unwrap()) {
+        (PipelineOutput::Shots(a), PipelineOutput::Shots(b)) => assert_eq!(a.counts(), b.counts()),
+        other => panic!("unexpected outputs: {:?}", other),
+    }
+
+    // A VM program evolution keeps its labels through the round trip
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate {
+            register: "n".to_string(),
+            value: 3,
+        })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::Addi {
+            r_dest: "sum".to_string(),
+            r_src: "sum".to_string(),
+            value: 2,
+        })
+        .pb_add(Instruction::Repeat {
+            register: "n".to_string(),
+            label: "loop".to_string(),
+        })
+        .build()
+        .unwrap();
+    let json = serde_json::to_string(&program).unwrap();
+    assert!(json.ends_with(r#""labels":{"loop":1}}"#), "{}", json);
+    assert_eq!(serde_json::from_str::<Program>(&json).unwrap(), program);
+
+    let program_pipeline = PipelineBuilder::new()
+        .evolve_program(program)
+        .stabilize(StabilizationSpec::None)
+        .build()
+        .unwrap();
+    let json = serde_json::to_string(&program_pipeline).unwrap();
+    let restored: Pipeline = serde_json::from_str(&json).unwrap();
+    match restored.run().unwrap() {
+        PipelineOutput::Vm { registers, .. } => assert_eq!(registers["sum"], 6),
+        other => panic!("unexpected output: {:?}", other),
+    }
+
+    // Stored artifacts are validated as the builders validate them
+    let mismatched = json.replace(r#""analyze":"Outcomes""#, r#""analyze":{"Shots":8}"#);
+    assert_ne!(mismatched, json);
+    assert!(serde_json::from_str::<Pipeline>(&mismatched).is_err());
+    let dangling = r#"{"instructions":[{"Jump":"nowhere"}]}"#;
+    assert!(serde_json::from_str::<Program>(dangling).is_err());
+}
+
+#[test]
+fn test_simulator_config_json_roundtrip() {
+    use onq::Simulator;
+    use onq::simulation::{
+        BoundaryModel, Precision, RenormalizationPolicy, ScoringMode, SemanticsVersion,
+        SimulatorConfig, StabilizationSeed, ValidationMode,
+    };
+
+    let config = SimulatorConfig::new()
+        .with_semantics(SemanticsVersion::V1)
+        .with_stabilization_seed(StabilizationSeed::Mixed(7))
+        .with_validation(ValidationMode::Strict)
+        .with_norm_tolerance(1e-9)
+        .with_renormalization(RenormalizationPolicy::WarnAndRenormalize)
+        .with_precision(Precision::Extended)
+        .with_scoring(ScoringMode::Full)
+        .with_boundary_model(BoundaryModel::new(0.25, 0.5));
+    let json = serde_json::to_string(&config).expect("serialize");
+    let restored: SimulatorConfig = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(restored, config);
+
+    // A published run carries the semantics it was produced under alongside its config
+    let q0 = QduId(0);
+    let circuit = CircuitBuilder::new().h(q0).stabilize(&[q0]).build();
+    let result = Simulator::with_config(restored).run(&circuit).unwrap();
+    let semantics = serde_json::to_string(&result.semantics()).unwrap();
+    assert_eq!(semantics, r#""V1""#);
+    assert_eq!(
+        Simulator::with_config(config).run(&circuit).unwrap(),
+        result
+    );
+}
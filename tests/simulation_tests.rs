@@ -2,8 +2,9 @@
 
 // Import necessary types from the onq crate
 use onq::{
-    Circuit, CircuitBuilder, OnqError, Operation, QduId, StableState, simulation::SimulationResult,
-    simulation::Simulator,
+    Circuit, CircuitBuilder, OnqError, Operation, PauliAxis, QduId, Quality, StableState,
+    simulation::JointOutcome, simulation::SeedMode, simulation::SemanticsVersion,
+    simulation::SimulationResult, simulation::Simulator, simulation::SimulatorConfig,
 };
 
 use std::f64::consts::PI;
@@ -196,3 +197,1397 @@ fn test_undefined_interaction_pattern() {
         e => panic!("Expected InvalidOperation error, got {:?}", e),
     }
 }
+
+#[test]
+fn test_broadcast_pattern_operation() -> Result<(), OnqError> {
+    // Flip every QDU in one entry, then phase-shift them all (phase doesn't affect outcome)
+    let qdus = vec![qid(0), qid(1), qid(2)];
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::BroadcastPattern {
+            targets: qdus.clone(),
+            pattern_id: "QualityFlip".to_string(),
+        })
+        .add_op(Operation::BroadcastPhaseShift {
+            targets: qdus.clone(),
+            theta: PI / 4.0,
+        })
+        .add_op(Operation::Stabilize {
+            targets: qdus.clone(),
+        })
+        .build();
+
+    assert_eq!(circuit.len(), 3);
+    assert_eq!(circuit.qdus().len(), 3);
+
+    let simulator = Simulator::new();
+    let result = simulator.run(&circuit)?;
+
+    for q in qdus {
+        check_stable_state(&result, q, 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_run_stream_matches_run() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    let q1 = qid(1);
+    let ops = vec![
+        Operation::InteractionPattern {
+            target: q1,
+            pattern_id: "QualityFlip".to_string(),
+        },
+        Operation::Stabilize {
+            targets: vec![q0, q1],
+        },
+    ];
+    let circuit = CircuitBuilder::new().add_ops(ops.clone()).build();
+
+    let simulator = Simulator::new();
+    let streamed = simulator.run_stream(circuit.qdus(), ops)?;
+    assert_eq!(streamed, simulator.run(&circuit)?);
+
+    // Operations on QDUs that were not declared up front are rejected
+    let undeclared = vec![Operation::Stabilize {
+        targets: vec![qid(7)],
+    }];
+    match simulator.run_stream(circuit.qdus(), undeclared) {
+        Err(OnqError::ReferenceViolation { .. }) => {}
+        other => panic!("Expected ReferenceViolation, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_pauli_product_zz_coupling() -> Result<(), OnqError> {
+    // q0 = |+>, q1 = |1>. exp(iπ/2·ZZ) acts as exp(-iπ/2·Z) on q0 (q1 is a Z eigenstate),
+    // turning |+> into |-> up to global phase; H then maps it to |1>.
+    let q0 = qid(0);
+    let q1 = qid(1);
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::InteractionPattern {
+            target: q1,
+            pattern_id: "QualityFlip".to_string(),
+        })
+        .add_op(Operation::PauliProduct {
+            terms: vec![(q0, PauliAxis::Z), (q1, PauliAxis::Z)],
+            theta: PI / 2.0,
+        })
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, q1],
+        })
+        .build();
+
+    let result = Simulator::new().run(&circuit)?;
+    check_stable_state(&result, q0, 1);
+    check_stable_state(&result, q1, 1);
+
+    // Repeating a QDU within the product is rejected
+    let invalid = CircuitBuilder::new()
+        .add_op(Operation::PauliProduct {
+            terms: vec![(q0, PauliAxis::X), (q0, PauliAxis::X)],
+            theta: PI,
+        })
+        .build();
+    assert!(matches!(
+        Simulator::new().run(&invalid),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_misspelled_pattern_suggests_correction() {
+    let q0 = qid(0);
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "Superpostion".to_string(), // Typo
+        })
+        .build();
+
+    match Simulator::new().run(&circuit) {
+        Err(OnqError::InvalidOperation { message }) => {
+            assert!(
+                message.contains("did you mean 'Superposition'?"),
+                "Missing suggestion: {}",
+                message
+            );
+        }
+        other => panic!("Expected InvalidOperation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_permute_relabels_qdus() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    let q1 = qid(1);
+    let q2 = qid(2);
+    // Flip q0, then rotate labels q0 -> q1 -> q2 -> q0
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "QualityFlip".to_string(),
+        })
+        .add_op(Operation::Permute {
+            mapping: vec![(q0, q1), (q1, q2), (q2, q0)],
+        })
+        .add_op(Operation::Stabilize {
+            targets: vec![q0, q1, q2],
+        })
+        .build();
+
+    let result = Simulator::new().run(&circuit)?;
+    check_stable_state(&result, q0, 0);
+    check_stable_state(&result, q1, 1);
+    check_stable_state(&result, q2, 0);
+
+    // A mapping that is not a permutation is rejected
+    let invalid = CircuitBuilder::new()
+        .add_op(Operation::Permute {
+            mapping: vec![(q0, q1), (q1, q1)],
+        })
+        .build();
+    assert!(matches!(
+        Simulator::new().run(&invalid),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_project_operation() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    // |+> projected onto Quality1 resolves to 1
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::Project {
+            target: q0,
+            onto: Quality::Quality1,
+        })
+        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .build();
+    let result = Simulator::new().run(&circuit)?;
+    check_stable_state(&result, q0, 1);
+
+    // |0> has zero overlap with Quality1
+    let invalid = CircuitBuilder::new()
+        .add_op(Operation::Project {
+            target: q0,
+            onto: Quality::Quality1,
+        })
+        .build();
+    assert!(matches!(
+        Simulator::new().run(&invalid),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_relax_operation() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    let relaxed = |rate: f64| {
+        CircuitBuilder::new()
+            .add_op(Operation::InteractionPattern {
+                target: q0,
+                pattern_id: "QualityFlip".to_string(),
+            })
+            .add_op(Operation::Relax { target: q0, rate })
+            .add_op(Operation::Stabilize { targets: vec![q0] })
+            .build()
+    };
+
+    let simulator = Simulator::new();
+    // Weak relaxation leaves |1> dominant; strong relaxation lets |0> dominate
+    check_stable_state(&simulator.run(&relaxed(0.1))?, q0, 1);
+    check_stable_state(&simulator.run(&relaxed(0.9))?, q0, 0);
+    check_stable_state(&simulator.run(&relaxed(1.0))?, q0, 0);
+
+    assert!(matches!(
+        simulator.run(&relaxed(1.5)),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_run_shots_heralded_state() -> Result<(), OnqError> {
+    let (herald, data) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: herald,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::InteractionPattern {
+            target: data,
+            pattern_id: "Superposition".to_string(),
+        })
+        .add_op(Operation::Stabilize {
+            targets: vec![herald],
+        })
+        .build();
+
+    let simulator = Simulator::new();
+    let herald_one: JointOutcome = [(herald, 1)].into_iter().collect();
+    let herald_zero: JointOutcome = [(herald, 0)].into_iter().collect();
+    let results = simulator.run_shots_heralded(&circuit, 64, |outcome| outcome == &herald_one)?;
+
+    // An even superposition resolves both ways across shots
+    assert_eq!(results.shots(), 64);
+    assert_eq!(results.count(&herald_one) + results.count(&herald_zero), 64);
+    assert!(results.count(&herald_one) > 0 && results.count(&herald_zero) > 0);
+
+    // Only the selected outcome keeps a residual state
+    assert!(results.heralded_state(&herald_zero).is_none());
+    let residual = results
+        .heralded_state(&herald_one)
+        .expect("heralded outcome should capture a state");
+    let herald_core = residual.network[&0].core_state;
+    let data_core = residual.network[&1].core_state;
+    assert!(herald_core[0].norm() < 1e-9 && (herald_core[1].norm() - 1.0).abs() < 1e-9);
+    // The un-stabilized QDU keeps its potentiality
+    assert!((data_core[0].norm_sqr() - 0.5).abs() < 1e-9);
+
+    // Shot 0 reproduces a plain run, and the plain variant captures nothing
+    let single = simulator.run(&circuit)?;
+    let plain = simulator.run_shots(&circuit, 1, SeedMode::PerShot)?;
+    let expected: JointOutcome = single
+        .all_stable_outcomes()
+        .iter()
+        .map(|(q, StableState::ResolvedQuality(v))| (*q, *v))
+        .collect();
+    assert_eq!(plain.count(&expected), 1);
+    assert!(plain.heralded_states().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_semantics_version_pins_stabilization() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    // PhiRotate leaves Quality1 with weight ~0.68, above the 1/phi threshold
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern {
+            target: q0,
+            pattern_id: "PhiRotate".to_string(),
+        })
+        .add_op(Operation::Stabilize { targets: vec![q0] })
+        .build();
+    let zero: JointOutcome = [(q0, 0)].into_iter().collect();
+    let one: JointOutcome = [(q0, 1)].into_iter().collect();
+
+    // v2 (default): the coherence filter always selects the dominant quality
+    let v2 = Simulator::new();
+    assert_eq!(v2.config().semantics(), SemanticsVersion::V2);
+    assert_eq!(
+        v2.run_shots(&circuit, 64, SeedMode::PerShot)?.count(&one),
+        64
+    );
+    assert_eq!(v2.run(&circuit)?.semantics(), SemanticsVersion::V2);
+
+    // v1: outcomes are drawn from the amplitudes alone, so both qualities occur
+    let v1 = Simulator::with_config(SimulatorConfig::new().with_semantics(SemanticsVersion::V1));
+    let shots = v1.run_shots(&circuit, 64, SeedMode::PerShot)?;
+    assert!(shots.count(&zero) > 0 && shots.count(&one) > shots.count(&zero));
+    assert_eq!(v1.run(&circuit)?.semantics(), SemanticsVersion::V1);
+    Ok(())
+}
+
+#[test]
+fn test_run_shots_seed_modes() -> Result<(), OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .h(q1)
+        .stabilize(&[q0, q1])
+        .build();
+    let simulator = Simulator::new();
+
+    // Fixed seeding repeats the single-run outcome in every shot
+    let fixed = simulator.run_shots(&circuit, 32, SeedMode::Fixed)?;
+    assert_eq!(fixed.counts().len(), 1);
+    assert_eq!(fixed.seed_mode(), SeedMode::Fixed);
+
+    // Varying the seed spreads the shots over several joint outcomes
+    let per_shot = simulator.run_shots(&circuit, 256, SeedMode::PerShot)?;
+    assert!(per_shot.counts().len() > 1);
+    assert_eq!(per_shot.counts().values().sum::<usize>(), 256);
+
+    // Seeded runs are reproducible and depend on the seed
+    let seeded = simulator.run_shots(&circuit, 256, SeedMode::Seeded(1))?;
+    assert_eq!(
+        seeded.counts(),
+        simulator
+            .run_shots(&circuit, 256, SeedMode::Seeded(1))?
+            .counts()
+    );
+    assert!(seeded.counts().len() > 1);
+    assert_ne!(
+        seeded.counts(),
+        simulator
+            .run_shots(&circuit, 256, SeedMode::Seeded(2))?
+            .counts()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_run_with_state_reports_unstabilized_marginals() -> Result<(), OnqError> {
+    let (q0, q1, q2) = (qid(0), qid(1), qid(2));
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .h(q1)
+        .add_op(Operation::InteractionPattern {
+            target: q2,
+            pattern_id: "PhiRotate".to_string(),
+        })
+        .stabilize(&[q0])
+        .build();
+    let simulator = Simulator::new();
+
+    let result = simulator.run_with_state(&circuit)?;
+    assert_eq!(
+        result.get_stable_state(&q0),
+        Some(&StableState::ResolvedQuality(1))
+    );
+    assert_eq!(
+        result.marginals().keys().copied().collect::<Vec<_>>(),
+        vec![q1, q2]
+    );
+    let [p0, p1] = result.marginal(&q2).expect("q2 is un-stabilized");
+    assert!((p0 + p1 - 1.0).abs() < 1e-9 && p1 > 0.6);
+
+    // QDUs occupy IVM nodes in ascending order
+    let state = result.final_state().expect("state is captured");
+    assert!((state.network[&0].core_state[1].norm() - 1.0).abs() < 1e-9);
+    assert!(result.to_string().contains("Marginals:"));
+
+    // A plain run captures nothing but agrees on the outcomes
+    let plain = simulator.run(&circuit)?;
+    assert!(plain.final_state().is_none() && plain.marginals().is_empty());
+    assert_eq!(plain.all_stable_outcomes(), result.all_stable_outcomes());
+    Ok(())
+}
+
+#[test]
+fn test_run_from_state_uses_prepared_nodes() -> Result<(), OnqError> {
+    use num_complex::Complex;
+    use onq::PotentialityState;
+
+    let (a, b) = (qid(5), qid(2));
+    let one = [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)];
+    let mut initial = PotentialityState::new();
+    initial.network.get_mut(&1).unwrap().core_state = one;
+
+    // Node 1 holds `b`, so flipping it back returns to |0>
+    let circuit = CircuitBuilder::new().x(b).stabilize(&[a, b]).build();
+    let simulator = Simulator::new();
+    let result = simulator.run_from_state(&circuit, initial.clone(), &[a, b])?;
+    assert_eq!(
+        result.get_stable_state(&a),
+        Some(&StableState::ResolvedQuality(0))
+    );
+    assert_eq!(
+        result.get_stable_state(&b),
+        Some(&StableState::ResolvedQuality(0))
+    );
+
+    // Swapping the order moves the prepared |1> onto `a`
+    let result = simulator.run_from_state(&circuit, initial.clone(), &[b, a])?;
+    assert_eq!(
+        result.get_stable_state(&a),
+        Some(&StableState::ResolvedQuality(1))
+    );
+
+    // The order must cover the circuit and name each QDU once
+    assert!(matches!(
+        simulator.run_from_state(&circuit, initial.clone(), &[a]),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    assert!(matches!(
+        simulator.run_from_state(&circuit, initial.clone(), &[a, b, a]),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    initial.network.get_mut(&0).unwrap().core_state = [Complex::new(0.0, 0.0); 2];
+    assert!(matches!(
+        simulator.run_from_state(&circuit, initial, &[a, b]),
+        Err(OnqError::Incoherence { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_simulator_config_tolerances_and_validation() -> Result<(), OnqError> {
+    use num_complex::Complex;
+    use onq::simulation::{ValidationMode, ValidationTiming};
+
+    let q0 = qid(0);
+    // A slightly over-scaled identity: not unitary at the default tolerance
+    let scale = Complex::new(1.0 + 1e-4, 0.0);
+    let zero = Complex::new(0.0, 0.0);
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .add_op(Operation::MatrixPattern {
+            target: q0,
+            matrix: [[scale, zero], [zero, scale]],
+        })
+        .stabilize(&[q0])
+        .build();
+
+    assert!(matches!(
+        Simulator::new().run(&circuit),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+
+    let loose = SimulatorConfig::new().with_amplitude_tolerance(1e-3);
+    assert!(Simulator::with_config(loose).run(&circuit).is_ok());
+
+    // Validation catches the norm drift the loose tolerance let through
+    let strict = loose
+        .with_validation(ValidationMode::Strict)
+        .with_validation_timing(ValidationTiming::PerOperation);
+    assert!(matches!(
+        Simulator::with_config(strict).run(&circuit),
+        Err(OnqError::Incoherence { .. })
+    ));
+    // Checking only at the end misses it: stabilization renormalizes the QDU
+    let basic = loose.with_validation(ValidationMode::Basic);
+    assert!(Simulator::with_config(basic).run(&circuit).is_ok());
+    let basic = basic.with_validation_timing(ValidationTiming::PerOperation);
+    assert!(Simulator::with_config(basic).run(&circuit).is_err());
+    assert!(
+        Simulator::with_config(basic.with_norm_tolerance(1e-2))
+            .run(&circuit)
+            .is_ok()
+    );
+
+    // Well-formed circuits pass strict per-operation validation
+    let bell = CircuitBuilder::new()
+        .h(q0)
+        .cnot(q0, qid(1))
+        .stabilize(&[q0, qid(1)])
+        .build();
+    let checked = SimulatorConfig::new()
+        .with_validation(ValidationMode::Strict)
+        .with_validation_timing(ValidationTiming::PerOperation);
+    assert!(Simulator::with_config(checked).run(&bell).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_stabilization_strategies_match_semantics_versions() -> Result<(), OnqError> {
+    use onq::simulation::{AmplitudeWeighted, CoherenceFiltered, MaxWeight};
+
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .add_op(Operation::InteractionPattern {
+            target: q1,
+            pattern_id: "PhiRotate".to_string(),
+        })
+        .stabilize(&[q0, q1])
+        .build();
+    let counts = |simulator: Simulator| -> Result<_, OnqError> {
+        Ok(simulator
+            .run_shots(&circuit, 128, SeedMode::PerShot)?
+            .counts()
+            .clone())
+    };
+
+    let v1 = Simulator::with_config(SimulatorConfig::new().with_semantics(SemanticsVersion::V1));
+    assert_eq!(
+        counts(v1)?,
+        counts(Simulator::new().with_strategy(AmplitudeWeighted))?
+    );
+    assert_eq!(
+        counts(Simulator::new())?,
+        counts(Simulator::new().with_strategy(CoherenceFiltered::default()))?
+    );
+    assert!(Simulator::new().strategy().is_none());
+
+    // Max-weight ignores the draw: q1 always resolves to its dominant quality
+    let argmax = counts(Simulator::new().with_strategy(MaxWeight))?;
+    assert!(argmax.keys().all(|outcome| outcome[&q1] == 1));
+    Ok(())
+}
+
+#[test]
+fn test_stabilization_seed_control() -> Result<(), OnqError> {
+    use onq::simulation::StabilizationSeed;
+
+    let qdus: Vec<QduId> = (0..8).map(qid).collect();
+    let circuit = CircuitBuilder::new()
+        .add_op(Operation::BroadcastPattern {
+            targets: qdus.clone(),
+            pattern_id: "Superposition".to_string(),
+        })
+        .stabilize(&qdus)
+        .build();
+    let outcomes = |seed: StabilizationSeed| -> Result<Vec<u64>, OnqError> {
+        let config = SimulatorConfig::new().with_stabilization_seed(seed);
+        let result = Simulator::with_config(config).run(&circuit)?;
+        Ok(qdus
+            .iter()
+            .map(|q| {
+                result
+                    .get_stable_state(q)
+                    .unwrap()
+                    .get_resolved_value()
+                    .unwrap()
+            })
+            .collect())
+    };
+
+    // Identical states resolve identically unless the state is ignored
+    let derived = outcomes(StabilizationSeed::StateDerived)?;
+    assert!(derived.iter().all(|v| *v == derived[0]));
+    let mixed = outcomes(StabilizationSeed::Mixed(5))?;
+    assert!(mixed.iter().all(|v| *v == mixed[0]));
+    let fixed = outcomes(StabilizationSeed::Fixed(11))?;
+    assert!(fixed.contains(&0) && fixed.contains(&1));
+    assert_eq!(fixed, outcomes(StabilizationSeed::Fixed(11))?);
+
+    // Different mixing values resolve the shared state differently at least once
+    let mixed: std::collections::HashSet<u64> = (0..16)
+        .map(|v| outcomes(StabilizationSeed::Mixed(v)).map(|o| o[0]))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(mixed.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_partial_stabilization_preserves_other_qdus() -> Result<(), OnqError> {
+    let (data, ancilla, spectator) = (qid(0), qid(1), qid(2));
+    let circuit = CircuitBuilder::new()
+        .h(data)
+        .cnot(data, ancilla)
+        .h(spectator)
+        .stabilize(&[ancilla])
+        // The data QDU can keep evolving after the ancilla is resolved
+        .x(data)
+        .build();
+
+    let result = Simulator::new().run_with_state(&circuit)?;
+    assert!(result.get_stable_state(&ancilla).is_some());
+    for qdu in [data, spectator] {
+        let [p0, p1] = result.marginal(&qdu).expect("un-stabilized");
+        assert!(
+            (p0 - 0.5).abs() < 1e-9 && (p1 - 0.5).abs() < 1e-9,
+            "{}",
+            qdu
+        );
+    }
+
+    // The resolved ancilla's bonds are severed on both sides
+    let state = result.final_state().expect("state captured");
+    assert!(state.network[&1].bonds.is_empty());
+    assert!(!state.network[&0].bonds.contains_key(&1));
+    Ok(())
+}
+
+#[test]
+fn test_density_matrix_mode_models_relaxation_as_a_mixture() -> Result<(), OnqError> {
+    use onq::simulation::StateRepresentation;
+
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .add_op(Operation::Relax {
+            target: q0,
+            rate: 0.5,
+        })
+        .h(q1)
+        .cnot(q0, q1)
+        .stabilize(&[q1])
+        .build();
+    let density = Simulator::with_config(
+        SimulatorConfig::new().with_representation(StateRepresentation::DensityMatrix),
+    );
+
+    // Both representations agree on weights: 3/4 of the population ends in Quality0
+    let amplitudes = Simulator::new().run_with_state(&circuit)?;
+    let mixed = density.run_with_state(&circuit)?;
+    assert!(amplitudes.final_density().is_none());
+    for result in [&amplitudes, &mixed] {
+        let [p0, _] = result.marginal(&q0).expect("un-stabilized");
+        assert!((p0 - 0.75).abs() < 1e-9, "{}", p0);
+    }
+    assert_eq!(
+        amplitudes.get_stable_state(&q1),
+        mixed.get_stable_state(&q1)
+    );
+
+    // ...but only the density matrix records that the damped QDU lost its purity
+    let rho = mixed.final_density().expect("density captured");
+    assert!(rho.purity(0).unwrap() < 0.9);
+    assert!((rho.purity(1).unwrap() - 1.0).abs() < 1e-12);
+    Ok(())
+}
+
+#[test]
+fn test_readout_error_flips_reports_but_not_the_state() -> Result<(), OnqError> {
+    use onq::simulation::ReadoutError;
+
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new().x(q1).stabilize(&[q0, q1]).build();
+    let noisy = Simulator::new().with_readout_error(ReadoutError::uniform(0.25)?.with_seed(9));
+
+    let shots = noisy.run_shots(&circuit, 400, SeedMode::PerShot)?;
+    let flipped_q0: usize = shots
+        .counts()
+        .iter()
+        .filter(|(outcome, _)| outcome[&q0] == 1)
+        .map(|(_, count)| count)
+        .sum();
+    assert!((60..=140).contains(&flipped_q0), "{}", flipped_q0);
+
+    // The state collapses onto the true quality: stabilizing again reads it anew
+    let twice = CircuitBuilder::new()
+        .stabilize(&[q0])
+        .stabilize(&[q0])
+        .build();
+    let strict = Simulator::new().with_readout_error(ReadoutError::uniform(1.0)?);
+    let result = strict.run_with_state(&twice)?;
+    check_stable_state(&result, q0, 1);
+    assert!(result.readout_flipped(&q0));
+    let node = &result.final_state().expect("state captured").network[&0];
+    assert!((node.core_state[0].norm_sqr() - 1.0).abs() < 1e-12);
+
+    assert!(ReadoutError::uniform(1.5).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_stabilizer_tableau_correlates_integrated_qdus() -> Result<(), OnqError> {
+    use onq::simulation::Backend;
+
+    let (q0, q1) = (qid(0), qid(1));
+    let bell = CircuitBuilder::new()
+        .h(q0)
+        .cnot(q0, q1)
+        .stabilize(&[q0, q1])
+        .build();
+    let fast =
+        Simulator::with_config(SimulatorConfig::new().with_backend(Backend::StabilizerTableau));
+
+    let shots = fast.run_shots(&bell, 64, SeedMode::PerShot)?;
+    assert_eq!(shots.counts().len(), 2);
+    assert!(
+        shots
+            .counts()
+            .keys()
+            .all(|outcome| outcome[&q0] == outcome[&q1])
+    );
+    assert!(fast.run_with_state(&bell)?.final_state().is_none());
+
+    // A non-Clifford pattern falls back to the tensor network
+    let rotated = CircuitBuilder::new()
+        .h(q0)
+        .add_op(Operation::PhaseShift {
+            target: q0,
+            theta: 0.3,
+        })
+        .stabilize(&[q0])
+        .build();
+    assert!(fast.run_with_state(&rotated)?.final_state().is_some());
+
+    // The Locality Rule is enforced on the tableau as well
+    let distant = CircuitBuilder::new()
+        .h(q1)
+        .cnot(q0, qid(2))
+        .stabilize(&[q0])
+        .build();
+    assert!(fast.run(&distant).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_stabilizer_tableau_matches_tensor_network_on_product_circuits() -> Result<(), OnqError> {
+    use onq::simulation::Backend;
+
+    const PATTERNS: [&str; 8] = [
+        "Superposition",
+        "HalfPhase",
+        "HalfPhase_Inv",
+        "QualityFlip",
+        "PhaseIntroduce",
+        "QualitativeY",
+        "SqrtFlip",
+        "SqrtFlip_Inv",
+    ];
+    let tableau =
+        Simulator::with_config(SimulatorConfig::new().with_backend(Backend::StabilizerTableau));
+    let network = Simulator::new();
+    let qdus = [qid(0), qid(1), qid(2)];
+
+    // Un-integrated QDUs resolve exactly as on the tensor network, whatever the rounding
+    // of their weights
+    let mut rng = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..300 {
+        let mut builder = CircuitBuilder::new();
+        for _ in 0..12 {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let qdu = qdus[(rng % 3) as usize];
+            builder = builder.pattern(qdu, PATTERNS[((rng >> 8) % 8) as usize]);
+            if (rng >> 16).is_multiple_of(5) {
+                builder = builder.stabilize(&[qdu]);
+            }
+        }
+        let circuit = builder.stabilize(&qdus).build();
+        assert_eq!(
+            tableau.run(&circuit)?,
+            network.run(&circuit)?,
+            "{:?}",
+            circuit.operations()
+        );
+        assert_eq!(
+            tableau.run_shots(&circuit, 8, SeedMode::PerShot)?.counts(),
+            network.run_shots(&circuit, 8, SeedMode::PerShot)?.counts()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_run_with_observer_sees_every_operation() -> Result<(), OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .x(q1)
+        .stabilize(&[q0, q1])
+        .build();
+
+    let mut steps = Vec::new();
+    let observed = Simulator::new().run_with_observer(&circuit, |step, op, state| {
+        let resolved = state.network[&0]
+            .core_state
+            .iter()
+            .any(|a| a.norm_sqr() == 1.0);
+        steps.push((step, op.clone(), resolved));
+    })?;
+
+    assert_eq!(observed, Simulator::new().run(&circuit)?);
+    let ops: Vec<Operation> = steps.iter().map(|(_, op, _)| op.clone()).collect();
+    assert_eq!(ops, circuit.operations().to_vec());
+    assert_eq!(
+        steps.iter().map(|(s, _, _)| *s).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    // QDU 0 is in superposition until the stabilization resolves it
+    assert!(!steps[0].2 && !steps[1].2 && steps[2].2);
+    Ok(())
+}
+
+#[test]
+fn test_snapshots_capture_intermediate_states() -> Result<(), OnqError> {
+    let q0 = qid(0);
+    let circuit = CircuitBuilder::new()
+        .snapshot("start")
+        .x(q0)
+        .snapshot("flipped")
+        .stabilize(&[q0])
+        .snapshot("end")
+        .build();
+
+    let result = Simulator::new().run(&circuit)?;
+    check_stable_state(&result, q0, 1);
+    assert_eq!(
+        result.snapshots().keys().collect::<Vec<_>>(),
+        vec!["end", "flipped", "start"]
+    );
+    let p1 = |label: &str| result.snapshot(label).unwrap().network[&0].core_state[1].norm_sqr();
+    assert!(p1("start") < 1e-12);
+    assert!((p1("flipped") - 1.0).abs() < 1e-12);
+    assert!((p1("end") - 1.0).abs() < 1e-12);
+
+    // Snapshots do not change the outcome
+    let plain = CircuitBuilder::new().x(q0).stabilize(&[q0]).build();
+    assert_eq!(result, Simulator::new().run(&plain)?);
+    Ok(())
+}
+
+#[test]
+fn test_session_pauses_and_matches_run() -> Result<(), OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .cnot(q0, q1)
+        .stabilize_into(q1, "m")
+        .build();
+    let simulator = Simulator::new();
+
+    let mut session = simulator.session(&circuit)?;
+    assert_eq!(session.position(), 0);
+    assert_eq!(session.step()?, Some(&circuit.operations()[0]));
+    // QDU 1 is untouched until the controlled flip runs
+    assert!(session.peek_state().network[&1].core_state[1].norm_sqr() < 1e-12);
+
+    session.run_to(2)?;
+    assert!((session.peek_state().network[&1].core_state[1].norm_sqr() - 1.0).abs() < 1e-12);
+    assert!(session.result().all_stable_outcomes().is_empty());
+    assert!(session.run_to(1).is_err());
+    assert!(session.run_to(4).is_err());
+
+    let result = session.finish()?;
+    assert_eq!(result.classical_bit("m"), Some(1));
+    assert_eq!(result, simulator.run(&circuit)?);
+
+    let mut done = simulator.session(&circuit)?;
+    done.run_to(circuit.len())?;
+    assert!(done.is_finished());
+    assert_eq!(done.step()?, None);
+    Ok(())
+}
+
+#[test]
+fn test_rebonding_overwrites_the_shared_bond() -> Result<(), OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    // The first bond is taken on |10>, the second on |01>
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .cnot(q0, q1)
+        .x(q0)
+        .cnot(q0, q1)
+        .build();
+
+    let result = Simulator::new().run_with_state(&circuit)?;
+    let state = result.final_state().unwrap();
+    for (node, neighbor) in [(0, 1), (1, 0)] {
+        let bond = &state.network[&node].bonds[&neighbor];
+        assert_eq!(bond.len(), 4);
+        assert!((bond[1].norm_sqr() - 1.0).abs() < 1e-12);
+        assert!([0, 2, 3].iter().all(|&k| bond[k].norm_sqr() < 1e-12));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_resume_matches_uninterrupted_run() -> Result<(), OnqError> {
+    use onq::simulation::Checkpoint;
+
+    let (q0, q1, q2) = (qid(0), qid(1), qid(2));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .phase(q1, 0.3)
+        .h(q1)
+        .stabilize(&[q0])
+        .cnot(q1, q2)
+        .t(q2)
+        .stabilize(&[q1, q2])
+        .build();
+    let simulator = Simulator::new();
+
+    let mut session = simulator.session(&circuit)?;
+    session.run_to(5)?;
+    let path = std::env::temp_dir().join(format!("onq-checkpoint-{}.txt", std::process::id()));
+    session.checkpoint()?.save(&path)?;
+    let checkpoint = Checkpoint::load(&path)?;
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(checkpoint.outcomes().len(), 1);
+    assert_eq!(checkpoint.qdu_order(), vec![q0, q1, q2]);
+    let remaining = session.remaining_operations().to_vec();
+    let resumed = simulator.resume(&checkpoint, remaining)?;
+    assert_eq!(resumed, simulator.run(&circuit)?);
+
+    assert!(matches!(
+        "not a checkpoint".parse::<Checkpoint>(),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+    assert!(matches!(
+        Checkpoint::load(std::env::temp_dir().join("onq-missing-checkpoint.txt")),
+        Err(OnqError::SimulationError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_profiling_records_every_operation() -> Result<(), OnqError> {
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .cnot(q0, q1)
+        .phase(q1, 0.5)
+        .stabilize(&[q0, q1])
+        .build();
+
+    let plain = Simulator::new().run(&circuit)?;
+    assert!(plain.profile().is_none());
+
+    // Profiling also takes Clifford-analog circuits off the stabilizer tableau
+    let config = SimulatorConfig::new()
+        .with_profiling(true)
+        .with_backend(onq::simulation::Backend::StabilizerTableau);
+    let profiled = Simulator::with_config(config).run(&circuit)?;
+    assert_eq!(profiled, plain);
+    let profile = profiled.profile().unwrap();
+    assert_eq!(profile.per_position().len(), circuit.len());
+    let kinds: Vec<_> = profile
+        .per_kind()
+        .map(|(kind, count, _)| (kind, count))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("ControlledInteraction", 1),
+            ("InteractionPattern", 1),
+            ("PhaseShift", 1),
+            ("Stabilize", 1)
+        ]
+    );
+    assert_eq!(profile.count("Relax"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_progress_reports_streamed_and_tableau_runs() -> Result<(), OnqError> {
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let config = SimulatorConfig::new().with_backend(onq::simulation::Backend::StabilizerTableau);
+    let simulator = Simulator::with_config(config).with_progress(move |progress| {
+        sink.lock().unwrap().push(*progress);
+    });
+
+    // Clifford-analog circuits report once, on completion
+    let (q0, q1) = (qid(0), qid(1));
+    let bell = CircuitBuilder::new()
+        .h(q0)
+        .cnot(q0, q1)
+        .stabilize(&[q0, q1])
+        .build();
+    simulator.run(&bell)?;
+    let last = seen.lock().unwrap().drain(..).collect::<Vec<_>>();
+    assert_eq!(last.len(), 1);
+    assert_eq!(
+        (last[0].completed, last[0].total, last[0].qdus),
+        (3, Some(3), 2)
+    );
+
+    let qdus: HashSet<QduId> = [q0, q1].into_iter().collect();
+    let ops = (0..5).map(|_| Operation::PhaseShift {
+        target: q1,
+        theta: 0.1,
+    });
+    simulator.run_stream(&qdus, ops)?;
+    let streamed = seen.lock().unwrap();
+    assert_eq!(streamed.len(), 5);
+    assert!(streamed.iter().all(|p| p.total.is_none() && p.qdus == 2));
+    assert_eq!(streamed[4].completed, 5);
+    Ok(())
+}
+
+#[test]
+fn test_global_phase_tracking_factors_out_local_phases() -> Result<(), OnqError> {
+    use onq::simulation::Checkpoint;
+
+    let q0 = qid(0);
+    let tracking = Simulator::with_config(SimulatorConfig::new().with_global_phase_tracking(true));
+    let plain = CircuitBuilder::new().x(q0).build();
+    let rephased = CircuitBuilder::new().x(q0).phase(q0, 0.5).build();
+
+    let local_state =
+        |result: &SimulationResult| result.final_state().unwrap().network[&0].core_state;
+
+    // Without tracking the phase stays in the local state and is never reported.
+    let untracked = Simulator::new().run_with_state(&rephased)?;
+    assert_eq!(untracked.global_phase(), 0.0);
+    assert_ne!(
+        local_state(&untracked),
+        local_state(&Simulator::new().run_with_state(&plain)?)
+    );
+
+    let result = tracking.run_with_state(&rephased)?;
+    assert!((result.global_phase() - 0.5).abs() < 1e-12);
+    let [a, b] = local_state(&result);
+    let [c, d] = local_state(&tracking.run_with_state(&plain)?);
+    assert!((a - c).norm() < 1e-12 && (b - d).norm() < 1e-12);
+    let amplitude = result.amplitude_of(&[(q0, 1)])?;
+    assert!((amplitude.arg() - 0.5).abs() < 1e-12);
+
+    // Accumulated phases wrap into (-π, π].
+    let wrapped = CircuitBuilder::new()
+        .x(q0)
+        .phase(q0, 3.0)
+        .phase(q0, 3.0)
+        .build();
+    let result = tracking.run_with_state(&wrapped)?;
+    assert!((result.global_phase() - (6.0 - 2.0 * PI)).abs() < 1e-12);
+
+    // Checkpoints carry the phase across a resume.
+    let mut session = tracking.session(&wrapped)?;
+    session.step()?;
+    session.step()?;
+    let checkpoint: Checkpoint = session.checkpoint()?.to_string().parse()?;
+    assert_eq!(checkpoint.global_phase(), session.global_phase());
+    let resumed = tracking.resume(&checkpoint, session.remaining_operations().to_vec())?;
+    assert!((resumed.global_phase() - result.global_phase()).abs() < 1e-12);
+    Ok(())
+}
+
+#[test]
+fn test_renormalization_policy_rescales_drifted_states() -> Result<(), OnqError> {
+    use num_complex::Complex;
+    use onq::PotentialityState;
+    use onq::simulation::{RenormalizationPolicy, ValidationMode, ValidationTiming};
+
+    let (q0, q1) = (qid(0), qid(1));
+    // Node 0 has drifted to a squared norm of 1.1
+    let mut drifted = PotentialityState::new();
+    drifted.network.get_mut(&0).unwrap().core_state =
+        [Complex::new(1.1f64.sqrt(), 0.0), Complex::new(0.0, 0.0)];
+    let circuit = CircuitBuilder::new()
+        .x(q0)
+        .x(q1)
+        .stabilize(&[q0, q1])
+        .build();
+    let config = SimulatorConfig::new()
+        .with_validation(ValidationMode::Basic)
+        .with_validation_timing(ValidationTiming::PerOperation);
+
+    let failing = Simulator::with_config(config);
+    assert!(matches!(
+        failing.run_from_state(&circuit, drifted.clone(), &[q0, q1]),
+        Err(OnqError::Incoherence { .. })
+    ));
+
+    let warning = Simulator::with_config(
+        config.with_renormalization(RenormalizationPolicy::WarnAndRenormalize),
+    );
+    let result = warning.run_from_state(&circuit, drifted.clone(), &[q0, q1])?;
+    check_stable_state(&result, q0, 1);
+    assert_eq!(result.renormalizations().len(), 1);
+    let record = result.renormalizations()[0];
+    assert_eq!((record.operation, record.qdu), (0, q0));
+    assert!((record.norm_sq - 1.1).abs() < 1e-12);
+
+    let silent = Simulator::with_config(
+        config.with_renormalization(RenormalizationPolicy::SilentRenormalize),
+    );
+    let quiet = silent.run_from_state(&circuit, drifted, &[q0, q1])?;
+    assert_eq!(quiet, result);
+    assert!(quiet.renormalizations().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_extended_precision_agrees_with_double() -> Result<(), OnqError> {
+    use onq::simulation::Precision;
+
+    let (q0, q1) = (qid(0), qid(1));
+    let circuit = CircuitBuilder::new()
+        .h(q0)
+        .phase(q0, 0.3)
+        .h(q0)
+        .cnot(q0, q1)
+        .t(q1)
+        .h(q1)
+        .build();
+    let double = Simulator::new().run_with_state(&circuit)?;
+    let extended =
+        Simulator::with_config(SimulatorConfig::new().with_precision(Precision::Extended))
+            .run_with_state(&circuit)?;
+
+    for node in [0, 1] {
+        let [a, b] = double.final_state().unwrap().network[&node].core_state;
+        let [c, d] = extended.final_state().unwrap().network[&node].core_state;
+        assert!((a - c).norm() < 1e-15 && (b - d).norm() < 1e-15);
+    }
+    let mut stabilized = circuit.clone();
+    stabilized.add_operation(Operation::Stabilize {
+        targets: vec![q0, q1],
+    });
+    assert_eq!(
+        Simulator::new().run(&stabilized)?,
+        Simulator::with_config(SimulatorConfig::new().with_precision(Precision::Extended))
+            .run(&stabilized)?
+    );
+    assert_eq!(Precision::Extended.mantissa_bits(), 106);
+    Ok(())
+}
+
+#[test]
+fn test_stabilization_analysis_predicts_outcomes() -> Result<(), OnqError> {
+    use onq::simulation::{MaxWeight, StabilizationSeed};
+
+    let qdus: Vec<QduId> = (0..4).map(qid).collect();
+    let prepare = CircuitBuilder::new()
+        .h(qdus[0])
+        .phase(qdus[1], 0.7)
+        .h(qdus[1])
+        .x(qdus[2])
+        .h(qdus[3])
+        .t(qdus[3])
+        .build();
+    let mut stabilized = prepare.clone();
+    stabilized.add_operation(Operation::Stabilize {
+        targets: qdus.clone(),
+    });
+
+    let seeded = SimulatorConfig::new().with_stabilization_seed(StabilizationSeed::Mixed(11));
+    for simulator in [
+        Simulator::new(),
+        Simulator::with_config(seeded),
+        Simulator::with_config(SimulatorConfig::new().with_semantics(SemanticsVersion::V1)),
+        Simulator::new().with_strategy(MaxWeight),
+    ] {
+        let state = simulator
+            .run_with_state(&prepare)?
+            .final_state()
+            .unwrap()
+            .clone();
+        let analysis = simulator.analyze_stabilization(&state, &[0, 1, 2, 3])?;
+        let result = simulator.run(&stabilized)?;
+        for (qdu, node) in qdus.iter().zip(&analysis) {
+            check_stable_state(&result, *qdu, node.selected as u64);
+            assert!((node.scores[0].weight + node.scores[1].weight - 1.0).abs() < 1e-12);
+        }
+        assert!(analysis[2].filtered() && analysis[2].phase_coherence == 1.0);
+    }
+
+    let state = Simulator::new()
+        .run_with_state(&prepare)?
+        .final_state()
+        .unwrap()
+        .clone();
+    assert!(matches!(
+        Simulator::new().analyze_stabilization(&state, &[64]),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_scoring_modes_reweight_selection() -> Result<(), OnqError> {
+    use num_complex::Complex;
+    use onq::PotentialityState;
+    use onq::simulation::ScoringMode;
+
+    // Weights 0.45 / 0.55, with the Quality1 amplitude real and positive
+    let mut state = PotentialityState::new();
+    state.network.get_mut(&0).unwrap().core_state = [
+        Complex::new(0.0, 0.45f64.sqrt()),
+        Complex::new(0.55f64.sqrt(), 0.0),
+    ];
+    let q0 = qid(0);
+    let circuit = CircuitBuilder::new().stabilize(&[q0]).build();
+    let scored = |scoring| Simulator::with_config(SimulatorConfig::new().with_scoring(scoring));
+
+    let amplitude = scored(ScoringMode::AmplitudeOnly).analyze_stabilization(&state, &[0])?;
+    assert_eq!(amplitude[0].scores[1].score, amplitude[0].scores[1].weight);
+    assert!(!amplitude[0].filtered());
+
+    // C_A halves the imaginary Quality0 amplitude: scores 0.225 / 0.55 -> Quality1 passes
+    let coherence = scored(ScoringMode::CoherenceWeighted).analyze_stabilization(&state, &[0])?;
+    assert!((coherence[0].scores[0].phase_coherence - 0.5).abs() < 1e-12);
+    assert!(coherence[0].scores[1].passes_filter && coherence[0].selected == 1);
+
+    // C_B halves Quality1: scores 0.45 / 0.275 -> Quality0 passes
+    let resonance = scored(ScoringMode::ResonanceWeighted).analyze_stabilization(&state, &[0])?;
+    assert!(resonance[0].scores[0].passes_filter && resonance[0].selected == 0);
+
+    // Both: 0.225 / 0.275, left to the draw
+    let full = scored(ScoringMode::Full).analyze_stabilization(&state, &[0])?;
+    assert!((full[0].scores[0].score - 0.45).abs() < 1e-12 && !full[0].filtered());
+
+    for scoring in [
+        ScoringMode::AmplitudeOnly,
+        ScoringMode::CoherenceWeighted,
+        ScoringMode::ResonanceWeighted,
+        ScoringMode::Full,
+    ] {
+        let simulator = scored(scoring);
+        let predicted = simulator.analyze_stabilization(&state, &[0])?[0].selected;
+        let result = simulator.run_from_state(&circuit, state.clone(), &[q0])?;
+        check_stable_state(&result, q0, predicted as u64);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_qudits_evolve_and_stabilize_to_levels() -> Result<(), OnqError> {
+    use num_complex::Complex;
+    use onq::simulation::StateRepresentation;
+
+    let (zero, one) = (Complex::new(0.0, 0.0), Complex::new(1.0, 0.0));
+    // Cyclic shift |k> -> |k+1 mod 3>
+    let shift = vec![
+        vec![zero, zero, one],
+        vec![one, zero, zero],
+        vec![zero, one, zero],
+    ];
+    let (q0, q1) = (qid(0), qid(1));
+
+    let circuit = CircuitBuilder::new()
+        .qudit(q0, 3)
+        .qudit_pattern(q0, shift.clone())
+        .qudit_pattern(q0, shift.clone())
+        .x(q1)
+        .stabilize(&[q0, q1])
+        .build();
+    assert_eq!(circuit.dimension(&q0), 3);
+    assert_eq!(circuit.dimension(&q1), 2);
+    let result = Simulator::new().run(&circuit)?;
+    check_stable_state(&result, q0, 2);
+    check_stable_state(&result, q1, 1);
+
+    // An equal superposition of three levels resolves to one of them, repeatably
+    let third = Complex::new(1.0 / 3f64.sqrt(), 0.0);
+    let omega = Complex::from_polar(1.0, 2.0 * PI / 3.0);
+    let fourier = vec![
+        vec![third, third, third],
+        vec![third, third * omega, third * omega * omega],
+        vec![third, third * omega * omega, third * omega],
+    ];
+    let circuit = CircuitBuilder::new()
+        .qudit(q0, 3)
+        .qudit_pattern(q0, fourier)
+        .stabilize(&[q0])
+        .build();
+    let first = Simulator::new().run(&circuit)?;
+    let level = first
+        .get_stable_state(&q0)
+        .and_then(|s| s.get_resolved_value());
+    assert!(level.is_some_and(|level| level < 3));
+    assert_eq!(Simulator::new().run(&circuit)?, first);
+
+    // Binary operations, mismatched matrices and degenerate dimensions are rejected
+    let binary_op = CircuitBuilder::new().qudit(q0, 3).x(q0).build();
+    let wrong_size = CircuitBuilder::new()
+        .qudit(q0, 4)
+        .qudit_pattern(q0, shift.clone())
+        .build();
+    let degenerate = CircuitBuilder::new().qudit(q0, 1).stabilize(&[q0]).build();
+    for circuit in [&binary_op, &wrong_size, &degenerate] {
+        assert!(matches!(
+            Simulator::new().run(circuit),
+            Err(OnqError::InvalidOperation { .. })
+        ));
+    }
+    let density = SimulatorConfig::new().with_representation(StateRepresentation::DensityMatrix);
+    let circuit = CircuitBuilder::new()
+        .qudit(q0, 3)
+        .qudit_pattern(q0, shift)
+        .build();
+    assert!(matches!(
+        Simulator::with_config(density).run(&circuit),
+        Err(OnqError::InvalidOperation { .. })
+    ));
+
+    // Checkpoints cannot hold qudit states
+    let simulator = Simulator::new();
+    let mut session = simulator.session(&circuit)?;
+    session.step()?;
+    assert!(matches!(
+        session.checkpoint(),
+        Err(OnqError::InvalidOperation { message }) if message.contains("qudits (QDU(0))")
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_reference_frames_require_locks_to_interact() -> Result<(), OnqError> {
+    use onq::LockType;
+    use onq::ReferenceFrame;
+
+    let (q0, q1, q2) = (qid(0), qid(1), qid(2));
+    let lock = |establish| Operation::RelationalLock {
+        qdu1: q2,
+        qdu2: q1,
+        lock_type: LockType::BellPhiPlus,
+        establish,
+    };
+    let framed = || {
+        CircuitBuilder::new()
+            .in_frame(q0, ReferenceFrame::new(1))
+            .in_frame(q1, ReferenceFrame::new(1))
+            .in_frame(q2, ReferenceFrame::new(2))
+            .x(q1)
+    };
+
+    // Same frame: unrestricted
+    let result = Simulator::new().run(&framed().cnot(q1, q0).stabilize(&[q0]).build())?;
+    check_stable_state(&result, q0, 1);
+
+    // Across frames: rejected until locked, and again once the lock is released
+    let unlocked = framed().cnot(q1, q2).build();
+    assert!(matches!(
+        Simulator::new().run(&unlocked),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    let locked = framed()
+        .add_op(lock(true))
+        .cnot(q1, q2)
+        .stabilize(&[q2])
+        .build();
+    check_stable_state(&Simulator::new().run(&locked)?, q2, 1);
+    let released = framed()
+        .add_op(lock(true))
+        .add_op(lock(false))
+        .cnot(q1, q2)
+        .build();
+    assert!(matches!(
+        Simulator::new().run(&released),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+
+    // A framed QDU and one in the default frame are in different frames too
+    let default_frame = framed().cnot(q2, qid(3)).build();
+    assert_eq!(default_frame.frame(&qid(3)), None);
+    assert!(matches!(
+        Simulator::new().run(&default_frame),
+        Err(OnqError::ReferenceViolation { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_boundary_model_fails_worn_down_qdus() -> Result<(), OnqError> {
+    use onq::simulation::BoundaryModel;
+
+    let (q0, q1) = (qid(0), qid(1));
+    let project = |target| Operation::Project {
+        target,
+        onto: Quality::Quality0,
+    };
+    // Integrity 0.9 per projection: 0.9, 0.81, 0.729 < 0.75 on the third
+    let model = BoundaryModel::new(0.1, 0.75);
+    let config = SimulatorConfig::new().with_boundary_model(model);
+    assert_eq!(config.boundary_model(), Some(model));
+
+    let spread = CircuitBuilder::new()
+        .add_op(project(q0))
+        .add_op(project(q0))
+        .add_op(project(q1))
+        .stabilize(&[q1])
+        .build();
+    Simulator::with_config(config).run(&spread)?;
+
+    let worn = CircuitBuilder::new()
+        .add_op(project(q1))
+        .add_op(project(q0))
+        .add_op(project(q0))
+        .stabilize(&[q0])
+        .build();
+    match Simulator::with_config(config).run(&worn) {
+        Err(OnqError::BoundaryFailure { qdu_id, .. }) => assert_eq!(qdu_id, q0),
+        other => panic!("expected a boundary failure on {}, got {:?}", q0, other),
+    }
+    Simulator::with_config(config.without_boundary_model()).run(&worn)?;
+    Ok(())
+}
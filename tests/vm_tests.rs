@@ -2,7 +2,9 @@
 
 use onq::core::QduId;
 use onq::operations::Operation;
-use onq::vm::{Instruction, ProgramBuilder, OnqVm}; // Import VM components
+use onq::vm::{Breakpoint, HaltReason, Instruction, InstructionMacro, Program, ProgramBuilder, OnqVm, RegisterChange, VmCheckpoint, TraceEvent, TraceLevel, defer_stabilization, link};
+use onq::simulation::SeedMode; // Import VM components
+use onq::OnqError;
 
 // Helper for QduId creation
 fn qid(id: u64) -> QduId {
@@ -109,7 +111,1779 @@ fn test_vm_conditional_quantum() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_defer_stabilization_rejects_conditional_corrections() -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = |qdu: u64, pattern_id: &str| {
+        Instruction::QuantumOp(Operation::InteractionPattern { target: qid(qdu), pattern_id: pattern_id.to_string() })
+    };
+    // Same shape as test_vm_conditional_quantum: flip q1 only if m0 == 0
+    let flip_if_zero = ProgramBuilder::new()
+        .pb_add(pattern(0, "Superposition"))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::BranchIfZero { register: "m0".to_string(), label: "apply_flip".to_string() })
+        .pb_add(Instruction::Jump("after_flip".to_string()))
+        .pb_add(Instruction::Label("apply_flip".to_string()))
+        .pb_add(pattern(1, "QualityFlip"))
+        .pb_add(Instruction::Label("after_flip".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(1)] })
+        .pb_add(Instruction::Record { qdu: qid(1), register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    // Flip q1 only if m0 == 1
+    let flip_if_one = ProgramBuilder::new()
+        .pb_add(pattern(0, "Superposition"))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::BranchIfZero { register: "m0".to_string(), label: "skip".to_string() })
+        .pb_add(pattern(1, "QualityFlip"))
+        .pb_add(Instruction::Label("skip".to_string()))
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    // ControlledInteraction flips the target whatever the control's state, so neither
+    // correction has a controlled equivalent
+    for program in [&flip_if_zero, &flip_if_one] {
+        OnqVm::new().run(program)?;
+        match defer_stabilization(program) {
+            Err(OnqError::InvalidOperation { message }) => {
+                assert!(message.contains("PC 3"), "Unexpected message: {}", message);
+                assert!(message.contains("no controlled equivalent"), "Unexpected message: {}", message);
+            }
+            other => panic!("Expected InvalidOperation, got {:?}", other),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_defer_stabilization_preserves_register_distributions() -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = |qdu: u64, pattern_id: &str| {
+        Instruction::QuantumOp(Operation::InteractionPattern { target: qid(qdu), pattern_id: pattern_id.to_string() })
+    };
+    // q0 is bonded to q1 before its stabilization; q1 and q2 evolve after it
+    let program = ProgramBuilder::new()
+        .pb_add(pattern(0, "Superposition"))
+        .pb_add(Instruction::QuantumOp(Operation::ControlledInteraction { control: qid(0), target: qid(1), pattern_id: "QualityFlip".to_string() }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(pattern(1, "PhiRotate"))
+        .pb_add(pattern(2, "Superposition"))
+        .pb_add(Instruction::StabilizeInto { targets: vec![(qid(1), "m1".to_string()), (qid(2), "m2".to_string())] })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let deferred = defer_stabilization(&program)?;
+    assert_eq!(deferred.instructions()[4], Instruction::Stabilize { targets: vec![qid(0), qid(1), qid(2)] });
+    let original = OnqVm::new().run_shots(&program, 200)?;
+    let rewritten = OnqVm::new().run_shots(&deferred, 200)?;
+    for register in ["m0", "m1", "m2"] {
+        assert_eq!(original.register_counts(register), rewritten.register_counts(register), "{}", register);
+    }
+    assert_eq!(original.outcome_counts(), rewritten.outcome_counts());
+    assert!(original.register_count("m0", 0) > 0 && original.register_count("m0", 1) > 0);
+    Ok(())
+}
+
+#[test]
+fn test_defer_stabilization_reports_impossible_rewrite() -> Result<(), Box<dyn std::error::Error>> {
+    // The recorded outcome feeds classical arithmetic, which cannot be deferred
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::Addi { r_dest: "k".to_string(), r_src: "m0".to_string(), value: 1 })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    match defer_stabilization(&program) {
+        Err(OnqError::InvalidOperation { message }) => {
+            assert!(message.contains("PC 2"), "Unexpected message: {}", message);
+            assert!(message.contains("'m0'"), "Unexpected message: {}", message);
+        }
+        other => panic!("Expected InvalidOperation, got {:?}", other),
+    }
+    Ok(())
+}
+
 // Add more tests later:
 // - Test other classical ops (And, Or, Xor, CmpGt etc.)
 // - Test loops involving quantum state preparation/stabilization inside
 // - Test error handling (e.g., undefined labels, invalid record target)
+
+#[test]
+fn test_pipeline_program_evolution() -> Result<(), Box<dyn std::error::Error>> {
+    use onq::CircuitBuilder;
+    use onq::pipeline::{PipelineBuilder, PipelineOutput, StabilizationSpec};
+
+    // Preparation flips q0; the program branches on it and flips q1 only when q0 was |1>.
+    // Labels in the program must still resolve after the preparation is prepended.
+    let (q0, q1) = (qid(0), qid(1));
+    let prepare = CircuitBuilder::new()
+        .add_op(Operation::InteractionPattern { target: q0, pattern_id: "QualityFlip".to_string() })
+        .build();
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::Stabilize { targets: vec![q0] })
+        .pb_add(Instruction::Record { qdu: q0, register: "m0".to_string() })
+        .pb_add(Instruction::BranchIfZero { register: "m0".to_string(), label: "skip".to_string() })
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: q1, pattern_id: "QualityFlip".to_string() }))
+        .pb_add(Instruction::Label("skip".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![q1] })
+        .pb_add(Instruction::Record { qdu: q1, register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    // Program evolution stabilizes on its own, so the default `All` is rejected
+    assert!(PipelineBuilder::new().evolve_program(program.clone()).build().is_err());
+
+    let pipeline = PipelineBuilder::new()
+        .prepare(prepare)
+        .evolve_program(program)
+        .stabilize(StabilizationSpec::None)
+        .build()?;
+    match pipeline.run()? {
+        PipelineOutput::Vm { registers, final_state } => {
+            assert_eq!(registers.get("m0"), Some(&1));
+            assert_eq!(registers.get("m1"), Some(&1));
+            assert!(final_state.is_some());
+        }
+        other => panic!("Expected VM output, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_vm_float_registers_drive_phase() -> Result<(), Box<dyn std::error::Error>> {
+    let q0 = qid(0);
+    let fr = |s: &str| s.to_string();
+    let superposition = || Instruction::QuantumOp(Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() });
+
+    // theta = (PI / 2) * 2.0 computed at runtime; H · Phase(PI) · H maps |0> to |1>
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::FLoad { register: fr("half_pi"), value: std::f64::consts::FRAC_PI_2 })
+        .pb_add(Instruction::FLoad { register: fr("two"), value: 2.0 })
+        .pb_add(Instruction::FMul { r_dest: fr("theta"), r_src1: fr("half_pi"), r_src2: fr("two") })
+        .pb_add(Instruction::FAdd { r_dest: fr("sum"), r_src1: fr("theta"), r_src2: fr("two") })
+        .pb_add(Instruction::FCmp { r_dest: fr("gt"), r_src1: fr("theta"), r_src2: fr("half_pi") })
+        .pb_add(Instruction::LoadImmediate { register: fr("bits"), value: 1.5f64.to_bits() })
+        .pb_add(Instruction::FFromBits { r_dest: fr("decoded"), r_src: fr("bits") })
+        .pb_add(superposition())
+        .pb_add(Instruction::FPhaseShift { target: q0, theta_reg: fr("theta") })
+        .pb_add(superposition())
+        .pb_add(Instruction::Stabilize { targets: vec![q0] })
+        .pb_add(Instruction::Record { qdu: q0, register: fr("m") })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert!((vm.get_float_register("theta") - std::f64::consts::PI).abs() < 1e-12);
+    assert!((vm.get_float_register("sum") - (std::f64::consts::PI + 2.0)).abs() < 1e-12);
+    assert_eq!(vm.get_float_register("decoded"), 1.5);
+    assert_eq!(vm.get_classical_register("gt"), 1);
+    assert_eq!(vm.get_classical_register("m"), 1);
+    // The float bank is separate from the u64 registers
+    assert_eq!(vm.get_classical_register("theta"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_vm_quantum_op_dyn_reads_angle() -> Result<(), Box<dyn std::error::Error>> {
+    let (q0, q1) = (qid(0), qid(1));
+    let layer = || Instruction::QuantumOp(Operation::BroadcastPattern { targets: vec![q0, q1], pattern_id: "Superposition".to_string() });
+
+    // The template angle (0.0) is replaced by the runtime value PI, flipping both QDUs
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::FLoad { register: "angle".to_string(), value: std::f64::consts::PI })
+        .pb_add(layer())
+        .pb_add(Instruction::QuantumOpDyn {
+            op_template: Operation::BroadcastPhaseShift { targets: vec![q0, q1], theta: 0.0 },
+            angle_register: "angle".to_string(),
+        })
+        .pb_add(layer())
+        .pb_add(Instruction::Stabilize { targets: vec![q0, q1] })
+        .pb_add(Instruction::Record { qdu: q0, register: "m0".to_string() })
+        .pb_add(Instruction::Record { qdu: q1, register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(vm.get_classical_register("m0"), 1);
+    assert_eq!(vm.get_classical_register("m1"), 1);
+
+    // Templates without an angle are rejected when building
+    let invalid = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOpDyn {
+            op_template: Operation::InteractionPattern { target: q0, pattern_id: "Superposition".to_string() },
+            angle_register: "angle".to_string(),
+        })
+        .build();
+    assert!(invalid.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_program_from_circuit_matches_simulator() -> Result<(), OnqError> {
+    use onq::vm::Program;
+    use onq::{CircuitBuilder, Simulator};
+
+    let mut builder = CircuitBuilder::new();
+    let (q0, idle, q2) = (builder.allocate_qdu(), builder.allocate_qdu(), builder.allocate_qdu());
+    let circuit = builder
+        .h(q0)
+        .x(q2)
+        .phase(q2, 0.4)
+        .stabilize(&[q0])
+        .sx(q0)
+        .stabilize(&[q0, q2])
+        .build();
+
+    let program = Program::from_circuit(&circuit);
+    // Identity for the idle QDU, 4 ops, 2 stabilizations with 3 records, Halt
+    assert_eq!(program.instruction_count(), 1 + 4 + 2 + 3 + 1);
+    assert_eq!(
+        program.instructions()[0],
+        Instruction::QuantumOp(Operation::InteractionPattern {
+            target: idle,
+            pattern_id: "Identity".to_string(),
+        })
+    );
+
+    let expected = Simulator::new().run(&circuit)?;
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    for qdu in [q0, q2] {
+        assert_eq!(
+            Some(vm.get_classical_register(&Program::register_for(qdu))),
+            expected.get_stable_state(&qdu).and_then(|s| s.get_resolved_value())
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_program_from_circuit_records_classical_bits() -> Result<(), OnqError> {
+    use onq::vm::Program;
+    use onq::CircuitBuilder;
+
+    let circuit = CircuitBuilder::new()
+        .x(qid(0))
+        .stabilize_into(qid(0), "m0")
+        .x(qid(0))
+        .stabilize(&[qid(0)])
+        .build();
+    let mut vm = OnqVm::new();
+    vm.run(&Program::from_circuit(&circuit))?;
+    assert_eq!(vm.get_classical_register("m0"), 1);
+    assert_eq!(vm.get_classical_register(&Program::register_for(qid(0))), 0);
+    Ok(())
+}
+
+#[test]
+fn test_vm_amplitude_and_probability_queries() -> Result<(), OnqError> {
+    use onq::vm::Program;
+    use onq::CircuitBuilder;
+
+    // QDUs 5 and 9 occupy IVM nodes 0 and 1; the queries take QDU IDs directly
+    let (q5, q9) = (qid(5), qid(9));
+    let circuit = CircuitBuilder::new().h(q5).x(q9).build();
+    let mut vm = OnqVm::new();
+    assert!(vm.amplitude_of(&[(q5, 0)]).is_err());
+    vm.run(&Program::from_circuit(&circuit))?;
+
+    let amplitude = vm.amplitude_of(&[(q5, 1), (q9, 1)])?;
+    assert!((amplitude.re - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+    assert!((vm.probability_of_outcome(&[(q5, 0)])? - 0.5).abs() < 1e-12);
+    assert_eq!(vm.probability_of_outcome(&[(q9, 0)])?, 0.0);
+
+    assert!(matches!(vm.amplitude_of(&[(qid(0), 0)]), Err(OnqError::ReferenceViolation { .. })));
+    assert!(vm.amplitude_of(&[(q5, 2)]).is_err());
+    assert!(vm.amplitude_of(&[(q5, 0), (q5, 1)]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_reports_progress_per_instruction() -> Result<(), Box<dyn std::error::Error>> {
+    use onq::simulation::Progress;
+    use std::sync::{Arc, Mutex};
+
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let seen: Arc<Mutex<Vec<Progress>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let mut vm = OnqVm::new().with_progress(move |progress| sink.lock().unwrap().push(*progress));
+    vm.run(&program)?;
+
+    let seen = seen.lock().unwrap();
+    let completed: Vec<usize> = seen.iter().map(|p| p.completed).collect();
+    assert_eq!(completed, vec![1, 2, 3, 4]);
+    assert!(seen.iter().all(|p| p.total.is_none() && p.qdus == 1));
+    Ok(())
+}
+
+#[test]
+fn test_vm_branch_if_not_zero() -> Result<(), Box<dyn std::error::Error>> {
+    // Counts down from 5, looping while the counter is non-zero
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "count".to_string(), value: 5 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::Addi { r_dest: "steps".to_string(), r_src: "steps".to_string(), value: 1 })
+        .pb_add(Instruction::Addi { r_dest: "count".to_string(), r_src: "count".to_string(), value: u64::MAX })
+        .pb_add(Instruction::BranchIfNotZero { register: "count".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    assert_eq!(program.instruction_count(), 5);
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(vm.get_classical_register("count"), 0);
+    assert_eq!(vm.get_classical_register("steps"), 5);
+
+    // Flip q1 unless m0 == 1, in one branch instead of BranchIfZero + Jump
+    let conditional = ProgramBuilder::new()
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "m0".to_string(), label: "skip".to_string() })
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(1),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Label("skip".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(1)] })
+        .pb_add(Instruction::Record { qdu: qid(1), register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    let mut vm = OnqVm::new();
+    vm.run(&conditional)?;
+    assert_eq!(vm.get_classical_register("m1"), 1);
+
+    assert!(defer_stabilization(&conditional).is_err());
+
+    assert!(ProgramBuilder::new()
+        .pb_add(Instruction::BranchIfNotZero { register: "r".to_string(), label: "nowhere".to_string() })
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_compare_and_branch() -> Result<(), Box<dyn std::error::Error>> {
+    // for i in 0..limit { sum += i }, with the loop test fused into one branch
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "limit".to_string(), value: 5 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::BranchIfGe { r1: "i".to_string(), r2: "limit".to_string(), label: "done".to_string() })
+        .pb_add(Instruction::OnqAdd { r_dest: "sum".to_string(), r_src1: "sum".to_string(), r_src2: "i".to_string() })
+        .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+        .pb_add(Instruction::Jump("loop".to_string()))
+        .pb_add(Instruction::Label("done".to_string()))
+        // Equal, not-equal and less-than each skip one marker
+        .pb_add(Instruction::BranchIfEq { r1: "i".to_string(), r2: "limit".to_string(), label: "eq".to_string() })
+        .pb_add(Instruction::LoadImmediate { register: "eq_missed".to_string(), value: 1 })
+        .pb_add(Instruction::Label("eq".to_string()))
+        .pb_add(Instruction::BranchIfNe { r1: "i".to_string(), r2: "limit".to_string(), label: "ne".to_string() })
+        .pb_add(Instruction::LoadImmediate { register: "ne_fell_through".to_string(), value: 1 })
+        .pb_add(Instruction::Label("ne".to_string()))
+        .pb_add(Instruction::BranchIfLt { r1: "limit".to_string(), r2: "sum".to_string(), label: "lt".to_string() })
+        .pb_add(Instruction::LoadImmediate { register: "lt_missed".to_string(), value: 1 })
+        .pb_add(Instruction::Label("lt".to_string()))
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(vm.get_classical_register("sum"), 10);
+    assert_eq!(vm.get_classical_register("i"), 5);
+    assert_eq!(vm.get_classical_register("eq_missed"), 0);
+    assert_eq!(vm.get_classical_register("ne_fell_through"), 1);
+    assert_eq!(vm.get_classical_register("lt_missed"), 0);
+
+    assert!(ProgramBuilder::new()
+        .pb_add(Instruction::BranchIfLt { r1: "a".to_string(), r2: "b".to_string(), label: "nowhere".to_string() })
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_call_and_return() -> Result<(), Box<dyn std::error::Error>> {
+    // A correction subroutine written once and called three times, one call nested
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::Call("flip".to_string()))
+        .pb_add(Instruction::Call("flip_twice".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::Halt)
+        .pb_add(Instruction::Label("flip".to_string()))
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Addi { r_dest: "calls".to_string(), r_src: "calls".to_string(), value: 1 })
+        .pb_add(Instruction::Return)
+        .pb_add(Instruction::Label("flip_twice".to_string()))
+        .pb_add(Instruction::Call("flip".to_string()))
+        .pb_add(Instruction::Call("flip".to_string()))
+        .pb_add(Instruction::Return)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(vm.get_classical_register("calls"), 3);
+    assert_eq!(vm.get_classical_register("m0"), 1);
+
+    let stray_return = ProgramBuilder::new().pb_add(Instruction::Return).build()?;
+    assert!(matches!(OnqVm::new().run(&stray_return), Err(OnqError::SimulationError { .. })));
+    assert!(ProgramBuilder::new().pb_add(Instruction::Call("nowhere".to_string())).build().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_indexed_array_memory() -> Result<(), Box<dyn std::error::Error>> {
+    // Stabilize q0..q3 (q1 and q3 flipped) in a loop body, collecting bit i into bits[i]
+    let mut builder = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(1),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(3),
+            pattern_id: "QualityFlip".to_string(),
+        }));
+    for i in 0..4 {
+        builder = builder
+            .pb_add(Instruction::Stabilize { targets: vec![qid(i)] })
+            .pb_add(Instruction::Record { qdu: qid(i), register: "bit".to_string() })
+            .pb_add(Instruction::Store { base: "bits".to_string(), index_reg: "i".to_string(), src: "bit".to_string() })
+            .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 });
+    }
+    // Read back bits[3] and an element never stored to
+    let program = builder
+        .pb_add(Instruction::LoadImmediate { register: "j".to_string(), value: 3 })
+        .pb_add(Instruction::Load { base: "bits".to_string(), index_reg: "j".to_string(), dest: "b3".to_string() })
+        .pb_add(Instruction::Load { base: "bits".to_string(), index_reg: "i".to_string(), dest: "b4".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(
+        vm.get_array("bits").into_iter().collect::<Vec<_>>(),
+        vec![(0, 0), (1, 1), (2, 0), (3, 1)]
+    );
+    assert_eq!(vm.get_array_element("bits", 1), 1);
+    assert_eq!(vm.get_classical_register("b3"), 1);
+    assert_eq!(vm.get_classical_register("b4"), 0);
+    assert!(vm.get_array("missing").is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_vm_signed_integer_ops() -> Result<(), Box<dyn std::error::Error>> {
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "three".to_string(), value: 3 })
+        .pb_add(Instruction::LoadImmediate { register: "five".to_string(), value: 5 })
+        // 3 - 5 = -2, which is less than 0 only when compared as signed
+        .pb_add(Instruction::SubS { r_dest: "diff".to_string(), r_src1: "three".to_string(), r_src2: "five".to_string() })
+        .pb_add(Instruction::CmpLtS { r_dest: "neg".to_string(), r_src1: "diff".to_string(), r_src2: "zero".to_string() })
+        .pb_add(Instruction::CmpLt { r_dest: "neg_unsigned".to_string(), r_src1: "diff".to_string(), r_src2: "zero".to_string() })
+        // 0xFE read as an 8-bit field is -2
+        .pb_add(Instruction::LoadImmediate { register: "byte".to_string(), value: 0xFE })
+        .pb_add(Instruction::SignExtend { r_dest: "wide".to_string(), r_src: "byte".to_string(), bits: 8 })
+        .pb_add(Instruction::LoadImmediate { register: "pos".to_string(), value: 0x17E })
+        .pb_add(Instruction::SignExtend { r_dest: "pos_wide".to_string(), r_src: "pos".to_string(), bits: 8 })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&program)?;
+    assert_eq!(vm.get_signed_register("diff"), -2);
+    assert_eq!(vm.get_classical_register("neg"), 1);
+    assert_eq!(vm.get_classical_register("neg_unsigned"), 0);
+    assert_eq!(vm.get_signed_register("wide"), -2);
+    assert_eq!(vm.get_signed_register("pos_wide"), 0x7E);
+
+    // i64::MIN - 1 overflows instead of wrapping
+    let overflow = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "min".to_string(), value: i64::MIN as u64 })
+        .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+        .pb_add(Instruction::SubS { r_dest: "d".to_string(), r_src1: "min".to_string(), r_src2: "one".to_string() })
+        .build()?;
+    assert!(matches!(OnqVm::new().run(&overflow), Err(OnqError::SimulationError { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_vm_rand_is_deterministic_per_seed() -> Result<(), Box<dyn std::error::Error>> {
+    // Draw 16 random basis choices in 0..2, storing them in an array
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "n".to_string(), value: 16 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::Rand { register: "basis".to_string(), upper_bound: 2 })
+        .pb_add(Instruction::Store { base: "bases".to_string(), index_reg: "i".to_string(), src: "basis".to_string() })
+        .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+        .pb_add(Instruction::BranchIfLt { r1: "i".to_string(), r2: "n".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Rand { register: "wide".to_string(), upper_bound: 0 })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let draws = |seed| -> Result<Vec<u64>, OnqError> {
+        let mut vm = OnqVm::new().with_rng_seed(seed);
+        vm.run(&program)?;
+        let first: Vec<u64> = vm.get_array("bases").into_values().collect();
+        // Every run restarts from the seed
+        vm.run(&program)?;
+        assert_eq!(vm.get_array("bases").into_values().collect::<Vec<_>>(), first);
+        Ok(first)
+    };
+    let seven = draws(7)?;
+    assert_eq!(seven.len(), 16);
+    assert!(seven.iter().all(|&basis| basis < 2));
+    assert!(seven.contains(&0) && seven.contains(&1));
+    assert_ne!(seven, draws(8)?);
+    Ok(())
+}
+
+#[test]
+fn test_vm_print_reports_intermediate_values() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Mutex};
+
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "n".to_string(), value: 3 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::Mul { r_dest: "sq".to_string(), r_src1: "i".to_string(), r_src2: "i".to_string() })
+        .pb_add(Instruction::Print {
+            format: "{}^2 = {}".to_string(),
+            registers: vec!["i".to_string(), "sq".to_string()],
+        })
+        .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+        .pb_add(Instruction::BranchIfLt { r1: "i".to_string(), r2: "n".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Print { format: "done".to_string(), registers: vec![] })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&lines);
+    let mut vm = OnqVm::new().with_output(move |line| sink.lock().unwrap().push(line.to_string()));
+    vm.run(&program)?;
+    assert_eq!(*lines.lock().unwrap(), ["0^2 = 0", "1^2 = 1", "2^2 = 4", "done"]);
+
+    assert!(ProgramBuilder::new()
+        .pb_add(Instruction::Print { format: "{} and {}".to_string(), registers: vec!["a".to_string()] })
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_run_with_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::HashMap;
+
+    // Prepares |x> on two QDUs from the input bits, then reads them back
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+        .pb_add(Instruction::And { r_dest: "bit0".to_string(), r_src1: "x".to_string(), r_src2: "one".to_string() })
+        .pb_add(Instruction::BranchIfZero { register: "bit0".to_string(), label: "skip0".to_string() })
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Label("skip0".to_string()))
+        .pb_add(Instruction::CmpLt { r_dest: "low".to_string(), r_src1: "x".to_string(), r_src2: "two".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "low".to_string(), label: "skip1".to_string() })
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(1),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Label("skip1".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0), qid(1)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::Record { qdu: qid(1), register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let mut vm = OnqVm::new();
+    for x in 0..4 {
+        let inputs = HashMap::from([("x".to_string(), x), ("two".to_string(), 2)]);
+        vm.run_with_inputs(&program, &inputs)?;
+        assert_eq!(vm.get_classical_register("m0"), x & 1);
+        assert_eq!(vm.get_classical_register("m1"), x >> 1);
+        assert_eq!(vm.get_classical_register("x"), x);
+    }
+
+    // Inputs do not leak into later runs
+    vm.run(&program)?;
+    assert_eq!(vm.get_classical_register("x"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_vm_run_result_summarizes_the_run() -> Result<(), Box<dyn std::error::Error>> {
+    // Flips QDU 0 twice, stabilizing after each flip, then halts
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "n".to_string(), value: 2 })
+        .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m".to_string() })
+        .pb_add(Instruction::Sub { r_dest: "n".to_string(), r_src1: "n".to_string(), r_src2: "one".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "n".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let result = OnqVm::new().run(&program)?;
+    assert_eq!(result.halt_reason(), HaltReason::Halted);
+    assert_eq!(result.instruction_count(), 2 + 2 * 5 + 1);
+    assert_eq!(result.register("m"), 0);
+    assert_eq!(result.classical_memory().get("n"), Some(&0));
+    let outcomes: Vec<_> = result
+        .stabilizations()
+        .iter()
+        .map(|event| (event.pc, event.outcomes[&qid(0)]))
+        .collect();
+    assert_eq!(outcomes, [(3, 1), (3, 0)]);
+
+    // Running off the end is reported separately from Halt
+    let unhalted = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "x".to_string(), value: 7 })
+        .build()?;
+    let result = OnqVm::new().run(&unhalted)?;
+    assert_eq!(result.halt_reason(), HaltReason::EndOfProgram);
+    assert_eq!(result.instruction_count(), 1);
+    assert!(result.stabilizations().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_vm_trace_events() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    // Flips QDU 0, stabilizes it and skips the NoOp if the outcome is 1
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "m".to_string(), label: "done".to_string() })
+        .pb_add(Instruction::NoOp)
+        .pb_add(Instruction::Label("done".to_string()))
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let traced = |level| -> Result<Vec<TraceEvent>, OnqError> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        OnqVm::new()
+            .with_trace(level, move |event| sink.lock().unwrap().push(event.clone()))
+            .run(&program)?;
+        Ok(events.lock().unwrap().clone())
+    };
+
+    assert_eq!(
+        traced(TraceLevel::Events)?,
+        [
+            TraceEvent::RunStarted { qdus: 1 },
+            TraceEvent::Stabilized { pc: 1, outcomes: BTreeMap::from([(qid(0), 1)]) },
+            TraceEvent::Branch { pc: 3, target: 5, taken: true },
+            TraceEvent::RunFinished { reason: HaltReason::Halted, instructions: 5 },
+        ]
+    );
+
+    // Instruction events come on top, one per executed instruction
+    let events = traced(TraceLevel::Instructions)?;
+    let executed: Vec<usize> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::Instruction { pc, .. } => Some(*pc),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(executed, [0, 1, 2, 3, 5]);
+    assert_eq!(events.len(), 4 + executed.len());
+
+    assert!(traced(TraceLevel::Off)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_vm_breakpoints_and_single_step() -> Result<(), Box<dyn std::error::Error>> {
+    // Superposes QDU 0, then flips QDU 1 only if QDU 0 stabilized to 1
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "Superposition".to_string(),
+        }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m0".to_string() })
+        .pb_add(Instruction::BranchIfZero { register: "m0".to_string(), label: "measure".to_string() })
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(1),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Label("measure".to_string()))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(1)] })
+        .pb_add(Instruction::Record { qdu: qid(1), register: "m1".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    let expected = OnqVm::new().run(&program)?;
+
+    let mut vm = OnqVm::new();
+    vm.set_breakpoint(0);
+    vm.set_breakpoint("measure");
+    assert_eq!(vm.breakpoints(), [Breakpoint::Pc(0), Breakpoint::Label("measure".to_string())]);
+
+    // A breakpoint at the first instruction pauses before anything runs
+    let paused = vm.run(&program)?;
+    assert_eq!(paused.halt_reason(), HaltReason::Breakpoint { pc: 0 });
+    assert_eq!(paused.instruction_count(), 0);
+
+    // Stepping executes one instruction at a time, with the state inspectable between them
+    let stepped = vm.step()?;
+    assert_eq!(stepped.halt_reason(), HaltReason::Step);
+    assert_eq!(vm.program_counter(), 1);
+    assert!((vm.probability_of_outcome(&[(qid(0), 1)])? - 0.5).abs() < 1e-9);
+    vm.step()?;
+    vm.step()?;
+    assert_eq!(vm.get_classical_register("m0"), expected.register("m0"));
+
+    // Continuing stops at the label, whichever way the branch went
+    let paused = vm.continue_run()?;
+    assert_eq!(paused.halt_reason(), HaltReason::Breakpoint { pc: 5 });
+    assert!(paused.halt_reason().is_paused());
+    assert_eq!(vm.probability_of_outcome(&[(qid(1), expected.register("m1") as u8)])?, 1.0);
+
+    // ...and then runs to the end, with the same outcome as an uninterrupted run
+    let finished = vm.continue_run()?;
+    assert_eq!(finished.halt_reason(), HaltReason::Halted);
+    assert!(vm.is_halted());
+    assert_eq!(finished.classical_memory(), expected.classical_memory());
+    assert_eq!(finished.stabilizations(), expected.stabilizations());
+    assert_eq!(finished.instruction_count(), expected.instruction_count());
+
+    // A halted run cannot be resumed
+    assert!(matches!(vm.step(), Err(OnqError::InvalidOperation { .. })));
+    assert!(matches!(OnqVm::new().continue_run(), Err(OnqError::InvalidOperation { .. })));
+
+    vm.remove_breakpoint(0);
+    vm.remove_breakpoint("measure");
+    assert_eq!(vm.run(&program)?.halt_reason(), HaltReason::Halted);
+    Ok(())
+}
+
+#[test]
+fn test_vm_register_watchpoints() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Mutex};
+
+    // Counts i down from 3, recording a fresh outcome of a flipped QDU into "m" each pass
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "i".to_string(), value: 3 })
+        .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "QualityFlip".to_string(),
+        }))
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m".to_string() })
+        .pb_add(Instruction::Sub { r_dest: "i".to_string(), r_src1: "i".to_string(), r_src2: "one".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "i".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    // Callbacks see every change without pausing
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&changes);
+    let mut vm = OnqVm::new();
+    vm.set_watch_callback("m", move |change| sink.lock().unwrap().push(change.clone()));
+    assert_eq!(vm.run(&program)?.halt_reason(), HaltReason::Halted);
+    let seen: Vec<_> = changes.lock().unwrap().iter().map(|c| (c.pc, c.old, c.new)).collect();
+    assert_eq!(seen, [(4, 0, 1), (4, 1, 0), (4, 0, 1)]);
+
+    // A pausing watchpoint stops right after the change, and the run can be continued
+    vm.set_watchpoint("i");
+    let mut values = Vec::new();
+    let mut result = vm.run(&program)?;
+    while let HaltReason::Watchpoint(RegisterChange { register, new, .. }) = result.halt_reason() {
+        assert_eq!(register, "i");
+        assert_eq!(vm.get_classical_register("i"), new);
+        values.push(new);
+        result = vm.continue_run()?;
+    }
+    assert_eq!(values, [3, 2, 1, 0]);
+    assert_eq!(result.halt_reason(), HaltReason::Halted);
+
+    vm.clear_watchpoints();
+    assert_eq!(vm.run(&program)?.halt_reason(), HaltReason::Halted);
+    Ok(())
+}
+
+#[test]
+fn test_vm_execution_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = |pattern_id: &str| {
+        Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: pattern_id.to_string(),
+        })
+    };
+
+    // Two formulations of the same flip, applied in a loop of 4 passes
+    let looped = |body: Vec<Instruction>| {
+        let mut builder = ProgramBuilder::new()
+            .pb_add(Instruction::LoadImmediate { register: "i".to_string(), value: 4 })
+            .pb_add(Instruction::LoadImmediate { register: "one".to_string(), value: 1 })
+            .pb_add(Instruction::Label("loop".to_string()));
+        for instruction in body {
+            builder = builder.pb_add(instruction);
+        }
+        builder
+            .pb_add(Instruction::Sub { r_dest: "i".to_string(), r_src1: "i".to_string(), r_src2: "one".to_string() })
+            .pb_add(Instruction::BranchIfNotZero { register: "i".to_string(), label: "loop".to_string() })
+            .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+            .pb_add(Instruction::Halt)
+            .build()
+    };
+    let direct = looped(vec![pattern("QualityFlip")])?;
+    let conjugated = looped(vec![pattern("Superposition"), pattern("PhiRotate"), pattern("Superposition")])?;
+
+    let mut vm = OnqVm::new();
+    vm.run(&direct)?;
+    let direct_stats = vm.stats().clone();
+    assert_eq!(direct_stats.pattern_count("QualityFlip"), 4);
+    assert_eq!(direct_stats.instruction_count("BranchIfNotZero"), 4);
+    assert_eq!(direct_stats.instruction_count("Stabilize"), 1);
+    assert_eq!(direct_stats.total_operations(), 4);
+    assert_eq!(direct_stats.total_instructions(), 2 + 4 * 3 + 2);
+
+    vm.run(&conjugated)?;
+    let stats = vm.stats();
+    assert_eq!(stats.pattern_count("Superposition"), 8);
+    assert_eq!(stats.pattern_count("PhiRotate"), 4);
+    assert_eq!(stats.pattern_count("QualityFlip"), 0);
+    assert_eq!(stats.operation_count("InteractionPattern"), 12);
+    assert!(stats.total_instructions() > direct_stats.total_instructions());
+    Ok(())
+}
+
+#[test]
+fn test_vm_instruction_limit_and_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    use std::time::Duration;
+
+    // Counts up to "n", 3 instructions per pass
+    let count_to_n = ProgramBuilder::new()
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+        .pb_add(Instruction::CmpLt { r_dest: "more".to_string(), r_src1: "i".to_string(), r_src2: "n".to_string() })
+        .pb_add(Instruction::BranchIfNotZero { register: "more".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    let inputs = std::collections::HashMap::from([("n".to_string(), 10_000)]);
+
+    // Loops far beyond a thousand instructions run under the default limit
+    let result = OnqVm::new().run_with_inputs(&count_to_n, &inputs)?;
+    assert_eq!(result.register("i"), 10_000);
+    assert_eq!(result.instruction_count(), 30_001);
+
+    // A lower limit stops the run once reached
+    let mut limited = OnqVm::new().with_instruction_limit(30_000);
+    match limited.run_with_inputs(&count_to_n, &inputs) {
+        Err(OnqError::ExecutionLimitExceeded { executed, .. }) => assert_eq!(executed, 30_000),
+        other => panic!("expected the instruction limit to be exceeded, got {:?}", other),
+    }
+    assert!(limited.is_halted());
+
+    // Without a limit, a timeout stops a program that never halts
+    let spin = ProgramBuilder::new()
+        .pb_add(Instruction::Label("spin".to_string()))
+        .pb_add(Instruction::Jump("spin".to_string()))
+        .build()?;
+    let mut timed = OnqVm::new()
+        .without_instruction_limit()
+        .with_timeout(Duration::from_millis(20));
+    match timed.run(&spin) {
+        Err(OnqError::ExecutionLimitExceeded { executed, elapsed, .. }) => {
+            assert!(elapsed >= Duration::from_millis(20));
+            assert!(executed > 0);
+        }
+        other => panic!("expected the timeout to be exceeded, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_vm_checkpoint_and_restore() -> Result<(), Box<dyn std::error::Error>> {
+    // Each pass superposes QDU 0, stabilizes it, stores the outcome and a random value
+    // in arrays via a subroutine, and accumulates an angle
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: "i".to_string(), value: 0 })
+        .pb_add(Instruction::LoadImmediate { register: "n".to_string(), value: 6 })
+        .pb_add(Instruction::FLoad { register: "step".to_string(), value: 0.1 })
+        .pb_add(Instruction::Label("loop".to_string()))
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern {
+            target: qid(0),
+            pattern_id: "Superposition".to_string(),
+        }))
+        .pb_add(Instruction::FPhaseShift { target: qid(1), theta_reg: "theta".to_string() })
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0)] })
+        .pb_add(Instruction::Call("save".to_string()))
+        .pb_add(Instruction::FAdd { r_dest: "theta".to_string(), r_src1: "theta".to_string(), r_src2: "step".to_string() })
+        .pb_add(Instruction::Addi { r_dest: "i".to_string(), r_src: "i".to_string(), value: 1 })
+        .pb_add(Instruction::BranchIfLt { r1: "i".to_string(), r2: "n".to_string(), label: "loop".to_string() })
+        .pb_add(Instruction::Halt)
+        .pb_add(Instruction::Label("save".to_string()))
+        .pb_add(Instruction::Record { qdu: qid(0), register: "m".to_string() })
+        .pb_add(Instruction::Store { base: "outcomes".to_string(), index_reg: "i".to_string(), src: "m".to_string() })
+        .pb_add(Instruction::Rand { register: "r".to_string(), upper_bound: 1000 })
+        .pb_add(Instruction::Store { base: "draws".to_string(), index_reg: "i".to_string(), src: "r".to_string() })
+        .pb_add(Instruction::Return)
+        .build()?;
+
+    // Pause inside the subroutine on the fourth pass
+    let mut vm = OnqVm::new().with_rng_seed(7);
+    vm.set_breakpoint("save");
+    vm.run(&program)?;
+    for _ in 0..3 {
+        vm.continue_run()?;
+    }
+    assert_eq!(vm.get_classical_register("i"), 3);
+    let checkpoint = vm.checkpoint()?;
+    assert_eq!(checkpoint.program_counter(), 11);
+
+    let path = std::env::temp_dir().join(format!("onq-vm-checkpoint-{}.txt", std::process::id()));
+    checkpoint.save(&path)?;
+    let loaded = VmCheckpoint::load(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(loaded.to_string(), checkpoint.to_string());
+
+    vm.clear_breakpoints();
+    let expected = vm.continue_run()?;
+
+    // Taking the checkpoint leaves the draws of the original run untouched
+    let mut untouched = OnqVm::new().with_rng_seed(7);
+    untouched.run(&program)?;
+    assert_eq!(untouched.get_array("draws"), vm.get_array("draws"));
+
+    // The restored run finishes exactly like the original
+    let mut restored = OnqVm::new();
+    restored.restore(&program, &loaded)?;
+    let result = restored.continue_run()?;
+    assert_eq!(result.halt_reason(), HaltReason::Halted);
+    assert_eq!(result.classical_memory(), expected.classical_memory());
+    assert_eq!(result.stabilizations(), expected.stabilizations());
+    assert_eq!(result.instruction_count(), expected.instruction_count());
+    assert_eq!(restored.get_array("outcomes"), vm.get_array("outcomes"));
+    assert_eq!(restored.get_array("draws"), vm.get_array("draws"));
+    assert_eq!(restored.get_float_register("theta"), vm.get_float_register("theta"));
+    for outcome in [[(qid(0), 0), (qid(1), 0)], [(qid(0), 1), (qid(1), 1)]] {
+        assert_eq!(restored.amplitude_of(&outcome)?, vm.amplitude_of(&outcome)?);
+    }
+
+    // Only paused runs can be checkpointed, into programs over the same QDUs
+    assert!(matches!(vm.checkpoint(), Err(OnqError::InvalidOperation { .. })));
+    let other = ProgramBuilder::new()
+        .pb_add(Instruction::Stabilize { targets: vec![qid(5)] })
+        .build()?;
+    assert!(matches!(OnqVm::new().restore(&other, &loaded), Err(OnqError::ReferenceViolation { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_vm_parse_asm() -> Result<(), Box<dyn std::error::Error>> {
+    let source = r#"
+; Entangle two QDUs and count the agreeing outcomes over three passes
+    load_immediate agree, 0
+    load_immediate n, 3
+    f_load theta, -0.5
+loop:
+    quantum_op interaction_pattern(q0, Superposition)
+    quantum_op controlled_interaction(q0, q1, QualityFlip)
+    quantum_op_dyn phase_shift(q1, 0.0), theta
+    stabilize [q0, q1]
+    record q0, "m 0"
+    record q1, m1
+    cmp_eq same, "m 0", m1
+    onq_add agree, agree, same
+    quantum_op relational_lock(q0, q1, BellPhiPlus, false) ; no-op release
+    quantum_op pauli_product([(q0, Z), (q1, X)], 0.25)
+    quantum_op matrix_pattern(q0, [[(1.0, 0.0), (0.0, 0.0)], [(0.0, 0.0), (1.0, 0.0)]])
+    quantum_op relax(q0, 1.0)
+    quantum_op relax(q1, 1e0)
+    addi n, n, 18446744073709551615
+    branch_if_not_zero n, loop
+    print "agree = {}\n", [agree]
+    halt
+"#;
+    let program = onq::Program::parse(source)?;
+    assert_eq!(program.instruction_count(), 20);
+    assert_eq!(program.instructions()[6], Instruction::Stabilize { targets: vec![qid(0), qid(1)] });
+    assert_eq!(
+        program.instructions()[7],
+        Instruction::Record { qdu: qid(0), register: "m 0".to_string() }
+    );
+    assert_eq!(
+        program.instructions()[12],
+        Instruction::QuantumOp(Operation::PauliProduct {
+            terms: vec![(qid(0), onq::PauliAxis::Z), (qid(1), onq::PauliAxis::X)],
+            theta: 0.25,
+        })
+    );
+
+    let mut vm = OnqVm::new().with_output(|_| {});
+    let result = vm.run(&program)?;
+    assert_eq!(result.halt_reason(), HaltReason::Halted);
+    assert_eq!(result.stabilizations().len(), 3);
+    let agreeing = result
+        .stabilizations()
+        .iter()
+        .filter(|event| event.outcomes[&qid(0)] == event.outcomes[&qid(1)])
+        .count();
+    assert_eq!(result.register("agree"), agreeing as u64);
+
+    // Errors name the offending line
+    let error = |source: &str| onq::Program::parse(source).unwrap_err().to_string();
+    assert!(error("halt\nbogus r0").contains("asm line 2: unknown mnemonic 'bogus'"));
+    assert!(error("record q0").contains("asm line 1: 'record' takes 2 operands, found 1"));
+    assert!(error("stabilize [q0, x1]").contains("expected a QDU 'q<id>' but found 'x1'"));
+    assert!(error("print \"oops").contains("unterminated string"));
+    assert!(error("a:\nhalt\na:").contains("asm line 3: label 'a' is defined twice"));
+    assert!(error("jump nowhere").contains("nowhere"));
+    assert!(error("quantum_op project(q0, Quality2)").contains("expected one of Quality0, Quality1"));
+    Ok(())
+}
+
+#[test]
+fn test_vm_asm_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use num_complex::Complex;
+    use onq::{PauliAxis, Program, Quality};
+    use onq::vm::program::LockType;
+
+    let s = |name: &str| name.to_string();
+    let h = std::f64::consts::FRAC_1_SQRT_2;
+    let ops = vec![
+        Operation::PhaseShift { target: qid(0), theta: 0.1 + 0.2 },
+        Operation::InteractionPattern { target: qid(1), pattern_id: s("Superposition") },
+        Operation::BroadcastPattern { targets: vec![qid(0), qid(2)], pattern_id: s("my pattern") },
+        Operation::BroadcastPhaseShift { targets: vec![], theta: -0.0 },
+        Operation::ControlledInteraction { control: qid(0), target: qid(1), pattern_id: s("QualityFlip") },
+        Operation::PauliProduct { terms: vec![(qid(0), PauliAxis::X), (qid(1), PauliAxis::Y)], theta: 1e-300 },
+        Operation::Permute { mapping: vec![(qid(0), qid(1)), (qid(1), qid(0))] },
+        Operation::RelationalLock { qdu1: qid(0), qdu2: qid(1), lock_type: LockType::BellPsiMinus, establish: true },
+        Operation::MatrixPattern {
+            target: qid(0),
+            matrix: [[Complex::new(h, 0.0), Complex::new(h, 0.0)], [Complex::new(0.0, h), Complex::new(0.0, -h)]],
+        },
+        Operation::QuditPattern { target: qid(3), matrix: vec![vec![Complex::new(1.0, 0.0)]] },
+        Operation::Project { target: qid(0), onto: Quality::Quality1 },
+        Operation::Relax { target: qid(0), rate: f64::INFINITY },
+        Operation::Delay { targets: vec![qid(2)], ticks: u64::MAX },
+        Operation::Snapshot { label: s("mid;point \"1\"\n") },
+        Operation::Stabilize { targets: vec![qid(0)] },
+    ];
+    let mut builder = ProgramBuilder::new().pb_add(Instruction::Label(s("entry")));
+    for op in ops {
+        builder = builder.pb_add(Instruction::QuantumOp(op));
+    }
+    let program = builder
+        .pb_add(Instruction::Stabilize { targets: vec![qid(0), qid(1)] })
+        .pb_add(Instruction::Record { qdu: qid(0), register: s("q0") })
+        .pb_add(Instruction::Label(s("a label")))
+        .pb_add(Instruction::Label(s("also_here")))
+        .pb_add(Instruction::Jump(s("entry")))
+        .pb_add(Instruction::BranchIfZero { register: s("q0"), label: s("a label") })
+        .pb_add(Instruction::BranchIfNotZero { register: s("q0"), label: s("end") })
+        .pb_add(Instruction::BranchIfEq { r1: s("a"), r2: s("b"), label: s("end") })
+        .pb_add(Instruction::BranchIfNe { r1: s("a"), r2: s("b"), label: s("end") })
+        .pb_add(Instruction::BranchIfLt { r1: s("a"), r2: s("b"), label: s("end") })
+        .pb_add(Instruction::BranchIfGe { r1: s("a"), r2: s("b"), label: s("end") })
+        .pb_add(Instruction::Call(s("sub")))
+        .pb_add(Instruction::LoadImmediate { register: s("a"), value: u64::MAX })
+        .pb_add(Instruction::Copy { source_reg: s("a"), dest_reg: s("") })
+        .pb_add(Instruction::Store { base: s("arr"), index_reg: s("i"), src: s("a") })
+        .pb_add(Instruction::Load { base: s("arr"), index_reg: s("i"), dest: s("b") })
+        .pb_add(Instruction::Rand { register: s("r"), upper_bound: 6 })
+        .pb_add(Instruction::Print { format: s("a = {}, \u{1b}b = {}"), registers: vec![s("a"), s("b")] })
+        .pb_add(Instruction::Print { format: s(""), registers: vec![] })
+        .pb_add(Instruction::NoOp)
+        .pb_add(Instruction::Addi { r_dest: s("a"), r_src: s("a"), value: 7 })
+        .pb_add(Instruction::OnqAdd { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::OnqNot { r_dest: s("c"), r_src: s("a") })
+        .pb_add(Instruction::And { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::Or { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::Xor { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::Sub { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::Mul { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::CmpEq { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::CmpGt { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::CmpLt { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::CmpLtS { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::SubS { r_dest: s("c"), r_src1: s("a"), r_src2: s("b") })
+        .pb_add(Instruction::SignExtend { r_dest: s("c"), r_src: s("a"), bits: 12 })
+        .pb_add(Instruction::FLoad { register: s("f"), value: f64::MIN_POSITIVE })
+        .pb_add(Instruction::FAdd { r_dest: s("f"), r_src1: s("f"), r_src2: s("g") })
+        .pb_add(Instruction::FMul { r_dest: s("f"), r_src1: s("f"), r_src2: s("g") })
+        .pb_add(Instruction::FCmp { r_dest: s("c"), r_src1: s("f"), r_src2: s("g") })
+        .pb_add(Instruction::FFromBits { r_dest: s("f"), r_src: s("a") })
+        .pb_add(Instruction::FPhaseShift { target: qid(1), theta_reg: s("f") })
+        .pb_add(Instruction::QuantumOpDyn {
+            op_template: Operation::BroadcastPhaseShift { targets: vec![qid(0), qid(1)], theta: 0.0 },
+            angle_register: s("f"),
+        })
+        .pb_add(Instruction::Halt)
+        .pb_add(Instruction::Label(s("sub")))
+        .pb_add(Instruction::Return)
+        .pb_add(Instruction::Label(s("end")))
+        .build()?;
+
+    let text = program.to_asm();
+    assert!(text.starts_with("entry:\n    quantum_op phase_shift(q0, 0.30000000000000004)\n"));
+    assert!(text.contains("\"a label\":\nalso_here:\n    jump entry\n"));
+    assert!(text.contains("    copy a, \"\"\n"));
+    assert!(text.contains("    quantum_op snapshot(\"mid;point \\\"1\\\"\\n\")\n"));
+    assert!(text.ends_with("sub:\n    return\nend:\n"));
+    let parsed = Program::parse(&text)?;
+    assert_eq!(parsed, program);
+    assert_eq!(parsed.to_asm(), text);
+
+    // A compiled circuit round-trips too
+    let circuit = onq::CircuitBuilder::new()
+        .h(qid(0))
+        .cnot(qid(0), qid(1))
+        .stabilize(&[qid(0), qid(1)])
+        .build();
+    let compiled = Program::from_circuit(&circuit);
+    assert_eq!(Program::parse(&compiled.to_asm())?, compiled);
+    Ok(())
+}
+
+#[test]
+fn test_vm_optimize_program() -> Result<(), Box<dyn std::error::Error>> {
+    use onq::vm::optimize::optimize;
+    use onq::vm::Optimization;
+
+    let program = onq::Program::parse(
+        "
+    load_immediate limit, 4
+    load_immediate one, 1
+    copy limit, n
+    sub n, n, one             ; n = 3
+    cmp_gt big, n, one
+    branch_if_zero big, small ; never taken
+    jump hop
+    print \"unreachable\"
+hop:
+    jump loop
+small:
+    print \"small\"
+loop:
+    quantum_op interaction_pattern(q0, QualityFlip)
+    addi n, n, 18446744073709551615
+    branch_if_not_zero n, hop ; threaded to loop
+    stabilize [q0]
+    record q0, m
+    call done
+    halt
+    print \"after halt\"
+done:
+    jump ret
+ret:
+    return
+",
+    )?;
+    let optimized = optimize(&program);
+    let changes = optimized.changes();
+
+    // Straight-line arithmetic on constants folds into loads
+    assert!(changes.contains(&Optimization::FoldedConstant {
+        pc: 2,
+        before: Instruction::Copy { source_reg: "limit".to_string(), dest_reg: "n".to_string() },
+        after: Some(Instruction::LoadImmediate { register: "n".to_string(), value: 4 }),
+    }));
+    assert!(changes.contains(&Optimization::FoldedConstant {
+        pc: 5,
+        before: Instruction::BranchIfZero { register: "big".to_string(), label: "small".to_string() },
+        after: None,
+    }));
+    // Jump chains are threaded, and code they skipped becomes unreachable
+    assert!(changes.contains(&Optimization::ThreadedJump { pc: 12, from: "hop".to_string(), to: "loop".to_string() }));
+    assert!(changes.contains(&Optimization::RemovedRedundantJump { pc: 18, label: "ret".to_string() }));
+    let removed: Vec<usize> = changes
+        .iter()
+        .filter(|change| matches!(change, Optimization::RemovedUnreachable { .. }))
+        .map(Optimization::pc)
+        .collect();
+    assert_eq!(removed, vec![7, 8, 9, 17]);
+    assert_eq!(changes[0].to_string(), "pc 6: retargeted from 'hop' to 'loop'");
+
+    // Values of the registers still in the loop are not known there
+    let optimized = optimized.into_program();
+    assert!(optimized.instructions().contains(&Instruction::Addi {
+        r_dest: "n".to_string(),
+        r_src: "n".to_string(),
+        value: u64::MAX,
+    }));
+
+    let output = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = std::sync::Arc::clone(&output);
+    let mut vm = OnqVm::new().with_output(move |line| sink.lock().unwrap().push(line.to_string()));
+    let expected = vm.run(&program)?;
+    let result = vm.run(&optimized)?;
+    assert_eq!(result.classical_memory(), expected.classical_memory());
+    assert_eq!(result.halt_reason(), HaltReason::Halted);
+    assert!(result.instruction_count() < expected.instruction_count());
+    assert!(output.lock().unwrap().is_empty());
+
+    // An already optimal program is left alone
+    assert!(optimize(&optimized).is_unchanged());
+    Ok(())
+}
+
+#[test]
+fn test_vm_verify_program() -> Result<(), Box<dyn std::error::Error>> {
+    use onq::vm::VerificationIssue;
+
+    let program = onq::Program::parse(
+        "
+    rand coin, 2
+    branch_if_zero coin, skip
+    stabilize [q0, q1]
+skip:
+    record q0, m0            ; q0 is not stabilized when the branch is taken
+    stabilize [q1]
+    call sub
+    record q1, m1            ; the subroutine re-stabilizes q1 on its only path
+    quantum_op permute([(q0, q1), (q1, q1)])
+    quantum_op_dyn phase_shift(q2, 0.0), angle
+    print \"{} {}\", [m0, missing]
+    halt
+orphan:
+    quantum_op relational_lock(q2, q2, BellPhiPlus, true)
+sub:
+    stabilize [q1, q2]
+    return
+",
+    )?;
+    let issues = program.verify().unwrap_err();
+    assert_eq!(
+        issues,
+        vec![
+            VerificationIssue::UnstabilizedRecord { pc: 3, qdu: qid(0) },
+            VerificationIssue::AliasedQdu { pc: 7, qdu: qid(1) },
+            VerificationIssue::UnwrittenFloatRegister { pc: 8, register: "angle".to_string() },
+            VerificationIssue::UnwrittenRegister { pc: 9, register: "missing".to_string() },
+            VerificationIssue::AliasedQdu { pc: 11, qdu: qid(2) },
+            VerificationIssue::UnreachableLabel { label: "orphan".to_string(), pc: 11 },
+        ]
+    );
+    assert_eq!(issues[0].to_string(), "pc 3: QDU(0) is recorded without a preceding Stabilize of it on every path");
+
+    // Compiled circuits verify cleanly
+    let circuit = onq::CircuitBuilder::new()
+        .h(qid(0))
+        .cnot(qid(0), qid(1))
+        .stabilize(&[qid(0), qid(1)])
+        .build();
+    assert_eq!(onq::Program::from_circuit(&circuit).verify(), Ok(()));
+    Ok(())
+}
+
+#[test]
+fn test_vm_structured_control_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let dec = |name: &str| Instruction::Sub { r_dest: reg(name), r_src1: reg(name), r_src2: reg("one") };
+    let add = |name: &str, value| Instruction::Addi { r_dest: reg(name), r_src: reg(name), value };
+
+    // Count the odd and even values of i in 3..=1, with an inner loop of j = i..=1
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: reg("one"), value: 1 })
+        .pb_add(Instruction::LoadImmediate { register: reg("i"), value: 3 })
+        .while_reg_nonzero("i", |outer| {
+            outer
+                .pb_add(Instruction::And { r_dest: reg("odd"), r_src1: reg("i"), r_src2: reg("one") })
+                .if_zero("odd", |even| even.pb_add(add("evens", 1)), |odd| odd.pb_add(add("odds", 1)))
+                .pb_add(Instruction::Copy { source_reg: reg("i"), dest_reg: reg("j") })
+                .while_reg_nonzero("j", |inner| inner.pb_add(add("inner", 1)).pb_add(dec("j")))
+                .pb_add(dec("i"))
+        })
+        .if_nonzero("odds", |then| then.pb_add(Instruction::Jump(reg("done"))), |otherwise| otherwise)
+        .pb_add(Instruction::LoadImmediate { register: reg("unreached"), value: 1 })
+        .pb_add(Instruction::Label(reg("done")))
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    // Generated labels are numbered in the order the helpers are called
+    let text = program.to_asm();
+    for label in ["while.0:", "if.1.else:", "if.1.end:", "while.2:", "while.2.end:", "while.0.end:", "if.3.end:"] {
+        assert!(text.contains(label), "missing {} in\n{}", label, text);
+    }
+    assert!(!text.contains("if.3.else"));
+    assert_eq!(program.verify(), Ok(()));
+
+    let result = OnqVm::new().run(&program)?;
+    assert_eq!(result.register("odds"), 2);
+    assert_eq!(result.register("evens"), 1);
+    assert_eq!(result.register("inner"), 6);
+    assert_eq!(result.register("unreached"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_vm_link_programs() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let dec = |name: &str| Instruction::Sub { r_dest: reg(name), r_src1: reg(name), r_src2: reg("one") };
+    let add = |r_src2: &str| Instruction::OnqAdd { r_dest: reg("total"), r_src1: reg("total"), r_src2: reg(r_src2) };
+
+    // total += 3 + 2 + 1, then + 2 + 1, then + 1, then doubled
+    let main = ProgramBuilder::new()
+        .import("sum_to")
+        .import("double")
+        .pb_add(Instruction::LoadImmediate { register: reg("one"), value: 1 })
+        .pb_add(Instruction::LoadImmediate { register: reg("n"), value: 3 })
+        .while_reg_nonzero("n", |body| {
+            body.pb_add(Instruction::Copy { source_reg: reg("n"), dest_reg: reg("k") })
+                .pb_add(Instruction::Call(reg("sum_to")))
+                .pb_add(dec("n"))
+        })
+        .pb_add(Instruction::Call(reg("double")))
+        .pb_add(Instruction::Halt)
+        .build()?;
+    let sum_to = ProgramBuilder::new()
+        .export("sum_to")
+        .pb_add(Instruction::Label(reg("sum_to")))
+        .while_reg_nonzero("k", |body| body.pb_add(add("k")).pb_add(dec("k")))
+        .pb_add(Instruction::Return)
+        .build()?;
+    let double = ProgramBuilder::new()
+        .export("double")
+        .pb_add(Instruction::Label(reg("double")))
+        .pb_add(add("total"))
+        .pb_add(Instruction::Return)
+        .build()?;
+
+    // A unit with imports round-trips through onq-asm and verifies on its own
+    let text = main.to_asm();
+    assert!(text.starts_with(".import double\n.import sum_to\n"), "{}", text);
+    assert_eq!(Program::parse(&text)?, main);
+    assert_eq!(main.verify(), Ok(()));
+    assert!(OnqVm::new().run(&main).is_err());
+
+    let program = link(&[main.clone(), sum_to.clone(), double.clone()])?;
+    assert_eq!(program.instruction_count(), main.instruction_count() + sum_to.instruction_count() + double.instruction_count());
+    assert_eq!(program.exports().iter().collect::<Vec<_>>(), ["double", "sum_to"]);
+    assert!(program.imports().is_empty());
+    // Both units generated 'while.0', so both are renamed
+    let text = program.to_asm();
+    assert!(text.contains("0.while.0:") && text.contains("1.while.0.end:"), "{}", text);
+    assert!(!text.contains("\nwhile.0:"), "{}", text);
+    assert_eq!(program.verify(), Ok(()));
+    assert_eq!(OnqVm::new().run(&program)?.register("total"), 20);
+
+    // A unit that runs off its end is followed by a Halt, so the next unit is never run into
+    let setup = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: reg("total"), value: 5 })
+        .build()?;
+    let padded = link(&[setup, main.clone(), sum_to.clone(), double.clone()])?;
+    assert_eq!(padded.instruction_count(), program.instruction_count() + 2);
+    assert_eq!(OnqVm::new().run(&padded)?.register("total"), 5);
+
+    // Optimizing a library keeps every exported routine, not just the code its start reaches
+    let library = ProgramBuilder::new()
+        .export("double")
+        .export("sum_to")
+        .pb_add(Instruction::Label(reg("double")))
+        .pb_add(add("total"))
+        .pb_add(Instruction::Return)
+        .pb_add(Instruction::Label(reg("sum_to")))
+        .while_reg_nonzero("k", |body| body.pb_add(add("k")).pb_add(dec("k")))
+        .pb_add(Instruction::Return)
+        .build()?;
+    let optimized = onq::vm::optimize::optimize(&library);
+    assert_eq!(optimized.program().instruction_count(), library.instruction_count());
+    assert_eq!(OnqVm::new().run(&link(&[main.clone(), optimized.program().clone()])?)?.register("total"), 20);
+
+    let missing = link(&[main.clone(), sum_to.clone()]).unwrap_err().to_string();
+    assert!(missing.contains("unit 0 imports label 'double', which no unit exports"), "{}", missing);
+    let duplicate = link(&[main, sum_to, double.clone(), double]).unwrap_err().to_string();
+    assert!(duplicate.contains("label 'double' is exported by units 2 and 3"), "{}", duplicate);
+
+    assert!(ProgramBuilder::new().export("missing").build().is_err());
+    assert!(ProgramBuilder::new().import("here").pb_add(Instruction::Label(reg("here"))).build().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_instruction_macros() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    // dst += count, counting count down to 0
+    let add_to = InstructionMacro::new("add_to", &[], &["dst", "count"], vec![
+        Instruction::Label(reg("loop")),
+        Instruction::BranchIfZero { register: reg("count"), label: reg("end") },
+        Instruction::Addi { r_dest: reg("dst"), r_src: reg("dst"), value: 1 },
+        Instruction::Sub { r_dest: reg("count"), r_src1: reg("count"), r_src2: reg("one") },
+        Instruction::Jump(reg("loop")),
+        Instruction::Label(reg("end")),
+    ]);
+    let cx = InstructionMacro::new("cx", &[QduId(0), QduId(1)], &[], vec![
+        Instruction::QuantumOp(Operation::ControlledInteraction { control: QduId(0), target: QduId(1), pattern_id: reg("QualityFlip") }),
+    ]);
+    let measure = InstructionMacro::new("measure", &[QduId(0)], &["m"], vec![
+        Instruction::Stabilize { targets: vec![QduId(0)] },
+        Instruction::Record { qdu: QduId(0), register: reg("m") },
+    ]);
+
+    let program = ProgramBuilder::new()
+        .define_macro(add_to.clone())
+        .define_macro(cx.clone())
+        .define_macro(measure)
+        .pb_add(Instruction::LoadImmediate { register: reg("one"), value: 1 })
+        .pb_add(Instruction::LoadImmediate { register: reg("a"), value: 2 })
+        .pb_add(Instruction::LoadImmediate { register: reg("b"), value: 3 })
+        .expand("add_to", &[], &["total", "a"])
+        .expand("add_to", &[], &["total", "b"])
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: QduId(1), pattern_id: reg("QualityFlip") }))
+        // Parameters are substituted at once, so swapping them works
+        .if_nonzero("one", |then| then.expand("cx", &[QduId(1), QduId(0)], &[]), |otherwise| otherwise)
+        .expand("measure", &[QduId(0)], &["m0"])
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let text = program.to_asm();
+    for label in ["add_to.0.loop:", "add_to.0.end:", "add_to.1.loop:", "add_to.1.end:"] {
+        assert!(text.contains(label), "missing {} in\n{}", label, text);
+    }
+    assert!(text.contains("controlled_interaction(q1, q0, QualityFlip)"), "{}", text);
+    assert_eq!(program.verify(), Ok(()));
+
+    let result = OnqVm::new().run(&program)?;
+    assert_eq!(result.register("total"), 5);
+    assert_eq!(result.register("a"), 0);
+    assert_eq!(result.register("b"), 0);
+    assert_eq!(result.register("m0"), 1);
+
+    let unknown = ProgramBuilder::new().expand("missing", &[], &[]).build().unwrap_err();
+    assert!(unknown.contains("Macro 'missing' is not defined"), "{}", unknown);
+    let arity = ProgramBuilder::new()
+        .define_macro(add_to)
+        .while_reg_nonzero("n", |body| body.expand("add_to", &[QduId(0)], &["total"]))
+        .build()
+        .unwrap_err();
+    assert!(arity.contains("takes 0 QDUs and 2 registers, but was given 1 and 1"), "{}", arity);
+    Ok(())
+}
+
+#[test]
+fn test_vm_stabilize_into() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: QduId(0), pattern_id: reg("QualityFlip") }))
+        .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(0), reg("a")), (QduId(1), reg("b"))] })
+        .pb_add(Instruction::Record { qdu: QduId(0), register: reg("c") })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let text = program.to_asm();
+    assert!(text.contains("    stabilize_into [(q0, a), (q1, b)]\n"), "{}", text);
+    assert_eq!(Program::parse(&text)?, program);
+    assert_eq!(program.verify(), Ok(()));
+
+    let mut vm = OnqVm::new();
+    let result = vm.run(&program)?;
+    assert_eq!((result.register("a"), result.register("b"), result.register("c")), (1, 0, 1));
+    assert_eq!(result.stabilizations().len(), 1);
+    assert_eq!(vm.stats().instruction_count("StabilizeInto"), 1);
+
+    // Deferring stabilization treats it as a Stabilize followed by Records
+    let deferred = defer_stabilization(&program)?;
+    assert_eq!(deferred.instructions()[1], Instruction::Stabilize { targets: vec![QduId(0), QduId(1)] });
+    assert_eq!(deferred.instructions()[2], Instruction::Record { qdu: QduId(0), register: reg("a") });
+    assert_eq!(OnqVm::new().run(&deferred)?.register("c"), 1);
+
+    let aliased = ProgramBuilder::new()
+        .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(2), reg("x")), (QduId(2), reg("y"))] })
+        .build()?;
+    assert_eq!(aliased.verify(), Err(vec![onq::vm::VerificationIssue::AliasedQdu { pc: 0, qdu: QduId(2) }]));
+    Ok(())
+}
+
+#[test]
+fn test_vm_reset_qdu() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let flip = |q| Instruction::QuantumOp(Operation::InteractionPattern { target: QduId(q), pattern_id: reg("QualityFlip") });
+
+    // One ancilla serves every pass of the loop
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: reg("one"), value: 1 })
+        .pb_add(Instruction::LoadImmediate { register: reg("k"), value: 5 })
+        .while_reg_nonzero("k", |body| {
+            body.pb_add(flip(1))
+                .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(1), reg("m"))] })
+                .pb_add(Instruction::OnqAdd { r_dest: reg("ones"), r_src1: reg("ones"), r_src2: reg("m") })
+                .pb_add(Instruction::ResetQdu { targets: vec![QduId(1)] })
+                .pb_add(Instruction::Sub { r_dest: reg("k"), r_src1: reg("k"), r_src2: reg("one") })
+        })
+        .pb_add(Instruction::Halt)
+        .build()?;
+    assert!(program.to_asm().contains("    reset_qdu [q1]\n"));
+    assert_eq!(Program::parse(&program.to_asm())?, program);
+    assert_eq!(program.verify(), Ok(()));
+    let mut vm = OnqVm::new();
+    assert_eq!(vm.run(&program)?.register("ones"), 5);
+    assert_eq!(vm.stats().instruction_count("ResetQdu"), 5);
+
+    // Whatever state the QDUs are in, they end in Quality0
+    let superpose = |q| Instruction::QuantumOp(Operation::InteractionPattern { target: QduId(q), pattern_id: reg("Superposition") });
+    let cnot = Instruction::QuantumOp(Operation::ControlledInteraction { control: QduId(0), target: QduId(1), pattern_id: reg("QualityFlip") });
+    for preparation in [vec![], vec![flip(0), flip(1)], vec![superpose(0), cnot.clone()], vec![flip(0), superpose(1), cnot]] {
+        let program = ProgramBuilder::new()
+            .add_many(preparation.clone())
+            .pb_add(Instruction::ResetQdu { targets: vec![QduId(0), QduId(1)] })
+            .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(0), reg("a")), (QduId(1), reg("b"))] })
+            .build()?;
+        let result = OnqVm::new().run(&program)?;
+        assert_eq!((result.register("a"), result.register("b")), (0, 0), "after {:?}", preparation);
+        assert_eq!(result.stabilizations().len(), 1);
+    }
+
+    // A reset QDU has no outcome left to record
+    let stale = ProgramBuilder::new()
+        .pb_add(Instruction::Stabilize { targets: vec![QduId(0)] })
+        .pb_add(Instruction::ResetQdu { targets: vec![QduId(0)] })
+        .pb_add(Instruction::Record { qdu: QduId(0), register: reg("m") })
+        .build()?;
+    assert_eq!(stale.verify(), Err(vec![onq::vm::VerificationIssue::UnstabilizedRecord { pc: 2, qdu: QduId(0) }]));
+    assert!(OnqVm::new().run(&stale).is_err());
+    assert!(defer_stabilization(&stale).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_quantum_op_if() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let flip = |q| Operation::InteractionPattern { target: QduId(q), pattern_id: reg("QualityFlip") };
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(flip(0)))
+        .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(0), reg("m"))] })
+        .pb_add(Instruction::QuantumOpIf { register: reg("m"), op: flip(1) })
+        .pb_add(Instruction::QuantumOpIf { register: reg("unset"), op: flip(2) })
+        .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(1), reg("b")), (QduId(2), reg("c"))] })
+        .build()?;
+
+    let text = program.to_asm();
+    assert!(text.contains("    quantum_op_if m, interaction_pattern(q1, QualityFlip)\n"), "{}", text);
+    assert_eq!(Program::parse(&text)?, program);
+    assert_eq!(
+        program.verify(),
+        Err(vec![onq::vm::VerificationIssue::UnwrittenRegister { pc: 3, register: reg("unset") }])
+    );
+
+    let mut vm = OnqVm::new();
+    let result = vm.run(&program)?;
+    assert_eq!((result.register("b"), result.register("c")), (1, 0));
+    // Only applied operations are counted
+    assert_eq!(vm.stats().instruction_count("QuantumOpIf"), 2);
+    assert_eq!(vm.stats().pattern_count("QualityFlip"), 2);
+
+    // A correction on a recorded outcome has no controlled equivalent to defer into
+    let correction = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(flip(0)))
+        .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(0), reg("m"))] })
+        .pb_add(Instruction::QuantumOpIf { register: reg("m"), op: flip(1) })
+        .build()?;
+    assert!(defer_stabilization(&correction).is_err());
+    let not_outcome = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOpIf { register: reg("unset"), op: flip(2) })
+        .build()?;
+    assert!(defer_stabilization(&not_outcome).is_err());
+
+    // Known conditions fold away
+    let constant = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: reg("yes"), value: 1 })
+        .pb_add(Instruction::QuantumOpIf { register: reg("yes"), op: flip(0) })
+        .pb_add(Instruction::QuantumOpIf { register: reg("no"), op: flip(1) })
+        .pb_add(Instruction::LoadImmediate { register: reg("no"), value: 0 })
+        .pb_add(Instruction::QuantumOpIf { register: reg("no"), op: flip(1) })
+        .build()?;
+    let optimized = onq::vm::optimize::optimize(&constant);
+    assert_eq!(
+        optimized.program().instructions(),
+        [
+            Instruction::LoadImmediate { register: reg("yes"), value: 1 },
+            Instruction::QuantumOp(flip(0)),
+            Instruction::QuantumOpIf { register: reg("no"), op: flip(1) },
+            Instruction::LoadImmediate { register: reg("no"), value: 0 },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_vm_repeat_loop() -> Result<(), Box<dyn std::error::Error>> {
+    let reg = |name: &str| name.to_string();
+    let add = |name: &str, value| Instruction::Addi { r_dest: reg(name), r_src: reg(name), value };
+
+    // Five passes of flip-and-count on one ancilla, with a nested loop of three passes
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: reg("k"), value: 5 })
+        .repeat("k", |body| {
+            body.pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: QduId(0), pattern_id: reg("QualityFlip") }))
+                .pb_add(Instruction::StabilizeInto { targets: vec![(QduId(0), reg("m"))] })
+                .pb_add(Instruction::OnqAdd { r_dest: reg("ones"), r_src1: reg("ones"), r_src2: reg("m") })
+                .pb_add(Instruction::LoadImmediate { register: reg("j"), value: 3 })
+                .repeat("j", |inner| inner.pb_add(add("inner", 1)))
+        })
+        .repeat("zero", |body| body.pb_add(add("skipped", 1)))
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let text = program.to_asm();
+    for line in ["repeat.0:", "    repeat k, repeat.0\n", "repeat.1.end:", "repeat.2.end:"] {
+        assert!(text.contains(line), "missing {} in\n{}", line, text);
+    }
+    assert_eq!(Program::parse(&text)?, program);
+    assert_eq!(program.verify(), Ok(()));
+
+    let mut vm = OnqVm::new();
+    let result = vm.run(&program)?;
+    assert_eq!(result.register("ones"), 3);
+    assert_eq!(result.register("inner"), 15);
+    assert_eq!(result.register("skipped"), 0);
+    assert_eq!((result.register("k"), result.register("j")), (0, 0));
+    assert_eq!(vm.stats().instruction_count("Repeat"), 5 + 5 * 3);
+    assert_eq!(vm.stats().instruction_count("Jump"), 0);
+
+    // A count known to be spent folds to clearing the register
+    let spent = ProgramBuilder::new()
+        .pb_add(Instruction::Label(reg("top")))
+        .pb_add(Instruction::LoadImmediate { register: reg("k"), value: 1 })
+        .pb_add(Instruction::Repeat { register: reg("k"), label: reg("top") })
+        .build()?;
+    let optimized = onq::vm::optimize::optimize(&spent);
+    assert_eq!(optimized.program().instructions()[1], Instruction::LoadImmediate { register: reg("k"), value: 0 });
+    assert!(defer_stabilization(&spent).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vm_run_shots() -> Result<(), Box<dyn std::error::Error>> {
+    let s = |name: &str| name.to_string();
+    // A coin flip copied onto a second QDU, plus an independent classical die roll
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::QuantumOp(Operation::InteractionPattern { target: qid(0), pattern_id: s("Superposition") }))
+        .pb_add(Instruction::StabilizeInto { targets: vec![(qid(0), s("m0"))] })
+        .pb_add(Instruction::QuantumOpIf { register: s("m0"), op: Operation::InteractionPattern { target: qid(1), pattern_id: s("QualityFlip") } })
+        .pb_add(Instruction::StabilizeInto { targets: vec![(qid(1), s("m1"))] })
+        .pb_add(Instruction::CmpEq { r_dest: s("agree"), r_src1: s("m0"), r_src2: s("m1") })
+        .pb_add(Instruction::Rand { register: s("die"), upper_bound: 6 })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let shots = 200;
+    let mut vm = OnqVm::new();
+    let results = vm.run_shots(&program, shots)?;
+    assert_eq!(results.shots(), shots);
+    assert_eq!(results.seed_mode(), SeedMode::PerShot);
+    assert_eq!(results.registers().collect::<Vec<_>>(), ["agree", "die", "m0", "m1"]);
+
+    // The pair always agrees, and both outcomes occur
+    assert_eq!(results.register_counts("agree"), [(1, shots)].into());
+    assert!((results.register_probability("m0", 1) - 0.5).abs() < 0.15);
+    assert_eq!(results.outcome_counts().len(), 2);
+    for value in 0..2 {
+        let outcome = [(qid(0), value), (qid(1), value)].into();
+        assert_eq!(results.outcome_count(&outcome), results.register_count("m1", value));
+    }
+    // Rand draws vary across shots too; an unwritten register counts as 0
+    assert!(results.register_counts("die").len() > 1);
+    assert!(results.register_counts("die").keys().all(|&v| v < 6));
+    assert_eq!(results.register_counts("missing"), [(0, shots)].into());
+
+    // Shot 0 of the default mode is a plain run; Fixed repeats it every shot
+    let single = vm.run(&program)?;
+    let fixed = OnqVm::new().with_seed_mode(SeedMode::Fixed).run_shots(&program, 10)?;
+    assert_eq!(fixed.register_count("m0", single.register("m0")), 10);
+    assert_eq!(fixed.register_count("die", single.register("die")), 10);
+    let first = OnqVm::new().run_shots(&program, 1)?;
+    assert_eq!(first.register_count("m0", single.register("m0")), 1);
+
+    // Seeded modes reproduce their histograms
+    let seeded = |seed| OnqVm::new().with_seed_mode(SeedMode::Seeded(seed)).run_shots(&program, 50);
+    assert_eq!(seeded(7)?.register_counts("m0"), seeded(7)?.register_counts("m0"));
+
+    // Shots cannot pause
+    vm.set_breakpoint(3);
+    assert!(matches!(vm.run_shots(&program, 3), Err(OnqError::InvalidOperation { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_vm_host_call() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Mutex};
+    let s = |name: &str| name.to_string();
+
+    // Minimizes a host-side cost function by scanning x = 0..5, logging every evaluation
+    let program = ProgramBuilder::new()
+        .pb_add(Instruction::LoadImmediate { register: s("best"), value: u64::MAX })
+        .pb_add(Instruction::LoadImmediate { register: s("k"), value: 5 })
+        .repeat("k", |body| {
+            body.pb_add(Instruction::HostCall { name: s("cost"), args: vec![s("x")], ret: s("c") })
+                .pb_add(Instruction::HostCall { name: s("log"), args: vec![s("x"), s("c")], ret: s("logged") })
+                .pb_add(Instruction::BranchIfGe { r1: s("c"), r2: s("best"), label: s("worse") })
+                .pb_add(Instruction::Copy { source_reg: s("c"), dest_reg: s("best") })
+                .pb_add(Instruction::Copy { source_reg: s("x"), dest_reg: s("argmin") })
+                .pb_add(Instruction::Label(s("worse")))
+                .pb_add(Instruction::Addi { r_dest: s("x"), r_src: s("x"), value: 1 })
+        })
+        .pb_add(Instruction::Halt)
+        .build()?;
+
+    let text = program.to_asm();
+    assert!(text.contains("    host_call log, [x, c], logged\n"), "{}", text);
+    assert_eq!(Program::parse(&text)?, program);
+    assert_eq!(program.verify(), Ok(()));
+    // Host calls are never folded away, even when their results go unread
+    let optimized = onq::vm::optimize::optimize(&program);
+    assert_eq!(optimized.program().instructions().iter().filter(|i| i.kind() == "HostCall").count(), 2);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&log);
+    let mut vm = OnqVm::new()
+        .with_host_function("cost", |args| Ok((args[0] as i64 - 3).pow(2) as u64 + 10))
+        .with_host_function("log", move |args| {
+            let mut log = sink.lock().unwrap();
+            log.push((args[0], args[1]));
+            Ok(log.len() as u64)
+        });
+    let result = vm.run(&program)?;
+    assert_eq!((result.register("argmin"), result.register("best")), (3, 10));
+    assert_eq!(result.register("logged"), 5);
+    assert_eq!(*log.lock().unwrap(), [(0, 19), (1, 14), (2, 11), (3, 10), (4, 11)]);
+    assert_eq!(vm.stats().instruction_count("HostCall"), 10);
+
+    // Unregistered and failing functions stop the run
+    let missing = OnqVm::new().run(&program);
+    assert!(matches!(missing, Err(OnqError::SimulationError { message }) if message.contains("'cost' is not registered")));
+    let mut failing = OnqVm::new()
+        .with_host_function("cost", |_| Err("diverged".to_string()))
+        .with_host_function("log", |_| Ok(0));
+    assert!(matches!(failing.run(&program), Err(OnqError::SimulationError { message }) if message.contains("diverged")));
+
+    // Host calls run in program order around a deferred stabilization, like other classical code
+    let renamed = InstructionMacro::new("lookup", &[], &["in", "out"], vec![Instruction::HostCall { name: s("cost"), args: vec![s("in")], ret: s("out") }]);
+    let expanded = ProgramBuilder::new().define_macro(renamed).expand("lookup", &[], &["a", "b"]).build()?;
+    assert_eq!(expanded.instructions(), [Instruction::HostCall { name: s("cost"), args: vec![s("a")], ret: s("b") }]);
+    assert_eq!(defer_stabilization(&expanded)?.instructions()[0], expanded.instructions()[0]);
+    Ok(())
+}